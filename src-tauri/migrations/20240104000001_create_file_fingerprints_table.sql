@@ -0,0 +1,17 @@
+-- Content fingerprints for imported files so moved or renamed files can be found again
+CREATE TABLE file_fingerprints (
+    id TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    chapter_id TEXT, -- NULL for the audiobook's own file_path
+    file_path TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    content_hash TEXT NOT NULL, -- md5 of the first 4 MB
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks(id) ON DELETE CASCADE,
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_file_fingerprints_path ON file_fingerprints(audiobook_id, file_path);
+CREATE INDEX idx_file_fingerprints_content ON file_fingerprints(file_size, content_hash);
@@ -0,0 +1,18 @@
+-- Compact play history: one row per continuous listen of a book (or chapter)
+CREATE TABLE plays (
+    id TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    chapter_id TEXT,
+    started_at TEXT NOT NULL,
+    ended_at TEXT NOT NULL,
+    start_position INTEGER NOT NULL DEFAULT 0, -- Seconds into the file
+    end_position INTEGER NOT NULL DEFAULT 0,
+    listened_seconds INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks(id) ON DELETE CASCADE,
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_plays_started_at ON plays(started_at DESC);
+CREATE INDEX idx_plays_audiobook ON plays(audiobook_id, chapter_id, ended_at);
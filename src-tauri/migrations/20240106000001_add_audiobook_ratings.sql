@@ -0,0 +1,5 @@
+-- Personal rating (1-5) and private review notes per audiobook
+ALTER TABLE audiobooks ADD COLUMN rating INTEGER CHECK (rating IS NULL OR (rating >= 1 AND rating <= 5));
+ALTER TABLE audiobooks ADD COLUMN review TEXT;
+
+CREATE INDEX idx_audiobooks_rating ON audiobooks(rating);
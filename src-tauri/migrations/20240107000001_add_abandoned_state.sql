@@ -0,0 +1,6 @@
+-- Books the listener gave up on, kept apart from finished and in-progress ones
+ALTER TABLE playback_progress ADD COLUMN is_abandoned BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE playback_progress ADD COLUMN abandoned_at TEXT;
+ALTER TABLE playback_progress ADD COLUMN abandon_reason TEXT;
+
+CREATE INDEX idx_playback_progress_abandoned ON playback_progress(is_abandoned);
@@ -0,0 +1,3 @@
+-- Collection cover art: a generated collage of member covers or a user-chosen image
+ALTER TABLE collections ADD COLUMN cover_image_path TEXT;
+ALTER TABLE collections ADD COLUMN cover_is_custom BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Nested collections: a collection may live inside another one
+ALTER TABLE collections ADD COLUMN parent_collection_id TEXT REFERENCES collections (id) ON DELETE SET NULL;
+
+CREATE INDEX idx_collections_parent ON collections(parent_collection_id);
@@ -0,0 +1,30 @@
+-- Author entities shared by audiobooks and ebooks
+CREATE TABLE authors (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    sort_name TEXT NOT NULL, -- "Last, First" for alphabetical listings
+    bio TEXT,
+    photo_url TEXT,
+    openlibrary_key TEXT,
+    details_fetched_at TEXT, -- Last OpenLibrary lookup, successful or not
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Every spelling seen for an author, including the canonical name
+CREATE TABLE author_aliases (
+    id TEXT PRIMARY KEY,
+    author_id TEXT NOT NULL,
+    alias TEXT NOT NULL,
+    normalized_alias TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (author_id) REFERENCES authors (id) ON DELETE CASCADE
+);
+
+ALTER TABLE audiobooks ADD COLUMN author_id TEXT REFERENCES authors (id) ON DELETE SET NULL;
+ALTER TABLE ebooks ADD COLUMN author_id TEXT REFERENCES authors (id) ON DELETE SET NULL;
+
+CREATE INDEX idx_authors_sort_name ON authors(sort_name);
+CREATE INDEX idx_author_aliases_author_id ON author_aliases(author_id);
+CREATE INDEX idx_audiobooks_author_id ON audiobooks(author_id);
+CREATE INDEX idx_ebooks_author_id ON ebooks(author_id);
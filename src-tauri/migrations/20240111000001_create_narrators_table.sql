@@ -0,0 +1,15 @@
+-- Narrator entities, with favorites that boost recommendations
+CREATE TABLE narrators (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL UNIQUE,
+    sort_name TEXT NOT NULL,
+    is_favorite BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+ALTER TABLE audiobooks ADD COLUMN narrator_id TEXT REFERENCES narrators (id) ON DELETE SET NULL;
+
+CREATE INDEX idx_narrators_sort_name ON narrators(sort_name);
+CREATE INDEX idx_audiobooks_narrator_id ON audiobooks(narrator_id);
@@ -0,0 +1,2 @@
+-- Spoken language of an audiobook, e.g. "English", used by the random picker
+ALTER TABLE audiobooks ADD COLUMN language TEXT;
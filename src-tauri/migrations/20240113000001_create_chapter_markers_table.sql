@@ -0,0 +1,14 @@
+-- Time-offset markers inside a single audio file, used as chapters for books
+-- that are one long file without embedded chapter information
+CREATE TABLE chapter_markers (
+    id TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    position INTEGER NOT NULL, -- Offset in seconds
+    title TEXT NOT NULL,
+    is_auto BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_chapter_markers_audiobook ON chapter_markers(audiobook_id, position);
@@ -0,0 +1,18 @@
+-- Sentence timings recorded while synthesizing TTS chapters, for highlighting
+-- the sentence being spoken. Offsets are relative to the start of file_path,
+-- since a chapter can be synthesized into several chunk files.
+CREATE TABLE tts_timings (
+    id TEXT PRIMARY KEY,
+    chapter_id TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    sentence_index INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    start_time REAL NOT NULL, -- Seconds
+    end_time REAL NOT NULL, -- Seconds
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_tts_timings_sentence ON tts_timings(chapter_id, sentence_index);
+CREATE INDEX idx_tts_timings_file ON tts_timings(chapter_id, file_path);
@@ -0,0 +1,11 @@
+-- Text a TTS chapter was synthesized from, with the voice and speed used, so a
+-- single chapter can be generated again without the original document
+CREATE TABLE tts_chapter_sources (
+    chapter_id TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    voice TEXT,
+    speed REAL NOT NULL DEFAULT 1.0,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+
+    FOREIGN KEY (chapter_id) REFERENCES chapters(id) ON DELETE CASCADE
+);
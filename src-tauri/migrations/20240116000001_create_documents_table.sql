@@ -0,0 +1,15 @@
+-- Documents processed for TTS, with the chunking they were split with and the
+-- resulting chapter layout, so reprocessing a file reproduces the same structure
+CREATE TABLE documents (
+    id TEXT PRIMARY KEY,
+    file_path TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    author TEXT,
+    format TEXT NOT NULL,
+    target_minutes REAL NOT NULL,
+    split_on_sentences BOOLEAN NOT NULL DEFAULT TRUE,
+    respect_headings BOOLEAN NOT NULL DEFAULT TRUE,
+    sections TEXT NOT NULL DEFAULT '[]', -- JSON array of {title, word_count, minutes}
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
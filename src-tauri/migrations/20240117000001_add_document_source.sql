@@ -0,0 +1,3 @@
+-- Where an imported document came from, for web articles saved as text
+ALTER TABLE documents ADD COLUMN source_url TEXT;
+ALTER TABLE documents ADD COLUMN site_name TEXT;
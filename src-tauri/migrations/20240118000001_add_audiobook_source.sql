@@ -0,0 +1,11 @@
+-- Where each audiobook was imported from: 'librivox' (source_id is the
+-- Archive.org identifier), 'tts' (the document id), 'local' (the imported
+-- path) or 'url_list' (the download folder)
+ALTER TABLE audiobooks ADD COLUMN source_type TEXT;
+ALTER TABLE audiobooks ADD COLUMN source_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_audiobooks_source ON audiobooks(source_type, source_id);
+
+-- TTS books are recognizable without looking at the disk; the rest are
+-- inferred from their paths during warm-up
+UPDATE audiobooks SET source_type = 'tts' WHERE genre = 'TTS Generated' AND source_type IS NULL;
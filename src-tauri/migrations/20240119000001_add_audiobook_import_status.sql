@@ -0,0 +1,3 @@
+-- 'incomplete' marks a download-based import whose files were not all fetched,
+-- e.g. because the app closed part-way; such books can be resumed
+ALTER TABLE audiobooks ADD COLUMN import_status TEXT NOT NULL DEFAULT 'complete';
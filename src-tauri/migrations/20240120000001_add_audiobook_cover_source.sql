@@ -0,0 +1,3 @@
+-- Where an audiobook's cover was found: 'embedded', 'folder', 'archive_org'
+-- or 'open_library'. NULL for covers set before this was recorded.
+ALTER TABLE audiobooks ADD COLUMN cover_source TEXT;
@@ -0,0 +1,15 @@
+-- Listening history older than the retention window, folded into one row per
+-- book and day so long-range stats survive without keeping every session
+CREATE TABLE IF NOT EXISTS listening_daily_summaries (
+    day TEXT NOT NULL, -- YYYY-MM-DD (UTC)
+    audiobook_id TEXT NOT NULL,
+    session_count INTEGER NOT NULL DEFAULT 0, -- listening_history rows
+    session_seconds INTEGER NOT NULL DEFAULT 0,
+    max_completion REAL NOT NULL DEFAULT 0.0,
+    play_count INTEGER NOT NULL DEFAULT 0, -- plays rows
+    played_seconds INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, audiobook_id),
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_listening_daily_summaries_audiobook ON listening_daily_summaries (audiobook_id);
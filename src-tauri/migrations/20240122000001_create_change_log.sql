@@ -0,0 +1,78 @@
+-- Journal of library changes, the basis for device sync and incremental
+-- backups. seq is the logical timestamp: it only grows and is never reused,
+-- so a reader that has seen everything up to N asks for the rows after N.
+CREATE TABLE IF NOT EXISTS change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL, -- audiobook, progress or collection
+    entity_id TEXT NOT NULL, -- The audiobook id for progress
+    operation TEXT NOT NULL, -- create, update or delete
+    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_log_entity ON change_log (entity_type, entity_id);
+CREATE INDEX IF NOT EXISTS idx_change_log_changed_at ON change_log (changed_at);
+
+-- Only the latest update of an entity is kept, and none once it is deleted:
+-- readers fetch the current state anyway, and a position saved every few
+-- seconds would flood the journal
+
+CREATE TRIGGER IF NOT EXISTS change_log_audiobook_insert AFTER INSERT ON audiobooks BEGIN
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('audiobook', NEW.id, 'create');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_audiobook_update AFTER UPDATE ON audiobooks BEGIN
+    DELETE FROM change_log WHERE entity_type = 'audiobook' AND entity_id = NEW.id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('audiobook', NEW.id, 'update');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_audiobook_delete AFTER DELETE ON audiobooks BEGIN
+    DELETE FROM change_log WHERE entity_type = 'audiobook' AND entity_id = OLD.id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('audiobook', OLD.id, 'delete');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_progress_insert AFTER INSERT ON playback_progress BEGIN
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('progress', NEW.audiobook_id, 'create');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_progress_update AFTER UPDATE ON playback_progress BEGIN
+    DELETE FROM change_log WHERE entity_type = 'progress' AND entity_id = NEW.audiobook_id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('progress', NEW.audiobook_id, 'update');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_progress_delete AFTER DELETE ON playback_progress BEGIN
+    DELETE FROM change_log WHERE entity_type = 'progress' AND entity_id = OLD.audiobook_id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('progress', OLD.audiobook_id, 'delete');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_collection_insert AFTER INSERT ON collections BEGIN
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', NEW.id, 'create');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_collection_update AFTER UPDATE ON collections BEGIN
+    DELETE FROM change_log WHERE entity_type = 'collection' AND entity_id = NEW.id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', NEW.id, 'update');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_collection_delete AFTER DELETE ON collections BEGIN
+    DELETE FROM change_log WHERE entity_type = 'collection' AND entity_id = OLD.id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', OLD.id, 'delete');
+END;
+
+-- Membership changes are updates of the collection
+
+CREATE TRIGGER IF NOT EXISTS change_log_collection_member_insert AFTER INSERT ON collection_audiobooks BEGIN
+    DELETE FROM change_log WHERE entity_type = 'collection' AND entity_id = NEW.collection_id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', NEW.collection_id, 'update');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_collection_member_update AFTER UPDATE ON collection_audiobooks BEGIN
+    DELETE FROM change_log WHERE entity_type = 'collection' AND entity_id = NEW.collection_id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', NEW.collection_id, 'update');
+END;
+
+-- Skipped when the whole collection is being deleted
+CREATE TRIGGER IF NOT EXISTS change_log_collection_member_delete AFTER DELETE ON collection_audiobooks
+WHEN EXISTS (SELECT 1 FROM collections WHERE id = OLD.collection_id) BEGIN
+    DELETE FROM change_log WHERE entity_type = 'collection' AND entity_id = OLD.collection_id AND operation = 'update';
+    INSERT INTO change_log (entity_type, entity_id, operation) VALUES ('collection', OLD.collection_id, 'update');
+END;
@@ -0,0 +1,17 @@
+-- Recordings LibriVox catalogued recently, cached for the new releases feed.
+-- Rows are kept while they are recent; first_seen_at is when this library
+-- first saw the release and orders the feed.
+CREATE TABLE IF NOT EXISTS librivox_releases (
+    id TEXT PRIMARY KEY, -- LibriVox project id
+    title TEXT NOT NULL,
+    authors TEXT NOT NULL DEFAULT '[]', -- JSON array of display names
+    genres TEXT NOT NULL DEFAULT '[]', -- JSON array
+    language TEXT,
+    description TEXT,
+    total_seconds INTEGER,
+    url_zip_file TEXT,
+    url_librivox TEXT,
+    first_seen_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_librivox_releases_first_seen ON librivox_releases (first_seen_at);
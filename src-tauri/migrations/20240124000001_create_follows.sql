@@ -0,0 +1,25 @@
+-- Authors and genres the listener follows for LibriVox release alerts
+CREATE TABLE IF NOT EXISTS follows (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('author', 'genre')),
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (kind, normalized_name)
+);
+
+-- A new release matching a follow. The release is stored with the alert so it
+-- can still be imported after it leaves the release cache; dismissed_at is
+-- set once the listener has dealt with it.
+CREATE TABLE IF NOT EXISTS release_alerts (
+    id TEXT PRIMARY KEY,
+    follow_id TEXT NOT NULL,
+    release_id TEXT NOT NULL,
+    release TEXT NOT NULL, -- LibrivoxRelease as JSON
+    created_at TEXT NOT NULL,
+    dismissed_at TEXT,
+    UNIQUE (follow_id, release_id),
+    FOREIGN KEY (follow_id) REFERENCES follows (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_release_alerts_pending ON release_alerts (dismissed_at, created_at);
@@ -0,0 +1,12 @@
+-- The series a book belongs to and where it falls in it. Books are matched to
+-- each other by normalized_name, so "The Expanse" and "the  expanse" agree.
+CREATE TABLE IF NOT EXISTS audiobook_series (
+    audiobook_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL,
+    position REAL, -- 1, 2, 2.5 for a novella between books; NULL when unknown
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_series_name ON audiobook_series (normalized_name, position);
@@ -0,0 +1,8 @@
+-- Data migrations written in Rust (src/database/data_migrations.rs), recorded
+-- once applied the way _sqlx_migrations records the SQL ones
+CREATE TABLE IF NOT EXISTS data_migrations (
+    version INTEGER PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at TEXT NOT NULL,
+    summary TEXT NOT NULL
+);
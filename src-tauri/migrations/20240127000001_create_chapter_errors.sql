@@ -0,0 +1,13 @@
+-- Library files that failed to load during playback, so the chapter list can
+-- flag them. A row is removed once the file loads again.
+CREATE TABLE IF NOT EXISTS chapter_errors (
+    file_path TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    chapter_id TEXT, -- NULL for single-file books
+    error TEXT NOT NULL,
+    failed_at TEXT NOT NULL,
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks (id) ON DELETE CASCADE,
+    FOREIGN KEY (chapter_id) REFERENCES chapters (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_chapter_errors_audiobook ON chapter_errors (audiobook_id);
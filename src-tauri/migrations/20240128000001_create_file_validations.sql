@@ -0,0 +1,21 @@
+-- Results of fully decoding each library file, taken at import and on demand.
+-- status is 'ok', 'warning' or 'unplayable'; issues is a JSON array of messages.
+CREATE TABLE IF NOT EXISTS file_validations (
+    file_path TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    chapter_id TEXT, -- NULL for single-file books
+    status TEXT NOT NULL,
+    codec TEXT,
+    sample_rate INTEGER,
+    channels INTEGER,
+    declared_duration REAL,
+    decoded_duration REAL NOT NULL,
+    packets INTEGER NOT NULL,
+    damaged_packets INTEGER NOT NULL,
+    issues TEXT NOT NULL DEFAULT '[]',
+    validated_at TEXT NOT NULL,
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks (id) ON DELETE CASCADE,
+    FOREIGN KEY (chapter_id) REFERENCES chapters (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_file_validations_audiobook ON file_validations (audiobook_id);
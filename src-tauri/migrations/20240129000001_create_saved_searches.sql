@@ -0,0 +1,22 @@
+-- What the listener searched for lately, one row per query however often it
+-- was typed; searched_at moves to the latest time
+CREATE TABLE IF NOT EXISTS search_history (
+    id TEXT PRIMARY KEY,
+    query TEXT NOT NULL,
+    normalized_query TEXT NOT NULL UNIQUE,
+    searched_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_history_searched_at ON search_history (searched_at);
+
+-- Named searches kept to run again. Pinned ones are listed with the
+-- collections and act like a collection that fills itself.
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    normalized_name TEXT NOT NULL UNIQUE,
+    filters TEXT NOT NULL, -- SearchFilters as JSON
+    pinned BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,4 @@
+-- What a recommendation's score was built from, as JSON: the genre, author or
+-- narrator it matched, the listener's score for it and the tuning weight, and
+-- for books similar to a finished one, that book. NULL for older recommendations.
+ALTER TABLE recommendations ADD COLUMN factors TEXT;
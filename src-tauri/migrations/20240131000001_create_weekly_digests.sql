@@ -0,0 +1,12 @@
+-- Weekly digests compiled by the maintenance scheduler. The digest is kept as
+-- it was compiled, so it still reads the same after books are finished or
+-- deleted; only the newest few are kept.
+CREATE TABLE IF NOT EXISTS weekly_digests (
+    id TEXT PRIMARY KEY,
+    period_start TEXT NOT NULL,
+    period_end TEXT NOT NULL,
+    digest TEXT NOT NULL, -- WeeklyDigest as JSON
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_weekly_digests_created_at ON weekly_digests (created_at);
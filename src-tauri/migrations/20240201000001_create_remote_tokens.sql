@@ -0,0 +1,27 @@
+-- Access tokens for the remote-control API. Only a SHA-256 hash of each token
+-- is stored; the token itself is shown once when it is issued. scopes is a
+-- JSON array of what the token may do: status, transport, library.
+CREATE TABLE IF NOT EXISTS remote_tokens (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_used_at TEXT,
+    revoked_at TEXT
+);
+
+-- Every remote request, allowed or refused. token_id is NULL when the request
+-- carried no known token; the name is copied so entries outlive the token.
+CREATE TABLE IF NOT EXISTS remote_audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_id TEXT,
+    token_name TEXT,
+    scope TEXT NOT NULL,
+    action TEXT NOT NULL,
+    allowed BOOLEAN NOT NULL,
+    reason TEXT,
+    at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_remote_audit_log_at ON remote_audit_log (at);
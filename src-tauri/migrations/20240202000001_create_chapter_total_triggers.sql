@@ -0,0 +1,51 @@
+-- Keep a book's chapters_count and duration in step with its chapters, however
+-- the chapters are written. The duration is the sum of the chapter durations
+-- that are known; books without chapters keep the duration of their file.
+
+CREATE TRIGGER IF NOT EXISTS chapter_totals_insert AFTER INSERT ON chapters BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+    WHERE id = NEW.audiobook_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS chapter_totals_update AFTER UPDATE OF audiobook_id, duration ON chapters BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = CASE
+            WHEN EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            THEN (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            ELSE duration
+        END
+    WHERE id IN (OLD.audiobook_id, NEW.audiobook_id);
+END;
+
+-- Skipped when the whole book is being deleted
+CREATE TRIGGER IF NOT EXISTS chapter_totals_delete AFTER DELETE ON chapters
+WHEN EXISTS (SELECT 1 FROM audiobooks WHERE id = OLD.audiobook_id) BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = CASE
+            WHEN EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            THEN (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            ELSE duration
+        END
+    WHERE id = OLD.audiobook_id;
+END;
+
+-- Correct the totals that have already drifted, touching only those books so
+-- the change log is not flooded
+UPDATE audiobooks
+SET chapters_count = totals.count,
+    duration = totals.total
+FROM (
+    SELECT audiobook_id, COUNT(*) AS count, SUM(duration) AS total
+    FROM chapters
+    GROUP BY audiobook_id
+) AS totals
+WHERE totals.audiobook_id = audiobooks.id
+  AND (audiobooks.chapters_count IS NOT totals.count OR audiobooks.duration IS NOT totals.total);
+
+UPDATE audiobooks
+SET chapters_count = 0
+WHERE chapters_count != 0 AND NOT EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id);
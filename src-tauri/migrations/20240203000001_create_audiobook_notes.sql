@@ -0,0 +1,13 @@
+-- The listener's notes on a book: free-form Markdown, optionally pinned to a
+-- position in the book, e.g. to capture a quote where it is read
+CREATE TABLE IF NOT EXISTS audiobook_notes (
+    id TEXT PRIMARY KEY,
+    audiobook_id TEXT NOT NULL,
+    position INTEGER, -- Seconds from the start of the book; NULL for notes on the whole book
+    body TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (audiobook_id) REFERENCES audiobooks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audiobook_notes_audiobook ON audiobook_notes (audiobook_id, position);
@@ -0,0 +1,3 @@
+-- Notes captured as quotes from a book's transcript, so they can be listed
+-- and exported apart from the listener's own notes
+ALTER TABLE audiobook_notes ADD COLUMN is_quote BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,27 @@
+-- Reading time per ebook per UTC day, for the activity calendar. The reader
+-- only reports a running total, so each rise in reading_time_seconds is added
+-- to the day the progress was saved on. Reading done before this table
+-- existed has no day to go to and is left out.
+CREATE TABLE IF NOT EXISTS reading_daily_activity (
+    ebook_id TEXT NOT NULL,
+    day TEXT NOT NULL, -- YYYY-MM-DD
+    seconds INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (ebook_id, day),
+    FOREIGN KEY (ebook_id) REFERENCES ebooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_reading_daily_activity_day ON reading_daily_activity (day);
+
+CREATE TRIGGER IF NOT EXISTS reading_activity_insert AFTER INSERT ON reading_progress
+WHEN NEW.reading_time_seconds > 0 BEGIN
+    INSERT INTO reading_daily_activity (ebook_id, day, seconds)
+    VALUES (NEW.ebook_id, substr(NEW.last_read_date, 1, 10), NEW.reading_time_seconds)
+    ON CONFLICT (ebook_id, day) DO UPDATE SET seconds = seconds + excluded.seconds;
+END;
+
+CREATE TRIGGER IF NOT EXISTS reading_activity_update AFTER UPDATE OF reading_time_seconds ON reading_progress
+WHEN NEW.reading_time_seconds > COALESCE(OLD.reading_time_seconds, 0) BEGIN
+    INSERT INTO reading_daily_activity (ebook_id, day, seconds)
+    VALUES (NEW.ebook_id, substr(NEW.last_read_date, 1, 10), NEW.reading_time_seconds - COALESCE(OLD.reading_time_seconds, 0))
+    ON CONFLICT (ebook_id, day) DO UPDATE SET seconds = seconds + excluded.seconds;
+END;
@@ -0,0 +1,5 @@
+-- Bitrate (kbps) and codec of each chapter file, filled in when the files are
+-- validated or by the maintenance backfill, so low-quality uploads show up
+-- before they are played
+ALTER TABLE chapters ADD COLUMN bitrate INTEGER;
+ALTER TABLE chapters ADD COLUMN codec TEXT;
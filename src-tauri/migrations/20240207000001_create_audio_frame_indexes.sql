@@ -0,0 +1,13 @@
+-- Frame indexes of MP3 files (see audio::frame_index), built by reading each
+-- file once so VBR files can be seeked exactly. Kept per file and rebuilt
+-- when the file's size or modification time no longer match.
+CREATE TABLE IF NOT EXISTS audio_frame_indexes (
+    file_path TEXT PRIMARY KEY,
+    file_size INTEGER NOT NULL,
+    modified INTEGER NOT NULL, -- seconds since the Unix epoch
+    sample_rate INTEGER NOT NULL,
+    samples_per_frame INTEGER NOT NULL,
+    total_samples INTEGER NOT NULL,
+    points BLOB NOT NULL, -- little-endian (sample, byte offset) pairs
+    created_at TEXT NOT NULL
+);
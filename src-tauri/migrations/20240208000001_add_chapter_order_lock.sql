@@ -0,0 +1,3 @@
+-- Set once a book's chapters were put in order by hand, so rescanning its
+-- folder adds new files at the end instead of sorting by file name again
+ALTER TABLE audiobooks ADD COLUMN chapter_order_locked INTEGER NOT NULL DEFAULT 0;
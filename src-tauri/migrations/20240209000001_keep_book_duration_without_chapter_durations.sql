@@ -0,0 +1,42 @@
+-- The chapter totals triggers only take the duration from the chapters once at
+-- least one of them has a known duration; until then the book keeps the
+-- duration of its file instead of being cleared by a NULL sum.
+
+DROP TRIGGER IF EXISTS chapter_totals_insert;
+DROP TRIGGER IF EXISTS chapter_totals_update;
+DROP TRIGGER IF EXISTS chapter_totals_delete;
+
+CREATE TRIGGER chapter_totals_insert AFTER INSERT ON chapters BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = CASE
+            WHEN EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id AND c.duration IS NOT NULL)
+            THEN (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            ELSE duration
+        END
+    WHERE id = NEW.audiobook_id;
+END;
+
+CREATE TRIGGER chapter_totals_update AFTER UPDATE OF audiobook_id, duration ON chapters BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = CASE
+            WHEN EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id AND c.duration IS NOT NULL)
+            THEN (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            ELSE duration
+        END
+    WHERE id IN (OLD.audiobook_id, NEW.audiobook_id);
+END;
+
+-- Skipped when the whole book is being deleted
+CREATE TRIGGER chapter_totals_delete AFTER DELETE ON chapters
+WHEN EXISTS (SELECT 1 FROM audiobooks WHERE id = OLD.audiobook_id) BEGIN
+    UPDATE audiobooks
+    SET chapters_count = (SELECT COUNT(*) FROM chapters c WHERE c.audiobook_id = audiobooks.id),
+        duration = CASE
+            WHEN EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id AND c.duration IS NOT NULL)
+            THEN (SELECT SUM(c.duration) FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            ELSE duration
+        END
+    WHERE id = OLD.audiobook_id;
+END;
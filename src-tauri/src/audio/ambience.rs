@@ -0,0 +1,231 @@
+// Background ambience mixed under the book on its own sink: generated noise
+// soundscapes, or loops the user drops into the ambience folder. Volume is
+// independent of the book's, and it keeps playing through pauses and track
+// changes until turned off.
+
+use anyhow::{anyhow, Result};
+use rodio::{ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ts_rs::TS;
+
+const NOISE_SAMPLE_RATE: SampleRate = 44_100;
+/// Generated noise is loud at full scale; this keeps volume 1.0 comparable to speech
+const NOISE_LEVEL: f32 = 0.25;
+const LOOP_EXTENSIONS: &[&str] = &["mp3", "ogg", "flac", "wav", "m4a"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+// The usual names for these colors of noise
+#[allow(clippy::enum_variant_names)]
+pub enum Soundscape {
+    /// Even hiss across all frequencies
+    WhiteNoise,
+    /// Softer, rain-like
+    PinkNoise,
+    /// Deep rumble, like distant surf or a fan
+    BrownNoise,
+}
+
+impl Soundscape {
+    pub const ALL: [Soundscape; 3] = [Soundscape::WhiteNoise, Soundscape::PinkNoise, Soundscape::BrownNoise];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Soundscape::WhiteNoise => "white_noise",
+            Soundscape::PinkNoise => "pink_noise",
+            Soundscape::BrownNoise => "brown_noise",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Soundscape::WhiteNoise => "White noise",
+            Soundscape::PinkNoise => "Rain (pink noise)",
+            Soundscape::BrownNoise => "Surf (brown noise)",
+        }
+    }
+}
+
+/// What plays on the ambience channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ambience {
+    Generated(Soundscape),
+    Loop(PathBuf),
+}
+
+impl Ambience {
+    /// A built-in key such as "pink_noise", or the path of a loop file
+    pub fn from_track(track: &str) -> Result<Self> {
+        if let Some(soundscape) = Soundscape::ALL.into_iter().find(|s| s.key() == track) {
+            return Ok(Ambience::Generated(soundscape));
+        }
+        let path = Path::new(track);
+        if !is_loop_file(path) {
+            return Err(anyhow!("Unknown ambience track: {}", track));
+        }
+        Ok(Ambience::Loop(path.to_path_buf()))
+    }
+
+    pub fn track(&self) -> String {
+        match self {
+            Ambience::Generated(soundscape) => soundscape.key().to_string(),
+            Ambience::Loop(path) => path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AmbienceTrack {
+    /// Passed to `set_ambience`
+    pub track: String,
+    pub name: String,
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AmbienceStatus {
+    pub track: Option<String>,
+    pub volume: f32,
+}
+
+/// The built-in soundscapes, then the loops in `loops_dir` by name
+pub fn list_tracks(loops_dir: &Path) -> Vec<AmbienceTrack> {
+    let mut tracks: Vec<AmbienceTrack> = Soundscape::ALL
+        .into_iter()
+        .map(|soundscape| AmbienceTrack { track: soundscape.key().to_string(), name: soundscape.label().to_string(), builtin: true })
+        .collect();
+
+    let mut loops: Vec<AmbienceTrack> = std::fs::read_dir(loops_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_loop_file(path))
+        .map(|path| AmbienceTrack {
+            name: path.file_stem().map(|stem| stem.to_string_lossy().replace('_', " ")).unwrap_or_default(),
+            track: path.to_string_lossy().to_string(),
+            builtin: false,
+        })
+        .collect();
+    loops.sort_by_key(|track| track.name.to_lowercase());
+    tracks.extend(loops);
+    tracks
+}
+
+fn is_loop_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| LOOP_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Endless mono noise. Pink is Paul Kellet's economy filter over white noise;
+/// brown integrates white noise with a leak so it does not drift off.
+pub struct NoiseSource {
+    soundscape: Soundscape,
+    state: u64,
+    pink: [f32; 3],
+    brown: f32,
+}
+
+impl NoiseSource {
+    pub fn new(soundscape: Soundscape) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0x9E37_79B9_7F4A_7C15);
+        Self { soundscape, state: seed | 1, pink: [0.0; 3], brown: 0.0 }
+    }
+
+    /// Uniform in [-1, 1) from xorshift64
+    fn white(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+}
+
+impl Iterator for NoiseSource {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let white = self.white();
+        let value = match self.soundscape {
+            Soundscape::WhiteNoise => white,
+            Soundscape::PinkNoise => {
+                self.pink[0] = 0.99765 * self.pink[0] + white * 0.0990460;
+                self.pink[1] = 0.96300 * self.pink[1] + white * 0.2965164;
+                self.pink[2] = 0.57000 * self.pink[2] + white * 1.0526913;
+                (self.pink[0] + self.pink[1] + self.pink[2] + white * 0.1848) * 0.25
+            }
+            Soundscape::BrownNoise => {
+                self.brown = (self.brown * 0.998 + white * 0.04).clamp(-1.0, 1.0);
+                self.brown * 3.0
+            }
+        };
+        Some((value * NOISE_LEVEL).clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for NoiseSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        1
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        NOISE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Average step between samples; lower means less high-frequency content
+    fn roughness(samples: &[f32]) -> f32 {
+        samples.windows(2).map(|pair| (pair[1] - pair[0]).abs()).sum::<f32>() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_noise_soundscapes() {
+        let mut roughnesses = Vec::new();
+        for soundscape in Soundscape::ALL {
+            let samples: Vec<f32> = NoiseSource::new(soundscape).take(NOISE_SAMPLE_RATE as usize).collect();
+            assert!(samples.iter().all(|s| (-1.0..=1.0).contains(s)));
+            let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+            assert!(rms > 0.01 && rms < 0.5, "{:?} rms {}", soundscape, rms);
+            roughnesses.push(roughness(&samples));
+        }
+        // White is harshest, brown the deepest
+        assert!(roughnesses[0] > roughnesses[1] && roughnesses[1] > roughnesses[2], "{:?}", roughnesses);
+    }
+
+    #[test]
+    fn test_ambience_tracks() {
+        assert_eq!(Ambience::from_track("pink_noise").unwrap(), Ambience::Generated(Soundscape::PinkNoise));
+        assert!(Ambience::from_track("thunder").is_err());
+        assert_eq!(Ambience::from_track("/loops/Rain.ogg").unwrap().track(), "/loops/Rain.ogg");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("fireplace.mp3"), b"").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        let tracks = list_tracks(dir.path());
+        assert_eq!(tracks.len(), 4);
+        assert!(tracks[..3].iter().all(|track| track.builtin));
+        assert_eq!(tracks[3].name, "fireplace");
+        assert!(!tracks[3].builtin);
+    }
+}
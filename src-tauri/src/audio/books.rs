@@ -0,0 +1,248 @@
+// Playing books rather than files: the chapter layout of what is playing,
+// seeking across chapter files, queueing a book from one of its chapters and
+// saving or restoring the player. These look books up in the database and
+// drive the audio thread through its commands.
+
+use super::frame_index;
+use super::thread::{self, AudioCommand};
+use super::{PlaybackState, PlaybackStatus};
+use crate::database::models::UpdatePlaybackProgressDto;
+use crate::database::repository::{ChapterRepository, PlaybackProgressRepository};
+use crate::services::{collection_queue_service, BookLayout, BookPositionService, ChapterPosition, CollectionQueueService, FrameIndexService, PlayHistoryService, PlayerState, PlayerStateService, VoiceBoostService};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+// Layout of the book being played, so status polls twice a second do not query
+// the database; refreshed when the file changes or the entry gets old, which
+// picks up chapter edits made while listening
+static PLAYING_BOOK_LAYOUT: Mutex<Option<(String, Instant, Option<BookLayout>)>> = Mutex::new(None);
+const PLAYING_BOOK_LAYOUT_TTL: Duration = Duration::from_secs(30);
+
+pub async fn playing_book_layout(pool: &sqlx::SqlitePool, file_path: &str) -> Option<BookLayout> {
+    if let Some((cached_file, loaded_at, layout)) = PLAYING_BOOK_LAYOUT.lock().unwrap().as_ref() {
+        if cached_file == file_path && loaded_at.elapsed() < PLAYING_BOOK_LAYOUT_TTL {
+            return layout.clone();
+        }
+    }
+    let layout = BookPositionService::new(pool).layout_for_file(file_path).await.unwrap_or_else(|e| {
+        log::warn!("Failed to load chapters for {}: {}", file_path, e);
+        None
+    });
+    *PLAYING_BOOK_LAYOUT.lock().unwrap() = Some((file_path.to_string(), Instant::now(), layout.clone()));
+    layout
+}
+
+/// Seek to `seconds` into the book the loaded file belongs to
+pub async fn seek_book(
+    pool: &sqlx::SqlitePool,
+    sender: &mpsc::Sender<AudioCommand>,
+    status: PlaybackStatus,
+    seconds: u64,
+) -> Result<Option<ChapterPosition>, String> {
+    let current_file = status.current_file.ok_or("Nothing is loaded")?;
+    let layout = BookPositionService::new(pool).layout_for_file(&current_file).await
+        .map_err(|e| e.to_string())?
+        .ok_or("The loaded file is not part of a book in the library")?;
+    let target = layout.seek_target(seconds).map_err(|e| e.to_string())?;
+
+    let switch_file = target.file_path != current_file;
+    if switch_file {
+        let was_playing = matches!(status.state, PlaybackState::Playing);
+        load_book_from_chapter(pool, sender, &layout.audiobook_id, &target.file_path, was_playing).await?;
+    }
+
+    // A freshly loaded chapter already starts at 0
+    if !switch_file || target.offset > 0 {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::Seek { position: target.offset as f32, response: response_sender })
+            .map_err(|e| format!("Failed to send seek command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+    Ok(layout.position(&target.file_path, target.offset))
+}
+
+/// After a book's chapters were renumbered, play on in the new order: the
+/// chapters queued behind the playing one are put in order, in the audio
+/// thread and in the saved player state
+pub async fn requeue_reordered_book(pool: &sqlx::SqlitePool, audiobook_id: &str) {
+    *PLAYING_BOOK_LAYOUT.lock().unwrap() = None;
+    let book_tracks = match CollectionQueueService::new(pool).audiobook_queue(audiobook_id).await {
+        Ok(tracks) => tracks,
+        Err(e) => {
+            log::warn!("Failed to requeue {} after reordering: {}", audiobook_id, e);
+            return;
+        }
+    };
+
+    if let Some(sender) = thread::running_sender() {
+        let requeued = thread::status(&sender).ok().and_then(|status| {
+            let (response_sender, response_receiver) = mpsc::channel();
+            sender.send(AudioCommand::GetQueue { response: response_sender }).ok()?;
+            let queue = response_receiver.recv().ok()?;
+            collection_queue_service::requeue_book(&queue, &status.current_file?, &book_tracks)
+        });
+        if let Some(tracks) = requeued {
+            let (response_sender, response_receiver) = mpsc::channel();
+            let result = sender.send(AudioCommand::ReplaceQueue { tracks, response: response_sender })
+                .map_err(|e| e.to_string())
+                .and_then(|_| response_receiver.recv().map_err(|e| e.to_string())?);
+            if let Err(e) = result {
+                log::warn!("Failed to requeue {} after reordering: {}", audiobook_id, e);
+            }
+        }
+    }
+
+    let service = PlayerStateService::new(pool);
+    if let Ok(Some(mut state)) = service.load().await {
+        if let Some(queue) = collection_queue_service::requeue_book(&state.queue, &state.current.file_path, &book_tracks) {
+            state.queue = queue;
+            if let Err(e) = service.save(&state).await {
+                log::warn!("Failed to save requeued player state: {}", e);
+            }
+        }
+    }
+}
+
+/// Queue a book from the chapter in `file_path` onwards and load that chapter
+pub async fn load_book_from_chapter(
+    pool: &sqlx::SqlitePool,
+    sender: &mpsc::Sender<AudioCommand>,
+    audiobook_id: &str,
+    file_path: &str,
+    play: bool,
+) -> Result<(), String> {
+    let mut tracks = CollectionQueueService::new(pool).audiobook_queue(audiobook_id).await
+        .map_err(|e| e.to_string())?;
+    let start = tracks.iter().position(|track| track.file_path == file_path)
+        .ok_or("Chapter file is not playable")?;
+    tracks.drain(..start);
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::LoadQueue { tracks, response: response_sender })
+        .map_err(|e| format!("Failed to send queue command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    if play {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::Play { response: response_sender })
+            .map_err(|e| format!("Failed to send play command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+    Ok(())
+}
+
+/// Queue a book's chapters and start playing the first
+pub async fn play_book_from_start(pool: &sqlx::SqlitePool, audiobook_id: &str) -> Result<(), String> {
+    let tracks = CollectionQueueService::new(pool).audiobook_queue(audiobook_id).await
+        .map_err(|e| e.to_string())?;
+    let sender = thread::sender()?;
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::LoadQueue { tracks, response: response_sender })
+        .map_err(|e| format!("Failed to send queue command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::Play { response: response_sender })
+        .map_err(|e| format!("Failed to send play command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+/// Switch voice boost to the setting of the book `file_path` belongs to
+pub async fn apply_book_voice_boost(pool: &sqlx::SqlitePool, file_path: &str) {
+    let audiobook_id = match PlayHistoryService::new(pool).resolve_file(file_path).await {
+        Ok(resolved) => resolved.map(|(audiobook_id, _)| audiobook_id),
+        Err(e) => {
+            log::warn!("Failed to look up book for voice boost: {}", e);
+            return;
+        }
+    };
+    match VoiceBoostService::new(pool).get(audiobook_id.as_deref()).await {
+        Ok(boost) => {
+            if let Err(e) = thread::send_voice_boost(boost.settings) {
+                log::warn!("Failed to apply voice boost: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to load voice boost: {}", e),
+    }
+}
+
+/// Read or build the frame index of a newly loaded MP3 and hand it to the audio
+/// thread. Building one reads the whole file, so callers run it on its own task.
+pub async fn attach_frame_index(pool: sqlx::SqlitePool, file_path: String) {
+    if !frame_index::is_indexable(std::path::Path::new(&file_path)) {
+        return;
+    }
+    match FrameIndexService::new(&pool).get_or_build(&file_path).await {
+        Ok(Some(index)) => {
+            if let Err(e) = thread::send_frame_index(file_path, index) {
+                log::warn!("Failed to hand over frame index: {}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to index frames of {}: {:#}", file_path, e),
+    }
+}
+
+/// Store a playing file's position against the book (and chapter) that owns it
+pub async fn save_position_for_file(pool: &sqlx::SqlitePool, file_path: &str, position: u64) -> anyhow::Result<()> {
+    let Some((audiobook_id, chapter_id)) = PlayHistoryService::new(pool).resolve_file(file_path).await? else {
+        return Ok(());
+    };
+    let chapter_index = match chapter_id {
+        Some(chapter_id) => ChapterRepository::new(pool).find_by_id(&chapter_id).await?
+            .map(|chapter| chapter.chapter_number - 1),
+        None => None,
+    };
+
+    PlaybackProgressRepository::new(pool)
+        .create_or_update(&audiobook_id, UpdatePlaybackProgressDto {
+            position: position as i64,
+            chapter_index,
+            playback_speed: None,
+            is_completed: None,
+        })
+        .await?;
+    Ok(())
+}
+
+/// What the running audio thread has loaded, with its book and chapter looked up;
+/// None when audio has not started or nothing is loaded
+pub async fn current_player_state(pool: &sqlx::SqlitePool) -> Result<Option<PlayerState>, String> {
+    let Some(sender) = thread::running_sender() else {
+        return Ok(None);
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetPlayerState { response: response_sender })
+        .map_err(|e| format!("Failed to send player state command: {}", e))?;
+    let Some(mut state) = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))? else {
+        return Ok(None);
+    };
+    if let Some((audiobook_id, chapter_id)) = PlayHistoryService::new(pool).resolve_file(&state.current.file_path).await
+        .map_err(|e| e.to_string())? {
+        state.audiobook_id = Some(audiobook_id);
+        state.chapter_id = chapter_id;
+    }
+    Ok(Some(state))
+}
+
+/// Put the player back as it was when the app last closed, paused
+pub async fn restore_saved_player_state(pool: &sqlx::SqlitePool) -> Result<Option<PlayerState>, String> {
+    let Some(state) = PlayerStateService::new(pool).load().await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    println!("▶️ PLAYER STATE: Restoring {}s into {}", state.position, state.current.file_path);
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::RestorePlayerState { state: state.clone(), response: response_sender })
+        .map_err(|e| format!("Failed to send restore command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+    Ok(Some(state))
+}
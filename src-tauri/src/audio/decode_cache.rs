@@ -0,0 +1,209 @@
+// Decoded audio of recently played small files, such as intros and short
+// chapters, kept in memory so hopping back and forth between them does not
+// open, probe and decode the same file each time. Only files under
+// MAX_FILE_SIZE are decoded up front; the cache is bounded by entry count and
+// by total samples, and the least recently played file goes first. Entries
+// remember the file's size and modification time, so a replaced file is
+// decoded afresh.
+
+use super::{decoder, AudioInfo};
+use crate::filesystem::long_path::long_path;
+use anyhow::Result;
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Files at least this large are streamed from disk as before
+pub const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+const MAX_ENTRIES: usize = 8;
+/// 96 MiB of f32 samples across all entries
+const MAX_TOTAL_SAMPLES: usize = 24 * 1024 * 1024;
+/// One file may take at most half the budget, so a long low-bitrate file
+/// cannot push everything else out
+const MAX_ENTRY_SAMPLES: usize = MAX_TOTAL_SAMPLES / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    /// The file's size and modification time, if it is small enough to cache
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(long_path(path)).ok()?;
+        (metadata.is_file() && metadata.len() < MAX_FILE_SIZE).then(|| Self { len: metadata.len(), modified: metadata.modified().ok() })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedAudio {
+    stamp: FileStamp,
+    samples: usize,
+    /// Cloning shares the samples; each clone plays from the start
+    pub source: SamplesBuffer,
+    pub info: AudioInfo,
+}
+
+impl CachedAudio {
+    /// Decode all of a small file into memory. None for files that are too
+    /// large, or would decode to more than one entry may hold.
+    pub fn decode(path: &Path, info: &AudioInfo) -> Result<Option<Self>> {
+        let Some(stamp) = FileStamp::read(path) else {
+            return Ok(None);
+        };
+        let estimate = info.duration.unwrap_or(0) as usize
+            * info.sample_rate.unwrap_or(0) as usize
+            * info.channels.unwrap_or(0) as usize;
+        if estimate > MAX_ENTRY_SAMPLES {
+            return Ok(None);
+        }
+
+        let source = decoder::open_source(path)?;
+        let (channels, sample_rate) = (source.channels(), source.sample_rate());
+        if channels == 0 || sample_rate == 0 {
+            return Ok(None);
+        }
+        let samples: Vec<f32> = source.take(MAX_ENTRY_SAMPLES + 1).collect();
+        if samples.is_empty() || samples.len() > MAX_ENTRY_SAMPLES {
+            return Ok(None);
+        }
+
+        let count = samples.len();
+        let source = SamplesBuffer::new(channels, sample_rate, samples);
+        let mut info = info.clone();
+        if info.duration.is_none() {
+            info.duration = source.total_duration().map(|duration| duration.as_secs());
+        }
+        Ok(Some(Self { stamp, samples: count, source, info }))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DecodeCache {
+    /// Most recently used last
+    entries: VecDeque<(PathBuf, CachedAudio)>,
+    total_samples: usize,
+}
+
+impl DecodeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The decoded file, if it is cached and has not changed since
+    pub fn get(&mut self, path: &Path) -> Option<CachedAudio> {
+        let index = self.entries.iter().position(|(cached, _)| cached == path)?;
+        let (path, audio) = self.entries.remove(index)?;
+        if FileStamp::read(&path) != Some(audio.stamp) {
+            self.total_samples -= audio.samples;
+            return None;
+        }
+        self.entries.push_back((path, audio.clone()));
+        Some(audio)
+    }
+
+    pub fn insert(&mut self, path: &Path, audio: CachedAudio) {
+        if let Some(index) = self.entries.iter().position(|(cached, _)| cached == path) {
+            if let Some((_, replaced)) = self.entries.remove(index) {
+                self.total_samples -= replaced.samples;
+            }
+        }
+        while !self.entries.is_empty() && (self.entries.len() >= MAX_ENTRIES || self.total_samples + audio.samples > MAX_TOTAL_SAMPLES) {
+            if let Some((_, evicted)) = self.entries.pop_front() {
+                self.total_samples -= evicted.samples;
+            }
+        }
+        self.total_samples += audio.samples;
+        self.entries.push_back((path.to_path_buf(), audio));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_wav(path: &Path, seconds: u32) {
+        let rate = 8_000u32;
+        let samples: Vec<i16> = (0..rate * seconds).map(|i| (i % 8_000) as i16).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        for value in [16u32, 0x0001_0001, rate, rate * 2, 0x0010_0002] {
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in &samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, wav).unwrap();
+    }
+
+    fn info() -> AudioInfo {
+        AudioInfo {
+            title: None,
+            artist: None,
+            album: None,
+            duration: None,
+            file_size: 0,
+            sample_rate: None,
+            channels: None,
+            bitrate: None,
+            codec: None,
+        }
+    }
+
+    #[test]
+    fn test_small_files_are_decoded_and_kept_until_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let intro = dir.path().join("intro.wav");
+        write_wav(&intro, 2);
+
+        let audio = CachedAudio::decode(&intro, &info()).unwrap().unwrap();
+        assert_eq!(audio.info.duration, Some(2), "filled in from the decoded length");
+        assert_eq!((audio.source.channels(), audio.source.sample_rate()), (1, 8_000));
+        assert_eq!(audio.samples, 16_000);
+
+        let mut cache = DecodeCache::new();
+        assert!(cache.get(&intro).is_none());
+        cache.insert(&intro, audio);
+        let mut hit = cache.get(&intro).unwrap().source;
+        hit.try_seek(std::time::Duration::from_millis(1_500)).unwrap();
+        assert_eq!(hit.next().map(|sample| (sample * 32_768.0).round() as i32), Some(4_000));
+        assert_eq!(cache.get(&intro).unwrap().source.count(), 16_000, "every hit starts at the beginning");
+
+        write_wav(&intro, 1);
+        assert!(cache.get(&intro).is_none(), "a replaced file is decoded again");
+        assert!(cache.entries.is_empty());
+
+        let large = dir.path().join("chapter.wav");
+        std::fs::write(&large, vec![0u8; MAX_FILE_SIZE as usize]).unwrap();
+        assert!(CachedAudio::decode(&large, &info()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_least_recently_played_file_is_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DecodeCache::new();
+        let paths: Vec<PathBuf> = (0..=MAX_ENTRIES).map(|i| dir.path().join(format!("{i}.wav"))).collect();
+        for path in &paths {
+            write_wav(path, 1);
+        }
+        for path in &paths[..MAX_ENTRIES] {
+            cache.insert(path, CachedAudio::decode(path, &info()).unwrap().unwrap());
+        }
+        // Playing the first again makes the second the oldest
+        assert!(cache.get(&paths[0]).is_some());
+        cache.insert(&paths[MAX_ENTRIES], CachedAudio::decode(&paths[MAX_ENTRIES], &info()).unwrap().unwrap());
+
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert_eq!(cache.total_samples, MAX_ENTRIES * 8_000);
+        assert!(cache.get(&paths[1]).is_none());
+        assert!(cache.get(&paths[0]).is_some());
+        assert!(cache.get(&paths[MAX_ENTRIES]).is_some());
+    }
+}
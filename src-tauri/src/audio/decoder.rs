@@ -0,0 +1,366 @@
+// Fallback decoding straight through symphonia. rodio's decoder only plays the
+// container's default track, probes without the file extension and gives up
+// on the first error that is not a damaged packet, so some ALAC files and MP4
+// variants fail there although symphonia itself decodes them. Files rodio
+// rejects are tried again with SymphoniaSource. WMA has no symphonia decoder
+// and stays unsupported either way.
+//
+// MP3s are wrapped in IndexedSource, which seeks through the file's frame
+// index once it has one.
+
+use super::frame_index::FrameIndex;
+use crate::filesystem::backend::{self, Access};
+use anyhow::{anyhow, Context, Result};
+use rodio::source::SeekError;
+use rodio::{ChannelCount, Decoder, SampleRate, Source};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder as CodecDecoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+
+/// Open a file for playback: rodio's decoder first, symphonia directly if that fails
+pub fn open_source(path: &Path) -> Result<Box<dyn Source + Send>> {
+    let file = backend::open(path, Access::Playback)
+        .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
+    match Decoder::try_from(file) {
+        Ok(decoder) => Ok(Box::new(decoder)),
+        Err(rodio_error) => {
+            println!("ENGINE: rodio could not decode {} ({:?}), trying symphonia directly", path.display(), rodio_error);
+            let source = SymphoniaSource::open(path)
+                .with_context(|| format!("Failed to decode audio file '{}': {:?}", path.display(), rodio_error))?;
+            Ok(Box::new(source))
+        }
+    }
+}
+
+pub struct SymphoniaSource {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn CodecDecoder>,
+    track_id: u32,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    total_duration: Option<Duration>,
+    /// Interleaved samples of the last decoded packet
+    buffer: Vec<f32>,
+    position: usize,
+    /// Track timestamp playback starts from; frames before it are dropped
+    /// when a seek lands short of the target
+    skip_until: u64,
+}
+
+impl SymphoniaSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = backend::open(path, Access::Playback)
+            .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+            hint.with_extension(extension);
+        }
+        Self::from_stream(MediaSourceStream::new(Box::new(file), Default::default()), &hint, 0)
+    }
+
+    /// Open an MP3 at the indexed frame a little ahead of `position` and
+    /// decode forward from there to exactly `position`
+    pub fn open_at(path: &Path, index: &FrameIndex, position: Duration) -> Result<Self> {
+        let target = index.sample_at(position);
+        let point = index.seek_point(target);
+        let file = backend::open(path, Access::Playback)
+            .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
+        let stream = OffsetFile::new(file, point.offset)?;
+        let mut hint = Hint::new();
+        hint.with_extension("mp3");
+        // Timestamps of the stream count from the frame it starts at
+        let mut source = Self::from_stream(MediaSourceStream::new(Box::new(stream), Default::default()), &hint, target - point.sample)?;
+        source.total_duration = Some(index.duration());
+        Ok(source)
+    }
+
+    fn from_stream(mss: MediaSourceStream, hint: &Hint, skip_until: u64) -> Result<Self> {
+        let format = symphonia::default::get_probe()
+            .format(hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| anyhow!("Unrecognised audio format: {}", e))?
+            .format;
+
+        // The first track there is a decoder for, whichever one the container calls default
+        let codecs = symphonia::default::get_codecs();
+        let (track, decoder) = format
+            .tracks()
+            .iter()
+            .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .find_map(|track| Some((track.clone(), codecs.make(&track.codec_params, &DecoderOptions::default()).ok()?)))
+            .ok_or_else(|| anyhow!("No audio track with a supported codec"))?;
+        let params = &track.codec_params;
+        let total_duration = params.time_base.zip(params.n_frames).map(|(base, frames)| base.calc_time(frames).into());
+
+        let mut source = Self {
+            format,
+            decoder,
+            track_id: track.id,
+            // Taken from the first decoded packet; headers can disagree with the
+            // decoder, as with HE-AAC that declares mono and decodes to stereo
+            channels: 0,
+            sample_rate: 0,
+            total_duration,
+            buffer: Vec::new(),
+            position: 0,
+            skip_until,
+        };
+        if !source.decode_next() {
+            return Err(anyhow!("No audio could be decoded"));
+        }
+        if source.channels == 0 || source.sample_rate == 0 {
+            return Err(anyhow!("Stream has no channel layout or sample rate"));
+        }
+        Ok(source)
+    }
+
+    /// Refill the buffer from the next packet of the track; false at the end of the stream
+    fn decode_next(&mut self) -> bool {
+        self.buffer.clear();
+        self.position = 0;
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return false,
+                Err(e) => {
+                    log::warn!("Symphonia source stopped: {}", e);
+                    return false;
+                }
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let timestamp = packet.ts();
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Damaged packets are skipped, as rodio does
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => {
+                    log::warn!("Symphonia source stopped: {}", e);
+                    return false;
+                }
+            };
+            let spec = *decoded.spec();
+            if self.channels == 0 {
+                self.channels = spec.channels.count() as ChannelCount;
+            }
+            if self.sample_rate == 0 {
+                self.sample_rate = spec.rate;
+            }
+            let frames = decoded.frames() as u64;
+            if frames == 0 {
+                continue;
+            }
+            let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            samples.copy_interleaved_ref(decoded);
+
+            let skip = self.skip_until.saturating_sub(timestamp).min(frames);
+            let channels = spec.channels.count();
+            self.buffer.extend_from_slice(&samples.samples()[skip as usize * channels..]);
+            if !self.buffer.is_empty() {
+                return true;
+            }
+        }
+    }
+}
+
+impl Iterator for SymphoniaSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.buffer.len() && !self.decode_next() {
+            return None;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for SymphoniaSource {
+    fn current_span_len(&self) -> Option<usize> {
+        // Books keep one layout throughout; the first packet's is used for all of it
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let time = Time::new(pos.as_secs(), pos.subsec_nanos() as f64 / 1_000_000_000.0);
+        let seeked = self
+            .format
+            .seek(SeekMode::Accurate, SeekTo::Time { time, track_id: Some(self.track_id) })
+            .map_err(|e| SeekError::Other(Box::new(e)))?;
+        self.decoder.reset();
+        self.buffer.clear();
+        self.position = 0;
+        self.skip_until = seeked.required_ts;
+        Ok(())
+    }
+}
+
+/// A file read from `start` on, as if the bytes before it were not there, so
+/// symphonia takes the frame at `start` for the beginning of the stream
+struct OffsetFile {
+    file: File,
+    start: u64,
+    len: u64,
+}
+
+impl OffsetFile {
+    fn new(mut file: File, start: u64) -> std::io::Result<Self> {
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(start))?;
+        Ok(Self { file, start, len })
+    }
+}
+
+impl Read for OffsetFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Seek for OffsetFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(self.start + offset),
+            other => other,
+        };
+        let absolute = self.file.seek(pos)?;
+        absolute.checked_sub(self.start).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Seek before the start of the stream"))
+    }
+}
+
+impl MediaSource for OffsetFile {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len.saturating_sub(self.start))
+    }
+}
+
+/// Frame index of the file an IndexedSource plays, empty until it is ready
+pub type FrameIndexSlot = Arc<Mutex<Option<Arc<FrameIndex>>>>;
+
+/// An MP3 decoded as open_source would, whose seeks go through the file's
+/// frame index once the slot is filled. The decoder's own seeks trust a
+/// duration estimated from the first frames, which for VBR files is off.
+pub struct IndexedSource {
+    path: PathBuf,
+    inner: Box<dyn Source + Send>,
+    index: FrameIndexSlot,
+    /// Channel the next sample belongs to, kept across seeks
+    channel: usize,
+}
+
+impl IndexedSource {
+    pub fn new(path: &Path, inner: Box<dyn Source + Send>, index: FrameIndexSlot) -> Self {
+        Self { path: path.to_path_buf(), inner, index, channel: 0 }
+    }
+}
+
+impl Iterator for IndexedSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.inner.next()?;
+        self.channel = (self.channel + 1) % self.inner.channels().max(1) as usize;
+        Some(sample)
+    }
+}
+
+impl Source for IndexedSource {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self.index.lock().unwrap().as_ref() {
+            Some(index) => Some(index.duration()),
+            None => self.inner.total_duration(),
+        }
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let index = self.index.lock().unwrap().clone();
+        let Some(index) = index else {
+            return self.inner.try_seek(pos);
+        };
+        let mut source = SymphoniaSource::open_at(&self.path, &index, pos).map_err(|e| SeekError::Other(e.into()))?;
+        for _ in 0..self.channel {
+            source.next();
+        }
+        self.inner = Box::new(source);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symphonia_source_plays_and_seeks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ramp.wav");
+        let rate = 8_000u32;
+        let samples: Vec<i16> = (0..rate * 2).map(|i| (i % 8_000) as i16).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        for value in [16u32, 0x0001_0001, rate, rate * 2, 0x0010_0002] {
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in &samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(&path, wav).unwrap();
+
+        let mut source = SymphoniaSource::open(&path).unwrap();
+        assert_eq!((source.channels(), source.sample_rate()), (1, rate));
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(2)));
+        let value = |sample: f32| (sample * 32_768.0).round() as i32;
+        assert_eq!(source.next().map(value), Some(0));
+        assert_eq!(source.next().map(value), Some(1));
+
+        source.try_seek(Duration::from_millis(1_500)).unwrap();
+        assert_eq!(source.next().map(value), Some(4_000), "lands on the exact frame");
+        assert_eq!(source.count(), 3_999);
+
+        assert!(SymphoniaSource::open(&dir.path().join("missing.m4b")).is_err());
+    }
+}
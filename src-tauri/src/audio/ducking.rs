@@ -0,0 +1,286 @@
+// Sidechain ducking: the narration passes through a level meter, and the
+// ambience through a gain stage that dips while the meter hears speech and
+// comes back up in the pauses. Both run in the output callback, so the level
+// is handed over through an atomic.
+
+use rodio::source::SeekError;
+use rodio::{ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use ts_rs::TS;
+
+/// Narration envelope above this (about -34 dBFS) counts as speech
+const SPEECH_THRESHOLD: f32 = 0.02;
+/// The envelope rises almost at once and falls slowly, so gaps between words
+/// do not let the ambience pump back up
+const METER_ATTACK_MS: f32 = 5.0;
+const METER_RELEASE_MS: f32 = 250.0;
+
+pub const MAX_DEPTH_DB: f32 = 40.0;
+pub const MAX_TIME_MS: u32 = 5000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuckingSettings {
+    pub enabled: bool,
+    /// How far the ambience is lowered during speech, in dB
+    pub depth_db: f32,
+    /// Time to duck once speech starts
+    pub attack_ms: u32,
+    /// Time to come back up once speech stops
+    pub release_ms: u32,
+}
+
+impl Default for DuckingSettings {
+    fn default() -> Self {
+        Self { enabled: true, depth_db: 12.0, attack_ms: 80, release_ms: 600 }
+    }
+}
+
+impl DuckingSettings {
+    pub fn clamped(self) -> Self {
+        Self {
+            enabled: self.enabled,
+            depth_db: self.depth_db.clamp(0.0, MAX_DEPTH_DB),
+            attack_ms: self.attack_ms.clamp(1, MAX_TIME_MS),
+            release_ms: self.release_ms.clamp(1, MAX_TIME_MS),
+        }
+    }
+}
+
+/// Shared between the meter on the narration and the ducker on the ambience
+#[derive(Debug)]
+pub struct DuckingControl {
+    level_bits: AtomicU32,
+    enabled: AtomicBool,
+    depth_db_bits: AtomicU32,
+    attack_ms: AtomicU32,
+    release_ms: AtomicU32,
+}
+
+impl DuckingControl {
+    pub fn new(settings: DuckingSettings) -> Self {
+        let control = Self {
+            level_bits: AtomicU32::new(0.0f32.to_bits()),
+            enabled: AtomicBool::new(false),
+            depth_db_bits: AtomicU32::new(0.0f32.to_bits()),
+            attack_ms: AtomicU32::new(0),
+            release_ms: AtomicU32::new(0),
+        };
+        control.apply(settings);
+        control
+    }
+
+    pub fn apply(&self, settings: DuckingSettings) {
+        let settings = settings.clamped();
+        self.enabled.store(settings.enabled, Ordering::Relaxed);
+        self.depth_db_bits.store(settings.depth_db.to_bits(), Ordering::Relaxed);
+        self.attack_ms.store(settings.attack_ms, Ordering::Relaxed);
+        self.release_ms.store(settings.release_ms, Ordering::Relaxed);
+    }
+
+    /// A paused sink stops pulling samples, so the meter would hold its last
+    /// reading; the engine clears it whenever narration stops
+    pub fn clear_level(&self) {
+        self.level_bits.store(0.0f32.to_bits(), Ordering::Relaxed);
+    }
+
+    fn level(&self) -> f32 {
+        f32::from_bits(self.level_bits.load(Ordering::Relaxed))
+    }
+
+    fn set_level(&self, level: f32) {
+        self.level_bits.store(level.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Gain the ambience is heading for right now
+    fn target_gain(&self) -> f32 {
+        if !self.enabled.load(Ordering::Relaxed) || self.level() < SPEECH_THRESHOLD {
+            return 1.0;
+        }
+        let depth_db = f32::from_bits(self.depth_db_bits.load(Ordering::Relaxed));
+        10f32.powf(-depth_db / 20.0)
+    }
+}
+
+/// One-pole smoothing coefficient for a time constant, per interleaved sample
+fn coefficient(time_ms: f32, sample_rate: SampleRate, channels: ChannelCount) -> f32 {
+    let samples = time_ms / 1000.0 * sample_rate as f32 * channels.max(1) as f32;
+    if samples <= 1.0 { 0.0 } else { (-1.0 / samples).exp() }
+}
+
+/// Passes narration through untouched, publishing its envelope
+pub struct LevelMeter<S: Source> {
+    inner: S,
+    control: Arc<DuckingControl>,
+    envelope: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl<S: Source> LevelMeter<S> {
+    pub fn new(inner: S, control: Arc<DuckingControl>) -> Self {
+        let (rate, channels) = (inner.sample_rate(), inner.channels());
+        Self {
+            attack: coefficient(METER_ATTACK_MS, rate, channels),
+            release: coefficient(METER_RELEASE_MS, rate, channels),
+            inner,
+            control,
+            envelope: 0.0,
+        }
+    }
+}
+
+impl<S: Source> Iterator for LevelMeter<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let Some(sample) = self.inner.next() else {
+            self.control.clear_level();
+            return None;
+        };
+        let level = sample.abs();
+        let coefficient = if level > self.envelope { self.attack } else { self.release };
+        self.envelope = level + (self.envelope - level) * coefficient;
+        self.control.set_level(self.envelope);
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for LevelMeter<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        self.envelope = 0.0;
+        self.control.clear_level();
+        Ok(())
+    }
+}
+
+/// Lowers the ambience while the meter hears speech
+pub struct Ducker<S: Source> {
+    inner: S,
+    control: Arc<DuckingControl>,
+    gain: f32,
+}
+
+impl<S: Source> Ducker<S> {
+    pub fn new(inner: S, control: Arc<DuckingControl>) -> Self {
+        Self { inner, control, gain: 1.0 }
+    }
+}
+
+impl<S: Source> Iterator for Ducker<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let sample = self.inner.next()?;
+        let target = self.control.target_gain();
+        let time_ms = if target < self.gain {
+            self.control.attack_ms.load(Ordering::Relaxed)
+        } else {
+            self.control.release_ms.load(Ordering::Relaxed)
+        };
+        let coefficient = coefficient(time_ms as f32, self.inner.sample_rate(), self.inner.channels());
+        self.gain = target + (self.gain - target) * coefficient;
+        Some(sample * self.gain)
+    }
+}
+
+impl<S: Source> Source for Ducker<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    const RATE: u32 = 8000;
+
+    fn constant(value: f32, seconds: f32) -> SamplesBuffer {
+        SamplesBuffer::new(1, RATE, vec![value; (seconds * RATE as f32) as usize])
+    }
+
+    #[test]
+    fn test_ambience_ducks_under_speech_and_recovers() {
+        let settings = DuckingSettings { enabled: true, depth_db: 20.0, attack_ms: 50, release_ms: 200 };
+        let control = Arc::new(DuckingControl::new(settings));
+        let mut narration = LevelMeter::new(constant(0.5, 2.0), control.clone());
+        let mut ambience = Ducker::new(constant(1.0, 10.0), control.clone());
+
+        // Silence before the narration starts: full level
+        assert!((ambience.next().unwrap() - 1.0).abs() < 1e-6);
+
+        // Half a second of speech with the two sinks pulled in step
+        let mut last = 1.0;
+        for _ in 0..RATE / 2 {
+            narration.next();
+            last = ambience.next().unwrap();
+        }
+        assert!((last - 0.1).abs() < 0.01, "ducked by 20 dB, got {}", last);
+
+        // Narration pauses; the level is cleared and the ambience comes back
+        control.clear_level();
+        for _ in 0..RATE {
+            last = ambience.next().unwrap();
+        }
+        assert!(last > 0.95, "restored after the pause, got {}", last);
+    }
+
+    #[test]
+    fn test_disabled_ducking_and_quiet_narration_leave_ambience_alone() {
+        let control = Arc::new(DuckingControl::new(DuckingSettings { enabled: false, ..DuckingSettings::default() }));
+        let mut narration = LevelMeter::new(constant(0.5, 1.0), control.clone());
+        let mut ambience = Ducker::new(constant(1.0, 1.0), control.clone());
+        for _ in 0..RATE / 2 {
+            narration.next();
+            assert_eq!(ambience.next().unwrap(), 1.0);
+        }
+
+        control.apply(DuckingSettings::default());
+        let mut hiss = LevelMeter::new(constant(0.005, 1.0), control.clone());
+        for _ in 0..RATE / 2 {
+            hiss.next();
+            assert_eq!(ambience.next().unwrap(), 1.0, "background hiss is no speech");
+        }
+
+        let clamped = DuckingSettings { enabled: true, depth_db: 90.0, attack_ms: 0, release_ms: 60_000 }.clamped();
+        assert_eq!((clamped.depth_db, clamped.attack_ms, clamped.release_ms), (MAX_DEPTH_DB, 1, MAX_TIME_MS));
+    }
+}
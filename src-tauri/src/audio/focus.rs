@@ -0,0 +1,247 @@
+// Audio focus: what playback does when another application starts playing or a
+// call comes in. The platform's audio sessions are polled while the book plays,
+// or while focus holds it ducked or paused, and the decisions made here become
+// commands for the audio thread. Only PulseAudio and
+// PipeWire expose other applications' streams; Windows already lowers other
+// sounds during calls by itself, and macOS offers no way to see them.
+
+use super::thread::{self, AudioCommand};
+use super::{settings, PlaybackState};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ts_rs::TS;
+
+pub const PREF_AUDIO_FOCUS: &str = "audio.focus";
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_DUCK_DB: f32 = 40.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusAction {
+    Ignore,
+    /// Lower the book by `duck_db` until the other audio stops
+    Duck,
+    /// Pause, and resume once the other audio stops
+    Pause,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudioFocusSettings {
+    pub on_other_audio: FocusAction,
+    pub on_call: FocusAction,
+    pub duck_db: f32,
+}
+
+impl Default for AudioFocusSettings {
+    fn default() -> Self {
+        Self { on_other_audio: FocusAction::Ignore, on_call: FocusAction::Pause, duck_db: 12.0 }
+    }
+}
+
+impl AudioFocusSettings {
+    pub fn is_active(&self) -> bool {
+        self.on_other_audio != FocusAction::Ignore || self.on_call != FocusAction::Ignore
+    }
+
+    /// Linear gain for the configured duck depth
+    pub fn duck_gain(&self) -> f32 {
+        10f32.powf(-self.duck_db.clamp(0.0, MAX_DUCK_DB) / 20.0)
+    }
+}
+
+/// What other applications are doing with audio right now
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OtherAudio {
+    pub playing: bool,
+    pub call: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FocusCommand {
+    /// Multiplies the book's volume; 1.0 undoes ducking
+    SetGain(f32),
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Default)]
+pub struct FocusState {
+    ducked: bool,
+    /// Only playback that focus paused is resumed by it
+    paused_by_focus: bool,
+}
+
+impl FocusState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether other audio needs checking: while the book plays, or while focus
+    /// has ducked or paused it and has to undo that when the other audio stops
+    pub fn needs_probe(&self, playing: bool) -> bool {
+        playing || self.ducked || self.paused_by_focus
+    }
+
+    pub fn next(&mut self, other: OtherAudio, settings: &AudioFocusSettings, playing: bool) -> Vec<FocusCommand> {
+        let action = if other.call {
+            settings.on_call
+        } else if other.playing {
+            settings.on_other_audio
+        } else {
+            FocusAction::Ignore
+        };
+
+        let mut commands = Vec::new();
+        let duck = action == FocusAction::Duck;
+        if duck != self.ducked {
+            commands.push(FocusCommand::SetGain(if duck { settings.duck_gain() } else { 1.0 }));
+            self.ducked = duck;
+        }
+        match action {
+            FocusAction::Pause if playing && !self.paused_by_focus => {
+                commands.push(FocusCommand::Pause);
+                self.paused_by_focus = true;
+            }
+            FocusAction::Pause => {}
+            _ if self.paused_by_focus => {
+                self.paused_by_focus = false;
+                if !playing {
+                    commands.push(FocusCommand::Resume);
+                }
+            }
+            _ => {}
+        }
+        commands
+    }
+}
+
+/// Watch for other applications' audio and calls, and duck or pause the book
+/// as the focus settings say. Resumes only what it paused itself.
+pub fn start_monitor() {
+    static STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+    if STARTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut focus = FocusState::new();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let (Some(sender), Some(live)) = (thread::running_sender(), thread::live_status()) else {
+                continue;
+            };
+            // Read without waiting on the audio thread, so an idle player costs nothing
+            let playing = matches!(live.read().state, PlaybackState::Playing);
+            if !focus.needs_probe(playing) {
+                continue;
+            }
+            let settings = settings::audio_focus();
+            // With focus handling off, whatever is in effect is undone
+            let other = if settings.is_active() {
+                match tokio::task::spawn_blocking(probe_other_audio).await.ok().flatten() {
+                    Some(other) => other,
+                    None => continue,
+                }
+            } else {
+                OtherAudio::default()
+            };
+
+            for command in focus.next(other, &settings, playing) {
+                println!("🔉 FOCUS: {:?} ({:?})", command, other);
+                let (response_sender, response_receiver) = std::sync::mpsc::channel();
+                let command = match command {
+                    FocusCommand::SetGain(gain) => AudioCommand::SetFocusGain { gain, response: response_sender },
+                    FocusCommand::Pause => AudioCommand::Pause { response: response_sender },
+                    FocusCommand::Resume => AudioCommand::Play { response: response_sender },
+                };
+                if sender.send(command).is_err() {
+                    break;
+                }
+                if let Ok(Err(e)) = response_receiver.recv() {
+                    log::warn!("FOCUS: Audio thread refused command: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Poll the platform for other applications' audio; None where that is not possible
+pub fn probe_other_audio() -> Option<OtherAudio> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // The parser matches English labels, which pactl translates otherwise
+        let output = std::process::Command::new("pactl")
+            .args(["list", "sink-inputs"])
+            .env("LC_ALL", "C")
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(parse_sink_inputs(&String::from_utf8_lossy(&output.stdout), std::process::id()))
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        None
+    }
+}
+
+/// Other processes' streams in `pactl list sink-inputs` output that are not
+/// corked (paused). Streams with the phone role are calls.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_sink_inputs(output: &str, own_pid: u32) -> OtherAudio {
+    let mut other = OtherAudio::default();
+    for block in output.split("Sink Input #").skip(1) {
+        let property = |name: &str| {
+            block.lines().find_map(|line| {
+                let (key, value) = line.trim().split_once(" = ")?;
+                (key == name).then(|| value.trim_matches('"').to_string())
+            })
+        };
+        let corked = block.lines().any(|line| line.trim() == "Corked: yes");
+        let own = property("application.process.id").is_some_and(|pid| pid == own_pid.to_string());
+        if corked || own {
+            continue;
+        }
+        if property("media.role").is_some_and(|role| role == "phone") {
+            other.call = true;
+        } else {
+            other.playing = true;
+        }
+    }
+    other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_ducks_pauses_and_resumes_only_what_it_paused() {
+        let settings = AudioFocusSettings { on_other_audio: FocusAction::Duck, on_call: FocusAction::Pause, duck_db: 20.0 };
+        let quiet = OtherAudio::default();
+        let music = OtherAudio { playing: true, call: false };
+        let call = OtherAudio { playing: false, call: true };
+        let mut state = FocusState::new();
+
+        assert!(matches!(state.next(music, &settings, true).as_slice(), [FocusCommand::SetGain(gain)] if (gain - 0.1).abs() < 1e-6));
+        assert!(state.next(music, &settings, true).is_empty());
+        assert_eq!(state.next(call, &settings, true), vec![FocusCommand::SetGain(1.0), FocusCommand::Pause]);
+        assert!(state.next(call, &settings, false).is_empty());
+        assert!(state.needs_probe(false), "paused by focus, so it waits for the call to end");
+        assert_eq!(state.next(quiet, &settings, false), vec![FocusCommand::Resume]);
+        assert!(!state.needs_probe(false), "stopped by the listener, nothing to watch");
+
+        // Paused by the listener, not by focus: stays paused
+        assert!(state.next(call, &settings, false).is_empty());
+        assert!(state.next(quiet, &settings, false).is_empty());
+
+        let output = "Sink Input #41\n\tCorked: no\n\tProperties:\n\t\tapplication.name = \"AudioVibe\"\n\t\tapplication.process.id = \"100\"\n\
+                      Sink Input #42\n\tCorked: yes\n\tProperties:\n\t\tapplication.process.id = \"200\"\n\
+                      Sink Input #43\n\tCorked: no\n\tProperties:\n\t\tmedia.role = \"phone\"\n\t\tapplication.process.id = \"300\"\n";
+        assert_eq!(parse_sink_inputs(output, 100), call);
+        assert_eq!(parse_sink_inputs(output, 300), music, "own and corked streams do not count");
+    }
+}
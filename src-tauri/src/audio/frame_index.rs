@@ -0,0 +1,289 @@
+// Where each MPEG audio frame of an MP3 starts, found by walking the frame
+// headers once. symphonia only knows a VBR file's length from a Xing or VBRI
+// header, and estimates it from the first few frames otherwise, so seeks are
+// clamped to a wrong duration and seeking backwards reads the file again from
+// the start. With the index a seek opens the file a few frames before the
+// target and decodes forward to the exact sample.
+//
+// Only every POINT_INTERVAL-th frame is kept, which is enough to land within
+// a second of any position before decoding the rest of the way.
+
+use crate::filesystem::backend::{self, Access};
+use anyhow::{Context, Result};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+const POINT_INTERVAL: u64 = 32;
+/// Frames decoded and thrown away ahead of the target, so the bit reservoir
+/// and the decoder's overlap are filled by the time it is reached
+const PRIMING_FRAMES: u64 = 10;
+/// Bytes searched for the next frame header after losing sync before giving up
+const MAX_RESYNC_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeekPoint {
+    /// First sample of the frame, counted per channel from the first audio frame
+    pub sample: u64,
+    /// Byte offset of the frame header in the file
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameIndex {
+    pub sample_rate: u32,
+    pub samples_per_frame: u32,
+    pub total_samples: u64,
+    points: Vec<SeekPoint>,
+}
+
+impl FrameIndex {
+    /// Rebuild an index stored with `points_blob`; None if the blob is damaged
+    pub fn from_parts(sample_rate: u32, samples_per_frame: u32, total_samples: u64, blob: &[u8]) -> Option<Self> {
+        if sample_rate == 0 || samples_per_frame == 0 || blob.is_empty() || !blob.len().is_multiple_of(16) {
+            return None;
+        }
+        let points = blob
+            .chunks_exact(16)
+            .map(|chunk| SeekPoint {
+                sample: u64::from_le_bytes(chunk[..8].try_into().unwrap()),
+                offset: u64::from_le_bytes(chunk[8..].try_into().unwrap()),
+            })
+            .collect();
+        Some(Self { sample_rate, samples_per_frame, total_samples, points })
+    }
+
+    /// The seek points as little-endian (sample, offset) pairs
+    pub fn points_blob(&self) -> Vec<u8> {
+        self.points
+            .iter()
+            .flat_map(|point| point.sample.to_le_bytes().into_iter().chain(point.offset.to_le_bytes()))
+            .collect()
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.total_samples as f64 / self.sample_rate as f64)
+    }
+
+    /// The sample at `position`, kept inside the file
+    pub fn sample_at(&self, position: Duration) -> u64 {
+        let sample = (position.as_secs_f64() * self.sample_rate as f64).round() as u64;
+        sample.min(self.total_samples.saturating_sub(1))
+    }
+
+    /// The point to start decoding from to reach `sample`, far enough ahead
+    /// of it for the first frames to prime the decoder
+    pub fn seek_point(&self, sample: u64) -> SeekPoint {
+        let from = sample.saturating_sub(PRIMING_FRAMES * self.samples_per_frame as u64);
+        let after = self.points.partition_point(|point| point.sample <= from);
+        self.points[after.saturating_sub(1)]
+    }
+}
+
+/// Whether a file is one frame indexes are built for
+pub fn is_indexable(path: &Path) -> bool {
+    path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| extension.eq_ignore_ascii_case("mp3"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameHeader {
+    mpeg1: bool,
+    mono: bool,
+    sample_rate: u32,
+    length: u64,
+}
+
+impl FrameHeader {
+    /// A Layer III frame header; other layers, free-format bitrates and
+    /// reserved values are treated as lost sync
+    fn parse(bytes: [u8; 4]) -> Option<Self> {
+        const BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+        const BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+        const SAMPLE_RATES: [u32; 3] = [44_100, 48_000, 32_000];
+
+        let header = u32::from_be_bytes(bytes);
+        if header >> 21 != 0x7FF || (header >> 17) & 3 != 1 {
+            return None;
+        }
+        let version = (header >> 19) & 3;
+        let bitrate_index = ((header >> 12) & 0xF) as usize;
+        let rate_index = ((header >> 10) & 3) as usize;
+        if version == 1 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+            return None;
+        }
+        let mpeg1 = version == 3;
+        let (bitrate, sample_rate) = match version {
+            3 => (BITRATES_V1[bitrate_index], SAMPLE_RATES[rate_index]),
+            2 => (BITRATES_V2[bitrate_index], SAMPLE_RATES[rate_index] / 2),
+            _ => (BITRATES_V2[bitrate_index], SAMPLE_RATES[rate_index] / 4),
+        };
+        let padding = (header >> 9) & 1;
+        let coefficient = if mpeg1 { 144 } else { 72 };
+        Some(Self {
+            mpeg1,
+            mono: (header >> 6) & 3 == 3,
+            sample_rate,
+            length: (coefficient * bitrate * 1000 / sample_rate + padding) as u64,
+        })
+    }
+
+    fn samples(&self) -> u32 {
+        if self.mpeg1 { 1152 } else { 576 }
+    }
+
+    /// Whether the frame holds a Xing, Info or VBRI tag rather than audio
+    fn is_info_frame(&self, frame: &[u8]) -> bool {
+        let side_info = match (self.mpeg1, self.mono) {
+            (true, false) => 32,
+            (true, true) | (false, false) => 17,
+            (false, true) => 9,
+        };
+        let tag_at = |at: usize| frame.get(at..at + 4);
+        matches!(tag_at(4 + side_info), Some(b"Xing" | b"Info")) || tag_at(4 + 32) == Some(b"VBRI")
+    }
+}
+
+/// Walk the frames of an MP3 once. None when no MPEG audio frames are found.
+pub fn scan(path: &Path) -> Result<Option<FrameIndex>> {
+    let file = backend::open(path, Access::Scan).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::with_capacity(64 * 1024, file);
+    let mut offset = id3v2_length(&mut reader)?;
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut first: Option<FrameHeader> = None;
+    let mut points = Vec::new();
+    let mut frames = 0u64;
+    let mut total_samples = 0u64;
+    let mut lost_at: Option<u64> = None;
+    let mut bytes = [0u8; 4];
+    loop {
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+        // A header with another sample rate than the first frame is stray bytes, not a frame
+        let header = FrameHeader::parse(bytes).filter(|header| first.is_none_or(|first| first.sample_rate == header.sample_rate));
+        let Some(header) = header else {
+            let since = *lost_at.get_or_insert(offset);
+            if offset - since > MAX_RESYNC_BYTES {
+                break;
+            }
+            offset += 1;
+            reader.seek_relative(-3)?;
+            continue;
+        };
+        lost_at = None;
+
+        if first.is_none() {
+            first = Some(header);
+            let mut frame = bytes.to_vec();
+            let read = (&mut reader).take(header.length.saturating_sub(4)).read_to_end(&mut frame)? as i64;
+            if header.is_info_frame(&frame) {
+                offset += header.length;
+                reader.seek_relative(header.length as i64 - 4 - read)?;
+                continue;
+            }
+            reader.seek_relative(-read)?;
+        }
+
+        if frames.is_multiple_of(POINT_INTERVAL) {
+            points.push(SeekPoint { sample: total_samples, offset });
+        }
+        frames += 1;
+        total_samples += header.samples() as u64;
+        offset += header.length;
+        reader.seek_relative(header.length as i64 - 4)?;
+    }
+
+    Ok(first.filter(|_| !points.is_empty()).map(|first| FrameIndex {
+        sample_rate: first.sample_rate,
+        samples_per_frame: first.samples(),
+        total_samples,
+        points,
+    }))
+}
+
+/// Bytes taken by an ID3v2 tag at the start of the file, 0 without one
+fn id3v2_length<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut header = [0u8; 10];
+    match reader.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(0),
+        Err(e) => return Err(e.into()),
+    }
+    if &header[..3] != b"ID3" {
+        return Ok(0);
+    }
+    let size = header[6..10].iter().fold(0u64, |size, byte| (size << 7) | (byte & 0x7F) as u64);
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    Ok(10 + size + footer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An MPEG-1 Layer III frame at 44.1 kHz of `kbps` with a silent body
+    fn frame(kbps: u32) -> Vec<u8> {
+        let index = [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320].iter().position(|&rate| rate == kbps).unwrap() as u8 + 1;
+        let mut frame = vec![0xFF, 0xFB, index << 4, 0x00];
+        frame.resize((144 * kbps * 1000 / 44_100) as usize, 0);
+        frame
+    }
+
+    /// A VBR MP3: an ID3v2 tag, a Xing frame, then `frames` frames cycling
+    /// through three bitrates
+    fn vbr_mp3(frames: usize) -> Vec<u8> {
+        let mut file = b"ID3\x04\x00\x00\x00\x00\x01\x00".to_vec();
+        file.resize(10 + 128, 0);
+        let mut xing = frame(128);
+        xing[36..40].copy_from_slice(b"Xing");
+        file.extend(xing);
+        for i in 0..frames {
+            file.extend(frame([64, 128, 320][i % 3]));
+        }
+        file
+    }
+
+    #[test]
+    fn test_scan_counts_audio_frames_of_a_vbr_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.mp3");
+        let mut bytes = vbr_mp3(100);
+        bytes.extend_from_slice(b"TAG");
+        bytes.resize(bytes.len() + 125, 0);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let index = scan(&path).unwrap().unwrap();
+        assert_eq!((index.sample_rate, index.samples_per_frame), (44_100, 1_152));
+        assert_eq!(index.total_samples, 100 * 1_152, "the Xing frame and ID3v1 tag are not audio");
+        assert_eq!(index.points.len(), 4);
+
+        // Tag, Xing frame, then 32 frames of 208, 417 and 1044 bytes in turn
+        let first_audio = 138 + 417;
+        assert_eq!(index.points[0], SeekPoint { sample: 0, offset: first_audio });
+        let cycle = 208 + 417 + 1_044;
+        assert_eq!(index.points[1], SeekPoint { sample: 32 * 1_152, offset: first_audio + 10 * cycle + 208 + 417 });
+        assert_eq!(&bytes[index.points[3].offset as usize..][..2], &[0xFF, 0xFB]);
+
+        assert_eq!(index.duration(), Duration::from_secs_f64(115_200.0 / 44_100.0));
+        assert_eq!(index.sample_at(Duration::from_secs(60)), 115_199, "kept inside the file");
+        assert_eq!(index.seek_point(41 * 1_152).sample, 0, "ten frames of priming go back past point 1");
+        assert_eq!(index.seek_point(42 * 1_152).sample, 32 * 1_152);
+
+        let stored = FrameIndex::from_parts(44_100, 1_152, index.total_samples, &index.points_blob()).unwrap();
+        assert_eq!(stored, index);
+        assert!(FrameIndex::from_parts(44_100, 1_152, 0, &[0; 15]).is_none());
+    }
+
+    #[test]
+    fn test_scan_finds_nothing_in_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.mp3");
+        std::fs::write(&path, b"not an mpeg stream at all").unwrap();
+        assert!(scan(&path).unwrap().is_none());
+        assert!(is_indexable(Path::new("/books/a/01.MP3")));
+        assert!(!is_indexable(Path::new("/books/a/01.m4b")));
+    }
+}
@@ -0,0 +1,160 @@
+// Playback state the engine publishes after every change, for status polling that
+// must not wait on the audio thread. The position keeps moving while playing:
+// readers work it out from the published clock the same way the engine does.
+
+use super::PlaybackState;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// Stands in for None in the u64 slots
+const UNSET: u64 = u64::MAX;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LiveStatus {
+    pub state: PlaybackState,
+    #[ts(type = "number")]
+    pub position: u64,
+    #[ts(type = "number | null")]
+    pub duration: Option<u64>,
+    pub volume: f32,
+    pub speed: f32,
+}
+
+/// What the engine's position clock was at its last change
+#[derive(Debug, Clone, Copy)]
+pub struct PositionClock {
+    pub seek_offset: u64,
+    pub started_at: Option<Instant>,
+    pub paused_at: Option<Instant>,
+    pub paused_duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct AtomicPositionState {
+    epoch: Instant,
+    state: AtomicU8,
+    seek_offset: AtomicU64,
+    started_at_ms: AtomicU64,
+    paused_at_ms: AtomicU64,
+    paused_ms: AtomicU64,
+    duration: AtomicU64,
+    volume: AtomicU32,
+    speed: AtomicU32,
+}
+
+impl AtomicPositionState {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            state: AtomicU8::new(encode_state(&PlaybackState::Stopped)),
+            seek_offset: AtomicU64::new(0),
+            started_at_ms: AtomicU64::new(UNSET),
+            paused_at_ms: AtomicU64::new(UNSET),
+            paused_ms: AtomicU64::new(0),
+            duration: AtomicU64::new(UNSET),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            speed: AtomicU32::new(1.0f32.to_bits()),
+        }
+    }
+
+    pub fn publish(&self, state: &PlaybackState, clock: PositionClock, duration: Option<u64>, volume: f32, speed: f32) {
+        self.seek_offset.store(clock.seek_offset, Ordering::Relaxed);
+        self.started_at_ms.store(self.millis(clock.started_at), Ordering::Relaxed);
+        self.paused_at_ms.store(self.millis(clock.paused_at), Ordering::Relaxed);
+        self.paused_ms.store(clock.paused_duration.as_millis() as u64, Ordering::Relaxed);
+        self.duration.store(duration.unwrap_or(UNSET), Ordering::Relaxed);
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+        // Written last so a reader seeing the new state also sees its clock
+        self.state.store(encode_state(state), Ordering::Release);
+    }
+
+    pub fn read(&self) -> LiveStatus {
+        self.read_at(Instant::now())
+    }
+
+    fn read_at(&self, now: Instant) -> LiveStatus {
+        let state = decode_state(self.state.load(Ordering::Acquire));
+        let speed = f32::from_bits(self.speed.load(Ordering::Relaxed));
+        LiveStatus {
+            state,
+            position: self.position_at(now, speed),
+            duration: Some(self.duration.load(Ordering::Relaxed)).filter(|&duration| duration != UNSET),
+            volume: f32::from_bits(self.volume.load(Ordering::Relaxed)),
+            speed,
+        }
+    }
+
+    // Mirrors AudioEngine::get_position
+    fn position_at(&self, now: Instant, speed: f32) -> u64 {
+        let seek_offset = self.seek_offset.load(Ordering::Relaxed);
+        let started_at = self.started_at_ms.load(Ordering::Relaxed);
+        if started_at == UNSET {
+            return seek_offset;
+        }
+        let paused_at = self.paused_at_ms.load(Ordering::Relaxed);
+        let until = if paused_at == UNSET { self.millis(Some(now)) } else { paused_at };
+        let active_ms = until
+            .saturating_sub(started_at)
+            .saturating_sub(self.paused_ms.load(Ordering::Relaxed));
+        seek_offset + (active_ms as f32 / 1000.0 * speed) as u64
+    }
+
+    fn millis(&self, instant: Option<Instant>) -> u64 {
+        instant.map_or(UNSET, |instant| instant.saturating_duration_since(self.epoch).as_millis() as u64)
+    }
+}
+
+impl Default for AtomicPositionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn encode_state(state: &PlaybackState) -> u8 {
+    match state {
+        PlaybackState::Stopped => 0,
+        PlaybackState::Playing => 1,
+        PlaybackState::Paused => 2,
+    }
+}
+
+fn decode_state(value: u8) -> PlaybackState {
+    match value {
+        1 => PlaybackState::Playing,
+        2 => PlaybackState::Paused,
+        _ => PlaybackState::Stopped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_runs_on_while_playing_and_holds_while_paused() {
+        let live = AtomicPositionState::new();
+        let start = live.epoch + Duration::from_secs(10);
+        let clock = PositionClock { seek_offset: 60, started_at: Some(start), paused_at: None, paused_duration: Duration::ZERO };
+        live.publish(&PlaybackState::Playing, clock, Some(600), 0.8, 2.0);
+
+        let status = live.read_at(start + Duration::from_secs(5));
+        assert_eq!(status.state, PlaybackState::Playing);
+        assert_eq!(status.position, 70, "5s at 2x after seeking to 60");
+        assert_eq!(status.duration, Some(600));
+        assert_eq!(status.volume, 0.8);
+
+        let paused = PositionClock { paused_at: Some(start + Duration::from_secs(5)), ..clock };
+        live.publish(&PlaybackState::Paused, paused, Some(600), 0.8, 2.0);
+        assert_eq!(live.read_at(start + Duration::from_secs(60)).position, 70);
+
+        let stopped = PositionClock { seek_offset: 0, started_at: None, paused_at: None, paused_duration: Duration::ZERO };
+        live.publish(&PlaybackState::Stopped, stopped, None, 0.8, 2.0);
+        let status = live.read_at(start + Duration::from_secs(60));
+        assert_eq!(status.position, 0);
+        assert_eq!(status.duration, None);
+    }
+}
@@ -1,15 +1,37 @@
 // Audio Manager for proper queue support and track switching
+use super::ambience::{Ambience, AmbienceStatus};
+use super::ducking::DuckingSettings;
+use super::frame_index::FrameIndex;
+use super::live_status::AtomicPositionState;
+use super::metrics::AudioMetrics;
+use super::voice_boost::VoiceBoostSettings;
+use super::output::{OutputDiagnostics, OutputSettings};
 use super::{AudioEngine, PlaybackStatus};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 use anyhow::Result;
+use ts_rs::TS;
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, TS)]
+#[ts(export)]
 pub struct Track {
     pub id: String,
     pub file_path: String,
     pub title: Option<String>,
+    #[ts(type = "number | null")]
     pub duration: Option<u64>,
+    /// Book this track belongs to, so multi-book queues can follow which book is playing
+    #[serde(default)]
+    pub audiobook_id: Option<String>,
+}
+
+/// A track that could not be loaded, waiting to be reported
+#[derive(Debug, Clone)]
+pub struct TrackError {
+    pub file_path: String,
+    pub error: String,
+    /// Whether playback moved on past it
+    pub skipped: bool,
 }
 
 pub struct AudioManager {
@@ -20,6 +42,22 @@ pub struct AudioManager {
     repeat_mode: Arc<Mutex<RepeatMode>>,
     #[allow(dead_code)]
     shuffle_enabled: Arc<Mutex<bool>>,
+    /// Move on to the next queued track when one cannot be loaded
+    skip_bad_tracks: Mutex<bool>,
+    track_errors: Mutex<Vec<TrackError>>,
+    /// Chapters still to finish before auto-advance stops, counting the current one
+    stop_after: Mutex<Option<u32>>,
+}
+
+/// Where playback stands after the current track played to its end
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Advance {
+    /// The next queued track is loaded and should start
+    Next,
+    /// The next queued track is loaded but the stop-after limit was reached there
+    StopAfter,
+    /// The queue ran out
+    QueueEnded,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -32,7 +70,11 @@ pub enum RepeatMode {
 
 impl AudioManager {
     pub fn new() -> Result<Self> {
-        let engine = AudioEngine::new()?;
+        Self::with_output_settings(OutputSettings::default())
+    }
+
+    pub fn with_output_settings(settings: OutputSettings) -> Result<Self> {
+        let engine = AudioEngine::with_output_settings(settings)?;
         
         Ok(Self {
             engine,
@@ -40,20 +82,20 @@ impl AudioManager {
             queue: Arc::new(Mutex::new(VecDeque::new())),
             repeat_mode: Arc::new(Mutex::new(RepeatMode::None)),
             shuffle_enabled: Arc::new(Mutex::new(false)),
+            skip_bad_tracks: Mutex::new(true),
+            track_errors: Mutex::new(Vec::new()),
+            stop_after: Mutex::new(None),
         })
     }
 
     /// Load and play a single track immediately, clearing any queue
     pub fn play_track_immediately(&self, track: Track) -> Result<()> {
         log::info!("MANAGER: Loading track immediately: {}", track.file_path);
+        self.set_stop_after(None);
         
-        // Load the new track (this will automatically stop previous audio)
-        self.engine.load_file(&track.file_path)?;
-        
-        // Update current track
-        {
-            let mut current = self.current_track.lock().unwrap();
-            *current = Some(track);
+        if let Err(e) = self.load_track(track.clone()) {
+            self.record_error(&track, &e, false);
+            return Err(e);
         }
         
         // Clear the queue since we're playing immediately
@@ -85,6 +127,20 @@ impl AudioManager {
     pub fn stop(&self) {
         log::info!("MANAGER: Stopping playback");
         self.engine.stop();
+        self.set_stop_after(None);
+    }
+
+    /// Load a track as the current one, leaving the queue alone
+    fn load_track(&self, track: Track) -> Result<()> {
+        // Load the new track (this will automatically stop previous audio)
+        self.engine.load_file(&track.file_path)?;
+        
+        // Update current track
+        {
+            let mut current = self.current_track.lock().unwrap();
+            *current = Some(track);
+        }
+        Ok(())
     }
 
     /// Add a track to the end of the queue
@@ -95,7 +151,6 @@ impl AudioManager {
     }
 
     /// Add multiple tracks to the queue
-    #[allow(dead_code)]
     pub fn add_tracks_to_queue(&self, tracks: Vec<Track>) {
         log::info!("MANAGER: Adding {} tracks to queue", tracks.len());
         let mut queue = self.queue.lock().unwrap();
@@ -104,20 +159,122 @@ impl AudioManager {
         }
     }
 
-    /// Play the next track in the queue
+    /// Replace what is queued behind the current track, which plays on
+    pub fn replace_queue(&self, tracks: Vec<Track>) {
+        log::info!("MANAGER: Replacing queue with {} tracks", tracks.len());
+        let mut queue = self.queue.lock().unwrap();
+        queue.clear();
+        queue.extend(tracks);
+    }
+
+    /// Replace the queue: load the first track and queue the rest behind it.
+    /// Tracks that fail to load are skipped when that is enabled.
+    pub fn load_queue(&self, tracks: Vec<Track>) -> Result<()> {
+        if tracks.is_empty() {
+            return Err(anyhow::anyhow!("No tracks to play"));
+        }
+        self.stop();
+        self.clear_queue();
+        self.add_tracks_to_queue(tracks);
+        if !self.play_next()? {
+            return Err(anyhow::anyhow!("None of the tracks could be loaded"));
+        }
+        Ok(())
+    }
+
+    /// Play the next track in the queue, skipping ones that fail to load when
+    /// that is enabled
     pub fn play_next(&self) -> Result<bool> {
-        let next_track = {
-            let mut queue = self.queue.lock().unwrap();
-            queue.pop_front()
-        };
+        loop {
+            let next_track = {
+                let mut queue = self.queue.lock().unwrap();
+                queue.pop_front()
+            };
 
-        if let Some(track) = next_track {
+            let Some(track) = next_track else {
+                log::info!("MANAGER: No more tracks in queue");
+                return Ok(false);
+            };
             log::info!("MANAGER: Playing next track from queue: {}", track.file_path);
-            self.play_track_immediately(track)?;
-            Ok(true)
-        } else {
-            log::info!("MANAGER: No more tracks in queue");
-            Ok(false)
+            // The rest of the queue stays behind it
+            match self.load_track(track.clone()) {
+                Ok(()) => return Ok(true),
+                Err(e) => {
+                    let skip = *self.skip_bad_tracks.lock().unwrap();
+                    self.record_error(&track, &e, skip);
+                    if !skip {
+                        return Err(e);
+                    }
+                    log::warn!("MANAGER: Skipping {}: {:#}", track.file_path, e);
+                }
+            }
+        }
+    }
+
+    /// Skip to the next queued track on request, which drops any stop-after limit
+    pub fn skip_to_next(&self) -> Result<bool> {
+        self.set_stop_after(None);
+        self.play_next()
+    }
+
+    /// The current track played to its end by itself: count it against the
+    /// stop-after limit and load the next queued track. The limit goes with the
+    /// end of the queue or a track that fails to load.
+    pub fn advance(&self) -> Result<Advance> {
+        let stop_here = self.chapter_finished();
+        match self.play_next() {
+            Ok(true) if stop_here => Ok(Advance::StopAfter),
+            Ok(true) => Ok(Advance::Next),
+            Ok(false) => {
+                self.set_stop_after(None);
+                Ok(Advance::QueueEnded)
+            }
+            Err(e) => {
+                self.set_stop_after(None);
+                Err(e)
+            }
+        }
+    }
+
+    fn record_error(&self, track: &Track, error: &anyhow::Error, skipped: bool) {
+        self.track_errors.lock().unwrap().push(TrackError {
+            file_path: track.file_path.clone(),
+            error: format!("{:#}", error),
+            skipped,
+        });
+    }
+
+    /// Tracks that failed to load since the last call
+    pub fn take_track_errors(&self) -> Vec<TrackError> {
+        std::mem::take(&mut *self.track_errors.lock().unwrap())
+    }
+
+    pub fn set_skip_bad_tracks(&self, enabled: bool) {
+        *self.skip_bad_tracks.lock().unwrap() = enabled;
+    }
+
+    /// Stop once `chapters` more queued tracks have played to their end, 1 for
+    /// the end of the current one; None or 0 plays on through the queue. Stopping,
+    /// loading something else and skipping ahead all clear it.
+    pub fn set_stop_after(&self, chapters: Option<u32>) {
+        log::info!("MANAGER: Stopping after {:?} chapters", chapters);
+        *self.stop_after.lock().unwrap() = chapters.filter(|&chapters| chapters > 0);
+    }
+
+    /// The current track played to its end. Counts it against the stop-after
+    /// limit and says whether playback should stop here instead of moving on.
+    fn chapter_finished(&self) -> bool {
+        let mut stop_after = self.stop_after.lock().unwrap();
+        match *stop_after {
+            Some(1) => {
+                *stop_after = None;
+                true
+            }
+            Some(chapters) => {
+                *stop_after = Some(chapters - 1);
+                false
+            }
+            None => false,
         }
     }
 
@@ -130,13 +287,40 @@ impl AudioManager {
         Ok(true)
     }
 
+    /// The current track reached its end by itself; see `AudioEngine::take_finished`
+    pub fn take_finished(&self) -> Option<u64> {
+        self.engine.take_finished()
+    }
+
     /// Get the current playback status
     pub fn get_status(&self) -> PlaybackStatus {
-        self.engine.get_status()
+        let mut status = self.engine.get_status();
+        status.stop_after = *self.stop_after.lock().unwrap();
+        status
+    }
+
+    pub fn output_position(&self) -> Option<std::time::Duration> {
+        self.engine.output_position()
+    }
+
+    /// Load the current track again, leaving the queue alone, and play on from
+    /// `position`; for when its decoder has stopped producing audio
+    pub fn reload_current(&self, position: u64) -> Result<()> {
+        let track = self.get_current_track().ok_or_else(|| anyhow::anyhow!("No track loaded"))?;
+        log::info!("MANAGER: Reloading {} at {}s", track.file_path, position);
+        self.load_track(track)?;
+        if position > 0 {
+            self.engine.seek(position as f32)?;
+        }
+        self.engine.play()
+    }
+
+    /// The engine's published status, for reads that skip the audio thread
+    pub fn live_status(&self) -> Arc<AtomicPositionState> {
+        self.engine.live_status()
     }
 
     /// Get the current track
-    #[allow(dead_code)]
     pub fn get_current_track(&self) -> Option<Track> {
         let current = self.current_track.lock().unwrap();
         current.clone()
@@ -161,18 +345,77 @@ impl AudioManager {
         self.engine.seek(position_seconds)
     }
 
+    /// Seek through the frame index of the current file from now on
+    pub fn set_frame_index(&self, file_path: &str, index: FrameIndex) -> bool {
+        self.engine.set_frame_index(file_path, index)
+    }
+
     /// Set volume (0.0 to 1.0)
     pub fn set_volume(&self, volume: f32) {
         log::info!("MANAGER: Setting volume to: {}", volume);
         self.engine.set_volume(volume);
     }
 
+    /// Lower the book under other audio, 1.0 for full volume
+    pub fn set_focus_gain(&self, gain: f32) {
+        self.engine.set_focus_gain(gain);
+    }
+
     /// Set playback speed
     pub fn set_speed(&self, speed: f32) {
         log::info!("MANAGER: Setting speed to: {}", speed);
         self.engine.set_speed(speed);
     }
 
+    /// Toggle pitch-preserving time stretch for non-1x speeds
+    pub fn set_preserve_pitch(&self, enabled: bool) {
+        log::info!("MANAGER: Setting pitch preservation to: {}", enabled);
+        self.engine.set_preserve_pitch(enabled);
+    }
+
+    /// Reopen the output stream, e.g. with a different buffer size
+    pub fn set_output_settings(&self, settings: OutputSettings) -> Result<()> {
+        log::info!("MANAGER: Applying output settings: {:?}", settings);
+        self.engine.rebuild_output(settings)
+    }
+
+    pub fn start_preview(&self, path: &str, limit: std::time::Duration) -> Result<()> {
+        self.engine.start_preview(path, limit)
+    }
+
+    pub fn stop_preview(&self) {
+        self.engine.stop_preview();
+    }
+
+    pub fn set_ambience(&self, ambience: Option<Ambience>, volume: f32) -> Result<()> {
+        self.engine.set_ambience(ambience, volume)
+    }
+
+    pub fn ambience_status(&self) -> AmbienceStatus {
+        self.engine.ambience_status()
+    }
+
+    pub fn set_ducking(&self, settings: DuckingSettings) {
+        self.engine.set_ducking(settings);
+    }
+
+    pub fn set_voice_boost(&self, settings: VoiceBoostSettings) {
+        self.engine.set_voice_boost(settings);
+    }
+
+    pub fn output_diagnostics(&self) -> OutputDiagnostics {
+        self.engine.output_diagnostics()
+    }
+
+    pub fn metrics(&self) -> AudioMetrics {
+        self.engine.metrics()
+    }
+
+    /// Rebuild the stream with a larger buffer if playback has been underrunning
+    pub fn recover_from_underruns(&self) -> Option<u32> {
+        self.engine.recover_from_underruns()
+    }
+
     /// Set repeat mode
     #[allow(dead_code)]
     pub fn set_repeat_mode(&self, mode: RepeatMode) {
@@ -188,4 +431,118 @@ impl AudioManager {
         let mut shuffle = self.shuffle_enabled.lock().unwrap();
         *shuffle = enabled;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn write_wav(path: &Path) {
+        let rate = 8_000u32;
+        let samples: Vec<i16> = (0..rate).map(|i| (i % 8_000) as i16).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + samples.len() as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        for value in [16u32, 0x0001_0001, rate, rate * 2, 0x0010_0002] {
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(samples.len() as u32 * 2).to_le_bytes());
+        for sample in &samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, wav).unwrap();
+    }
+
+    fn track(path: &Path) -> Track {
+        Track {
+            id: path.display().to_string(),
+            file_path: path.to_string_lossy().into_owned(),
+            title: None,
+            duration: None,
+            audiobook_id: None,
+        }
+    }
+
+    fn chapters(dir: &Path, count: usize) -> Vec<Track> {
+        (0..count)
+            .map(|i| {
+                let path = dir.join(format!("{:02}.wav", i));
+                write_wav(&path);
+                track(&path)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stop_after_chapters() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = AudioManager::new().unwrap();
+        manager.load_queue(chapters(dir.path(), 4)).unwrap();
+
+        // The next chapter loads but stays stopped once the limit is reached
+        manager.set_stop_after(Some(2));
+        assert_eq!(manager.advance().unwrap(), Advance::Next);
+        assert_eq!(manager.get_status().stop_after, Some(1));
+        assert_eq!(manager.advance().unwrap(), Advance::StopAfter);
+        assert_eq!(manager.get_status().stop_after, None);
+        assert!(manager.get_current_track().unwrap().file_path.ends_with("02.wav"));
+
+        // Without a limit playback moves on to the end of the queue
+        assert_eq!(manager.advance().unwrap(), Advance::Next);
+        assert_eq!(manager.advance().unwrap(), Advance::QueueEnded);
+
+        // 0 is no limit
+        manager.set_stop_after(Some(0));
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // The limit goes with the end of the queue
+        manager.set_stop_after(Some(3));
+        assert_eq!(manager.advance().unwrap(), Advance::QueueEnded);
+        assert_eq!(manager.get_status().stop_after, None);
+    }
+
+    #[test]
+    fn test_stop_after_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = AudioManager::new().unwrap();
+        let tracks = chapters(dir.path(), 3);
+        let missing = track(&dir.path().join("missing.wav"));
+
+        // Stopping
+        manager.load_queue(tracks.clone()).unwrap();
+        manager.set_stop_after(Some(3));
+        manager.stop();
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // Loading a queue
+        manager.set_stop_after(Some(3));
+        manager.load_queue(tracks.clone()).unwrap();
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // Loading a single file
+        manager.set_stop_after(Some(3));
+        manager.play_track_immediately(tracks[2].clone()).unwrap();
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // Skipping ahead by hand
+        manager.load_queue(tracks.clone()).unwrap();
+        manager.set_stop_after(Some(3));
+        assert!(manager.skip_to_next().unwrap());
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // A file that fails to load
+        manager.set_stop_after(Some(3));
+        assert!(manager.play_track_immediately(missing.clone()).is_err());
+        assert_eq!(manager.get_status().stop_after, None);
+
+        // The next chapter failing to load on auto-advance
+        manager.set_skip_bad_tracks(false);
+        manager.load_queue(tracks).unwrap();
+        manager.replace_queue(vec![missing]);
+        manager.set_stop_after(Some(3));
+        assert!(manager.advance().is_err());
+        assert_eq!(manager.get_status().stop_after, None);
+    }
+}
@@ -5,21 +5,21 @@ use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
-use std::fs::{File, metadata};
 use std::path::Path;
+use crate::filesystem::backend::{self, Access};
 use anyhow::{Result, Context};
 
 pub fn extract_audio_metadata<P: AsRef<Path>>(path: P) -> Result<AudioInfo> {
     let path = path.as_ref();
     
-    // Get file size
-    let file_metadata = metadata(path)
-        .with_context(|| format!("Failed to read file metadata for: {}", path.display()))?;
-    let file_size = file_metadata.len();
-    
     // Open the media source
-    let file = File::open(path)
+    let file = backend::open(path, Access::Scan)
         .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+    // Get file size
+    let file_size = file.metadata()
+        .with_context(|| format!("Failed to read file metadata for: {}", path.display()))?
+        .len();
     
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
 
@@ -80,16 +80,12 @@ pub fn extract_audio_metadata<P: AsRef<Path>>(path: P) -> Result<AudioInfo> {
         None
     };
 
+    let codec = symphonia::default::get_codecs()
+        .get_codec(codec_params.codec)
+        .map(|codec| codec.short_name.to_string());
+
     // Estimate bitrate (this is approximate)
-    let bitrate = if let Some(dur) = duration {
-        if dur > 0 {
-            Some((file_size * 8 / dur / 1000) as u32) // Convert to kbps
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    let bitrate = duration.and_then(|dur| bitrate_kbps(file_size, dur as f64));
 
     Ok(AudioInfo {
         title,
@@ -100,9 +96,15 @@ pub fn extract_audio_metadata<P: AsRef<Path>>(path: P) -> Result<AudioInfo> {
         sample_rate,
         channels,
         bitrate,
+        codec,
     })
 }
 
+/// Average bitrate in kbps of a file of `file_size` bytes playing for `seconds`
+pub fn bitrate_kbps(file_size: u64, seconds: f64) -> Option<u32> {
+    (seconds >= 1.0).then(|| (file_size as f64 * 8.0 / seconds / 1000.0).round() as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
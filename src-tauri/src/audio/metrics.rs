@@ -0,0 +1,159 @@
+// Timings of the playback pipeline, recorded by the engine as it works, so a
+// slower release shows up as numbers rather than a feeling: how long loading a
+// file takes, how long from opening the decoder until audio is queued in the
+// sink, how long seeks take, and how often the output underran. Nothing is
+// persisted; the figures cover this run of the app.
+
+use super::output::OutputDiagnostics;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use ts_rs::TS;
+
+/// Timings kept per stage for the percentiles; the count and mean cover all of them
+const RECENT_TIMINGS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// The whole of load_file
+    Load,
+    /// Opening the decoder until the sink has audio queued
+    DecodeStart,
+    /// A seek, native or by reloading the file
+    Seek,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TimingSummary {
+    #[ts(type = "number")]
+    pub count: u64,
+    pub mean_ms: f64,
+    /// Over the last RECENT_TIMINGS only
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudioMetrics {
+    pub load: TimingSummary,
+    pub decode_start: TimingSummary,
+    pub seek: TimingSummary,
+    #[ts(type = "number")]
+    pub underrun_count: u64,
+    #[ts(type = "number")]
+    pub output_rebuild_count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+    count: u64,
+    total: Duration,
+    recent: VecDeque<Duration>,
+}
+
+impl Timings {
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        if self.recent.len() == RECENT_TIMINGS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(elapsed);
+    }
+
+    fn summary(&self) -> TimingSummary {
+        let ms = |duration: Duration| duration.as_nanos() as f64 / 1_000_000.0;
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort();
+        // Nearest rank
+        let percentile = |p: f64| match sorted.len() {
+            0 => 0.0,
+            len => ms(sorted[((p * len as f64).ceil() as usize).clamp(1, len) - 1]),
+        };
+        TimingSummary {
+            count: self.count,
+            mean_ms: if self.count == 0 { 0.0 } else { ms(self.total) / self.count as f64 },
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: sorted.last().copied().map_or(0.0, ms),
+            last_ms: self.recent.back().copied().map(ms),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    load: Mutex<Timings>,
+    decode_start: Mutex<Timings>,
+    seek: Mutex<Timings>,
+}
+
+impl PipelineMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, stage: Stage, elapsed: Duration) {
+        let timings = match stage {
+            Stage::Load => &self.load,
+            Stage::DecodeStart => &self.decode_start,
+            Stage::Seek => &self.seek,
+        };
+        timings.lock().unwrap().record(elapsed);
+    }
+
+    /// The timings so far, with the output's underrun counts alongside
+    pub fn snapshot(&self, output: &OutputDiagnostics) -> AudioMetrics {
+        AudioMetrics {
+            load: self.load.lock().unwrap().summary(),
+            decode_start: self.decode_start.lock().unwrap().summary(),
+            seek: self.seek.lock().unwrap().summary(),
+            underrun_count: output.underrun_count,
+            output_rebuild_count: output.rebuild_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_summarises_each_stage() {
+        let metrics = PipelineMetrics::new();
+        for ms in 1..=100 {
+            metrics.record(Stage::Seek, Duration::from_millis(ms));
+        }
+        metrics.record(Stage::Load, Duration::from_millis(40));
+        let output = OutputDiagnostics { buffer_frames: None, underrun_count: 3, rebuild_count: 1, last_error: None };
+
+        let snapshot = metrics.snapshot(&output);
+        assert_eq!(snapshot.seek.count, 100);
+        assert_eq!((snapshot.seek.p50_ms, snapshot.seek.p95_ms, snapshot.seek.max_ms), (50.0, 95.0, 100.0));
+        assert_eq!(snapshot.seek.mean_ms, 50.5);
+        assert_eq!(snapshot.seek.last_ms, Some(100.0));
+        assert_eq!((snapshot.load.count, snapshot.load.p95_ms), (1, 40.0));
+        assert_eq!(snapshot.decode_start, TimingSummary::default());
+        assert_eq!((snapshot.underrun_count, snapshot.output_rebuild_count), (3, 1));
+    }
+
+    #[test]
+    fn test_percentiles_cover_recent_timings_only() {
+        let mut timings = Timings::default();
+        for _ in 0..RECENT_TIMINGS {
+            timings.record(Duration::from_secs(1));
+        }
+        for _ in 0..RECENT_TIMINGS {
+            timings.record(Duration::from_millis(10));
+        }
+        let summary = timings.summary();
+        assert_eq!(summary.count, 2 * RECENT_TIMINGS as u64);
+        assert_eq!(summary.max_ms, 10.0);
+        assert_eq!(summary.mean_ms, 505.0);
+    }
+}
@@ -1,56 +1,105 @@
 // Audio engine module for AudioVibe
 // This module will handle audio playback, metadata extraction, and audio processing
 
-use rodio::{Decoder, OutputStream, Sink, Source, OutputStreamBuilder};
+use rodio::{Decoder, OutputStream, Sink, Source};
 use std::fs::File;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use crate::filesystem::backend::{backend_for, BackendKind};
+use crate::filesystem::long_path::long_path;
+use crate::services::book_position_service::ChapterPosition;
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 
+pub mod ambience;
+pub mod books;
+pub mod decode_cache;
+pub mod decoder;
+pub mod ducking;
+pub mod focus;
+pub mod frame_index;
+pub mod live_status;
 pub mod player;
 pub mod manager;
 pub mod metadata;
+pub mod metrics;
+pub mod output;
+pub mod probe;
+pub mod seek_history;
+pub mod settings;
+pub mod stretch;
+pub mod tags;
+pub mod thread;
+pub mod voice_boost;
+pub mod watchdog;
 
 pub use manager::*;
 pub use metadata::*;
+use ambience::{Ambience, AmbienceStatus, NoiseSource};
+use decode_cache::{CachedAudio, DecodeCache};
+use decoder::{FrameIndexSlot, IndexedSource};
+use frame_index::FrameIndex;
+use ducking::{Ducker, DuckingControl, DuckingSettings, LevelMeter};
+use live_status::{AtomicPositionState, PositionClock};
+use metrics::{AudioMetrics, PipelineMetrics, Stage};
+use output::{OutputDiagnostics, OutputSettings, UnderrunMonitor};
+use stretch::{StretchControl, TimeStretch};
+use voice_boost::{VoiceBoost, VoiceBoostControl, VoiceBoostSettings};
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub enum PlaybackState {
     Stopped,
     Playing,
     Paused,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct AudioInfo {
     pub title: Option<String>,
     pub artist: Option<String>,
     pub album: Option<String>,
+    #[ts(type = "number | null")]
     pub duration: Option<u64>, // Duration in seconds
+    #[ts(type = "number")]
     pub file_size: u64,
     pub sample_rate: Option<u32>,
     pub channels: Option<u16>,
     pub bitrate: Option<u32>,
+    pub codec: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct PlaybackStatus {
     pub state: PlaybackState,
+    #[ts(type = "number")]
     pub position: u64, // Position in seconds
+    #[ts(type = "number | null")]
     pub duration: Option<u64>, // Duration in seconds
     pub volume: f32,
     pub speed: f32,
     pub current_file: Option<String>,
+    /// Chapter and whole-book position, filled in for books in the library
+    #[serde(default)]
+    pub chapter: Option<ChapterPosition>,
+    /// Chapters left before playback stops by itself, counting the current one
+    #[serde(default)]
+    pub stop_after: Option<u32>,
 }
 
 pub struct AudioEngine {
-    _stream: OutputStream,
+    stream: Mutex<OutputStream>,
+    output_settings: Mutex<OutputSettings>,
+    underruns: Arc<UnderrunMonitor>,
     sink: Arc<Mutex<Sink>>,
     current_file: Arc<Mutex<Option<String>>>,
     current_audio_info: Arc<Mutex<Option<AudioInfo>>>,
     state: Arc<Mutex<PlaybackState>>,
     volume: Arc<Mutex<f32>>,
+    focus_gain: Mutex<f32>, // Lowers the book while other audio has focus, on top of the volume
     speed: Arc<Mutex<f32>>,
     start_time: Arc<Mutex<Option<std::time::Instant>>>,
     pause_time: Arc<Mutex<Option<std::time::Instant>>>,
@@ -58,26 +107,38 @@ pub struct AudioEngine {
     seek_offset: Arc<Mutex<u64>>, // Offset from seeking
     last_speed_change: Arc<Mutex<Option<std::time::Instant>>>,
     speed_adjusted_duration: Arc<Mutex<std::time::Duration>>, // Duration adjusted for previous speeds
+    stretch: Arc<StretchControl>, // Pitch-preserving speed, shared with the source in the sink
+    preview_sink: Mutex<Option<Sink>>, // Second sink on the same mixer for previews; never touches playback state
+    ambience_sink: Mutex<Option<Sink>>, // Background channel, independent of the book's volume and play state
+    ambience: Mutex<Option<(Ambience, f32)>>,
+    ducking: Arc<DuckingControl>, // Narration level meter feeding the ambience ducker
+    voice_boost: Arc<VoiceBoostControl>, // Compressor/limiter on the narration
+    live_status: Arc<AtomicPositionState>, // Published after every change for lock-free status reads
+    decode_cache: Mutex<DecodeCache>, // Small files recently loaded, already decoded
+    frame_index: Mutex<Option<FrameIndexSlot>>, // Filled in for the loaded MP3 once its frame index is ready
+    metrics: PipelineMetrics, // Load, decode-start and seek timings for this run
 }
 
 impl AudioEngine {
     pub fn new() -> Result<Self> {
-        // Use the new Rodio 0.21 API
-        let mut stream = OutputStreamBuilder::open_default_stream()
-            .context("Failed to create audio output stream")?;
-
-        // Disable logging on drop to avoid cluttering output
-        stream.log_on_drop(false);
+        Self::with_output_settings(OutputSettings::default())
+    }
 
+    pub fn with_output_settings(settings: OutputSettings) -> Result<Self> {
+        let underruns = Arc::new(UnderrunMonitor::default());
+        let stream = output::open_output_stream(&settings, underruns.clone())?;
         let sink = Sink::connect_new(stream.mixer());
 
         Ok(Self {
-            _stream: stream,
+            stream: Mutex::new(stream),
+            output_settings: Mutex::new(settings),
+            underruns,
             sink: Arc::new(Mutex::new(sink)),
             current_file: Arc::new(Mutex::new(None)),
             current_audio_info: Arc::new(Mutex::new(None)),
             state: Arc::new(Mutex::new(PlaybackState::Stopped)),
             volume: Arc::new(Mutex::new(1.0)),
+            focus_gain: Mutex::new(1.0),
             speed: Arc::new(Mutex::new(1.0)),
             start_time: Arc::new(Mutex::new(None)),
             pause_time: Arc::new(Mutex::new(None)),
@@ -85,12 +146,51 @@ impl AudioEngine {
             seek_offset: Arc::new(Mutex::new(0)),
             last_speed_change: Arc::new(Mutex::new(None)),
             speed_adjusted_duration: Arc::new(Mutex::new(std::time::Duration::ZERO)),
+            stretch: Arc::new(StretchControl::new(true)),
+            preview_sink: Mutex::new(None),
+            ambience_sink: Mutex::new(None),
+            ambience: Mutex::new(None),
+            ducking: Arc::new(DuckingControl::new(DuckingSettings::default())),
+            voice_boost: Arc::new(VoiceBoostControl::new(VoiceBoostSettings::default())),
+            live_status: Arc::new(AtomicPositionState::new()),
+            decode_cache: Mutex::new(DecodeCache::new()),
+            frame_index: Mutex::new(None),
+            metrics: PipelineMetrics::new(),
         })
     }
 
+    /// Status readable from any thread without waiting on this one
+    pub fn live_status(&self) -> Arc<AtomicPositionState> {
+        self.live_status.clone()
+    }
+
+    // Takes the state and timing locks one at a time; callers must not hold them
+    fn publish_status(&self) {
+        let state = self.state.lock().unwrap().clone();
+        let clock = PositionClock {
+            seek_offset: *self.seek_offset.lock().unwrap(),
+            started_at: *self.start_time.lock().unwrap(),
+            paused_at: *self.pause_time.lock().unwrap(),
+            paused_duration: *self.paused_duration.lock().unwrap(),
+        };
+        let duration = self.current_audio_info.lock().unwrap().as_ref().and_then(|info| info.duration);
+        self.live_status.publish(&state, clock, duration, self.get_volume(), self.get_speed());
+    }
+
+    /// The book's audio as it goes into the sink: stretched for speed, compressed
+    /// when voice boost is on, and metered for ducking
+    fn narration<S: Source>(&self, source: S) -> LevelMeter<VoiceBoost<TimeStretch<S>>> {
+        let stretched = TimeStretch::new(source, self.stretch.clone());
+        LevelMeter::new(VoiceBoost::new(stretched, self.voice_boost.clone()), self.ducking.clone())
+    }
+
     pub fn load_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let path = path.as_ref();
+        let load_started = std::time::Instant::now();
         println!("ENGINE: Starting load_file for: {}", path.display());
+        if backend_for(path).kind() == BackendKind::NetworkShare {
+            log::info!("ENGINE: {} is on a network share; opens are retried and the file cached", path.display());
+        }
 
         // Forcefully stop and drain all audio from the sink
         {
@@ -111,47 +211,77 @@ impl AudioEngine {
             println!("ENGINE: Sink fully drained");
         }
 
-        // Extract metadata in parallel if possible (but don't block loading)
-        let audio_info = extract_audio_metadata(path).unwrap_or_else(|e| {
-            log::warn!("Failed to extract metadata, using defaults: {}", e);
-            AudioInfo {
-                title: None,
-                artist: None,
-                album: None,
-                duration: None,
-                file_size: 0,
-                sample_rate: None,
-                channels: None,
-                bitrate: None,
+        let mut frame_index = None;
+        let mut decode_started = std::time::Instant::now();
+        // Small files played recently are still decoded in memory
+        let cached = self.decode_cache.lock().unwrap().get(path);
+        let (audio_info, source): (AudioInfo, Box<dyn Source + Send>) = match cached {
+            Some(audio) => {
+                println!("ENGINE: Using decoded audio from the cache");
+                (audio.info, Box::new(audio.source))
             }
-        });
-
-        // Load the file and decoder OUTSIDE the sink lock to avoid deadlocks
-        let file = File::open(path)
-            .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
-
-        println!("ENGINE: Attempting to decode file with Rodio Decoder (seekable mode)");
+            None => {
+                // Extract metadata in parallel if possible (but don't block loading)
+                let audio_info = extract_audio_metadata(path).unwrap_or_else(|e| {
+                    log::warn!("Failed to extract metadata, using defaults: {}", e);
+                    AudioInfo {
+                        title: None,
+                        artist: None,
+                        album: None,
+                        duration: None,
+                        file_size: 0,
+                        sample_rate: None,
+                        channels: None,
+                        bitrate: None,
+                        codec: None,
+                    }
+                });
+                decode_started = std::time::Instant::now();
 
-        // Use Decoder::try_from for seekable sources in Rodio 0.21
-        // This properly supports M4B files with seeking capability
-        let source = match Decoder::try_from(file) {
-            Ok(decoder) => {
-                println!("ENGINE: Successfully created decoder with seeking support");
-                decoder
-            }
-            Err(e) => {
-                eprintln!("ENGINE: Failed to create decoder: {:?}", e);
-                eprintln!("ENGINE: File path: {}", path.display());
-                eprintln!("ENGINE: File extension: {:?}", path.extension());
+                // Load the file and decoder OUTSIDE the sink lock to avoid deadlocks
+                println!("ENGINE: Attempting to decode file (seekable mode)");
 
-                return Err(anyhow::anyhow!("Failed to decode audio file '{}': {:?}", path.display(), e));
+                // Small files are decoded whole and kept; anything else, or a
+                // file that fails that way, streams through rodio's seekable
+                // decoder, or symphonia directly for files it rejects
+                match CachedAudio::decode(path, &audio_info) {
+                    Ok(Some(audio)) => {
+                        println!("ENGINE: Decoded small file into memory");
+                        let source = audio.source.clone();
+                        let audio_info = audio.info.clone();
+                        self.decode_cache.lock().unwrap().insert(path, audio);
+                        (audio_info, Box::new(source))
+                    }
+                    decoded => {
+                        if let Err(e) = decoded {
+                            log::warn!("Failed to decode {} into memory: {:#}", path.display(), e);
+                        }
+                        match decoder::open_source(path) {
+                            Ok(source) if frame_index::is_indexable(path) => {
+                                println!("ENGINE: Successfully created decoder, seeking through the frame index once built");
+                                let slot = FrameIndexSlot::default();
+                                frame_index = Some(slot.clone());
+                                (audio_info, Box::new(IndexedSource::new(path, source, slot)))
+                            }
+                            Ok(source) => {
+                                println!("ENGINE: Successfully created decoder with seeking support");
+                                (audio_info, source)
+                            }
+                            Err(e) => {
+                                eprintln!("ENGINE: Failed to create decoder: {:#}", e);
+                                eprintln!("ENGINE: File extension: {:?}", path.extension());
+                                return Err(e);
+                            }
+                        }
+                    }
+                }
             }
         };
 
         {
             let sink = self.sink.lock().unwrap();
             println!("ENGINE: Appending source to sink");
-            sink.append(source);
+            sink.append(self.narration(source));
             // Pause immediately after append to prevent auto-play
             // This ensures timing (start_time) is only set when play() is explicitly called
             sink.pause();
@@ -168,6 +298,7 @@ impl AudioEngine {
                 if !sink.empty() {
                     println!("ENGINE: Sink loaded with content after {} attempts ({} ms)",
                              attempts, attempts * 5);
+                    self.metrics.record(Stage::DecodeStart, decode_started.elapsed());
                     break;
                 }
             }
@@ -195,6 +326,8 @@ impl AudioEngine {
             
             let mut current_audio_info = self.current_audio_info.lock().unwrap();
             *current_audio_info = Some(audio_info);
+
+            *self.frame_index.lock().unwrap() = frame_index;
             
             let mut state = self.state.lock().unwrap();
             *state = PlaybackState::Stopped;
@@ -214,6 +347,9 @@ impl AudioEngine {
             *speed_adjusted_duration = std::time::Duration::ZERO;
         }
         
+        self.publish_status();
+
+        self.metrics.record(Stage::Load, load_started.elapsed());
         println!("ENGINE: Load complete, sink has content confirmed");
         log::info!("Loaded audio file: {}", path.display());
         Ok(())
@@ -264,28 +400,32 @@ impl AudioEngine {
             }
         }
         
-        let mut state = self.state.lock().unwrap();
-        *state = PlaybackState::Playing;
+        *self.state.lock().unwrap() = PlaybackState::Playing;
+        self.publish_status();
         
         log::info!("PLAY: Audio playback started successfully");
         Ok(())
     }
 
     pub fn pause(&self) {
-        let sink = self.sink.lock().unwrap();
-        sink.pause();
+        self.sink.lock().unwrap().pause();
+        self.ducking.clear_level();
         
         // Record pause time
-        let mut pause_time = self.pause_time.lock().unwrap();
-        *pause_time = Some(std::time::Instant::now());
-        
-        let mut state = self.state.lock().unwrap();
-        *state = PlaybackState::Paused;
+        *self.pause_time.lock().unwrap() = Some(std::time::Instant::now());
+        *self.state.lock().unwrap() = PlaybackState::Paused;
+        self.publish_status();
         
         log::info!("Paused audio playback");
     }
 
     pub fn stop(&self) {
+        self.stop_sink();
+        self.publish_status();
+        log::info!("STOP: Audio engine stopped and cleared completely");
+    }
+
+    fn stop_sink(&self) {
         log::info!("STOP: Stopping audio engine");
         let sink = self.sink.lock().unwrap();
         log::info!("STOP: Got sink lock, calling sink.stop()");
@@ -298,6 +438,7 @@ impl AudioEngine {
             cleared_count += 1;
         }
         log::info!("STOP: Cleared {} items from sink queue", cleared_count);
+        self.ducking.clear_level();
         
         // Reset timing
         {
@@ -317,11 +458,50 @@ impl AudioEngine {
         
         let mut state = self.state.lock().unwrap();
         *state = PlaybackState::Stopped;
-        
-        log::info!("STOP: Audio engine stopped and cleared completely");
     }
 
     pub fn seek(&self, position_seconds: f32) -> Result<()> {
+        let started = std::time::Instant::now();
+        let result = self.seek_sink(position_seconds);
+        if result.is_ok() {
+            self.metrics.record(Stage::Seek, started.elapsed());
+        }
+        self.publish_status();
+        result
+    }
+
+    /// Hand over the frame index of the loaded MP3 once it has been read or
+    /// built. Later seeks land on the exact sample, and the duration becomes
+    /// the counted one instead of the decoder's estimate. False if another
+    /// file has been loaded since.
+    pub fn set_frame_index(&self, file_path: &str, index: FrameIndex) -> bool {
+        if self.current_file.lock().unwrap().as_deref() != Some(file_path) {
+            return false;
+        }
+        let Some(slot) = self.frame_index.lock().unwrap().clone() else {
+            return false;
+        };
+        let duration = index.duration().as_secs();
+        *slot.lock().unwrap() = Some(Arc::new(index));
+        if let Some(info) = self.current_audio_info.lock().unwrap().as_mut() {
+            info.duration = Some(duration);
+        }
+
+        // A resume seek made before the index arrived went by the estimate;
+        // nothing has played since, so seek there again
+        let resumed_at = *self.seek_offset.lock().unwrap();
+        let playing = matches!(*self.state.lock().unwrap(), PlaybackState::Playing);
+        let played_since = self.pause_time.lock().unwrap().is_some();
+        if resumed_at > 0 && !playing && !played_since {
+            if let Err(e) = self.seek_sink(resumed_at as f32) {
+                log::warn!("Failed to seek again through the frame index: {:#}", e);
+            }
+        }
+        self.publish_status();
+        true
+    }
+
+    fn seek_sink(&self, position_seconds: f32) -> Result<()> {
         let position_seconds = position_seconds.max(0.0);
         let current_file = {
             let file_lock = self.current_file.lock().unwrap();
@@ -454,21 +634,16 @@ impl AudioEngine {
         let path = path.as_ref();
         log::info!("ENGINE: Loading file with {}s offset: {}", offset_seconds, path.display());
 
-        let file = File::open(path)
-            .with_context(|| format!("Failed to open audio file: {}", path.display()))?;
-
-        // Use Decoder::try_from for seekable sources (Rodio 0.21)
-        let decoder = Decoder::try_from(file)
-            .with_context(|| format!("Failed to decode audio file: {}", path.display()))?;
+        let decoder = decoder::open_source(path)?;
 
         let sink = self.sink.lock().unwrap();
 
         // Skip samples to reach the desired position using rodio's skip_duration
         if offset_seconds > 0 {
             let source_with_skip = decoder.skip_duration(std::time::Duration::from_secs(offset_seconds));
-            sink.append(source_with_skip);
+            sink.append(self.narration(source_with_skip));
         } else {
-            sink.append(decoder);
+            sink.append(self.narration(decoder));
         }
 
         // Update seek offset and reset timing
@@ -493,10 +668,10 @@ impl AudioEngine {
     pub fn set_volume(&self, volume: f32) {
         let sink = self.sink.lock().unwrap();
         let clamped_volume = volume.clamp(0.0, 1.0);
-        sink.set_volume(clamped_volume);
+        sink.set_volume(clamped_volume * *self.focus_gain.lock().unwrap());
         
-        let mut vol = self.volume.lock().unwrap();
-        *vol = clamped_volume;
+        *self.volume.lock().unwrap() = clamped_volume;
+        self.publish_status();
         
         log::debug!("Set volume to: {}", clamped_volume);
     }
@@ -506,13 +681,25 @@ impl AudioEngine {
         *volume
     }
 
+    /// Duck the book under other audio without touching the volume setting
+    pub fn set_focus_gain(&self, gain: f32) {
+        *self.focus_gain.lock().unwrap() = gain.clamp(0.0, 1.0);
+        self.sink.lock().unwrap().set_volume(self.output_volume());
+    }
+
+    /// What the sink plays at: the volume with any focus ducking applied
+    fn output_volume(&self) -> f32 {
+        self.get_volume() * *self.focus_gain.lock().unwrap()
+    }
+
     pub fn set_speed(&self, speed: f32) {
         let sink = self.sink.lock().unwrap();
         let clamped_speed = speed.clamp(0.25, 4.0);
-        sink.set_speed(clamped_speed);
+        self.stretch.set_speed(clamped_speed);
+        sink.set_speed(self.stretch.sink_speed());
         
-        let mut spd = self.speed.lock().unwrap();
-        *spd = clamped_speed;
+        *self.speed.lock().unwrap() = clamped_speed;
+        self.publish_status();
         
         log::debug!("Set playback speed to: {}x", clamped_speed);
     }
@@ -522,6 +709,16 @@ impl AudioEngine {
         *speed
     }
 
+    /// Keep the narrator's pitch when speeding up (on by default). Off falls back
+    /// to plain resampling, which also raises the pitch.
+    pub fn set_preserve_pitch(&self, enabled: bool) {
+        let sink = self.sink.lock().unwrap();
+        self.stretch.set_preserve_pitch(enabled);
+        sink.set_speed(self.stretch.sink_speed());
+
+        log::debug!("Pitch preservation {}", if enabled { "enabled" } else { "disabled" });
+    }
+
     pub fn get_position(&self) -> u64 {
         let start_time = self.start_time.lock().unwrap();
         let pause_time = self.pause_time.lock().unwrap();
@@ -576,6 +773,192 @@ impl AudioEngine {
             volume: self.get_volume(),
             speed: self.get_speed(),
             current_file,
+            chapter: None,
+            stop_after: None,
+        }
+    }
+
+    /// Play up to `limit` of a file on its own sink, mixed over whatever is
+    /// playing. Replaces a running preview; position, state and the loaded file
+    /// are left alone.
+    pub fn start_preview<P: AsRef<Path>>(&self, path: P, limit: std::time::Duration) -> Result<()> {
+        let path = path.as_ref();
+        let source = decoder::open_source(path)
+            .with_context(|| format!("Failed to decode preview '{}'", path.display()))?;
+
+        let sink = Sink::connect_new(self.stream.lock().unwrap().mixer());
+        sink.set_volume(self.get_volume());
+        sink.append(source.take_duration(limit));
+        if let Some(previous) = self.preview_sink.lock().unwrap().replace(sink) {
+            previous.stop();
+        }
+        Ok(())
+    }
+
+    pub fn stop_preview(&self) {
+        if let Some(sink) = self.preview_sink.lock().unwrap().take() {
+            sink.stop();
+        }
+    }
+
+    /// Play ambience under the book, or stop it with None. Choosing the track
+    /// already playing only changes its volume, so a volume slider does not
+    /// restart the loop.
+    pub fn set_ambience(&self, ambience: Option<Ambience>, volume: f32) -> Result<()> {
+        let volume = volume.clamp(0.0, 1.0);
+        let Some(ambience) = ambience else {
+            if let Some(sink) = self.ambience_sink.lock().unwrap().take() {
+                sink.stop();
+            }
+            *self.ambience.lock().unwrap() = None;
+            return Ok(());
+        };
+
+        let same_track = self.ambience.lock().unwrap().as_ref().is_some_and(|(current, _)| *current == ambience);
+        if same_track {
+            if let Some(sink) = self.ambience_sink.lock().unwrap().as_ref() {
+                sink.set_volume(volume);
+                *self.ambience.lock().unwrap() = Some((ambience, volume));
+                return Ok(());
+            }
+        }
+
+        let sink = Sink::connect_new(self.stream.lock().unwrap().mixer());
+        sink.set_volume(volume);
+        match &ambience {
+            Ambience::Generated(soundscape) => sink.append(Ducker::new(NoiseSource::new(*soundscape), self.ducking.clone())),
+            Ambience::Loop(path) => {
+                let file = File::open(long_path(path))
+                    .with_context(|| format!("Failed to open ambience loop: {}", path.display()))?;
+                let source = Decoder::try_from(file)
+                    .map_err(|e| anyhow::anyhow!("Failed to decode ambience loop '{}': {:?}", path.display(), e))?;
+                sink.append(Ducker::new(source.repeat_infinite(), self.ducking.clone()));
+            }
+        }
+        if let Some(previous) = self.ambience_sink.lock().unwrap().replace(sink) {
+            previous.stop();
+        }
+        *self.ambience.lock().unwrap() = Some((ambience, volume));
+        Ok(())
+    }
+
+    /// How far the sink has really played the loaded file, or None once it has
+    /// run out. Unlike get_position, which follows the clock, this stops
+    /// moving when the decoder does.
+    pub fn output_position(&self) -> Option<std::time::Duration> {
+        let sink = self.sink.lock().unwrap();
+        (!sink.empty()).then(|| sink.get_pos())
+    }
+
+    /// Notices the loaded file playing out on its own: the state goes to
+    /// Stopped and the final position is returned, once per file
+    pub fn take_finished(&self) -> Option<u64> {
+        let playing = matches!(*self.state.lock().unwrap(), PlaybackState::Playing);
+        if !playing || !self.sink.lock().unwrap().empty() {
+            return None;
+        }
+        *self.state.lock().unwrap() = PlaybackState::Stopped;
+        self.ducking.clear_level();
+        self.publish_status();
+
+        let duration = self.current_audio_info.lock().unwrap().as_ref().and_then(|info| info.duration);
+        Some(duration.unwrap_or_else(|| self.get_position()))
+    }
+
+    /// Takes effect on the playing file straight away
+    pub fn set_voice_boost(&self, settings: VoiceBoostSettings) {
+        self.voice_boost.apply(settings);
+    }
+
+    pub fn set_ducking(&self, settings: DuckingSettings) {
+        self.ducking.apply(settings);
+    }
+
+    pub fn ambience_status(&self) -> AmbienceStatus {
+        match self.ambience.lock().unwrap().as_ref() {
+            Some((ambience, volume)) => AmbienceStatus { track: Some(ambience.track()), volume: *volume },
+            None => AmbienceStatus { track: None, volume: 0.0 },
+        }
+    }
+
+    pub fn output_diagnostics(&self) -> OutputDiagnostics {
+        self.underruns.diagnostics(&self.output_settings.lock().unwrap())
+    }
+
+    /// Pipeline timings since the engine started, with the output's underruns
+    pub fn metrics(&self) -> AudioMetrics {
+        self.metrics.snapshot(&self.output_diagnostics())
+    }
+
+    /// Reopen the output stream with new settings, carrying over the loaded file,
+    /// position, volume, speed and play state
+    pub fn rebuild_output(&self, settings: OutputSettings) -> Result<()> {
+        let position = self.get_position();
+        let was_playing = matches!(*self.state.lock().unwrap(), PlaybackState::Playing);
+        let current_file = self.current_file.lock().unwrap().clone();
+
+        let stream = output::open_output_stream(&settings, self.underruns.clone())?;
+        // A preview is short; it is not carried over to the new stream
+        self.stop_preview();
+        {
+            let mut sink = self.sink.lock().unwrap();
+            sink.stop();
+            let new_sink = Sink::connect_new(stream.mixer());
+            new_sink.set_volume(self.output_volume());
+            new_sink.set_speed(self.stretch.sink_speed());
+            new_sink.pause();
+            *sink = new_sink;
+        }
+        *self.stream.lock().unwrap() = stream;
+        *self.output_settings.lock().unwrap() = settings.clone();
+        log::info!("AUDIO OUTPUT: Rebuilt stream with buffer {:?}", settings.buffer_frames);
+
+        // Ambience was playing on the old stream; start it again on the new one
+        self.ambience_sink.lock().unwrap().take();
+        let ambience = self.ambience.lock().unwrap().take();
+        if let Some((ambience, volume)) = ambience {
+            if let Err(e) = self.set_ambience(Some(ambience), volume) {
+                log::warn!("Failed to restart ambience after rebuilding output: {}", e);
+            }
+        }
+
+        let Some(file_path) = current_file else {
+            return Ok(());
+        };
+        self.load_file_with_offset(&file_path, position)?;
+        if was_playing {
+            self.sink.lock().unwrap().play();
+        } else {
+            // Not playing: position stays at the offset until play() starts the clock
+            *self.start_time.lock().unwrap() = None;
+            self.sink.lock().unwrap().pause();
+        }
+        self.publish_status();
+        Ok(())
+    }
+
+    /// Grow the output buffer after a burst of underruns. Returns the new size
+    /// in frames when the stream was rebuilt.
+    pub fn recover_from_underruns(&self) -> Option<u32> {
+        if !self.underruns.needs_rebuild() {
+            return None;
+        }
+
+        let current = self.output_settings.lock().unwrap().clone();
+        let Some(frames) = output::next_buffer_frames(current.buffer_frames) else {
+            // Already at the largest buffer; stop re-checking the same burst
+            self.underruns.mark_rebuilt();
+            return None;
+        };
+
+        log::warn!("AUDIO OUTPUT: Repeated underruns, rebuilding stream with {} frame buffer", frames);
+        self.underruns.mark_rebuilt();
+        match self.rebuild_output(OutputSettings { buffer_frames: Some(frames), ..current }) {
+            Ok(()) => Some(frames),
+            Err(e) => {
+                log::error!("AUDIO OUTPUT: Failed to rebuild stream: {}", e);
+                None
+            }
         }
     }
 
@@ -0,0 +1,279 @@
+// Output stream setup: configurable buffer size, and underrun tracking so the
+// engine can reopen the stream with a larger buffer when playback starts crackling
+
+use anyhow::{Context, Result};
+use rodio::cpal::{self, traits::{DeviceTrait, HostTrait}};
+use rodio::{OutputStream, OutputStreamBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// First fixed size tried when the device default keeps underrunning
+pub const AUTO_BUFFER_START_FRAMES: u32 = 2048;
+pub const MAX_BUFFER_FRAMES: u32 = 16384;
+/// This many stream errors within the window trigger a rebuild
+const UNDERRUN_THRESHOLD: usize = 3;
+const UNDERRUN_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutputSettings {
+    /// Fixed buffer size in frames; None leaves it to the device
+    pub buffer_frames: Option<u32>,
+    /// Output device id from `probe_output_devices`; None follows the system default
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutputDiagnostics {
+    pub buffer_frames: Option<u32>,
+    #[ts(type = "number")]
+    pub underrun_count: u64,
+    /// Times the stream was reopened with a larger buffer after underruns
+    #[ts(type = "number")]
+    pub rebuild_count: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutputFormat {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+    pub min_buffer_frames: Option<u32>,
+    pub max_buffer_frames: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutputDeviceInfo {
+    /// cpal has no stable device ids, so the device name doubles as one
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub default_format: Option<OutputFormat>,
+    pub formats: Vec<OutputFormat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudioCapabilities {
+    pub host: String,
+    pub devices: Vec<OutputDeviceInfo>,
+}
+
+/// Result of explicitly starting audio output, enough for the UI to show a
+/// "no audio device" screen with a retry button
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudioInitReport {
+    pub initialized: bool,
+    /// Device playback went to, when it started
+    pub device: Option<String>,
+    pub error: Option<String>,
+    pub capabilities: Option<AudioCapabilities>,
+}
+
+/// Collects stream errors reported from the audio callback thread
+#[derive(Debug, Default)]
+pub struct UnderrunMonitor {
+    total: AtomicU64,
+    rebuilds: AtomicU64,
+    recent: Mutex<VecDeque<Instant>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl UnderrunMonitor {
+    pub fn record(&self, error: &cpal::StreamError) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        self.record_at(Instant::now());
+        *self.last_error.lock().unwrap() = Some(error.to_string());
+        log::warn!("AUDIO OUTPUT: Stream error (possible underrun): {}", error);
+    }
+
+    fn record_at(&self, at: Instant) {
+        let mut recent = self.recent.lock().unwrap();
+        recent.push_back(at);
+        while recent.len() > UNDERRUN_THRESHOLD {
+            recent.pop_front();
+        }
+    }
+
+    /// Whether errors came in a burst, meaning the buffer should grow
+    pub fn needs_rebuild(&self) -> bool {
+        let recent = self.recent.lock().unwrap();
+        match (recent.front(), recent.back()) {
+            (Some(first), Some(last)) if recent.len() >= UNDERRUN_THRESHOLD => {
+                last.duration_since(*first) <= UNDERRUN_WINDOW
+            }
+            _ => false,
+        }
+    }
+
+    pub fn mark_rebuilt(&self) {
+        self.rebuilds.fetch_add(1, Ordering::Relaxed);
+        self.recent.lock().unwrap().clear();
+    }
+
+    pub fn diagnostics(&self, settings: &OutputSettings) -> OutputDiagnostics {
+        OutputDiagnostics {
+            buffer_frames: settings.buffer_frames,
+            underrun_count: self.total.load(Ordering::Relaxed),
+            rebuild_count: self.rebuilds.load(Ordering::Relaxed),
+            last_error: self.last_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Buffer size to try after underruns, or None when already at the maximum
+pub fn next_buffer_frames(current: Option<u32>) -> Option<u32> {
+    match current {
+        None => Some(AUTO_BUFFER_START_FRAMES),
+        Some(frames) if frames >= MAX_BUFFER_FRAMES => None,
+        Some(frames) => Some((frames.max(1) * 2).clamp(AUTO_BUFFER_START_FRAMES, MAX_BUFFER_FRAMES)),
+    }
+}
+
+fn format_of_range(range: &cpal::SupportedStreamConfigRange) -> OutputFormat {
+    let (min_buffer_frames, max_buffer_frames) = buffer_limits(range.buffer_size());
+    OutputFormat {
+        channels: range.channels(),
+        min_sample_rate: range.min_sample_rate().0,
+        max_sample_rate: range.max_sample_rate().0,
+        sample_format: range.sample_format().to_string(),
+        min_buffer_frames,
+        max_buffer_frames,
+    }
+}
+
+fn format_of_config(config: &cpal::SupportedStreamConfig) -> OutputFormat {
+    let (min_buffer_frames, max_buffer_frames) = buffer_limits(config.buffer_size());
+    OutputFormat {
+        channels: config.channels(),
+        min_sample_rate: config.sample_rate().0,
+        max_sample_rate: config.sample_rate().0,
+        sample_format: config.sample_format().to_string(),
+        min_buffer_frames,
+        max_buffer_frames,
+    }
+}
+
+fn buffer_limits(size: &cpal::SupportedBufferSize) -> (Option<u32>, Option<u32>) {
+    match size {
+        cpal::SupportedBufferSize::Range { min, max } => (Some(*min), Some(*max)),
+        cpal::SupportedBufferSize::Unknown => (None, None),
+    }
+}
+
+/// Output devices on the default host with the formats each one accepts
+pub fn probe_output_devices() -> Result<AudioCapabilities> {
+    let host = cpal::default_host();
+    let default_name = host.default_output_device().and_then(|device| device.name().ok());
+    let devices = host.output_devices().context("Failed to list audio output devices")?;
+
+    let devices = devices
+        .filter_map(|device| {
+            // Devices that vanish or refuse to report a name cannot be selected anyway
+            let name = device.name().ok()?;
+            let formats = device
+                .supported_output_configs()
+                .map(|configs| configs.map(|range| format_of_range(&range)).collect())
+                .unwrap_or_default();
+            Some(OutputDeviceInfo {
+                id: name.clone(),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                default_format: device.default_output_config().ok().map(|config| format_of_config(&config)),
+                formats,
+                name,
+            })
+        })
+        .collect();
+
+    Ok(AudioCapabilities {
+        host: host.id().name().to_string(),
+        devices,
+    })
+}
+
+/// The requested device, or the system default when it is unset or has been unplugged
+fn find_output_device(device_id: Option<&str>) -> Result<cpal::Device> {
+    let host = cpal::default_host();
+    if let Some(device_id) = device_id {
+        let found = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|name| name == device_id)));
+        match found {
+            Some(device) => return Ok(device),
+            None => log::warn!("AUDIO OUTPUT: Device {} not found, using the default output", device_id),
+        }
+    }
+    host.default_output_device().context("No audio output device available")
+}
+
+/// Open the configured output with the requested buffer size. Devices that reject
+/// the size fall back to any configuration they support.
+pub fn open_output_stream(settings: &OutputSettings, monitor: Arc<UnderrunMonitor>) -> Result<OutputStream> {
+    let device = find_output_device(settings.device.as_deref())?;
+    let buffer_size = match settings.buffer_frames {
+        Some(frames) => cpal::BufferSize::Fixed(frames),
+        None => cpal::BufferSize::Default,
+    };
+
+    let builder = OutputStreamBuilder::from_device(device)
+        .context("Failed to read output device configuration")?
+        .with_buffer_size(buffer_size)
+        .with_error_callback(move |error| monitor.record(&error));
+
+    let mut stream = builder
+        .open_stream_or_fallback()
+        .or_else(|_| OutputStreamBuilder::open_default_stream())
+        .context("Failed to create audio output stream")?;
+
+    // Disable logging on drop to avoid cluttering output
+    stream.log_on_drop(false);
+    log::info!("AUDIO OUTPUT: Opened stream with buffer {:?}", stream.config().buffer_size());
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_grows_until_maximum() {
+        assert_eq!(next_buffer_frames(None), Some(AUTO_BUFFER_START_FRAMES));
+        assert_eq!(next_buffer_frames(Some(256)), Some(AUTO_BUFFER_START_FRAMES));
+        assert_eq!(next_buffer_frames(Some(4096)), Some(8192));
+        assert_eq!(next_buffer_frames(Some(12000)), Some(MAX_BUFFER_FRAMES));
+        assert_eq!(next_buffer_frames(Some(MAX_BUFFER_FRAMES)), None);
+    }
+
+    #[test]
+    fn test_rebuild_needs_a_burst_of_underruns() {
+        let monitor = UnderrunMonitor::default();
+        let start = Instant::now();
+
+        // Errors spread over a long time are not a burst
+        monitor.record_at(start);
+        monitor.record_at(start + UNDERRUN_WINDOW);
+        monitor.record_at(start + UNDERRUN_WINDOW * 2);
+        assert!(!monitor.needs_rebuild());
+
+        monitor.record_at(start + UNDERRUN_WINDOW * 2 + Duration::from_secs(1));
+        assert!(!monitor.needs_rebuild());
+        monitor.record_at(start + UNDERRUN_WINDOW * 2 + Duration::from_secs(2));
+        assert!(monitor.needs_rebuild());
+
+        monitor.mark_rebuilt();
+        assert!(!monitor.needs_rebuild());
+        assert_eq!(monitor.diagnostics(&OutputSettings::default()).rebuild_count, 1);
+    }
+}
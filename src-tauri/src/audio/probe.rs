@@ -0,0 +1,255 @@
+// Full decode of a file to find problems before playback runs into them.
+// Metadata extraction stops after the headers; this reads every packet, so
+// damaged frames and files cut short by a broken download show up too.
+
+use crate::filesystem::backend::{self, Access};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use ts_rs::TS;
+
+/// Share of damaged packets above which playback is not worth attempting
+const MAX_DAMAGED_RATIO: f64 = 0.1;
+/// Decoded audio shorter than this share of the declared length is cut short
+const MIN_COMPLETE_RATIO: f64 = 0.98;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    Ok,
+    /// Plays, but with skips or an early end
+    Warning,
+    /// Will fail to load or stop almost at once
+    Unplayable,
+}
+
+impl ProbeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProbeStatus::Ok => "ok",
+            ProbeStatus::Warning => "warning",
+            ProbeStatus::Unplayable => "unplayable",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "ok" => ProbeStatus::Ok,
+            "warning" => ProbeStatus::Warning,
+            _ => ProbeStatus::Unplayable,
+        }
+    }
+}
+
+/// What decoding a whole file found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileProbe {
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    /// From the headers, in seconds
+    pub declared_duration: Option<f64>,
+    /// Of the audio that actually decoded, in seconds
+    pub decoded_duration: f64,
+    pub packets: u64,
+    pub damaged_packets: u64,
+    /// Why the file could not be opened or decoding stopped early
+    pub error: Option<String>,
+}
+
+impl FileProbe {
+    /// The verdict and the problems behind it, worst first
+    pub fn assess(&self) -> (ProbeStatus, Vec<String>) {
+        let mut unplayable = Vec::new();
+        let mut warnings = Vec::new();
+
+        if let Some(error) = &self.error {
+            if self.packets == 0 {
+                unplayable.push(error.clone());
+            } else {
+                warnings.push(format!("Decoding stopped early: {}", error));
+            }
+        }
+        if self.sample_rate.is_none() && self.error.is_none() {
+            unplayable.push("No sample rate in the stream headers".to_string());
+        }
+        if self.packets > 0 && self.decoded_duration == 0.0 {
+            unplayable.push("No audio could be decoded".to_string());
+        }
+        if self.damaged_packets > 0 {
+            let ratio = self.damaged_packets as f64 / self.packets.max(1) as f64;
+            let message = format!("{} of {} frames are damaged", self.damaged_packets, self.packets);
+            if ratio > MAX_DAMAGED_RATIO {
+                unplayable.push(message);
+            } else {
+                warnings.push(format!("{}; playback will skip over them", message));
+            }
+        }
+        if let Some(declared) = self.declared_duration.filter(|declared| *declared > 0.0) {
+            if self.decoded_duration > 0.0 && self.decoded_duration < declared * MIN_COMPLETE_RATIO {
+                warnings.push(format!(
+                    "Audio ends at {:.0}s of {:.0}s; the file may be cut short",
+                    self.decoded_duration, declared
+                ));
+            }
+        }
+        if let Some(sample_rate) = self.sample_rate.filter(|rate| !(8_000..=192_000).contains(rate)) {
+            warnings.push(format!("Unusual sample rate of {} Hz", sample_rate));
+        }
+
+        let status = if !unplayable.is_empty() {
+            ProbeStatus::Unplayable
+        } else if !warnings.is_empty() {
+            ProbeStatus::Warning
+        } else {
+            ProbeStatus::Ok
+        };
+        unplayable.extend(warnings);
+        (status, unplayable)
+    }
+}
+
+/// Decode the whole file. Failures end up in the result rather than an error,
+/// since a file that cannot be opened is exactly what this is looking for.
+pub fn probe_file<P: AsRef<Path>>(path: P) -> FileProbe {
+    let path = path.as_ref();
+    let mut probe = FileProbe::default();
+
+    let file = match backend::open(path, Access::Scan) {
+        Ok(file) => file,
+        Err(e) => {
+            probe.error = Some(format!("Failed to open file: {}", e));
+            return probe;
+        }
+    };
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = match symphonia::default::get_probe().format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default()) {
+        Ok(probed) => probed,
+        Err(e) => {
+            probe.error = Some(format!("Unrecognised audio format: {}", e));
+            return probe;
+        }
+    };
+    let mut format = probed.format;
+
+    let Some(track) = format.tracks().iter().find(|track| track.codec_params.codec != CODEC_TYPE_NULL) else {
+        probe.error = Some("No audio track".to_string());
+        return probe;
+    };
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let codecs = symphonia::default::get_codecs();
+    probe.codec = codecs.get_codec(params.codec).map(|codec| codec.short_name.to_string());
+    probe.sample_rate = params.sample_rate;
+    probe.channels = params.channels.map(|channels| channels.count() as u16);
+    probe.declared_duration = params.n_frames.zip(params.sample_rate).map(|(frames, rate)| frames as f64 / rate as f64);
+
+    let mut decoder = match codecs.make(&params, &DecoderOptions::default()) {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            probe.error = Some(format!("Unsupported codec: {}", e));
+            return probe;
+        }
+    };
+
+    let mut decoded_frames = 0u64;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(SymphoniaError::DecodeError(_)) => {
+                probe.damaged_packets += 1;
+                probe.packets += 1;
+                continue;
+            }
+            Err(e) => {
+                probe.error = Some(e.to_string());
+                break;
+            }
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        probe.packets += 1;
+        match decoder.decode(&packet) {
+            Ok(buffer) => decoded_frames += buffer.frames() as u64,
+            Err(SymphoniaError::DecodeError(_)) | Err(SymphoniaError::IoError(_)) => probe.damaged_packets += 1,
+            Err(e) => {
+                probe.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    let rate = probe.sample_rate.or(decoder.codec_params().sample_rate);
+    probe.decoded_duration = rate.map(|rate| decoded_frames as f64 / rate as f64).unwrap_or(0.0);
+    probe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+
+    fn wav(seconds: u32, declared_seconds: u32) -> Vec<u8> {
+        let rate = 8_000u32;
+        let data_len = rate * 2 * declared_seconds;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&rate.to_le_bytes());
+        bytes.extend_from_slice(&(rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + (rate * 2 * seconds) as usize, 0);
+        bytes
+    }
+
+    #[test]
+    fn test_probe_finds_unreadable_and_cut_short_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, bytes: &[u8]| {
+            let path = dir.path().join(name);
+            File::create(&path).unwrap().write_all(bytes).unwrap();
+            path
+        };
+
+        let complete = probe_file(write("complete.wav", &wav(2, 2)));
+        assert_eq!(complete.codec.as_deref(), Some("pcm_s16le"));
+        assert_eq!((complete.sample_rate, complete.channels), (Some(8_000), Some(1)));
+        assert!((complete.decoded_duration - 2.0).abs() < 0.01);
+        assert_eq!(complete.assess(), (ProbeStatus::Ok, vec![]));
+
+        let (status, issues) = probe_file(write("cut.wav", &wav(1, 4))).assess();
+        assert_eq!(status, ProbeStatus::Warning);
+        assert!(issues[0].contains("cut short"), "{:?}", issues);
+
+        let (status, _) = probe_file(write("garbage.mp3", b"not audio at all")).assess();
+        assert_eq!(status, ProbeStatus::Unplayable);
+        assert_eq!(probe_file(dir.path().join("missing.mp3")).assess().0, ProbeStatus::Unplayable);
+
+        let damaged = FileProbe { sample_rate: Some(44_100), packets: 100, damaged_packets: 30, decoded_duration: 60.0, ..Default::default() };
+        assert_eq!(damaged.assess().0, ProbeStatus::Unplayable);
+        let scratched = FileProbe { damaged_packets: 2, ..damaged };
+        assert_eq!(scratched.assess().0, ProbeStatus::Warning);
+    }
+}
@@ -0,0 +1,107 @@
+// Places playback jumped away from during this session, newest last, so an
+// accidental seek or chapter jump can be undone. Nothing is persisted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+const MAX_ENTRIES: usize = 50;
+/// Positions this close to the start of a file are not worth going back to
+const MIN_POSITION_SECONDS: u64 = 5;
+/// Seeks this soon after the last recorded one are the same gesture, such as
+/// dragging the progress bar; only the place before the first is kept
+const BURST_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SeekHistoryEntry {
+    pub file_path: String,
+    #[ts(type = "number")]
+    pub position: u64,
+    /// RFC 3339
+    pub recorded_at: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SeekHistory {
+    entries: VecDeque<SeekHistoryEntry>,
+    last_recorded: Option<Instant>,
+}
+
+impl SeekHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember where playback was before a jump
+    pub fn record(&mut self, file_path: &str, position: u64, now: Instant) {
+        if position < MIN_POSITION_SECONDS {
+            return;
+        }
+        if self.last_recorded.is_some_and(|last| now.duration_since(last) < BURST_WINDOW) {
+            return;
+        }
+        self.last_recorded = Some(now);
+        if self.entries.back().is_some_and(|last| last.file_path == file_path && last.position.abs_diff(position) < MIN_POSITION_SECONDS) {
+            return;
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(SeekHistoryEntry {
+            file_path: file_path.to_string(),
+            position,
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// The most recent place, removed so the next undo goes further back.
+    /// Going back there is itself a jump; it falls within the burst window and
+    /// is not recorded.
+    pub fn pop(&mut self, now: Instant) -> Option<SeekHistoryEntry> {
+        let entry = self.entries.pop_back()?;
+        self.last_recorded = Some(now);
+        Some(entry)
+    }
+
+    /// Newest first
+    pub fn entries(&self) -> Vec<SeekHistoryEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seek_history_keeps_the_place_before_each_jump() {
+        let start = Instant::now();
+        let mut history = SeekHistory::new();
+
+        history.record("a.mp3", 2, start);
+        assert!(history.entries().is_empty(), "too close to the start");
+
+        history.record("a.mp3", 600, start);
+        // Dragging the progress bar sends a run of seeks
+        history.record("a.mp3", 900, start + Duration::from_millis(300));
+        history.record("a.mp3", 1200, start + Duration::from_millis(600));
+        history.record("b.mp3", 45, start + Duration::from_secs(10));
+        let entries = history.entries();
+        assert_eq!(entries.iter().map(|e| (e.file_path.as_str(), e.position)).collect::<Vec<_>>(), vec![("b.mp3", 45), ("a.mp3", 600)]);
+
+        let undo_at = start + Duration::from_secs(20);
+        assert_eq!(history.pop(undo_at).unwrap().position, 45);
+        history.record("b.mp3", 300, undo_at + Duration::from_millis(100));
+        assert_eq!(history.entries().len(), 1, "the undo's own seek is not recorded");
+        assert_eq!(history.pop(undo_at).unwrap().position, 600);
+        assert!(history.pop(undo_at).is_none());
+
+        for i in 0..MAX_ENTRIES as u64 + 5 {
+            history.record("c.mp3", 100 + i * 60, start + Duration::from_secs(30 + i * 10));
+        }
+        assert_eq!(history.entries().len(), MAX_ENTRIES);
+        assert_eq!(history.entries().last().unwrap().position, 100 + 5 * 60, "oldest dropped first");
+    }
+}
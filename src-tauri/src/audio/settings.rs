@@ -0,0 +1,125 @@
+// Playback preferences the audio thread reads. They are loaded from the
+// preferences table at startup and kept here, so the thread picks them up when
+// it starts and commands can answer without a database read.
+
+use super::ducking::DuckingSettings;
+use super::focus::{self, AudioFocusSettings};
+use super::output::{self, OutputSettings};
+use super::watchdog;
+use crate::database::repository::PreferencesRepository;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+pub const PREF_PRESERVE_PITCH: &str = "playback.preserve_pitch";
+pub const PREF_SKIP_BAD_CHAPTERS: &str = "playback.skip_bad_chapters";
+pub const PREF_STALL_GRACE_SECONDS: &str = "playback.stall_grace_seconds";
+pub const PREF_OUTPUT_BUFFER_FRAMES: &str = "audio.output_buffer_frames";
+pub const PREF_KEEP_AWAKE: &str = "playback.keep_awake";
+pub const PREF_OUTPUT_DEVICE: &str = "audio.output_device";
+pub const PREF_DUCKING: &str = "audio.ducking";
+
+static PRESERVE_PITCH: AtomicBool = AtomicBool::new(true);
+// Whether playback moves past chapter files that fail to load
+static SKIP_BAD_CHAPTERS: AtomicBool = AtomicBool::new(true);
+// Seconds between reporting a decoder stall and reloading the file, 0 to only report it
+static STALL_GRACE_SECONDS: AtomicU64 = AtomicU64::new(watchdog::DEFAULT_GRACE_SECONDS);
+// Output buffer size in frames, 0 for the device default
+static OUTPUT_BUFFER_FRAMES: AtomicU32 = AtomicU32::new(0);
+// Checked by the audio thread whenever playback state may have changed
+static KEEP_AWAKE: AtomicBool = AtomicBool::new(false);
+// Output device chosen through init_audio, None for the system default
+static OUTPUT_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+// None until loaded from preferences
+static DUCKING: Mutex<Option<DuckingSettings>> = Mutex::new(None);
+static AUDIO_FOCUS: Mutex<Option<AudioFocusSettings>> = Mutex::new(None);
+
+/// Read every setting from the preferences table
+pub async fn load(pool: &sqlx::SqlitePool) {
+    let repo = PreferencesRepository::new(pool);
+    set_preserve_pitch(repo.get_bool(PREF_PRESERVE_PITCH, true).await.unwrap_or(true));
+    set_skip_bad_chapters(repo.get_bool(PREF_SKIP_BAD_CHAPTERS, true).await.unwrap_or(true));
+    let stall_grace = repo
+        .get_i64(PREF_STALL_GRACE_SECONDS, watchdog::DEFAULT_GRACE_SECONDS as i64)
+        .await
+        .unwrap_or(watchdog::DEFAULT_GRACE_SECONDS as i64);
+    set_stall_grace_seconds(stall_grace.clamp(0, watchdog::MAX_GRACE_SECONDS as i64) as u64);
+    let buffer_frames = repo.get_i64(PREF_OUTPUT_BUFFER_FRAMES, 0).await.unwrap_or(0);
+    set_output_buffer_frames(buffer_frames.clamp(0, output::MAX_BUFFER_FRAMES as i64) as u32);
+    set_keep_awake(repo.get_bool(PREF_KEEP_AWAKE, false).await.unwrap_or(false));
+    *OUTPUT_DEVICE.lock().unwrap() = repo.get(PREF_OUTPUT_DEVICE).await.ok().flatten();
+    *DUCKING.lock().unwrap() = repo.get(PREF_DUCKING).await.ok().flatten()
+        .and_then(|json| serde_json::from_str::<DuckingSettings>(&json).ok());
+    *AUDIO_FOCUS.lock().unwrap() = repo.get(focus::PREF_AUDIO_FOCUS).await.ok().flatten()
+        .and_then(|json| serde_json::from_str::<AudioFocusSettings>(&json).ok());
+}
+
+pub fn preserve_pitch() -> bool {
+    PRESERVE_PITCH.load(Ordering::Relaxed)
+}
+
+pub fn set_preserve_pitch(enabled: bool) {
+    PRESERVE_PITCH.store(enabled, Ordering::Relaxed);
+}
+
+pub fn skip_bad_chapters() -> bool {
+    SKIP_BAD_CHAPTERS.load(Ordering::Relaxed)
+}
+
+pub fn set_skip_bad_chapters(enabled: bool) {
+    SKIP_BAD_CHAPTERS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn stall_grace_seconds() -> u64 {
+    STALL_GRACE_SECONDS.load(Ordering::Relaxed)
+}
+
+pub fn set_stall_grace_seconds(seconds: u64) {
+    STALL_GRACE_SECONDS.store(seconds, Ordering::Relaxed);
+}
+
+pub fn keep_awake() -> bool {
+    KEEP_AWAKE.load(Ordering::Relaxed)
+}
+
+pub fn set_keep_awake(enabled: bool) {
+    KEEP_AWAKE.store(enabled, Ordering::Relaxed);
+}
+
+/// 0 goes back to the device default
+pub fn set_output_buffer_frames(frames: u32) {
+    OUTPUT_BUFFER_FRAMES.store(frames, Ordering::Relaxed);
+}
+
+pub fn output_device() -> Option<String> {
+    OUTPUT_DEVICE.lock().unwrap().clone()
+}
+
+pub fn set_output_device(device_id: Option<String>) {
+    *OUTPUT_DEVICE.lock().unwrap() = device_id;
+}
+
+/// The buffer size and device the audio thread opens its stream with
+pub fn output_settings() -> OutputSettings {
+    let frames = OUTPUT_BUFFER_FRAMES.load(Ordering::Relaxed);
+    OutputSettings {
+        buffer_frames: (frames > 0).then_some(frames),
+        device: output_device(),
+    }
+}
+
+pub fn ducking() -> DuckingSettings {
+    DUCKING.lock().unwrap().clone().unwrap_or_default()
+}
+
+pub fn set_ducking(settings: DuckingSettings) {
+    *DUCKING.lock().unwrap() = Some(settings);
+}
+
+/// What playback does when other audio or a call takes focus
+pub fn audio_focus() -> AudioFocusSettings {
+    AUDIO_FOCUS.lock().unwrap().clone().unwrap_or_default()
+}
+
+pub fn set_audio_focus(settings: AudioFocusSettings) {
+    *AUDIO_FOCUS.lock().unwrap() = Some(settings);
+}
@@ -0,0 +1,340 @@
+// Pitch-preserving time stretch (WSOLA) for playback speeds other than 1x.
+// rodio's own speed control resamples, which raises the narrator's pitch along
+// with the tempo; this source changes tempo only and leaves the sink at 1x.
+
+use rodio::source::SeekError;
+use rodio::{ChannelCount, Sample, SampleRate, Source};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Analysis/synthesis window, long enough to span a couple of voice pitch periods
+const WINDOW_MS: u32 = 40;
+/// How far a window may be shifted to line up with the previous one
+const TOLERANCE_MS: u32 = 8;
+/// Speeds this close to 1x are played untouched
+const BYPASS_EPSILON: f32 = 0.01;
+
+/// Settings shared between the engine and the source inside the sink
+#[derive(Debug)]
+pub struct StretchControl {
+    speed_bits: AtomicU32,
+    preserve_pitch: AtomicBool,
+}
+
+impl StretchControl {
+    pub fn new(preserve_pitch: bool) -> Self {
+        Self {
+            speed_bits: AtomicU32::new(1.0f32.to_bits()),
+            preserve_pitch: AtomicBool::new(preserve_pitch),
+        }
+    }
+
+    pub fn set_speed(&self, speed: f32) {
+        self.speed_bits.store(speed.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn speed(&self) -> f32 {
+        f32::from_bits(self.speed_bits.load(Ordering::Relaxed))
+    }
+
+    pub fn set_preserve_pitch(&self, enabled: bool) {
+        self.preserve_pitch.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn preserve_pitch(&self) -> bool {
+        self.preserve_pitch.load(Ordering::Relaxed)
+    }
+
+    /// Tempo change the stretcher applies; 1.0 means pass-through
+    pub fn stretch_ratio(&self) -> f32 {
+        let speed = self.speed();
+        if self.preserve_pitch() && (speed - 1.0).abs() > BYPASS_EPSILON {
+            speed
+        } else {
+            1.0
+        }
+    }
+
+    /// Speed the sink should resample at: 1x while the stretcher handles tempo
+    pub fn sink_speed(&self) -> f32 {
+        if self.preserve_pitch() { 1.0 } else { self.speed() }
+    }
+}
+
+pub struct TimeStretch<S: Source> {
+    inner: S,
+    control: Arc<StretchControl>,
+    channels: usize,
+    sample_rate: SampleRate,
+    /// Synthesis hop in frames; the window is two hops long
+    hop: usize,
+    tolerance: usize,
+    window: Vec<f32>,
+    /// Interleaved input not yet discarded
+    input: Vec<Sample>,
+    /// Ideal position of the next analysis window, in frames into `input`
+    analysis_pos: f64,
+    /// Where the previous window was actually taken from
+    previous_pos: Option<usize>,
+    /// Second half of the previous windowed frame, waiting for its overlap
+    overlap: Vec<Sample>,
+    output: VecDeque<Sample>,
+    stretching: bool,
+    inner_done: bool,
+}
+
+impl<S: Source> TimeStretch<S> {
+    pub fn new(inner: S, control: Arc<StretchControl>) -> Self {
+        let channels = inner.channels().max(1) as usize;
+        let sample_rate = inner.sample_rate();
+        let hop = (sample_rate * WINDOW_MS / 2000).max(1) as usize;
+        let tolerance = (sample_rate * TOLERANCE_MS / 1000) as usize;
+
+        // Periodic Hann: overlapping halves sum to exactly one
+        let length = hop * 2;
+        let window = (0..length)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / length as f32).cos())
+            .collect();
+
+        Self {
+            inner,
+            control,
+            channels,
+            sample_rate,
+            hop,
+            tolerance,
+            window,
+            input: Vec::new(),
+            analysis_pos: 0.0,
+            previous_pos: None,
+            overlap: vec![0.0; hop * channels],
+            output: VecDeque::new(),
+            stretching: false,
+            inner_done: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.analysis_pos = 0.0;
+        self.previous_pos = None;
+        self.overlap.iter_mut().for_each(|sample| *sample = 0.0);
+        self.output.clear();
+    }
+
+    fn available_frames(&self) -> usize {
+        self.input.len() / self.channels
+    }
+
+    fn fill_to(&mut self, frames: usize) {
+        while !self.inner_done && self.available_frames() < frames {
+            match self.inner.next() {
+                Some(sample) => self.input.push(sample),
+                None => self.inner_done = true,
+            }
+        }
+    }
+
+    /// Hand whatever input has not been played yet to the output unchanged, so
+    /// going back to 1x neither skips nor repeats audio
+    fn leave_stretch(&mut self) {
+        let resume_at = match self.previous_pos {
+            Some(pos) => pos + self.hop,
+            None => self.analysis_pos as usize,
+        };
+        let start = (resume_at * self.channels).min(self.input.len());
+        let pending: Vec<Sample> = self.input.drain(start..).collect();
+        self.reset();
+        self.output.extend(pending);
+        self.stretching = false;
+    }
+
+    /// Mono mixdown of one frame, used for alignment only
+    fn mono(&self, frame: usize) -> f32 {
+        let start = frame * self.channels;
+        self.input[start..start + self.channels].iter().sum()
+    }
+
+    /// Start of the window between `from` and `to` that best continues the
+    /// previous window's natural continuation at `template`
+    fn best_offset(&self, template: usize, from: usize, to: usize) -> usize {
+        let mut best = from;
+        let mut best_score = f32::MIN;
+        for candidate in from..=to {
+            let mut score = 0.0;
+            // Every other frame is plenty for lining up speech
+            for i in (0..self.hop).step_by(2) {
+                score += self.mono(template + i) * self.mono(candidate + i);
+            }
+            if score > best_score {
+                best_score = score;
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    /// Overlap-add one more window into the output. False once the input is exhausted.
+    fn produce_frame(&mut self, ratio: f32) -> bool {
+        let length = self.hop * 2;
+        let ideal = self.analysis_pos as usize;
+        self.fill_to(ideal + self.tolerance + length);
+
+        let available = self.available_frames();
+        if ideal >= available {
+            return false;
+        }
+
+        let position = match self.previous_pos {
+            Some(previous) if previous + self.hop * 2 <= available && ideal + self.hop <= available => {
+                let from = ideal.saturating_sub(self.tolerance);
+                let to = (ideal + self.tolerance).min(available - self.hop);
+                self.best_offset(previous + self.hop, from, to.max(from))
+            }
+            _ => ideal,
+        };
+
+        let channels = self.channels;
+        for i in 0..length {
+            for channel in 0..channels {
+                let sample = self.input.get((position + i) * channels + channel).copied().unwrap_or(0.0);
+                let windowed = sample * self.window[i];
+                let index = i * channels + channel;
+                if i < self.hop {
+                    self.output.push_back(windowed + self.overlap[index]);
+                } else {
+                    self.overlap[index - self.hop * channels] = windowed;
+                }
+            }
+        }
+
+        self.previous_pos = Some(position);
+        self.analysis_pos += self.hop as f64 * ratio as f64;
+
+        // Drop input that no future window or template can reach
+        let keep_from = position.min(self.analysis_pos as usize).saturating_sub(self.tolerance);
+        if keep_from > length * 4 {
+            self.input.drain(..keep_from * channels);
+            self.analysis_pos -= keep_from as f64;
+            self.previous_pos = Some(position - keep_from);
+        }
+
+        true
+    }
+}
+
+impl<S: Source> Iterator for TimeStretch<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        loop {
+            if let Some(sample) = self.output.pop_front() {
+                return Some(sample);
+            }
+
+            let ratio = self.control.stretch_ratio();
+            if ratio == 1.0 {
+                if self.stretching {
+                    self.leave_stretch();
+                    continue;
+                }
+                return self.inner.next();
+            }
+
+            self.stretching = true;
+            if !self.produce_frame(ratio) {
+                // Let the last window fade out, then end
+                if self.overlap.iter().any(|sample| *sample != 0.0) {
+                    self.output.extend(self.overlap.iter().copied());
+                    self.overlap.iter_mut().for_each(|sample| *sample = 0.0);
+                    continue;
+                }
+                return None;
+            }
+        }
+    }
+}
+
+impl<S: Source> Source for TimeStretch<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        // Buffering hides the inner span boundaries; audiobook files keep one
+        // channel layout and rate throughout
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels as ChannelCount
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        self.reset();
+        self.stretching = false;
+        self.inner_done = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn sine(seconds: f32, frequency: f32, sample_rate: u32) -> Vec<f32> {
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    /// Zero crossings per second, a rough pitch estimate for a pure tone
+    fn crossings_per_second(samples: &[f32], sample_rate: u32) -> f32 {
+        let crossings = samples.windows(2).filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0)).count();
+        crossings as f32 / (samples.len() as f32 / sample_rate as f32)
+    }
+
+    #[test]
+    fn test_stretch_changes_length_but_not_pitch() {
+        let sample_rate = 16_000;
+        let input = sine(2.0, 220.0, sample_rate);
+        let control = Arc::new(StretchControl::new(true));
+        control.set_speed(2.0);
+
+        let output: Vec<f32> = TimeStretch::new(SamplesBuffer::new(1, sample_rate, input.clone()), control).collect();
+
+        let length_ratio = output.len() as f32 / input.len() as f32;
+        assert!((length_ratio - 0.5).abs() < 0.05, "length ratio {}", length_ratio);
+
+        // Skip the fade-in of the first window
+        let settled = &output[sample_rate as usize / 10..];
+        let pitch_ratio = crossings_per_second(settled, sample_rate) / crossings_per_second(&input, sample_rate);
+        assert!((pitch_ratio - 1.0).abs() < 0.05, "pitch ratio {}", pitch_ratio);
+    }
+
+    #[test]
+    fn test_pass_through_at_normal_speed_or_when_disabled() {
+        let sample_rate = 8_000;
+        let input = sine(0.5, 200.0, sample_rate);
+
+        let control = Arc::new(StretchControl::new(false));
+        control.set_speed(1.8);
+        assert_eq!(control.stretch_ratio(), 1.0);
+        assert_eq!(control.sink_speed(), 1.8);
+        let output: Vec<f32> = TimeStretch::new(SamplesBuffer::new(1, sample_rate, input.clone()), control).collect();
+        assert_eq!(output, input);
+
+        let control = Arc::new(StretchControl::new(true));
+        assert_eq!(control.sink_speed(), 1.0);
+        let output: Vec<f32> = TimeStretch::new(SamplesBuffer::new(1, sample_rate, input.clone()), control).collect();
+        assert_eq!(output, input);
+    }
+}
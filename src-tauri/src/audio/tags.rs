@@ -0,0 +1,305 @@
+// Tag writing module using lofty
+
+use anyhow::{Context, Result};
+use lofty::config::WriteOptions;
+use lofty::picture::{MimeType, Picture, PictureType};
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::{ItemKey, Tag};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// Tag values to write into an audio file; `None` leaves the existing value untouched
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TagValues {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    /// Narrator, stored in the composer field as most audiobook players expect
+    pub narrator: Option<String>,
+    pub genre: Option<String>,
+    pub comment: Option<String>,
+    pub track: Option<u32>,
+    pub track_total: Option<u32>,
+}
+
+/// Original tags of one file, captured before AudioVibe rewrites them
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FileTagBackup {
+    pub file_path: String,
+    pub tags: TagValues,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TagBackup {
+    pub audiobook_id: String,
+    pub created_at: String,
+    pub files: Vec<FileTagBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TagWriteResult {
+    pub files_written: usize,
+    pub failed_files: Vec<String>,
+    pub backup_path: Option<String>,
+}
+
+/// Read the text tags this module knows how to write
+pub fn read_tags<P: AsRef<Path>>(path: P) -> Result<TagValues> {
+    let path = path.as_ref();
+
+    let tagged_file = Probe::open(path)
+        .with_context(|| format!("Failed to open file for tagging: {}", path.display()))?
+        .read()
+        .with_context(|| format!("Failed to read tags from: {}", path.display()))?;
+
+    let Some(tag) = tagged_file.primary_tag().or_else(|| tagged_file.first_tag()) else {
+        return Ok(TagValues::default());
+    };
+
+    Ok(TagValues {
+        title: tag.title().map(|v| v.to_string()),
+        artist: tag.artist().map(|v| v.to_string()),
+        album: tag.album().map(|v| v.to_string()),
+        album_artist: tag.get_string(&ItemKey::AlbumArtist).map(|v| v.to_string()),
+        narrator: tag.get_string(&ItemKey::Composer).map(|v| v.to_string()),
+        genre: tag.genre().map(|v| v.to_string()),
+        comment: tag.comment().map(|v| v.to_string()),
+        track: tag.track(),
+        track_total: tag.track_total(),
+    })
+}
+
+/// The cover picture embedded in an audio file and a file extension for it,
+/// preferring the front cover when a file carries several pictures
+pub fn read_embedded_cover<P: AsRef<Path>>(path: P) -> Result<Option<(Vec<u8>, &'static str)>> {
+    let path = path.as_ref();
+
+    let tagged_file = Probe::open(path)
+        .with_context(|| format!("Failed to open file for tagging: {}", path.display()))?
+        .read()
+        .with_context(|| format!("Failed to read tags from: {}", path.display()))?;
+
+    let pictures: Vec<&Picture> = tagged_file.tags().iter().flat_map(|tag| tag.pictures()).collect();
+    let Some(picture) = pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+    else {
+        return Ok(None);
+    };
+
+    let extension = match picture.mime_type() {
+        Some(MimeType::Png) => "png",
+        Some(MimeType::Gif) => "gif",
+        Some(MimeType::Bmp) => "bmp",
+        _ => "jpg",
+    };
+    Ok(Some((picture.data().to_vec(), extension)))
+}
+
+pub fn write_tags<P: AsRef<Path>>(path: P, values: &TagValues, cover: Option<&Path>) -> Result<()> {
+    save_tags(path.as_ref(), values, cover, false)
+}
+
+/// Write `values` exactly, removing any field that is `None` (used to restore a backup)
+pub fn replace_tags<P: AsRef<Path>>(path: P, values: &TagValues) -> Result<()> {
+    save_tags(path.as_ref(), values, None, true)
+}
+
+fn save_tags(path: &Path, values: &TagValues, cover: Option<&Path>, clear_missing: bool) -> Result<()> {
+    let mut tagged_file = Probe::open(path)
+        .with_context(|| format!("Failed to open file for tagging: {}", path.display()))?
+        .read()
+        .with_context(|| format!("Failed to read tags from: {}", path.display()))?;
+
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or_else(|| anyhow::anyhow!("File format does not support tags: {}", path.display()))?;
+
+    if clear_missing {
+        tag.remove_title();
+        tag.remove_artist();
+        tag.remove_album();
+        tag.remove_key(&ItemKey::AlbumArtist);
+        tag.remove_key(&ItemKey::Composer);
+        tag.remove_genre();
+        tag.remove_comment();
+        tag.remove_track();
+        tag.remove_track_total();
+    }
+
+    if let Some(title) = &values.title {
+        tag.set_title(title.clone());
+    }
+    if let Some(artist) = &values.artist {
+        tag.set_artist(artist.clone());
+    }
+    if let Some(album) = &values.album {
+        tag.set_album(album.clone());
+    }
+    if let Some(album_artist) = &values.album_artist {
+        tag.insert_text(ItemKey::AlbumArtist, album_artist.clone());
+    }
+    if let Some(narrator) = &values.narrator {
+        tag.insert_text(ItemKey::Composer, narrator.clone());
+    }
+    if let Some(genre) = &values.genre {
+        tag.set_genre(genre.clone());
+    }
+    if let Some(comment) = &values.comment {
+        tag.set_comment(comment.clone());
+    }
+    if let Some(track) = values.track {
+        tag.set_track(track);
+    }
+    if let Some(track_total) = values.track_total {
+        tag.set_track_total(track_total);
+    }
+
+    if let Some(cover_path) = cover {
+        let data = std::fs::read(cover_path)
+            .with_context(|| format!("Failed to read cover image: {}", cover_path.display()))?;
+        let mime_type = match cover_path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+            Some("png") => MimeType::Png,
+            _ => MimeType::Jpeg,
+        };
+        tag.remove_picture_type(PictureType::CoverFront);
+        tag.push_picture(Picture::new_unchecked(PictureType::CoverFront, Some(mime_type), None, data));
+    }
+
+    tag.save_to_path(path, WriteOptions::default())
+        .with_context(|| format!("Failed to save tags to: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Save the current tags of every target to a timestamped JSON backup, then write the new values
+pub fn write_tags_with_backup(
+    audiobook_id: &str,
+    targets: &[(PathBuf, TagValues)],
+    backup_dir: &Path,
+) -> Result<TagWriteResult> {
+    let mut backup = TagBackup {
+        audiobook_id: audiobook_id.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: Vec::new(),
+    };
+    let mut failed_files = Vec::new();
+    let mut writable = Vec::new();
+
+    for (path, values) in targets {
+        match read_tags(path) {
+            Ok(tags) => {
+                backup.files.push(FileTagBackup {
+                    file_path: path.to_string_lossy().to_string(),
+                    tags,
+                });
+                writable.push((path, values));
+            }
+            Err(e) => {
+                // Never touch a file whose original tags could not be saved
+                log::warn!("Skipping tag write for {}: {}", path.display(), e);
+                failed_files.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let backup_path = if backup.files.is_empty() {
+        None
+    } else {
+        std::fs::create_dir_all(backup_dir).context("Failed to create tag backup directory")?;
+        let path = backup_dir.join(format!("{}.json", chrono::Utc::now().format("%Y%m%d%H%M%S%3f")));
+        let json = serde_json::to_string_pretty(&backup).context("Failed to serialize tag backup")?;
+        std::fs::write(&path, json).context("Failed to write tag backup")?;
+        Some(path.to_string_lossy().to_string())
+    };
+
+    let mut files_written = 0;
+    for (path, values) in writable {
+        match write_tags(path, values, None) {
+            Ok(()) => files_written += 1,
+            Err(e) => {
+                log::warn!("Failed to write tags to {}: {}", path.display(), e);
+                failed_files.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Ok(TagWriteResult {
+        files_written,
+        failed_files,
+        backup_path,
+    })
+}
+
+/// Most recent backup file in `backup_dir`; names are timestamps so they sort chronologically
+pub fn latest_backup_path(backup_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(backup_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+        .max()
+}
+
+/// Put back the tags from the most recent backup and return how many files were restored
+pub fn restore_latest_backup(backup_dir: &Path) -> Result<usize> {
+    let path = latest_backup_path(backup_dir)
+        .ok_or_else(|| anyhow::anyhow!("No tag backup found"))?;
+    let json = std::fs::read_to_string(&path).context("Failed to read tag backup")?;
+    let backup: TagBackup = serde_json::from_str(&json).context("Failed to parse tag backup")?;
+
+    let mut restored = 0;
+    for file in &backup.files {
+        match replace_tags(&file.file_path, &file.tags) {
+            Ok(()) => restored += 1,
+            Err(e) => log::warn!("Failed to restore tags for {}: {}", file.file_path, e),
+        }
+    }
+
+    std::fs::remove_file(&path).context("Failed to remove restored tag backup")?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_backup_path() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(latest_backup_path(dir.path()).is_none());
+
+        std::fs::write(dir.path().join("20240101120000000.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("20240301120000000.json"), "{}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        assert_eq!(latest_backup_path(dir.path()).unwrap(), dir.path().join("20240301120000000.json"));
+    }
+
+    #[test]
+    fn test_unreadable_files_are_not_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let fake = dir.path().join("fake.mp3");
+        std::fs::write(&fake, b"not audio").unwrap();
+
+        let values = TagValues { title: Some("New".to_string()), ..Default::default() };
+        let result = write_tags_with_backup("book", &[(fake.clone(), values)], &dir.path().join("backups")).unwrap();
+
+        assert_eq!(result.files_written, 0);
+        assert_eq!(result.failed_files.len(), 1);
+        assert!(result.backup_path.is_none());
+        assert_eq!(std::fs::read(&fake).unwrap(), b"not audio");
+    }
+}
@@ -0,0 +1,597 @@
+// The dedicated audio thread. It owns the AudioManager, whose output stream
+// cannot move between threads, and takes AudioCommands over a channel; commands
+// get the sender through `sender` or `running_sender`.
+
+use super::ambience::{Ambience, AmbienceStatus};
+use super::ducking::DuckingSettings;
+use super::frame_index::FrameIndex;
+use super::live_status::AtomicPositionState;
+use super::metrics::AudioMetrics;
+use super::output::{OutputDiagnostics, OutputSettings};
+use super::seek_history::{SeekHistory, SeekHistoryEntry};
+use super::voice_boost::VoiceBoostSettings;
+use super::watchdog::{self, OutputSample, StallWatchdog, WatchdogAction};
+use super::{settings, Advance, AudioManager, PlaybackState, PlaybackStatus, Track, TrackError};
+use crate::download::{self, throttle};
+use crate::events::{self, AppEvent};
+use crate::power;
+use crate::services::{PlaybackEvent, PlayerState};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+// Audio command messages for the dedicated audio thread
+#[derive(Debug)]
+pub enum AudioCommand {
+    LoadFile { file_path: String, response: mpsc::Sender<Result<(), String>> },
+    Play { response: mpsc::Sender<Result<(), String>> },
+    Pause { response: mpsc::Sender<Result<(), String>> },
+    Stop { response: mpsc::Sender<Result<(), String>> },
+    SetVolume { volume: f32, response: mpsc::Sender<Result<(), String>> },
+    SetSpeed { speed: f32, response: mpsc::Sender<Result<(), String>> },
+    Seek { position: f32, response: mpsc::Sender<Result<(), String>> },
+    GetStatus { response: mpsc::Sender<PlaybackStatus> },
+    AddToQueue { track: Track, response: mpsc::Sender<Result<(), String>> },
+    PlayNext { response: mpsc::Sender<Result<bool, String>> },
+    ClearQueue { response: mpsc::Sender<Result<(), String>> },
+    GetQueue { response: mpsc::Sender<Vec<Track>> },
+    LoadQueue { tracks: Vec<Track>, response: mpsc::Sender<Result<(), String>> },
+    ReplaceQueue { tracks: Vec<Track>, response: mpsc::Sender<Result<(), String>> },
+    SetPreservePitch { enabled: bool, response: mpsc::Sender<Result<(), String>> },
+    SetSkipBadChapters { enabled: bool, response: mpsc::Sender<Result<(), String>> },
+    SetStopAfter { chapters: Option<u32>, response: mpsc::Sender<Result<(), String>> },
+    SetOutputSettings { settings: OutputSettings, response: mpsc::Sender<Result<(), String>> },
+    GetOutputDiagnostics { response: mpsc::Sender<OutputDiagnostics> },
+    GetMetrics { response: mpsc::Sender<AudioMetrics> },
+    StartPreview { file_path: String, response: mpsc::Sender<Result<(), String>> },
+    StopPreview { response: mpsc::Sender<Result<(), String>> },
+    SetAmbience { ambience: Option<Ambience>, volume: f32, response: mpsc::Sender<Result<(), String>> },
+    SetDucking { settings: DuckingSettings, response: mpsc::Sender<Result<(), String>> },
+    SetFocusGain { gain: f32, response: mpsc::Sender<Result<(), String>> },
+    SetVoiceBoost { settings: VoiceBoostSettings, response: mpsc::Sender<Result<(), String>> },
+    SetFrameIndex { file_path: String, index: FrameIndex, response: mpsc::Sender<Result<(), String>> },
+    GetAmbience { response: mpsc::Sender<AmbienceStatus> },
+    HandleSystemResume { position: Option<u64>, response: mpsc::Sender<Result<(), String>> },
+    PopSeekHistory { response: mpsc::Sender<Option<SeekHistoryEntry>> },
+    GetSeekHistory { response: mpsc::Sender<Vec<SeekHistoryEntry>> },
+    GetPlayerState { response: mpsc::Sender<Option<PlayerState>> },
+    RestorePlayerState { state: PlayerState, response: mpsc::Sender<Result<(), String>> },
+    /// Sent every watchdog::CHECK_INTERVAL so the checks after each command run while idle too
+    CheckOutput,
+}
+
+// Sender for the audio thread; None until audio output has started, so a failed
+// start can be retried
+static SENDER: Mutex<Option<mpsc::Sender<AudioCommand>>> = Mutex::new(None);
+
+// The running engine's published status, read by get_playback_status_fast
+static LIVE_STATUS: Mutex<Option<Arc<AtomicPositionState>>> = Mutex::new(None);
+
+/// The running engine's status, readable without waiting on the thread
+pub fn live_status() -> Option<Arc<AtomicPositionState>> {
+    LIVE_STATUS.lock().unwrap().clone()
+}
+
+// Playback transitions published by the audio thread for the play history recorder
+static PLAYBACK_EVENTS: OnceLock<UnboundedSender<PlaybackEvent>> = OnceLock::new();
+
+/// The playback transitions from now on; None when another listener already has them
+pub fn subscribe_playback_events() -> Option<UnboundedReceiver<PlaybackEvent>> {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    PLAYBACK_EVENTS.set(sender).ok()?;
+    Some(receiver)
+}
+
+fn emit_playback_event(event: PlaybackEvent) {
+    events::emit(match &event {
+        PlaybackEvent::Loaded { file_path } => AppEvent::PlaybackChanged {
+            state: events::PlaybackState::Loaded,
+            file_path: Some(file_path.clone()),
+            position: None,
+        },
+        PlaybackEvent::Started { position } => AppEvent::PlaybackChanged {
+            state: events::PlaybackState::Playing,
+            file_path: None,
+            position: Some(*position),
+        },
+        PlaybackEvent::Paused { position } => AppEvent::PlaybackChanged {
+            state: events::PlaybackState::Paused,
+            file_path: None,
+            position: Some(*position),
+        },
+        PlaybackEvent::Stopped { position } => AppEvent::PlaybackChanged {
+            state: events::PlaybackState::Stopped,
+            file_path: None,
+            position: Some(*position),
+        },
+        PlaybackEvent::Finished { file_path, position } => AppEvent::PlaybackChanged {
+            state: events::PlaybackState::Stopped,
+            file_path: Some(file_path.clone()),
+            position: Some(*position),
+        },
+    });
+    if let Some(sender) = PLAYBACK_EVENTS.get() {
+        let _ = sender.send(event);
+    }
+}
+
+// Files the audio thread failed to load, for the chapter error recorder
+static TRACK_ERRORS: OnceLock<UnboundedSender<TrackError>> = OnceLock::new();
+
+/// Files that fail to load from now on; None when another listener already has them
+pub fn subscribe_track_errors() -> Option<UnboundedReceiver<TrackError>> {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    TRACK_ERRORS.set(sender).ok()?;
+    Some(receiver)
+}
+
+fn report_track_errors(audio_manager: &AudioManager) {
+    for error in audio_manager.take_track_errors() {
+        println!("THREAD: Failed to load {}: {}", error.file_path, error.error);
+        if let Some(sender) = TRACK_ERRORS.get() {
+            let _ = sender.send(error);
+        }
+    }
+}
+
+// Hand voice boost settings to the audio thread, if it is running
+pub fn send_voice_boost(settings: VoiceBoostSettings) -> Result<(), String> {
+    let Some(sender) = running_sender() else {
+        return Ok(());
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::SetVoiceBoost { settings, response: response_sender })
+        .map_err(|e| format!("Failed to send voice boost command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+// Hand a frame index to the audio thread, if it is running
+pub fn send_frame_index(file_path: String, index: FrameIndex) -> Result<(), String> {
+    let Some(sender) = running_sender() else {
+        return Ok(());
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::SetFrameIndex { file_path, index, response: response_sender })
+        .map_err(|e| format!("Failed to send frame index command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+// Start the audio thread and return its sender once the output device is open
+fn spawn() -> Result<mpsc::Sender<AudioCommand>, String> {
+    let (sender, receiver) = mpsc::channel::<AudioCommand>();
+    let (ready_sender, ready_receiver) = mpsc::channel::<Result<(), String>>();
+    
+    thread::spawn(move || {
+        println!("THREAD: Starting dedicated audio thread");
+        let audio_manager = match AudioManager::with_output_settings(settings::output_settings()) {
+            Ok(manager) => {
+                println!("THREAD: Audio manager created successfully");
+                manager.set_preserve_pitch(settings::preserve_pitch());
+                manager.set_skip_bad_tracks(settings::skip_bad_chapters());
+                manager.set_ducking(settings::ducking());
+                *LIVE_STATUS.lock().unwrap() = Some(manager.live_status());
+                let _ = ready_sender.send(Ok(()));
+                manager
+            }
+            Err(e) => {
+                eprintln!("THREAD: Failed to create audio manager: {}", e);
+                let _ = ready_sender.send(Err(format!("{:#}", e)));
+                return;
+            }
+        };
+
+        let mut sleep_inhibitor = power::SleepInhibitor::new();
+        let mut seek_history = SeekHistory::new();
+        let mut stall_watchdog = StallWatchdog::new();
+
+        // Main audio thread loop with error recovery
+        for command in receiver {
+            // Wrap each command in a catch_unwind to prevent thread crashes
+            let panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                match command {
+                    AudioCommand::LoadFile { file_path, response } => {
+                        println!("THREAD: Loading file: {}", file_path);
+                        record_seek_origin(&mut seek_history, &audio_manager);
+                        // Stop any existing audio first
+                        audio_manager.stop();
+
+                        let track = Track {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            file_path: file_path.clone(),
+                            title: None,
+                            duration: None,
+                            audiobook_id: None,
+                        };
+
+                        // Just load the track, don't play it automatically
+                        // The frontend will call play() separately when ready
+                        let result = audio_manager.play_track_immediately(track)
+                            .map_err(|e| {
+                                eprintln!("THREAD: Failed to load track: {}", e);
+                                e.to_string()
+                            });
+                        if result.is_ok() {
+                            emit_playback_event(PlaybackEvent::Loaded { file_path: file_path.clone() });
+                        }
+
+                        if let Err(send_err) = response.send(result) {
+                            eprintln!("THREAD: Failed to send response: {:?}", send_err);
+                        }
+                    }
+                    AudioCommand::Play { response } => {
+                        println!("THREAD: Playing");
+                        let result = audio_manager.play().map_err(|e| e.to_string());
+                        if result.is_ok() {
+                            emit_playback_event(PlaybackEvent::Started { position: audio_manager.get_status().position });
+                        }
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::Pause { response } => {
+                        println!("THREAD: Pausing");
+                        audio_manager.pause();
+                        emit_playback_event(PlaybackEvent::Paused { position: audio_manager.get_status().position });
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::Stop { response } => {
+                        println!("THREAD: Stopping");
+                        record_seek_origin(&mut seek_history, &audio_manager);
+                        let position = audio_manager.get_status().position;
+                        audio_manager.stop();
+                        emit_playback_event(PlaybackEvent::Stopped { position });
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetVolume { volume, response } => {
+                        println!("THREAD: Setting volume: {}", volume);
+                        audio_manager.set_volume(volume);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetSpeed { speed, response } => {
+                        println!("THREAD: Setting speed: {}", speed);
+                        audio_manager.set_speed(speed);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::Seek { position, response } => {
+                        println!("THREAD: Seeking to: {}", position);
+                        record_seek_origin(&mut seek_history, &audio_manager);
+                        let result = audio_manager.seek(position).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::GetStatus { response } => {
+                        let status = audio_manager.get_status();
+                        let _ = response.send(status);
+                    }
+                    AudioCommand::AddToQueue { track, response } => {
+                        println!("THREAD: Adding to queue: {}", track.file_path);
+                        audio_manager.add_to_queue(track);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::PlayNext { response } => {
+                        println!("THREAD: Playing next");
+                        record_seek_origin(&mut seek_history, &audio_manager);
+                        let result = audio_manager.skip_to_next().map_err(|e| e.to_string());
+                        if let Ok(true) = result {
+                            if let Some(file_path) = audio_manager.get_status().current_file {
+                                emit_playback_event(PlaybackEvent::Loaded { file_path });
+                                emit_playback_event(PlaybackEvent::Started { position: 0 });
+                            }
+                        }
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::ClearQueue { response } => {
+                        println!("THREAD: Clearing queue");
+                        audio_manager.clear_queue();
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::GetQueue { response } => {
+                        let queue = audio_manager.get_queue();
+                        let _ = response.send(queue);
+                    }
+                    AudioCommand::LoadQueue { tracks, response } => {
+                        println!("THREAD: Loading queue of {} tracks", tracks.len());
+                        record_seek_origin(&mut seek_history, &audio_manager);
+                        let result = audio_manager.load_queue(tracks).map_err(|e| e.to_string());
+                        if result.is_ok() {
+                            if let Some(file_path) = audio_manager.get_status().current_file {
+                                emit_playback_event(PlaybackEvent::Loaded { file_path });
+                            }
+                        }
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::ReplaceQueue { tracks, response } => {
+                        println!("THREAD: Replacing queue with {} tracks", tracks.len());
+                        audio_manager.replace_queue(tracks);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetPreservePitch { enabled, response } => {
+                        println!("THREAD: Setting pitch preservation: {}", enabled);
+                        audio_manager.set_preserve_pitch(enabled);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetSkipBadChapters { enabled, response } => {
+                        println!("THREAD: Skipping chapters that fail to load: {}", enabled);
+                        audio_manager.set_skip_bad_tracks(enabled);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetStopAfter { chapters, response } => {
+                        println!("THREAD: Stopping after {:?} chapters", chapters);
+                        audio_manager.set_stop_after(chapters);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetOutputSettings { settings, response } => {
+                        println!("THREAD: Applying output settings: {:?}", settings);
+                        let result = audio_manager.set_output_settings(settings).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::GetOutputDiagnostics { response } => {
+                        let _ = response.send(audio_manager.output_diagnostics());
+                    }
+                    AudioCommand::GetMetrics { response } => {
+                        let _ = response.send(audio_manager.metrics());
+                    }
+                    AudioCommand::StartPreview { file_path, response } => {
+                        println!("THREAD: Previewing: {}", file_path);
+                        let limit = std::time::Duration::from_secs(download::preview::PREVIEW_SECONDS);
+                        let result = audio_manager.start_preview(&file_path, limit).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::StopPreview { response } => {
+                        println!("THREAD: Stopping preview");
+                        audio_manager.stop_preview();
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetAmbience { ambience, volume, response } => {
+                        println!("THREAD: Setting ambience: {:?} at {}", ambience, volume);
+                        let result = audio_manager.set_ambience(ambience, volume).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::SetFrameIndex { file_path, index, response } => {
+                        if audio_manager.set_frame_index(&file_path, index) {
+                            println!("THREAD: Seeking {} through its frame index", file_path);
+                        }
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::GetAmbience { response } => {
+                        let _ = response.send(audio_manager.ambience_status());
+                    }
+                    AudioCommand::SetDucking { settings, response } => {
+                        println!("THREAD: Setting ambience ducking: {:?}", settings);
+                        audio_manager.set_ducking(settings);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetFocusGain { gain, response } => {
+                        println!("THREAD: Setting focus gain: {}", gain);
+                        audio_manager.set_focus_gain(gain);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::SetVoiceBoost { settings, response } => {
+                        println!("THREAD: Setting voice boost: {:?}", settings);
+                        audio_manager.set_voice_boost(settings);
+                        let _ = response.send(Ok(()));
+                    }
+                    AudioCommand::HandleSystemResume { position, response } => {
+                        println!("THREAD: System resumed, pausing and reopening output");
+                        let was_playing = matches!(audio_manager.get_status().state, PlaybackState::Playing);
+                        // Position tracking ran on across the sleep; go back to where it stopped
+                        if let Some(position) = position {
+                            if let Err(e) = audio_manager.seek(position as f32) {
+                                log::warn!("Failed to restore position after sleep: {}", e);
+                            }
+                        }
+                        if was_playing {
+                            audio_manager.pause();
+                            emit_playback_event(PlaybackEvent::Paused { position: audio_manager.get_status().position });
+                        }
+                        // The device may have changed or disappeared while asleep
+                        let result = audio_manager.set_output_settings(settings::output_settings()).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::PopSeekHistory { response } => {
+                        let _ = response.send(seek_history.pop(std::time::Instant::now()));
+                    }
+                    AudioCommand::GetSeekHistory { response } => {
+                        let _ = response.send(seek_history.entries());
+                    }
+                    AudioCommand::GetPlayerState { response } => {
+                        let _ = response.send(capture_player_state(&audio_manager));
+                    }
+                    AudioCommand::RestorePlayerState { state, response } => {
+                        println!("THREAD: Restoring player state at {}s in {}", state.position, state.current.file_path);
+                        let result = restore_player_state(&audio_manager, state).map_err(|e| e.to_string());
+                        let _ = response.send(result);
+                    }
+                    AudioCommand::CheckOutput => {}
+                }
+
+                // The frontend polls status twice a second while playing, so bursts of
+                // underruns are noticed promptly
+                if let Some(frames) = audio_manager.recover_from_underruns() {
+                    println!("THREAD: Underruns detected, output buffer raised to {} frames", frames);
+                    settings::set_output_buffer_frames(frames);
+                }
+
+                // Status polling also catches playback reaching the end on its own.
+                // The next queued track follows on; once the queue runs out the
+                // recorder decides whether the book is over. When the stop-after
+                // limit is reached the next track is loaded but left stopped, so
+                // playback resumes at the start of the next chapter.
+                if let Some(position) = audio_manager.take_finished() {
+                    let finished_file = audio_manager.get_status().current_file;
+                    match audio_manager.advance() {
+                        Ok(Advance::StopAfter) => {
+                            println!("THREAD: Reached the stop-after limit, not starting the next track");
+                            emit_playback_event(PlaybackEvent::Stopped { position });
+                            if let Some(file_path) = audio_manager.get_status().current_file {
+                                emit_playback_event(PlaybackEvent::Loaded { file_path });
+                            }
+                        }
+                        Ok(Advance::Next) => {
+                            emit_playback_event(PlaybackEvent::Stopped { position });
+                            if let Some(file_path) = audio_manager.get_status().current_file {
+                                emit_playback_event(PlaybackEvent::Loaded { file_path });
+                            }
+                            match audio_manager.play() {
+                                Ok(()) => emit_playback_event(PlaybackEvent::Started { position: 0 }),
+                                Err(e) => log::warn!("Failed to start next queued track: {}", e),
+                            }
+                        }
+                        Ok(Advance::QueueEnded) => {
+                            if let Some(file_path) = finished_file {
+                                emit_playback_event(PlaybackEvent::Finished { file_path, position });
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("Failed to load next queued track: {}", e);
+                            emit_playback_event(PlaybackEvent::Stopped { position });
+                        }
+                    }
+                }
+                report_track_errors(&audio_manager);
+                check_for_stall(&mut stall_watchdog, &audio_manager);
+
+                let playing = matches!(audio_manager.get_status().state, PlaybackState::Playing);
+                sleep_inhibitor.set_active(playing && settings::keep_awake());
+                throttle::set_playback_active(playing);
+            }));
+
+            if let Err(panic_err) = panic_result {
+                eprintln!("THREAD: Audio thread panic caught and recovered: {:?}", panic_err);
+                // Thread continues running despite the panic
+                // The command's response channel may have been consumed in the panic,
+                // but future commands will still work
+            }
+        }
+        println!("THREAD: Audio thread ending");
+    });
+    
+    ready_receiver.recv()
+        .map_err(|_| "Audio thread exited during startup".to_string())??;
+
+    // Commands only arrive while something polls, so the watchdog gets its own
+    let ticker = sender.clone();
+    thread::spawn(move || loop {
+        thread::sleep(watchdog::CHECK_INTERVAL);
+        if ticker.send(AudioCommand::CheckOutput).is_err() {
+            break;
+        }
+    });
+    Ok(sender)
+}
+
+// Report a decoder stall, and once the grace period has passed, load the file
+// again where the audio stopped
+fn check_for_stall(watchdog: &mut StallWatchdog, audio_manager: &AudioManager) {
+    let status = audio_manager.get_status();
+    let sample = OutputSample {
+        file_path: status.current_file,
+        playing: matches!(status.state, PlaybackState::Playing),
+        position: status.position,
+        output: audio_manager.output_position(),
+    };
+    let grace = match settings::stall_grace_seconds() {
+        0 => None,
+        seconds => Some(std::time::Duration::from_secs(seconds)),
+    };
+    match watchdog.observe(sample, grace, std::time::Instant::now()) {
+        Some(WatchdogAction::Stalled(stall)) => {
+            log::warn!("Playback stalled in {} at {}s", stall.file_path, stall.position);
+            events::emit(AppEvent::PlaybackStalled(stall));
+        }
+        Some(WatchdogAction::Recover { file_path, position }) => {
+            log::warn!("Reloading {} at {}s after a stall", file_path, position);
+            match audio_manager.reload_current(position) {
+                Ok(()) => {
+                    emit_playback_event(PlaybackEvent::Loaded { file_path });
+                    emit_playback_event(PlaybackEvent::Started { position });
+                }
+                Err(e) => log::warn!("Failed to reload {} after a stall: {}", file_path, e),
+            }
+        }
+        None => {}
+    }
+}
+
+// Remember the current place before a seek, stop or file change so it can be undone
+fn record_seek_origin(history: &mut SeekHistory, audio_manager: &AudioManager) {
+    let status = audio_manager.get_status();
+    if let Some(file_path) = status.current_file {
+        history.record(&file_path, status.position, std::time::Instant::now());
+    }
+}
+
+// Everything needed to put the player back as it is; None with nothing loaded.
+// The book and chapter are filled in by the caller from the database.
+fn capture_player_state(audio_manager: &AudioManager) -> Option<PlayerState> {
+    let current = audio_manager.get_current_track()?;
+    let status = audio_manager.get_status();
+    let ambience = audio_manager.ambience_status();
+    Some(PlayerState {
+        current,
+        position: status.position,
+        queue: audio_manager.get_queue(),
+        speed: status.speed,
+        volume: status.volume,
+        ambience: ambience.track,
+        ambience_volume: ambience.volume,
+        audiobook_id: None,
+        chapter_id: None,
+        saved_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+// Load the saved tracks paused at the saved place, with the saved speed, volume
+// and ambience. The position only applies if the saved track itself loaded.
+fn restore_player_state(audio_manager: &AudioManager, state: PlayerState) -> anyhow::Result<()> {
+    audio_manager.set_volume(state.volume);
+    audio_manager.set_speed(state.speed);
+    if let Some(track) = &state.ambience {
+        match Ambience::from_track(track) {
+            Ok(ambience) => audio_manager.set_ambience(Some(ambience), state.ambience_volume)?,
+            Err(e) => log::warn!("Not restoring ambience {}: {}", track, e),
+        }
+    }
+
+    let tracks = state.tracks();
+    if tracks.is_empty() {
+        return Err(anyhow::anyhow!("None of the saved tracks exist any more"));
+    }
+    audio_manager.load_queue(tracks)?;
+    let current_file = audio_manager.get_status().current_file;
+    if let Some(file_path) = &current_file {
+        emit_playback_event(PlaybackEvent::Loaded { file_path: file_path.clone() });
+    }
+    if current_file.as_deref() == Some(state.current.file_path.as_str()) && state.position > 0 {
+        audio_manager.seek(state.position as f32)?;
+    }
+    Ok(())
+}
+
+/// The audio thread's sender, starting the thread with the configured device if
+/// init_audio has not run yet
+pub fn sender() -> Result<mpsc::Sender<AudioCommand>, String> {
+    let mut audio_sender = SENDER.lock().unwrap();
+    if let Some(sender) = audio_sender.as_ref() {
+        return Ok(sender.clone());
+    }
+
+    println!("INIT: Initializing audio thread");
+    let sender = spawn()
+        .map_err(|e| format!("Audio output unavailable: {}", e))?;
+    *audio_sender = Some(sender.clone());
+    Ok(sender)
+}
+
+/// The audio thread's sender, without starting it
+pub fn running_sender() -> Option<mpsc::Sender<AudioCommand>> {
+    SENDER.lock().unwrap().clone()
+}
+
+/// Ask the audio thread what it is playing
+pub fn status(sender: &mpsc::Sender<AudioCommand>) -> Result<PlaybackStatus, String> {
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: response_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
@@ -0,0 +1,241 @@
+// "Voice boost": a compressor followed by a limiter on the narration, for noisy
+// places like cars. Quiet passages come up, loud ones stay below clipping.
+// A single amount from 0 to 1 sets threshold, ratio and makeup gain together.
+
+use rodio::source::SeekError;
+use rodio::{ChannelCount, Sample, SampleRate, Source};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use ts_rs::TS;
+
+const ATTACK_MS: f32 = 5.0;
+const RELEASE_MS: f32 = 120.0;
+const LIMITER_RELEASE_MS: f32 = 50.0;
+/// Output never goes above this (about -0.5 dBFS)
+const CEILING: f32 = 0.95;
+/// Envelope floor so silence does not produce -inf dB
+const MIN_LEVEL: f32 = 1e-5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct VoiceBoostSettings {
+    pub enabled: bool,
+    /// 0 is gentle, 1 is as flat as it goes
+    pub amount: f32,
+}
+
+impl Default for VoiceBoostSettings {
+    fn default() -> Self {
+        Self { enabled: false, amount: 0.5 }
+    }
+}
+
+impl VoiceBoostSettings {
+    pub fn clamped(self) -> Self {
+        let amount = if self.amount.is_finite() { self.amount.clamp(0.0, 1.0) } else { Self::default().amount };
+        Self { enabled: self.enabled, amount }
+    }
+}
+
+/// Shared between the engine and the source inside the sink
+#[derive(Debug)]
+pub struct VoiceBoostControl {
+    enabled: AtomicBool,
+    amount_bits: AtomicU32,
+}
+
+impl VoiceBoostControl {
+    pub fn new(settings: VoiceBoostSettings) -> Self {
+        let control = Self { enabled: AtomicBool::new(false), amount_bits: AtomicU32::new(0) };
+        control.apply(settings);
+        control
+    }
+
+    pub fn apply(&self, settings: VoiceBoostSettings) {
+        let settings = settings.clamped();
+        self.amount_bits.store(settings.amount.to_bits(), Ordering::Relaxed);
+        self.enabled.store(settings.enabled, Ordering::Relaxed);
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn amount(&self) -> f32 {
+        f32::from_bits(self.amount_bits.load(Ordering::Relaxed))
+    }
+}
+
+/// Compressor curve for an amount
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Curve {
+    threshold_db: f32,
+    ratio: f32,
+    makeup_db: f32,
+}
+
+impl Curve {
+    fn for_amount(amount: f32) -> Self {
+        let threshold_db = -12.0 - 18.0 * amount;
+        let ratio = 2.0 + 4.0 * amount;
+        // Most of the level taken off the loudest parts is given back to everything
+        let makeup_db = -threshold_db * (1.0 - 1.0 / ratio) * 0.7;
+        Self { threshold_db, ratio, makeup_db }
+    }
+
+    fn gain(&self, level: f32) -> f32 {
+        let level_db = 20.0 * level.max(MIN_LEVEL).log10();
+        let reduction_db = if level_db > self.threshold_db {
+            (self.threshold_db + (level_db - self.threshold_db) / self.ratio) - level_db
+        } else {
+            0.0
+        };
+        10f32.powf((reduction_db + self.makeup_db) / 20.0)
+    }
+}
+
+fn coefficient(time_ms: f32, sample_rate: SampleRate, channels: ChannelCount) -> f32 {
+    let samples = time_ms / 1000.0 * sample_rate as f32 * channels.max(1) as f32;
+    if samples <= 1.0 { 0.0 } else { (-1.0 / samples).exp() }
+}
+
+pub struct VoiceBoost<S: Source> {
+    inner: S,
+    control: Arc<VoiceBoostControl>,
+    curve: Curve,
+    curve_amount: f32,
+    envelope: f32,
+    limiter_gain: f32,
+    attack: f32,
+    release: f32,
+    limiter_release: f32,
+}
+
+impl<S: Source> VoiceBoost<S> {
+    pub fn new(inner: S, control: Arc<VoiceBoostControl>) -> Self {
+        let (rate, channels) = (inner.sample_rate(), inner.channels());
+        let amount = control.amount();
+        Self {
+            curve: Curve::for_amount(amount),
+            curve_amount: amount,
+            envelope: 0.0,
+            limiter_gain: 1.0,
+            attack: coefficient(ATTACK_MS, rate, channels),
+            release: coefficient(RELEASE_MS, rate, channels),
+            limiter_release: coefficient(LIMITER_RELEASE_MS, rate, channels),
+            inner,
+            control,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.limiter_gain = 1.0;
+    }
+}
+
+impl<S: Source> Iterator for VoiceBoost<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let sample = self.inner.next()?;
+        if !self.control.enabled() {
+            self.reset();
+            return Some(sample);
+        }
+
+        let amount = self.control.amount();
+        if amount != self.curve_amount {
+            self.curve = Curve::for_amount(amount);
+            self.curve_amount = amount;
+        }
+
+        let level = sample.abs();
+        let coefficient = if level > self.envelope { self.attack } else { self.release };
+        self.envelope = level + (self.envelope - level) * coefficient;
+        let compressed = sample * self.curve.gain(self.envelope);
+
+        // Limiter: clamp at once when a peak would clip, ease back afterwards
+        self.limiter_gain = 1.0 + (self.limiter_gain - 1.0) * self.limiter_release;
+        if compressed.abs() * self.limiter_gain > CEILING {
+            self.limiter_gain = CEILING / compressed.abs();
+        }
+        Some(compressed * self.limiter_gain)
+    }
+}
+
+impl<S: Source> Source for VoiceBoost<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.inner.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)?;
+        self.reset();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    const RATE: u32 = 8000;
+
+    fn sine(amplitude: f32, seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| (2.0 * std::f32::consts::PI * 200.0 * i as f32 / RATE as f32).sin() * amplitude)
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn test_voice_boost_narrows_dynamics_without_clipping() {
+        // A whispered second followed by a shouted one
+        let mut input = sine(0.05, 1.0);
+        input.extend(sine(1.0, 1.0));
+        let half = input.len() / 2;
+
+        let control = Arc::new(VoiceBoostControl::new(VoiceBoostSettings { enabled: false, amount: 0.8 }));
+        let bypass: Vec<f32> = VoiceBoost::new(SamplesBuffer::new(1, RATE, input.clone()), control.clone()).collect();
+        assert_eq!(bypass, input, "disabled is a pass-through");
+
+        control.apply(VoiceBoostSettings { enabled: true, amount: 0.8 });
+        let output: Vec<f32> = VoiceBoost::new(SamplesBuffer::new(1, RATE, input.clone()), control).collect();
+        // Skip the first 100 ms of each half while the envelope settles
+        let settle = RATE as usize / 10;
+        let quiet = peak(&output[settle..half]);
+        let loud = peak(&output[half + settle..]);
+        assert!(quiet > 0.05 * 2.0, "quiet passage boosted, got {}", quiet);
+        assert!(loud / quiet < 1.0 / 0.05 / 2.0, "range narrowed: {} vs {}", loud, quiet);
+        assert!(peak(&output) <= CEILING + 1e-6, "never clips, peak {}", peak(&output));
+    }
+
+    #[test]
+    fn test_voice_boost_amount_is_clamped() {
+        assert_eq!(VoiceBoostSettings { enabled: true, amount: 3.0 }.clamped().amount, 1.0);
+        assert_eq!(VoiceBoostSettings { enabled: true, amount: f32::NAN }.clamped().amount, 0.5);
+        let gentle = Curve::for_amount(0.0);
+        let strong = Curve::for_amount(1.0);
+        assert!(strong.threshold_db < gentle.threshold_db && strong.ratio > gentle.ratio);
+        assert!(strong.gain(0.01) > gentle.gain(0.01), "more amount lifts quiet speech more");
+    }
+}
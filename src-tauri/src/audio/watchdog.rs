@@ -0,0 +1,193 @@
+// Decoder stalls: the sink says it is playing and the position clock runs on,
+// but no audio comes out because the source has stopped producing samples.
+// The audio thread samples how far the output has really played every
+// CHECK_INTERVAL; when that stops moving while playing, the stall is reported
+// and, after a grace period, the file is loaded again where audio stopped.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// How often the audio thread checks on the output
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// No output for this long while playing is a stall
+pub const STALL_AFTER: Duration = Duration::from_secs(3);
+/// Time after a stall is reported before the file is reloaded; a setting of 0
+/// turns recovery off
+pub const DEFAULT_GRACE_SECONDS: u64 = 5;
+pub const MAX_GRACE_SECONDS: u64 = 300;
+/// Reloads tried per file before leaving it to the listener
+const MAX_RECOVERIES: u32 = 3;
+
+/// Sent as the playback-stalled event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlaybackStall {
+    pub file_path: String,
+    /// Where audio stopped, in seconds
+    #[ts(type = "number")]
+    pub position: u64,
+    #[ts(type = "number")]
+    pub stalled_seconds: u64,
+    /// Seconds until the file is reloaded, or None when it will not be
+    #[ts(type = "number | null")]
+    pub recovery_in_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchdogAction {
+    Stalled(PlaybackStall),
+    /// Load `file_path` again and play on from `position`
+    Recover { file_path: String, position: u64 },
+}
+
+/// What the audio thread sees on each check
+#[derive(Debug, Clone)]
+pub struct OutputSample {
+    pub file_path: Option<String>,
+    pub playing: bool,
+    /// The position clock, in seconds
+    pub position: u64,
+    /// How far the sink has played the source; None once it has run out
+    pub output: Option<Duration>,
+}
+
+/// The last time output moved
+#[derive(Debug, Clone)]
+struct Progress {
+    file_path: String,
+    output: Duration,
+    position: u64,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct StallWatchdog {
+    last: Option<Progress>,
+    reported: bool,
+    recoveries: u32,
+}
+
+impl StallWatchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check one sample; `grace` is how long to wait after reporting a stall
+    /// before recovering, None to only report
+    pub fn observe(&mut self, sample: OutputSample, grace: Option<Duration>, now: Instant) -> Option<WatchdogAction> {
+        let (Some(file_path), true, Some(output)) = (sample.file_path, sample.playing, sample.output) else {
+            // Paused, stopped or played out: nothing is expected to come out
+            self.last = None;
+            self.reported = false;
+            return None;
+        };
+
+        let Some(last) = self.last.as_mut().filter(|last| last.file_path == file_path) else {
+            if self.last.is_some() {
+                self.recoveries = 0;
+            }
+            self.last = Some(Progress { file_path, output, position: sample.position, at: now });
+            self.reported = false;
+            return None;
+        };
+        // Moving on, or a seek back, starts the wait again
+        if output != last.output {
+            *last = Progress { file_path, output, position: sample.position, at: now };
+            self.reported = false;
+            return None;
+        }
+
+        let stalled_for = now.duration_since(last.at);
+        if stalled_for < STALL_AFTER {
+            return None;
+        }
+        let grace = grace.filter(|_| self.recoveries < MAX_RECOVERIES);
+        if !self.reported {
+            self.reported = true;
+            return Some(WatchdogAction::Stalled(PlaybackStall {
+                file_path,
+                position: last.position,
+                stalled_seconds: stalled_for.as_secs(),
+                recovery_in_seconds: grace.map(|grace| grace.as_secs()),
+            }));
+        }
+        let grace = grace?;
+        if stalled_for < STALL_AFTER + grace {
+            return None;
+        }
+
+        self.recoveries += 1;
+        let position = last.position;
+        // Give the reloaded file the full wait before it can stall again
+        last.at = now;
+        self.reported = false;
+        Some(WatchdogAction::Recover { file_path, position })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(output: u64, position: u64) -> OutputSample {
+        OutputSample {
+            file_path: Some("/books/emma/01.mp3".to_string()),
+            playing: true,
+            position,
+            output: Some(Duration::from_secs(output)),
+        }
+    }
+
+    #[test]
+    fn test_stall_is_reported_then_recovered() {
+        let mut watchdog = StallWatchdog::new();
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let grace = Some(Duration::from_secs(DEFAULT_GRACE_SECONDS));
+
+        assert_eq!(watchdog.observe(sample(10, 10), grace, at(0)), None);
+        assert_eq!(watchdog.observe(sample(11, 11), grace, at(1)), None);
+        // The clock runs on but the output does not
+        assert_eq!(watchdog.observe(sample(11, 13), grace, at(3)), None);
+        let Some(WatchdogAction::Stalled(stall)) = watchdog.observe(sample(11, 15), grace, at(5)) else {
+            panic!("stall not reported");
+        };
+        assert_eq!((stall.position, stall.stalled_seconds, stall.recovery_in_seconds), (11, 4, Some(5)));
+        assert_eq!(watchdog.observe(sample(11, 17), grace, at(7)), None, "reported once");
+        assert_eq!(
+            watchdog.observe(sample(11, 20), grace, at(10)),
+            Some(WatchdogAction::Recover { file_path: "/books/emma/01.mp3".to_string(), position: 11 })
+        );
+        assert_eq!(watchdog.observe(sample(12, 12), grace, at(11)), None, "playing again");
+    }
+
+    #[test]
+    fn test_pauses_and_limits_are_respected() {
+        let mut watchdog = StallWatchdog::new();
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+
+        watchdog.observe(sample(10, 10), None, at(0));
+        let paused = OutputSample { playing: false, ..sample(10, 10) };
+        assert_eq!(watchdog.observe(paused, None, at(60)), None);
+        assert_eq!(watchdog.observe(sample(10, 10), None, at(61)), None, "the wait starts over after a pause");
+
+        // Without a grace period the stall is only reported
+        let Some(WatchdogAction::Stalled(stall)) = watchdog.observe(sample(10, 14), None, at(65)) else {
+            panic!("stall not reported");
+        };
+        assert_eq!(stall.recovery_in_seconds, None);
+        assert_eq!(watchdog.observe(sample(10, 74), None, at(120)), None);
+
+        // Reloads stop after MAX_RECOVERIES on the same file
+        let grace = Some(Duration::ZERO);
+        let mut recoveries = 0;
+        for second in 121..200 {
+            if let Some(WatchdogAction::Recover { .. }) = watchdog.observe(sample(10, 10), grace, at(second)) {
+                recoveries += 1;
+            }
+        }
+        assert_eq!(recoveries, MAX_RECOVERIES);
+    }
+}
@@ -0,0 +1,44 @@
+// Progress synced through a folder the user shares between machines. Syncs are
+// serialized, so a manual sync never overlaps the periodic one.
+
+use crate::events::{self, AppEvent};
+use crate::services::{folder_sync_service, FolderSyncReport, FolderSyncService};
+use crate::storage;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FOLDER_SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Sync progress through the user's synced folder at startup and every few
+/// minutes after. Like the inbox, the folder is read from preferences each round.
+pub fn start(pool: sqlx::SqlitePool) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let folder = FolderSyncService::new(&pool).folder().await.ok().flatten();
+            // A folder on a drive that is not mounted is skipped, not created
+            if let Some(folder) = folder.filter(|folder| folder.is_dir()) {
+                if let Err(e) = run(&pool, &folder).await {
+                    log::warn!("SYNC: Failed to sync with {}: {:#}", folder.display(), e);
+                }
+            }
+            tokio::time::sleep(folder_sync_service::SYNC_INTERVAL).await;
+        }
+    });
+}
+
+pub async fn run(pool: &sqlx::SqlitePool, folder: &Path) -> anyhow::Result<FolderSyncReport> {
+    let _guard = FOLDER_SYNC_LOCK.lock().await;
+    let report = FolderSyncService::new(pool).sync(folder, &storage::paths().download_cache_dir()).await?;
+    for error in &report.errors {
+        log::warn!("SYNC: {}", error);
+    }
+    if !report.books_updated.is_empty() || report.bookmarks_added > 0 || report.collections_created > 0 || report.collection_books_added > 0 {
+        events::emit(AppEvent::FolderSyncApplied(report.clone()));
+    }
+    Ok(report)
+}
@@ -0,0 +1,122 @@
+// Scheduled maintenance: cache eviction, backfills, backups, retention and the
+// feeds refreshed in the background. Runs are serialized, so a manual run never
+// overlaps a scheduled one.
+
+use crate::covers;
+use crate::database::repository::PreferencesRepository;
+use crate::events::{self, AppEvent};
+use crate::services::librivox_release_service::{self, ReleaseFetch};
+use crate::services::{maintenance_service, DigestService, FollowService, LibrivoxReleaseService, MaintenanceService, MaintenanceTask, RecommendationService, RetentionService, TaskRun};
+use crate::storage;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static MAINTENANCE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Run due maintenance tasks in the background. The first check waits a few
+/// minutes so startup is left alone.
+pub fn start_scheduler(pool: sqlx::SqlitePool) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(5 * 60)).await;
+        loop {
+            for task in MaintenanceService::new(&pool).due_tasks(chrono::Utc::now()).await {
+                run(&pool, task).await;
+            }
+            tokio::time::sleep(maintenance_service::TICK_INTERVAL).await;
+        }
+    });
+}
+
+/// Run one maintenance task and record how it went
+pub async fn run(pool: &sqlx::SqlitePool, task: MaintenanceTask) -> TaskRun {
+    let _guard = MAINTENANCE_LOCK.lock().await;
+    let started_at = chrono::Utc::now();
+    let started = Instant::now();
+    let result = run_task(pool, task).await;
+
+    let run = TaskRun {
+        started_at: started_at.to_rfc3339(),
+        duration_ms: started.elapsed().as_millis() as u64,
+        success: result.is_ok(),
+        message: result.unwrap_or_else(|e| e.to_string()),
+    };
+    if run.success {
+        log::info!("MAINTENANCE: {} finished: {}", task.key(), run.message);
+    } else {
+        log::warn!("MAINTENANCE: {} failed: {}", task.key(), run.message);
+    }
+    if let Err(e) = MaintenanceService::new(pool).record_run(task, &run).await {
+        log::warn!("Failed to record maintenance run: {}", e);
+    }
+    events::emit(AppEvent::MaintenanceTaskFinished { task, run: run.clone() });
+    run
+}
+
+async fn run_task(pool: &sqlx::SqlitePool, task: MaintenanceTask) -> anyhow::Result<String> {
+    let service = MaintenanceService::new(pool);
+    match task {
+        MaintenanceTask::CacheEviction => {
+            let cache_dir = storage::paths().covers_dir().join("sized");
+            let (removed, freed) = tauri::async_runtime::spawn_blocking(move || {
+                covers::evict_cache(&cache_dir, covers::CACHE_MAX_BYTES)
+            })
+            .await??;
+            Ok(format!("Evicted {} cached covers ({} bytes)", removed, freed))
+        }
+        MaintenanceTask::DurationBackfill => service.backfill_durations().await,
+        MaintenanceTask::OrphanCleanup => service.remove_orphans(&storage::paths().covers_dir()).await,
+        MaintenanceTask::FeedRefresh => {
+            let recommendations = RecommendationService::new(pool).generate_recommendations(Some(20)).await?;
+            events::emit(AppEvent::RecommendationsRefreshed { count: recommendations.len() });
+            Ok(format!("Generated {} recommendations", recommendations.len()))
+        }
+        MaintenanceTask::Backup => service.backup_database(&storage::paths().backups_dir(), chrono::Utc::now()).await,
+        MaintenanceTask::Retention => {
+            let report = RetentionService::new(pool).enforce(chrono::Utc::now()).await?;
+            Ok(format!(
+                "Summarized {} sessions and {} plays, deleted {} recommendations and {} playback states",
+                report.sessions_summarized, report.plays_summarized, report.recommendations_deleted, report.playback_states_deleted
+            ))
+        }
+        MaintenanceTask::LibrivoxReleases => {
+            let fetch = fetch_librivox_releases(pool).await?;
+            Ok(format!("Found {} new LibriVox releases, dropped {} old ones", fetch.new_releases.len(), fetch.pruned))
+        }
+        MaintenanceTask::WeeklyDigest => {
+            let digest = DigestService::new(pool).compile(chrono::Utc::now()).await?;
+            let message = format!(
+                "Compiled the weekly digest: {} minutes listened, {} books finished, {} new releases, {} stalled books",
+                digest.listened_seconds / 60,
+                digest.books_finished.len(),
+                digest.new_releases.len(),
+                digest.stalled.len()
+            );
+            events::emit(AppEvent::WeeklyDigestReady(digest));
+            Ok(message)
+        }
+    }
+}
+
+/// Refresh the LibriVox release cache, raise alerts for followed authors and
+/// genres, and when library alerts are on, tell the frontend about new
+/// recordings by authors in the library
+pub async fn fetch_librivox_releases(pool: &sqlx::SqlitePool) -> anyhow::Result<ReleaseFetch> {
+    let fetch = LibrivoxReleaseService::new(pool).fetch(chrono::Utc::now()).await?;
+    let followed = FollowService::new(pool).record_alerts(&fetch.new_releases).await?;
+    if !followed.is_empty() {
+        events::emit(AppEvent::FollowedReleaseAvailable(followed));
+    }
+    let alerts = PreferencesRepository::new(pool)
+        .get_bool(librivox_release_service::PREF_RELEASE_ALERTS, false)
+        .await
+        .unwrap_or(false);
+    if alerts && !fetch.by_known_authors.is_empty() {
+        events::emit(AppEvent::NewLibrivoxReleases(fetch.by_known_authors.clone()));
+    }
+    Ok(fetch)
+}
@@ -0,0 +1,211 @@
+// Tasks started once the database is open that run for the life of the app:
+// recorders fed by the audio thread, watchers and schedulers. Each start function
+// may be called again when the frontend reloads and only starts its task once.
+
+use crate::audio::{self, books, thread};
+use crate::commands::{with_pool, AppState};
+use crate::events::{self, AppEvent, LibraryChange};
+use crate::import;
+use crate::services::{player_state_service, volume_service, BookPositionService, ChapterErrorEvent, ChapterErrorService, EndOfBookService, OfflineVolume, PlayHistoryService, PlayerState, PlayerStateService, PlaySessionTracker, PlaybackEvent, VolumeService};
+use crate::{inbox, media_session, power, sharing};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod folder_sync;
+pub mod maintenance;
+pub mod warm_up;
+
+/// Start every background task
+pub fn start(app: &tauri::AppHandle, pool: sqlx::SqlitePool) {
+    start_play_history_recorder(pool.clone());
+    start_chapter_error_recorder(pool.clone());
+    import::start_validator(pool.clone());
+    power::start_monitor(pool.clone());
+    audio::focus::start_monitor();
+    inbox::start_watcher(pool.clone());
+    sharing::start_if_enabled(pool.clone());
+    media_session::start(app, pool.clone());
+    folder_sync::start(pool.clone());
+    start_volume_monitor(pool.clone());
+    maintenance::start_scheduler(pool.clone());
+    start_player_state_snapshots(pool);
+}
+
+// Flag chapter files that failed to load so the library can show them, and tell
+// the frontend. The flag is cleared by the play history recorder once the file
+// loads again.
+fn start_chapter_error_recorder(pool: sqlx::SqlitePool) {
+    let Some(mut receiver) = thread::subscribe_track_errors() else {
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(error) = receiver.recv().await {
+            let chapter = match ChapterErrorService::new(&pool).record(&error.file_path, &error.error).await {
+                Ok(chapter) => chapter,
+                Err(e) => {
+                    log::warn!("Failed to flag {} as unplayable: {}", error.file_path, e);
+                    None
+                }
+            };
+            events::emit(AppEvent::ChapterError(ChapterErrorEvent {
+                file_path: error.file_path,
+                error: error.error,
+                skipped: error.skipped,
+                chapter,
+            }));
+        }
+    });
+}
+
+// Consume playback events and turn them into deduplicated rows in the plays table.
+// A newly loaded file also switches voice boost to its book's setting, and a
+// finished one may end its book.
+fn start_play_history_recorder(pool: sqlx::SqlitePool) {
+    let Some(mut receiver) = thread::subscribe_playback_events() else {
+        // Already running from an earlier initialize_app call
+        return;
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut tracker = PlaySessionTracker::new();
+        while let Some(event) = receiver.recv().await {
+            if let PlaybackEvent::Loaded { file_path } = &event {
+                tauri::async_runtime::spawn(books::attach_frame_index(pool.clone(), file_path.clone()));
+                books::apply_book_voice_boost(&pool, file_path).await;
+                if let Err(e) = ChapterErrorService::new(&pool).clear(file_path).await {
+                    log::warn!("Failed to clear chapter error for {}: {}", file_path, e);
+                }
+            }
+            let finished_file = match &event {
+                PlaybackEvent::Finished { file_path, .. } => Some(file_path.clone()),
+                _ => None,
+            };
+            if let Some(play) = tracker.handle(event, chrono::Utc::now()) {
+                if let Err(e) = PlayHistoryService::new(&pool).record_play(&play).await {
+                    log::warn!("Failed to record play for {}: {}", play.file_path, e);
+                }
+            }
+            if let Some(file_path) = finished_file {
+                handle_track_finished(&pool, &file_path).await;
+            }
+        }
+    });
+}
+
+// When the file that played out was the last chapter of its book, apply the
+// end-of-book policy: queue and start whatever it picks, then tell the frontend
+async fn handle_track_finished(pool: &sqlx::SqlitePool, file_path: &str) {
+    let layout = match BookPositionService::new(pool).layout_for_file(file_path).await {
+        Ok(Some(layout)) => layout,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("Failed to look up book for finished file {}: {}", file_path, e);
+            return;
+        }
+    };
+    if !layout.is_last_file(file_path) {
+        return;
+    }
+
+    let finished = match EndOfBookService::new(pool).book_finished(&layout.audiobook_id).await {
+        Ok(finished) => finished,
+        Err(e) => {
+            log::warn!("Failed to apply end-of-book action for {}: {}", layout.audiobook_id, e);
+            return;
+        }
+    };
+    println!("📕 END OF BOOK: {} finished, action {:?}", finished.audiobook_id, finished.action);
+    if let Some(next) = &finished.next_audiobook {
+        if let Err(e) = books::play_book_from_start(pool, &next.id).await {
+            log::warn!("Failed to start {} after {}: {}", next.id, finished.audiobook_id, e);
+        }
+    }
+    events::emit(AppEvent::BookFinished(Box::new(finished)));
+}
+
+/// Snapshot the player every few seconds while the app runs, writing only when
+/// something changed, so a crash loses little more than an exit does
+fn start_player_state_snapshots(pool: sqlx::SqlitePool) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_saved: Option<PlayerState> = None;
+        loop {
+            tokio::time::sleep(player_state_service::SNAPSHOT_INTERVAL).await;
+            let state = match books::current_player_state(&pool).await {
+                Ok(Some(state)) => state,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::warn!("Failed to snapshot player state: {}", e);
+                    continue;
+                }
+            };
+            if last_saved.as_ref().is_some_and(|saved| saved.same_as(&state)) {
+                continue;
+            }
+            match PlayerStateService::new(&pool).save(&state).await {
+                Ok(()) => last_saved = Some(state),
+                Err(e) => log::warn!("Failed to save player state: {}", e),
+            }
+        }
+    });
+}
+
+/// Last snapshot on the way out; runs on exit, after the windows are gone
+pub fn save_player_state_on_exit(app: &tauri::AppHandle) {
+    use tauri::Manager;
+    let state = app.state::<AppState>();
+    tauri::async_runtime::block_on(async {
+        let Ok(pool) = with_pool(&state).await else {
+            return;
+        };
+        match books::current_player_state(&pool).await {
+            Ok(Some(state)) => {
+                if let Err(e) = PlayerStateService::new(&pool).save(&state).await {
+                    log::warn!("Failed to save player state on exit: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to snapshot player state on exit: {}", e),
+        }
+    });
+}
+
+/// Watch the drives books live on. Books on a drive that goes away are reported
+/// offline; when it comes back they are fingerprinted and validated again.
+fn start_volume_monitor(pool: sqlx::SqlitePool) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let mut offline: Vec<OfflineVolume> = Vec::new();
+        loop {
+            match VolumeService::new(&pool).offline_volumes().await {
+                Ok(current) => {
+                    let (went_offline, came_back) = volume_service::changes(&offline, &current);
+                    for volume in went_offline {
+                        println!("💽 VOLUME: {} is not connected ({} books)", volume.path, volume.audiobook_ids.len());
+                        events::emit(AppEvent::VolumeOffline(volume));
+                    }
+                    for volume in came_back {
+                        println!("💽 VOLUME: {} is back, checking {} books", volume.path, volume.audiobook_ids.len());
+                        for audiobook_id in &volume.audiobook_ids {
+                            import::record_fingerprints(&pool, audiobook_id).await;
+                            import::queue_validation(audiobook_id);
+                        }
+                        events::emit(AppEvent::LibraryChanged { change: LibraryChange::Updated, audiobook_ids: volume.audiobook_ids.clone() });
+                        events::emit(AppEvent::VolumeOnline(volume));
+                    }
+                    offline = current;
+                }
+                Err(e) => log::warn!("VOLUME: Failed to check library drives: {}", e),
+            }
+            tokio::time::sleep(volume_service::VOLUME_CHECK_INTERVAL).await;
+        }
+    });
+}
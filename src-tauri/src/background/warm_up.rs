@@ -0,0 +1,165 @@
+// Startup work run after the first screen is up: the download manager, the
+// backfills for books imported by older versions, and cleanup after a crash.
+
+use crate::commands::AppState;
+use crate::database::DatabaseManager;
+use crate::download::{throttle, DownloadManager};
+use crate::events::{self, AppEvent};
+use crate::filesystem::atomic;
+use crate::models::WarmUpReport;
+use crate::services::{AudiobookSourceService, AuthorService, FollowService, ImportRepairService, NarratorService, RelocationService};
+use crate::storage;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime};
+use tauri::Manager;
+
+// Set once warm-up has finished, for frontends that subscribe to init-complete late
+static COMPLETE: AtomicBool = AtomicBool::new(false);
+
+pub fn is_complete() -> bool {
+    COMPLETE.load(Ordering::Relaxed)
+}
+
+/// Startup work the UI does not need to wait for. Emits init-complete once
+/// downloads are available and the backfills have run.
+pub async fn run(app: tauri::AppHandle, db_manager: DatabaseManager) {
+    let started = Instant::now();
+    let mut report = WarmUpReport {
+        duration_ms: 0,
+        fingerprints_backfilled: 0,
+        authors_linked: 0,
+        narrators_linked: 0,
+        sources_backfilled: 0,
+        incomplete_imports: 0,
+        release_alerts: 0,
+        temp_files_removed: 0,
+        errors: Vec::new(),
+    };
+
+    if let Err(e) = db_manager.verify_migrations().await {
+        log::warn!("Migration verification failed: {}", e);
+        report.errors.push(e.to_string());
+    }
+
+    let Ok(pool) = db_manager.get_pool().cloned() else {
+        return;
+    };
+
+    // Initialize download manager with the persisted throttle settings
+    match DownloadManager::new() {
+        Ok(download_manager) => {
+            download_manager.throttle().apply(&throttle::load_settings(&pool).await);
+            throttle::follow_playback(download_manager.throttle().clone());
+            *app.state::<AppState>().download_manager.write().await = Some(download_manager);
+            println!("Download manager initialized successfully");
+            log::info!("Download manager initialized successfully");
+        }
+        Err(e) => {
+            log::warn!("Failed to initialize download manager: {}", e);
+            report.errors.push(format!("Failed to initialize download manager: {}", e));
+        }
+    }
+
+    // Fingerprint older imports so relocation can find them later
+    match RelocationService::new(&pool).backfill_fingerprints().await {
+        Ok(count) => {
+            report.fingerprints_backfilled = count;
+            if count > 0 {
+                println!("🔑 FINGERPRINT: Backfilled {} file fingerprints", count);
+            }
+        }
+        Err(e) => {
+            log::warn!("Fingerprint backfill failed: {}", e);
+            report.errors.push(format!("Fingerprint backfill failed: {}", e));
+        }
+    }
+
+    // Link books imported before author entities existed
+    match AuthorService::new(&pool).backfill().await {
+        Ok(count) => {
+            report.authors_linked = count;
+            if count > 0 {
+                println!("👤 AUTHOR: Linked {} books to authors", count);
+            }
+        }
+        Err(e) => {
+            log::warn!("Author backfill failed: {}", e);
+            report.errors.push(format!("Author backfill failed: {}", e));
+        }
+    }
+    match NarratorService::new(&pool).backfill().await {
+        Ok(count) => {
+            report.narrators_linked = count;
+            if count > 0 {
+                println!("🎙️ NARRATOR: Linked {} audiobooks to narrators", count);
+            }
+        }
+        Err(e) => {
+            log::warn!("Narrator backfill failed: {}", e);
+            report.errors.push(format!("Narrator backfill failed: {}", e));
+        }
+    }
+
+    // Writes a crash cut short leave temporary files next to their targets
+    let temp_dirs = [storage::paths().data_dir.clone(), storage::paths().cache_dir.clone()];
+    match tokio::task::spawn_blocking(move || {
+        temp_dirs.iter().map(|dir| atomic::clean_stale(dir, SystemTime::now())).sum::<usize>()
+    }).await {
+        Ok(removed) => report.temp_files_removed = removed,
+        Err(e) => report.errors.push(format!("Temporary file cleanup failed: {}", e)),
+    }
+    storage::disk_space::warn_if_low(&storage::paths().data_dir);
+    storage::disk_space::warn_if_low(&storage::paths().cache_dir);
+
+    // Record where books imported before sources were tracked came from
+    let librivox_dir = app.state::<AppState>().download_manager.read().await
+        .as_ref()
+        .map(|manager| manager.cache_dir().to_path_buf());
+    if let Some(librivox_dir) = librivox_dir {
+        match AudiobookSourceService::new(&pool).backfill(&librivox_dir).await {
+            Ok(count) => {
+                report.sources_backfilled = count;
+                if count > 0 {
+                    println!("🔗 SOURCE: Recorded the source of {} audiobooks", count);
+                }
+            }
+            Err(e) => {
+                log::warn!("Source backfill failed: {}", e);
+                report.errors.push(format!("Source backfill failed: {}", e));
+            }
+        }
+    }
+
+    // LibriVox downloads cut short by the app closing can be resumed from the frontend
+    match ImportRepairService::new(&pool).reconcile().await {
+        Ok(incomplete) => {
+            report.incomplete_imports = incomplete.len();
+            if !incomplete.is_empty() {
+                events::emit(AppEvent::IncompleteImports(incomplete));
+            }
+        }
+        Err(e) => {
+            log::warn!("Import reconciliation failed: {}", e);
+            report.errors.push(format!("Import reconciliation failed: {}", e));
+        }
+    }
+
+    // Alerts raised by the last fetch before the app was closed
+    match FollowService::new(&pool).pending_alerts().await {
+        Ok(alerts) => {
+            report.release_alerts = alerts.len();
+            if !alerts.is_empty() {
+                events::emit(AppEvent::FollowedReleaseAvailable(alerts));
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to load release alerts: {}", e);
+            report.errors.push(format!("Failed to load release alerts: {}", e));
+        }
+    }
+
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    COMPLETE.store(true, Ordering::Relaxed);
+    println!("INIT: Warm-up finished in {}ms", report.duration_ms);
+    events::emit(AppEvent::InitComplete(report));
+}
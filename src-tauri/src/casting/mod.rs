@@ -0,0 +1,288 @@
+// Casting to UPnP/DLNA media renderers on the local network. Renderers are
+// found with an SSDP search for the AVTransport service and driven with its
+// SOAP actions; the chapter file itself is fetched by the renderer from the
+// LAN sharing server. Chromecast speaks its own TLS and protobuf protocol and
+// is only reachable here when it also exposes a DLNA renderer.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use ts_rs::TS;
+
+pub mod session;
+
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+const AV_TRANSPORT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CastDevice {
+    /// The renderer's UDN
+    pub id: String,
+    pub name: String,
+    pub model: Option<String>,
+    pub control_url: String,
+    pub service_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CastStatus {
+    pub device: CastDevice,
+    pub file_path: String,
+    pub playing: bool,
+    #[ts(type = "number")]
+    pub position: u64,
+    #[ts(type = "number | null")]
+    pub duration: Option<u64>,
+}
+
+/// Search the network for renderers, waiting `timeout` for answers
+pub async fn discover(timeout: Duration) -> Result<Vec<CastDevice>> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await.context("Failed to open discovery socket")?;
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: {}\r\n\r\n",
+        SSDP_ADDRESS,
+        timeout.as_secs().clamp(1, 5),
+        AV_TRANSPORT
+    );
+    socket.send_to(search.as_bytes(), SSDP_ADDRESS).await.context("Failed to send discovery search")?;
+
+    let mut locations = Vec::new();
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buffer = [0u8; 2048];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await {
+        let Ok((len, _)) = received else { continue };
+        if let Some(location) = ssdp_location(&String::from_utf8_lossy(&buffer[..len])) {
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+    }
+
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let mut devices: Vec<CastDevice> = Vec::new();
+    for location in locations {
+        let description = match client.get(&location).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response.text().await.unwrap_or_default(),
+            Err(e) => {
+                log::debug!("Skipping renderer at {}: {}", location, e);
+                continue;
+            }
+        };
+        if let Some(device) = parse_description(&location, &description) {
+            if !devices.iter().any(|known| known.id == device.id) {
+                devices.push(device);
+            }
+        }
+    }
+    devices.sort_by_key(|device| device.name.to_lowercase());
+    Ok(devices)
+}
+
+/// The LOCATION header of a search response
+fn ssdp_location(response: &str) -> Option<String> {
+    if !response.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    response
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// The renderer a device description offers, if it has AVTransport
+fn parse_description(location: &str, xml: &str) -> Option<CastDevice> {
+    let (service_type, control_url) = xml
+        .split("<service>")
+        .skip(1)
+        .filter_map(|service| Some((xml_text(service, "serviceType")?, xml_text(service, "controlURL")?)))
+        .find(|(service_type, _)| service_type.contains(":AVTransport:"))?;
+    let base = xml_text(xml, "URLBase").unwrap_or_else(|| location.to_string());
+    Some(CastDevice {
+        id: xml_text(xml, "UDN").unwrap_or_else(|| location.to_string()),
+        name: xml_text(xml, "friendlyName").unwrap_or_else(|| "Media renderer".to_string()),
+        model: xml_text(xml, "modelName"),
+        control_url: resolve_url(&base, &control_url),
+        service_type,
+    })
+}
+
+/// Text of the first element with this local name, whatever its prefix
+fn xml_text(xml: &str, name: &str) -> Option<String> {
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let tag_end = rest.find('>')?;
+        let tag = &rest[..tag_end];
+        let tag_name = tag.split_whitespace().next().unwrap_or("");
+        let local_name = tag_name.rsplit(':').next().unwrap_or("");
+        if local_name == name && !tag.ends_with('/') {
+            let content = &rest[tag_end + 1..];
+            let close = content.find(&format!("</{}>", tag_name))?;
+            let text = unescape_xml(content[..close].trim());
+            return (!text.is_empty()).then_some(text);
+        }
+    }
+    None
+}
+
+fn resolve_url(base: &str, url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    // Only the scheme and host of the base matter for absolute paths
+    let origin_end = base.find("://").map(|scheme| scheme + 3).and_then(|host| base[host..].find('/').map(|path| host + path));
+    let origin = origin_end.map(|end| &base[..end]).unwrap_or(base.trim_end_matches('/'));
+    if url.starts_with('/') {
+        format!("{}{}", origin, url)
+    } else {
+        format!("{}/{}", origin, url)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+/// `H:MM:SS` as AVTransport wants it
+fn format_time(seconds: u64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Whole seconds of an `H:MM:SS[.fff]` time; None for NOT_IMPLEMENTED and the like
+fn parse_time(time: &str) -> Option<u64> {
+    let mut seconds = 0u64;
+    for part in time.split(':') {
+        let whole = part.split('.').next()?;
+        seconds = seconds * 60 + whole.parse::<u64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// One renderer being controlled
+pub struct Renderer {
+    pub device: CastDevice,
+    client: reqwest::Client,
+}
+
+impl Renderer {
+    pub fn new(device: CastDevice) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self { device, client })
+    }
+
+    /// Hand the renderer a file to play
+    pub async fn load(&self, url: &str, title: &str, content_type: &str) -> Result<()> {
+        let metadata = format!(
+            "<DIDL-Lite xmlns=\"urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+             xmlns:upnp=\"urn:schemas-upnp-org:metadata-1-0/upnp/\"><item id=\"0\" parentID=\"-1\" restricted=\"1\">\
+             <dc:title>{}</dc:title><upnp:class>object.item.audioItem.audioBook</upnp:class>\
+             <res protocolInfo=\"http-get:*:{}:*\">{}</res></item></DIDL-Lite>",
+            escape_xml(title),
+            content_type,
+            escape_xml(url)
+        );
+        self.action("SetAVTransportURI", &[("CurrentURI", url), ("CurrentURIMetaData", &metadata)]).await?;
+        Ok(())
+    }
+
+    pub async fn play(&self) -> Result<()> {
+        self.action("Play", &[("Speed", "1")]).await.map(|_| ())
+    }
+
+    pub async fn pause(&self) -> Result<()> {
+        self.action("Pause", &[]).await.map(|_| ())
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        self.action("Stop", &[]).await.map(|_| ())
+    }
+
+    pub async fn seek(&self, seconds: u64) -> Result<()> {
+        self.action("Seek", &[("Unit", "REL_TIME"), ("Target", &format_time(seconds))]).await.map(|_| ())
+    }
+
+    /// Position and duration in seconds
+    pub async fn position(&self) -> Result<(u64, Option<u64>)> {
+        let response = self.action("GetPositionInfo", &[]).await?;
+        let position = xml_text(&response, "RelTime").and_then(|time| parse_time(&time)).unwrap_or(0);
+        let duration = xml_text(&response, "TrackDuration").and_then(|time| parse_time(&time)).filter(|duration| *duration > 0);
+        Ok((position, duration))
+    }
+
+    pub async fn is_playing(&self) -> Result<bool> {
+        let response = self.action("GetTransportInfo", &[]).await?;
+        Ok(xml_text(&response, "CurrentTransportState").is_some_and(|state| state == "PLAYING" || state == "TRANSITIONING"))
+    }
+
+    async fn action(&self, action: &str, arguments: &[(&str, &str)]) -> Result<String> {
+        let service_type = &self.device.service_type;
+        let mut body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body><u:{} xmlns:u=\"{}\"><InstanceID>0</InstanceID>",
+            action, service_type
+        );
+        for (name, value) in arguments {
+            body.push_str(&format!("<{0}>{1}</{0}>", name, escape_xml(value)));
+        }
+        body.push_str(&format!("</u:{}></s:Body></s:Envelope>", action));
+
+        let response = self.client
+            .post(&self.device.control_url)
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPAction", format!("\"{}#{}\"", service_type, action))
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("{} did not answer", self.device.name))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            let reason = xml_text(&text, "errorDescription").unwrap_or_else(|| status.to_string());
+            return Err(anyhow!("{} refused {}: {}", self.device.name, action, reason));
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_search_responses_and_descriptions() {
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLocation: http://192.168.1.20:49152/description.xml\r\nST: urn:schemas-upnp-org:service:AVTransport:1\r\n\r\n";
+        assert_eq!(ssdp_location(response).as_deref(), Some("http://192.168.1.20:49152/description.xml"));
+        assert_eq!(ssdp_location("NOTIFY * HTTP/1.1\r\nLOCATION: http://x/\r\n\r\n"), None);
+
+        let description = r#"<?xml version="1.0"?>
+            <root xmlns="urn:schemas-upnp-org:device-1-0"><device>
+              <friendlyName>Living Room &amp; Kitchen</friendlyName><modelName>Speaker</modelName>
+              <UDN>uuid:1234</UDN>
+              <serviceList>
+                <service><serviceType>urn:schemas-upnp-org:service:RenderingControl:1</serviceType><controlURL>/rc</controlURL></service>
+                <service><serviceType>urn:schemas-upnp-org:service:AVTransport:1</serviceType><controlURL>upnp/control/avt</controlURL></service>
+              </serviceList>
+            </device></root>"#;
+        let device = parse_description("http://192.168.1.20:49152/description.xml", description).unwrap();
+        assert_eq!(device.name, "Living Room & Kitchen");
+        assert_eq!(device.id, "uuid:1234");
+        assert_eq!(device.control_url, "http://192.168.1.20:49152/upnp/control/avt");
+        assert!(parse_description("http://x/", "<root><device><UDN>uuid:tv</UDN></device></root>").is_none());
+
+        let position = "<s:Envelope><s:Body><u:GetPositionInfoResponse><Track>1</Track><TrackDuration>1:02:03</TrackDuration><RelTime>0:00:42.250</RelTime></u:GetPositionInfoResponse></s:Body></s:Envelope>";
+        assert_eq!(xml_text(position, "RelTime").and_then(|time| parse_time(&time)), Some(42));
+        assert_eq!(xml_text(position, "TrackDuration").and_then(|time| parse_time(&time)), Some(3723));
+        assert_eq!(parse_time("NOT_IMPLEMENTED"), None);
+        assert_eq!(format_time(3723), "1:02:03");
+    }
+}
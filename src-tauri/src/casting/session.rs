@@ -0,0 +1,113 @@
+// The renderers found by the last search and the one playback is handed to.
+// While a session is open, transport commands drive the renderer instead of
+// local output.
+
+use super::{CastDevice, CastStatus, Renderer};
+use anyhow::Result;
+use std::sync::Mutex;
+
+static DEVICES: Mutex<Vec<CastDevice>> = Mutex::new(Vec::new());
+static SESSION: tokio::sync::Mutex<Option<Session>> = tokio::sync::Mutex::const_new(None);
+
+struct Session {
+    renderer: Renderer,
+    file_path: String,
+}
+
+pub enum CastControl {
+    Play,
+    Pause,
+    Stop,
+    Seek(u64),
+}
+
+pub fn remember_devices(devices: &[CastDevice]) {
+    *DEVICES.lock().unwrap() = devices.to_vec();
+}
+
+/// A device from the last search
+pub fn find_device(device_id: &str) -> Option<CastDevice> {
+    DEVICES.lock().unwrap().iter().find(|device| device.id == device_id).cloned()
+}
+
+/// Stop any cast in progress and play `url` on `device` from `position`
+pub async fn start(device: CastDevice, url: &str, content_type: &str, file_path: &str, position: u64, duration: Option<u64>) -> Result<CastStatus> {
+    let mut session = SESSION.lock().await;
+    if let Some(previous) = session.take() {
+        if let Err(e) = previous.renderer.stop().await {
+            log::warn!("Failed to stop previous cast: {}", e);
+        }
+    }
+    let renderer = Renderer::new(device)?;
+    let title = std::path::Path::new(file_path).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    renderer.load(url, &title, content_type).await?;
+    renderer.play().await?;
+    if position > 0 {
+        if let Err(e) = renderer.seek(position).await {
+            log::warn!("Renderer did not seek to {}s: {}", position, e);
+        }
+    }
+
+    let status = CastStatus {
+        device: renderer.device.clone(),
+        file_path: file_path.to_string(),
+        playing: true,
+        position,
+        duration,
+    };
+    *session = Some(Session { renderer, file_path: file_path.to_string() });
+    Ok(status)
+}
+
+/// End the cast; returns where the renderer got to, None when nothing was
+/// being cast or the renderer could not say
+pub async fn stop() -> Option<u64> {
+    let session = SESSION.lock().await.take()?;
+    let position = match session.renderer.position().await {
+        Ok((position, _)) => Some(position),
+        Err(e) => {
+            log::warn!("Failed to get cast position: {}", e);
+            None
+        }
+    };
+    if let Err(e) = session.renderer.stop().await {
+        log::warn!("Failed to stop renderer: {}", e);
+    }
+    position
+}
+
+pub async fn is_active() -> bool {
+    SESSION.lock().await.is_some()
+}
+
+/// While casting, transport commands drive the renderer instead of local
+/// output; None when not casting. Stopping ends the cast.
+pub async fn control(control: CastControl) -> Option<Result<()>> {
+    let mut session = SESSION.lock().await;
+    let renderer = &session.as_ref()?.renderer;
+    let result = match control {
+        CastControl::Play => renderer.play().await,
+        CastControl::Pause => renderer.pause().await,
+        CastControl::Stop => renderer.stop().await,
+        CastControl::Seek(position) => renderer.seek(position).await,
+    };
+    if matches!(control, CastControl::Stop) {
+        *session = None;
+    }
+    Some(result)
+}
+
+pub async fn status() -> Option<CastStatus> {
+    let session = SESSION.lock().await;
+    let session = session.as_ref()?;
+    let (position, duration) = session.renderer.position().await
+        .map_err(|e| log::warn!("Failed to get cast position: {}", e))
+        .ok()?;
+    Some(CastStatus {
+        device: session.renderer.device.clone(),
+        file_path: session.file_path.clone(),
+        playing: session.renderer.is_playing().await.unwrap_or(false),
+        position,
+        duration,
+    })
+}
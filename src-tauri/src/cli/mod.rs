@@ -0,0 +1,269 @@
+// Headless library operations for scripts, run instead of the window when the
+// first argument is a subcommand:
+//
+//   audiovibe import <dir>             import a folder as one audiobook
+//   audiovibe export-library <path>    write the whole library as JSON
+//   audiovibe play <id>                play a book in the terminal from where it was left
+//   audiovibe scan                     find moved files and folders not in the library yet
+//
+// Anything else starts the app as usual.
+
+use crate::audio::{AudioManager, PlaybackState};
+use crate::database::repository::PlaybackProgressRepository;
+use crate::database::DatabaseManager;
+use crate::filesystem::long_path::long_path;
+use crate::filesystem::FileSystemScanner;
+use crate::services::{CollectionQueueService, LibraryExportService, LibraryRootService, RelocationService};
+use crate::storage;
+use anyhow::{anyhow, Context, Result};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const USAGE: &str = "Usage:
+  audiovibe import <dir>             Import a folder as one audiobook
+  audiovibe export-library <path>    Write the whole library as JSON
+  audiovibe play <id>                Play an audiobook, resuming where it was left
+  audiovibe scan                     Find moved files and folders not in the library yet
+  audiovibe help                     Show this help
+
+Start without a subcommand to open the app. --portable works with every subcommand.";
+
+/// How often `play` checks whether the current track has finished
+const PLAY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Import(PathBuf),
+    ExportLibrary(PathBuf),
+    Play(String),
+    Scan,
+    Help,
+}
+
+/// The subcommand in the arguments after the program name. None starts the app:
+/// no arguments, only flags, or a first argument that is no subcommand (a file
+/// the OS asked the app to open).
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<CliCommand>, String> {
+    let mut args = args.into_iter().filter(|arg| arg != storage::PORTABLE_ARG);
+    let Some(subcommand) = args.next() else {
+        return Ok(None);
+    };
+    let operands: Vec<String> = args.collect();
+    let single = |name: &str, operand: &str| match operands.as_slice() {
+        [value] => Ok(value.clone()),
+        _ => Err(format!("Usage: audiovibe {} <{}>", name, operand)),
+    };
+
+    let command = match subcommand.as_str() {
+        "import" => CliCommand::Import(PathBuf::from(single("import", "dir")?)),
+        "export-library" => CliCommand::ExportLibrary(PathBuf::from(single("export-library", "path")?)),
+        "play" => CliCommand::Play(single("play", "id")?),
+        "scan" if operands.is_empty() => CliCommand::Scan,
+        "scan" => return Err("Usage: audiovibe scan".to_string()),
+        "help" | "--help" | "-h" => CliCommand::Help,
+        _ => return Ok(None),
+    };
+    Ok(Some(command))
+}
+
+/// Run a subcommand to completion and return the process exit code
+pub fn run(command: CliCommand) -> i32 {
+    #[cfg(windows)]
+    console::attach_to_parent();
+
+    if command == CliCommand::Help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start: {}", e);
+            return 1;
+        }
+    };
+    match runtime.block_on(execute(command)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            1
+        }
+    }
+}
+
+async fn execute(command: CliCommand) -> Result<()> {
+    let mut db = DatabaseManager::new(storage::paths().database_file().to_string_lossy().to_string());
+    db.initialize().await.context("Failed to open the library database")?;
+    let pool = db.get_pool()?.clone();
+
+    match command {
+        CliCommand::Import(dir) => import(&pool, &dir).await,
+        CliCommand::ExportLibrary(path) => {
+            let count = LibraryExportService::new(&pool).export_to_file(&path).await?;
+            println!("Exported {} audiobooks to {}", count, path.display());
+            Ok(())
+        }
+        CliCommand::Play(id) => play(&pool, &id).await,
+        CliCommand::Scan => scan(&pool).await,
+        CliCommand::Help => Ok(()),
+    }
+}
+
+async fn import(pool: &SqlitePool, dir: &Path) -> Result<()> {
+    let dir = crate::validation::canonical_path("dir", &dir.to_string_lossy())?;
+    let dir = crate::filesystem::long_path::strip_extended(&dir);
+    crate::services::LibraryRootService::new(pool).add(&dir).await?;
+    let audiobook = crate::import::import_directory(pool, &dir).await.map_err(|e| anyhow!(e))?;
+    println!("Imported '{}' ({} chapters) as {}", audiobook.title, audiobook.chapters_count, audiobook.id);
+    Ok(())
+}
+
+async fn play(pool: &SqlitePool, audiobook_id: &str) -> Result<()> {
+    let mut tracks = CollectionQueueService::new(pool).audiobook_queue(audiobook_id).await?;
+    if tracks.is_empty() {
+        return Err(anyhow!("No playable files for audiobook {}", audiobook_id));
+    }
+
+    // Resume like the app does: the saved chapter and position, unless the book was finished
+    let progress = PlaybackProgressRepository::new(pool).find_by_audiobook_id(audiobook_id).await?
+        .filter(|progress| !progress.is_completed);
+    let (chapter_index, position) = progress
+        .map(|progress| (progress.chapter_index.max(0) as usize, progress.position.max(0)))
+        .unwrap_or((0, 0));
+    if chapter_index < tracks.len() {
+        tracks.drain(..chapter_index);
+    }
+
+    let manager = AudioManager::new()?;
+    manager.load_queue(tracks)?;
+    if position > 0 {
+        manager.seek(position as f32)?;
+    }
+
+    loop {
+        if let Some(track) = manager.get_current_track() {
+            let status = manager.get_status();
+            print!("\r{} {}:{:02}   ", track.title.as_deref().unwrap_or(&track.file_path), status.position / 60, status.position % 60);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        }
+        tokio::time::sleep(PLAY_POLL_INTERVAL).await;
+        if matches!(manager.get_status().state, PlaybackState::Stopped) && !manager.play_next()? {
+            break;
+        }
+    }
+    println!();
+    Ok(())
+}
+
+async fn scan(pool: &SqlitePool) -> Result<()> {
+    let report = RelocationService::new(pool).auto_relocate_missing(&[]).await?;
+    for file in &report.relocated {
+        println!("relocated\t{}\t{}", file.old_path, file.new_path);
+    }
+    for path in report.ambiguous.iter().chain(&report.still_missing) {
+        println!("missing\t{}", path);
+    }
+    for path in &report.volume_offline {
+        println!("offline\t{}", path);
+    }
+
+    let known: HashSet<String> = sqlx::query_scalar("SELECT file_path FROM audiobooks UNION SELECT file_path FROM chapters")
+        .fetch_all(pool)
+        .await
+        .context("Failed to load library file paths")?
+        .into_iter()
+        .collect();
+    let scanner = FileSystemScanner::new();
+    let mut files = Vec::new();
+    for root in LibraryRootService::new(pool).roots().await? {
+        collect_audio_files(&scanner, &root, &mut files);
+    }
+    for (folder, count) in unlisted_folders(&files, &known) {
+        println!("new\t{}\t{}", folder.display(), count);
+    }
+    Ok(())
+}
+
+fn collect_audio_files(scanner: &FileSystemScanner, dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(long_path(dir)) else { return };
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_audio_files(scanner, &path, files),
+            Ok(file_type) if file_type.is_file() && scanner.is_supported_audio_file(&path) => files.push(path),
+            _ => {}
+        }
+    }
+}
+
+/// Folders with audio files that belong to no book, with how many such files
+/// each holds. A file is in the library when it is a book or chapter file, or
+/// sits in the folder of a folder-based book.
+fn unlisted_folders(files: &[PathBuf], known: &HashSet<String>) -> BTreeMap<PathBuf, usize> {
+    let mut folders = BTreeMap::new();
+    for file in files {
+        let folder = file.parent().unwrap_or(Path::new(""));
+        if known.contains(file.to_string_lossy().as_ref()) || known.contains(folder.to_string_lossy().as_ref()) {
+            continue;
+        }
+        *folders.entry(folder.to_path_buf()).or_insert(0) += 1;
+    }
+    folders
+}
+
+// Release builds are GUI programs on Windows and start without a console;
+// borrow the one of the shell that ran the subcommand so output shows up there
+#[cfg(windows)]
+mod console {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    pub fn attach_to_parent() {
+        // Fails harmlessly when there already is a console (debug builds)
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        assert_eq!(parse(args(&[])), Ok(None));
+        assert_eq!(parse(args(&["--portable"])), Ok(None));
+        assert_eq!(parse(args(&["/books/Dune.m4b"])), Ok(None));
+        assert_eq!(parse(args(&["import", "/books/Dune"])), Ok(Some(CliCommand::Import(PathBuf::from("/books/Dune")))));
+        assert_eq!(parse(args(&["--portable", "play", "abc"])), Ok(Some(CliCommand::Play("abc".to_string()))));
+        assert_eq!(parse(args(&["export-library", "out.json"])), Ok(Some(CliCommand::ExportLibrary(PathBuf::from("out.json")))));
+        assert_eq!(parse(args(&["scan"])), Ok(Some(CliCommand::Scan)));
+        assert_eq!(parse(args(&["--help"])), Ok(Some(CliCommand::Help)));
+        assert!(parse(args(&["import"])).is_err());
+        assert!(parse(args(&["play", "a", "b"])).is_err());
+        assert!(parse(args(&["scan", "/books"])).is_err());
+    }
+
+    #[test]
+    fn test_unlisted_folders() {
+        let files: Vec<PathBuf> = ["/books/Dune/01.mp3", "/books/Dune/02.mp3", "/books/Emma.m4b", "/books/New/01.mp3", "/books/New/02.mp3", "/books/Loose.mp3"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let known: HashSet<String> = ["/books/Dune", "/books/Emma.m4b"].iter().map(|path| path.to_string()).collect();
+        let folders = unlisted_folders(&files, &known);
+        assert_eq!(folders.into_iter().collect::<Vec<_>>(), vec![(PathBuf::from("/books"), 1), (PathBuf::from("/books/New"), 2)]);
+    }
+}
@@ -0,0 +1,752 @@
+// Window, startup and settings commands, including the settings for the
+// background services: maintenance, retention, LAN sharing and folder sync.
+
+use super::{AppState, download_throttle, path_roots, with_pool};
+use crate::{audio, inbox, sharing, storage, validation};
+use crate::audio::books;
+use crate::background::{self, folder_sync, maintenance, warm_up};
+use crate::database::{content_filter, DatabaseManager, SchemaVersion};
+use crate::database::content_filter::ContentFilter;
+use crate::database::data_migrations::DataMigrationContext;
+use crate::database::models::*;
+use crate::database::repository::*;
+use crate::download::throttle;
+use crate::filesystem;
+use crate::models::{AppConfig, DiagnosticsBundle, LastPlaybackSnapshot, SystemInfo};
+use crate::services::{folder_sync_service, FolderSyncReport, library_root_service, FolderSyncService, IssuedRemoteToken, MaintenanceConfig, MaintenanceService, MaintenanceTask, OfflineVolume, player_state_service, privacy, RelocationReport, RelocationService, RemoteAccessService, RemoteAuditEntry, RemoteScope, RemoteToken, RetentionReport, RetentionService, RetentionSettings, TaskRun, TaskStatus, VolumeService};
+use std::env;
+use tauri::State;
+use ts_rs::TS;
+
+#[tauri::command]
+pub fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust and AudioVibe!", name)
+}
+
+// Window control commands
+#[tauri::command]
+pub async fn minimize_window(window: tauri::Window) -> Result<(), String> {
+    window.minimize().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn maximize_window(window: tauri::Window) -> Result<(), String> {
+    window.maximize().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn close_window(window: tauri::Window) -> Result<(), String> {
+    window.close().map_err(|e| e.to_string())
+}
+
+
+#[tauri::command]
+pub async fn initialize_app(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<AppConfig, String> {
+    // A reload of the frontend calls this again; the backend is already up
+    let existing_pool = {
+        let db_state = state.db.read().await;
+        db_state.as_ref().filter(|db| db.is_initialized()).and_then(|db| db.get_pool().ok().cloned())
+    };
+    if let Some(pool) = existing_pool {
+        return Ok(app_config(&pool).await);
+    }
+
+    // Initialize logging with proper level
+    if storage::init_logging() {
+        println!("Logger initialized successfully");
+    }
+    
+    println!("INITIALIZING AUDIOVIBE APPLICATION");
+    log::info!("Initializing AudioVibe application");
+
+    println!("AUDIO: Using simplified single manager approach");
+
+    // Initialize database
+    let storage_paths = storage::paths();
+    log::info!("Storage mode {:?}, data in {}", storage_paths.mode, storage_paths.data_dir.display());
+    tokio::fs::create_dir_all(&storage_paths.data_dir).await
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    
+    let db_path = storage_paths.database_file().to_string_lossy().to_string();
+    let mut db_manager = DatabaseManager::new(db_path)
+        .with_backup_dir(storage_paths.backups_dir())
+        .with_data_migrations(DataMigrationContext { covers_dir: storage_paths.covers_dir() });
+    
+    db_manager.initialize().await
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+    let pool = db_manager.get_pool().map_err(|e| e.to_string())?.clone();
+    
+    // Store database manager in app state
+    *state.db.write().await = Some(db_manager.clone());
+    
+    log::info!("Database initialized successfully");
+
+    // A portable copy on a USB stick may come back under another drive letter
+    match storage::migrate::follow_moved_folders(&pool, storage_paths).await {
+        Ok(0) => {}
+        Ok(rewritten) => log::info!("Updated {} stored paths to the moved storage folders", rewritten),
+        Err(e) => log::warn!("Failed to update paths for moved storage folders: {}", e),
+    }
+
+    // Preferences that change what the first screens show are cheap single-row reads
+    content_filter::set_active(load_content_filter(&pool).await);
+    let incognito = PreferencesRepository::new(&pool).get_bool(privacy::PREF_INCOGNITO, false).await.unwrap_or(false);
+    privacy::set_incognito(incognito);
+    audio::settings::load(&pool).await;
+    background::start(&app, pool.clone());
+
+    let restore_player = PreferencesRepository::new(&pool)
+        .get_bool(player_state_service::PREF_RESTORE_PLAYER, true).await.unwrap_or(true);
+    if restore_player {
+        let restore_pool = pool.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = books::restore_saved_player_state(&restore_pool).await {
+                log::warn!("Failed to restore player state: {}", e);
+            }
+        });
+    }
+
+    tauri::async_runtime::spawn(warm_up::run(app, db_manager));
+
+    Ok(app_config(&pool).await)
+}
+
+async fn app_config(pool: &sqlx::SqlitePool) -> AppConfig {
+    AppConfig {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        initialized: true,
+        app_name: "AudioVibe".to_string(),
+        build_date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+        warm_up_complete: warm_up::is_complete(),
+        last_playback: load_last_playback(pool).await.unwrap_or_else(|e| {
+            log::warn!("Failed to load last playback snapshot: {}", e);
+            None
+        }),
+    }
+}
+
+async fn load_last_playback(pool: &sqlx::SqlitePool) -> anyhow::Result<Option<LastPlaybackSnapshot>> {
+    let Some(progress) = PlaybackProgressRepository::new(pool).find_last_played().await? else {
+        return Ok(None);
+    };
+    let Some(audiobook) = AudiobookRepository::new(pool).find_by_id(&progress.audiobook_id).await? else {
+        return Ok(None);
+    };
+    if content_filter::active().is_some_and(|filter| filter.blocks_audiobook(&audiobook)) {
+        return Ok(None);
+    }
+
+    let state_data = sqlx::query_scalar::<_, String>("SELECT state_data FROM playback_states WHERE audiobook_id = ?")
+        .bind(&progress.audiobook_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(Some(LastPlaybackSnapshot { audiobook, progress, state_data }))
+}
+
+#[tauri::command]
+pub async fn is_warm_up_complete() -> Result<bool, String> {
+    Ok(warm_up::is_complete())
+}
+
+#[tauri::command]
+pub async fn get_system_info() -> Result<SystemInfo, String> {
+    Ok(SystemInfo {
+        platform: env::consts::OS.to_string(),
+        arch: env::consts::ARCH.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        tauri_version: "2.0".to_string(),
+    })
+}
+
+/// Applied and expected migrations, for support to spot a database from another version
+#[tauri::command]
+pub async fn get_schema_version(state: State<'_, AppState>) -> Result<SchemaVersion, String> {
+    let db = {
+        let db_state = state.db.read().await;
+        db_state.as_ref().ok_or("Database not initialized")?.clone()
+    };
+    db.schema_version().await.map_err(|e| e.to_string())
+}
+
+/// Write system info, schema version, output diagnostics and playback metrics
+/// to one JSON file the user can attach to a bug report
+#[tauri::command]
+pub async fn export_diagnostics(state: State<'_, AppState>, path: String) -> Result<String, String> {
+    let db = state.db.read().await.clone();
+    let schema = match db {
+        Some(db) => Some(db.schema_version().await.map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let bundle = DiagnosticsBundle {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        system: get_system_info().await?,
+        schema,
+        output: super::playback::get_audio_output_diagnostics().await?,
+        metrics: super::playback::get_audio_metrics().await?,
+    };
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    filesystem::atomic::write_async(std::path::Path::new(&path), json).await
+        .map_err(|e| format!("Failed to write diagnostics file: {:#}", e))?;
+
+    log::info!("Wrote diagnostics bundle to {}", path);
+    Ok(path)
+}
+
+/// Where the library is stored and whether this copy runs portable
+#[tauri::command]
+pub async fn get_storage_info() -> Result<storage::StorageInfo, String> {
+    Ok(storage::StorageInfo::from(storage::paths()))
+}
+
+/// Copy the library to the other storage mode's folders and switch to it on the
+/// next start. The current files are left in place.
+#[tauri::command]
+pub async fn migrate_storage(
+    state: State<'_, AppState>,
+    mode: storage::StorageMode,
+) -> Result<storage::migrate::StorageMigrationReport, String> {
+    let pool = with_pool(&state).await?;
+
+    let from = storage::paths();
+    if from.mode == mode {
+        return Err(format!("Already using {:?} storage", mode));
+    }
+    let to = storage::StoragePaths::for_mode(mode).map_err(|e| e.to_string())?;
+    let report = storage::migrate::migrate(&pool, from, &to).await.map_err(|e| e.to_string())?;
+
+    let exe_dir = storage::exe_dir().map_err(|e| e.to_string())?;
+    storage::set_portable_marker(&exe_dir, mode == storage::StorageMode::Portable)
+        .map_err(|e| format!("Library copied, but failed to switch storage mode: {}", e))?;
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn read_file_binary(state: State<'_, AppState>, file_path: String) -> Result<Vec<u8>, String> {
+    use std::fs;
+
+    println!("Reading binary file: {}", file_path);
+    let resolved = path_roots(&state).await?.resolve("file_path", &file_path)?;
+
+    // Read the file as binary
+    let file_data = fs::read(&resolved)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    println!("Successfully read {} bytes", file_data.len());
+    Ok(file_data)
+}
+
+#[tauri::command]
+pub async fn read_file_chunk(state: State<'_, AppState>, file_path: String, start: usize, end: usize) -> Result<Vec<u8>, String> {
+    use std::fs::File;
+    use std::io::{Read, Seek, SeekFrom};
+
+    println!("Reading file chunk: {} [{}-{}]", file_path, start, end);
+    if end < start || end - start > validation::MAX_CHUNK_BYTES {
+        return Err(validation::ValidationErrors::single(
+            "end",
+            format!("must be after start and at most {} bytes past it", validation::MAX_CHUNK_BYTES),
+        ).into());
+    }
+    let resolved = path_roots(&state).await?.resolve("file_path", &file_path)?;
+
+    let mut file = File::open(&resolved)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+
+    // Seek to start position
+    file.seek(SeekFrom::Start(start as u64))
+        .map_err(|e| format!("Failed to seek: {}", e))?;
+
+    // Read chunk
+    let chunk_size = end - start;
+    let mut buffer = vec![0u8; chunk_size];
+    file.read_exact(&mut buffer)
+        .map_err(|e| format!("Failed to read chunk: {}", e))?;
+
+    println!("Successfully read chunk of {} bytes", buffer.len());
+    Ok(buffer)
+}
+
+#[tauri::command]
+pub async fn get_file_metadata(state: State<'_, AppState>, file_path: String) -> Result<serde_json::Value, String> {
+    use std::fs;
+    use std::path::Path;
+
+    println!("Getting file metadata: {}", file_path);
+    let resolved = path_roots(&state).await?.resolve("file_path", &file_path)?;
+
+    let metadata = fs::metadata(&resolved)
+        .map_err(|e| format!("Failed to get metadata: {}", e))?;
+
+    let path = Path::new(&file_path);
+    let file_name = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let extension = path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+
+    let mime_type = match extension {
+        "epub" => "application/epub+zip",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    };
+
+    let result = serde_json::json!({
+        "name": file_name,
+        "size": metadata.len(),
+        "mime_type": mime_type,
+    });
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn save_app_preferences(
+    state: State<'_, AppState>,
+    preferences: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let query = r#"
+        INSERT OR REPLACE INTO app_preferences (key, value, updated_at)
+        VALUES ('user_preferences', ?, datetime('now'))
+    "#;
+
+    sqlx::query(query)
+        .bind(&preferences)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_app_preferences(
+    state: State<'_, AppState>
+) -> Result<Option<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    let query = "SELECT value FROM app_preferences WHERE key = 'user_preferences'";
+
+    let result = sqlx::query_scalar::<_, String>(query)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+/// Preferences that only change through their own commands, which check the
+/// content filter PIN or validate what is stored: the filter and its PIN hash,
+/// the library roots that file access is limited to, and LAN sharing
+fn check_open_preference(key: &str) -> Result<(), String> {
+    let guarded = key.starts_with("content_filter.")
+        || key == library_root_service::PREF_LIBRARY_ROOTS
+        || key.starts_with("sharing.");
+    if guarded {
+        return Err(format!("The {} preference cannot be read or changed directly", key));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_preference(
+    state: State<'_, AppState>,
+    key: String
+) -> Result<Option<String>, String> {
+    check_open_preference(&key)?;
+    let pool = with_pool(&state).await?;
+
+    let repo = PreferencesRepository::new(&pool);
+    repo.get(&key).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_preference(
+    state: State<'_, AppState>,
+    key: String,
+    value: String
+) -> Result<(), String> {
+    check_open_preference(&key)?;
+    let pool = with_pool(&state).await?;
+
+    let repo = PreferencesRepository::new(&pool);
+    repo.set(&key, &value).await.map_err(|e| e.to_string())?;
+
+    // Apply download settings immediately so running transfers pick them up
+    if key.starts_with("download.") {
+        if let Some(throttle) = download_throttle(&state).await {
+            throttle.apply(&throttle::load_settings(&pool).await);
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_content_filter(pool: &sqlx::SqlitePool) -> ContentFilter {
+    PreferencesRepository::new(pool)
+        .get(content_filter::PREF_CONTENT_FILTER)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Changes to a PIN-protected filter require the current PIN
+async fn check_content_filter_pin(pool: &sqlx::SqlitePool, pin: Option<&str>) -> Result<(), String> {
+    let stored = PreferencesRepository::new(pool)
+        .get(content_filter::PREF_CONTENT_FILTER_PIN)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let Some(hash) = stored.filter(|hash| !hash.is_empty()) else {
+        return Ok(());
+    };
+    let pin = pin.ok_or("Incorrect PIN")?.to_string();
+    // Slow on purpose, so keep it off the async workers
+    let verified = tokio::task::spawn_blocking(move || content_filter::verify_pin(&pin, &hash))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !verified {
+        return Err("Incorrect PIN".to_string());
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize, TS)]
+#[ts(export)]
+pub struct ContentFilterStatus {
+    filter: ContentFilter,
+    pin_set: bool,
+}
+
+#[tauri::command]
+pub async fn get_content_filter(state: State<'_, AppState>) -> Result<ContentFilterStatus, String> {
+    let pool = with_pool(&state).await?;
+
+    let pin_set = PreferencesRepository::new(&pool)
+        .get(content_filter::PREF_CONTENT_FILTER_PIN)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|hash| !hash.is_empty())
+        .unwrap_or(false);
+
+    Ok(ContentFilterStatus {
+        filter: load_content_filter(&pool).await,
+        pin_set,
+    })
+}
+
+#[tauri::command]
+pub async fn set_content_filter(
+    state: State<'_, AppState>,
+    filter: ContentFilter,
+    pin: Option<String>
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    check_content_filter_pin(&pool, pin.as_deref()).await?;
+
+    let json = serde_json::to_string(&filter).map_err(|e| e.to_string())?;
+    PreferencesRepository::new(&pool)
+        .set(content_filter::PREF_CONTENT_FILTER, &json)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!("🔒 FILTER: Content filter {} ({} genres, {} tags, {} authors)",
+        if filter.enabled { "enabled" } else { "disabled" },
+        filter.blocked_genres.len(), filter.blocked_tags.len(), filter.blocked_authors.len());
+    content_filter::set_active(filter);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_content_filter_pin(
+    state: State<'_, AppState>,
+    current_pin: Option<String>,
+    new_pin: Option<String>
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    check_content_filter_pin(&pool, current_pin.as_deref()).await?;
+
+    // An empty or missing new PIN removes the protection
+    let stored = match new_pin.as_deref().map(str::trim).filter(|pin| !pin.is_empty()) {
+        Some(pin) if pin.len() < 4 => return Err("PIN must be at least 4 characters".to_string()),
+        Some(pin) => {
+            let pin = pin.to_string();
+            tokio::task::spawn_blocking(move || content_filter::hash_pin(&pin)).await.map_err(|e| e.to_string())?
+        }
+        None => String::new(),
+    };
+
+    PreferencesRepository::new(&pool)
+        .set(content_filter::PREF_CONTENT_FILTER_PIN, &stored)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_incognito(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    println!("🕶️ PRIVACY: Incognito listening {}", if enabled { "on" } else { "off" });
+
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(privacy::PREF_INCOGNITO, if enabled { "true" } else { "false" })
+        .await
+        .map_err(|e| e.to_string())?;
+    privacy::set_incognito(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_incognito() -> Result<bool, String> {
+    Ok(privacy::is_incognito())
+}
+
+#[tauri::command]
+pub async fn get_maintenance_status(state: State<'_, AppState>) -> Result<Vec<TaskStatus>, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(MaintenanceService::new(&pool).status().await)
+}
+
+#[tauri::command]
+pub async fn get_maintenance_config(state: State<'_, AppState>) -> Result<MaintenanceConfig, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(MaintenanceService::new(&pool).load_config().await)
+}
+
+#[tauri::command]
+pub async fn set_maintenance_config(state: State<'_, AppState>, config: MaintenanceConfig) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    MaintenanceService::new(&pool).save_config(&config).await.map_err(|e| e.to_string())
+}
+
+/// Run a maintenance task now, whether or not it is enabled or due
+#[tauri::command]
+pub async fn run_maintenance_now(
+    state: State<'_, AppState>,
+    task: MaintenanceTask
+) -> Result<TaskRun, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(maintenance::run(&pool, task).await)
+}
+
+#[tauri::command]
+pub async fn get_retention_settings(state: State<'_, AppState>) -> Result<RetentionSettings, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(RetentionService::new(&pool).load_settings().await)
+}
+
+#[tauri::command]
+pub async fn set_retention_settings(state: State<'_, AppState>, settings: RetentionSettings) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    RetentionService::new(&pool).save_settings(&settings).await.map_err(|e| e.to_string())
+}
+
+/// Apply the retention settings now instead of waiting for the daily run
+#[tauri::command]
+pub async fn enforce_retention(state: State<'_, AppState>) -> Result<RetentionReport, String> {
+    let pool = with_pool(&state).await?;
+
+    RetentionService::new(&pool).enforce(chrono::Utc::now()).await.map_err(|e| e.to_string())
+}
+
+/// Library changes after `cursor` (0 for everything still journaled); pass the
+/// returned cursor back to continue. `reset_required` means changes were pruned
+/// and the caller has to read the whole library again.
+#[tauri::command]
+pub async fn get_changes_since(
+    state: State<'_, AppState>,
+    cursor: i64,
+    limit: Option<u32>
+) -> Result<ChangeSet, String> {
+    let pool = with_pool(&state).await?;
+
+    ChangeLogRepository::new(&pool)
+        .changes_since(cursor, limit.unwrap_or(500))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Folder watched for documents to convert automatically; `None` stops watching
+#[tauri::command]
+pub async fn set_inbox_folder(state: State<'_, AppState>, folder: Option<String>) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let prefs = PreferencesRepository::new(&pool);
+    match folder.filter(|folder| !folder.trim().is_empty()) {
+        Some(folder) => {
+            // Inside the library, or a new folder directly in it
+            let path = std::path::Path::new(&folder);
+            let existing = if path.exists() { path } else { path.parent().ok_or("Invalid inbox folder")? };
+            path_roots(&state).await?.resolve("folder", &existing.to_string_lossy())?;
+            std::fs::create_dir_all(&folder).map_err(|e| format!("Failed to create inbox folder: {}", e))?;
+            println!("📥 INBOX: Watching {}", folder);
+            prefs.set(inbox::PREF_INBOX_FOLDER, &folder).await.map_err(|e| e.to_string())
+        }
+        None => prefs.delete(inbox::PREF_INBOX_FOLDER).await.map_err(|e| e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_inbox_folder(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool).get(inbox::PREF_INBOX_FOLDER).await.map_err(|e| e.to_string())
+}
+
+/// Turn sharing the library over the local network on or off. A new port
+/// restarts the server there; the sharing key stays the same.
+#[tauri::command]
+pub async fn set_lan_sharing(state: State<'_, AppState>, enabled: bool, port: Option<u16>) -> Result<sharing::LanSharingStatus, String> {
+    let pool = with_pool(&state).await?;
+
+    let mut settings = sharing::load_settings(&pool).await.map_err(|e| e.to_string())?;
+    settings.enabled = enabled;
+    if let Some(port) = port.filter(|port| *port > 0) {
+        settings.port = port;
+    }
+    sharing::save_settings(&pool, &settings).await.map_err(|e| e.to_string())?;
+    Ok(sharing::apply(pool, &settings).await)
+}
+
+/// Issue a new sharing key and revoke the old one, ending every link shared
+/// so far. The server keeps running.
+#[tauri::command]
+pub async fn regenerate_lan_sharing_key(state: State<'_, AppState>) -> Result<sharing::LanSharingStatus, String> {
+    let pool = with_pool(&state).await?;
+
+    let settings = sharing::regenerate_key(&pool).await.map_err(|e| e.to_string())?;
+    println!("🔑 SHARING: Issued a new sharing key");
+    Ok(sharing::status(&settings).await)
+}
+
+// Remote-control access: tokens, their scopes and the audit log of what
+// remote clients did
+#[tauri::command]
+pub async fn issue_remote_token(state: State<'_, AppState>, name: String, scopes: Vec<RemoteScope>) -> Result<IssuedRemoteToken, String> {
+    let pool = with_pool(&state).await?;
+    RemoteAccessService::new(&pool).issue(&name, scopes).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_remote_tokens(state: State<'_, AppState>) -> Result<Vec<RemoteToken>, String> {
+    let pool = with_pool(&state).await?;
+    RemoteAccessService::new(&pool).list().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_remote_token_scopes(state: State<'_, AppState>, id: String, scopes: Vec<RemoteScope>) -> Result<RemoteToken, String> {
+    let pool = with_pool(&state).await?;
+    RemoteAccessService::new(&pool).set_scopes(&id, scopes).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn revoke_remote_token(state: State<'_, AppState>, id: String) -> Result<RemoteToken, String> {
+    let pool = with_pool(&state).await?;
+    RemoteAccessService::new(&pool).revoke(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_remote_audit_log(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<RemoteAuditEntry>, String> {
+    let pool = with_pool(&state).await?;
+    RemoteAccessService::new(&pool).audit_log(limit.unwrap_or(200)).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_lan_sharing_status(state: State<'_, AppState>) -> Result<sharing::LanSharingStatus, String> {
+    let pool = with_pool(&state).await?;
+
+    let settings = sharing::load_settings(&pool).await.map_err(|e| e.to_string())?;
+    Ok(sharing::status(&settings).await)
+}
+
+/// Choose the synced folder (Dropbox, Syncthing, ...) progress is shared
+/// through, or None to stop syncing. Syncs right away when one is set.
+#[tauri::command]
+pub async fn set_sync_folder(state: State<'_, AppState>, folder: Option<String>) -> Result<Option<FolderSyncReport>, String> {
+    let pool = with_pool(&state).await?;
+
+    let prefs = PreferencesRepository::new(&pool);
+    match folder.filter(|folder| !folder.trim().is_empty()) {
+        Some(folder) => {
+            let path = validation::canonical_path("folder", &folder).map_err(|e| e.to_string())?;
+            if !path.is_dir() {
+                return Err(format!("Not a folder: {}", folder));
+            }
+            prefs.set(folder_sync_service::PREF_SYNC_FOLDER, &folder).await.map_err(|e| e.to_string())?;
+            println!("🔄 SYNC: Syncing through {}", folder);
+            folder_sync::run(&pool, std::path::Path::new(&folder)).await.map(Some).map_err(|e| format!("{:#}", e))
+        }
+        None => {
+            prefs.delete(folder_sync_service::PREF_SYNC_FOLDER).await.map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_sync_folder(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool).get(folder_sync_service::PREF_SYNC_FOLDER).await.map_err(|e| e.to_string())
+}
+
+/// Sync now instead of waiting for the next round, e.g. before closing the app
+#[tauri::command]
+pub async fn sync_folder_now(state: State<'_, AppState>) -> Result<FolderSyncReport, String> {
+    let pool = with_pool(&state).await?;
+
+    let folder = FolderSyncService::new(&pool).folder().await.map_err(|e| e.to_string())?
+        .ok_or("No sync folder is set")?;
+    if !folder.is_dir() {
+        return Err(format!("Sync folder is not available: {}", folder.display()));
+    }
+    folder_sync::run(&pool, &folder).await.map_err(|e| format!("{:#}", e))
+}
+
+/// Drives and shares holding books that are not connected, with their books
+#[tauri::command]
+pub async fn get_offline_volumes(state: State<'_, AppState>) -> Result<Vec<OfflineVolume>, String> {
+    let pool = with_pool(&state).await?;
+    VolumeService::new(&pool).offline_volumes().await.map_err(|e| e.to_string())
+}
+
+/// Look for moved files under the library roots and `search_dirs`, which must
+/// be inside the roots too (a folder chosen with `pick_library_folder` is)
+#[tauri::command]
+pub async fn auto_relocate_missing(
+    state: State<'_, AppState>,
+    search_dirs: Option<Vec<String>>
+) -> Result<RelocationReport, String> {
+    println!("🔗 RELOCATE: Scanning for moved or renamed files");
+
+    let pool = with_pool(&state).await?;
+
+    let roots = path_roots(&state).await?;
+    let mut extra_dirs = search_dirs.unwrap_or_default()
+        .iter()
+        .map(|dir| roots.resolve("search_dirs", dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    extra_dirs.push(storage::paths().data_dir.join("library"));
+
+    let report = RelocationService::new(&pool).auto_relocate_missing(&extra_dirs).await
+        .map_err(|e| format!("Failed to relocate missing files: {}", e))?;
+
+    println!("RELOCATE: {} missing, {} relocated, {} ambiguous, {} still missing, {} on offline drives",
+        report.missing_files, report.relocated.len(), report.ambiguous.len(), report.still_missing.len(), report.volume_offline.len());
+    Ok(report)
+}
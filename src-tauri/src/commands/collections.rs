@@ -0,0 +1,242 @@
+// Collection commands: the tree of collections, their books, covers and
+// sharing them as files.
+
+use super::{AppState, download_manager, with_pool};
+use crate::{covers, filesystem, storage};
+use crate::database::models::*;
+use crate::database::repository::*;
+use crate::services::{CollectionImportReport, CollectionShareService, CoverService, SharedCollection, SortPreferenceService};
+use tauri::State;
+
+// Collection management commands
+#[tauri::command]
+pub async fn create_collection(
+    state: State<'_, AppState>,
+    dto: CreateCollectionDto
+) -> Result<Collection, String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.create(dto).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_collections(
+    state: State<'_, AppState>
+) -> Result<Vec<Collection>, String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.find_all().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_collections_with_counts(
+    state: State<'_, AppState>
+) -> Result<Vec<CollectionWithCount>, String> {
+    let pool = with_pool(&state).await?;
+
+    CollectionRepository::new(&pool).find_all_with_counts().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_collection_by_id(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<Option<Collection>, String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.find_by_id(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_collection(
+    state: State<'_, AppState>,
+    id: String,
+    dto: CreateCollectionDto
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.update(&id, dto).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_collection(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.delete(&id).await.map_err(|e| e.to_string())?;
+    if let Err(e) = SortPreferenceService::new(&pool).forget_collection(&id).await {
+        log::warn!("Failed to remove sort preference of collection {}: {}", id, e);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_audiobook_to_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    audiobook_id: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.add_audiobook_to_collection(&collection_id, &audiobook_id).await.map_err(|e| e.to_string())?;
+
+    covers::refresh_collection_cover(&pool, &collection_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_audiobook_from_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    audiobook_id: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.remove_audiobook_from_collection(&collection_id, &audiobook_id).await.map_err(|e| e.to_string())?;
+
+    covers::refresh_collection_cover(&pool, &collection_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_collection_audiobooks(
+    state: State<'_, AppState>,
+    collection_id: String
+) -> Result<Vec<Audiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    SortPreferenceService::new(&pool).collection(&collection_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reorder_collection_audiobooks(
+    state: State<'_, AppState>,
+    collection_id: String,
+    audiobook_orders: Vec<(String, i32)>
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.reorder_audiobooks(&collection_id, audiobook_orders).await.map_err(|e| e.to_string())?;
+
+    // The collage shows the first books, so a new order can change it
+    covers::refresh_collection_cover(&pool, &collection_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_collection_tree(
+    state: State<'_, AppState>
+) -> Result<Vec<CollectionTreeNode>, String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.find_tree().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn move_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    parent_collection_id: Option<String>
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.move_collection(&collection_id, parent_collection_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    path: String
+) -> Result<String, String> {
+    let pool = with_pool(&state).await?;
+    let download_manager = download_manager(&state).await?;
+
+    let shared = CollectionShareService::new(&pool)
+        .export(&collection_id, download_manager.cache_dir())
+        .await
+        .map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&shared).map_err(|e| e.to_string())?;
+    filesystem::atomic::write_async(std::path::Path::new(&path), json).await
+        .map_err(|e| format!("Failed to write collection file: {:#}", e))?;
+
+    println!("📚 COLLECTION EXPORT: Wrote '{}' ({} books) to {}", shared.name, shared.books.len(), path);
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn import_collection(
+    state: State<'_, AppState>,
+    path: String
+) -> Result<CollectionImportReport, String> {
+    let pool = with_pool(&state).await?;
+    let download_manager = download_manager(&state).await?;
+
+    let json = tokio::fs::read_to_string(&path).await
+        .map_err(|e| format!("Failed to read collection file: {}", e))?;
+    let shared: SharedCollection = serde_json::from_str(&json)
+        .map_err(|e| format!("Invalid collection file: {}", e))?;
+
+    let report = CollectionShareService::new(&pool)
+        .import(shared, download_manager.cache_dir())
+        .await
+        .map_err(|e| e.to_string())?;
+    covers::refresh_collection_cover(&pool, &report.collection.id).await;
+    Ok(report)
+}
+
+/// Use a chosen image as the collection cover, or pass None to go back to the generated collage
+#[tauri::command]
+pub async fn set_collection_cover(
+    state: State<'_, AppState>,
+    collection_id: String,
+    image_path: Option<String>
+) -> Result<Collection, String> {
+    let pool = with_pool(&state).await?;
+
+    let repository = CollectionRepository::new(&pool);
+    repository.find_by_id(&collection_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Collection not found: {}", collection_id))?;
+
+    match image_path {
+        Some(path) => {
+            if !std::path::Path::new(&path).is_file() {
+                return Err(format!("Cover image not found: {}", path));
+            }
+            repository.set_cover(&collection_id, Some(&path), true).await.map_err(|e| e.to_string())?;
+        }
+        None => {
+            CoverService::new(&pool)
+                .generate_collection_collage(&collection_id, &storage::paths().covers_dir())
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    repository.find_by_id(&collection_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Collection not found: {}", collection_id))
+}
+
+#[tauri::command]
+pub async fn get_collection_stats(
+    state: State<'_, AppState>,
+    collection_id: String
+) -> Result<CollectionStats, String> {
+    let pool = with_pool(&state).await?;
+
+    CollectionRepository::new(&pool).get_stats(&collection_id).await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,1274 @@
+// Commands that fetch books from the network: LibriVox search and imports,
+// release alerts, direct URL imports and the download throttle.
+
+use super::{AppState, download_manager, download_throttle, with_pool};
+use crate::{covers, download, events, filesystem, import, services, storage};
+use crate::audio::thread::{self, AudioCommand};
+use crate::audio::extract_audio_metadata;
+use crate::database::content_filter;
+use crate::database::models::*;
+use crate::database::repository::*;
+use crate::background::maintenance;
+use crate::download::{throttle, ThrottleSettings};
+use crate::events::{AppEvent, LibraryChange};
+use crate::services::{audiobook_source_service, AudiobookSourceService, ChapterErrorService, compilation_service, CompilationPlan, CompilationWork, CoverResolutionService, import_preview_service, ImportOverrides, ImportPreview, ImportPreviewCover, ImportPreviewSource, ImportRepairService, LibrivoxAuthorPage, LibrivoxAuthorService, LibrivoxDiscoveryService, LibrivoxRelease, LibrivoxReleaseService, LibrivoxSuggestion, SharedCollectionBook};
+use std::sync::mpsc;
+use tauri::State;
+use ts_rs::TS;
+
+// Downloads that lost files part-way are flagged so they can be resumed
+async fn check_import(pool: &sqlx::SqlitePool, audiobook: &Audiobook) {
+    match ImportRepairService::new(pool).check(audiobook).await {
+        Ok(IMPORT_STATUS_INCOMPLETE) => println!("🧩 IMPORT REPAIR: '{}' is missing files", audiobook.title),
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to check import of {}: {}", audiobook.id, e),
+    }
+}
+
+/// Fetch the files an interrupted LibriVox import is missing, using the
+/// Archive.org identifier recorded when it was imported
+#[tauri::command]
+pub async fn resume_import(
+    state: State<'_, AppState>,
+    audiobook_id: String
+) -> Result<Audiobook, String> {
+    let pool = with_pool(&state).await?;
+    let download_manager = download_manager(&state).await?;
+
+    let audiobook = AudiobookRepository::new(&pool).find_by_id(&audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Audiobook not found: {}", audiobook_id))?;
+    let identifier = match (audiobook.source_type.as_deref(), audiobook.source_id) {
+        (Some(audiobook_source_service::SOURCE_LIBRIVOX), Some(identifier)) => identifier,
+        _ => return Err("Only LibriVox imports can be resumed".to_string()),
+    };
+
+    println!("🔁 IMPORT REPAIR: Resuming '{}' ({})", audiobook.title, identifier);
+    let result = download_manager.download_archive_files(&identifier).await
+        .map_err(|e| format!("Failed to download LibriVox content: {}", e))?;
+    let audiobook = ImportRepairService::new(&pool)
+        .finish_resume(&audiobook_id, &result.local_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    import::record_fingerprints(&pool, &audiobook.id).await;
+
+    if audiobook.import_status == IMPORT_STATUS_INCOMPLETE {
+        return Err("Some files could not be downloaded; try resuming again later".to_string());
+    }
+    Ok(audiobook)
+}
+
+/// Download one missing or damaged chapter file of a LibriVox book again,
+/// verify it against Archive.org's checksum and refresh the chapter's details
+#[tauri::command]
+pub async fn repair_chapter(state: State<'_, AppState>, chapter_id: String) -> Result<Chapter, String> {
+    let pool = with_pool(&state).await?;
+    let download_manager = download_manager(&state).await?;
+
+    let chapter_repo = ChapterRepository::new(&pool);
+    let chapter = chapter_repo.find_by_id(&chapter_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Chapter not found: {}", chapter_id))?;
+    let audiobook = AudiobookRepository::new(&pool).find_by_id(&chapter.audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Audiobook not found: {}", chapter.audiobook_id))?;
+    let identifier = match (audiobook.source_type.as_deref(), audiobook.source_id.as_deref()) {
+        (Some(audiobook_source_service::SOURCE_LIBRIVOX), Some(identifier)) => identifier.to_string(),
+        _ => return Err("Only chapters of LibriVox imports can be repaired".to_string()),
+    };
+
+    println!("🩹 IMPORT REPAIR: Repairing '{}' of '{}'", chapter.title, audiobook.title);
+    let file_path = std::path::Path::new(&chapter.file_path);
+    download_manager.redownload_archive_file(&identifier, file_path).await
+        .map_err(|e| format!("Failed to repair chapter: {:#}", e))?;
+
+    let (duration, file_size) = match extract_audio_metadata(file_path) {
+        Ok(info) => (info.duration.map(|d| d as i64).or(chapter.duration), Some(info.file_size as i64)),
+        Err(e) => return Err(format!("The downloaded file is still unreadable: {}", e)),
+    };
+    chapter_repo.update_chapter(&chapter.id, CreateChapterDto {
+        audiobook_id: chapter.audiobook_id.clone(),
+        chapter_number: chapter.chapter_number,
+        title: chapter.title.clone(),
+        file_path: chapter.file_path.clone(),
+        duration,
+        file_size,
+    }).await.map_err(|e| e.to_string())?;
+
+    if let Err(e) = ChapterErrorService::new(&pool).clear(&chapter.file_path).await {
+        log::warn!("Failed to clear chapter error for {}: {}", chapter.file_path, e);
+    }
+    import::record_fingerprints(&pool, &audiobook.id).await;
+    check_import(&pool, &audiobook).await;
+    import::queue_validation(&audiobook.id);
+
+    chapter_repo.find_by_id(&chapter_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Chapter not found: {}", chapter_id))
+}
+
+/// Read a book's title, author and description again from where it was imported:
+/// Archive.org for LibriVox books, the stored document for TTS books, or the
+/// files' tags for local imports
+#[tauri::command]
+pub async fn refresh_from_source(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Audiobook, String> {
+    let pool = with_pool(&state).await?;
+
+    let audiobook = AudiobookSourceService::new(&pool)
+        .refresh(&audiobook_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    events::emit(AppEvent::library_changed(LibraryChange::Updated, &audiobook_id));
+
+    // Books imported without a usable cover get another try through the fallback chain
+    let cover_missing = audiobook.cover_image_path.as_deref()
+        .is_none_or(|path| !path.starts_with("data:") && !std::path::Path::new(path).exists());
+    if !cover_missing {
+        return Ok(audiobook);
+    }
+    match CoverResolutionService::new(&pool).fetch_cover(&audiobook_id, &storage::paths().covers_dir(), true).await {
+        Ok(Some(_)) => AudiobookRepository::new(&pool).find_by_id(&audiobook_id).await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Audiobook not found: {}", audiobook_id)),
+        Ok(None) => Ok(audiobook),
+        Err(e) => {
+            log::warn!("Could not fetch a cover for {}: {}", audiobook_id, e);
+            Ok(audiobook)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_download_throttle(state: State<'_, AppState>) -> Result<ThrottleSettings, String> {
+    download_throttle(&state).await
+        .map(|throttle| throttle.settings())
+        .ok_or_else(|| "Download manager not initialized".to_string())
+}
+
+#[tauri::command]
+pub async fn set_download_throttle(
+    state: State<'_, AppState>,
+    settings: ThrottleSettings
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let repo = PreferencesRepository::new(&pool);
+    repo.set(throttle::PREF_GLOBAL_LIMIT_KBPS, &settings.global_limit_kbps.to_string()).await
+        .map_err(|e| e.to_string())?;
+    repo.set(throttle::PREF_PER_DOWNLOAD_LIMIT_KBPS, &settings.per_download_limit_kbps.to_string()).await
+        .map_err(|e| e.to_string())?;
+    repo.set(throttle::PREF_PAUSE_WHILE_PLAYING, &settings.pause_while_playing.to_string()).await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(throttle) = download_throttle(&state).await {
+        throttle.apply(&settings);
+    }
+
+    Ok(())
+}
+
+/// Download a missing entry of an imported collection from LibriVox and add it to the collection
+#[tauri::command]
+pub async fn download_collection_entry(
+    state: State<'_, AppState>,
+    collection_id: String,
+    book: SharedCollectionBook
+) -> Result<Audiobook, String> {
+    let identifier = book.librivox_identifier.clone()
+        .ok_or("This entry has no LibriVox identifier to download")?;
+
+    let pool = with_pool(&state).await?;
+    let download_manager = download_manager(&state).await?;
+
+    println!("📥 COLLECTION IMPORT: Downloading '{}' ({})", book.title, identifier);
+    let result = download_manager.download_archive_files(&identifier).await
+        .map_err(|e| format!("Failed to download LibriVox content: {}", e))?;
+    if result.extracted_files.is_empty() {
+        return Err("No audio files found for this audiobook".to_string());
+    }
+
+    let audiobook = AudiobookRepository::new(&pool).create(CreateAudiobookDto {
+        title: book.title,
+        author: book.author,
+        narrator: book.narrator,
+        description: None,
+        genre: None,
+        file_path: result.local_path.to_string_lossy().to_string(),
+        duration: None,
+        cover_image_path: None,
+        source_type: Some(audiobook_source_service::SOURCE_LIBRIVOX.to_string()),
+        source_id: Some(identifier),
+    }).await.map_err(|e| format!("Failed to save audiobook to database: {}", e))?;
+    import::record_fingerprints(&pool, &audiobook.id).await;
+    check_import(&pool, &audiobook).await;
+    import::link_people(&pool, &audiobook.id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    import::queue_validation(&audiobook.id);
+
+    CollectionRepository::new(&pool)
+        .add_audiobook_to_collection(&collection_id, &audiobook.id)
+        .await
+        .map_err(|e| e.to_string())?;
+    covers::refresh_collection_cover(&pool, &collection_id).await;
+
+    Ok(audiobook)
+}
+
+// Helper function to detect likely author from book title patterns
+fn detect_author_from_title(title: &str) -> Option<String> {
+    let title_lower = title.to_lowercase();
+    
+    // Classic literature patterns
+    if (title_lower.contains("picture") && (title_lower.contains("dorian") || title_lower.contains("gray") || title_lower.contains("grey")))
+        || title_lower.contains("wilde") 
+        || title_lower.contains("importance of being earnest")
+        || title_lower.contains("canterville ghost") {
+        return Some("wilde".to_string());
+    }
+    
+    if (title_lower.contains("pride") && title_lower.contains("prejudice"))
+        || title_lower.contains("sense and sensibility")
+        || title_lower.contains("emma")
+        || (title_lower.contains("jane") && title_lower.contains("austen")) {
+        return Some("austen".to_string());
+    }
+    
+    if (title_lower.contains("alice") && title_lower.contains("wonderland"))
+        || title_lower.contains("through the looking glass")
+        || title_lower.contains("carroll") {
+        return Some("carroll".to_string());
+    }
+    
+    if title_lower.contains("great expectations")
+        || title_lower.contains("tale of two cities")
+        || title_lower.contains("christmas carol")
+        || title_lower.contains("oliver twist")
+        || title_lower.contains("bleak house")
+        || title_lower.contains("david copperfield") {
+        return Some("dickens".to_string());
+    }
+    
+    if (title_lower.contains("romeo") && title_lower.contains("juliet"))
+        || title_lower.contains("hamlet")
+        || title_lower.contains("macbeth")
+        || title_lower.contains("othello")
+        || title_lower.contains("king lear")
+        || title_lower.contains("midsummer night")
+        || title_lower.contains("merchant of venice") {
+        return Some("shakespeare".to_string());
+    }
+    
+    if title_lower.contains("war and peace")
+        || title_lower.contains("anna karenina")
+        || title_lower.contains("tolstoy") {
+        return Some("tolstoy".to_string());
+    }
+    
+    if title_lower.contains("adventures of huckleberry")
+        || title_lower.contains("tom sawyer")
+        || title_lower.contains("mark twain") {
+        return Some("twain".to_string());
+    }
+    
+    if title_lower.contains("adventures of sherlock")
+        || title_lower.contains("sherlock holmes")
+        || title_lower.contains("study in scarlet")
+        || title_lower.contains("hound of baskervilles")
+        || (title_lower.contains("arthur") && title_lower.contains("conan")) {
+        return Some("doyle".to_string());
+    }
+    
+    if title_lower.contains("moby dick")
+        || title_lower.contains("melville") {
+        return Some("melville".to_string());
+    }
+    
+    if title_lower.contains("frankenstein")
+        || (title_lower.contains("mary") && title_lower.contains("shelley")) {
+        return Some("shelley".to_string());
+    }
+    
+    if title_lower.contains("dracula")
+        || title_lower.contains("bram stoker") {
+        return Some("stoker".to_string());
+    }
+    
+    None
+}
+
+// LibriVox search command
+#[derive(Debug, Clone, serde::Deserialize, TS)]
+#[ts(export)]
+pub struct LibriVoxSearchParams {
+    author: Option<String>,
+    title: Option<String>,
+    genre: Option<String>,
+    language: Option<String>,
+    limit: Option<u32>,
+}
+
+#[tauri::command]
+pub async fn search_librivox(params: LibriVoxSearchParams) -> Result<serde_json::Value, String> {
+    println!("🌐 LIBRIVOX: Searching with params: {:?}", params);
+    
+    // Try multiple search strategies
+    let mut search_strategies = Vec::new();
+    
+    // Strategy 1: Original search
+    if params.title.is_some() || params.author.is_some() || params.genre.is_some() {
+        search_strategies.push(params.clone());
+    }
+    
+    // Strategy 2: Try different title variations and smart author detection
+    if let Some(title) = &params.title {
+        let title_lower = title.to_lowercase();
+        
+        // Smart author detection for common books (this works better than title searches)
+        let author_guess = detect_author_from_title(&title_lower);
+        if let Some(author) = author_guess {
+            search_strategies.push(LibriVoxSearchParams {
+                author: Some(author),
+                title: None,
+                genre: params.genre.clone(),
+                language: params.language.clone(),
+                limit: params.limit,
+            });
+        }
+        
+        // Try searching without common articles
+        let cleaned_title = title_lower
+            .replace("the ", "")
+            .replace("a ", "")
+            .replace("an ", "");
+        
+        if !cleaned_title.is_empty() && cleaned_title != title_lower {
+            search_strategies.push(LibriVoxSearchParams {
+                author: params.author.clone(),
+                title: Some(cleaned_title),
+                genre: params.genre.clone(),
+                language: params.language.clone(),
+                limit: params.limit,
+            });
+        }
+        
+        // Strategy 3: Try progressive title shortening (useful for partial searches)
+        let words: Vec<&str> = title_lower.split_whitespace().collect();
+        if words.len() > 2 {
+            // Try first 2 words
+            let short_title = words[..2].join(" ");
+            if short_title != title_lower {
+                search_strategies.push(LibriVoxSearchParams {
+                    author: params.author.clone(),
+                    title: Some(short_title),
+                    genre: params.genre.clone(),
+                    language: params.language.clone(),
+                    limit: params.limit,
+                });
+            }
+            
+            // Try just the first word if it's substantial
+            if words[0].len() > 3 {
+                search_strategies.push(LibriVoxSearchParams {
+                    author: params.author.clone(),
+                    title: Some(words[0].to_string()),
+                    genre: params.genre.clone(),
+                    language: params.language.clone(),
+                    limit: params.limit,
+                });
+            }
+        }
+        
+        // Strategy 4: Try a broad search with no title (get all books) - ONLY if we have no other strategies
+        // This is useful when specific title searches fail, but don't do it if we already have good strategies
+        if search_strategies.len() <= 1 { // Only original strategy exists
+            search_strategies.push(LibriVoxSearchParams {
+                author: None,
+                title: None,
+                genre: params.genre.clone(),
+                language: params.language.clone(),
+                limit: Some(50), // Get more results for broad search
+            });
+        }
+    }
+    
+    // Try each strategy until we get results
+    for (index, strategy) in search_strategies.iter().enumerate() {
+        println!("LIBRIVOX: Trying search strategy {}: {:?}", index + 1, strategy);
+
+        match try_librivox_search(&strategy).await {
+            Ok(mut results) => {
+                // If we have an original title to match against, filter the results client-side
+                if let Some(original_title) = &params.title {
+                    if let Some(books) = results.get_mut("books").and_then(|b| b.as_array_mut()) {
+                        let original_title_lower = original_title.to_lowercase();
+                        let title_words: Vec<&str> = original_title_lower.split_whitespace().collect();
+
+                        // Filter books to only include those that match the title closely
+                        let filtered_books: Vec<serde_json::Value> = books.iter()
+                            .filter(|book| {
+                                if let Some(book_title) = book.get("title").and_then(|t| t.as_str()) {
+                                    let book_title_lower = book_title.to_lowercase();
+
+                                    // Exact match or contains all words from original title
+                                    if book_title_lower.contains(&original_title_lower) {
+                                        return true;
+                                    }
+
+                                    // Check if most title words are present
+                                    let matching_words = title_words.iter()
+                                        .filter(|word| word.len() > 2 && book_title_lower.contains(*word))
+                                        .count();
+
+                                    matching_words >= (title_words.len() / 2).max(1)
+                                } else {
+                                    false
+                                }
+                            })
+                            .cloned()
+                            .collect();
+
+                        if !filtered_books.is_empty() {
+                            println!("LIBRIVOX: Filtered {} books down to {} matches for title '{}'",
+                                books.len(), filtered_books.len(), original_title);
+                            *books = filtered_books;
+                        } else {
+                            println!("LIBRIVOX: No books matched title '{}' after filtering, keeping all {} results",
+                                original_title, books.len());
+                        }
+                    }
+                }
+
+                if let Some(filter) = content_filter::active() {
+                    if let Some(books) = results.get_mut("books").and_then(|b| b.as_array_mut()) {
+                        books.retain(|book| !filter.blocks_librivox_book(book));
+                    }
+                }
+
+                println!("LIBRIVOX: Strategy {} succeeded", index + 1);
+                return Ok(results);
+            },
+            Err(e) => {
+                println!("LIBRIVOX: Strategy {} failed: {}", index + 1, e);
+                continue;
+            }
+        }
+    }
+
+    // If all strategies fail, return the last error
+    Err("No search strategy succeeded. Try more specific search terms or author names.".to_string())
+}
+
+/// Recently catalogued LibriVox recordings, newest first. The cache is
+/// refreshed first when it is stale; offline, the cached feed is returned.
+#[tauri::command]
+pub async fn get_new_librivox_releases(
+    state: State<'_, AppState>,
+    genre: Option<String>,
+    language: Option<String>
+) -> Result<Vec<LibrivoxRelease>, String> {
+    let pool = with_pool(&state).await?;
+
+    let service = LibrivoxReleaseService::new(&pool);
+    if service.is_stale(chrono::Utc::now()).await.map_err(|e| e.to_string())? {
+        if let Err(e) = maintenance::fetch_librivox_releases(&pool).await {
+            log::warn!("LIBRIVOX: Failed to refresh new releases, using the cache: {:#}", e);
+        }
+    }
+
+    service.releases(genre.as_deref(), language.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// Every LibriVox recording by an author, gathered across all the LibriVox ids
+/// the author is catalogued under, with the ones already imported marked
+#[tauri::command]
+pub async fn get_librivox_author(state: State<'_, AppState>, author_id_or_name: String) -> Result<LibrivoxAuthorPage, String> {
+    let pool = with_pool(&state).await?;
+
+    let service = LibrivoxAuthorService::new(&pool).map_err(|e| e.to_string())?;
+    service.author_page(&author_id_or_name).await.map_err(|e| format!("{:#}", e))
+}
+
+#[derive(serde::Serialize, TS)]
+#[ts(export)]
+pub struct LibrivoxWorkImport {
+    work_id: String,
+    title: String,
+    /// None when the import succeeded
+    error: Option<String>,
+}
+
+/// Import the selected recordings from an author page one after another.
+/// Recordings already in the library are skipped, and a failed import does
+/// not stop the others.
+#[tauri::command]
+pub async fn import_librivox_author_works(
+    state: State<'_, AppState>,
+    author_id_or_name: String,
+    work_ids: Vec<String>
+) -> Result<Vec<LibrivoxWorkImport>, String> {
+    let pool = with_pool(&state).await?;
+
+    let page = LibrivoxAuthorService::new(&pool)
+        .map_err(|e| e.to_string())?
+        .author_page(&author_id_or_name)
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+
+    let mut results = Vec::new();
+    for work_id in work_ids {
+        let Some(work) = page.works.iter().find(|work| work.id == work_id) else {
+            results.push(LibrivoxWorkImport { title: String::new(), error: Some(format!("{} has no LibriVox recording {}", page.name, work_id)), work_id });
+            continue;
+        };
+        if work.audiobook_id.is_some() {
+            continue;
+        }
+
+        let error = match &work.url_zip_file {
+            Some(zip_url) => {
+                let params = ImportLibriVoxParams {
+                    title: work.title.clone(),
+                    author: if work.authors.is_empty() { page.name.clone() } else { work.authors.join(", ") },
+                    zip_url: zip_url.clone(),
+                    description: work.description.clone().unwrap_or_default(),
+                    genre: Some(work.genres.join(", ")).filter(|genre| !genre.is_empty()),
+                    runtime: work.total_seconds.map(|secs| format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)),
+                    cover_url: None,
+                };
+                import_librivox(&state, params).await.err()
+            }
+            None => Some("LibriVox has no download for this recording".to_string()),
+        };
+        results.push(LibrivoxWorkImport { work_id, title: work.title.clone(), error });
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn set_librivox_release_alerts(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(services::librivox_release_service::PREF_RELEASE_ALERTS, &enabled.to_string())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_librivox_release_alerts(state: State<'_, AppState>) -> Result<bool, String> {
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .get_bool(services::librivox_release_service::PREF_RELEASE_ALERTS, false)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn try_librivox_search(params: &LibriVoxSearchParams) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::new();
+    
+    // Use the base LibriVox API URL with query parameters
+    let mut url = reqwest::Url::parse("https://librivox.org/api/feed/audiobooks")
+        .map_err(|e| format!("Invalid LibriVox URL: {}", e))?;
+    
+    // Add query parameters according to LibriVox API documentation
+    {
+        let mut query_pairs = url.query_pairs_mut();
+        query_pairs.append_pair("format", "json");
+        query_pairs.append_pair("extended", "1"); // Get full data
+        
+        // Add search parameters as query params, not path segments
+        if let Some(author) = &params.author {
+            query_pairs.append_pair("author", author);
+        }
+        if let Some(title) = &params.title {
+            query_pairs.append_pair("title", title);
+        }
+        if let Some(genre) = &params.genre {
+            query_pairs.append_pair("genre", genre);
+        }
+        
+        if let Some(limit) = params.limit {
+            query_pairs.append_pair("limit", &limit.to_string());
+        }
+    }
+    
+    println!("🌐 LIBRIVOX: Request URL: {}", url);
+    
+    let response = client
+        .get(url)
+        .header("User-Agent", "AudioVibe/1.0.0")
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| {
+            println!("LIBRIVOX: Request failed: {}", e);
+            format!("LibriVox API request failed: {}", e)
+        })?;
+    
+    println!("🌐 LIBRIVOX: Got response with status: {}", response.status());
+    
+    if !response.status().is_success() {
+        let error_msg = format!("LibriVox API error: {}", response.status());
+        println!("LIBRIVOX: {}", error_msg);
+        return Err(error_msg);
+    }
+    
+    // Get response text first for debugging
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| {
+            println!("LIBRIVOX: Failed to get response text: {}", e);
+            format!("Failed to get response text: {}", e)
+        })?;
+    
+    println!("🌐 LIBRIVOX: Response text length: {}", response_text.len());
+    println!("🌐 LIBRIVOX: First 500 chars: {}", 
+        if response_text.len() > 500 { &response_text[..500] } else { &response_text });
+    
+    let json_data: serde_json::Value = serde_json::from_str(&response_text)
+        .map_err(|e| {
+            println!("LIBRIVOX: Failed to parse JSON: {}", e);
+            println!("LIBRIVOX: Response text: {}", response_text);
+            format!("Failed to parse LibriVox response: {}", e)
+        })?;
+    
+    let book_count = json_data.get("books")
+        .and_then(|b| b.as_array())
+        .map(|a| a.len())
+        .unwrap_or(0);
+    
+    println!("🌐 LIBRIVOX: Response received, {} books found", book_count);
+    
+    if book_count > 0 {
+        println!("🌐 LIBRIVOX: First book title: {}", 
+            json_data.get("books")
+                .and_then(|books| books.as_array())
+                .and_then(|arr| arr.first())
+                .and_then(|book| book.get("title"))
+                .and_then(|title| title.as_str())
+                .unwrap_or("Unknown"));
+    }
+    
+    // Only return success if we actually found books
+    if book_count == 0 {
+        return Err("No books found".to_string());
+    }
+    
+    Ok(json_data)
+}
+
+#[tauri::command]
+pub async fn load_and_play_librivox(
+    state: State<'_, AppState>, 
+    url: String
+) -> Result<String, String> {
+    println!("📥 LIBRIVOX: Starting download and play process for: {}", url);
+    
+    // Extract Archive.org identifier from the URL
+    let identifier = audiobook_source_service::archive_identifier(&url)
+        .ok_or("Could not extract Archive.org identifier from URL")?;
+        
+    println!("📥 LIBRIVOX: Extracted Archive.org identifier: {}", identifier);
+    
+    // Get the download manager from app state
+    let download_manager = download_manager(&state).await?;
+    
+    // Download individual files from Archive.org
+    match download_manager.download_archive_files(&identifier).await {
+        Ok(result) => {
+            println!("LIBRIVOX: Download completed. Found {} audio files", result.extracted_files.len());
+            
+            if result.extracted_files.is_empty() {
+                return Err("No audio files found for this audiobook".to_string());
+            }
+            
+            // Sort files to get consistent ordering (usually chapter order)
+            let mut files = result.extracted_files;
+            files.sort();
+            
+            // Play the first audio file
+            let first_file = &files[0];
+            let file_path = first_file.to_string_lossy().to_string();
+            
+            println!("LIBRIVOX: Playing first file: {}", file_path);
+            
+            // Send load command to audio thread
+            let sender = thread::sender()?;
+            let (response_tx, response_rx) = mpsc::channel();
+            
+            sender.send(AudioCommand::LoadFile { 
+                file_path: file_path.clone(), 
+                response: response_tx 
+            }).map_err(|e| format!("Failed to send load command: {}", e))?;
+            
+            // Wait for response
+            let load_result = response_rx.recv()
+                .map_err(|e| format!("Failed to receive load response: {}", e))?;
+                
+            match load_result {
+                Ok(_) => {
+                    println!("LIBRIVOX: Successfully loaded and started playing: {}", file_path);
+                    Ok(format!("Playing LibriVox audio: {} ({} files available)", 
+                        first_file.file_name().unwrap_or_default().to_string_lossy(),
+                        files.len()))
+                },
+                Err(e) => Err(format!("Failed to load audio file: {}", e)),
+            }
+        }
+        Err(e) => {
+            println!("LIBRIVOX: Download failed: {}", e);
+            Err(format!("Failed to download LibriVox content: {}", e))
+        }
+    }
+}
+
+#[derive(serde::Deserialize, TS)]
+#[ts(export)]
+pub struct ImportLibriVoxParams {
+    title: String,
+    author: String,
+    #[serde(rename = "zipUrl")]
+    zip_url: String,
+    description: String,
+    genre: Option<String>,
+    runtime: Option<String>,
+    #[serde(rename = "coverUrl")]
+    cover_url: Option<String>,
+}
+
+#[tauri::command]
+pub async fn import_librivox_audiobook(
+    state: State<'_, AppState>,
+    params: ImportLibriVoxParams
+) -> Result<String, String> {
+    import_librivox(&state, params).await
+}
+
+async fn import_librivox(state: &AppState, params: ImportLibriVoxParams) -> Result<String, String> {
+    println!("📥 LIBRIVOX IMPORT: Starting import for: {} by {}", params.title, params.author);
+    
+    // Extract Archive.org identifier from the ZIP URL
+    // ZIP URLs look like: https://archive.org/compress/picturedoriangr_1608_librivox/formats=64KBPS%20MP3&file=/picturedoriangr_1608_librivox.zip
+    let identifier = audiobook_source_service::archive_identifier(&params.zip_url)
+        .ok_or("Could not extract Archive.org identifier from URL")?;
+        
+    println!("📥 LIBRIVOX IMPORT: Extracted Archive.org identifier: {}", identifier);
+    
+    // Get the download manager from app state
+    let download_manager = download_manager(state).await?;
+    
+    // Download individual files from Archive.org instead of ZIP
+    match download_manager.download_archive_files(&identifier).await {
+        Ok(result) => {
+            println!("LIBRIVOX IMPORT: Download completed. Found {} audio files", result.extracted_files.len());
+            
+            if result.extracted_files.is_empty() {
+                return Err("No audio files found for this audiobook".to_string());
+            }
+            
+            // Sort files to get consistent ordering (usually chapter order)
+            let mut files = result.extracted_files;
+            files.sort();
+            
+            // Use the first file as the primary file path (we'll store the directory path)
+            let first_file = &files[0];
+            let local_directory = first_file.parent()
+                .ok_or("Could not determine local directory")?
+                .to_string_lossy()
+                .to_string();
+            
+            println!("LIBRIVOX IMPORT: Storing local directory: {}", local_directory);
+            
+            // Parse runtime to seconds
+            let duration_seconds = params.runtime.as_ref()
+                .and_then(|runtime| parse_runtime_to_seconds(runtime));
+            
+            // Download cover image if available
+            let cover_image_path = if let Some(cover_url) = &params.cover_url {
+                download_cover_image(cover_url, &identifier).await.ok()
+            } else {
+                None
+            };
+            
+            // Create audiobook record with local directory path
+            let pool = with_pool(state).await?;
+            
+            let repository = AudiobookRepository::new(&pool);
+            let dto = CreateAudiobookDto {
+                title: params.title,
+                author: Some(params.author),
+                file_path: local_directory, // Store local directory path
+                description: Some(params.description),
+                genre: params.genre,
+                narrator: None,
+                duration: duration_seconds,
+                cover_image_path,
+                source_type: Some(audiobook_source_service::SOURCE_LIBRIVOX.to_string()),
+                source_id: Some(identifier.clone()),
+            };
+            
+            match repository.create(dto).await {
+                Ok(audiobook) => {
+                    println!("LIBRIVOX IMPORT: Successfully imported audiobook with ID: {}", audiobook.id);
+                    import::record_fingerprints(&pool, &audiobook.id).await;
+                    check_import(&pool, &audiobook).await;
+                    import::link_people(&pool, &audiobook.id).await;
+                    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+                    import::queue_validation(&audiobook.id);
+                    Ok(format!("Successfully imported '{}' with {} audio files. Ready to play immediately!", 
+                        audiobook.title, files.len()))
+                },
+                Err(e) => {
+                    println!("LIBRIVOX IMPORT: Database error: {}", e);
+                    Err(format!("Failed to save audiobook to database: {}", e))
+                }
+            }
+        }
+        Err(e) => {
+            println!("LIBRIVOX IMPORT: Download failed: {}", e);
+            Err(format!("Failed to download LibriVox content: {}", e))
+        }
+    }
+}
+
+/// Read a LibriVox recording's sections before importing it and propose one
+/// audiobook per work when it is a compilation of several
+#[tauri::command]
+pub async fn preview_librivox_compilation(state: State<'_, AppState>, zip_url: String) -> Result<CompilationPlan, String> {
+    let identifier = audiobook_source_service::archive_identifier(&zip_url).unwrap_or(zip_url);
+    let download_manager = download_manager(&state).await?;
+
+    let files = download_manager.get_archive_files_metadata(&identifier).await
+        .map_err(|e| format!("Failed to read the recording's sections: {:#}", e))?;
+    Ok(compilation_service::plan(&identifier, &files))
+}
+
+/// Import a LibriVox compilation as one audiobook per work, grouped as the
+/// user confirmed it. All works share the download folder; each book gets
+/// chapter records for its own sections only.
+#[tauri::command]
+pub async fn import_librivox_compilation(
+    state: State<'_, AppState>,
+    params: ImportLibriVoxParams,
+    works: Vec<CompilationWork>
+) -> Result<Vec<Audiobook>, String> {
+    let identifier = audiobook_source_service::archive_identifier(&params.zip_url)
+        .ok_or("Could not extract Archive.org identifier from URL")?;
+    let download_manager = download_manager(&state).await?;
+
+    let files = download_manager.get_archive_files_metadata(&identifier).await
+        .map_err(|e| format!("Failed to read the recording's sections: {:#}", e))?;
+    let plan = compilation_service::plan(&identifier, &files);
+    compilation_service::check_works(&plan.sections, &works).map_err(|e| e.to_string())?;
+
+    let result = download_manager.download_archive_files(&identifier).await
+        .map_err(|e| format!("Failed to download LibriVox content: {}", e))?;
+    let local_paths: std::collections::HashMap<String, std::path::PathBuf> = download::archive_file_paths(&result.local_path).into_iter().collect();
+    if let Some(missing) = works.iter().flat_map(|work| &work.file_names).find(|name| !local_paths.get(*name).is_some_and(|path| path.is_file())) {
+        return Err(format!("'{}' did not download", missing));
+    }
+
+    let pool = with_pool(&state).await?;
+    let cover_image_path = match &params.cover_url {
+        Some(cover_url) => download_cover_image(cover_url, &identifier).await.ok(),
+        None => None,
+    };
+
+    let audiobook_repo = AudiobookRepository::new(&pool);
+    let chapter_repo = ChapterRepository::new(&pool);
+    let mut audiobooks = Vec::new();
+    for work in &works {
+        let mut chapter_files = Vec::new();
+        for file_name in &work.file_names {
+            let file_path = &local_paths[file_name];
+            let section = plan.sections.iter().find(|section| &section.file_name == file_name);
+            let (duration, file_size) = match extract_audio_metadata(file_path) {
+                Ok(info) => (info.duration.map(|d| d as i64), Some(info.file_size as i64)),
+                Err(_) => (section.and_then(|section| section.duration), None),
+            };
+            chapter_files.push((file_path.clone(), section.map(|section| section.title.clone()), duration, file_size));
+        }
+        let total_duration: i64 = chapter_files.iter().filter_map(|(_, _, duration, _)| *duration).sum();
+
+        let mut audiobook = audiobook_repo.create(CreateAudiobookDto {
+            title: work.title.trim().to_string(),
+            author: work.author.clone().or_else(|| Some(params.author.clone())),
+            narrator: None,
+            description: Some(params.description.clone()),
+            genre: params.genre.clone(),
+            file_path: result.local_path.to_string_lossy().to_string(),
+            duration: if total_duration > 0 { Some(total_duration) } else { None },
+            cover_image_path: cover_image_path.clone(),
+            source_type: Some(audiobook_source_service::SOURCE_LIBRIVOX.to_string()),
+            source_id: Some(identifier.clone()),
+        }).await.map_err(|e| format!("Failed to create audiobook: {}", e))?;
+
+        let chapter_dtos: Vec<CreateChapterDto> = chapter_files.iter().enumerate()
+            .map(|(index, (file_path, title, duration, file_size))| CreateChapterDto {
+                audiobook_id: audiobook.id.clone(),
+                chapter_number: (index + 1) as i32,
+                title: title.clone().unwrap_or_else(|| format!("Part {}", index + 1)),
+                file_path: file_path.to_string_lossy().to_string(),
+                duration: *duration,
+                file_size: *file_size,
+            })
+            .collect();
+        let chapters = chapter_repo.create_multiple(chapter_dtos).await
+            .map_err(|e| format!("Failed to create chapters: {}", e))?;
+        audiobook.chapters_count = chapters.len() as i32;
+
+        import::record_fingerprints(&pool, &audiobook.id).await;
+        import::link_people(&pool, &audiobook.id).await;
+        events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+        import::queue_validation(&audiobook.id);
+        audiobooks.push(audiobook);
+    }
+
+    println!("LIBRIVOX IMPORT: Imported {} works from '{}'", audiobooks.len(), params.title);
+    Ok(audiobooks)
+}
+
+/// What importing a LibriVox recording would create, read from its
+/// Archive.org metadata without downloading anything; the preview is imported
+/// with `confirm_import`
+#[tauri::command]
+pub async fn preview_librivox_import(state: State<'_, AppState>, params: ImportLibriVoxParams) -> Result<ImportPreview, String> {
+    let identifier = audiobook_source_service::archive_identifier(&params.zip_url)
+        .ok_or("Could not extract Archive.org identifier from URL")?;
+    let download_manager = download_manager(&state).await?;
+
+    let files = download_manager.get_archive_files_metadata(&identifier).await
+        .map_err(|e| format!("Failed to read the recording's sections: {:#}", e))?;
+    let sections = compilation_service::plan(&identifier, &files).sections;
+    if sections.is_empty() {
+        return Err("No audio files found for this audiobook".to_string());
+    }
+
+    let chapters = import_preview_service::librivox_chapters(&sections);
+    let total_duration: i64 = chapters.iter().filter_map(|chapter| chapter.duration).sum();
+    let mut preview = ImportPreview::new(
+        ImportPreviewSource::Librivox { identifier, cover_url: params.cover_url.clone() },
+        params.title,
+        chapters,
+    );
+    preview.author = Some(params.author);
+    preview.description = Some(params.description);
+    preview.genre = params.genre;
+    preview.duration = if total_duration > 0 {
+        Some(total_duration)
+    } else {
+        params.runtime.as_deref().and_then(parse_runtime_to_seconds)
+    };
+    preview.cover = params.cover_url.map(|cover_url| ImportPreviewCover {
+        source: audiobook_source_service::SOURCE_LIBRIVOX.to_string(),
+        image: cover_url,
+    });
+    import_preview_service::store(&preview);
+    Ok(preview)
+}
+
+/// Import a previewed folder or LibriVox recording with the user's changes to
+/// its metadata and chapter titles
+#[tauri::command]
+pub async fn confirm_import(
+    state: State<'_, AppState>,
+    preview_id: String,
+    overrides: ImportOverrides
+) -> Result<Audiobook, String> {
+    let preview = import_preview_service::get(&preview_id)
+        .and_then(|preview| preview.with_overrides(overrides))
+        .map_err(|e| e.to_string())?;
+    let pool = with_pool(&state).await?;
+
+    let audiobook = match preview.source {
+        ImportPreviewSource::Directory { .. } => import::import_preview(&pool, preview).await?,
+        ImportPreviewSource::Librivox { .. } => import_librivox_preview(&state, &pool, preview).await?,
+    };
+    import_preview_service::discard(&preview_id);
+    println!("📥 IMPORT: Imported preview of '{}'", audiobook.title);
+    Ok(audiobook)
+}
+
+/// Download a previewed recording and create the book with one chapter per
+/// section, titled as previewed
+async fn import_librivox_preview(state: &AppState, pool: &sqlx::SqlitePool, preview: ImportPreview) -> Result<Audiobook, String> {
+    let ImportPreviewSource::Librivox { identifier, cover_url } = &preview.source else {
+        return Err("Not a LibriVox import".to_string());
+    };
+    let download_manager = download_manager(state).await?;
+
+    let result = download_manager.download_archive_files(identifier).await
+        .map_err(|e| format!("Failed to download LibriVox content: {}", e))?;
+    let local_paths: std::collections::HashMap<String, std::path::PathBuf> = download::archive_file_paths(&result.local_path).into_iter().collect();
+    if let Some(missing) = preview.chapters.iter().find(|chapter| !local_paths.get(&chapter.file).is_some_and(|path| path.is_file())) {
+        return Err(format!("'{}' did not download", missing.file));
+    }
+    let cover_image_path = match cover_url {
+        Some(cover_url) => download_cover_image(cover_url, identifier).await.ok(),
+        None => None,
+    };
+
+    let audiobook_repo = AudiobookRepository::new(pool);
+    let audiobook = audiobook_repo.create(CreateAudiobookDto {
+        title: preview.title.clone(),
+        author: preview.author.clone(),
+        narrator: preview.narrator.clone(),
+        description: preview.description.clone(),
+        genre: preview.genre.clone(),
+        file_path: result.local_path.to_string_lossy().to_string(),
+        duration: preview.duration,
+        cover_image_path,
+        source_type: Some(audiobook_source_service::SOURCE_LIBRIVOX.to_string()),
+        source_id: Some(identifier.clone()),
+    }).await.map_err(|e| format!("Failed to create audiobook: {}", e))?;
+
+    let chapter_dtos: Vec<CreateChapterDto> = preview.chapters.iter()
+        .map(|chapter| {
+            let file_path = &local_paths[&chapter.file];
+            let (duration, file_size) = match extract_audio_metadata(file_path) {
+                Ok(info) => (info.duration.map(|d| d as i64), Some(info.file_size as i64)),
+                Err(_) => (chapter.duration, None),
+            };
+            CreateChapterDto {
+                audiobook_id: audiobook.id.clone(),
+                chapter_number: chapter.chapter_number,
+                title: chapter.title.clone(),
+                file_path: file_path.to_string_lossy().to_string(),
+                duration,
+                file_size,
+            }
+        })
+        .collect();
+    ChapterRepository::new(pool).create_multiple(chapter_dtos).await
+        .map_err(|e| format!("Failed to create chapters: {}", e))?;
+    let audiobook = audiobook_repo.find_by_id(&audiobook.id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("Audiobook not found")?;
+
+    import::record_fingerprints(pool, &audiobook.id).await;
+    check_import(pool, &audiobook).await;
+    import::link_people(pool, &audiobook.id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    import::queue_validation(&audiobook.id);
+    Ok(audiobook)
+}
+
+#[tauri::command]
+pub async fn import_audiobook_from_urls(
+    state: State<'_, AppState>,
+    title: String,
+    author: Option<String>,
+    urls: Vec<String>
+) -> Result<Audiobook, String> {
+    println!("📥 URL IMPORT: Importing '{}' from {} URLs", title, urls.len());
+
+    let urls: Vec<String> = urls.into_iter()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+
+    if urls.is_empty() {
+        return Err("No URLs provided".to_string());
+    }
+    if let Some(bad_url) = urls.iter().find(|url| reqwest::Url::parse(url).is_err()) {
+        return Err(format!("Invalid URL: {}", bad_url));
+    }
+
+    let download_manager = download_manager(&state).await?;
+
+    // Same title + URL list always maps to the same folder so retries reuse finished files
+    let folder_key = format!("{}{:x}", audiobook_source_service::URL_LIST_FOLDER_PREFIX, md5::compute(format!("{}|{}", title, urls.join("|")).as_bytes()));
+    let result = download_manager.download_url_list(&folder_key, &urls).await
+        .map_err(|e| format!("Failed to download audio files: {}", e))?;
+
+    let pool = with_pool(&state).await?;
+
+    let mut chapter_files = Vec::new();
+    let mut total_duration = 0i64;
+    for file_path in &result.extracted_files {
+        let (duration, file_size) = match extract_audio_metadata(file_path) {
+            Ok(info) => (info.duration.map(|d| d as i64), Some(info.file_size as i64)),
+            Err(e) => {
+                println!("URL IMPORT: Could not extract metadata for {}: {}", file_path.display(), e);
+                (None, None)
+            }
+        };
+        total_duration += duration.unwrap_or(0);
+        chapter_files.push((file_path.clone(), duration, file_size));
+    }
+
+    let audiobook_repo = AudiobookRepository::new(&pool);
+    let mut audiobook = audiobook_repo.create(CreateAudiobookDto {
+        title: title.clone(),
+        author,
+        narrator: None,
+        description: None,
+        genre: None,
+        file_path: result.local_path.to_string_lossy().to_string(),
+        duration: if total_duration > 0 { Some(total_duration) } else { None },
+        cover_image_path: None,
+        source_type: Some(audiobook_source_service::SOURCE_URL_LIST.to_string()),
+        source_id: Some(folder_key),
+    }).await.map_err(|e| format!("Failed to create audiobook: {}", e))?;
+
+    let chapter_dtos: Vec<CreateChapterDto> = chapter_files.iter().enumerate()
+        .map(|(index, (file_path, duration, file_size))| {
+            // Strip the "001_" ordering prefix and the extension for display
+            let chapter_title = file_path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .map(|stem| stem.split_once('_').map(|(_, rest)| rest.to_string()).unwrap_or(stem))
+                .map(|stem| stem.replace('_', " ").trim().to_string())
+                .filter(|stem| !stem.is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", index + 1));
+
+            CreateChapterDto {
+                audiobook_id: audiobook.id.clone(),
+                chapter_number: (index + 1) as i32,
+                title: chapter_title,
+                file_path: file_path.to_string_lossy().to_string(),
+                duration: *duration,
+                file_size: *file_size,
+            }
+        })
+        .collect();
+
+    let chapter_repo = ChapterRepository::new(&pool);
+    let chapters = chapter_repo.create_multiple(chapter_dtos).await
+        .map_err(|e| format!("Failed to create chapters: {}", e))?;
+    audiobook.chapters_count = chapters.len() as i32;
+
+    if chapters.len() < urls.len() {
+        println!("URL IMPORT: Only {} of {} files downloaded for '{}'", chapters.len(), urls.len(), title);
+    }
+
+    import::record_fingerprints(&pool, &audiobook.id).await;
+    import::link_people(&pool, &audiobook.id).await;
+    println!("URL IMPORT: Imported '{}' with {} chapters", audiobook.title, chapters.len());
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    import::queue_validation(&audiobook.id);
+    Ok(audiobook)
+}
+
+/// LibriVox recordings matching the listener's taste that are not in the library
+#[tauri::command]
+pub async fn get_librivox_discoveries(state: State<'_, AppState>, limit: Option<usize>) -> Result<Vec<LibrivoxSuggestion>, String> {
+    let pool = with_pool(&state).await?;
+    LibrivoxDiscoveryService::new(&pool)
+        .map_err(|e| e.to_string())?
+        .discover(limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn download_librivox_book(
+    state: State<'_, AppState>,
+    archive_id: String,
+    zip_url: String
+) -> Result<serde_json::Value, String> {
+    println!("📥 LIBRIVOX BOOK: Downloading {} from {}", archive_id, zip_url);
+    
+    // Get the download manager from app state
+    let download_manager = download_manager(&state).await?;
+    
+    // Download individual files from Archive.org (better than ZIP for LibriVox)
+    match download_manager.download_archive_files(&archive_id).await {
+        Ok(result) => {
+            println!("LIBRIVOX BOOK: Download completed. Found {} audio files", result.extracted_files.len());
+            
+            if result.extracted_files.is_empty() {
+                return Err("No audio files found for this audiobook".to_string());
+            }
+            
+            // Return download result as JSON
+            let response = serde_json::json!({
+                "local_path": result.local_path.to_string_lossy(),
+                "file_count": result.extracted_files.len(),
+                "first_file": result.extracted_files.first()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+            
+            Ok(response)
+        }
+        Err(e) => {
+            println!("LIBRIVOX BOOK: Download failed: {}", e);
+            Err(format!("Failed to download LibriVox content: {}", e))
+        }
+    }
+}
+
+// Helper function to parse runtime strings like "1:23:45" into seconds
+fn parse_runtime_to_seconds(runtime: &str) -> Option<i64> {
+    let parts: Vec<&str> = runtime.split(':').collect();
+    match parts.len() {
+        1 => {
+            // Just minutes
+            parts[0].parse::<i64>().ok().map(|m| m * 60)
+        }
+        2 => {
+            // minutes:seconds
+            let minutes = parts[0].parse::<i64>().ok()?;
+            let seconds = parts[1].parse::<i64>().ok()?;
+            Some(minutes * 60 + seconds)
+        }
+        3 => {
+            // hours:minutes:seconds
+            let hours = parts[0].parse::<i64>().ok()?;
+            let minutes = parts[1].parse::<i64>().ok()?;
+            let seconds = parts[2].parse::<i64>().ok()?;
+            Some(hours * 3600 + minutes * 60 + seconds)
+        }
+        _ => None,
+    }
+}
+
+// Helper function to download cover images
+async fn download_cover_image(cover_url: &str, identifier: &str) -> Result<String, String> {
+    println!("📸 COVER: Downloading cover from: {}", cover_url);
+    
+    // Create covers directory in the app's public assets folder
+    // This should be accessible via file:// protocol for frontend
+    let covers_dir = storage::paths().covers_dir();
+    tokio::fs::create_dir_all(&covers_dir).await
+        .map_err(|e| format!("Failed to create covers directory: {}", e))?;
+    
+    // Determine file extension from URL or use jpg as default
+    let extension = if cover_url.ends_with(".png") { "png" } else { "jpg" };
+    let filename = format!("{}.{}", identifier, extension);
+    let file_path = covers_dir.join(&filename);
+    
+    // Download the image
+    let client = reqwest::Client::new();
+    let response = client.get(cover_url)
+        .header("User-Agent", "AudioVibe/1.0.0")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download cover: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Cover download failed with status: {}", response.status()));
+    }
+    
+    let bytes = response.bytes().await
+        .map_err(|e| format!("Failed to get cover bytes: {}", e))?;
+    
+    // Save to file
+    filesystem::atomic::write_async(&file_path, bytes.to_vec()).await
+        .map_err(|e| format!("Failed to save cover image: {:#}", e))?;
+    
+    // Convert to base64 data URL for immediate use
+    use base64::{Engine as _, engine::general_purpose};
+    let base64_data = general_purpose::STANDARD.encode(&bytes);
+    let mime_type = if extension == "png" { "image/png" } else { "image/jpeg" };
+    let data_url = format!("data:{};base64,{}", mime_type, base64_data);
+    
+    println!("COVER: Saved cover as base64 data URL (length: {} bytes)", bytes.len());
+    
+    Ok(data_url)
+}
@@ -0,0 +1,341 @@
+// Ebook commands: the ebook library, reading progress, bookmarks, annotations
+// and reader settings.
+
+use super::{AppState, path_roots, with_pool};
+use crate::ebook;
+use crate::database::models::*;
+use crate::services::AuthorService;
+use tauri::State;
+
+async fn link_ebook_author(pool: &sqlx::SqlitePool, ebook: &Ebook) {
+    if let Err(e) = AuthorService::new(pool).link_ebook(&ebook.id, ebook.author.as_deref()).await {
+        log::warn!("Failed to link author for ebook {}: {}", ebook.id, e);
+    }
+}
+
+#[tauri::command]
+pub async fn extract_ebook_metadata(state: State<'_, AppState>, file_path: String) -> Result<EbookMetadata, String> {
+    println!("EBOOK: Extracting metadata from: {}", file_path);
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+
+    use ebook::EbookMetadataExtractor;
+    let extractor = EbookMetadataExtractor::new();
+    extractor.extract_metadata(&file_path)
+        .map_err(|e| {
+            println!("EBOOK: Failed to extract metadata from {}: {}", file_path, e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn create_ebook(
+    state: State<'_, AppState>,
+    dto: CreateEbookDto
+) -> Result<Ebook, String> {
+    println!("EBOOK: Creating new ebook: {}", dto.title);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    path_roots(&state).await?.resolve("file_path", &dto.file_path)?;
+    let repo = EbookRepository::new(&pool);
+    let mut ebook = repo.create(dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to create ebook: {}", e);
+            e.to_string()
+        })?;
+
+    link_ebook_author(&pool, &ebook).await;
+    ebook.author_id = repo.find_by_id(&ebook.id).await.ok().flatten().and_then(|e| e.author_id);
+    Ok(ebook)
+}
+
+#[tauri::command]
+pub async fn get_all_ebooks(state: State<'_, AppState>) -> Result<Vec<Ebook>, String> {
+    println!("EBOOK: Fetching all ebooks");
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    let repo = EbookRepository::new(&pool);
+    repo.find_all()
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to fetch ebooks: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_ebook_by_id(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<Option<Ebook>, String> {
+    println!("EBOOK: Fetching ebook with id: {}", id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    let repo = EbookRepository::new(&pool);
+    repo.find_by_id(&id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to fetch ebook: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn update_ebook(
+    state: State<'_, AppState>,
+    id: String,
+    dto: UpdateEbookDto
+) -> Result<Ebook, String> {
+    println!("EBOOK: Updating ebook: {}", id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    let repo = EbookRepository::new(&pool);
+    let mut ebook = repo.update(&id, dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to update ebook: {}", e);
+            e.to_string()
+        })?;
+
+    link_ebook_author(&pool, &ebook).await;
+    ebook.author_id = repo.find_by_id(&ebook.id).await.ok().flatten().and_then(|e| e.author_id);
+    Ok(ebook)
+}
+
+#[tauri::command]
+pub async fn delete_ebook(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<(), String> {
+    println!("EBOOK: Deleting ebook: {}", id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    let repo = EbookRepository::new(&pool);
+    repo.delete(&id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to delete ebook: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn search_ebooks(
+    state: State<'_, AppState>,
+    query: String
+) -> Result<Vec<Ebook>, String> {
+    println!("EBOOK: Searching ebooks with query: {}", query);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::EbookRepository;
+    let repo = EbookRepository::new(&pool);
+    repo.search(&query)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to search ebooks: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn update_reading_progress(
+    state: State<'_, AppState>,
+    ebook_id: String,
+    dto: UpdateReadingProgressDto
+) -> Result<ReadingProgress, String> {
+    println!("EBOOK: Updating reading progress for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::ReadingProgressRepository;
+    let repo = ReadingProgressRepository::new(&pool);
+    repo.upsert(&ebook_id, dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to update reading progress: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_reading_progress(
+    state: State<'_, AppState>,
+    ebook_id: String
+) -> Result<Option<ReadingProgress>, String> {
+    println!("EBOOK: Getting reading progress for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::ReadingProgressRepository;
+    let repo = ReadingProgressRepository::new(&pool);
+    repo.find_by_ebook_id(&ebook_id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to get reading progress: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn create_bookmark(
+    state: State<'_, AppState>,
+    dto: CreateBookmarkDto
+) -> Result<EbookBookmark, String> {
+    println!("EBOOK: Creating bookmark for: {}", dto.ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::BookmarkRepository;
+    let repo = BookmarkRepository::new(&pool);
+    repo.create(dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to create bookmark: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_ebook_bookmarks(
+    state: State<'_, AppState>,
+    ebook_id: String
+) -> Result<Vec<EbookBookmark>, String> {
+    println!("EBOOK: Getting bookmarks for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::BookmarkRepository;
+    let repo = BookmarkRepository::new(&pool);
+    repo.find_by_ebook_id(&ebook_id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to get bookmarks: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn delete_bookmark(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<(), String> {
+    println!("EBOOK: Deleting bookmark: {}", id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::BookmarkRepository;
+    let repo = BookmarkRepository::new(&pool);
+    repo.delete(&id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to delete bookmark: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn create_annotation(
+    state: State<'_, AppState>,
+    dto: CreateAnnotationDto
+) -> Result<EbookAnnotation, String> {
+    println!("EBOOK: Creating annotation for: {}", dto.ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::AnnotationRepository;
+    let repo = AnnotationRepository::new(&pool);
+    repo.create(dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to create annotation: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_ebook_annotations(
+    state: State<'_, AppState>,
+    ebook_id: String
+) -> Result<Vec<EbookAnnotation>, String> {
+    println!("EBOOK: Getting annotations for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::AnnotationRepository;
+    let repo = AnnotationRepository::new(&pool);
+    repo.find_by_ebook_id(&ebook_id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to get annotations: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn delete_annotation(
+    state: State<'_, AppState>,
+    id: String
+) -> Result<(), String> {
+    println!("EBOOK: Deleting annotation: {}", id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::AnnotationRepository;
+    let repo = AnnotationRepository::new(&pool);
+    repo.delete(&id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to delete annotation: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn update_reader_settings(
+    state: State<'_, AppState>,
+    ebook_id: String,
+    dto: UpdateReaderSettingsDto
+) -> Result<EbookReaderSettings, String> {
+    println!("EBOOK: Updating reader settings for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::ReaderSettingsRepository;
+    let repo = ReaderSettingsRepository::new(&pool);
+    repo.upsert(&ebook_id, dto)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to update reader settings: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn get_reader_settings(
+    state: State<'_, AppState>,
+    ebook_id: String
+) -> Result<Option<EbookReaderSettings>, String> {
+    println!("EBOOK: Getting reader settings for: {}", ebook_id);
+
+    let pool = with_pool(&state).await?;
+
+    use ebook::ReaderSettingsRepository;
+    let repo = ReaderSettingsRepository::new(&pool);
+    repo.find_by_ebook_id(&ebook_id)
+        .await
+        .map_err(|e| {
+            println!("EBOOK: Failed to get reader settings: {}", e);
+            e.to_string()
+        })
+}
@@ -0,0 +1,1859 @@
+// Library commands: books and their chapters, search, authors and narrators,
+// follows, history, recommendations, tags and exports.
+
+use super::{AppState, download_manager, path_roots, with_pool};
+use crate::{audio, covers, download, events, export, filesystem, import, storage, validation};
+use crate::audio::books;
+use crate::audio::extract_audio_metadata;
+use crate::audio::tags::{TagValues, TagWriteResult};
+use crate::database::models::*;
+use crate::database::repository::*;
+use crate::download::DownloadManager;
+use crate::events::{AppEvent, LibraryChange};
+use crate::export::{ExportChapter, ExportMetadata, ExportResult, FolderExportProgress, M4bExportOptions};
+use crate::filesystem::{AudioFileInfo, FileSystemScanner};
+use crate::services::{audiobook_source_service, ActivityDay, ActivityService, AudiobookNote, AudiobookValidation, AuthorService, ChapterMarkerService, ChapterOrderService, CoverResolutionService, CoverResult, DeletedHistory, DigestService, FileValidationService, Follow, FollowKind, FollowService, HistoryRange, HomeFeedConfig, HomeFeedService, HomeShelf, import_preview_service, ImportPreview, ListeningEstimateService, MergedVersions, NarratorService, NoteService, PlayHistoryService, RandomPickService, RecommendationExplanation, RecommendationService, RecommendationWeights, ReleaseAlert, RetentionService, saved_search_service, SavedSearch, SavedSearchService, SearchHistoryEntry, SeriesEntry, SeriesService, SortPreferenceService, Suggestion, suggestion_service, SuggestionService, TasteProfile, VersionComparison, VersionService, WeeklyDigest};
+use tauri::State;
+use tauri_plugin_dialog::DialogExt;
+
+/// Covers may also be stored inline or as a web address; only local files
+/// have to live under a library folder
+fn check_cover_path(roots: &validation::PathRoots, cover: &str) -> Result<(), String> {
+    if cover.is_empty() || cover.starts_with("data:") || cover.starts_with("http://") || cover.starts_with("https://") {
+        return Ok(());
+    }
+    roots.resolve("cover_image_path", cover)?;
+    Ok(())
+}
+
+// Database commands
+#[tauri::command]
+pub async fn create_audiobook(
+    state: State<'_, AppState>,
+    dto: CreateAudiobookDto,
+) -> Result<Audiobook, String> {
+    let roots = path_roots(&state).await?;
+    roots.resolve("file_path", &dto.file_path)?;
+    if let Some(cover) = &dto.cover_image_path {
+        check_cover_path(&roots, cover)?;
+    }
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    let audiobook = repo.create(dto).await.map_err(|e| e.to_string())?;
+    import::record_fingerprints(&pool, &audiobook.id).await;
+    import::link_people(&pool, &audiobook.id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    import::queue_validation(&audiobook.id);
+    Ok(audiobook)
+}
+
+#[tauri::command]
+pub async fn get_incomplete_imports(state: State<'_, AppState>) -> Result<Vec<Audiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    AudiobookRepository::new(&pool).find_incomplete().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_audiobooks(state: State<'_, AppState>) -> Result<Vec<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let audiobooks = SortPreferenceService::new(&pool).library().await.map_err(|e| e.to_string())?;
+    let mut audiobooks = ListeningEstimateService::new(&pool).estimate_all(audiobooks).await.map_err(|e| e.to_string())?;
+    // Covers are fetched over the cover protocol instead of travelling as base64 in the list
+    for book in &mut audiobooks {
+        book.audiobook.cover_image_path = covers::list_cover_url(&book.audiobook);
+    }
+    Ok(audiobooks)
+}
+
+/// Library list without descriptions or other long fields; the detail view
+/// loads those per book through get_audiobook_details
+#[tauri::command]
+pub async fn get_audiobook_summaries(state: State<'_, AppState>) -> Result<Vec<AudiobookSummary>, String> {
+    let pool = with_pool(&state).await?;
+
+    let audiobooks = SortPreferenceService::new(&pool).library().await.map_err(|e| e.to_string())?;
+    let progress: std::collections::HashMap<String, PlaybackProgress> = PlaybackProgressRepository::new(&pool)
+        .find_all()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|progress| (progress.audiobook_id.clone(), progress))
+        .collect();
+
+    Ok(audiobooks
+        .iter()
+        .map(|audiobook| AudiobookSummary::new(audiobook, progress.get(&audiobook.id), covers::list_cover_url(audiobook)))
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_audiobook_details(state: State<'_, AppState>, id: String) -> Result<Option<AudiobookDetails>, String> {
+    let pool = with_pool(&state).await?;
+
+    let Some(audiobook) = AudiobookRepository::new(&pool).find_by_id(&id).await.map_err(|e| e.to_string())? else {
+        return Ok(None);
+    };
+    let mut audiobook = ListeningEstimateService::new(&pool).estimate(audiobook).await.map_err(|e| e.to_string())?;
+    audiobook.audiobook.cover_image_path = covers::list_cover_url(&audiobook.audiobook);
+    let chapters = ChapterRepository::new(&pool).find_by_audiobook_id(&id).await.map_err(|e| e.to_string())?;
+    let progress = PlaybackProgressRepository::new(&pool).find_by_audiobook_id(&id).await.map_err(|e| e.to_string())?;
+
+    Ok(Some(AudiobookDetails { audiobook, chapters, progress }))
+}
+
+#[tauri::command]
+pub async fn get_audiobook_by_id(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Option<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    match repo.find_by_id(&id).await.map_err(|e| e.to_string())? {
+        Some(audiobook) => ListeningEstimateService::new(&pool)
+            .estimate(audiobook)
+            .await
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Look for a cover through the fallback chain (embedded art, folder images,
+/// Archive.org, OpenLibrary) and store it with the source it came from
+#[tauri::command]
+pub async fn fetch_cover(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Option<CoverResult>, String> {
+    let pool = with_pool(&state).await?;
+
+    let cover = CoverResolutionService::new(&pool)
+        .fetch_cover(&audiobook_id, &storage::paths().covers_dir(), true)
+        .await
+        .map_err(|e| e.to_string())?;
+    if cover.is_some() {
+        events::emit(AppEvent::library_changed(LibraryChange::Updated, &audiobook_id));
+    }
+    Ok(cover)
+}
+
+#[tauri::command]
+pub async fn search_audiobooks(
+    state: State<'_, AppState>,
+    query: String,
+) -> Result<Vec<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    let audiobooks = repo.search(&query).await.map_err(|e| e.to_string())?;
+    if let Err(e) = SavedSearchService::new(&pool).record_search(&query).await {
+        log::warn!("Failed to record search: {}", e);
+    }
+    ListeningEstimateService::new(&pool).estimate_all(audiobooks).await.map_err(|e| e.to_string())
+}
+
+/// Type-ahead suggestions for a partly typed search: titles, authors, series,
+/// narrators and genre tags, best matches first
+#[tauri::command]
+pub async fn suggest_library(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<u32>,
+) -> Result<Vec<Suggestion>, String> {
+    let pool = with_pool(&state).await?;
+
+    SuggestionService::new(&pool)
+        .suggest(&query, limit.unwrap_or(suggestion_service::DEFAULT_SUGGESTION_LIMIT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_audiobooks_with_filters(
+    state: State<'_, AppState>,
+    filters: SearchFilters,
+) -> Result<Vec<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let query = filters.query.clone();
+    let repo = AudiobookRepository::new(&pool);
+    let audiobooks = repo.search_with_filters(filters).await.map_err(|e| e.to_string())?;
+    if let Some(query) = query {
+        if let Err(e) = SavedSearchService::new(&pool).record_search(&query).await {
+            log::warn!("Failed to record search: {}", e);
+        }
+    }
+    ListeningEstimateService::new(&pool).estimate_all(audiobooks).await.map_err(|e| e.to_string())
+}
+
+/// Recent search queries, newest first
+#[tauri::command]
+pub async fn get_search_history(state: State<'_, AppState>, limit: Option<u32>) -> Result<Vec<SearchHistoryEntry>, String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool)
+        .recent(limit.unwrap_or(saved_search_service::DEFAULT_HISTORY_LIMIT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Forget one recent search, or all of them without an id
+#[tauri::command]
+pub async fn clear_search_history(state: State<'_, AppState>, id: Option<String>) -> Result<u64, String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool).clear_history(id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// Save search filters under a name; an existing search with that name is replaced
+#[tauri::command]
+pub async fn save_search(
+    state: State<'_, AppState>,
+    name: String,
+    filters: SearchFilters,
+    pinned: Option<bool>,
+) -> Result<SavedSearch, String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool).save(&name, &filters, pinned).await.map_err(|e| format!("{:#}", e))
+}
+
+/// Saved searches, pinned ones first with their current book counts
+#[tauri::command]
+pub async fn list_saved_searches(state: State<'_, AppState>) -> Result<Vec<SavedSearch>, String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool).list().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn run_saved_search(state: State<'_, AppState>, id: String) -> Result<Vec<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+
+    let audiobooks = SavedSearchService::new(&pool).run(&id).await.map_err(|e| e.to_string())?;
+    ListeningEstimateService::new(&pool).estimate_all(audiobooks).await.map_err(|e| e.to_string())
+}
+
+/// Show a saved search next to the collections, or take it away again
+#[tauri::command]
+pub async fn set_saved_search_pinned(state: State<'_, AppState>, id: String, pinned: bool) -> Result<SavedSearch, String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool).set_pinned(&id, pinned).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    SavedSearchService::new(&pool).delete(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_distinct_authors(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    repo.get_distinct_authors().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_distinct_genres(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    repo.get_distinct_genres().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_distinct_narrators(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    repo.get_distinct_narrators().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_audiobook(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    repo.delete(&id).await.map_err(|e| e.to_string())?;
+    events::emit(AppEvent::library_changed(LibraryChange::Removed, &id));
+    Ok(())
+}
+
+/// Copies of the same book side by side, with the one worth keeping
+#[tauri::command]
+pub async fn compare_versions(state: State<'_, AppState>, ids: Vec<String>) -> Result<VersionComparison, String> {
+    let pool = with_pool(&state).await?;
+
+    VersionService::new(&pool).compare(&ids).await.map_err(|e| e.to_string())
+}
+
+/// Keep one copy of a book, moving the other's progress, bookmarks, notes and
+/// collections onto it before removing the other from the library
+#[tauri::command]
+pub async fn merge_versions(
+    state: State<'_, AppState>,
+    keep_id: String,
+    remove_id: String,
+) -> Result<MergedVersions, String> {
+    let pool = with_pool(&state).await?;
+
+    let merged = VersionService::new(&pool).merge(&keep_id, &remove_id).await.map_err(|e| e.to_string())?;
+    events::emit(AppEvent::library_changed(LibraryChange::Removed, &remove_id));
+    events::emit(AppEvent::library_changed(LibraryChange::Updated, &keep_id));
+    Ok(merged)
+}
+
+#[tauri::command]
+pub async fn rate_audiobook(
+    state: State<'_, AppState>,
+    id: String,
+    rating: Option<i32>
+) -> Result<(), String> {
+    if let Some(stars) = rating {
+        if !(1..=5).contains(&stars) {
+            return Err("Rating must be between 1 and 5".to_string());
+        }
+    }
+
+    let pool = with_pool(&state).await?;
+
+    let repo = AudiobookRepository::new(&pool);
+    let audiobook = repo.find_by_id(&id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Audiobook not found: {}", id))?;
+
+    repo.set_rating(&id, rating).await.map_err(|e| e.to_string())?;
+    RecommendationService::new(&pool)
+        .apply_rating_change(&audiobook, audiobook.rating, rating)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_review(
+    state: State<'_, AppState>,
+    id: String,
+    text: Option<String>
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let review = text.as_deref().map(str::trim).filter(|text| !text.is_empty());
+    AudiobookRepository::new(&pool).set_review(&id, review).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_continue_listening(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+) -> Result<Vec<AudiobookWithEstimate>, String> {
+    let pool = with_pool(&state).await?;
+
+    let audiobooks = PlaybackProgressRepository::new(&pool)
+        .find_in_progress(limit.unwrap_or(10))
+        .await
+        .map_err(|e| e.to_string())?;
+    ListeningEstimateService::new(&pool).estimate_all(audiobooks).await.map_err(|e| e.to_string())
+}
+
+
+#[tauri::command]
+pub async fn pick_random_audiobook(
+    state: State<'_, AppState>,
+    filters: Option<RandomPickFilters>,
+) -> Result<Option<Audiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    let picked = RandomPickService::new(&pool)
+        .pick(&filters.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(audiobook) = &picked {
+        println!("🎲 SURPRISE: Picked '{}'", audiobook.title);
+    }
+    Ok(picked)
+}
+
+#[tauri::command]
+pub async fn get_home_feed(state: State<'_, AppState>) -> Result<Vec<HomeShelf>, String> {
+    let pool = with_pool(&state).await?;
+
+    HomeFeedService::new(&pool)
+        .get_feed()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_home_feed_config(state: State<'_, AppState>) -> Result<HomeFeedConfig, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(HomeFeedService::new(&pool).load_config().await)
+}
+
+#[tauri::command]
+pub async fn set_home_feed_config(state: State<'_, AppState>, config: HomeFeedConfig) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    HomeFeedService::new(&pool)
+        .save_config(&config)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Decode every file of a book now and store what was found
+#[tauri::command]
+pub async fn validate_audiobook(state: State<'_, AppState>, audiobook_id: String) -> Result<AudiobookValidation, String> {
+    let pool = with_pool(&state).await?;
+    FileValidationService::new(&pool).validate_audiobook(&audiobook_id).await.map_err(|e| e.to_string())
+}
+
+/// What the last validation of a book's files found
+#[tauri::command]
+pub async fn get_audiobook_validation(state: State<'_, AppState>, audiobook_id: String) -> Result<AudiobookValidation, String> {
+    let pool = with_pool(&state).await?;
+    FileValidationService::new(&pool).for_audiobook(&audiobook_id).await.map_err(|e| e.to_string())
+}
+
+/// Put a book in a series at `position`; an empty or missing name takes it out
+#[tauri::command]
+pub async fn set_audiobook_series(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    name: Option<String>,
+    position: Option<f64>,
+) -> Result<Option<SeriesEntry>, String> {
+    let pool = with_pool(&state).await?;
+    SeriesService::new(&pool).set(&audiobook_id, name.as_deref(), position).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_audiobook_series(state: State<'_, AppState>, audiobook_id: String) -> Result<Option<SeriesEntry>, String> {
+    let pool = with_pool(&state).await?;
+    SeriesService::new(&pool).get(&audiobook_id).await.map_err(|e| e.to_string())
+}
+
+// File system commands
+#[tauri::command]
+pub async fn scan_directory(state: State<'_, AppState>, directory_path: String) -> Result<Vec<AudioFileInfo>, String> {
+    path_roots(&state).await?.resolve("directory_path", &directory_path)?;
+    events::emit(AppEvent::ScanStarted { path: directory_path.clone() });
+    let scanner = FileSystemScanner::new();
+    let path = std::path::Path::new(&directory_path);
+    let result = scanner.scan_directory(path);
+    events::emit(AppEvent::ScanFinished {
+        path: directory_path,
+        files_found: result.as_ref().map(|files| files.len()).unwrap_or(0),
+        error: result.as_ref().err().cloned(),
+    });
+    result
+}
+
+
+#[tauri::command]
+pub async fn get_file_info(state: State<'_, AppState>, file_path: String) -> Result<AudioFileInfo, String> {
+    let scanner = FileSystemScanner::new();
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    let path = std::path::Path::new(&file_path);
+    Ok(scanner.get_audio_file_info(path))
+}
+
+/// Let the user choose a folder to import. The chosen folder becomes a library
+/// root, which the import and any later playback of its files require.
+#[tauri::command]
+pub async fn pick_library_folder(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    title: Option<String>
+) -> Result<Option<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        let mut dialog = app.dialog().file();
+        if let Some(title) = title {
+            dialog = dialog.set_title(title);
+        }
+        dialog.blocking_pick_folder()
+    })
+    .await
+    .map_err(|e| format!("Folder dialog failed: {}", e))?;
+
+    let Some(folder) = picked else {
+        return Ok(None);
+    };
+    let folder = folder.into_path().map_err(|e| e.to_string())?;
+    import::add_library_root(&pool, &folder).await;
+    Ok(Some(folder.to_string_lossy().to_string()))
+}
+
+/// Let the user choose files to import, limited to `extensions` when given.
+/// The folders they are in become library roots.
+#[tauri::command]
+pub async fn pick_library_files(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    title: Option<String>,
+    extensions: Vec<String>,
+    multiple: bool
+) -> Result<Vec<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        let mut dialog = app.dialog().file();
+        if let Some(title) = title {
+            dialog = dialog.set_title(title);
+        }
+        if !extensions.is_empty() {
+            let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+            dialog = dialog.add_filter("Supported files", &extensions);
+        }
+        if multiple {
+            dialog.blocking_pick_files()
+        } else {
+            dialog.blocking_pick_file().map(|file| vec![file])
+        }
+    })
+    .await
+    .map_err(|e| format!("File dialog failed: {}", e))?;
+
+    let mut files = Vec::new();
+    for file in picked.unwrap_or_default() {
+        let file = file.into_path().map_err(|e| e.to_string())?;
+        import::add_library_root(&pool, &file).await;
+        files.push(file.to_string_lossy().to_string());
+    }
+    Ok(files)
+}
+
+#[tauri::command]
+pub async fn import_audiobook_from_files(
+    state: State<'_, AppState>,
+    file_paths: Vec<String>
+) -> Result<Audiobook, String> {
+    let scanner = FileSystemScanner::new();
+    let mut audio_files = Vec::new();
+    let roots = path_roots(&state).await?;
+    
+    // Get info for all files
+    for path_str in file_paths {
+        roots.resolve("file_paths", &path_str)?;
+        let path = std::path::Path::new(&path_str);
+        let file_info = scanner.get_audio_file_info(path);
+        if !file_info.is_valid {
+            return Err(format!("Invalid audio file: {}", file_info.error_message.unwrap_or_default()));
+        }
+        audio_files.push(file_info);
+    }
+
+    if audio_files.is_empty() {
+        return Err("No valid audio files provided".to_string());
+    }
+
+    // Use first file's metadata for audiobook info
+    let first_file = &audio_files[0];
+    let metadata = first_file.metadata.as_ref();
+    
+    // Calculate total duration
+    let total_duration: f64 = audio_files.iter()
+        .filter_map(|f| f.metadata.as_ref().and_then(|m| m.duration))
+        .sum();
+
+    // Create audiobook DTO
+    let dto = CreateAudiobookDto {
+        title: metadata
+            .and_then(|m| m.title.clone())
+            .or_else(|| Some(first_file.filename.clone().replace(&first_file.extension, "").trim_end_matches('.').to_string()))
+            .unwrap_or_else(|| "Unknown Title".to_string()),
+        author: Some(metadata
+            .and_then(|m| m.artist.clone())
+            .unwrap_or_else(|| "Unknown Author".to_string())),
+        narrator: None,
+        description: None,
+        genre: metadata.and_then(|m| m.genre.clone()),
+        file_path: first_file.path.clone(),
+        duration: Some((total_duration as i64).max(0)), // Convert float to int seconds
+        cover_image_path: None, // Filled in from embedded art below
+        source_type: Some(audiobook_source_service::SOURCE_LOCAL.to_string()),
+        source_id: Some(first_file.path.clone()),
+    };
+
+    // Save to database
+    let pool = with_pool(&state).await?;
+    
+    let repo = AudiobookRepository::new(&pool);
+    let mut audiobook = repo.create(dto).await.map_err(|e| e.to_string())?;
+    covers::apply_local_cover(&pool, &mut audiobook).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    import::queue_validation(&audiobook.id);
+    Ok(audiobook)
+}
+
+#[tauri::command]
+pub async fn import_audiobook_from_directory(
+    state: State<'_, AppState>,
+    directory_path: String
+) -> Result<Audiobook, String> {
+    // Get database pool
+    let pool = with_pool(&state).await?;
+
+    path_roots(&state).await?.resolve("directory_path", &directory_path)?;
+    import::import_directory(&pool, std::path::Path::new(&directory_path)).await
+}
+
+/// What importing a folder would create, without saving anything; the
+/// preview is imported with `confirm_import`
+#[tauri::command]
+pub async fn preview_directory_import(state: State<'_, AppState>, directory_path: String) -> Result<ImportPreview, String> {
+    path_roots(&state).await?.resolve("directory_path", &directory_path)?;
+    let audiobook_info = FileSystemScanner::new()
+        .analyze_audiobook_directory(std::path::Path::new(&directory_path))
+        .map_err(|e| format!("Failed to analyze directory: {}", e))?;
+
+    let mut preview = ImportPreview::from_directory(&audiobook_info);
+    preview.cover = import_preview_service::detect_folder_cover(&audiobook_info);
+    import_preview_service::store(&preview);
+    Ok(preview)
+}
+
+#[tauri::command]
+pub async fn import_audiobook_from_archive(
+    state: State<'_, AppState>,
+    archive_path: String
+) -> Result<Audiobook, String> {
+    path_roots(&state).await?.resolve("archive_path", &archive_path)?;
+    let archive = std::path::Path::new(&archive_path);
+    println!("📦 ARCHIVE IMPORT: Importing {}", archive.display());
+
+    if !archive.is_file() {
+        return Err(format!("Archive not found: {}", archive_path));
+    }
+    if !DownloadManager::is_supported_archive(archive) {
+        return Err("Unsupported archive format. Supported formats: .zip, .rar".to_string());
+    }
+
+    let download_manager = download_manager(&state).await?;
+
+    let pool = with_pool(&state).await?;
+
+    // Extract into the library folder, never on top of an earlier import of the same archive
+    let folder_name = archive.file_stem()
+        .map(|stem| stem.to_string_lossy()
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | ' ') { c } else { '_' })
+            .collect::<String>()
+            .trim()
+            .to_string())
+        .and_then(|name| download::safe_path::sanitize_file_name(&name))
+        .unwrap_or_else(|| "archive".to_string());
+    let library_dir = storage::paths().data_dir.join("library");
+    let mut extract_dir = library_dir.join(&folder_name);
+    if extract_dir.exists() {
+        extract_dir = library_dir.join(format!("{}_{}", folder_name, &uuid::Uuid::new_v4().simple().to_string()[..8]));
+    }
+
+    let result = async {
+        let audio_files = download_manager.extract_local_archive(archive, &extract_dir).await
+            .map_err(|e| format!("Failed to extract archive: {}", e))?;
+        if audio_files.is_empty() {
+            return Err("Archive does not contain any supported audio files".to_string());
+        }
+
+        let book_dir = archive_book_root(&extract_dir);
+        import::import_directory(&pool, &book_dir).await
+    }.await;
+
+    match result {
+        Ok(audiobook) => {
+            println!("✅ ARCHIVE IMPORT: Imported '{}' into {}", audiobook.title, extract_dir.display());
+            Ok(audiobook)
+        }
+        Err(e) => {
+            println!("❌ ARCHIVE IMPORT: {} - cleaning up {}", e, extract_dir.display());
+            if let Err(cleanup_error) = std::fs::remove_dir_all(&extract_dir) {
+                log::warn!("Failed to clean up {}: {}", extract_dir.display(), cleanup_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Archives usually wrap the book in one top-level folder; descend through
+/// single-folder levels until we reach the directory that holds the audio
+fn archive_book_root(extract_dir: &std::path::Path) -> std::path::PathBuf {
+    let scanner = FileSystemScanner::new();
+    let mut current = extract_dir.to_path_buf();
+
+    loop {
+        let Ok(entries) = std::fs::read_dir(&current) else { return current };
+        let mut subdirs = Vec::new();
+        let mut has_audio = false;
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                subdirs.push(path);
+            } else if scanner.is_supported_audio_file(&path) {
+                has_audio = true;
+            }
+        }
+
+        if has_audio || subdirs.len() != 1 {
+            return current;
+        }
+        current = subdirs.remove(0);
+    }
+}
+
+#[tauri::command]
+pub async fn find_cover_art(state: State<'_, AppState>, directory_path: String) -> Result<Option<String>, String> {
+    let scanner = FileSystemScanner::new();
+    let resolved = path_roots(&state).await?.resolve("directory_path", &directory_path)?;
+    let path = resolved.as_path();
+    
+    if let Some(cover_path) = scanner.find_cover_art(path) {
+        Ok(Some(filesystem::long_path::strip_extended(&cover_path).to_string_lossy().to_string()))
+    } else {
+        Ok(None)
+    }
+}
+
+#[tauri::command]
+pub async fn read_cover_image_as_base64(state: State<'_, AppState>, image_path: String) -> Result<String, String> {
+    use std::fs;
+    use base64::{Engine as _, engine::general_purpose};
+
+    // Read the image file
+    let resolved = path_roots(&state).await?.resolve("image_path", &image_path)?;
+    let image_data = fs::read(&resolved)
+        .map_err(|e| format!("Failed to read image file: {}", e))?;
+
+    // Determine MIME type from extension
+    let extension = std::path::Path::new(&image_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        _ => "image/jpeg", // default to jpeg
+    };
+
+    // Encode to base64
+    let base64_data = general_purpose::STANDARD.encode(&image_data);
+
+    // Return as data URL
+    Ok(format!("data:{};base64,{}", mime_type, base64_data))
+}
+
+// Chapter management commands
+#[tauri::command]
+pub async fn get_audiobook_chapters(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Vec<Chapter>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = ChapterRepository::new(&pool);
+    let chapters = repo.find_by_audiobook_id(&audiobook_id).await.map_err(|e| e.to_string())?;
+    
+    // If no chapters found, try to create them automatically
+    if chapters.is_empty() {
+        let audiobook_repo = AudiobookRepository::new(&pool);
+        if let Ok(Some(audiobook)) = audiobook_repo.find_by_id(&audiobook_id).await {
+            // Check if this looks like a TTS audiobook (has description mentioning TTS)
+            if audiobook.description.as_deref().unwrap_or("").contains("Text-to-Speech") {
+                println!("🔧 TTS: Auto-creating chapters for TTS audiobook: {}", audiobook.title);
+                return create_chapters_for_existing_tts_audiobook(&pool, &audiobook).await;
+            } else {
+                // Try to create chapters for LibriVox/regular audiobooks
+                println!("🔧 LIBRIVOX: Auto-creating chapters for LibriVox audiobook: {}", audiobook.title);
+                return create_chapters_for_librivox_audiobook(&pool, &audiobook).await;
+            }
+        }
+    }
+    
+    Ok(chapters)
+}
+
+async fn create_chapters_for_existing_tts_audiobook(
+    pool: &sqlx::SqlitePool,
+    audiobook: &Audiobook,
+) -> Result<Vec<Chapter>, String> {
+    println!("🔧 TTS: Creating missing chapters for TTS audiobook: {}", audiobook.title);
+
+    // First, check again if chapters already exist (race condition protection)
+    let chapter_repo = ChapterRepository::new(pool);
+    let existing = chapter_repo.find_by_audiobook_id(&audiobook.id).await.map_err(|e| e.to_string())?;
+    if !existing.is_empty() {
+        println!("🔧 TTS: Chapters already exist ({}), skipping creation", existing.len());
+        return Ok(existing);
+    }
+
+    let output_dir = std::path::Path::new(&audiobook.file_path);
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    // Scan for existing chunk files (each chunk becomes a chapter)
+    let mut chapter_files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(output_dir) {
+        for entry in entries.flatten() {
+            if let Some(file_name) = entry.file_name().to_str() {
+                if file_name.ends_with(".wav") && (file_name.starts_with("chapter_") || file_name.starts_with("chunk_")) {
+                    // Extract chapter and chunk numbers from filename
+                    if let Some(captures) = extract_chapter_chunk_numbers(file_name) {
+                        let (chapter_num, chunk_num) = captures;
+                        // Create a unique chapter number by combining chapter and chunk
+                        let unique_chapter_num = chapter_num * 1000 + chunk_num;
+                        chapter_files.push((unique_chapter_num, chapter_num, chunk_num, entry.path()));
+                    }
+                }
+            }
+        }
+    }
+    
+    // Sort by unique chapter number
+    chapter_files.sort_by_key(|&(unique_num, _, _, _)| unique_num);
+    
+    let chapter_repo = ChapterRepository::new(pool);
+    let mut created_chapters = Vec::new();
+    
+    for (unique_chapter_num, chapter_num, chunk_num, file_path) in &chapter_files {
+        let chapter_title = if *chunk_num == 1 && chapter_files.iter().filter(|(_, ch, _, _)| *ch == *chapter_num).count() == 1 {
+            format!("Chapter {}", chapter_num)
+        } else {
+            format!("Chapter {} - Part {}", chapter_num, chunk_num)
+        };
+
+        // Extract duration and file size from the actual audio file
+        let (duration, file_size) = match extract_audio_metadata(file_path) {
+            Ok(info) => (info.duration.map(|d| d as i64), Some(info.file_size as i64)),
+            Err(e) => {
+                println!("TTS: Could not extract metadata for {}: {}", chapter_title, e);
+                (None, None)
+            }
+        };
+
+        let chapter_dto = CreateChapterDto {
+            audiobook_id: audiobook.id.clone(),
+            chapter_number: *unique_chapter_num,
+            title: chapter_title.clone(),
+            file_path: file_path.to_string_lossy().to_string(),
+            duration,
+            file_size,
+        };
+
+        match chapter_repo.create(chapter_dto).await {
+            Ok(chapter) => {
+                let duration_str = duration.map(|d| format!("{}s", d)).unwrap_or_else(|| "unknown".to_string());
+                println!("TTS: Created missing chapter record: {} ({})", chapter_title, duration_str);
+                created_chapters.push(chapter);
+            },
+            Err(e) => {
+                println!("TTS: Failed to create chapter record '{}': {}", chapter_title, e);
+            }
+        }
+    }
+    
+    Ok(created_chapters)
+}
+
+async fn create_chapters_for_librivox_audiobook(
+    pool: &sqlx::SqlitePool,
+    audiobook: &Audiobook,
+) -> Result<Vec<Chapter>, String> {
+    println!("🔧 LIBRIVOX: Creating missing chapters for LibriVox audiobook: {}", audiobook.title);
+
+    // First, check again if chapters already exist (race condition protection)
+    let chapter_repo = ChapterRepository::new(pool);
+    let existing = chapter_repo.find_by_audiobook_id(&audiobook.id).await.map_err(|e| e.to_string())?;
+    if !existing.is_empty() {
+        println!("🔧 LIBRIVOX: Chapters already exist ({}), skipping creation", existing.len());
+        return Ok(existing);
+    }
+
+    let audio_dir = std::path::Path::new(&audiobook.file_path);
+    if !audio_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    // Scan for audio files in the directory (each file = one chapter)
+    let mut audio_files = Vec::new();
+    let mut has_m4b = false;
+    let mut m4b_file: Option<(String, std::path::PathBuf)> = None;
+
+    if let Ok(entries) = std::fs::read_dir(audio_dir) {
+        for entry in entries.flatten() {
+            if let Some(file_name) = entry.file_name().to_str() {
+                let lower_name = file_name.to_lowercase();
+
+                // Check for M4B files separately
+                if lower_name.ends_with(".m4b") {
+                    has_m4b = true;
+                    m4b_file = Some((file_name.to_string(), entry.path()));
+                    continue; // Don't add M4B to audio_files yet
+                }
+
+                // Add other audio files
+                if lower_name.ends_with(".mp3") || lower_name.ends_with(".wav") ||
+                   lower_name.ends_with(".m4a") || lower_name.ends_with(".ogg") {
+                    audio_files.push((file_name.to_string(), entry.path()));
+                }
+            }
+        }
+    }
+
+    // If we have both M4B and individual MP3/other files, prefer the individual files
+    // The M4B is likely a combined version of the same audiobook
+    if has_m4b && !audio_files.is_empty() {
+        println!("🔧 LIBRIVOX: Found both M4B and individual audio files ({} files). Using individual files only.", audio_files.len());
+    } else if has_m4b && audio_files.is_empty() {
+        // Only M4B file exists - this is a single-file audiobook
+        println!("🔧 LIBRIVOX: Found only M4B file, treating as single-file audiobook");
+        if let Some(m4b) = m4b_file {
+            audio_files.push(m4b);
+        }
+    }
+
+    if audio_files.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    // Sort files naturally (handles numbers properly)
+    audio_files.sort_by(|a, b| {
+        // Extract numbers from filename for proper sorting
+        let extract_number = |s: &str| -> i32 {
+            s.chars()
+                .filter(|c| c.is_digit(10))
+                .collect::<String>()
+                .parse::<i32>()
+                .unwrap_or(0)
+        };
+        
+        let num_a = extract_number(&a.0);
+        let num_b = extract_number(&b.0);
+        
+        if num_a != num_b {
+            num_a.cmp(&num_b)
+        } else {
+            a.0.cmp(&b.0) // fallback to alphabetical
+        }
+    });
+    
+    let chapter_repo = ChapterRepository::new(pool);
+    let mut created_chapters = Vec::new();
+    
+    for (index, (file_name, file_path)) in audio_files.iter().enumerate() {
+        // Create a nice chapter title from filename
+        let chapter_title = if file_name.to_lowercase().contains("chapter") {
+            // Remove file extension and clean up
+            file_name.rsplit('.').skip(1).collect::<Vec<_>>().join(".")
+                .replace("_", " ")
+                .replace("-", " ")
+        } else {
+            format!("Chapter {}", index + 1)
+        };
+
+        // Extract duration and file size from the actual audio file
+        let (duration, file_size) = match extract_audio_metadata(file_path) {
+            Ok(info) => (info.duration.map(|d| d as i64), Some(info.file_size as i64)),
+            Err(e) => {
+                println!("LIBRIVOX: Could not extract metadata for {}: {}", file_name, e);
+                (None, None)
+            }
+        };
+
+        let chapter_dto = CreateChapterDto {
+            audiobook_id: audiobook.id.clone(),
+            chapter_number: (index + 1) as i32,
+            title: chapter_title,
+            file_path: file_path.to_string_lossy().to_string(),
+            duration,
+            file_size,
+        };
+
+        match chapter_repo.create(chapter_dto).await {
+            Ok(chapter) => {
+                let duration_str = duration.map(|d| format!("{}s", d)).unwrap_or_else(|| "unknown".to_string());
+                println!("LIBRIVOX: Created chapter: {} -> {} ({})", index + 1, file_name, duration_str);
+                created_chapters.push(chapter);
+            }
+            Err(e) => {
+                println!("LIBRIVOX: Failed to create chapter {}: {}", index + 1, e);
+            }
+        }
+    }
+    
+    println!("🎉 LIBRIVOX: Created {} chapters for {}", created_chapters.len(), audiobook.title);
+    Ok(created_chapters)
+}
+
+fn extract_chapter_chunk_numbers(filename: &str) -> Option<(i32, i32)> {
+    // Handle patterns like "chapter_1_chunk_1.wav"
+    if let Some(stripped) = filename.strip_prefix("chapter_").and_then(|s| s.strip_suffix(".wav")) {
+        let parts: Vec<&str> = stripped.split('_').collect();
+        if parts.len() >= 3 && parts[1] == "chunk" {
+            if let (Ok(chapter), Ok(chunk)) = (parts[0].parse::<i32>(), parts[2].parse::<i32>()) {
+                return Some((chapter, chunk));
+            }
+        }
+    }
+    // Handle patterns like "chunk_1.wav" (treat as chapter 1, chunk 1)
+    else if let Some(stripped) = filename.strip_prefix("chunk_").and_then(|s| s.strip_suffix(".wav")) {
+        if let Ok(chunk) = stripped.parse::<i32>() {
+            return Some((1, chunk));
+        }
+    }
+    None
+}
+
+/// Split a single-file book into evenly spaced pseudo-chapters, replacing any
+/// generated earlier. Hand-placed markers are kept.
+#[tauri::command]
+pub async fn auto_chapterize(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    interval_minutes: u32,
+) -> Result<Vec<ChapterMarker>, String> {
+    let pool = with_pool(&state).await?;
+
+    println!("📑 CHAPTERS: Auto-chapterizing {} every {} minutes", audiobook_id, interval_minutes);
+    ChapterMarkerService::new(&pool)
+        .auto_chapterize(&audiobook_id, interval_minutes)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_chapter_marker(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    position: i64,
+    title: Option<String>,
+) -> Result<ChapterMarker, String> {
+    let pool = with_pool(&state).await?;
+
+    ChapterMarkerService::new(&pool)
+        .add_marker(&audiobook_id, position, title)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_chapter_markers(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Vec<ChapterMarker>, String> {
+    let pool = with_pool(&state).await?;
+
+    ChapterMarkerRepository::new(&pool)
+        .find_by_audiobook_id(&audiobook_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_chapter_marker(state: State<'_, AppState>, marker_id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    ChapterMarkerRepository::new(&pool)
+        .delete(&marker_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_audiobook_note(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    position: Option<i64>,
+    body: String,
+) -> Result<AudiobookNote, String> {
+    let pool = with_pool(&state).await?;
+
+    NoteService::new(&pool)
+        .create(&audiobook_id, position, &body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Save the transcript around `position` as a quote; fails for books without
+/// a transcript there
+#[tauri::command]
+pub async fn capture_quote(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    position: i64,
+) -> Result<AudiobookNote, String> {
+    let pool = with_pool(&state).await?;
+
+    NoteService::new(&pool)
+        .capture_quote(&audiobook_id, position)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_audiobook_note(
+    state: State<'_, AppState>,
+    note_id: String,
+    position: Option<i64>,
+    body: String,
+) -> Result<AudiobookNote, String> {
+    let pool = with_pool(&state).await?;
+
+    NoteService::new(&pool)
+        .update(&note_id, position, &body)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_audiobook_note(state: State<'_, AppState>, note_id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    NoteService::new(&pool)
+        .delete(&note_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_audiobook_notes(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Vec<AudiobookNote>, String> {
+    let pool = with_pool(&state).await?;
+
+    NoteService::new(&pool)
+        .list(&audiobook_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Write a book's notes, or only its captured quotes, to `path` as Markdown
+#[tauri::command]
+pub async fn export_audiobook_notes(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    path: String,
+    quotes_only: Option<bool>,
+) -> Result<String, String> {
+    let pool = with_pool(&state).await?;
+
+    let markdown = NoteService::new(&pool)
+        .export_markdown(&audiobook_id, quotes_only.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+    filesystem::atomic::write_async(std::path::Path::new(&path), markdown).await
+        .map_err(|e| format!("Failed to write notes file: {:#}", e))?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn get_chapter_by_number(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    chapter_number: i32,
+) -> Result<Option<Chapter>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = ChapterRepository::new(&pool);
+    repo.get_chapter_by_number(&audiobook_id, chapter_number).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_chapters_for_audiobook(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Vec<Chapter>, String> {
+    let pool = with_pool(&state).await?;
+    
+    // Get the audiobook
+    let audiobook_repo = AudiobookRepository::new(&pool);
+    let audiobook = audiobook_repo.find_by_id(&audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("Audiobook not found")?;
+    
+    println!("📁 CHAPTERS: Creating chapters for audiobook: {}", audiobook.title);
+    
+    // Check if chapters already exist
+    let chapter_repo = ChapterRepository::new(&pool);
+    let existing_chapters = chapter_repo.find_by_audiobook_id(&audiobook_id).await
+        .map_err(|e| e.to_string())?;
+    
+    if !existing_chapters.is_empty() {
+        println!("📁 CHAPTERS: Chapters already exist ({})", existing_chapters.len());
+        return Ok(existing_chapters);
+    }
+    
+    // Analyze the directory
+    let scanner = FileSystemScanner::new();
+    let directory = std::path::Path::new(&audiobook.file_path);
+    
+    if !directory.is_dir() {
+        return Err("Audiobook file path is not a directory".to_string());
+    }
+    
+    let audiobook_info = scanner.analyze_audiobook_directory(directory)
+        .map_err(|e| format!("Failed to analyze directory: {}", e))?;
+    
+    if !audiobook_info.is_multi_file || audiobook_info.chapters.is_empty() {
+        return Err("Not a multi-file audiobook or no chapters found".to_string());
+    }
+    
+    // Create chapter records
+    let chapter_dtos: Vec<CreateChapterDto> = audiobook_info.chapters.iter()
+        .map(|ch| CreateChapterDto {
+            audiobook_id: audiobook.id.clone(),
+            chapter_number: ch.chapter_number,
+            title: ch.title.clone(),
+            file_path: ch.file_path.clone(),
+            duration: ch.duration.map(|d| d as i64),
+            file_size: Some(ch.file_size as i64),
+        })
+        .collect();
+    
+    let chapters = chapter_repo.create_multiple(chapter_dtos).await
+        .map_err(|e| format!("Failed to create chapters: {}", e))?;
+    
+    println!("CHAPTERS: Created {} chapters for audiobook: {}", chapters.len(), audiobook.title);
+    Ok(chapters)
+}
+
+/// Put a book's chapters in the order the user dragged them into and keep
+/// that order through later rescans. Progress and the play queue follow.
+#[tauri::command]
+pub async fn reorder_chapters(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    ordered_chapter_ids: Vec<String>
+) -> Result<Vec<Chapter>, String> {
+    let pool = with_pool(&state).await?;
+    let chapters = ChapterOrderService::new(&pool).reorder(&audiobook_id, &ordered_chapter_ids).await
+        .map_err(|e| e.to_string())?;
+    books::requeue_reordered_book(&pool, &audiobook_id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Updated, &audiobook_id));
+    Ok(chapters)
+}
+
+/// Unlocking lets the next rescan sort the chapters by file name again
+#[tauri::command]
+pub async fn set_chapter_order_locked(state: State<'_, AppState>, audiobook_id: String, locked: bool) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+    ChapterOrderService::new(&pool).set_locked(&audiobook_id, locked).await.map_err(|e| e.to_string())?;
+    events::emit(AppEvent::library_changed(LibraryChange::Updated, &audiobook_id));
+    Ok(())
+}
+
+/// Add chapters for files that appeared in a folder book's folder since it
+/// was imported
+#[tauri::command]
+pub async fn rescan_chapters(state: State<'_, AppState>, audiobook_id: String) -> Result<Vec<Chapter>, String> {
+    let pool = with_pool(&state).await?;
+    let chapters = ChapterOrderService::new(&pool).rescan(&audiobook_id).await.map_err(|e| e.to_string())?;
+    books::requeue_reordered_book(&pool, &audiobook_id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Updated, &audiobook_id));
+    Ok(chapters)
+}
+
+#[tauri::command]
+pub async fn get_sort_preference(state: State<'_, AppState>, scope: SortScope) -> Result<SortPreference, String> {
+    let pool = with_pool(&state).await?;
+    SortPreferenceService::new(&pool).get(&scope).await.map_err(|e| e.to_string())
+}
+
+/// Store how the library or a collection is sorted; every open view is told to re-sort
+#[tauri::command]
+pub async fn set_sort_preference(state: State<'_, AppState>, scope: SortScope, sort: SortPreference) -> Result<SortPreference, String> {
+    let pool = with_pool(&state).await?;
+    let sort = SortPreferenceService::new(&pool).set(&scope, sort).await.map_err(|e| e.to_string())?;
+    events::emit(AppEvent::SortPreferenceChanged { scope, sort });
+    Ok(sort)
+}
+
+#[tauri::command]
+pub async fn get_all_authors(state: State<'_, AppState>) -> Result<Vec<Author>, String> {
+    let pool = with_pool(&state).await?;
+
+    AuthorRepository::new(&pool).find_all().await.map_err(|e| e.to_string())
+}
+
+/// Author page data; bio and photo are looked up on OpenLibrary the first time
+#[tauri::command]
+pub async fn get_author(state: State<'_, AppState>, id: String) -> Result<Option<AuthorDetails>, String> {
+    let pool = with_pool(&state).await?;
+
+    let service = AuthorService::new(&pool);
+    let needs_details = AuthorRepository::new(&pool).find_by_id(&id).await
+        .map_err(|e| e.to_string())?
+        .map(|author| author.details_fetched_at.is_none())
+        .unwrap_or(false);
+    if needs_details {
+        if let Err(e) = service.fetch_openlibrary_details(&id).await {
+            log::warn!("OpenLibrary lookup failed for author {}: {}", id, e);
+        }
+    }
+
+    service.get_details(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_author_books(state: State<'_, AppState>, id: String) -> Result<AuthorBooks, String> {
+    let pool = with_pool(&state).await?;
+
+    AuthorService::new(&pool).get_books(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_all_narrators(state: State<'_, AppState>) -> Result<Vec<Narrator>, String> {
+    let pool = with_pool(&state).await?;
+
+    NarratorRepository::new(&pool).find_all().await.map_err(|e| e.to_string())
+}
+
+/// "More by this narrator": every audiobook linked to the narrator
+#[tauri::command]
+pub async fn get_narrator_books(state: State<'_, AppState>, narrator_id: String) -> Result<Vec<Audiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    NarratorService::new(&pool).get_books(&narrator_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_favorite_narrator(
+    state: State<'_, AppState>,
+    narrator_id: String,
+    is_favorite: bool
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    NarratorRepository::new(&pool).set_favorite(&narrator_id, is_favorite).await.map_err(|e| e.to_string())
+}
+
+/// Follow an author or genre for release alerts; following twice is harmless
+#[tauri::command]
+pub async fn follow(state: State<'_, AppState>, kind: FollowKind, name: String) -> Result<Follow, String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).follow(kind, &name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_follow(state: State<'_, AppState>, id: String, name: String) -> Result<Follow, String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).rename(&id, &name).await.map_err(|e| format!("{:#}", e))
+}
+
+#[tauri::command]
+pub async fn unfollow(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).unfollow(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_follows(state: State<'_, AppState>) -> Result<Vec<Follow>, String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).list().await.map_err(|e| e.to_string())
+}
+
+/// Release alerts not dismissed yet, newest first
+#[tauri::command]
+pub async fn get_release_alerts(state: State<'_, AppState>) -> Result<Vec<ReleaseAlert>, String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).pending_alerts().await.map_err(|e| e.to_string())
+}
+
+/// Dismiss one release alert, or all of them without an id
+#[tauri::command]
+pub async fn dismiss_release_alerts(state: State<'_, AppState>, id: Option<String>) -> Result<u64, String> {
+    let pool = with_pool(&state).await?;
+
+    FollowService::new(&pool).dismiss_alerts(id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_play_history(
+    state: State<'_, AppState>,
+    page: Option<i64>,
+    page_size: Option<i64>
+) -> Result<PlayHistoryPage, String> {
+    let pool = with_pool(&state).await?;
+
+    PlayHistoryService::new(&pool)
+        .get_play_history(page.unwrap_or(0), page_size.unwrap_or(0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_history(
+    state: State<'_, AppState>,
+    range: Option<HistoryRange>
+) -> Result<DeletedHistory, String> {
+    let range = range.unwrap_or_default();
+    println!("🗑️ PRIVACY: Deleting history from {:?} to {:?}", range.from, range.to);
+
+    let pool = with_pool(&state).await?;
+
+    PlayHistoryService::new(&pool).delete_history(&range).await.map_err(|e| e.to_string())
+}
+
+/// Listening per day between two YYYY-MM-DD dates, inclusive
+#[tauri::command]
+pub async fn get_daily_listening(
+    state: State<'_, AppState>,
+    from: String,
+    to: String
+) -> Result<Vec<DailyListening>, String> {
+    let pool = with_pool(&state).await?;
+
+    RetentionService::new(&pool).daily_listening(&from, &to).await.map_err(|e| e.to_string())
+}
+
+/// Minutes listened and read on each day of the year, for the activity calendar
+#[tauri::command]
+pub async fn get_activity_heatmap(state: State<'_, AppState>, year: i32) -> Result<Vec<ActivityDay>, String> {
+    let pool = with_pool(&state).await?;
+
+    ActivityService::new(&pool).heatmap(year).await.map_err(|e| e.to_string())
+}
+
+/// The most recent weekly digest, or None before the first one is compiled
+#[tauri::command]
+pub async fn get_latest_digest(state: State<'_, AppState>) -> Result<Option<WeeklyDigest>, String> {
+    let pool = with_pool(&state).await?;
+    DigestService::new(&pool).latest().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn generate_recommendations(
+    state: State<'_, AppState>,
+    limit: Option<i32>
+) -> Result<Vec<RecommendationWithAudiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    let recommendation_service = RecommendationService::new(&pool);
+    recommendation_service.generate_recommendations(limit).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_current_recommendations(
+    state: State<'_, AppState>,
+    limit: Option<i32>
+) -> Result<Vec<RecommendationWithAudiobook>, String> {
+    let pool = with_pool(&state).await?;
+
+    let recommendation_service = RecommendationService::new(&pool);
+    recommendation_service.get_current_recommendations(limit).await.map_err(|e| e.to_string())
+}
+
+/// What a recommendation's score was built from
+#[tauri::command]
+pub async fn get_recommendation_explanation(state: State<'_, AppState>, id: String) -> Result<RecommendationExplanation, String> {
+    let pool = with_pool(&state).await?;
+    RecommendationService::new(&pool).explain(&id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_recommendation_weights(state: State<'_, AppState>) -> Result<RecommendationWeights, String> {
+    let pool = with_pool(&state).await?;
+    RecommendationService::new(&pool).weights().await.map_err(|e| e.to_string())
+}
+
+/// Tune how much genres, authors, narrators and similar books count in new recommendations
+#[tauri::command]
+pub async fn set_recommendation_weights(state: State<'_, AppState>, weights: RecommendationWeights) -> Result<RecommendationWeights, String> {
+    let pool = with_pool(&state).await?;
+    RecommendationService::new(&pool).set_weights(weights).await.map_err(|e| e.to_string())
+}
+
+/// Onboarding for a new library: seed the taste preferences with the picked
+/// genres and authors so recommendations work before anything is played
+#[tauri::command]
+pub async fn submit_taste_profile(state: State<'_, AppState>, genres: Vec<String>, authors: Vec<String>) -> Result<TasteProfile, String> {
+    let pool = with_pool(&state).await?;
+    RecommendationService::new(&pool)
+        .submit_taste_profile(TasteProfile { genres, authors })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn is_recommendation_cold_start(state: State<'_, AppState>) -> Result<bool, String> {
+    let pool = with_pool(&state).await?;
+    RecommendationService::new(&pool).is_cold_start().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn submit_recommendation_feedback(
+    state: State<'_, AppState>,
+    dto: CreateRecommendationFeedbackDto
+) -> Result<RecommendationFeedback, String> {
+    let pool = with_pool(&state).await?;
+
+    let recommendation_service = RecommendationService::new(&pool);
+    recommendation_service.submit_recommendation_feedback(dto).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_listening_stats(
+    state: State<'_, AppState>
+) -> Result<std::collections::HashMap<String, f64>, String> {
+    let pool = with_pool(&state).await?;
+
+    let recommendation_service = RecommendationService::new(&pool);
+    recommendation_service.get_listening_stats().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn extract_thumbnail(identifier: String) -> Result<Option<String>, String> {
+    use std::process::Command;
+    use std::env;
+    
+    // Get the path to the extractThumbnail.py script
+    let current_dir = env::current_dir().map_err(|e| e.to_string())?;
+    let python_script = current_dir.parent()
+        .and_then(|p| p.parent())
+        .map(|p| p.join("extractThumbnail.py"))
+        .ok_or("Could not find extractThumbnail.py script")?;
+    
+    if !python_script.exists() {
+        return Err("extractThumbnail.py not found".to_string());
+    }
+    
+    // Run the Python script to extract thumbnail info
+    let output = Command::new("python")
+        .arg(python_script)
+        .arg("--identifier")
+        .arg(&identifier)
+        .arg("--info-only")
+        .output()
+        .map_err(|e| format!("Failed to run Python script: {}", e))?;
+    
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Python script failed: {}", error));
+    }
+    
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    
+    // Parse the output to extract the best cover URL
+    // The Python script should output JSON with cover file info
+    if let Ok(cover_info) = serde_json::from_str::<serde_json::Value>(&output_str) {
+        if let Some(cover_files) = cover_info.get("cover_files").and_then(|cf| cf.as_array()) {
+            if let Some(first_cover) = cover_files.first() {
+                if let Some(url) = first_cover.get("url").and_then(|u| u.as_str()) {
+                    return Ok(Some(url.to_string()));
+                }
+            }
+        }
+    }
+    
+    Ok(None)
+}
+
+#[tauri::command]
+pub async fn update_audiobook_file_path(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    file_path: String
+) -> Result<(), String> {
+    println!("📝 UPDATE: Updating audiobook {} file path to: {}", audiobook_id, file_path);
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    
+    // Get database pool
+    let pool = with_pool(&state).await?;
+    
+    // Update the audiobook's file_path
+    sqlx::query("UPDATE audiobooks SET file_path = ? WHERE id = ?")
+        .bind(&file_path)
+        .bind(&audiobook_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update audiobook file path: {}", e))?;
+    
+    println!("UPDATE: Successfully updated audiobook file path");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_audiobook(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    updates: std::collections::HashMap<String, String>
+) -> Result<(), String> {
+    println!("📝 UPDATE: Updating audiobook {} with {} fields", audiobook_id, updates.len());
+
+    if updates.contains_key("file_path") || updates.contains_key("cover_image_path") {
+        let roots = path_roots(&state).await?;
+        if let Some(file_path) = updates.get("file_path") {
+            roots.resolve("file_path", file_path)?;
+        }
+        if let Some(cover) = updates.get("cover_image_path") {
+            check_cover_path(&roots, cover)?;
+        }
+    }
+
+    // Get database pool
+    let pool = with_pool(&state).await?;
+
+    // Build dynamic UPDATE query
+    let mut query_parts = Vec::new();
+    let mut values: Vec<String> = Vec::new();
+
+    for (key, value) in updates.iter() {
+        // Only allow safe field names to prevent SQL injection
+        let safe_key = match key.as_str() {
+            "title" | "author" | "narrator" | "description" | "genre" |
+            "file_path" | "cover_image_path" | "duration" | "language" => key.as_str(),
+            _ => return Err(format!("Invalid field name: {}", key))
+        };
+
+        query_parts.push(format!("{} = ?", safe_key));
+        values.push(value.clone());
+    }
+
+    if query_parts.is_empty() {
+        return Err("No valid fields to update".to_string());
+    }
+
+    // Add updated_at field
+    query_parts.push("updated_at = ?".to_string());
+    values.push(chrono::Utc::now().to_rfc3339());
+
+    let query_str = format!("UPDATE audiobooks SET {} WHERE id = ?", query_parts.join(", "));
+    values.push(audiobook_id.clone());
+
+    println!("📝 UPDATE: Query: {}", query_str);
+
+    let mut query = sqlx::query(&query_str);
+    for value in values {
+        query = query.bind(value);
+    }
+
+    query.execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update audiobook: {}", e))?;
+
+    println!("UPDATE: Successfully updated audiobook");
+
+    if updates.contains_key("author") || updates.contains_key("narrator") {
+        import::link_people(&pool, &audiobook_id).await;
+    }
+
+    // Optionally mirror metadata fixes into the audio files themselves
+    let touches_tags = updates.keys().any(|key| matches!(key.as_str(), "title" | "author" | "narrator" | "genre"));
+    let write_on_edit = PreferencesRepository::new(&pool).get_bool(PREF_WRITE_TAGS_ON_EDIT, false).await.unwrap_or(false);
+    if touches_tags && write_on_edit {
+        if let Err(e) = write_audiobook_tags(&pool, &audiobook_id).await {
+            println!("⚠️ UPDATE: Could not write tags to audio files: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+const PREF_WRITE_TAGS_ON_EDIT: &str = "tags.write_on_edit";
+
+fn tag_backup_dir(audiobook_id: &str) -> Result<std::path::PathBuf, String> {
+    Ok(storage::paths().data_dir.join("tag_backups").join(audiobook_id))
+}
+
+/// Write the library metadata of an audiobook into its audio files, backing up the original tags first
+async fn write_audiobook_tags(pool: &sqlx::SqlitePool, audiobook_id: &str) -> Result<TagWriteResult, String> {
+    let (metadata, chapters) = load_export_source(pool, audiobook_id).await?;
+    let is_single_file = chapters.len() == 1;
+
+    let targets: Vec<(std::path::PathBuf, TagValues)> = chapters.iter().enumerate()
+        .map(|(index, chapter)| {
+            let values = TagValues {
+                title: Some(if is_single_file { metadata.title.clone() } else { chapter.title.clone() }),
+                artist: metadata.author.clone(),
+                album: Some(metadata.title.clone()),
+                album_artist: metadata.author.clone(),
+                narrator: metadata.narrator.clone(),
+                genre: metadata.genre.clone(),
+                comment: None,
+                track: if is_single_file { None } else { Some((index + 1) as u32) },
+                track_total: if is_single_file { None } else { Some(chapters.len() as u32) },
+            };
+            (chapter.file_path.clone(), values)
+        })
+        .collect();
+
+    let audiobook_id = audiobook_id.to_string();
+    let backup_dir = tag_backup_dir(&audiobook_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        audio::tags::write_tags_with_backup(&audiobook_id, &targets, &backup_dir)
+    })
+    .await
+    .map_err(|e| format!("Tag writing task failed: {}", e))?
+    .map_err(|e| format!("Failed to write tags: {}", e))
+}
+
+#[tauri::command]
+pub async fn write_tags(state: State<'_, AppState>, audiobook_id: String) -> Result<TagWriteResult, String> {
+    println!("🏷️ TAGS: Writing tags for audiobook {}", audiobook_id);
+
+    let pool = with_pool(&state).await?;
+
+    let result = write_audiobook_tags(&pool, &audiobook_id).await?;
+    println!("TAGS: Wrote {} files, {} failed", result.files_written, result.failed_files.len());
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn restore_tags_backup(audiobook_id: String) -> Result<usize, String> {
+    println!("🏷️ TAGS: Restoring original tags for audiobook {}", audiobook_id);
+
+    let backup_dir = tag_backup_dir(&audiobook_id)?;
+    tauri::async_runtime::spawn_blocking(move || audio::tags::restore_latest_backup(&backup_dir))
+        .await
+        .map_err(|e| format!("Tag restore task failed: {}", e))?
+        .map_err(|e| format!("Failed to restore tags: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_chapter_file_path(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    chapter_number: i32,
+    file_path: String
+) -> Result<(), String> {
+    println!("📝 UPDATE CHAPTER: Updating chapter {} file path to: {}", chapter_number, file_path);
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    
+    // Get database pool
+    let pool = with_pool(&state).await?;
+    
+    // Update the chapter's file_path
+    sqlx::query("UPDATE chapters SET file_path = ? WHERE audiobook_id = ? AND chapter_number = ?")
+        .bind(&file_path)
+        .bind(&audiobook_id)
+        .bind(&chapter_number)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update chapter file path: {}", e))?;
+    
+    println!("UPDATE CHAPTER: Successfully updated chapter file path");
+    Ok(())
+}
+
+// ============= EXPORT COMMANDS =============
+
+/// Collect the tags and ordered source files needed to export an audiobook
+async fn load_export_source(
+    pool: &sqlx::SqlitePool,
+    audiobook_id: &str
+) -> Result<(ExportMetadata, Vec<ExportChapter>), String> {
+    let audiobook = AudiobookRepository::new(pool).find_by_id(audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Audiobook not found: {}", audiobook_id))?;
+
+    let mut chapters = ChapterRepository::new(pool).find_by_audiobook_id(audiobook_id).await
+        .map_err(|e| e.to_string())?;
+    chapters.sort_by_key(|chapter| chapter.chapter_number);
+
+    let export_chapters: Vec<ExportChapter> = if chapters.is_empty() {
+        // Single-file audiobooks have no chapter rows; export the book file itself
+        let book_path = std::path::PathBuf::from(&audiobook.file_path);
+        if !book_path.is_file() {
+            return Err("Audiobook has no chapters and its file path is not an audio file".to_string());
+        }
+        vec![ExportChapter {
+            title: audiobook.title.clone(),
+            file_path: book_path,
+            duration_seconds: audiobook.duration,
+        }]
+    } else {
+        chapters.into_iter()
+            .map(|chapter| ExportChapter {
+                title: chapter.title,
+                file_path: std::path::PathBuf::from(chapter.file_path),
+                duration_seconds: chapter.duration,
+            })
+            .collect()
+    };
+
+    let metadata = ExportMetadata {
+        title: audiobook.title,
+        author: audiobook.author,
+        narrator: audiobook.narrator,
+        genre: audiobook.genre,
+        description: audiobook.description,
+        cover_path: audiobook.cover_image_path.map(std::path::PathBuf::from),
+    };
+
+    Ok((metadata, export_chapters))
+}
+
+#[tauri::command]
+pub async fn export_audiobook_as_m4b(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    dest: String,
+    options: Option<M4bExportOptions>
+) -> Result<ExportResult, String> {
+    println!("📦 EXPORT: Exporting audiobook {} as M4B to {}", audiobook_id, dest);
+
+    let pool = with_pool(&state).await?;
+
+    let (metadata, chapters) = load_export_source(&pool, &audiobook_id).await?;
+    export::export_m4b(&metadata, &chapters, std::path::Path::new(&dest), &options.unwrap_or_default())
+        .await
+        .map_err(|e| format!("Failed to export M4B: {}", e))
+}
+
+#[tauri::command]
+pub async fn export_audiobook_to_folder(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    dest: String,
+    structure_template: Option<String>
+) -> Result<ExportResult, String> {
+    println!("📤 EXPORT: Exporting audiobook {} to folder {}", audiobook_id, dest);
+
+    let pool = with_pool(&state).await?;
+
+    let (metadata, chapters) = load_export_source(&pool, &audiobook_id).await?;
+    let template = structure_template.unwrap_or_else(|| export::folder::DEFAULT_STRUCTURE_TEMPLATE.to_string());
+
+    // Copying can take minutes on slow USB devices; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut last_percent = None;
+        export::export_to_folder(
+            &audiobook_id,
+            &metadata,
+            &chapters,
+            std::path::Path::new(&dest),
+            &template,
+            |progress: FolderExportProgress| {
+                // Emit at most once per percent to avoid flooding the frontend
+                let percent = (progress.bytes_copied * 100).checked_div(progress.total_bytes).unwrap_or(100);
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    events::emit(AppEvent::FolderExportProgress(progress));
+                }
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Failed to export audiobook: {}", e))
+}
@@ -0,0 +1,62 @@
+// The Tauri command handlers, grouped by what they work on, and the app state
+// they share. Handlers get at the database through `with_pool`, which waits on
+// an async lock, so no guard is ever held across an await. Background tasks
+// such as the maintenance scheduler and the watchers use the same helpers
+// through `app.state::<AppState>()`.
+
+pub mod app;
+pub mod collections;
+pub mod downloads;
+pub mod ebooks;
+pub mod library;
+pub mod playback;
+pub mod tts;
+
+use crate::database::DatabaseManager;
+use crate::download::{DownloadManager, DownloadThrottle};
+use crate::services::LibraryRootService;
+use crate::storage;
+use crate::validation::PathRoots;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Holds the database manager, set once initialize_app has opened the
+/// database, and the download manager, set during warm-up
+#[derive(Default)]
+pub struct AppState {
+    pub db: RwLock<Option<DatabaseManager>>,
+    pub download_manager: RwLock<Option<DownloadManager>>,
+}
+
+/// The database pool, once the database is open
+pub async fn with_pool(state: &AppState) -> Result<SqlitePool, String> {
+    let db = state.db.read().await;
+    let db = db.as_ref().ok_or("Database not initialized")?;
+    db.get_pool().cloned().map_err(|e| e.to_string())
+}
+
+/// The download manager, once warm-up has started it
+pub async fn download_manager(state: &AppState) -> Result<DownloadManager, String> {
+    let manager = state.download_manager.read().await;
+    manager.as_ref().cloned().ok_or_else(|| "Download manager not initialized".to_string())
+}
+
+/// The throttle shared by all downloads, once warm-up has started the download manager
+pub async fn download_throttle(state: &AppState) -> Option<Arc<DownloadThrottle>> {
+    let manager = state.download_manager.read().await;
+    manager.as_ref().map(|manager| manager.throttle().clone())
+}
+
+/// Where file paths from the frontend may point: the library roots, the app's
+/// data folder and the download cache
+pub async fn path_roots(state: &AppState) -> Result<PathRoots, String> {
+    let pool = with_pool(state).await?;
+
+    let mut roots = LibraryRootService::new(&pool).roots().await.map_err(|e| e.to_string())?;
+    roots.push(storage::paths().data_dir.clone());
+    if let Some(manager) = state.download_manager.read().await.as_ref() {
+        roots.push(manager.cache_dir().to_path_buf());
+    }
+    Ok(PathRoots::new(roots))
+}
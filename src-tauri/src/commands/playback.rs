@@ -0,0 +1,1358 @@
+// Playback commands. The audio thread in audio::thread owns the output; these send it
+// commands and keep progress, queues and player settings in the database.
+
+use super::{AppState, download_manager, path_roots, with_pool};
+use crate::{audio, casting, filesystem, sharing, storage, validation};
+use crate::audio::{AudioInfo, books, focus as audio_focus, output as audio_output, PlaybackStatus, settings, Track, watchdog as audio_watchdog};
+use crate::audio::thread::{self, AudioCommand};
+use crate::audio::ambience::{Ambience, AmbienceStatus, AmbienceTrack};
+use crate::audio::ducking::DuckingSettings;
+use crate::audio::focus::AudioFocusSettings;
+use crate::audio::live_status::LiveStatus;
+use crate::audio::metrics::AudioMetrics;
+use crate::audio::output::{AudioCapabilities, AudioInitReport, OutputDiagnostics};
+use crate::audio::seek_history::SeekHistoryEntry;
+use crate::audio::voice_boost::VoiceBoostSettings;
+use crate::casting::session::{self as cast_session, CastControl};
+use crate::database::models::*;
+use crate::database::repository::*;
+use crate::services::{audiobook_source_service, ChapterError, ChapterErrorService, ChapterPosition, CollectionQueueService, EndOfBookAction, EndOfBookService, PlayerState, PlayHistoryService, RecommendationService, SpeedDirection, SpeedPresets, SpeedPresetService, VoiceBoost, VoiceBoostService};
+use std::sync::mpsc;
+use tauri::State;
+
+// Re-apply voice boost for the loaded file after a setting changed
+async fn apply_current_voice_boost(pool: &sqlx::SqlitePool) {
+    let current_file = thread::running_sender().and_then(|sender| {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::GetStatus { response: response_sender }).ok()?;
+        response_receiver.recv().ok()?.current_file
+    });
+    if let Some(file_path) = current_file {
+        books::apply_book_voice_boost(pool, &file_path).await;
+    }
+}
+
+#[tauri::command]
+pub async fn update_playback_progress(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    dto: UpdatePlaybackProgressDto,
+) -> Result<PlaybackProgress, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = PlaybackProgressRepository::new(&pool);
+    repo.create_or_update(&audiobook_id, dto).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_playback_progress(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<Option<PlaybackProgress>, String> {
+    let pool = with_pool(&state).await?;
+    
+    let repo = PlaybackProgressRepository::new(&pool);
+    repo.find_by_audiobook_id(&audiobook_id).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn mark_abandoned(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    reason: Option<String>,
+) -> Result<PlaybackProgress, String> {
+    set_abandoned_state(&state, &audiobook_id, true, reason).await
+}
+
+#[tauri::command]
+pub async fn unmark_abandoned(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+) -> Result<PlaybackProgress, String> {
+    set_abandoned_state(&state, &audiobook_id, false, None).await
+}
+
+async fn set_abandoned_state(
+    state: &State<'_, AppState>,
+    audiobook_id: &str,
+    abandoned: bool,
+    reason: Option<String>,
+) -> Result<PlaybackProgress, String> {
+    let pool = with_pool(state).await?;
+
+    let audiobook = AudiobookRepository::new(&pool).find_by_id(audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Audiobook not found: {}", audiobook_id))?;
+
+    let repo = PlaybackProgressRepository::new(&pool);
+    let was_abandoned = repo.find_by_audiobook_id(audiobook_id).await
+        .map_err(|e| e.to_string())?
+        .map(|progress| progress.is_abandoned)
+        .unwrap_or(false);
+
+    let reason = reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+    let progress = repo.set_abandoned(audiobook_id, abandoned, reason).await.map_err(|e| e.to_string())?;
+
+    if was_abandoned != abandoned {
+        println!("📕 ABANDON: '{}' abandoned = {}", audiobook.title, abandoned);
+        RecommendationService::new(&pool)
+            .apply_abandon_change(&audiobook, abandoned)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(progress)
+}
+
+// Audio control commands
+#[tauri::command]
+pub async fn load_audio_file(state: State<'_, AppState>, file_path: String) -> Result<(), String> {
+    println!("LOAD: Loading and playing audio file: {}", file_path);
+    log::info!("LOAD: Loading audio file: {}", file_path);
+    
+    // First, verify the path exists
+    let path_exists = std::path::Path::new(&file_path).exists();
+    let is_file = std::path::Path::new(&file_path).is_file();
+    let is_dir = std::path::Path::new(&file_path).is_dir();
+    
+    println!("LOAD: Path analysis - exists: {}, is_file: {}, is_dir: {}", path_exists, is_file, is_dir);
+    
+    if !path_exists {
+        let error_msg = match filesystem::volume::mount_root(std::path::Path::new(&file_path)).filter(|root| !filesystem::volume::is_mounted(root)) {
+            Some(root) => format!("The drive this book is on is not connected: {}", root.display()),
+            None => format!("File or directory does not exist: {}", file_path),
+        };
+        println!("LOAD: {}", error_msg);
+        return Err(error_msg);
+    }
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    
+    // Check if this is a LibriVox URL (contains archive.org and .zip) or a local LibriVox directory
+    if file_path.contains("archive.org") && file_path.contains(".zip") {
+        println!("🌐 LIBRIVOX: Detected LibriVox URL, using download system");
+        
+        // Get the download manager from app state
+        let download_manager = download_manager(&state).await?;
+        
+        // Download and extract the LibriVox ZIP file
+        match download_manager.download_and_extract_zip(&file_path).await {
+            Ok(result) => {
+                println!("LIBRIVOX: Download completed. Found {} audio files", result.extracted_files.len());
+                
+                if result.extracted_files.is_empty() {
+                    return Err("No audio files found in the downloaded archive".to_string());
+                }
+                
+                // Sort files to get consistent ordering (usually chapter order)
+                let mut files = result.extracted_files;
+                files.sort();
+                
+                // Use the first audio file
+                let first_file = &files[0];
+                let local_file_path = first_file.to_string_lossy().to_string();
+                
+                println!("LIBRIVOX: Using local file: {}", local_file_path);
+                
+                // Now load the local file using the standard audio system
+                let sender = thread::sender()?;
+                let (response_sender, response_receiver) = mpsc::channel();
+                
+                sender.send(AudioCommand::LoadFile { 
+                    file_path: local_file_path, 
+                    response: response_sender 
+                }).map_err(|e| format!("Failed to send load command: {}", e))?;
+                
+                response_receiver.recv()
+                    .map_err(|e| format!("Failed to receive response: {}", e))?
+            }
+            Err(e) => {
+                println!("LIBRIVOX: Download failed: {}", e);
+                Err(format!("Failed to download LibriVox content: {}", e))
+            }
+        }
+    } else if std::path::Path::new(&file_path).is_dir() {
+        // Handle local LibriVox directory (files already downloaded)
+        println!("📁 LIBRIVOX LOCAL: Detected local LibriVox directory: {}", file_path);
+        
+        use std::fs;
+        
+        // Find all audio files in the directory
+        let mut audio_files = Vec::new();
+        let mut m4b_files = Vec::new();
+
+        match fs::read_dir(&file_path) {
+            Ok(entries) => {
+                for entry in entries {
+                    if let Ok(entry) = entry {
+                        let path = entry.path();
+                        if let Some(extension) = path.extension() {
+                            let ext = extension.to_string_lossy().to_lowercase();
+
+                            // Separate M4B files from other audio files
+                            if ext == "m4b" {
+                                m4b_files.push(path);
+                            } else if matches!(ext.as_str(), "mp3" | "m4a" | "aac" | "flac" | "wav" | "ogg" | "opus" | "wma") {
+                                audio_files.push(path);
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("LIBRIVOX LOCAL: Failed to read directory '{}': {}", file_path, e);
+                return Err(format!("Failed to read LibriVox directory '{}': {}", file_path, e));
+            }
+        }
+
+        // If we have both M4B and other audio files, prefer the other files
+        // The M4B is likely a combined version that may be corrupt or metadata-only
+        if !m4b_files.is_empty() && !audio_files.is_empty() {
+            println!("📁 LIBRIVOX LOCAL: Found both M4B ({}) and individual audio files ({}). Using individual files only.",
+                m4b_files.len(), audio_files.len());
+        } else if !m4b_files.is_empty() && audio_files.is_empty() {
+            // Only M4B exists - use it
+            println!("📁 LIBRIVOX LOCAL: Found only M4B files, using them");
+            audio_files = m4b_files;
+        }
+
+        // Sort files for consistent ordering (usually gives us proper chapter order)
+        audio_files.sort();
+        
+        if audio_files.is_empty() {
+            println!("LIBRIVOX LOCAL: No audio files found in directory: {}", file_path);
+            return Err(format!("No audio files found in LibriVox directory: {}", file_path));
+        }
+        
+        // Play the first audio file
+        let first_file = audio_files[0].to_string_lossy().to_string();
+        println!("LIBRIVOX LOCAL: Playing first file: {}", first_file);
+        println!("LIBRIVOX LOCAL: Found {} total audio files", audio_files.len());
+        
+        // Check if we need to create chapter records for this audiobook
+        // We'll do this synchronously to avoid lifetime issues
+        if audio_files.len() > 1 {
+            println!("📁 CHAPTERS: Detected multi-file audiobook, will create chapters on next navigation");
+        }
+        
+        let sender = thread::sender()?;
+        let (response_sender, response_receiver) = mpsc::channel();
+        
+        sender.send(AudioCommand::LoadFile { 
+            file_path: first_file, 
+            response: response_sender 
+        }).map_err(|e| format!("Failed to send load command: {}", e))?;
+        
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))?
+    } else {
+        // Standard local file loading
+        let sender = thread::sender()?;
+        let (response_sender, response_receiver) = mpsc::channel();
+        
+        sender.send(AudioCommand::LoadFile { file_path, response: response_sender })
+            .map_err(|e| format!("Failed to send load command: {}", e))?;
+        
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))?
+    }
+}
+
+#[tauri::command]
+pub async fn play_audio() -> Result<(), String> {
+    println!("🟢 PLAY: Starting play command");
+    log::info!("🟢 PLAY: Starting play command");
+    if let Some(result) = cast_session::control(CastControl::Play).await {
+        return result.map_err(|e| e.to_string());
+    }
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::Play { response: response_sender })
+        .map_err(|e| format!("Failed to send play command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn pause_audio() -> Result<(), String> {
+    println!("⏸️ PAUSE: Pausing audio");
+    if let Some(result) = cast_session::control(CastControl::Pause).await {
+        return result.map_err(|e| e.to_string());
+    }
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::Pause { response: response_sender })
+        .map_err(|e| format!("Failed to send pause command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn stop_audio() -> Result<(), String> {
+    println!("🛑 STOP: Stopping audio");
+    if let Some(result) = cast_session::control(CastControl::Stop).await {
+        return result.map_err(|e| e.to_string());
+    }
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::Stop { response: response_sender })
+        .map_err(|e| format!("Failed to send stop command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn set_volume(volume: f32) -> Result<(), String> {
+    println!("🔊 VOLUME: Setting volume: {}", volume);
+    validation::validate_volume(volume)?;
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::SetVolume { volume, response: response_sender })
+        .map_err(|e| format!("Failed to send volume command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn set_playback_speed(speed: f32) -> Result<(), String> {
+    println!("⏩ SPEED: Setting speed: {}", speed);
+    validation::validate_speed(speed)?;
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::SetSpeed { speed, response: response_sender })
+        .map_err(|e| format!("Failed to send speed command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn set_preserve_pitch(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    println!("⏩ SPEED: Pitch preservation {}", if enabled { "on" } else { "off" });
+
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(settings::PREF_PRESERVE_PITCH, if enabled { "true" } else { "false" })
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::set_preserve_pitch(enabled);
+
+    // An audio thread that has not started yet picks the setting up when it does
+    if let Some(sender) = thread::running_sender() {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::SetPreservePitch { enabled, response: response_sender })
+            .map_err(|e| format!("Failed to send pitch command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_preserve_pitch() -> Result<bool, String> {
+    Ok(settings::preserve_pitch())
+}
+
+/// Move on to the next chapter when one fails to load, instead of stopping there
+#[tauri::command]
+pub async fn set_skip_bad_chapters(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(settings::PREF_SKIP_BAD_CHAPTERS, if enabled { "true" } else { "false" })
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::set_skip_bad_chapters(enabled);
+
+    if let Some(sender) = thread::running_sender() {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::SetSkipBadChapters { enabled, response: response_sender })
+            .map_err(|e| format!("Failed to send skip command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_skip_bad_chapters() -> Result<bool, String> {
+    Ok(settings::skip_bad_chapters())
+}
+
+/// How long after a playback-stalled event the file is reloaded at the place
+/// audio stopped; 0 only reports stalls. Returns the stored value.
+#[tauri::command]
+pub async fn set_stall_grace_seconds(state: State<'_, AppState>, seconds: u64) -> Result<u64, String> {
+    let pool = with_pool(&state).await?;
+
+    let seconds = seconds.min(audio_watchdog::MAX_GRACE_SECONDS);
+    PreferencesRepository::new(&pool)
+        .set(settings::PREF_STALL_GRACE_SECONDS, &seconds.to_string())
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::set_stall_grace_seconds(seconds);
+    Ok(seconds)
+}
+
+#[tauri::command]
+pub async fn get_stall_grace_seconds() -> Result<u64, String> {
+    Ok(settings::stall_grace_seconds())
+}
+
+/// Chapters of a book that failed to load and have not played since
+#[tauri::command]
+pub async fn get_chapter_errors(state: State<'_, AppState>, audiobook_id: String) -> Result<Vec<ChapterError>, String> {
+    let pool = with_pool(&state).await?;
+    ChapterErrorService::new(&pool).for_audiobook(&audiobook_id).await.map_err(|e| e.to_string())
+}
+
+/// Keep the system from sleeping while audio is playing. Takes effect on the
+/// audio thread's next command, which status polling provides within a second.
+#[tauri::command]
+pub async fn set_keep_awake(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    println!("💡 KEEP AWAKE: {}", if enabled { "enabled" } else { "disabled" });
+
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(settings::PREF_KEEP_AWAKE, if enabled { "true" } else { "false" })
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::set_keep_awake(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_keep_awake() -> Result<bool, String> {
+    Ok(settings::keep_awake())
+}
+
+/// Fixed output buffer size in frames, or None for the device default.
+/// Larger buffers add latency but stop crackling on busy or slow machines.
+#[tauri::command]
+pub async fn set_audio_buffer_size(state: State<'_, AppState>, buffer_frames: Option<u32>) -> Result<(), String> {
+    if let Some(frames) = buffer_frames {
+        if !(64..=audio_output::MAX_BUFFER_FRAMES).contains(&frames) {
+            return Err(format!("Buffer size must be between 64 and {} frames", audio_output::MAX_BUFFER_FRAMES));
+        }
+    }
+    println!("🔊 OUTPUT: Setting buffer size to {:?}", buffer_frames);
+
+    let pool = with_pool(&state).await?;
+
+    PreferencesRepository::new(&pool)
+        .set(settings::PREF_OUTPUT_BUFFER_FRAMES, &buffer_frames.unwrap_or(0).to_string())
+        .await
+        .map_err(|e| e.to_string())?;
+    settings::set_output_buffer_frames(buffer_frames.unwrap_or(0));
+
+    if let Some(sender) = thread::running_sender() {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::SetOutputSettings { settings: settings::output_settings(), response: response_sender })
+            .map_err(|e| format!("Failed to send output command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_audio_output_diagnostics() -> Result<OutputDiagnostics, String> {
+    let Some(sender) = thread::running_sender() else {
+        return Ok(OutputDiagnostics {
+            buffer_frames: settings::output_settings().buffer_frames,
+            underrun_count: 0,
+            rebuild_count: 0,
+            last_error: None,
+        });
+    };
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetOutputDiagnostics { response: response_sender })
+        .map_err(|e| format!("Failed to send diagnostics command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
+
+/// Load, decode-start and seek timings and underruns of this run, for
+/// comparing playback performance between releases
+#[tauri::command]
+pub async fn get_audio_metrics() -> Result<AudioMetrics, String> {
+    let Some(sender) = thread::running_sender() else {
+        return Ok(AudioMetrics::default());
+    };
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetMetrics { response: response_sender })
+        .map_err(|e| format!("Failed to send metrics command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
+
+/// Play the first 30 seconds of a LibriVox book without importing it. Takes the
+/// Archive.org identifier or the book's ZIP URL. The sample plays on its own
+/// sink over the current book, whose queue and progress are left alone.
+#[tauri::command]
+pub async fn preview_librivox(state: State<'_, AppState>, identifier: String) -> Result<(), String> {
+    let identifier = audiobook_source_service::archive_identifier(&identifier).unwrap_or(identifier);
+    if identifier.is_empty() || !identifier.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+        return Err(format!("Invalid Archive.org identifier: {}", identifier));
+    }
+    let download_manager = download_manager(&state).await?;
+
+    println!("🎧 PREVIEW: Fetching sample of {}", identifier);
+    let file_path = download_manager.download_preview(&identifier).await
+        .map_err(|e| format!("Failed to fetch preview: {:#}", e))?;
+
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::StartPreview { file_path: file_path.to_string_lossy().to_string(), response: response_sender })
+        .map_err(|e| format!("Failed to send preview command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn stop_preview() -> Result<(), String> {
+    // Nothing can be previewing before the audio thread has started
+    let Some(sender) = thread::running_sender() else {
+        return Ok(());
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::StopPreview { response: response_sender })
+        .map_err(|e| format!("Failed to send preview command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+/// Built-in soundscapes and the loops in the ambience folder
+#[tauri::command]
+pub async fn list_ambience_tracks() -> Result<Vec<AmbienceTrack>, String> {
+    let dir = storage::paths().ambience_dir();
+    // Created up front so there is a folder to drop loops into
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create ambience folder: {}", e))?;
+    Ok(audio::ambience::list_tracks(&dir))
+}
+
+/// Play background ambience under the book, or stop it when `track` is None.
+/// `track` is a built-in key from `list_ambience_tracks` or the path of a loop.
+#[tauri::command]
+pub async fn set_ambience(track: Option<String>, volume: f32) -> Result<(), String> {
+    validation::validate_volume(volume)?;
+    let ambience = match track {
+        Some(track) => {
+            let ambience = Ambience::from_track(&track).map_err(|e| e.to_string())?;
+            if let Ambience::Loop(path) = &ambience {
+                validation::canonical_path("track", &path.to_string_lossy())?;
+            }
+            Some(ambience)
+        }
+        None => None,
+    };
+
+    // Turning ambience off needs no audio thread
+    let sender = match ambience {
+        Some(_) => thread::sender()?,
+        None => match thread::running_sender() {
+            Some(sender) => sender,
+            None => return Ok(()),
+        },
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::SetAmbience { ambience, volume, response: response_sender })
+        .map_err(|e| format!("Failed to send ambience command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_ambience() -> Result<AmbienceStatus, String> {
+    let Some(sender) = thread::running_sender() else {
+        return Ok(AmbienceStatus { track: None, volume: 0.0 });
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetAmbience { response: response_sender })
+        .map_err(|e| format!("Failed to send ambience command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
+
+/// Turn voice boost (compression for noisy places) on or off, for one book or
+/// for all books without their own setting
+#[tauri::command]
+pub async fn set_voice_boost(
+    state: State<'_, AppState>,
+    enabled: bool,
+    amount: f32,
+    audiobook_id: Option<String>,
+) -> Result<VoiceBoost, String> {
+    let pool = with_pool(&state).await?;
+    let boost = VoiceBoostService::new(&pool)
+        .set(VoiceBoostSettings { enabled, amount }, audiobook_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+    apply_current_voice_boost(&pool).await;
+    Ok(boost)
+}
+
+#[tauri::command]
+pub async fn get_voice_boost(state: State<'_, AppState>, audiobook_id: Option<String>) -> Result<VoiceBoost, String> {
+    let pool = with_pool(&state).await?;
+    VoiceBoostService::new(&pool).get(audiobook_id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// Drop a book's own voice boost setting so it follows the global one again
+#[tauri::command]
+pub async fn reset_book_voice_boost(state: State<'_, AppState>, audiobook_id: String) -> Result<VoiceBoost, String> {
+    let pool = with_pool(&state).await?;
+    let boost = VoiceBoostService::new(&pool).reset_book(&audiobook_id).await.map_err(|e| e.to_string())?;
+    apply_current_voice_boost(&pool).await;
+    Ok(boost)
+}
+
+/// What happens when a book's last chapter plays out
+#[tauri::command]
+pub async fn set_end_of_book_action(state: State<'_, AppState>, action: EndOfBookAction) -> Result<EndOfBookAction, String> {
+    let pool = with_pool(&state).await?;
+    EndOfBookService::new(&pool).set_action(action).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_end_of_book_action(state: State<'_, AppState>) -> Result<EndOfBookAction, String> {
+    let pool = with_pool(&state).await?;
+    EndOfBookService::new(&pool).action().await.map_err(|e| e.to_string())
+}
+
+/// How far and how fast ambience dips under the narration
+#[tauri::command]
+pub async fn set_ducking(state: State<'_, AppState>, settings: DuckingSettings) -> Result<DuckingSettings, String> {
+    let settings = settings.clamped();
+    let pool = with_pool(&state).await?;
+
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    PreferencesRepository::new(&pool).set(settings::PREF_DUCKING, &json).await.map_err(|e| e.to_string())?;
+    settings::set_ducking(settings.clone());
+
+    // An audio thread that has not started yet picks the settings up when it does
+    if let Some(sender) = thread::running_sender() {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::SetDucking { settings: settings.clone(), response: response_sender })
+            .map_err(|e| format!("Failed to send ducking command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_ducking() -> Result<DuckingSettings, String> {
+    Ok(settings::ducking())
+}
+
+/// What happens when another application plays audio or a call comes in
+#[tauri::command]
+pub async fn set_audio_focus(state: State<'_, AppState>, settings: AudioFocusSettings) -> Result<AudioFocusSettings, String> {
+    if !settings.duck_db.is_finite() || settings.duck_db < 0.0 {
+        return Err("Duck depth must be a positive number of dB".to_string());
+    }
+    let pool = with_pool(&state).await?;
+
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    PreferencesRepository::new(&pool).set(audio_focus::PREF_AUDIO_FOCUS, &json).await.map_err(|e| e.to_string())?;
+    settings::set_audio_focus(settings.clone());
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_audio_focus() -> Result<AudioFocusSettings, String> {
+    Ok(settings::audio_focus())
+}
+
+/// Start audio output on the given device (or the saved/system default) and
+/// report the devices and formats available. Failures come back in the report
+/// rather than as an error so the UI can offer a retry.
+#[tauri::command]
+pub async fn init_audio(state: State<'_, AppState>, device_id: Option<String>) -> Result<AudioInitReport, String> {
+    println!("🔊 INIT AUDIO: Requested device {:?}", device_id);
+
+    let capabilities = match audio_output::probe_output_devices() {
+        Ok(capabilities) => capabilities,
+        Err(e) => {
+            return Ok(AudioInitReport {
+                initialized: false,
+                device: None,
+                error: Some(format!("{:#}", e)),
+                capabilities: None,
+            });
+        }
+    };
+    let failed = |error: String, capabilities: AudioCapabilities| AudioInitReport {
+        initialized: false,
+        device: None,
+        error: Some(error),
+        capabilities: Some(capabilities),
+    };
+
+    if capabilities.devices.is_empty() {
+        return Ok(failed("No audio output device found".to_string(), capabilities));
+    }
+
+    if let Some(device_id) = &device_id {
+        if !capabilities.devices.iter().any(|device| &device.id == device_id) {
+            return Ok(failed(format!("Audio device '{}' is not available", device_id), capabilities));
+        }
+        settings::set_output_device(Some(device_id.clone()));
+
+        let pool = with_pool(&state).await.ok();
+        if let Some(pool) = pool {
+            if let Err(e) = PreferencesRepository::new(&pool).set(settings::PREF_OUTPUT_DEVICE, device_id).await {
+                log::warn!("Failed to save output device: {}", e);
+            }
+        }
+    }
+
+    let result = match thread::running_sender() {
+        // Already running: move the stream to the chosen device
+        Some(sender) => {
+            let (response_sender, response_receiver) = mpsc::channel();
+            sender.send(AudioCommand::SetOutputSettings { settings: settings::output_settings(), response: response_sender })
+                .map_err(|e| format!("Failed to send output command: {}", e))?;
+            response_receiver.recv()
+                .map_err(|e| format!("Failed to receive response: {}", e))?
+        }
+        None => thread::sender().map(|_| ()),
+    };
+    if let Err(e) = result {
+        return Ok(failed(e, capabilities));
+    }
+
+    // A saved device that has since been unplugged falls back to the default
+    let selected = settings::output_device();
+    let device = capabilities.devices.iter()
+        .find(|device| selected.as_deref() == Some(device.id.as_str()))
+        .or_else(|| capabilities.devices.iter().find(|device| device.is_default))
+        .map(|device| device.name.clone());
+
+    Ok(AudioInitReport {
+        initialized: true,
+        device,
+        error: None,
+        capabilities: Some(capabilities),
+    })
+}
+
+#[tauri::command]
+pub async fn list_speed_presets(state: State<'_, AppState>, audiobook_id: Option<String>) -> Result<SpeedPresets, String> {
+    let pool = with_pool(&state).await?;
+
+    SpeedPresetService::new(&pool)
+        .get(audiobook_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_speed_preset(
+    state: State<'_, AppState>,
+    speed: f32,
+    audiobook_id: Option<String>,
+) -> Result<SpeedPresets, String> {
+    let pool = with_pool(&state).await?;
+
+    SpeedPresetService::new(&pool)
+        .add(speed, audiobook_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_speed_preset(
+    state: State<'_, AppState>,
+    speed: f32,
+    audiobook_id: Option<String>,
+) -> Result<SpeedPresets, String> {
+    let pool = with_pool(&state).await?;
+
+    SpeedPresetService::new(&pool)
+        .remove(speed, audiobook_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn reset_book_speed_presets(state: State<'_, AppState>, audiobook_id: String) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    SpeedPresetService::new(&pool)
+        .reset_book(&audiobook_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_speed_step(state: State<'_, AppState>, step: f32) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    SpeedPresetService::new(&pool)
+        .set_step(step)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Move the engine to the next/previous preset or one step up/down, and remember
+/// the new speed for the book. Returns the speed now in effect.
+#[tauri::command]
+pub async fn cycle_speed(
+    state: State<'_, AppState>,
+    direction: Option<SpeedDirection>,
+    audiobook_id: Option<String>,
+) -> Result<f32, String> {
+    let pool = with_pool(&state).await?;
+
+    let presets = SpeedPresetService::new(&pool)
+        .get(audiobook_id.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let sender = thread::sender()?;
+    let (status_sender, status_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: status_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    let current = status_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+        .speed;
+
+    let speed = presets.apply(current, direction.unwrap_or(SpeedDirection::Next));
+    println!("⏩ SPEED: Cycling {}x -> {}x", current, speed);
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::SetSpeed { speed, response: response_sender })
+        .map_err(|e| format!("Failed to send speed command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    if let Some(audiobook_id) = &audiobook_id {
+        if let Err(e) = PlaybackProgressRepository::new(&pool).set_playback_speed(audiobook_id, speed as f64).await {
+            log::warn!("Failed to save playback speed for {}: {}", audiobook_id, e);
+        }
+    }
+
+    Ok(speed)
+}
+
+#[tauri::command]
+pub async fn get_playback_status(state: State<'_, AppState>) -> Result<PlaybackStatus, String> {
+    println!("📊 STATUS: Getting playback status");
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::GetStatus { response: response_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    
+    let mut status = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?;
+    if let Some(cast) = cast_session::status().await {
+        status.state = if cast.playing { audio::PlaybackState::Playing } else { audio::PlaybackState::Paused };
+        status.position = cast.position;
+        status.duration = cast.duration.or(status.duration);
+        status.current_file = Some(cast.file_path);
+    }
+
+    let pool = with_pool(&state).await.ok();
+    if let (Some(pool), Some(file_path)) = (pool, status.current_file.clone()) {
+        if let Some(layout) = books::playing_book_layout(&pool, &file_path).await {
+            status.chapter = layout.position(&file_path, status.position);
+        }
+    }
+    Ok(status)
+}
+
+/// State, position and duration without waiting on the audio thread, so a
+/// progress bar keeps moving through a slow load or seek. Covers local output
+/// only; use get_playback_status while casting or for chapter positions.
+#[tauri::command]
+pub fn get_playback_status_fast() -> LiveStatus {
+    let live = thread::live_status();
+    live.unwrap_or_default().read()
+}
+
+/// Search the network for renderers to cast to
+#[tauri::command]
+pub async fn discover_cast_devices(timeout_ms: Option<u64>) -> Result<Vec<casting::CastDevice>, String> {
+    let timeout = timeout_ms.map(std::time::Duration::from_millis).unwrap_or(casting::DEFAULT_DISCOVERY_TIMEOUT);
+    let devices = casting::discover(timeout).await.map_err(|e| e.to_string())?;
+    cast_session::remember_devices(&devices);
+    Ok(devices)
+}
+
+/// Hand the loaded chapter to a renderer at the current position and pause
+/// local playback. The renderer streams the file from the LAN sharing server.
+#[tauri::command]
+pub async fn start_casting(state: State<'_, AppState>, device_id: String) -> Result<casting::CastStatus, String> {
+    let pool = with_pool(&state).await?;
+
+    let device = cast_session::find_device(&device_id)
+        .ok_or("Unknown device; search for devices again")?;
+    let port = sharing::running_port().await
+        .ok_or("Turn on LAN sharing first; the device streams the book from it")?;
+    let key = sharing::load_settings(&pool).await.map_err(|e| e.to_string())?.key;
+
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: response_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    let status = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?;
+    let file_path = status.current_file.ok_or("Nothing is loaded")?;
+    let url = sharing::stream_url(&pool, port, &key, &file_path).await
+        .map_err(|e| e.to_string())?
+        .ok_or("Only books in the library can be cast")?;
+
+    let content_type = sharing::audio_content_type(std::path::Path::new(&file_path));
+    let cast = cast_session::start(device, &url, content_type, &file_path, status.position, status.duration).await
+        .map_err(|e| e.to_string())?;
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::Pause { response: response_sender })
+        .map_err(|e| format!("Failed to send pause command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    println!("📺 CAST: Casting {} to {}", file_path, cast.device.name);
+    Ok(cast)
+}
+
+/// Stop the renderer and bring playback back here, paused where the renderer
+/// got to. Returns that position, or None when nothing was being cast.
+#[tauri::command]
+pub async fn stop_casting() -> Result<Option<u64>, String> {
+    let Some(position) = cast_session::stop().await else {
+        return Ok(None);
+    };
+    if let Some(sender) = thread::running_sender() {
+        let (response_sender, response_receiver) = mpsc::channel();
+        sender.send(AudioCommand::Seek { position: position as f32, response: response_sender })
+            .map_err(|e| format!("Failed to send seek command: {}", e))?;
+        response_receiver.recv()
+            .map_err(|e| format!("Failed to receive response: {}", e))??;
+    }
+    Ok(Some(position))
+}
+
+#[tauri::command]
+pub async fn get_cast_status() -> Result<Option<casting::CastStatus>, String> {
+    Ok(cast_session::status().await)
+}
+
+/// Seek to a position in the whole book, switching to the chapter file it
+/// falls in. Keeps playing if the book was playing.
+#[tauri::command]
+pub async fn seek_in_book(state: State<'_, AppState>, absolute_seconds: f64) -> Result<Option<ChapterPosition>, String> {
+    println!("⏭️ SEEK: Seeking to {}s into the book", absolute_seconds);
+    if !absolute_seconds.is_finite() {
+        return Err("Invalid position".to_string());
+    }
+    let pool = with_pool(&state).await?;
+
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: response_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    let status = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?;
+    books::seek_book(&pool, &sender, status, absolute_seconds.max(0.0) as u64).await
+}
+
+/// Go back to where playback was before the last seek, stop or chapter jump.
+/// Returns the place gone back to, or None when there is nothing to undo.
+#[tauri::command]
+pub async fn undo_seek(state: State<'_, AppState>) -> Result<Option<SeekHistoryEntry>, String> {
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::PopSeekHistory { response: response_sender })
+        .map_err(|e| format!("Failed to send seek history command: {}", e))?;
+    let Some(entry) = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))? else {
+        return Ok(None);
+    };
+    println!("⏪ UNDO SEEK: Going back to {}s in {}", entry.position, entry.file_path);
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: response_sender })
+        .map_err(|e| format!("Failed to send status command: {}", e))?;
+    let status = response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?;
+
+    if status.current_file.as_deref() != Some(entry.file_path.as_str()) {
+        let was_playing = matches!(status.state, audio::PlaybackState::Playing);
+        let pool = with_pool(&state).await.ok();
+        let book = match &pool {
+            Some(pool) => PlayHistoryService::new(pool).resolve_file(&entry.file_path).await
+                .map_err(|e| e.to_string())?
+                .map(|(audiobook_id, _)| audiobook_id),
+            None => None,
+        };
+        match (pool, book) {
+            (Some(pool), Some(audiobook_id)) => {
+                books::load_book_from_chapter(&pool, &sender, &audiobook_id, &entry.file_path, was_playing).await?;
+            }
+            _ => {
+                // Not part of a book: just the file, as load_audio_file would
+                let (response_sender, response_receiver) = mpsc::channel();
+                sender.send(AudioCommand::LoadFile { file_path: entry.file_path.clone(), response: response_sender })
+                    .map_err(|e| format!("Failed to send load command: {}", e))?;
+                response_receiver.recv()
+                    .map_err(|e| format!("Failed to receive response: {}", e))??;
+                if was_playing {
+                    let (response_sender, response_receiver) = mpsc::channel();
+                    sender.send(AudioCommand::Play { response: response_sender })
+                        .map_err(|e| format!("Failed to send play command: {}", e))?;
+                    response_receiver.recv()
+                        .map_err(|e| format!("Failed to receive response: {}", e))??;
+                }
+            }
+        }
+    }
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::Seek { position: entry.position as f32, response: response_sender })
+        .map_err(|e| format!("Failed to send seek command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+    Ok(Some(entry))
+}
+
+/// Load the player as it was when the app last closed: the track, position,
+/// queue, speed, volume and ambience, paused. Returns what was restored, or None
+/// when nothing was saved.
+#[tauri::command]
+pub async fn restore_player_state(state: State<'_, AppState>) -> Result<Option<PlayerState>, String> {
+    let pool = with_pool(&state).await?;
+    books::restore_saved_player_state(&pool).await
+}
+
+/// Places that undo_seek can go back to, newest first
+#[tauri::command]
+pub async fn get_seek_history() -> Result<Vec<SeekHistoryEntry>, String> {
+    let Some(sender) = thread::running_sender() else {
+        return Ok(Vec::new());
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetSeekHistory { response: response_sender })
+        .map_err(|e| format!("Failed to send seek history command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
+
+#[tauri::command]
+pub async fn seek_audio(position_seconds: f32) -> Result<(), String> {
+    println!("⏭️ SEEK: Seeking to position: {}", position_seconds);
+    if let Some(result) = cast_session::control(CastControl::Seek(position_seconds.max(0.0) as u64)).await {
+        return result.map_err(|e| e.to_string());
+    }
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::Seek { position: position_seconds, response: response_sender })
+        .map_err(|e| format!("Failed to send seek command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+// Queue management commands
+#[tauri::command]
+pub async fn add_to_queue(state: State<'_, AppState>, file_path: String, title: Option<String>) -> Result<(), String> {
+    log::info!("QUEUE: Adding to queue: {}", file_path);
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    
+    let track = Track {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_path,
+        title,
+        duration: None,
+        audiobook_id: None,
+    };
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::AddToQueue { track, response: response_sender })
+        .map_err(|e| format!("Failed to send add to queue command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn play_next() -> Result<bool, String> {
+    log::info!("QUEUE: Playing next track");
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::PlayNext { response: response_sender })
+        .map_err(|e| format!("Failed to send play next command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+/// Stop by itself after `chapters` more chapters, 1 for the end of the current
+/// one. None turns it off. The count shows up as `stop_after` in the playback
+/// status. It is cleared when playback stops, by itself or on request, and when
+/// another file or queue is loaded or the next track is picked by hand.
+#[tauri::command]
+pub async fn set_stop_after(chapters: Option<u32>) -> Result<(), String> {
+    log::info!("QUEUE: Stopping after {:?} chapters", chapters);
+
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+
+    sender.send(AudioCommand::SetStopAfter { chapters, response: response_sender })
+        .map_err(|e| format!("Failed to send stop after command: {}", e))?;
+
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn clear_queue() -> Result<(), String> {
+    log::info!("QUEUE: Clearing queue");
+    
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::ClearQueue { response: response_sender })
+        .map_err(|e| format!("Failed to send clear queue command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_queue() -> Result<Vec<Track>, String> {
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::GetQueue { response: response_sender })
+        .map_err(|e| format!("Failed to send get queue command: {}", e))?;
+    
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))
+}
+
+/// Replace the queue with every book of a collection and start playing the
+/// first track. Returns the full sequence with the playing track first.
+#[tauri::command]
+pub async fn play_collection(
+    state: State<'_, AppState>,
+    collection_id: String,
+    shuffle: Option<bool>,
+) -> Result<Vec<Track>, String> {
+    let pool = with_pool(&state).await?;
+
+    let tracks = CollectionQueueService::new(&pool)
+        .build_queue(&collection_id, shuffle.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())?;
+    if tracks.is_empty() {
+        return Err("Collection has no playable audiobooks".to_string());
+    }
+    log::info!("QUEUE: Playing collection {} ({} tracks)", collection_id, tracks.len());
+
+    let sender = thread::sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+
+    sender.send(AudioCommand::LoadQueue { tracks: tracks.clone(), response: response_sender })
+        .map_err(|e| format!("Failed to send load queue command: {}", e))?;
+
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    // Through Play, which reports the start to play history and the download throttle
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::Play { response: response_sender })
+        .map_err(|e| format!("Failed to send play command: {}", e))?;
+    response_receiver.recv()
+        .map_err(|e| format!("Failed to receive response: {}", e))??;
+
+    Ok(tracks)
+}
+
+#[tauri::command]
+pub async fn get_audio_info(state: State<'_, AppState>, file_path: String) -> Result<AudioInfo, String> {
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+    audio::AudioEngine::get_audio_info(&file_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn play_chapter(
+    state: State<'_, AppState>,
+    chapter_id: String,
+) -> Result<Chapter, String> {
+    println!("CHAPTER: Playing chapter with ID: {}", chapter_id);
+    
+    // Get chapter info from database
+    let pool = with_pool(&state).await?;
+    
+    let chapter_repo = ChapterRepository::new(&pool);
+    let chapter = chapter_repo.find_by_id(&chapter_id).await
+        .map_err(|e| e.to_string())?
+        .ok_or("Chapter not found")?;
+    
+    println!("CHAPTER: Found chapter: {} at {}", chapter.title, chapter.file_path);
+    
+    // Stop any current audio first to prevent overlap
+    let sender = thread::sender()?;
+    let (stop_sender, stop_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::Stop { response: stop_sender })
+        .map_err(|e| format!("Failed to send stop command: {}", e))?;
+        
+    stop_receiver.recv()
+        .map_err(|e| format!("Failed to receive stop response: {}", e))?
+        .map_err(|e| format!("Failed to stop audio: {}", e))?;
+    
+    // Longer delay to ensure clean stop and resource cleanup
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    
+    // Load and play the chapter file
+    let (load_sender, load_receiver) = mpsc::channel();
+    
+    sender.send(AudioCommand::LoadFile { 
+        file_path: chapter.file_path.clone(), 
+        response: load_sender 
+    }).map_err(|e| format!("Failed to send load command: {}", e))?;
+    
+    load_receiver.recv()
+        .map_err(|e| format!("Failed to receive load response: {}", e))?
+        .map_err(|e| format!("Failed to load chapter: {}", e))?;
+    
+    println!("CHAPTER: Successfully loaded and started playing chapter: {}", chapter.title);
+    Ok(chapter)
+}
+
+// Persistence commands
+#[tauri::command]
+pub async fn save_playback_state(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    state_json: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let query = r#"
+        INSERT OR REPLACE INTO playback_states (audiobook_id, state_data, updated_at)
+        VALUES (?, ?, datetime('now'))
+    "#;
+
+    sqlx::query(query)
+        .bind(&audiobook_id)
+        .bind(&state_json)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_playback_state(
+    state: State<'_, AppState>,
+    audiobook_id: String
+) -> Result<Option<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    let query = "SELECT state_data FROM playback_states WHERE audiobook_id = ?";
+
+    let result = sqlx::query_scalar::<_, String>(query)
+        .bind(&audiobook_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn remove_playback_state(
+    state: State<'_, AppState>,
+    audiobook_id: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let query = "DELETE FROM playback_states WHERE audiobook_id = ?";
+
+    sqlx::query(query)
+        .bind(&audiobook_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_all_playback_states(
+    state: State<'_, AppState>
+) -> Result<Vec<String>, String> {
+    let pool = with_pool(&state).await?;
+
+    let query = "SELECT state_data FROM playback_states ORDER BY updated_at DESC";
+
+    let results = sqlx::query_scalar::<_, String>(query)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn cleanup_old_playback_states(
+    state: State<'_, AppState>,
+    cutoff_date: String
+) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    let query = "DELETE FROM playback_states WHERE updated_at < ?";
+
+    sqlx::query(query)
+        .bind(&cutoff_date)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn track_listening_session(
+    state: State<'_, AppState>,
+    dto: CreateListeningHistoryDto
+) -> Result<ListeningHistory, String> {
+    let pool = with_pool(&state).await?;
+
+    let recommendation_service = RecommendationService::new(&pool);
+    recommendation_service.track_listening_session(dto).await.map_err(|e| e.to_string())
+}
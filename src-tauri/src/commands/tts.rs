@@ -0,0 +1,233 @@
+// Text-to-speech commands: documents and articles, their text, and the
+// audiobooks generated from them.
+
+use super::{AppState, path_roots, with_pool};
+use crate::{filesystem, storage, tts};
+use crate::database::models::*;
+use crate::document::{article as document_article, cleaning as text_cleaning, ProcessedDocument};
+use crate::document::chunking::ChunkingOptions;
+use crate::document::cleaning::TextCleaningOptions;
+use crate::services::{ChapterTextService, DocumentService, TtsChapterService, TtsTimingService};
+use tauri::State;
+
+#[tauri::command]
+pub async fn process_document(
+    state: State<'_, AppState>,
+    file_path: String,
+    cleaning: Option<TextCleaningOptions>,
+    chunking: Option<ChunkingOptions>,
+    ocr_language: Option<String>
+) -> Result<ProcessedDocument, String> {
+    println!("📄 DOCUMENT: Processing document at: {}", file_path);
+    path_roots(&state).await?.resolve("file_path", &file_path)?;
+
+    let pool = with_pool(&state).await?;
+
+    let (mut document, chunking) = tts::library::process_document(&pool, &file_path, cleaning, chunking, ocr_language).await?;
+
+    // Remember the layout so reprocessing the file gives the same chapters
+    if let Err(e) = DocumentService::new(&pool).store(&file_path, &mut document, &chunking).await {
+        log::warn!("DOCUMENT: Failed to store structure for {}: {}", file_path, e);
+    }
+    println!("📄 DOCUMENT: {} chapters at ~{} min per chunk", document.total_chapters, chunking.target_minutes);
+    Ok(document)
+}
+
+/// Fetch a web article, keep its readable text and process it like a text document
+#[tauri::command]
+pub async fn import_article(
+    state: State<'_, AppState>,
+    url: String,
+    chunking: Option<ChunkingOptions>
+) -> Result<ProcessedDocument, String> {
+    println!("📰 ARTICLE: Importing {}", url);
+
+    let pool = with_pool(&state).await?;
+
+    let article = document_article::fetch_article(&url).await.map_err(|e| {
+        println!("ARTICLE: Failed to import {}: {}", url, e);
+        e.to_string()
+    })?;
+    let articles_dir = storage::paths().data_dir.join("articles");
+    let file_path = document_article::save_article(&article, &articles_dir).map_err(|e| e.to_string())?;
+    let file_path = file_path.to_string_lossy().to_string();
+
+    let (mut document, chunking) = tts::library::process_document(&pool, &file_path, None, chunking, None).await?;
+    // The file name is a sanitized title; keep the page's own metadata
+    document.title = article.title.clone();
+    document.author = article.author.clone();
+
+    let documents = DocumentService::new(&pool);
+    let stored = documents.store(&file_path, &mut document, &chunking).await.map_err(|e| e.to_string())?;
+    documents.set_article_source(&stored.id, &article).await.map_err(|e| e.to_string())?;
+
+    println!("📰 ARTICLE: Imported \"{}\" ({} words, {} chapters)", article.title, article.word_count, document.total_chapters);
+    Ok(document)
+}
+
+/// The stored layout of a previously processed document
+#[tauri::command]
+pub async fn get_stored_document(state: State<'_, AppState>, file_path: String) -> Result<Option<StoredDocument>, String> {
+    let pool = with_pool(&state).await?;
+
+    DocumentService::new(&pool).find(&file_path).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_chunking_options(state: State<'_, AppState>) -> Result<ChunkingOptions, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(DocumentService::new(&pool).load_default_chunking().await)
+}
+
+/// Default chunking for documents that have not been processed before
+#[tauri::command]
+pub async fn set_chunking_options(state: State<'_, AppState>, options: ChunkingOptions) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    DocumentService::new(&pool).save_default_chunking(&options).await.map_err(|e| e.to_string())
+}
+
+/// Run the cleaning pipeline over text without storing it, for previews
+#[tauri::command]
+pub async fn clean_document_text(
+    state: State<'_, AppState>,
+    text: String,
+    options: Option<TextCleaningOptions>
+) -> Result<String, String> {
+    let options = match options {
+        Some(options) => options,
+        None => get_text_cleaning_options(state).await?,
+    };
+    Ok(text_cleaning::clean_text(&text, &options))
+}
+
+#[tauri::command]
+pub async fn get_text_cleaning_options(state: State<'_, AppState>) -> Result<TextCleaningOptions, String> {
+    let pool = with_pool(&state).await?;
+
+    Ok(ChapterTextService::new(&pool).load_options().await)
+}
+
+#[tauri::command]
+pub async fn set_text_cleaning_options(state: State<'_, AppState>, options: TextCleaningOptions) -> Result<(), String> {
+    let pool = with_pool(&state).await?;
+
+    ChapterTextService::new(&pool).save_options(&options).await.map_err(|e| e.to_string())
+}
+
+/// The text a TTS chapter is generated from
+#[tauri::command]
+pub async fn get_chapter_text(state: State<'_, AppState>, chapter_id: String) -> Result<TtsChapterSource, String> {
+    let pool = with_pool(&state).await?;
+
+    ChapterTextService::new(&pool).get(&chapter_id).await.map_err(|e| e.to_string())
+}
+
+/// Correct a TTS chapter's text; takes effect when the chapter is regenerated
+#[tauri::command]
+pub async fn update_chapter_text(
+    state: State<'_, AppState>,
+    chapter_id: String,
+    text: String,
+    clean: Option<bool>
+) -> Result<TtsChapterSource, String> {
+    let pool = with_pool(&state).await?;
+
+    println!("📝 CHAPTER TEXT: Updating text for chapter {}", chapter_id);
+    ChapterTextService::new(&pool)
+        .update(&chapter_id, &text, clean.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_audio_file(
+    base64_data: String,
+    filename: String,
+    audiobook_id: String
+) -> Result<String, String> {
+    use base64::{Engine as _, engine::general_purpose};
+    
+    println!("💾 SAVE: Saving audio file: {} for audiobook: {}", filename, audiobook_id);
+    
+    // Create audiobook_output directory in the app's data folder
+    let output_dir = storage::paths().data_dir.join("audiobook_output").join(&audiobook_id);
+    tokio::fs::create_dir_all(&output_dir).await
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    
+    // Decode base64 data
+    let audio_bytes = general_purpose::STANDARD.decode(&base64_data)
+        .map_err(|e| format!("Failed to decode base64 audio data: {}", e))?;
+    storage::disk_space::ensure_space(&output_dir, audio_bytes.len() as u64).map_err(|e| e.to_string())?;
+    
+    // Create the full file path
+    let file_path = output_dir.join(&filename);
+    
+    // Save audio file
+    filesystem::atomic::write_async(&file_path, audio_bytes).await
+        .map_err(|e| format!("Failed to save audio file: {:#}", e))?;
+    
+    let full_path = file_path.to_string_lossy().to_string();
+    println!("SAVE: Successfully saved audio file: {}", full_path);
+    
+    Ok(full_path)
+}
+
+#[tauri::command]
+pub async fn create_tts_audiobook(
+    state: State<'_, AppState>,
+    title: String,
+    author: Option<String>,
+    chapters: Vec<serde_json::Value>,
+    voice: Option<String>,
+    document_id: Option<String>
+) -> Result<Audiobook, String> {
+    let pool = with_pool(&state).await?;
+
+    tts::library::create_audiobook_record(&pool, title, author, chapters, voice, document_id).await
+}
+
+/// Store the engine's alignment for one synthesized chunk file of a TTS chapter
+#[tauri::command]
+pub async fn record_tts_timings(
+    state: State<'_, AppState>,
+    audiobook_id: String,
+    chapter_number: i32,
+    file_path: String,
+    alignment: Vec<AlignedSegment>,
+) -> Result<usize, String> {
+    let pool = with_pool(&state).await?;
+
+    let timings = TtsTimingService::new(&pool)
+        .record(&audiobook_id, chapter_number, &file_path, alignment)
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("🗣️ TTS TIMINGS: Recorded {} sentences for chapter {}", timings.len(), chapter_number);
+    Ok(timings.len())
+}
+
+/// Sentence timings for a TTS chapter, in reading order
+#[tauri::command]
+pub async fn get_tts_timings(state: State<'_, AppState>, chapter_id: String) -> Result<Vec<TtsTiming>, String> {
+    let pool = with_pool(&state).await?;
+
+    TtsTimingService::new(&pool).get(&chapter_id).await.map_err(|e| e.to_string())
+}
+
+/// Synthesize one TTS chapter again, optionally with another voice or speed
+#[tauri::command]
+pub async fn regenerate_tts_chapter(
+    state: State<'_, AppState>,
+    chapter_id: String,
+    voice: Option<String>,
+    speed: Option<f32>,
+) -> Result<Chapter, String> {
+    let pool = with_pool(&state).await?;
+
+    println!("🎤 TTS: Regenerating chapter {} (voice: {:?}, speed: {:?})", chapter_id, voice, speed);
+    TtsChapterService::new(&pool)
+        .regenerate(&chapter_id, voice, speed)
+        .await
+        .map_err(|e| e.to_string())
+}
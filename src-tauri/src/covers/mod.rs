@@ -0,0 +1,296 @@
+// Audiobook covers served to the webview over the audiovibe-cover:// protocol,
+// so list responses carry a short URL instead of a base64 image. Covers are
+// scaled down to the requested size and the results cached on disk.
+
+use crate::commands::{with_pool, AppState};
+use crate::database::models::Audiobook;
+use crate::database::repository::AudiobookRepository;
+use crate::filesystem::atomic;
+use crate::services::{CoverResolutionService, CoverService};
+use crate::storage;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use std::path::{Path, PathBuf};
+
+mod resize;
+
+pub const COVER_SCHEME: &str = "audiovibe-cover";
+/// Requested sizes are rounded up to one of these so the cache stays small
+const SIZE_BUCKETS: [u32; 5] = [64, 128, 256, 512, 1024];
+/// Resized covers beyond this are evicted, least recently written first
+pub const CACHE_MAX_BYTES: u64 = 200 * 1024 * 1024;
+/// Cover URLs carry the book's update time, so a changed cover gets a new URL
+pub const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The protocol URL of a book's cover. Windows and Android webviews only reach
+/// custom protocols through an http://<scheme>.localhost host.
+pub fn cover_url(audiobook_id: &str, version: &str) -> String {
+    let base = if cfg!(any(windows, target_os = "android")) {
+        format!("http://{}.localhost", COVER_SCHEME)
+    } else {
+        format!("{}://localhost", COVER_SCHEME)
+    };
+    format!("{}/{}?v={:x}", base, audiobook_id, md5::compute(version.as_bytes()))
+}
+
+/// What list responses carry instead of the stored cover: the protocol URL for
+/// local files and data URLs, remote URLs as they are
+pub fn list_cover_url(audiobook: &Audiobook) -> Option<String> {
+    let cover = audiobook.cover_image_path.as_deref().filter(|cover| !cover.is_empty())?;
+    if cover.starts_with("http://") || cover.starts_with("https://") {
+        return Some(cover.to_string());
+    }
+    Some(cover_url(&audiobook.id, &audiobook.updated_at))
+}
+
+/// Audiobook id and requested size (in pixels) from a cover request path and query
+pub fn parse_request(path: &str, query: Option<&str>) -> Option<(String, Option<u32>)> {
+    let id = path.trim_matches('/');
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+
+    let size = query
+        .unwrap_or("")
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("size="))
+        .and_then(|size| size.parse::<u32>().ok())
+        .filter(|size| *size > 0);
+    Some((id.to_string(), size))
+}
+
+/// Smallest cached size that is at least as large as requested
+fn bucket(size: u32) -> u32 {
+    SIZE_BUCKETS
+        .iter()
+        .copied()
+        .find(|bucket| *bucket >= size)
+        .unwrap_or(SIZE_BUCKETS[SIZE_BUCKETS.len() - 1])
+}
+
+/// The image bytes and MIME type of a stored cover, which is either a file
+/// path or a base64 data URL
+pub fn read_cover(cover: &str) -> Result<(Vec<u8>, String)> {
+    if let Some(data_url) = cover.strip_prefix("data:") {
+        let (mime_type, data) = data_url
+            .split_once(";base64,")
+            .context("Cover data URL is not base64 encoded")?;
+        let bytes = general_purpose::STANDARD
+            .decode(data.trim())
+            .context("Cover data URL is not valid base64")?;
+        return Ok((bytes, mime_type.to_string()));
+    }
+
+    let path = Path::new(cover);
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read cover: {}", cover))?;
+    let mime_type = match path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase().as_str() {
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        _ => "image/jpeg",
+    };
+    Ok((bytes, mime_type.to_string()))
+}
+
+/// The cover at the requested size. Resized copies are kept in `cache_dir`
+/// under a name derived from the stored cover, so replacing it misses the cache.
+pub fn load_cover(audiobook_id: &str, cover: &str, size: Option<u32>, cache_dir: &Path) -> Result<(Vec<u8>, String)> {
+    let (data, mime_type) = read_cover(cover)?;
+    // Vector covers scale on their own
+    let Some(size) = size.filter(|_| mime_type != "image/svg+xml") else {
+        return Ok((data, mime_type));
+    };
+
+    let size = bucket(size);
+    let cached = cache_path(cache_dir, audiobook_id, size, cover);
+    if let Ok(bytes) = std::fs::read(&cached) {
+        return Ok((bytes, "image/jpeg".to_string()));
+    }
+
+    match resize::shrink_to_jpeg(&data, size)? {
+        Some(resized) => {
+            remove_stale(cache_dir, audiobook_id, size);
+            std::fs::create_dir_all(cache_dir).context("Failed to create cover cache directory")?;
+            atomic::write(&cached, &resized).context("Failed to cache resized cover")?;
+            Ok((resized, "image/jpeg".to_string()))
+        }
+        None => Ok((data, mime_type)),
+    }
+}
+
+fn cache_path(cache_dir: &Path, audiobook_id: &str, size: u32, cover: &str) -> PathBuf {
+    // File covers are keyed by modification time as well, since they are often
+    // rewritten in place
+    let modified = std::fs::metadata(cover)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|modified| modified.as_secs())
+        .unwrap_or(0);
+    let key = md5::compute(format!("{}|{}", cover, modified).as_bytes());
+    cache_dir.join(format!("{}_{}_{:x}.jpg", audiobook_id, size, key))
+}
+
+fn remove_stale(cache_dir: &Path, audiobook_id: &str, size: u32) {
+    let prefix = format!("{}_{}_", audiobook_id, size);
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Delete the oldest resized covers until the cache fits in `max_bytes`.
+/// Returns how many files were removed and how many bytes that freed.
+pub fn evict_cache(cache_dir: &Path, max_bytes: u64) -> Result<(usize, u64)> {
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return Ok((0, 0));
+    };
+    let mut files: Vec<(std::time::SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|metadata| metadata.is_file())?;
+            let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+            Some((modified, metadata.len(), entry.path()))
+        })
+        .collect();
+    files.sort();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let (mut removed, mut freed) = (0, 0);
+    for (_, size, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        std::fs::remove_file(&path).with_context(|| format!("Failed to evict cached cover: {}", path.display()))?;
+        total -= size;
+        removed += 1;
+        freed += size;
+    }
+    Ok((removed, freed))
+}
+
+/// Answer an audiovibe-cover:// request with the book's cover at the requested size
+pub async fn serve(app: &tauri::AppHandle, request: tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Vec<u8>> {
+    use tauri::http::{header, Response, StatusCode};
+    use tauri::Manager;
+
+    let not_found = |message: String| {
+        log::warn!("Cover request failed: {}", message);
+        Response::builder().status(StatusCode::NOT_FOUND).body(Vec::new()).unwrap_or_default()
+    };
+
+    let Some((audiobook_id, size)) = parse_request(request.uri().path(), request.uri().query()) else {
+        return not_found(format!("Bad cover URL: {}", request.uri()));
+    };
+    let pool = match with_pool(&app.state::<AppState>()).await {
+        Ok(pool) => pool,
+        Err(e) => return not_found(e),
+    };
+    let cover = match AudiobookRepository::new(&pool).find_by_id(&audiobook_id).await {
+        Ok(audiobook) => audiobook.and_then(|audiobook| audiobook.cover_image_path),
+        Err(e) => return not_found(e.to_string()),
+    };
+    let Some(cover) = cover else {
+        return not_found(format!("Audiobook {} has no cover", audiobook_id));
+    };
+    let cache_dir = storage::paths().covers_dir().join("sized");
+
+    let loaded = tauri::async_runtime::spawn_blocking(move || {
+        load_cover(&audiobook_id, &cover, size, &cache_dir)
+    }).await;
+    match loaded {
+        Ok(Ok((bytes, mime_type))) => Response::builder()
+            .header(header::CONTENT_TYPE, mime_type)
+            .header(header::CACHE_CONTROL, CACHE_CONTROL)
+            .body(bytes)
+            .unwrap_or_default(),
+        Ok(Err(e)) => not_found(e.to_string()),
+        Err(e) => not_found(e.to_string()),
+    }
+}
+
+/// Give a newly imported book the art embedded in its files or stored beside them
+pub async fn apply_local_cover(pool: &sqlx::SqlitePool, audiobook: &mut Audiobook) {
+    match CoverResolutionService::new(pool).fetch_cover(&audiobook.id, &storage::paths().covers_dir(), false).await {
+        Ok(Some(cover)) => {
+            audiobook.cover_image_path = Some(cover.cover_image_path);
+            audiobook.cover_source = Some(cover.source);
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to find a cover for {}: {}", audiobook.id, e),
+    }
+}
+
+/// Keep a generated collection collage in sync with its members; failures only cost the cover
+pub async fn refresh_collection_cover(pool: &sqlx::SqlitePool, collection_id: &str) {
+    if let Err(e) = CoverService::new(pool).refresh_collection_cover(collection_id, &storage::paths().covers_dir()).await {
+        log::warn!("Failed to refresh cover for collection {}: {}", collection_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_and_buckets() {
+        assert_eq!(parse_request("/abc-123", Some("size=200&v=1")), Some(("abc-123".to_string(), Some(200))));
+        assert_eq!(parse_request("abc-123", None), Some(("abc-123".to_string(), None)));
+        assert_eq!(parse_request("/abc-123", Some("size=big")), Some(("abc-123".to_string(), None)));
+        assert_eq!(parse_request("/", None), None);
+        assert_eq!(parse_request("/../etc/passwd", None), None);
+
+        assert_eq!(bucket(1), 64);
+        assert_eq!(bucket(200), 256);
+        assert_eq!(bucket(256), 256);
+        assert_eq!(bucket(5000), 1024);
+
+        assert!(cover_url("abc", "2024-01-01").contains("/abc?v="));
+        assert_ne!(cover_url("abc", "2024-01-01"), cover_url("abc", "2024-01-02"));
+
+        let mut book = Audiobook::new("Book".to_string(), "/books/book".to_string());
+        assert_eq!(list_cover_url(&book), None);
+        book.cover_image_path = Some("https://archive.org/services/img/book".to_string());
+        assert_eq!(list_cover_url(&book).as_deref(), Some("https://archive.org/services/img/book"));
+        book.cover_image_path = Some("data:image/jpeg;base64,/9j/4AAQ".to_string());
+        assert_eq!(list_cover_url(&book), Some(cover_url(&book.id, &book.updated_at)));
+    }
+
+    #[test]
+    fn test_read_cover_from_data_url_and_file() {
+        let (bytes, mime_type) = read_cover("data:image/png;base64,aGVsbG8=").unwrap();
+        assert_eq!(bytes, b"hello");
+        assert_eq!(mime_type, "image/png");
+        assert!(read_cover("data:image/png,hello").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("cover.svg");
+        std::fs::write(&svg, "<svg/>").unwrap();
+        let (bytes, mime_type) = load_cover("book", &svg.to_string_lossy(), Some(128), dir.path()).unwrap();
+        assert_eq!(bytes, b"<svg/>");
+        assert_eq!(mime_type, "image/svg+xml");
+    }
+
+    #[test]
+    fn test_evict_cache_removes_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, name) in ["old.jpg", "mid.jpg", "new.jpg"].iter().enumerate() {
+            let path = dir.path().join(name);
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000 + i as u64);
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        }
+
+        assert_eq!(evict_cache(dir.path(), 150).unwrap(), (2, 200));
+        assert!(dir.path().join("new.jpg").exists());
+        assert!(!dir.path().join("old.jpg").exists());
+        assert_eq!(evict_cache(dir.path(), 150).unwrap(), (0, 0));
+        assert_eq!(evict_cache(&dir.path().join("missing"), 0).unwrap(), (0, 0));
+    }
+}
@@ -0,0 +1,21 @@
+// Scaling covers down for the sizes the library views display them at
+
+use anyhow::{Context, Result};
+use std::io::Cursor;
+
+/// Re-encode the image as a JPEG that fits within `size` pixels, or None when
+/// it is already that small
+pub fn shrink_to_jpeg(data: &[u8], size: u32) -> Result<Option<Vec<u8>>> {
+    let image = image::load_from_memory(data).context("Unsupported cover image")?;
+    if image.width() <= size && image.height() <= size {
+        return Ok(None);
+    }
+
+    // JPEG has no alpha channel, so flatten before encoding
+    let thumbnail = image.thumbnail(size, size).to_rgb8();
+    let mut encoded = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut encoded, image::ImageFormat::Jpeg)
+        .context("Failed to encode resized cover")?;
+    Ok(Some(encoded.into_inner()))
+}
@@ -0,0 +1,203 @@
+// Content filtering for shared family machines: hides blocked genres, tags and
+// authors from library listings and LibriVox browsing
+
+use super::models::Audiobook;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use std::sync::RwLock;
+use ts_rs::TS;
+
+pub const PREF_CONTENT_FILTER: &str = "content_filter.config";
+pub const PREF_CONTENT_FILTER_PIN: &str = "content_filter.pin_hash";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ContentFilter {
+    pub enabled: bool,
+    #[serde(default)]
+    pub blocked_genres: Vec<String>,
+    /// Keywords matched as whole words against title, genre and description
+    #[serde(default)]
+    pub blocked_tags: Vec<String>,
+    #[serde(default)]
+    pub blocked_authors: Vec<String>,
+}
+
+static ACTIVE_FILTER: RwLock<Option<ContentFilter>> = RwLock::new(None);
+
+/// The filter repositories enforce, if one is switched on
+pub fn active() -> Option<ContentFilter> {
+    ACTIVE_FILTER.read().ok()?.clone().filter(|filter| filter.is_active())
+}
+
+pub fn set_active(filter: ContentFilter) {
+    if let Ok(mut active) = ACTIVE_FILTER.write() {
+        *active = Some(filter);
+    }
+}
+
+/// Drop blocked audiobooks from a query result
+pub fn apply(audiobooks: Vec<Audiobook>) -> Vec<Audiobook> {
+    match active() {
+        Some(filter) => audiobooks.into_iter().filter(|book| !filter.blocks_audiobook(book)).collect(),
+        None => audiobooks,
+    }
+}
+
+impl ContentFilter {
+    pub fn is_active(&self) -> bool {
+        self.enabled
+            && (!self.blocked_genres.is_empty() || !self.blocked_tags.is_empty() || !self.blocked_authors.is_empty())
+    }
+
+    pub fn blocks(&self, title: &str, author: Option<&str>, genre: Option<&str>, description: Option<&str>) -> bool {
+        let genre = genre.unwrap_or("");
+        let author = author.unwrap_or("");
+
+        if self.blocked_genres.iter().any(|blocked| contains_term(genre, blocked)) {
+            return true;
+        }
+        if self.blocked_authors.iter().any(|blocked| contains_term(author, blocked)) {
+            return true;
+        }
+
+        let tag_fields = [title, genre, description.unwrap_or("")];
+        self.blocked_tags
+            .iter()
+            .any(|tag| tag_fields.iter().any(|field| contains_term(field, tag)))
+    }
+
+    pub fn blocks_audiobook(&self, audiobook: &Audiobook) -> bool {
+        self.blocks(
+            &audiobook.title,
+            audiobook.author.as_deref(),
+            audiobook.genre.as_deref(),
+            audiobook.description.as_deref(),
+        )
+    }
+
+    /// LibriVox API book objects carry `authors` and `genres` as arrays
+    pub fn blocks_librivox_book(&self, book: &serde_json::Value) -> bool {
+        let title = book.get("title").and_then(|t| t.as_str()).unwrap_or("");
+        let description = book.get("description").and_then(|d| d.as_str());
+
+        let authors = book.get("authors")
+            .and_then(|a| a.as_array())
+            .map(|authors| {
+                authors.iter()
+                    .map(|author| format!(
+                        "{} {}",
+                        author.get("first_name").and_then(|n| n.as_str()).unwrap_or(""),
+                        author.get("last_name").and_then(|n| n.as_str()).unwrap_or("")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+
+        let genres = book.get("genres")
+            .and_then(|g| g.as_array())
+            .map(|genres| {
+                genres.iter()
+                    .filter_map(|genre| genre.get("name").and_then(|n| n.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+
+        self.blocks(title, Some(&authors), Some(&genres), description)
+    }
+}
+
+/// Case-insensitive match of `term` in `text` on word boundaries, so "war" does not hide "Software"
+fn contains_term(text: &str, term: &str) -> bool {
+    let term = term.trim().to_lowercase();
+    if term.is_empty() {
+        return false;
+    }
+    let text = text.to_lowercase();
+
+    text.match_indices(&term).any(|(start, matched)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + matched.len()..].chars().next();
+        !before.map(char::is_alphanumeric).unwrap_or(false) && !after.map(char::is_alphanumeric).unwrap_or(false)
+    })
+}
+
+/// PBKDF2-HMAC-SHA256 rounds; PINs are short, so each guess has to be slow
+const PIN_ROUNDS: u32 = 600_000;
+const PIN_SCHEME: &str = "pbkdf2-sha256";
+
+/// Salted PBKDF2 of the PIN, stored as "pbkdf2-sha256$rounds$salt$hash"
+pub fn hash_pin(pin: &str) -> String {
+    let salt = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}${}${}${}", PIN_SCHEME, PIN_ROUNDS, salt, pin_digest(&salt, pin, PIN_ROUNDS))
+}
+
+pub fn verify_pin(pin: &str, stored: &str) -> bool {
+    let [PIN_SCHEME, rounds, salt, hash] = stored.split('$').collect::<Vec<_>>()[..] else {
+        return false;
+    };
+    let Ok(rounds) = rounds.parse() else {
+        return false;
+    };
+    pin_digest(salt, pin, rounds).as_bytes().ct_eq(hash.as_bytes()).into()
+}
+
+fn pin_digest(salt: &str, pin: &str, rounds: u32) -> String {
+    let mut hash = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(pin.as_bytes(), salt.as_bytes(), rounds, &mut hash);
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> ContentFilter {
+        ContentFilter {
+            enabled: true,
+            blocked_genres: vec!["Horror".to_string()],
+            blocked_tags: vec!["war".to_string()],
+            blocked_authors: vec!["poe".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_blocks_by_genre_author_and_tag() {
+        let filter = filter();
+        assert!(filter.blocks("Dracula", Some("Bram Stoker"), Some("Gothic Fiction, Horror"), None));
+        assert!(filter.blocks("The Raven", Some("Edgar Allan Poe"), None, None));
+        assert!(filter.blocks("Stories", None, None, Some("Tales from the war.")));
+        assert!(!filter.blocks("Software Engineering", Some("Poehler"), Some("Non-fiction"), None));
+    }
+
+    #[test]
+    fn test_disabled_or_empty_filter_is_inactive() {
+        let mut disabled = filter();
+        disabled.enabled = false;
+        assert!(!disabled.is_active());
+
+        let empty = ContentFilter { enabled: true, ..Default::default() };
+        assert!(!empty.is_active());
+    }
+
+    #[test]
+    fn test_librivox_book_fields() {
+        let book = serde_json::json!({
+            "title": "Tales",
+            "authors": [{ "first_name": "Edgar Allan", "last_name": "Poe" }],
+            "genres": [{ "id": "1", "name": "Short Stories" }]
+        });
+        assert!(filter().blocks_librivox_book(&book));
+    }
+
+    #[test]
+    fn test_pin_hash_roundtrip() {
+        let stored = hash_pin("1234");
+        assert!(verify_pin("1234", &stored));
+        assert!(!verify_pin("4321", &stored));
+        assert_ne!(hash_pin("1234"), stored);
+    }
+}
@@ -0,0 +1,215 @@
+// Data migrations: versioned fixes to stored data that SQL alone cannot make,
+// such as writing inline covers out to files. They run after the SQL
+// migrations and are recorded in data_migrations once they succeed, so each
+// runs once. Every migration must be safe to run again after a crash halfway.
+
+use crate::filesystem::atomic;
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// What data migrations need from outside the database
+#[derive(Debug, Clone)]
+pub struct DataMigrationContext {
+    pub covers_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataMigration {
+    CoversToFiles,
+    LinkTtsDocuments,
+}
+
+impl DataMigration {
+    /// In the order they run; versions only ever grow
+    pub const ALL: [DataMigration; 2] = [DataMigration::CoversToFiles, DataMigration::LinkTtsDocuments];
+
+    pub fn version(self) -> i64 {
+        match self {
+            DataMigration::CoversToFiles => 1,
+            DataMigration::LinkTtsDocuments => 2,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            DataMigration::CoversToFiles => "Move base64 covers out of the database into the covers folder",
+            DataMigration::LinkTtsDocuments => "Link generated audiobooks to the documents they were read from",
+        }
+    }
+
+    /// Returns a short summary of what changed
+    async fn run(self, pool: &SqlitePool, context: &DataMigrationContext) -> Result<String> {
+        match self {
+            DataMigration::CoversToFiles => covers_to_files(pool, &context.covers_dir).await,
+            DataMigration::LinkTtsDocuments => link_tts_documents(pool).await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DataMigrationRecord {
+    pub version: i64,
+    pub description: String,
+    pub applied_at: String,
+    pub summary: String,
+}
+
+/// Versions already recorded as applied
+pub async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>> {
+    sqlx::query_scalar("SELECT version FROM data_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await
+        .context("Failed to read applied data migrations")
+}
+
+pub async fn pending(pool: &SqlitePool) -> Result<Vec<DataMigration>> {
+    let applied = applied_versions(pool).await?;
+    Ok(DataMigration::ALL.into_iter().filter(|migration| !applied.contains(&migration.version())).collect())
+}
+
+/// Run every pending migration in order. Stops at the first failure, since
+/// later migrations may rely on earlier ones; it is retried on the next start.
+pub async fn run_pending(pool: &SqlitePool, context: &DataMigrationContext) -> Result<Vec<DataMigrationRecord>> {
+    let mut applied = Vec::new();
+    for migration in pending(pool).await? {
+        log::info!("Running data migration {}: {}", migration.version(), migration.description());
+        let summary = migration.run(pool, context).await
+            .with_context(|| format!("Data migration {} failed", migration.version()))?;
+        let record = DataMigrationRecord {
+            version: migration.version(),
+            description: migration.description().to_string(),
+            applied_at: Utc::now().to_rfc3339(),
+            summary,
+        };
+        sqlx::query("INSERT INTO data_migrations (version, description, applied_at, summary) VALUES (?, ?, ?, ?)")
+            .bind(record.version)
+            .bind(&record.description)
+            .bind(&record.applied_at)
+            .bind(&record.summary)
+            .execute(pool)
+            .await
+            .context("Failed to record data migration")?;
+        log::info!("Data migration {} done: {}", record.version, record.summary);
+        applied.push(record);
+    }
+    Ok(applied)
+}
+
+/// Covers downloaded by older versions were stored as data URLs in the row
+/// itself, which made every library query carry the images along
+async fn covers_to_files(pool: &SqlitePool, covers_dir: &Path) -> Result<String> {
+    let mut moved = 0;
+    for (table, prefix) in [("audiobooks", ""), ("collections", "collection_")] {
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, cover_image_path FROM {} WHERE cover_image_path LIKE 'data:%'",
+            table
+        ))
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("Failed to find inline covers in {}", table))?;
+
+        for (id, data_url) in rows {
+            let (extension, bytes) = match decode_data_url(&data_url) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    log::warn!("Leaving unreadable inline cover of {} {} alone: {}", table, id, e);
+                    continue;
+                }
+            };
+            std::fs::create_dir_all(covers_dir).context("Failed to create covers directory")?;
+            let path = covers_dir.join(format!("{}{}.{}", prefix, id, extension));
+            atomic::write(&path, bytes)?;
+            sqlx::query(&format!("UPDATE {} SET cover_image_path = ? WHERE id = ?", table))
+                .bind(path.to_string_lossy().to_string())
+                .bind(&id)
+                .execute(pool)
+                .await
+                .with_context(|| format!("Failed to update cover of {} {}", table, id))?;
+            moved += 1;
+        }
+    }
+    Ok(format!("Moved {} covers to files", moved))
+}
+
+/// File extension and bytes of a base64 data URL
+fn decode_data_url(data_url: &str) -> Result<(&'static str, Vec<u8>)> {
+    let (header, data) = data_url.split_once(',').ok_or_else(|| anyhow!("Not a data URL"))?;
+    let mime_type = header.strip_prefix("data:").and_then(|rest| rest.strip_suffix(";base64"))
+        .ok_or_else(|| anyhow!("Not a base64 data URL"))?;
+    let extension = match mime_type {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        _ => "jpg",
+    };
+    let bytes = general_purpose::STANDARD.decode(data.trim()).context("Invalid base64")?;
+    Ok((extension, bytes))
+}
+
+/// Generated audiobooks from before documents were linked only share a title
+/// with their document; link them where the title picks out exactly one
+async fn link_tts_documents(pool: &SqlitePool) -> Result<String> {
+    let result = sqlx::query(
+        r#"
+        UPDATE audiobooks
+        SET source_id = (SELECT d.id FROM documents d WHERE d.title = audiobooks.title)
+        WHERE source_type = 'tts'
+          AND source_id IS NULL
+          AND (SELECT COUNT(*) FROM documents d WHERE d.title = audiobooks.title) = 1
+        "#,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to link generated audiobooks to documents")?;
+    Ok(format!("Linked {} audiobooks to their documents", result.rows_affected()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_data_migrations_run_once_and_move_inline_covers() {
+        let dir = tempfile::tempdir().unwrap();
+        let covers_dir = dir.path().join("covers");
+        let mut db = DatabaseManager::new(dir.path().join("data.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        // Without a context initialize leaves data migrations alone, like an older database
+        assert_eq!(pending(pool).await.unwrap(), DataMigration::ALL);
+        sqlx::query(
+            "INSERT INTO audiobooks (id, title, file_path, cover_image_path, source_type, added_date, created_at, updated_at)
+             VALUES ('inline', 'Dune', '/books/dune', 'data:image/png;base64,cG5n', 'local', '', '', ''),
+                    ('generated', 'Essay', '/books/essay', NULL, 'tts', '', '', '')",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO documents (id, file_path, title, format, target_minutes) VALUES ('doc', '/docs/essay.txt', 'Essay', 'txt', 10)")
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let context = DataMigrationContext { covers_dir: covers_dir.clone() };
+        let applied = run_pending(pool, &context).await.unwrap();
+        assert_eq!(applied.iter().map(|record| record.version).collect::<Vec<_>>(), vec![1, 2]);
+
+        let cover: String = sqlx::query_scalar("SELECT cover_image_path FROM audiobooks WHERE id = 'inline'").fetch_one(pool).await.unwrap();
+        assert_eq!(Path::new(&cover), covers_dir.join("inline.png"));
+        assert_eq!(std::fs::read(&cover).unwrap(), b"png");
+        let source: Option<String> = sqlx::query_scalar("SELECT source_id FROM audiobooks WHERE id = 'generated'").fetch_one(pool).await.unwrap();
+        assert_eq!(source.as_deref(), Some("doc"));
+
+        assert!(run_pending(pool, &context).await.unwrap().is_empty(), "already applied");
+        assert!(decode_data_url("data:image/png,raw").is_err());
+    }
+}
@@ -2,17 +2,51 @@
 // This module will handle SQLite database operations and data persistence
 
 use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
-use sqlx::migrate::MigrateDatabase;
-use std::path::Path;
+use sqlx::migrate::{MigrateDatabase, Migrator};
+use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
+pub mod content_filter;
+pub mod data_migrations;
 pub mod models;
 pub mod repository;
 
+use data_migrations::DataMigrationContext;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+const PRE_MIGRATION_BACKUP_PREFIX: &str = "pre-migration-";
+/// Pre-migration backups kept; they are only taken when an update changes the schema
+const PRE_MIGRATION_BACKUPS_KEPT: usize = 3;
+
+/// Which migrations the database has and this build expects, for diagnosing a
+/// database written by a newer or older version
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SchemaVersion {
+    pub app_version: String,
+    /// Newest SQL migration applied
+    pub schema_version: Option<i64>,
+    /// Newest SQL migration this build knows
+    pub latest_schema_version: i64,
+    pub pending_migrations: Vec<i64>,
+    /// Applied, but unknown to this build: the database was opened by a newer version
+    pub unknown_migrations: Vec<i64>,
+    /// Started but not finished
+    pub failed_migrations: Vec<i64>,
+    pub data_version: Option<i64>,
+    pub latest_data_version: i64,
+    pub pending_data_migrations: Vec<i64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct DatabaseManager {
     pool: Option<SqlitePool>,
     database_path: String,
+    backup_dir: Option<PathBuf>,
+    data_migrations: Option<DataMigrationContext>,
 }
 
 impl DatabaseManager {
@@ -20,9 +54,23 @@ impl DatabaseManager {
         Self {
             pool: None,
             database_path,
+            backup_dir: None,
+            data_migrations: None,
         }
     }
 
+    /// Copy the database into `backup_dir` before applying migrations to it
+    pub fn with_backup_dir(mut self, backup_dir: PathBuf) -> Self {
+        self.backup_dir = Some(backup_dir);
+        self
+    }
+
+    /// Run the data migrations after the SQL ones
+    pub fn with_data_migrations(mut self, context: DataMigrationContext) -> Self {
+        self.data_migrations = Some(context);
+        self
+    }
+
     pub async fn initialize(&mut self) -> Result<()> {
         log::info!("Initializing database at: {}", self.database_path);
         
@@ -34,8 +82,9 @@ impl DatabaseManager {
 
         // Create database if it doesn't exist
         let database_url = format!("sqlite:{}", self.database_path);
-        if !sqlx::Sqlite::database_exists(&database_url).await
-            .context("Failed to check if database exists")? {
+        let existed = sqlx::Sqlite::database_exists(&database_url).await
+            .context("Failed to check if database exists")?;
+        if !existed {
             sqlx::Sqlite::create_database(&database_url).await
                 .context("Failed to create database")?;
             log::info!("Created new database at: {}", self.database_path);
@@ -47,10 +96,37 @@ impl DatabaseManager {
             .connect(&database_url).await
             .context("Failed to create database connection pool")?;
 
-        // Run migrations
-        sqlx::migrate!("./migrations")
-            .run(&pool).await
-            .context("Failed to run database migrations")?;
+        // Startup waits on this, so only hand the database to the migrator when
+        // something is actually pending; checksums are verified during warm-up
+        let pending_sql = Self::has_pending_migrations(&pool).await?;
+        let pending_data = match &self.data_migrations {
+            // Before its table exists every data migration is pending
+            Some(_) => pending_sql || !data_migrations::pending(&pool).await?.is_empty(),
+            None => false,
+        };
+        if existed && (pending_sql || pending_data) {
+            if let Some(backup_dir) = &self.backup_dir {
+                // Better to migrate without a backup than to not start at all
+                match Self::backup_before_migrating(&pool, backup_dir).await {
+                    Ok(path) => log::info!("Backed up database before migrating to {}", path.display()),
+                    Err(e) => log::warn!("Failed to back up database before migrating: {:#}", e),
+                }
+            }
+        }
+
+        if pending_sql {
+            MIGRATOR.run(&pool).await
+                .context("Failed to run database migrations")?;
+        } else {
+            log::info!("Database schema is up to date");
+        }
+
+        if let (Some(context), true) = (&self.data_migrations, pending_data) {
+            // A failed data migration leaves its data as it was and is retried next start
+            if let Err(e) = data_migrations::run_pending(&pool, context).await {
+                log::warn!("{:#}", e);
+            }
+        }
 
         self.pool = Some(pool);
         log::info!("Database initialized successfully");
@@ -62,6 +138,92 @@ impl DatabaseManager {
             .ok_or_else(|| anyhow::anyhow!("Database not initialized"))
     }
 
+    pub fn is_initialized(&self) -> bool {
+        self.pool.is_some()
+    }
+
+    async fn has_pending_migrations(pool: &SqlitePool) -> Result<bool> {
+        let applied: Vec<i64> = match sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await
+        {
+            Ok(applied) => applied,
+            // A brand new database has no migrations table yet
+            Err(_) => return Ok(true),
+        };
+        Ok(MIGRATOR.iter().any(|migration| !applied.contains(&migration.version)))
+    }
+
+    async fn backup_before_migrating(pool: &SqlitePool, backup_dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(backup_dir).context("Failed to create backup directory")?;
+        let schema_version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(pool)
+            .await
+            .unwrap_or(None);
+        let path = backup_dir.join(format!(
+            "{}{}-{}.db",
+            PRE_MIGRATION_BACKUP_PREFIX,
+            chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+            schema_version.unwrap_or(0)
+        ));
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(pool)
+            .await
+            .context("Failed to copy database")?;
+
+        // Names sort by their timestamp
+        let mut backups: Vec<PathBuf> = std::fs::read_dir(backup_dir)
+            .context("Failed to read backup directory")?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(PRE_MIGRATION_BACKUP_PREFIX) && name.ends_with(".db"))
+            })
+            .collect();
+        backups.sort();
+        let stale = backups.len().saturating_sub(PRE_MIGRATION_BACKUPS_KEPT);
+        for old in backups.drain(..stale) {
+            let _ = std::fs::remove_file(old);
+        }
+        Ok(path)
+    }
+
+    pub async fn schema_version(&self) -> Result<SchemaVersion> {
+        let pool = self.get_pool()?;
+        let rows: Vec<(i64, bool)> = sqlx::query_as("SELECT version, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await
+            .context("Failed to read applied migrations")?;
+        let applied: Vec<i64> = rows.iter().filter(|(_, success)| *success).map(|(version, _)| *version).collect();
+        let known: Vec<i64> = MIGRATOR.iter().map(|migration| migration.version).collect();
+        let applied_data = data_migrations::applied_versions(pool).await?;
+
+        Ok(SchemaVersion {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            schema_version: applied.iter().max().copied(),
+            latest_schema_version: known.iter().max().copied().unwrap_or(0),
+            pending_migrations: known.iter().filter(|version| !applied.contains(version)).copied().collect(),
+            unknown_migrations: applied.iter().filter(|version| !known.contains(version)).copied().collect(),
+            failed_migrations: rows.iter().filter(|(_, success)| !*success).map(|(version, _)| *version).collect(),
+            data_version: applied_data.iter().max().copied(),
+            latest_data_version: data_migrations::DataMigration::ALL.iter().map(|migration| migration.version()).max().unwrap_or(0),
+            pending_data_migrations: data_migrations::DataMigration::ALL
+                .iter()
+                .map(|migration| migration.version())
+                .filter(|version| !applied_data.contains(version))
+                .collect(),
+        })
+    }
+
+    /// Full migrator pass, which also checks applied migrations were not edited
+    pub async fn verify_migrations(&self) -> Result<()> {
+        MIGRATOR.run(self.get_pool()?).await
+            .context("Failed to verify database migrations")?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +248,28 @@ mod tests {
         assert!(db.is_initialized());
         assert!(db.get_pool().is_ok());
     }
+
+    #[tokio::test]
+    async fn test_pending_migrations_are_backed_up_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("library.db").to_string_lossy().to_string();
+        let backups = temp_dir.path().join("backups");
+        let context = DataMigrationContext { covers_dir: temp_dir.path().join("covers") };
+
+        let mut fresh = DatabaseManager::new(db_path.clone()).with_backup_dir(backups.clone()).with_data_migrations(context.clone());
+        fresh.initialize().await.unwrap();
+        assert!(!backups.exists(), "a new database has nothing to back up");
+        let version = fresh.schema_version().await.unwrap();
+        assert_eq!(version.schema_version, Some(version.latest_schema_version));
+        assert!(version.pending_migrations.is_empty() && version.pending_data_migrations.is_empty());
+        assert_eq!(version.data_version, Some(version.latest_data_version));
+
+        // As if the app was updated with a new data migration
+        sqlx::query("DELETE FROM data_migrations WHERE version = 2").execute(fresh.get_pool().unwrap()).await.unwrap();
+        fresh.get_pool().unwrap().close().await;
+        let mut updated = DatabaseManager::new(db_path).with_backup_dir(backups.clone()).with_data_migrations(context);
+        updated.initialize().await.unwrap();
+        assert_eq!(std::fs::read_dir(&backups).unwrap().count(), 1);
+        assert!(updated.schema_version().await.unwrap().pending_data_migrations.is_empty());
+    }
 }
\ No newline at end of file
@@ -1,39 +1,67 @@
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
 use uuid::Uuid;
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub const IMPORT_STATUS_COMPLETE: &str = "complete";
+pub const IMPORT_STATUS_INCOMPLETE: &str = "incomplete";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct Audiobook {
     pub id: String,
     pub title: String,
     pub author: Option<String>,
+    pub author_id: Option<String>,
     pub narrator: Option<String>,
+    pub narrator_id: Option<String>,
+    #[ts(type = "number | null")]
     pub duration: Option<i64>, // Duration in seconds
     pub file_path: String,
     pub cover_image_path: Option<String>,
     pub description: Option<String>,
     pub genre: Option<String>,
+    pub language: Option<String>,
     pub publish_date: Option<String>,
     pub added_date: String,
+    #[ts(type = "number | null")]
     pub file_size: Option<i64>,
     pub bitrate: Option<i32>,
     pub sample_rate: Option<i32>,
     pub chapters_count: i32,
+    pub rating: Option<i32>, // 1-5 stars
+    pub review: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// How the book was imported: "librivox", "tts", "local" or "url_list"
+    pub source_type: Option<String>,
+    /// Archive.org identifier, document id or imported path, depending on `source_type`
+    pub source_id: Option<String>,
+    /// `IMPORT_STATUS_INCOMPLETE` when the book's downloaded files are not all there
+    pub import_status: String,
+    /// Where the cover was found, when it was fetched by the cover fallback chain
+    pub cover_source: Option<String>,
+    /// The chapters were ordered by hand; rescans keep that order
+    pub chapter_order_locked: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct Chapter {
     pub id: String,
     pub audiobook_id: String,
     pub chapter_number: i32,
     pub title: String,
     pub file_path: String,
+    #[ts(type = "number | null")]
     pub duration: Option<i64>, // Duration in seconds
+    #[ts(type = "number | null")]
     pub file_size: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
+    /// kbps, once the file has been validated or backfilled
+    pub bitrate: Option<i32>,
+    pub codec: Option<String>,
 }
 
 impl Chapter {
@@ -51,6 +79,8 @@ impl Chapter {
             file_size: None,
             created_at: now.clone(),
             updated_at: now,
+            bitrate: None,
+            codec: None,
         }
     }
 }
@@ -64,34 +94,50 @@ impl Audiobook {
             id,
             title,
             author: None,
+            author_id: None,
             narrator: None,
+            narrator_id: None,
             duration: None,
             file_path,
             cover_image_path: None,
             description: None,
             genre: None,
+            language: None,
             publish_date: None,
             added_date: now.clone(),
             file_size: None,
             bitrate: None,
             sample_rate: None,
             chapters_count: 0,
+            rating: None,
+            review: None,
             created_at: now.clone(),
             updated_at: now,
+            source_type: None,
+            source_id: None,
+            import_status: IMPORT_STATUS_COMPLETE.to_string(),
+            cover_source: None,
+            chapter_order_locked: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct PlaybackProgress {
     pub id: String,
     pub audiobook_id: String,
+    #[ts(type = "number")]
     pub position: i64, // Position in seconds
+    #[ts(type = "number | null")]
     pub duration: Option<i64>, // Total duration in seconds
     pub chapter_index: i32,
     pub playback_speed: f64,
     pub last_played_at: String,
     pub is_completed: bool,
+    pub is_abandoned: bool,
+    pub abandoned_at: Option<String>,
+    pub abandon_reason: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -110,13 +156,17 @@ impl PlaybackProgress {
             playback_speed: 1.0,
             last_played_at: now.clone(),
             is_completed: false,
+            is_abandoned: false,
+            abandoned_at: None,
+            abandon_reason: None,
             created_at: now.clone(),
             updated_at: now,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct Collection {
     pub id: String,
     pub name: String,
@@ -124,6 +174,9 @@ pub struct Collection {
     pub color: String,
     pub is_smart: bool,
     pub smart_criteria: Option<String>, // JSON string for smart collection rules
+    pub parent_collection_id: Option<String>,
+    pub cover_image_path: Option<String>,
+    pub cover_is_custom: bool, // User-chosen cover; never replaced by a generated collage
     pub created_at: String,
     pub updated_at: String,
 }
@@ -140,13 +193,200 @@ impl Collection {
             color: "#3B82F6".to_string(), // Default blue color
             is_smart: false,
             smart_criteria: None,
+            parent_collection_id: None,
+            cover_image_path: None,
+            cover_is_custom: false,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct Author {
+    pub id: String,
+    pub name: String,
+    pub sort_name: String,
+    pub bio: Option<String>,
+    pub photo_url: Option<String>,
+    pub openlibrary_key: Option<String>,
+    pub details_fetched_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Author {
+    pub fn new(name: String, sort_name: String) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        Self {
+            id,
+            name,
+            sort_name,
+            bio: None,
+            photo_url: None,
+            openlibrary_key: None,
+            details_fetched_at: None,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct Narrator {
+    pub id: String,
+    pub name: String,
+    pub normalized_name: String,
+    pub sort_name: String,
+    pub is_favorite: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Narrator {
+    pub fn new(name: String, normalized_name: String, sort_name: String) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        Self {
+            id,
+            name,
+            normalized_name,
+            sort_name,
+            is_favorite: false,
             created_at: now.clone(),
             updated_at: now,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+/// A pseudo-chapter: a named offset into a book's single audio file
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct ChapterMarker {
+    pub id: String,
+    pub audiobook_id: String,
+    #[ts(type = "number")]
+    pub position: i64, // Offset in seconds
+    pub title: String,
+    /// Created by auto_chapterize rather than by the listener
+    pub is_auto: bool,
+    pub created_at: String,
+}
+
+impl ChapterMarker {
+    pub fn new(audiobook_id: String, position: i64, title: String, is_auto: bool) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            audiobook_id,
+            position,
+            title,
+            is_auto,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// One spoken sentence of a TTS chapter and where it sits in its audio file
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct TtsTiming {
+    pub id: String,
+    pub chapter_id: String,
+    pub file_path: String,
+    /// Position of the sentence within the whole chapter
+    #[ts(type = "number")]
+    pub sentence_index: i64,
+    pub text: String,
+    pub start_time: f64, // Seconds into file_path
+    pub end_time: f64,
+    pub created_at: String,
+}
+
+/// A document processed for TTS and how it was divided into chapters
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct StoredDocument {
+    pub id: String,
+    pub file_path: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub format: String,
+    pub target_minutes: f64,
+    pub split_on_sentences: bool,
+    pub respect_headings: bool,
+    /// JSON array of sections: title, word_count, minutes
+    pub sections: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Page an imported article was fetched from
+    pub source_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct TtsChapterSource {
+    pub chapter_id: String,
+    pub text: String,
+    pub voice: Option<String>,
+    pub speed: f64,
+    pub updated_at: String,
+}
+
+/// A span of the TTS engine's alignment output: a word or a whole sentence
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AlignedSegment {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct AuthorAlias {
+    pub id: String,
+    pub author_id: String,
+    pub alias: String,
+    pub normalized_alias: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AuthorDetails {
+    #[serde(flatten)]
+    pub author: Author,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AuthorBooks {
+    pub audiobooks: Vec<Audiobook>,
+    pub ebooks: Vec<Ebook>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct CollectionStats {
+    pub collection_id: String,
+    #[ts(type = "number")]
+    pub audiobook_count: i64,
+    #[ts(type = "number")]
+    pub total_duration: i64, // Seconds
+    #[ts(type = "number")]
+    pub finished_count: i64,
+    pub last_played_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct CollectionAudiobook {
     pub id: String,
     pub collection_id: String,
@@ -172,7 +412,8 @@ impl CollectionAudiobook {
 }
 
 // DTOs for API communication
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateAudiobookDto {
     pub title: String,
     pub file_path: String,
@@ -180,46 +421,287 @@ pub struct CreateAudiobookDto {
     pub narrator: Option<String>,
     pub description: Option<String>,
     pub genre: Option<String>,
+    #[ts(type = "number | null")]
     pub duration: Option<i64>,
     pub cover_image_path: Option<String>,
+    pub source_type: Option<String>,
+    pub source_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Book details read back from where an audiobook was imported from; `None`
+/// leaves the stored value as it is
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SourceMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub genre: Option<String>,
+    pub language: Option<String>,
+    pub publish_date: Option<String>,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+    pub cover_image_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct UpdatePlaybackProgressDto {
+    #[ts(type = "number")]
     pub position: i64,
     pub chapter_index: Option<i32>,
     pub playback_speed: Option<f64>,
     pub is_completed: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateCollectionDto {
     pub name: String,
     pub description: Option<String>,
     pub color: Option<String>,
+    /// Only used on create; moving an existing collection goes through move_collection
+    #[serde(default)]
+    pub parent_collection_id: Option<String>,
+}
+
+/// Audiobook with listening-time estimates from its progress and recent listening
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudiobookWithEstimate {
+    #[serde(flatten)]
+    pub audiobook: Audiobook,
+    pub playback_speed: f64,
+    /// Audio not yet heard, in seconds of recording
+    #[ts(type = "number | null")]
+    pub remaining_seconds: Option<i64>,
+    /// Time left to listen at the book's playback speed
+    #[ts(type = "number | null")]
+    pub remaining_listening_seconds: Option<i64>,
+    /// Date (YYYY-MM-DD) the book will be finished at the recent daily pace
+    pub estimated_finish_date: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Slim library-list row; descriptions and chapters come from get_audiobook_details
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudiobookSummary {
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+    /// 0-100, 100 once the book is marked completed
+    pub progress_percent: f64,
+    pub is_completed: bool,
+    /// Cover protocol URL (or remote URL), never an inline image
+    pub cover_url: Option<String>,
+}
+
+impl AudiobookSummary {
+    pub fn new(audiobook: &Audiobook, progress: Option<&PlaybackProgress>, cover_url: Option<String>) -> Self {
+        let is_completed = progress.is_some_and(|p| p.is_completed);
+        let progress_percent = if is_completed {
+            100.0
+        } else {
+            progress
+                .and_then(|p| {
+                    let duration = p.duration.or(audiobook.duration).filter(|d| *d > 0)?;
+                    Some((p.position as f64 / duration as f64 * 100.0).clamp(0.0, 100.0))
+                })
+                .unwrap_or(0.0)
+        };
+
+        Self {
+            id: audiobook.id.clone(),
+            title: audiobook.title.clone(),
+            author: audiobook.author.clone(),
+            duration: audiobook.duration,
+            progress_percent,
+            is_completed,
+            cover_url,
+        }
+    }
+}
+
+/// Everything the detail view needs for one book
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudiobookDetails {
+    #[serde(flatten)]
+    pub audiobook: AudiobookWithEstimate,
+    pub chapters: Vec<Chapter>,
+    pub progress: Option<PlaybackProgress>,
+}
+
+/// Constraints for the "surprise me" picker
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RandomPickFilters {
+    /// Only books that were never started
+    #[serde(default)]
+    pub unlistened_only: bool,
+    #[ts(type = "number | null")]
+    pub max_duration: Option<i64>,
+    pub genre: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RandomPickCandidate {
+    #[sqlx(flatten)]
+    pub audiobook: Audiobook,
+    /// Days since the book was last played, or added if it never was
+    pub idle_days: f64,
+}
+
+/// Collection with its book count, for list views that would otherwise
+/// fetch every collection's books just to count them
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct CollectionWithCount {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub collection: Collection,
+    #[ts(type = "number")]
+    pub audiobook_count: i64,
+    #[ts(type = "number")]
+    pub total_duration: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CollectionTreeNode {
+    #[serde(flatten)]
+    pub collection: Collection,
+    pub children: Vec<CollectionTreeNode>,
+}
+
+impl CollectionTreeNode {
+    /// Arrange a flat list of collections into trees. Collections whose parent is
+    /// missing, or that sit in a parent cycle, are treated as roots.
+    pub fn build(collections: Vec<Collection>) -> Vec<CollectionTreeNode> {
+        use std::collections::{HashMap, HashSet};
+
+        let ids: HashSet<String> = collections.iter().map(|c| c.id.clone()).collect();
+        let parent_of: HashMap<String, Option<String>> = collections
+            .iter()
+            .map(|c| (c.id.clone(), c.parent_collection_id.clone().filter(|p| ids.contains(p))))
+            .collect();
+
+        // A collection only nests under its parent if walking up from it reaches a root
+        let reaches_root = |id: &str| {
+            let mut seen = HashSet::new();
+            let mut current = Some(id.to_string());
+            while let Some(node) = current {
+                if !seen.insert(node.clone()) {
+                    return false;
+                }
+                current = parent_of.get(&node).cloned().flatten();
+            }
+            true
+        };
+
+        let mut children_of: HashMap<Option<String>, Vec<Collection>> = HashMap::new();
+        for collection in collections {
+            let parent = parent_of.get(&collection.id).cloned().flatten()
+                .filter(|_| reaches_root(&collection.id));
+            children_of.entry(parent).or_default().push(collection);
+        }
+
+        fn attach(parent: Option<String>, children_of: &mut HashMap<Option<String>, Vec<Collection>>) -> Vec<CollectionTreeNode> {
+            let mut nodes: Vec<CollectionTreeNode> = children_of
+                .remove(&parent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|collection| CollectionTreeNode { collection, children: Vec::new() })
+                .collect();
+            for node in &mut nodes {
+                node.children = attach(Some(node.collection.id.clone()), children_of);
+            }
+            nodes.sort_by_key(|node| node.collection.name.to_lowercase());
+            nodes
+        }
+
+        attach(None, &mut children_of)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct SearchFilters {
     pub query: Option<String>,
     pub author: Option<String>,
     pub genre: Option<String>,
     pub narrator: Option<String>,
+    #[ts(type = "number | null")]
     pub min_duration: Option<i64>,
+    #[ts(type = "number | null")]
     pub max_duration: Option<i64>,
     pub added_after: Option<String>,
     pub added_before: Option<String>,
+    pub min_rating: Option<i32>,
+    /// kbps; books are judged by their lowest-quality file, and books whose
+    /// bitrate is not known yet are left out
+    pub min_bitrate: Option<i32>,
+    /// Some(true) lists only abandoned books, Some(false) hides them
+    pub abandoned: Option<bool>,
+    /// One of "title", "author", "rating", "duration", "added_date"
+    pub sort_by: Option<String>,
+    pub sort_desc: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum LibrarySortField {
+    Title,
+    Author,
+    /// By series name, then number in the series; books outside a series last
+    Series,
+    RecentlyPlayed,
+    Added,
+    /// The order books were arranged in; collections only
+    Manual,
+}
+
+/// How a book list is sorted, kept per collection and for the main library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SortPreference {
+    pub field: LibrarySortField,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl SortPreference {
+    /// Newest additions first, as the library has always been listed
+    pub const LIBRARY_DEFAULT: Self = Self { field: LibrarySortField::Added, descending: true };
+    pub const COLLECTION_DEFAULT: Self = Self { field: LibrarySortField::Manual, descending: false };
+}
+
+/// The list a sort preference belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum SortScope {
+    Library,
+    Collection { collection_id: String },
 }
 
 // Recommendation system models
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct ListeningHistory {
     pub id: String,
     pub audiobook_id: String,
     pub listened_at: String,
+    #[ts(type = "number")]
     pub position_seconds: i64,
+    #[ts(type = "number | null")]
     pub duration_seconds: Option<i64>,
     pub completion_percentage: f64,
+    #[ts(type = "number")]
     pub session_duration: i64,
     pub playback_speed: f64,
     pub created_at: String,
@@ -244,7 +726,8 @@ impl ListeningHistory {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct UserPreference {
     pub id: String,
     pub preference_type: String,
@@ -270,7 +753,8 @@ impl UserPreference {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct Recommendation {
     pub id: String,
     pub audiobook_id: String,
@@ -312,7 +796,8 @@ impl Recommendation {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct RecommendationFeedback {
     pub id: String,
     pub recommendation_id: String,
@@ -344,16 +829,21 @@ impl RecommendationFeedback {
 }
 
 // DTOs for the recommendation system
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateListeningHistoryDto {
     pub audiobook_id: String,
+    #[ts(type = "number")]
     pub position_seconds: i64,
+    #[ts(type = "number | null")]
     pub duration_seconds: Option<i64>,
+    #[ts(type = "number")]
     pub session_duration: i64,
     pub playback_speed: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateRecommendationFeedbackDto {
     pub recommendation_id: String,
     pub feedback_type: String,
@@ -361,34 +851,187 @@ pub struct CreateRecommendationFeedbackDto {
     pub feedback_reason: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct RecommendationWithAudiobook {
     pub recommendation: Recommendation,
     pub audiobook: Audiobook,
 }
 
 // Chapter DTOs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateChapterDto {
     pub audiobook_id: String,
     pub chapter_number: i32,
     pub title: String,
     pub file_path: String,
+    #[ts(type = "number | null")]
     pub duration: Option<i64>,
+    #[ts(type = "number | null")]
     pub file_size: Option<i64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct FileFingerprint {
+    pub id: String,
+    pub audiobook_id: String,
+    pub chapter_id: Option<String>,
+    pub file_path: String,
+    #[ts(type = "number")]
+    pub file_size: i64,
+    pub content_hash: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl FileFingerprint {
+    pub fn new(audiobook_id: String, chapter_id: Option<String>, file_path: String, file_size: i64, content_hash: String) -> Self {
+        let now = Utc::now().to_rfc3339();
+
+        Self {
+            id: Uuid::new_v4().to_string(),
+            audiobook_id,
+            chapter_id,
+            file_path,
+            file_size,
+            content_hash,
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct Play {
+    pub id: String,
+    pub audiobook_id: String,
+    pub chapter_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: String,
+    #[ts(type = "number")]
+    pub start_position: i64,
+    #[ts(type = "number")]
+    pub end_position: i64,
+    #[ts(type = "number")]
+    pub listened_seconds: i64,
+    pub created_at: String,
+}
+
+impl Play {
+    pub fn new(audiobook_id: String, chapter_id: Option<String>, started_at: String, ended_at: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            audiobook_id,
+            chapter_id,
+            started_at,
+            ended_at,
+            start_position: 0,
+            end_position: 0,
+            listened_seconds: 0,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// A play joined with the book and chapter details the history screen shows
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct PlayHistoryEntry {
+    pub id: String,
+    pub audiobook_id: String,
+    pub audiobook_title: String,
+    pub author: Option<String>,
+    pub cover_image_path: Option<String>,
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub chapter_number: Option<i32>,
+    pub started_at: String,
+    pub ended_at: String,
+    #[ts(type = "number")]
+    pub start_position: i64,
+    #[ts(type = "number")]
+    pub end_position: i64,
+    #[ts(type = "number")]
+    pub listened_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlayHistoryPage {
+    pub entries: Vec<PlayHistoryEntry>,
+    #[ts(type = "number")]
+    pub page: i64,
+    #[ts(type = "number")]
+    pub page_size: i64,
+    #[ts(type = "number")]
+    pub total: i64,
+}
+
+/// One row of the change journal; `seq` orders all changes
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct ChangeLogEntry {
+    #[ts(type = "number")]
+    pub seq: i64,
+    pub entity_type: String, // audiobook, progress or collection
+    pub entity_id: String,   // The audiobook id for progress
+    pub operation: String,   // create, update or delete
+    pub changed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ChangeSet {
+    pub changes: Vec<ChangeLogEntry>,
+    /// Pass this back to get the changes after these
+    #[ts(type = "number")]
+    pub cursor: i64,
+    pub has_more: bool,
+    /// The journal no longer reaches back to the requested cursor: read the
+    /// whole library, then continue from `cursor`
+    pub reset_required: bool,
+}
+
+/// Listening on one day, from raw history and the daily summaries combined
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct DailyListening {
+    pub day: String, // YYYY-MM-DD
+    #[ts(type = "number")]
+    pub session_count: i64,
+    #[ts(type = "number")]
+    pub session_seconds: i64,
+    #[ts(type = "number")]
+    pub play_count: i64,
+    #[ts(type = "number")]
+    pub played_seconds: i64,
+}
+
+impl DailyListening {
+    /// Time listened on the day. Plays are recorded by the player itself and
+    /// are preferred; a day without any falls back to the tracked sessions.
+    pub fn listened_seconds(&self) -> i64 {
+        if self.play_count > 0 { self.played_seconds } else { self.session_seconds }
+    }
+}
+
 // ============= EBOOK MODELS =============
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct Ebook {
     pub id: String,
     pub title: String,
     pub author: Option<String>,
+    pub author_id: Option<String>,
     pub file_path: String,
     pub file_format: String, // 'pdf' or 'epub'
     pub cover_path: Option<String>,
     pub total_pages: Option<i32>,
+    #[ts(type = "number | null")]
     pub file_size: Option<i64>,
     pub language: Option<String>,
     pub publisher: Option<String>,
@@ -410,6 +1053,7 @@ impl Ebook {
             id,
             title,
             author: None,
+            author_id: None,
             file_path,
             file_format,
             cover_path: None,
@@ -428,7 +1072,8 @@ impl Ebook {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct ReadingProgress {
     pub id: String,
     pub ebook_id: String,
@@ -436,6 +1081,7 @@ pub struct ReadingProgress {
     pub current_cfi: Option<String>,
     pub current_chapter_href: Option<String>,
     pub percentage_complete: f64,
+    #[ts(type = "number")]
     pub reading_time_seconds: i64,
     pub last_read_date: String,
     pub created_at: String,
@@ -462,7 +1108,8 @@ impl ReadingProgress {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct EbookBookmark {
     pub id: String,
     pub ebook_id: String,
@@ -494,7 +1141,8 @@ impl EbookBookmark {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct EbookAnnotation {
     pub id: String,
     pub ebook_id: String,
@@ -530,7 +1178,8 @@ impl EbookAnnotation {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
 pub struct EbookReaderSettings {
     pub ebook_id: String,
     pub font_family: String,
@@ -568,7 +1217,8 @@ impl EbookReaderSettings {
 }
 
 // Ebook DTOs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateEbookDto {
     pub title: String,
     pub file_path: String,
@@ -580,11 +1230,13 @@ pub struct CreateEbookDto {
     pub publisher: Option<String>,
     pub publication_date: Option<String>,
     pub total_pages: Option<i32>,
+    #[ts(type = "number | null")]
     pub file_size: Option<i64>,
     pub cover_path: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct UpdateEbookDto {
     pub title: Option<String>,
     pub author: Option<String>,
@@ -595,16 +1247,19 @@ pub struct UpdateEbookDto {
     pub publication_date: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct UpdateReadingProgressDto {
     pub current_page: Option<i32>,
     pub current_cfi: Option<String>,
     pub current_chapter_href: Option<String>,
     pub percentage_complete: Option<f64>,
+    #[ts(type = "number | null")]
     pub reading_time_seconds: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateBookmarkDto {
     pub ebook_id: String,
     pub page_number: Option<i32>,
@@ -613,7 +1268,8 @@ pub struct CreateBookmarkDto {
     pub note: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct CreateAnnotationDto {
     pub ebook_id: String,
     pub annotation_type: String,
@@ -624,7 +1280,8 @@ pub struct CreateAnnotationDto {
     pub note: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct UpdateReaderSettingsDto {
     pub font_family: Option<String>,
     pub font_size: Option<i32>,
@@ -637,7 +1294,8 @@ pub struct UpdateReaderSettingsDto {
     pub flow_mode: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct EbookMetadata {
     pub title: Option<String>,
     pub author: Option<String>,
@@ -649,3 +1307,62 @@ pub struct EbookMetadata {
     pub description: Option<String>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(name: &str, parent: Option<&Collection>) -> Collection {
+        let mut collection = Collection::new(name.to_string());
+        collection.parent_collection_id = parent.map(|p| p.id.clone());
+        collection
+    }
+
+    #[test]
+    fn test_collection_tree_nests_children() {
+        let sci_fi = collection("Sci-Fi", None);
+        let foundation = collection("Foundation series", Some(&sci_fi));
+        let robots = collection("Robots", Some(&sci_fi));
+        let kids = collection("Kids", None);
+
+        let tree = CollectionTreeNode::build(vec![robots, kids, foundation, sci_fi]);
+        assert_eq!(tree.iter().map(|n| n.collection.name.as_str()).collect::<Vec<_>>(), vec!["Kids", "Sci-Fi"]);
+        assert_eq!(
+            tree[1].children.iter().map(|n| n.collection.name.as_str()).collect::<Vec<_>>(),
+            vec!["Foundation series", "Robots"]
+        );
+    }
+
+    #[test]
+    fn test_collection_tree_survives_cycles_and_orphans() {
+        let mut a = collection("A", None);
+        let b = collection("B", Some(&a));
+        a.parent_collection_id = Some(b.id.clone());
+        let mut orphan = collection("Orphan", None);
+        orphan.parent_collection_id = Some("missing".to_string());
+
+        let tree = CollectionTreeNode::build(vec![a, b, orphan]);
+        assert_eq!(tree.len(), 3);
+        assert!(tree.iter().all(|node| node.children.is_empty()));
+    }
+
+    #[test]
+    fn test_summary_progress_percent() {
+        let mut book = Audiobook::new("Book".to_string(), "/books/book".to_string());
+        book.duration = Some(1000);
+        assert_eq!(AudiobookSummary::new(&book, None, None).progress_percent, 0.0);
+
+        let mut progress = PlaybackProgress::new(book.id.clone());
+        progress.position = 250;
+        assert_eq!(AudiobookSummary::new(&book, Some(&progress), None).progress_percent, 25.0);
+
+        // The progress row's own duration wins, and overshoot is clamped
+        progress.duration = Some(200);
+        assert_eq!(AudiobookSummary::new(&book, Some(&progress), None).progress_percent, 100.0);
+
+        progress.position = 0;
+        progress.is_completed = true;
+        let summary = AudiobookSummary::new(&book, Some(&progress), None);
+        assert!(summary.is_completed);
+        assert_eq!(summary.progress_percent, 100.0);
+    }
+}
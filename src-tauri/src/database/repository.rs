@@ -1,8 +1,10 @@
+use super::content_filter;
 use super::models::*;
 use sqlx::SqlitePool;
 use anyhow::{Result, Context};
 use chrono::Utc;
 use uuid::Uuid;
+use crate::validation::Validate;
 
 pub struct AudiobookRepository<'a> {
     pool: &'a SqlitePool,
@@ -14,6 +16,7 @@ impl<'a> AudiobookRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateAudiobookDto) -> Result<Audiobook> {
+        dto.validate()?;
         let mut audiobook = Audiobook::new(dto.title, dto.file_path);
         audiobook.author = dto.author;
         audiobook.narrator = dto.narrator;
@@ -21,13 +24,16 @@ impl<'a> AudiobookRepository<'a> {
         audiobook.genre = dto.genre;
         audiobook.duration = dto.duration;
         audiobook.cover_image_path = dto.cover_image_path;
+        audiobook.source_type = dto.source_type;
+        audiobook.source_id = dto.source_id;
         
         sqlx::query(
             r#"
             INSERT INTO audiobooks (
                 id, title, author, narrator, file_path, description, genre,
-                duration, cover_image_path, added_date, chapters_count, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                duration, cover_image_path, added_date, chapters_count, created_at, updated_at,
+                source_type, source_id
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&audiobook.id)
@@ -43,6 +49,8 @@ impl<'a> AudiobookRepository<'a> {
         .bind(&audiobook.chapters_count)
         .bind(&audiobook.created_at)
         .bind(&audiobook.updated_at)
+        .bind(&audiobook.source_type)
+        .bind(&audiobook.source_id)
         .execute(self.pool)
         .await
         .context("Failed to create audiobook")?;
@@ -70,7 +78,19 @@ impl<'a> AudiobookRepository<'a> {
         .await
         .context("Failed to fetch all audiobooks")?;
 
-        Ok(audiobooks)
+        Ok(content_filter::apply(audiobooks))
+    }
+
+    /// The whole library in the given order. Manual order only exists within
+    /// collections, so it lists the library by date added.
+    pub async fn find_all_sorted(&self, sort: SortPreference) -> Result<Vec<Audiobook>> {
+        let query = format!("SELECT a.* FROM audiobooks a {} ORDER BY {}", SORT_JOINS, order_by(sort, None));
+        let audiobooks = sqlx::query_as::<_, Audiobook>(&query)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch all audiobooks")?;
+
+        Ok(content_filter::apply(audiobooks))
     }
 
     pub async fn search(&self, query: &str) -> Result<Vec<Audiobook>> {
@@ -104,10 +124,11 @@ impl<'a> AudiobookRepository<'a> {
         .await
         .context("Failed to search audiobooks")?;
 
-        Ok(audiobooks)
+        Ok(content_filter::apply(audiobooks))
     }
 
     pub async fn search_with_filters(&self, filters: SearchFilters) -> Result<Vec<Audiobook>> {
+        filters.validate()?;
         let mut query = String::from("SELECT * FROM audiobooks WHERE 1=1");
         let mut params: Vec<String> = Vec::new();
 
@@ -164,8 +185,38 @@ impl<'a> AudiobookRepository<'a> {
             params.push(added_before.clone());
         }
 
+        if let Some(min_rating) = filters.min_rating {
+            query.push_str(" AND rating >= ?");
+            params.push(min_rating.to_string());
+        }
+
+        if let Some(min_bitrate) = filters.min_bitrate {
+            query.push_str(" AND COALESCE((SELECT MIN(c.bitrate) FROM chapters c WHERE c.audiobook_id = audiobooks.id), bitrate) >= CAST(? AS INTEGER)");
+            params.push(min_bitrate.to_string());
+        }
+
+        if let Some(abandoned) = filters.abandoned {
+            query.push_str(if abandoned { " AND" } else { " AND NOT" });
+            query.push_str(" EXISTS (SELECT 1 FROM playback_progress pp WHERE pp.audiobook_id = audiobooks.id AND pp.is_abandoned)");
+        }
+
+        // An explicit sort wins over relevance ordering
+        let explicit_sort = filters.sort_by.as_deref().and_then(|sort_by| {
+            let direction = if filters.sort_desc.unwrap_or(sort_by == "rating") { "DESC" } else { "ASC" };
+            match sort_by {
+                "title" => Some(format!(" ORDER BY title COLLATE NOCASE {}", direction)),
+                "author" => Some(format!(" ORDER BY author IS NULL, author COLLATE NOCASE {}, title COLLATE NOCASE", direction)),
+                "rating" => Some(format!(" ORDER BY rating IS NULL, rating {}, title COLLATE NOCASE", direction)),
+                "duration" => Some(format!(" ORDER BY duration IS NULL, duration {}", direction)),
+                "added_date" => Some(format!(" ORDER BY added_date {}", direction)),
+                _ => None,
+            }
+        });
+
         // Add ordering with relevance scoring if search query exists
-        if let Some(search_query) = &filters.query {
+        if let Some(order_by) = explicit_sort {
+            query.push_str(&order_by);
+        } else if let Some(search_query) = &filters.query {
             if !search_query.is_empty() {
                 query.push_str(
                     " ORDER BY 
@@ -198,7 +249,59 @@ impl<'a> AudiobookRepository<'a> {
             .await
             .context("Failed to search audiobooks with filters")?;
 
-        Ok(audiobooks)
+        Ok(content_filter::apply(audiobooks))
+    }
+
+    /// Books eligible for a random pick, each with how long it has sat untouched.
+    /// Finished and abandoned books are never candidates.
+    pub async fn find_random_candidates(&self, filters: &RandomPickFilters) -> Result<Vec<RandomPickCandidate>> {
+        filters.validate()?;
+        let mut query = String::from(
+            r#"
+            SELECT a.*,
+                   MAX(julianday('now') - julianday(COALESCE(pp.last_played_at, a.added_date)), 0.0) as idle_days
+            FROM audiobooks a
+            LEFT JOIN playback_progress pp ON pp.audiobook_id = a.id
+            WHERE NOT COALESCE(pp.is_completed, FALSE)
+              AND NOT COALESCE(pp.is_abandoned, FALSE)
+            "#,
+        );
+        let mut params: Vec<String> = Vec::new();
+
+        if filters.unlistened_only {
+            query.push_str(" AND COALESCE(pp.position, 0) = 0");
+        }
+
+        if let Some(max_duration) = filters.max_duration {
+            query.push_str(" AND a.duration IS NOT NULL AND a.duration <= ?");
+            params.push(max_duration.to_string());
+        }
+
+        if let Some(genre) = filters.genre.as_deref().filter(|genre| !genre.is_empty()) {
+            query.push_str(" AND a.genre LIKE ?");
+            params.push(format!("%{}%", genre));
+        }
+
+        if let Some(language) = filters.language.as_deref().filter(|language| !language.is_empty()) {
+            query.push_str(" AND a.language = ? COLLATE NOCASE");
+            params.push(language.to_string());
+        }
+
+        let mut sql_query = sqlx::query_as::<_, RandomPickCandidate>(&query);
+        for param in params {
+            sql_query = sql_query.bind(param);
+        }
+
+        let candidates = sql_query
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch random pick candidates")?;
+
+        let filter = content_filter::active();
+        Ok(candidates
+            .into_iter()
+            .filter(|candidate| !filter.as_ref().is_some_and(|filter| filter.blocks_audiobook(&candidate.audiobook)))
+            .collect())
     }
 
     pub async fn get_distinct_authors(&self) -> Result<Vec<String>> {
@@ -235,6 +338,149 @@ impl<'a> AudiobookRepository<'a> {
     }
 
 
+    pub async fn set_rating(&self, id: &str, rating: Option<i32>) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET rating = ?, updated_at = ? WHERE id = ?")
+            .bind(rating)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook rating")?;
+
+        Ok(())
+    }
+
+    pub async fn set_review(&self, id: &str, review: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET review = ?, updated_at = ? WHERE id = ?")
+            .bind(review)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook review")?;
+
+        Ok(())
+    }
+
+    pub async fn set_file_path(&self, id: &str, file_path: &str) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET file_path = ?, updated_at = ? WHERE id = ?")
+            .bind(file_path)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook file path")?;
+
+        Ok(())
+    }
+
+    pub async fn set_source(&self, id: &str, source_type: &str, source_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET source_type = ?, source_id = ?, updated_at = ? WHERE id = ?")
+            .bind(source_type)
+            .bind(source_id)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook source")?;
+
+        Ok(())
+    }
+
+    pub async fn set_cover(&self, id: &str, cover_image_path: &str, cover_source: &str) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET cover_image_path = ?, cover_source = ?, updated_at = ? WHERE id = ?")
+            .bind(cover_image_path)
+            .bind(cover_source)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook cover")?;
+
+        Ok(())
+    }
+
+    pub async fn set_import_status(&self, id: &str, import_status: &str) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET import_status = ?, updated_at = ? WHERE id = ?")
+            .bind(import_status)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update audiobook import status")?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_source_type(&self, source_type: &str) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            "SELECT * FROM audiobooks WHERE source_type = ? ORDER BY title"
+        )
+        .bind(source_type)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch audiobooks by source")?;
+
+        Ok(audiobooks)
+    }
+
+    pub async fn find_incomplete(&self) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            "SELECT * FROM audiobooks WHERE import_status = ? ORDER BY added_date DESC"
+        )
+        .bind(IMPORT_STATUS_INCOMPLETE)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch incomplete audiobooks")?;
+
+        Ok(audiobooks)
+    }
+
+    /// Books imported before sources were recorded
+    pub async fn find_without_source(&self) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            "SELECT * FROM audiobooks WHERE source_type IS NULL"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch audiobooks without a source")?;
+
+        Ok(audiobooks)
+    }
+
+    pub async fn apply_source_metadata(&self, id: &str, metadata: &SourceMetadata) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE audiobooks SET
+                title = COALESCE(?, title),
+                author = COALESCE(?, author),
+                description = COALESCE(?, description),
+                genre = COALESCE(?, genre),
+                language = COALESCE(?, language),
+                publish_date = COALESCE(?, publish_date),
+                duration = COALESCE(?, duration),
+                cover_image_path = COALESCE(?, cover_image_path),
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&metadata.title)
+        .bind(&metadata.author)
+        .bind(&metadata.description)
+        .bind(&metadata.genre)
+        .bind(&metadata.language)
+        .bind(&metadata.publish_date)
+        .bind(metadata.duration)
+        .bind(&metadata.cover_image_path)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .context("Failed to update audiobook metadata")?;
+
+        Ok(())
+    }
+
     pub async fn delete(&self, id: &str) -> Result<()> {
         sqlx::query("DELETE FROM audiobooks WHERE id = ?")
             .bind(id)
@@ -256,6 +502,7 @@ impl<'a> PlaybackProgressRepository<'a> {
     }
 
     pub async fn create_or_update(&self, audiobook_id: &str, dto: UpdatePlaybackProgressDto) -> Result<PlaybackProgress> {
+        dto.validate()?;
         let updated_at = Utc::now().to_rfc3339();
 
         // Try to find existing progress
@@ -282,12 +529,19 @@ impl<'a> PlaybackProgressRepository<'a> {
             if let Some(is_completed) = dto.is_completed {
                 progress.is_completed = is_completed;
             }
+            // Finishing a book overrides having given up on it earlier
+            if progress.is_completed {
+                progress.is_abandoned = false;
+                progress.abandoned_at = None;
+                progress.abandon_reason = None;
+            }
 
             sqlx::query(
                 r#"
                 UPDATE playback_progress SET
                     position = ?, chapter_index = ?, playback_speed = ?,
-                    last_played_at = ?, is_completed = ?, updated_at = ?
+                    last_played_at = ?, is_completed = ?, is_abandoned = ?,
+                    abandoned_at = ?, abandon_reason = ?, updated_at = ?
                 WHERE audiobook_id = ?
                 "#
             )
@@ -296,6 +550,9 @@ impl<'a> PlaybackProgressRepository<'a> {
             .bind(&progress.playback_speed)
             .bind(&progress.last_played_at)
             .bind(&progress.is_completed)
+            .bind(progress.is_abandoned)
+            .bind(&progress.abandoned_at)
+            .bind(&progress.abandon_reason)
             .bind(&progress.updated_at)
             .bind(audiobook_id)
             .execute(self.pool)
@@ -357,6 +614,131 @@ impl<'a> PlaybackProgressRepository<'a> {
         Ok(progress)
     }
 
+    pub async fn find_all(&self) -> Result<Vec<PlaybackProgress>> {
+        let progress = sqlx::query_as::<_, PlaybackProgress>("SELECT * FROM playback_progress")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch playback progress")?;
+
+        Ok(progress)
+    }
+
+    /// The book most recently listened to, for restoring the player at startup
+    pub async fn find_last_played(&self) -> Result<Option<PlaybackProgress>> {
+        let progress = sqlx::query_as::<_, PlaybackProgress>(
+            "SELECT * FROM playback_progress ORDER BY last_played_at DESC LIMIT 1"
+        )
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to fetch last played progress")?;
+
+        Ok(progress)
+    }
+
+    /// Move the saved position without counting it as listening
+    pub async fn set_position(&self, audiobook_id: &str, position: i64) -> Result<()> {
+        sqlx::query("UPDATE playback_progress SET position = ?, updated_at = ? WHERE audiobook_id = ?")
+            .bind(position)
+            .bind(Utc::now().to_rfc3339())
+            .bind(audiobook_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update playback position")?;
+
+        Ok(())
+    }
+
+    /// Remember the speed a book is listened at; books never played have no row to update
+    pub async fn set_playback_speed(&self, audiobook_id: &str, speed: f64) -> Result<()> {
+        sqlx::query("UPDATE playback_progress SET playback_speed = ?, updated_at = ? WHERE audiobook_id = ?")
+            .bind(speed)
+            .bind(Utc::now().to_rfc3339())
+            .bind(audiobook_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update playback speed")?;
+
+        Ok(())
+    }
+
+    /// Flag or unflag a book as abandoned, creating its progress row if it was never played
+    pub async fn set_abandoned(&self, audiobook_id: &str, abandoned: bool, reason: Option<&str>) -> Result<PlaybackProgress> {
+        let now = Utc::now().to_rfc3339();
+        let abandoned_at = abandoned.then(|| now.clone());
+        let reason = if abandoned { reason } else { None };
+
+        let updated = sqlx::query(
+            r#"
+            UPDATE playback_progress SET
+                is_abandoned = ?, abandoned_at = ?, abandon_reason = ?, updated_at = ?
+            WHERE audiobook_id = ?
+            "#
+        )
+        .bind(abandoned)
+        .bind(&abandoned_at)
+        .bind(reason)
+        .bind(&now)
+        .bind(audiobook_id)
+        .execute(self.pool)
+        .await
+        .context("Failed to update abandoned state")?;
+
+        if updated.rows_affected() == 0 {
+            let mut progress = PlaybackProgress::new(audiobook_id.to_string());
+            progress.is_abandoned = abandoned;
+            progress.abandoned_at = abandoned_at;
+            progress.abandon_reason = reason.map(str::to_string);
+
+            sqlx::query(
+                r#"
+                INSERT INTO playback_progress (
+                    id, audiobook_id, position, chapter_index, playback_speed, last_played_at,
+                    is_completed, is_abandoned, abandoned_at, abandon_reason, created_at, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&progress.id)
+            .bind(&progress.audiobook_id)
+            .bind(&progress.position)
+            .bind(&progress.chapter_index)
+            .bind(&progress.playback_speed)
+            .bind(&progress.last_played_at)
+            .bind(&progress.is_completed)
+            .bind(progress.is_abandoned)
+            .bind(&progress.abandoned_at)
+            .bind(&progress.abandon_reason)
+            .bind(&progress.created_at)
+            .bind(&progress.updated_at)
+            .execute(self.pool)
+            .await
+            .context("Failed to create playback progress")?;
+
+            return Ok(progress);
+        }
+
+        self.find_by_audiobook_id(audiobook_id)
+            .await?
+            .context("Playback progress disappeared after update")
+    }
+
+    /// Books started but neither finished nor abandoned, most recently played first
+    pub async fn find_in_progress(&self, limit: i64) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            r#"
+            SELECT a.* FROM audiobooks a
+            JOIN playback_progress pp ON pp.audiobook_id = a.id
+            WHERE pp.position > 0 AND NOT pp.is_completed AND NOT pp.is_abandoned
+            ORDER BY pp.last_played_at DESC
+            LIMIT ?
+            "#
+        )
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch in-progress audiobooks")?;
+
+        Ok(content_filter::apply(audiobooks))
+    }
 }
 
 pub struct CollectionRepository<'a> {
@@ -369,6 +751,7 @@ impl<'a> CollectionRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateCollectionDto) -> Result<Collection> {
+        dto.validate()?;
         let mut collection = Collection::new(dto.name);
         
         if let Some(description) = dto.description {
@@ -377,12 +760,18 @@ impl<'a> CollectionRepository<'a> {
         if let Some(color) = dto.color {
             collection.color = color;
         }
+        if let Some(parent_id) = dto.parent_collection_id {
+            if self.find_by_id(&parent_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Parent collection not found: {}", parent_id));
+            }
+            collection.parent_collection_id = Some(parent_id);
+        }
 
         sqlx::query(
             r#"
             INSERT INTO collections (
-                id, name, description, color, is_smart, smart_criteria, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                id, name, description, color, is_smart, smart_criteria, parent_collection_id, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#
         )
         .bind(&collection.id)
@@ -391,6 +780,7 @@ impl<'a> CollectionRepository<'a> {
         .bind(&collection.color)
         .bind(&collection.is_smart)
         .bind(&collection.smart_criteria)
+        .bind(&collection.parent_collection_id)
         .bind(&collection.created_at)
         .bind(&collection.updated_at)
         .execute(self.pool)
@@ -411,6 +801,26 @@ impl<'a> CollectionRepository<'a> {
         Ok(collections)
     }
 
+    pub async fn find_all_with_counts(&self) -> Result<Vec<CollectionWithCount>> {
+        let collections = sqlx::query_as::<_, CollectionWithCount>(
+            r#"
+            SELECT c.*,
+                   COUNT(a.id) as audiobook_count,
+                   COALESCE(SUM(a.duration), 0) as total_duration
+            FROM collections c
+            LEFT JOIN collection_audiobooks ca ON ca.collection_id = c.id
+            LEFT JOIN audiobooks a ON a.id = ca.audiobook_id
+            GROUP BY c.id
+            ORDER BY c.created_at DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch collections with counts")?;
+
+        Ok(collections)
+    }
+
     pub async fn find_by_id(&self, id: &str) -> Result<Option<Collection>> {
         let collection = sqlx::query_as::<_, Collection>(
             "SELECT * FROM collections WHERE id = ?"
@@ -424,6 +834,7 @@ impl<'a> CollectionRepository<'a> {
     }
 
     pub async fn update(&self, id: &str, dto: CreateCollectionDto) -> Result<()> {
+        dto.validate()?;
         let updated_at = Utc::now().to_rfc3339();
         
         sqlx::query(
@@ -445,6 +856,94 @@ impl<'a> CollectionRepository<'a> {
         Ok(())
     }
 
+    pub async fn set_cover(&self, id: &str, cover_image_path: Option<&str>, is_custom: bool) -> Result<()> {
+        sqlx::query(
+            "UPDATE collections SET cover_image_path = ?, cover_is_custom = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(cover_image_path)
+        .bind(is_custom)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .context("Failed to update collection cover")?;
+
+        Ok(())
+    }
+
+    pub async fn get_stats(&self, id: &str) -> Result<CollectionStats> {
+        // Progress rows also exist for books that were only flagged (e.g. abandoned),
+        // so only rows with actual listening count towards last played
+        let stats = sqlx::query_as::<_, CollectionStats>(
+            r#"
+            SELECT ? as collection_id,
+                   COUNT(a.id) as audiobook_count,
+                   COALESCE(SUM(a.duration), 0) as total_duration,
+                   COALESCE(SUM(pp.is_completed), 0) as finished_count,
+                   MAX(CASE WHEN pp.position > 0 OR pp.is_completed THEN pp.last_played_at END) as last_played_at
+            FROM collection_audiobooks ca
+            JOIN audiobooks a ON a.id = ca.audiobook_id
+            LEFT JOIN playback_progress pp ON pp.audiobook_id = a.id
+            WHERE ca.collection_id = ?
+            "#
+        )
+        .bind(id)
+        .bind(id)
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to compute collection stats")?;
+
+        Ok(stats)
+    }
+
+    pub async fn find_tree(&self) -> Result<Vec<CollectionTreeNode>> {
+        Ok(CollectionTreeNode::build(self.find_all().await?))
+    }
+
+    /// Move a collection under a new parent (None moves it to the top level),
+    /// refusing moves that would put a collection inside itself or its descendants
+    pub async fn move_collection(&self, id: &str, new_parent_id: Option<&str>) -> Result<()> {
+        if self.find_by_id(id).await?.is_none() {
+            return Err(anyhow::anyhow!("Collection not found: {}", id));
+        }
+
+        if let Some(parent_id) = new_parent_id {
+            if self.find_by_id(parent_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Parent collection not found: {}", parent_id));
+            }
+
+            let ancestors = sqlx::query_scalar::<_, String>(
+                r#"
+                WITH RECURSIVE ancestors(id, parent_collection_id) AS (
+                    SELECT id, parent_collection_id FROM collections WHERE id = ?
+                    UNION
+                    SELECT c.id, c.parent_collection_id FROM collections c
+                    JOIN ancestors ON c.id = ancestors.parent_collection_id
+                )
+                SELECT id FROM ancestors
+                "#
+            )
+            .bind(parent_id)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to walk collection ancestors")?;
+
+            if ancestors.iter().any(|ancestor| ancestor == id) {
+                return Err(anyhow::anyhow!("A collection cannot be moved inside itself or one of its sub-collections"));
+            }
+        }
+
+        sqlx::query("UPDATE collections SET parent_collection_id = ?, updated_at = ? WHERE id = ?")
+            .bind(new_parent_id)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to move collection")?;
+
+        Ok(())
+    }
+
     pub async fn delete(&self, id: &str) -> Result<()> {
         // First, delete all collection_audiobook relationships
         sqlx::query("DELETE FROM collection_audiobooks WHERE collection_id = ?")
@@ -453,6 +952,20 @@ impl<'a> CollectionRepository<'a> {
             .await
             .context("Failed to delete collection audiobook relationships")?;
 
+        // Sub-collections move up to the deleted collection's parent instead of vanishing
+        sqlx::query(
+            r#"
+            UPDATE collections
+            SET parent_collection_id = (SELECT parent_collection_id FROM collections WHERE id = ?)
+            WHERE parent_collection_id = ?
+            "#
+        )
+        .bind(id)
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .context("Failed to re-parent sub-collections")?;
+
         // Then delete the collection itself
         sqlx::query("DELETE FROM collections WHERE id = ?")
             .bind(id)
@@ -527,20 +1040,22 @@ impl<'a> CollectionRepository<'a> {
     }
 
     pub async fn get_collection_audiobooks(&self, collection_id: &str) -> Result<Vec<Audiobook>> {
-        let audiobooks = sqlx::query_as::<_, Audiobook>(
-            r#"
-            SELECT a.* FROM audiobooks a
-            JOIN collection_audiobooks ca ON a.id = ca.audiobook_id
-            WHERE ca.collection_id = ?
-            ORDER BY ca.sort_order, ca.added_at
-            "#
-        )
-        .bind(collection_id)
-        .fetch_all(self.pool)
-        .await
-        .context("Failed to fetch collection audiobooks")?;
+        self.get_collection_audiobooks_sorted(collection_id, SortPreference::COLLECTION_DEFAULT).await
+    }
 
-        Ok(audiobooks)
+    pub async fn get_collection_audiobooks_sorted(&self, collection_id: &str, sort: SortPreference) -> Result<Vec<Audiobook>> {
+        let query = format!(
+            "SELECT a.* FROM audiobooks a JOIN collection_audiobooks ca ON a.id = ca.audiobook_id {} WHERE ca.collection_id = ? ORDER BY {}",
+            SORT_JOINS,
+            order_by(sort, Some("ca.sort_order {dir}, ca.added_at {dir}"))
+        );
+        let audiobooks = sqlx::query_as::<_, Audiobook>(&query)
+            .bind(collection_id)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch collection audiobooks")?;
+
+        Ok(content_filter::apply(audiobooks))
     }
 
     pub async fn reorder_audiobooks(&self, collection_id: &str, audiobook_orders: Vec<(String, i32)>) -> Result<()> {
@@ -560,6 +1075,28 @@ impl<'a> CollectionRepository<'a> {
     }
 }
 
+/// Series and last-played columns for sorted book lists, on audiobooks aliased `a`
+const SORT_JOINS: &str = "LEFT JOIN audiobook_series s ON s.audiobook_id = a.id \
+    LEFT JOIN (SELECT audiobook_id, MAX(last_played_at) AS last_played_at FROM playback_progress GROUP BY audiobook_id) pp ON pp.audiobook_id = a.id";
+
+/// The ORDER BY terms for a sort preference. `manual` is the list's own order
+/// with `{dir}` for the direction; lists without one fall back to date added.
+fn order_by(sort: SortPreference, manual: Option<&str>) -> String {
+    let dir = if sort.descending { "DESC" } else { "ASC" };
+    if let (LibrarySortField::Manual, Some(manual)) = (sort.field, manual) {
+        return manual.replace("{dir}", dir);
+    }
+    match sort.field {
+        LibrarySortField::Title => format!("a.title COLLATE NOCASE {}", dir),
+        LibrarySortField::Author => format!("a.author IS NULL, a.author COLLATE NOCASE {}, a.title COLLATE NOCASE", dir),
+        LibrarySortField::Series => format!(
+            "s.name IS NULL, s.name COLLATE NOCASE {dir}, s.position IS NULL, s.position {dir}, a.title COLLATE NOCASE"
+        ),
+        LibrarySortField::RecentlyPlayed => format!("pp.last_played_at IS NULL, pp.last_played_at {}, a.added_date DESC", dir),
+        LibrarySortField::Added | LibrarySortField::Manual => format!("a.added_date {}", dir),
+    }
+}
+
 pub struct ChapterRepository<'a> {
     pool: &'a SqlitePool,
 }
@@ -570,6 +1107,7 @@ impl<'a> ChapterRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateChapterDto) -> Result<Chapter> {
+        dto.validate()?;
         let mut chapter = Chapter::new(dto.audiobook_id, dto.chapter_number, dto.title, dto.file_path);
         chapter.duration = dto.duration;
         chapter.file_size = dto.file_size;
@@ -656,8 +1194,8 @@ impl<'a> ChapterRepository<'a> {
         Ok(())
     }
 
-    #[allow(dead_code)]
     pub async fn update_chapter(&self, id: &str, dto: CreateChapterDto) -> Result<()> {
+        dto.validate()?;
         let now = Utc::now().to_rfc3339();
         
         sqlx::query(
@@ -681,3 +1219,1159 @@ impl<'a> ChapterRepository<'a> {
     }
 }
 
+
+pub struct ChapterMarkerRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChapterMarkerRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, marker: &ChapterMarker) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO chapter_markers (id, audiobook_id, position, title, is_auto, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&marker.id)
+        .bind(&marker.audiobook_id)
+        .bind(marker.position)
+        .bind(&marker.title)
+        .bind(marker.is_auto)
+        .bind(&marker.created_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to create chapter marker")?;
+
+        Ok(())
+    }
+
+    /// Swap a book's generated markers for a new set in one transaction; markers
+    /// the listener added by hand are kept
+    pub async fn replace_auto(&self, audiobook_id: &str, markers: &[ChapterMarker]) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+
+        sqlx::query("DELETE FROM chapter_markers WHERE audiobook_id = ? AND is_auto = TRUE")
+            .bind(audiobook_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear generated chapter markers")?;
+
+        for marker in markers {
+            sqlx::query(
+                r#"
+                INSERT INTO chapter_markers (id, audiobook_id, position, title, is_auto, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&marker.id)
+            .bind(&marker.audiobook_id)
+            .bind(marker.position)
+            .bind(&marker.title)
+            .bind(marker.is_auto)
+            .bind(&marker.created_at)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to create chapter marker")?;
+        }
+
+        tx.commit().await.context("Failed to commit chapter markers")?;
+        Ok(())
+    }
+
+    pub async fn find_by_audiobook_id(&self, audiobook_id: &str) -> Result<Vec<ChapterMarker>> {
+        let markers = sqlx::query_as::<_, ChapterMarker>(
+            "SELECT * FROM chapter_markers WHERE audiobook_id = ? ORDER BY position ASC, created_at ASC"
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch chapter markers")?;
+
+        Ok(markers)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM chapter_markers WHERE id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete chapter marker")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Chapter marker not found: {}", id));
+        }
+        Ok(())
+    }
+}
+
+pub struct TtsTimingRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TtsTimingRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Store the sentences of one chunk file, replacing timings from an earlier
+    /// synthesis of the same file. New sentences are numbered after the
+    /// chapter's existing ones, so chunks recorded in order read in order.
+    pub async fn replace_for_file(&self, chapter_id: &str, file_path: &str, sentences: &[AlignedSegment]) -> Result<Vec<TtsTiming>> {
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+
+        sqlx::query("DELETE FROM tts_timings WHERE chapter_id = ? AND file_path = ?")
+            .bind(chapter_id)
+            .bind(file_path)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear TTS timings")?;
+
+        let next_index: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(sentence_index) + 1, 0) FROM tts_timings WHERE chapter_id = ?")
+            .bind(chapter_id)
+            .fetch_one(&mut *tx)
+            .await
+            .context("Failed to number TTS timings")?;
+
+        let created_at = Utc::now().to_rfc3339();
+        let mut timings = Vec::with_capacity(sentences.len());
+        for (offset, sentence) in sentences.iter().enumerate() {
+            let timing = TtsTiming {
+                id: Uuid::new_v4().to_string(),
+                chapter_id: chapter_id.to_string(),
+                file_path: file_path.to_string(),
+                sentence_index: next_index + offset as i64,
+                text: sentence.text.clone(),
+                start_time: sentence.start,
+                end_time: sentence.end,
+                created_at: created_at.clone(),
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO tts_timings (id, chapter_id, file_path, sentence_index, text, start_time, end_time, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&timing.id)
+            .bind(&timing.chapter_id)
+            .bind(&timing.file_path)
+            .bind(timing.sentence_index)
+            .bind(&timing.text)
+            .bind(timing.start_time)
+            .bind(timing.end_time)
+            .bind(&timing.created_at)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store TTS timing")?;
+
+            timings.push(timing);
+        }
+
+        tx.commit().await.context("Failed to commit TTS timings")?;
+        Ok(timings)
+    }
+
+    pub async fn delete_for_chapter(&self, chapter_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM tts_timings WHERE chapter_id = ?")
+            .bind(chapter_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to clear TTS timings")?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_chapter_id(&self, chapter_id: &str) -> Result<Vec<TtsTiming>> {
+        let timings = sqlx::query_as::<_, TtsTiming>(
+            "SELECT * FROM tts_timings WHERE chapter_id = ? ORDER BY sentence_index ASC"
+        )
+        .bind(chapter_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch TTS timings")?;
+
+        Ok(timings)
+    }
+
+    /// Sentences of `file_path` spoken at any time between `from` and `to`
+    /// seconds into the file, in reading order
+    pub async fn find_in_file(&self, file_path: &str, from: f64, to: f64) -> Result<Vec<TtsTiming>> {
+        let timings = sqlx::query_as::<_, TtsTiming>(
+            "SELECT * FROM tts_timings WHERE file_path = ? AND end_time > ? AND start_time < ? ORDER BY sentence_index ASC"
+        )
+        .bind(file_path)
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch TTS timings")?;
+
+        Ok(timings)
+    }
+}
+
+pub struct DocumentRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> DocumentRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert or update the document stored for `document.file_path`, keeping its id
+    pub async fn upsert(&self, document: &StoredDocument) -> Result<StoredDocument> {
+        sqlx::query(
+            r#"
+            INSERT INTO documents (id, file_path, title, author, format, target_minutes,
+                                   split_on_sentences, respect_headings, sections, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(file_path) DO UPDATE SET
+                title = excluded.title, author = COALESCE(excluded.author, documents.author), format = excluded.format,
+                target_minutes = excluded.target_minutes, split_on_sentences = excluded.split_on_sentences,
+                respect_headings = excluded.respect_headings, sections = excluded.sections,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(&document.id)
+        .bind(&document.file_path)
+        .bind(&document.title)
+        .bind(&document.author)
+        .bind(&document.format)
+        .bind(document.target_minutes)
+        .bind(document.split_on_sentences)
+        .bind(document.respect_headings)
+        .bind(&document.sections)
+        .bind(&document.created_at)
+        .bind(&document.updated_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to save document")?;
+
+        self.find_by_path(&document.file_path)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document disappeared after saving"))
+    }
+
+    pub async fn set_source(&self, id: &str, source_url: &str, site_name: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE documents SET source_url = ?, site_name = ?, updated_at = ? WHERE id = ?")
+            .bind(source_url)
+            .bind(site_name)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update document source")?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<StoredDocument>> {
+        let document = sqlx::query_as::<_, StoredDocument>("SELECT * FROM documents WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to fetch document")?;
+
+        Ok(document)
+    }
+
+    pub async fn find_by_path(&self, file_path: &str) -> Result<Option<StoredDocument>> {
+        let document = sqlx::query_as::<_, StoredDocument>("SELECT * FROM documents WHERE file_path = ?")
+            .bind(file_path)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to fetch document")?;
+
+        Ok(document)
+    }
+}
+
+pub struct TtsChapterSourceRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TtsChapterSourceRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn upsert(&self, chapter_id: &str, text: &str, voice: Option<&str>, speed: f64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO tts_chapter_sources (chapter_id, text, voice, speed, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(chapter_id) DO UPDATE SET
+                text = excluded.text, voice = excluded.voice, speed = excluded.speed, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(chapter_id)
+        .bind(text)
+        .bind(voice)
+        .bind(speed)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to save TTS chapter source")?;
+
+        Ok(())
+    }
+
+    pub async fn update_text(&self, chapter_id: &str, text: &str) -> Result<()> {
+        let result = sqlx::query("UPDATE tts_chapter_sources SET text = ?, updated_at = ? WHERE chapter_id = ?")
+            .bind(text)
+            .bind(Utc::now().to_rfc3339())
+            .bind(chapter_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update TTS chapter text")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("No source text stored for chapter {}", chapter_id));
+        }
+        Ok(())
+    }
+
+    pub async fn find(&self, chapter_id: &str) -> Result<Option<TtsChapterSource>> {
+        let source = sqlx::query_as::<_, TtsChapterSource>("SELECT * FROM tts_chapter_sources WHERE chapter_id = ?")
+            .bind(chapter_id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to fetch TTS chapter source")?;
+
+        Ok(source)
+    }
+}
+
+pub struct PreferencesRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PreferencesRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<String>> {
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM app_preferences WHERE key = ?"
+        )
+        .bind(key)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to read preference")?;
+
+        Ok(value)
+    }
+
+    pub async fn set(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO app_preferences (key, value, updated_at)
+            VALUES (?, ?, datetime('now'))
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(key)
+        .bind(value)
+        .execute(self.pool)
+        .await
+        .context("Failed to save preference")?;
+
+        Ok(())
+    }
+
+    pub async fn get_bool(&self, key: &str, default: bool) -> Result<bool> {
+        Ok(self.get(key).await?
+            .map(|v| matches!(v.as_str(), "true" | "1"))
+            .unwrap_or(default))
+    }
+
+    pub async fn get_i64(&self, key: &str, default: i64) -> Result<i64> {
+        Ok(self.get(key).await?
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(default))
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM app_preferences WHERE key = ?")
+            .bind(key)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete preference")?;
+
+        Ok(())
+    }
+}
+
+pub struct FingerprintRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> FingerprintRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn upsert(&self, fingerprint: &FileFingerprint) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO file_fingerprints (id, audiobook_id, chapter_id, file_path, file_size, content_hash, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(audiobook_id, file_path) DO UPDATE SET
+                chapter_id = excluded.chapter_id,
+                file_size = excluded.file_size,
+                content_hash = excluded.content_hash,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&fingerprint.id)
+        .bind(&fingerprint.audiobook_id)
+        .bind(&fingerprint.chapter_id)
+        .bind(&fingerprint.file_path)
+        .bind(fingerprint.file_size)
+        .bind(&fingerprint.content_hash)
+        .bind(&fingerprint.created_at)
+        .bind(&fingerprint.updated_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to save file fingerprint")?;
+
+        Ok(())
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<FileFingerprint>> {
+        let fingerprints = sqlx::query_as::<_, FileFingerprint>(
+            "SELECT * FROM file_fingerprints ORDER BY audiobook_id, file_path"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch file fingerprints")?;
+
+        Ok(fingerprints)
+    }
+
+    pub async fn find_by_audiobook_id(&self, audiobook_id: &str) -> Result<Vec<FileFingerprint>> {
+        let fingerprints = sqlx::query_as::<_, FileFingerprint>(
+            "SELECT * FROM file_fingerprints WHERE audiobook_id = ? ORDER BY file_path"
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch file fingerprints for audiobook")?;
+
+        Ok(fingerprints)
+    }
+
+    pub async fn update_path(&self, id: &str, new_path: &str) -> Result<()> {
+        sqlx::query("UPDATE file_fingerprints SET file_path = ?, updated_at = ? WHERE id = ?")
+            .bind(new_path)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update file fingerprint path")?;
+
+        Ok(())
+    }
+}
+
+pub struct PlayRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PlayRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, play: &Play) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO plays (id, audiobook_id, chapter_id, started_at, ended_at, start_position, end_position, listened_seconds, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&play.id)
+        .bind(&play.audiobook_id)
+        .bind(&play.chapter_id)
+        .bind(&play.started_at)
+        .bind(&play.ended_at)
+        .bind(play.start_position)
+        .bind(play.end_position)
+        .bind(play.listened_seconds)
+        .bind(&play.created_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to insert play")?;
+
+        Ok(())
+    }
+
+    /// Most recent play of the same book and chapter, used to merge short interruptions
+    pub async fn find_latest(&self, audiobook_id: &str, chapter_id: Option<&str>) -> Result<Option<Play>> {
+        let play = sqlx::query_as::<_, Play>(
+            "SELECT * FROM plays WHERE audiobook_id = ? AND chapter_id IS ? ORDER BY ended_at DESC LIMIT 1"
+        )
+        .bind(audiobook_id)
+        .bind(chapter_id)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to fetch latest play")?;
+
+        Ok(play)
+    }
+
+    pub async fn extend(&self, id: &str, ended_at: &str, end_position: i64, listened_seconds: i64) -> Result<()> {
+        sqlx::query("UPDATE plays SET ended_at = ?, end_position = ?, listened_seconds = ? WHERE id = ?")
+            .bind(ended_at)
+            .bind(end_position)
+            .bind(listened_seconds)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to extend play")?;
+
+        Ok(())
+    }
+
+    pub async fn find_page(&self, limit: i64, offset: i64) -> Result<Vec<PlayHistoryEntry>> {
+        let entries = sqlx::query_as::<_, PlayHistoryEntry>(
+            r#"
+            SELECT p.id, p.audiobook_id, a.title AS audiobook_title, a.author, a.cover_image_path,
+                   p.chapter_id, c.title AS chapter_title, c.chapter_number,
+                   p.started_at, p.ended_at, p.start_position, p.end_position, p.listened_seconds
+            FROM plays p
+            JOIN audiobooks a ON a.id = p.audiobook_id
+            LEFT JOIN chapters c ON c.id = p.chapter_id
+            ORDER BY p.started_at DESC
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch play history")?;
+
+        Ok(entries)
+    }
+
+    pub async fn count(&self) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM plays")
+            .fetch_one(self.pool)
+            .await
+            .context("Failed to count plays")?;
+
+        Ok(count)
+    }
+}
+
+pub struct AuthorRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> AuthorRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Create an author and register its name as the first alias
+    pub async fn create(&self, name: &str, sort_name: &str, normalized_name: &str) -> Result<Author> {
+        let author = Author::new(name.to_string(), sort_name.to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO authors (id, name, sort_name, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&author.id)
+        .bind(&author.name)
+        .bind(&author.sort_name)
+        .bind(&author.created_at)
+        .bind(&author.updated_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to create author")?;
+
+        self.add_alias(&author.id, name, normalized_name).await?;
+        Ok(author)
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> Result<Option<Author>> {
+        let author = sqlx::query_as::<_, Author>("SELECT * FROM authors WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to find author")?;
+
+        Ok(author)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Author>> {
+        let authors = sqlx::query_as::<_, Author>("SELECT * FROM authors ORDER BY sort_name COLLATE NOCASE")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch authors")?;
+
+        Ok(authors)
+    }
+
+    pub async fn find_by_alias(&self, normalized_alias: &str) -> Result<Option<Author>> {
+        let author = sqlx::query_as::<_, Author>(
+            r#"
+            SELECT au.* FROM authors au
+            JOIN author_aliases aa ON aa.author_id = au.id
+            WHERE aa.normalized_alias = ?
+            "#
+        )
+        .bind(normalized_alias)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to find author by alias")?;
+
+        Ok(author)
+    }
+
+    pub async fn find_all_aliases(&self) -> Result<Vec<AuthorAlias>> {
+        let aliases = sqlx::query_as::<_, AuthorAlias>("SELECT * FROM author_aliases")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to fetch author aliases")?;
+
+        Ok(aliases)
+    }
+
+    pub async fn find_aliases(&self, author_id: &str) -> Result<Vec<AuthorAlias>> {
+        let aliases = sqlx::query_as::<_, AuthorAlias>(
+            "SELECT * FROM author_aliases WHERE author_id = ? ORDER BY created_at"
+        )
+        .bind(author_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch author aliases")?;
+
+        Ok(aliases)
+    }
+
+    /// Aliases are unique across authors; a spelling already claimed is left alone
+    pub async fn add_alias(&self, author_id: &str, alias: &str, normalized_alias: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO author_aliases (id, author_id, alias, normalized_alias, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(author_id)
+        .bind(alias)
+        .bind(normalized_alias)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to add author alias")?;
+
+        Ok(())
+    }
+
+    pub async fn update_details(
+        &self,
+        id: &str,
+        bio: Option<&str>,
+        photo_url: Option<&str>,
+        openlibrary_key: Option<&str>,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            UPDATE authors SET
+                bio = COALESCE(?, bio), photo_url = COALESCE(?, photo_url),
+                openlibrary_key = COALESCE(?, openlibrary_key),
+                details_fetched_at = ?, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(bio)
+        .bind(photo_url)
+        .bind(openlibrary_key)
+        .bind(&now)
+        .bind(&now)
+        .bind(id)
+        .execute(self.pool)
+        .await
+        .context("Failed to update author details")?;
+
+        Ok(())
+    }
+
+    pub async fn link_audiobook(&self, audiobook_id: &str, author_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET author_id = ? WHERE id = ?")
+            .bind(author_id)
+            .bind(audiobook_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to link audiobook author")?;
+
+        Ok(())
+    }
+
+    pub async fn link_ebook(&self, ebook_id: &str, author_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE ebooks SET author_id = ? WHERE id = ?")
+            .bind(author_id)
+            .bind(ebook_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to link ebook author")?;
+
+        Ok(())
+    }
+
+    pub async fn find_audiobooks(&self, author_id: &str) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            "SELECT * FROM audiobooks WHERE author_id = ? ORDER BY title COLLATE NOCASE"
+        )
+        .bind(author_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch author audiobooks")?;
+
+        Ok(content_filter::apply(audiobooks))
+    }
+
+    pub async fn find_ebooks(&self, author_id: &str) -> Result<Vec<Ebook>> {
+        let ebooks = sqlx::query_as::<_, Ebook>(
+            "SELECT * FROM ebooks WHERE author_id = ? ORDER BY title COLLATE NOCASE"
+        )
+        .bind(author_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch author ebooks")?;
+
+        Ok(ebooks)
+    }
+
+    /// Books whose author text has not been linked to an author entity yet
+    pub async fn find_unlinked(&self) -> Result<Vec<(String, String, String)>> {
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            r#"
+            SELECT 'audiobook', id, author FROM audiobooks
+            WHERE author_id IS NULL AND author IS NOT NULL AND TRIM(author) != ''
+            UNION ALL
+            SELECT 'ebook', id, author FROM ebooks
+            WHERE author_id IS NULL AND author IS NOT NULL AND TRIM(author) != ''
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch unlinked books")?;
+
+        Ok(rows)
+    }
+}
+
+pub struct NarratorRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> NarratorRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, name: &str, normalized_name: &str, sort_name: &str) -> Result<Narrator> {
+        let narrator = Narrator::new(name.to_string(), normalized_name.to_string(), sort_name.to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO narrators (id, name, normalized_name, sort_name, is_favorite, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&narrator.id)
+        .bind(&narrator.name)
+        .bind(&narrator.normalized_name)
+        .bind(&narrator.sort_name)
+        .bind(narrator.is_favorite)
+        .bind(&narrator.created_at)
+        .bind(&narrator.updated_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to create narrator")?;
+
+        Ok(narrator)
+    }
+
+    pub async fn find_by_normalized_name(&self, normalized_name: &str) -> Result<Option<Narrator>> {
+        let narrator = sqlx::query_as::<_, Narrator>("SELECT * FROM narrators WHERE normalized_name = ?")
+            .bind(normalized_name)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to find narrator by name")?;
+
+        Ok(narrator)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Narrator>> {
+        let narrators = sqlx::query_as::<_, Narrator>(
+            "SELECT * FROM narrators ORDER BY is_favorite DESC, sort_name COLLATE NOCASE"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch narrators")?;
+
+        Ok(narrators)
+    }
+
+    pub async fn set_favorite(&self, id: &str, is_favorite: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE narrators SET is_favorite = ?, updated_at = ? WHERE id = ?")
+            .bind(is_favorite)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update favorite narrator")?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Narrator not found: {}", id));
+        }
+        Ok(())
+    }
+
+    pub async fn link_audiobook(&self, audiobook_id: &str, narrator_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE audiobooks SET narrator_id = ? WHERE id = ?")
+            .bind(narrator_id)
+            .bind(audiobook_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to link audiobook narrator")?;
+
+        Ok(())
+    }
+
+    pub async fn find_audiobooks(&self, narrator_id: &str) -> Result<Vec<Audiobook>> {
+        let audiobooks = sqlx::query_as::<_, Audiobook>(
+            "SELECT * FROM audiobooks WHERE narrator_id = ? ORDER BY title COLLATE NOCASE"
+        )
+        .bind(narrator_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch narrator audiobooks")?;
+
+        Ok(content_filter::apply(audiobooks))
+    }
+
+    /// Audiobooks with narrator text that has not been linked to a narrator entity yet
+    pub async fn find_unlinked(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT id, narrator FROM audiobooks
+            WHERE narrator_id IS NULL AND narrator IS NOT NULL AND TRIM(narrator) != ''
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch unlinked audiobooks")?;
+
+        Ok(rows)
+    }
+}
+
+/// Changes before this sequence number have been pruned from the journal
+pub const PREF_CHANGE_LOG_PRUNED_THROUGH: &str = "change_log.pruned_through";
+pub const MAX_CHANGES_PER_PAGE: u32 = 5000;
+
+pub struct ChangeLogRepository<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChangeLogRepository<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Changes after `cursor` in journal order, at most `limit` of them. Take
+    /// the cursor before reading library state so nothing falls in between.
+    pub async fn changes_since(&self, cursor: i64, limit: u32) -> Result<ChangeSet> {
+        let pruned_through = PreferencesRepository::new(self.pool).get_i64(PREF_CHANGE_LOG_PRUNED_THROUGH, 0).await?;
+        if cursor < pruned_through {
+            return Ok(ChangeSet {
+                changes: Vec::new(),
+                cursor: self.latest_seq().await?,
+                has_more: false,
+                reset_required: true,
+            });
+        }
+
+        let limit = limit.clamp(1, MAX_CHANGES_PER_PAGE);
+        let mut changes = sqlx::query_as::<_, ChangeLogEntry>(
+            "SELECT seq, entity_type, entity_id, operation, changed_at FROM change_log WHERE seq > ? ORDER BY seq LIMIT ?"
+        )
+        .bind(cursor)
+        .bind(limit as i64 + 1)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to fetch changes")?;
+
+        let has_more = changes.len() > limit as usize;
+        changes.truncate(limit as usize);
+        Ok(ChangeSet {
+            cursor: changes.last().map(|change| change.seq).unwrap_or(cursor),
+            changes,
+            has_more,
+            reset_required: false,
+        })
+    }
+
+    /// The newest sequence number handed out, 0 for an empty journal
+    pub async fn latest_seq(&self) -> Result<i64> {
+        let seq = sqlx::query_scalar::<_, i64>("SELECT seq FROM sqlite_sequence WHERE name = 'change_log'")
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to read change journal position")?;
+        Ok(seq.unwrap_or(0))
+    }
+
+    /// Delete changes recorded before `cutoff` (the journal's own timestamp
+    /// format); readers behind them have to start over
+    pub async fn prune_before(&self, cutoff: &str) -> Result<u64> {
+        let pruned_through = sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(seq) FROM change_log WHERE changed_at < ?")
+            .bind(cutoff)
+            .fetch_one(self.pool)
+            .await
+            .context("Failed to find old changes")?;
+        let Some(pruned_through) = pruned_through else {
+            return Ok(0);
+        };
+
+        let deleted = sqlx::query("DELETE FROM change_log WHERE seq <= ?")
+            .bind(pruned_through)
+            .execute(self.pool)
+            .await
+            .context("Failed to prune change journal")?
+            .rows_affected();
+        PreferencesRepository::new(self.pool)
+            .set(PREF_CHANGE_LOG_PRUNED_THROUGH, &pruned_through.to_string())
+            .await?;
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+    use std::time::Instant;
+
+    /// Run with `cargo test --release bench_ -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn bench_collections_with_counts() {
+        const COLLECTIONS: usize = 200;
+        const BOOKS_PER_COLLECTION: usize = 10;
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("bench.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let audiobooks = AudiobookRepository::new(pool);
+        let collections = CollectionRepository::new(pool);
+        for c in 0..COLLECTIONS {
+            let collection = collections.create(CreateCollectionDto {
+                name: format!("Collection {}", c),
+                description: None,
+                color: None,
+                parent_collection_id: None,
+            }).await.unwrap();
+            for b in 0..BOOKS_PER_COLLECTION {
+                let book = audiobooks.create(CreateAudiobookDto {
+                    title: format!("Book {}-{}", c, b),
+                    file_path: format!("/books/{}/{}", c, b),
+                    author: None,
+                    narrator: None,
+                    description: None,
+                    genre: None,
+                    duration: Some(60),
+                    cover_image_path: None,
+                    source_type: None,
+                    source_id: None,
+                }).await.unwrap();
+                collections.add_audiobook_to_collection(&collection.id, &book.id).await.unwrap();
+            }
+        }
+
+        // The previous approach: list the collections, then count each one
+        let started = Instant::now();
+        let mut per_row = Vec::new();
+        for collection in collections.find_all().await.unwrap() {
+            let stats = collections.get_stats(&collection.id).await.unwrap();
+            per_row.push((collection.id, stats.audiobook_count));
+        }
+        let per_row_time = started.elapsed();
+
+        let started = Instant::now();
+        let joined = collections.find_all_with_counts().await.unwrap();
+        let join_time = started.elapsed();
+
+        println!(
+            "{} collections / {} books: per-collection counts {:?}, join {:?}",
+            COLLECTIONS, COLLECTIONS * BOOKS_PER_COLLECTION, per_row_time, join_time
+        );
+        let mut joined: Vec<_> = joined.into_iter().map(|c| (c.collection.id, c.audiobook_count)).collect();
+        joined.sort();
+        per_row.sort();
+        assert_eq!(joined, per_row);
+        assert!(join_time < per_row_time);
+    }
+
+    #[tokio::test]
+    async fn test_change_log_records_and_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("changes.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let journal = ChangeLogRepository::new(pool);
+        assert_eq!(journal.latest_seq().await.unwrap(), 0);
+
+        let book = AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: "Dune".to_string(),
+            file_path: "/books/dune.m4b".to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: Some(60),
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let collections = CollectionRepository::new(pool);
+        let collection = collections.create(CreateCollectionDto {
+            name: "Sci-fi".to_string(),
+            description: None,
+            color: None,
+            parent_collection_id: None,
+        }).await.unwrap();
+        collections.add_audiobook_to_collection(&collection.id, &book.id).await.unwrap();
+        for title in ["Dune Messiah", "Children of Dune"] {
+            sqlx::query("UPDATE audiobooks SET title = ? WHERE id = ?").bind(title).bind(&book.id).execute(pool).await.unwrap();
+        }
+
+        let all = journal.changes_since(0, 100).await.unwrap();
+        let summary: Vec<(&str, &str)> = all.changes.iter().map(|c| (c.entity_type.as_str(), c.operation.as_str())).collect();
+        // Repeated updates collapse into the latest one
+        assert_eq!(summary, vec![("audiobook", "create"), ("collection", "create"), ("collection", "update"), ("audiobook", "update")]);
+        assert!(!all.has_more && !all.reset_required);
+        assert_eq!(all.cursor, journal.latest_seq().await.unwrap());
+
+        let first = journal.changes_since(0, 1).await.unwrap();
+        assert!(first.has_more);
+        let rest = journal.changes_since(first.cursor, 100).await.unwrap();
+        assert_eq!(rest.changes.len(), 3);
+
+        collections.delete(&collection.id).await.unwrap();
+        let deleted = journal.changes_since(all.cursor, 100).await.unwrap();
+        let summary: Vec<(&str, &str)> = deleted.changes.iter().map(|c| (c.entity_type.as_str(), c.operation.as_str())).collect();
+        assert_eq!(summary, vec![("collection", "delete")]);
+
+        assert_eq!(journal.prune_before("9999").await.unwrap(), 4);
+        assert!(journal.changes_since(0, 100).await.unwrap().reset_required);
+        let caught_up = journal.changes_since(deleted.cursor, 100).await.unwrap();
+        assert!(!caught_up.reset_required && caught_up.changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chapter_writes_keep_book_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("chapters.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let audiobooks = AudiobookRepository::new(pool);
+        let book = audiobooks.create(CreateAudiobookDto {
+            title: "Emma".to_string(),
+            file_path: "/books/emma".to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: Some(900),
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let chapter = |chapter_number: i32, duration: Option<i64>| CreateChapterDto {
+            audiobook_id: book.id.clone(),
+            chapter_number,
+            title: format!("Chapter {}", chapter_number),
+            file_path: format!("/books/emma/{:02}.mp3", chapter_number),
+            duration,
+            file_size: None,
+        };
+        let totals = || async {
+            let book = audiobooks.find_by_id(&book.id).await.unwrap().unwrap();
+            (book.chapters_count, book.duration)
+        };
+
+        let chapters = ChapterRepository::new(pool);
+        let created = chapters.create_multiple(vec![chapter(1, Some(300)), chapter(2, None), chapter(3, Some(200))]).await.unwrap();
+        assert_eq!(totals().await, (3, Some(500)));
+
+        chapters.update_chapter(&created[1].id, chapter(2, Some(250))).await.unwrap();
+        assert_eq!(totals().await, (3, Some(750)));
+
+        // Writes outside the repository are counted too
+        sqlx::query("DELETE FROM chapters WHERE id = ?").bind(&created[0].id).execute(pool).await.unwrap();
+        assert_eq!(totals().await, (2, Some(450)));
+
+        chapters.delete_by_audiobook_id(&book.id).await.unwrap();
+        assert_eq!(totals().await.0, 0);
+        audiobooks.delete(&book.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chapters_without_durations_keep_book_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("chapters.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let audiobooks = AudiobookRepository::new(pool);
+        let book = audiobooks.create(CreateAudiobookDto {
+            title: "Persuasion".to_string(),
+            file_path: "/books/persuasion".to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: Some(900),
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let chapter = |chapter_number: i32, duration: Option<i64>| CreateChapterDto {
+            audiobook_id: book.id.clone(),
+            chapter_number,
+            title: format!("Chapter {}", chapter_number),
+            file_path: format!("/books/persuasion/{:02}.mp3", chapter_number),
+            duration,
+            file_size: None,
+        };
+        let totals = || async {
+            let book = audiobooks.find_by_id(&book.id).await.unwrap().unwrap();
+            (book.chapters_count, book.duration)
+        };
+
+        let chapters = ChapterRepository::new(pool);
+        let created = chapters.create_multiple(vec![chapter(1, None), chapter(2, None)]).await.unwrap();
+        assert_eq!(totals().await, (2, Some(900)));
+
+        sqlx::query("DELETE FROM chapters WHERE id = ?").bind(&created[0].id).execute(pool).await.unwrap();
+        assert_eq!(totals().await, (1, Some(900)));
+
+        chapters.update_chapter(&created[1].id, chapter(2, Some(400))).await.unwrap();
+        assert_eq!(totals().await, (1, Some(400)));
+        audiobooks.delete(&book.id).await.unwrap();
+    }
+}
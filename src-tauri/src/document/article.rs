@@ -0,0 +1,325 @@
+// Web article import. Finds the main text of a page the way reader views do:
+// drop page furniture (scripts, navigation, sidebars), prefer the <article>
+// element, and keep paragraphs that read like prose rather than link lists.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+/// Pages with less text than this are not articles (paywalls, index pages)
+const MIN_ARTICLE_WORDS: usize = 80;
+/// Paragraphs that are mostly link text are menus or "related" lists
+const MAX_LINK_RATIO: f32 = 0.5;
+const MAX_FILE_STEM_CHARS: usize = 100;
+/// Elements that never hold article text
+const FURNITURE_TAGS: [&str; 10] = [
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "figure", "template",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Article {
+    pub url: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub site_name: Option<String>,
+    /// Paragraphs separated by blank lines
+    pub text: String,
+    pub word_count: usize,
+}
+
+pub async fn fetch_article(url: &str) -> Result<Article> {
+    let parsed = Url::parse(url).context("Invalid article URL")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(anyhow::anyhow!("Only http and https pages can be imported"));
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent("Mozilla/5.0 (compatible; AudioVibe article import)")
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .context("Failed to create HTTP client")?;
+    let response = client
+        .get(parsed.clone())
+        .send()
+        .await
+        .context("Failed to fetch article")?
+        .error_for_status()
+        .context("The site returned an error")?;
+
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|content_type| content_type.contains("html"));
+    if !is_html {
+        return Err(anyhow::anyhow!("The URL does not point to a web page"));
+    }
+
+    // Redirects may have moved us; the final address is the one worth keeping
+    let final_url = response.url().clone();
+    let html = response.text().await.context("Failed to read article")?;
+    extract_article(&html, &final_url)
+}
+
+pub fn extract_article(html: &str, url: &Url) -> Result<Article> {
+    let meta = meta_tags(html);
+    let body = strip_furniture(html);
+    let container = largest_article_element(&body).unwrap_or(&body);
+
+    let paragraphs = paragraphs(container);
+    let text = paragraphs.join("\n\n");
+    let word_count = text.split_whitespace().count();
+    if word_count < MIN_ARTICLE_WORDS {
+        return Err(anyhow::anyhow!("Could not find article text on this page"));
+    }
+
+    let title = ["og:title", "twitter:title"]
+        .iter()
+        .find_map(|key| meta.get(*key).cloned())
+        .or_else(|| first_element_text(html, "title"))
+        .or_else(|| first_element_text(&body, "h1"))
+        .unwrap_or_else(|| url.host_str().unwrap_or("Article").to_string());
+    let author = ["author", "article:author", "twitter:creator"]
+        .iter()
+        .filter_map(|key| meta.get(*key))
+        // article:author is often a profile URL rather than a name
+        .find(|value| !value.starts_with("http"))
+        .cloned();
+    let site_name = meta
+        .get("og:site_name")
+        .cloned()
+        .or_else(|| url.host_str().map(|host| host.trim_start_matches("www.").to_string()));
+
+    Ok(Article {
+        url: url.to_string(),
+        title,
+        author,
+        site_name,
+        text,
+        word_count,
+    })
+}
+
+/// Save the article text as a .txt file named after its title, so it goes
+/// through the same processing as any other text document
+pub fn save_article(article: &Article, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).context("Failed to create articles folder")?;
+
+    let stem: String = article
+        .title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '\'' | ',') { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_FILE_STEM_CHARS)
+        .collect();
+    let stem = if stem.is_empty() { "Article".to_string() } else { stem };
+
+    let mut path = dir.join(format!("{}.txt", stem));
+    let mut copy = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).txt", stem, copy));
+        copy += 1;
+    }
+    std::fs::write(&path, &article.text).context("Failed to save article")?;
+    Ok(path)
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+/// `<meta>` values keyed by their name or property, lowercased
+fn meta_tags(html: &str) -> HashMap<String, String> {
+    static META: OnceLock<Regex> = OnceLock::new();
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let meta_regex = regex(&META, r"(?is)<meta\b[^>]*>");
+    let attribute_regex = regex(&ATTRIBUTE, r#"(?is)([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#);
+
+    let mut tags = HashMap::new();
+    for tag in meta_regex.find_iter(html) {
+        let attributes: HashMap<String, String> = attribute_regex
+            .captures_iter(tag.as_str())
+            .map(|captures| {
+                let value = captures.get(2).or_else(|| captures.get(3)).map_or("", |m| m.as_str());
+                (captures[1].to_lowercase(), value.to_string())
+            })
+            .collect();
+        let key = attributes.get("property").or_else(|| attributes.get("name"));
+        if let (Some(key), Some(content)) = (key, attributes.get("content")) {
+            let content = clean_inline(content);
+            if !content.is_empty() {
+                tags.entry(key.to_lowercase()).or_insert(content);
+            }
+        }
+    }
+    tags
+}
+
+fn strip_furniture(html: &str) -> String {
+    static COMMENTS: OnceLock<Regex> = OnceLock::new();
+    static TAGS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let comments = regex(&COMMENTS, r"(?s)<!--.*?-->");
+    let tags = TAGS.get_or_init(|| {
+        FURNITURE_TAGS
+            .iter()
+            .map(|tag| Regex::new(&format!(r"(?is)<{0}\b.*?</{0}\s*>", tag)).unwrap())
+            .collect()
+    });
+
+    let mut html = comments.replace_all(html, "").into_owned();
+    for tag in tags {
+        html = tag.replace_all(&html, " ").into_owned();
+    }
+    html
+}
+
+/// Sites that mark up their story with <article> put everything else outside it
+fn largest_article_element(html: &str) -> Option<&str> {
+    static ARTICLE: OnceLock<Regex> = OnceLock::new();
+    regex(&ARTICLE, r"(?is)<article\b[^>]*>(.*?)</article\s*>")
+        .captures_iter(html)
+        .filter_map(|captures| captures.get(1))
+        .max_by_key(|inner| inner.as_str().len())
+        .map(|inner| inner.as_str())
+}
+
+/// Prose blocks in document order; headings are kept so sections stay separated
+fn paragraphs(html: &str) -> Vec<String> {
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    let block_regex = regex(
+        &BLOCK,
+        r"(?is)<(p|h[1-6]|li|blockquote)\b[^>]*>(.*?)</(?:p|h[1-6]|li|blockquote)\s*>",
+    );
+    let link_regex = regex(&LINK, r"(?is)<a\b[^>]*>(.*?)</a\s*>");
+
+    let mut paragraphs = Vec::new();
+    for captures in block_regex.captures_iter(html) {
+        let tag = captures[1].to_lowercase();
+        let inner = &captures[2];
+        let text = clean_inline(inner);
+        if text.is_empty() {
+            continue;
+        }
+
+        let link_chars: usize = link_regex
+            .captures_iter(inner)
+            .map(|link| clean_inline(&link[1]).chars().count())
+            .sum();
+        if link_chars as f32 > text.chars().count() as f32 * MAX_LINK_RATIO {
+            continue;
+        }
+        // List items and quotes count only when they read as sentences
+        let is_heading = tag.starts_with('h');
+        if !is_heading && tag != "p" && text.split_whitespace().count() < 6 {
+            continue;
+        }
+        paragraphs.push(text);
+    }
+    paragraphs
+}
+
+fn first_element_text(html: &str, tag: &str) -> Option<String> {
+    let element = Regex::new(&format!(r"(?is)<{0}\b[^>]*>(.*?)</{0}\s*>", tag)).ok()?;
+    element
+        .captures(html)
+        .map(|captures| clean_inline(&captures[1]))
+        .filter(|text| !text.is_empty())
+}
+
+/// Remove tags, decode entities and collapse whitespace
+pub(crate) fn clean_inline(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let without_tags = regex(&TAG, r"(?s)<[^>]*>").replace_all(html, " ");
+    decode_entities(&without_tags).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    regex(&ENTITY, r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);")
+        .replace_all(text, |captures: &regex::Captures| {
+            let entity = &captures[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "lsquo" => Some('‘'),
+                "rsquo" => Some('’'),
+                "ldquo" => Some('“'),
+                "rdquo" => Some('”'),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            decoded.map_or_else(|| captures[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_article_text_and_metadata() {
+        let paragraph = "The lighthouse keeper climbed the stairs every evening, counting each step as the light faded over the water.";
+        let html = format!(
+            r#"<html><head>
+                <title>Keeper | The Coast Times</title>
+                <meta property="og:title" content="The Last Keeper &amp; His Light">
+                <meta content="Ada Lovelace" name="author">
+                <meta property="og:site_name" content='The Coast Times'>
+                <script>var ads = "<p>not text</p>";</script>
+            </head><body>
+                <nav><p>Home News Sport Weather Culture Travel</p></nav>
+                <p>Subscribe to our newsletter for more stories like this one today.</p>
+                <article>
+                    <h2>Evening</h2>
+                    <p>{0}</p><p>{0}</p><p>{0}</p><p>{0}</p><p>{0}</p>
+                    <p><a href="/a">Read more</a> <a href="/b">about lighthouses</a></p>
+                    <li>Short item</li>
+                    <p>It was &#8220;quiet&#x201D; &mdash; and cold.</p>
+                </article>
+                <footer><p>Copyright</p></footer>
+            </body></html>"#,
+            paragraph
+        );
+
+        let url = Url::parse("https://www.coasttimes.example/keeper").unwrap();
+        let article = extract_article(&html, &url).unwrap();
+        assert_eq!(article.title, "The Last Keeper & His Light");
+        assert_eq!(article.author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(article.site_name.as_deref(), Some("The Coast Times"));
+
+        let paragraphs: Vec<&str> = article.text.split("\n\n").collect();
+        assert_eq!(paragraphs.len(), 7);
+        assert_eq!(paragraphs[0], "Evening");
+        assert_eq!(paragraphs[6], "It was “quiet” — and cold.");
+        assert!(!article.text.contains("newsletter") && !article.text.contains("Read more"));
+    }
+
+    #[test]
+    fn test_pages_without_prose_are_rejected() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let html = "<html><title>Index</title><body><ul><li><a href='/1'>One</a></li></ul></body></html>";
+        assert!(extract_article(html, &url).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+// How a document is divided into TTS chapters. Lengths are set in listening
+// minutes and converted to words at a typical narration pace.
+
+use super::DocumentChapter;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Typical narration pace of the TTS voices
+pub const WORDS_PER_MINUTE: f32 = 150.0;
+pub const MIN_TARGET_MINUTES: f32 = 1.0;
+pub const MAX_TARGET_MINUTES: f32 = 180.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct ChunkingOptions {
+    /// Listening length each chunk aims for
+    pub target_minutes: f32,
+    /// End chunks at a sentence boundary instead of exactly at the word count
+    pub split_on_sentences: bool,
+    /// Keep the document's own chapters, only splitting those that run long
+    pub respect_headings: bool,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            // About 2000 words, the previous fixed section size
+            target_minutes: 13.0,
+            split_on_sentences: true,
+            respect_headings: true,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    pub fn validate(&self) -> Result<()> {
+        if !(MIN_TARGET_MINUTES..=MAX_TARGET_MINUTES).contains(&self.target_minutes) {
+            return Err(anyhow::anyhow!(
+                "Chunk length must be between {} and {} minutes",
+                MIN_TARGET_MINUTES, MAX_TARGET_MINUTES
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn target_words(&self) -> usize {
+        (self.target_minutes * WORDS_PER_MINUTE).round().max(1.0) as usize
+    }
+}
+
+/// One chapter of a document's layout, without its text
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DocumentSection {
+    pub title: String,
+    pub word_count: usize,
+    /// Estimated listening time at normal speed
+    pub minutes: f32,
+}
+
+pub fn sections(chapters: &[DocumentChapter]) -> Vec<DocumentSection> {
+    chapters
+        .iter()
+        .map(|chapter| DocumentSection {
+            title: chapter.title.clone(),
+            word_count: chapter.word_count,
+            minutes: ((chapter.word_count as f32 / WORDS_PER_MINUTE) * 10.0).round() / 10.0,
+        })
+        .collect()
+}
+
+/// Lay out the chapters found in a document. Without headings to respect (or
+/// with none found) the whole text is cut into evenly sized sections.
+pub fn arrange(detected: Vec<DocumentChapter>, title: &str, options: &ChunkingOptions) -> Vec<DocumentChapter> {
+    if !options.respect_headings || detected.len() <= 1 {
+        let full_text = detected
+            .iter()
+            .map(|chapter| chapter.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let sections = split_into_chunks(&full_text, options);
+        if sections.len() <= 1 {
+            return vec![chapter(title.to_string(), full_text.trim().to_string())];
+        }
+        return sections
+            .into_iter()
+            .enumerate()
+            .map(|(index, text)| chapter(format!("{} - Section {}", title, index + 1), text))
+            .collect();
+    }
+
+    let mut chapters = Vec::new();
+    for detected in detected {
+        let parts = split_into_chunks(&detected.text, options);
+        if parts.len() <= 1 {
+            chapters.push(detected);
+            continue;
+        }
+        for (index, text) in parts.into_iter().enumerate() {
+            chapters.push(chapter(format!("{} (Part {})", detected.title, index + 1), text));
+        }
+    }
+    chapters
+}
+
+/// Cut text into pieces of about the target length; the last piece may be short
+pub fn split_into_chunks(text: &str, options: &ChunkingOptions) -> Vec<String> {
+    let target = options.target_words();
+    if !options.split_on_sentences {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        return words.chunks(target).map(|chunk| chunk.join(" ")).collect();
+    }
+
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for sentence in text.split_inclusive(['.', '!', '?']) {
+        let words: Vec<&str> = sentence.split_whitespace().collect();
+        if !current.is_empty() && current.len() + words.len() > target {
+            chunks.push(current.join(" "));
+            current.clear();
+        }
+        current.extend(words);
+    }
+    if !current.is_empty() {
+        chunks.push(current.join(" "));
+    }
+    chunks
+}
+
+fn chapter(title: String, text: String) -> DocumentChapter {
+    DocumentChapter {
+        title,
+        word_count: text.split_whitespace().count(),
+        text,
+        page_start: None,
+        page_end: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(target_minutes: f32, split_on_sentences: bool, respect_headings: bool) -> ChunkingOptions {
+        ChunkingOptions { target_minutes, split_on_sentences, respect_headings }
+    }
+
+    #[test]
+    fn test_chunks_end_on_sentences_when_asked() {
+        // 150 words per chunk; sentences of 100 words each
+        let sentence = format!("{}.", vec!["word"; 100].join(" "));
+        let text = [sentence.as_str(); 3].join(" ");
+
+        let by_sentence = split_into_chunks(&text, &options(1.0, true, true));
+        assert_eq!(by_sentence.len(), 3);
+        assert!(by_sentence.iter().all(|chunk| chunk.ends_with('.')));
+
+        let by_words = split_into_chunks(&text, &options(1.0, false, true));
+        assert_eq!(by_words.len(), 2);
+        assert_eq!(by_words[0].split_whitespace().count(), 150);
+    }
+
+    #[test]
+    fn test_arrange_respects_or_ignores_headings() {
+        let long = vec!["word."; 200].join(" ");
+        let detected = vec![chapter("One".into(), "Short chapter.".into()), chapter("Two".into(), long)];
+
+        let kept = arrange(detected.clone(), "Book", &options(1.0, true, true));
+        let titles: Vec<&str> = kept.iter().map(|chapter| chapter.title.as_str()).collect();
+        assert_eq!(titles, vec!["One", "Two (Part 1)", "Two (Part 2)"]);
+
+        let flat = arrange(detected, "Book", &options(1.0, true, false));
+        assert_eq!(flat[0].title, "Book - Section 1");
+        assert_eq!(flat.iter().map(|chapter| chapter.word_count).sum::<usize>(), 202);
+
+        assert!(options(0.5, true, true).validate().is_err());
+    }
+}
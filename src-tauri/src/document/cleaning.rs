@@ -0,0 +1,164 @@
+// Cleans extracted document text before it is read aloud. PDF and OCR output
+// carries layout artifacts that a TTS voice would otherwise speak: words split
+// across lines, running headers and footers, page numbers and footnote marks.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+/// A line must repeat at least this often to be treated as a running header or footer
+const MIN_HEADER_REPEATS: usize = 3;
+const MAX_HEADER_LENGTH: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct TextCleaningOptions {
+    /// Rejoin words hyphenated across a line break
+    pub dehyphenate: bool,
+    /// Drop short lines that repeat throughout the text, like running titles
+    pub strip_headers_footers: bool,
+    pub remove_page_numbers: bool,
+    /// Drop reference marks and the footnote text they point to
+    pub remove_footnotes: bool,
+}
+
+impl Default for TextCleaningOptions {
+    fn default() -> Self {
+        Self {
+            dehyphenate: true,
+            strip_headers_footers: true,
+            remove_page_numbers: true,
+            remove_footnotes: true,
+        }
+    }
+}
+
+pub fn clean_text(text: &str, options: &TextCleaningOptions) -> String {
+    let text = text.replace(['\r', '\u{00AD}'], "");
+    let headers = if options.strip_headers_footers { repeated_lines(&text) } else { Vec::new() };
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if options.remove_page_numbers && is_page_number(trimmed) {
+            continue;
+        }
+        if !headers.is_empty() && headers.contains(&normalize_line(trimmed)) {
+            continue;
+        }
+        if options.remove_footnotes && is_footnote_body(trimmed) {
+            continue;
+        }
+        lines.push(line.trim_end());
+    }
+    let mut text = lines.join("\n");
+
+    if options.dehyphenate {
+        static HYPHENATED: OnceLock<Regex> = OnceLock::new();
+        let hyphenated = HYPHENATED.get_or_init(|| Regex::new(r"(\p{L})-[ \t]*\n\s*(\p{Ll})").unwrap());
+        text = hyphenated.replace_all(&text, "$1$2").into_owned();
+    }
+    if options.remove_footnotes {
+        static MARKERS: OnceLock<Regex> = OnceLock::new();
+        static TRAILING_DIGITS: OnceLock<Regex> = OnceLock::new();
+        let markers = MARKERS.get_or_init(|| Regex::new(r"\[\d{1,3}\]|[¹²³⁰⁴⁵⁶⁷⁸⁹]+").unwrap());
+        // "the end.12 Next" -> "the end. Next"; digits glued to punctuation are reference marks
+        let trailing = TRAILING_DIGITS.get_or_init(|| Regex::new(r#"(\p{Ll}[.,;:!?"”’])\d{1,3}\b"#).unwrap());
+        text = markers.replace_all(&text, "").into_owned();
+        text = trailing.replace_all(&text, "$1").into_owned();
+    }
+
+    static BLANK_RUNS: OnceLock<Regex> = OnceLock::new();
+    let blank_runs = BLANK_RUNS.get_or_init(|| Regex::new(r"\n{3,}").unwrap());
+    blank_runs.replace_all(&text, "\n\n").trim().to_string()
+}
+
+/// "12", "- 12 -", "Page 12", "12 of 300" or a lowercase roman numeral
+fn is_page_number(line: &str) -> bool {
+    static PAGE_NUMBER: OnceLock<Regex> = OnceLock::new();
+    let page_number = PAGE_NUMBER.get_or_init(|| {
+        Regex::new(r"^(?:[Pp]age\s+)?[-–—]?\s*(?:\d{1,4}|[ivxlc]{1,7})(?:\s+of\s+\d{1,4})?\s*[-–—]?$").unwrap()
+    });
+    page_number.is_match(line)
+}
+
+fn is_footnote_body(line: &str) -> bool {
+    static FOOTNOTE: OnceLock<Regex> = OnceLock::new();
+    let footnote = FOOTNOTE.get_or_init(|| Regex::new(r"^(?:\[\d{1,3}\]|[¹²³⁰⁴⁵⁶⁷⁸⁹]+)\s*\S").unwrap());
+    footnote.is_match(line)
+}
+
+/// Page numbers inside a header change from page to page, so digits are ignored
+fn normalize_line(line: &str) -> String {
+    line.chars()
+        .filter(|c| !c.is_ascii_digit())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn repeated_lines(text: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for line in text.lines().map(str::trim) {
+        // Sentences that end properly are prose, even when a character repeats them
+        if line.is_empty() || line.len() > MAX_HEADER_LENGTH || line.ends_with(['.', '!', '?', '"', '”']) {
+            continue;
+        }
+        let normalized = normalize_line(line);
+        if normalized.chars().any(char::is_alphabetic) {
+            *counts.entry(normalized).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count >= MIN_HEADER_REPEATS)
+        .map(|(line, _)| line)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_text_removes_layout_artifacts() {
+        let text = "THE SEA WOLF 3\n\
+                    The schooner rolled in a heavy swell as the cap-\n\
+                    tain came on deck.[1] Nobody spoke.²\n\
+                    - 3 -\n\
+                    THE SEA WOLF 4\n\
+                    He looked at the sky and said nothing.12 Then he went below.\n\
+                    [1] The captain was Wolf Larsen.\n\
+                    4\n\
+                    THE SEA WOLF 5\n\
+                    Well-known sailors agree.";
+
+        let cleaned = clean_text(text, &TextCleaningOptions::default());
+        assert_eq!(
+            cleaned,
+            "The schooner rolled in a heavy swell as the captain came on deck. Nobody spoke.\n\
+             He looked at the sky and said nothing. Then he went below.\n\
+             Well-known sailors agree."
+        );
+    }
+
+    #[test]
+    fn test_disabled_steps_leave_text_alone() {
+        let options = TextCleaningOptions {
+            dehyphenate: false,
+            strip_headers_footers: false,
+            remove_page_numbers: false,
+            remove_footnotes: false,
+        };
+        let text = "cap-\ntain[1]\n12";
+        assert_eq!(clean_text(text, &options), text);
+        // Dialogue that repeats is not a running header
+        let dialogue = "\"No.\"\n\"No.\"\n\"No.\"";
+        assert_eq!(clean_text(dialogue, &TextCleaningOptions::default()), dialogue);
+    }
+}
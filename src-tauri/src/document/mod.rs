@@ -1,9 +1,19 @@
+pub mod article;
+pub mod chunking;
+pub mod cleaning;
+pub mod ocr;
+
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use anyhow::{Result, Context};
 use regex::Regex;
+use chunking::ChunkingOptions;
+use cleaning::TextCleaningOptions;
+use ocr::OcrProgress;
+use ts_rs::TS;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct DocumentChapter {
     pub title: String,
     pub text: String,
@@ -12,7 +22,8 @@ pub struct DocumentChapter {
     pub page_end: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ProcessedDocument {
     pub title: String,
     pub author: Option<String>,
@@ -20,16 +31,44 @@ pub struct ProcessedDocument {
     pub format: String,
     pub total_pages: Option<u32>,
     pub total_chapters: usize,
+    /// Stored document this was processed as, once saved
+    #[serde(default)]
+    pub document_id: Option<String>,
 }
 
-pub struct DocumentProcessor;
+pub struct DocumentProcessor {
+    ocr_language: String,
+    on_ocr_progress: Option<Box<dyn Fn(OcrProgress) + Send>>,
+}
 
 impl DocumentProcessor {
     pub fn new() -> Self {
-        DocumentProcessor
+        DocumentProcessor {
+            ocr_language: ocr::DEFAULT_LANGUAGE.to_string(),
+            on_ocr_progress: None,
+        }
+    }
+
+    /// Tesseract language code(s) for scanned PDFs, e.g. "eng" or "deu+eng"
+    pub fn with_ocr_language(mut self, language: &str) -> Self {
+        self.ocr_language = language.to_string();
+        self
     }
 
-    pub fn process_document<P: AsRef<Path>>(&self, file_path: P) -> Result<ProcessedDocument> {
+    /// Called after each page of a scanned PDF has been read
+    pub fn on_ocr_progress(mut self, callback: impl Fn(OcrProgress) + Send + 'static) -> Self {
+        self.on_ocr_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Extract, clean and divide a document into chapters. Text is cleaned before
+    /// it is divided, while line breaks still show where headers and page numbers are.
+    pub fn process_document<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        chunking: &ChunkingOptions,
+        cleaning: &TextCleaningOptions,
+    ) -> Result<ProcessedDocument> {
         let path = file_path.as_ref();
         let extension = path.extension()
             .and_then(|ext| ext.to_str())
@@ -37,14 +76,19 @@ impl DocumentProcessor {
             .ok_or_else(|| anyhow::anyhow!("File has no extension"))?;
 
         match extension.as_str() {
-            "pdf" => self.process_pdf(path),
-            "epub" => self.process_epub(path),
-            "txt" | "text" => self.process_text(path),
+            "pdf" => self.process_pdf(path, chunking, cleaning),
+            "epub" => self.process_epub(path, chunking, cleaning),
+            "txt" | "text" => self.process_text(path, chunking, cleaning),
             _ => Err(anyhow::anyhow!("Unsupported file format: {}", extension)),
         }
     }
 
-    fn process_pdf<P: AsRef<Path>>(&self, file_path: P) -> Result<ProcessedDocument> {
+    fn process_pdf<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        chunking: &ChunkingOptions,
+        cleaning: &TextCleaningOptions,
+    ) -> Result<ProcessedDocument> {
         use pdf_extract::extract_text;
         
         let path = file_path.as_ref();
@@ -53,18 +97,24 @@ impl DocumentProcessor {
             .unwrap_or("Unknown")
             .to_string();
 
-        let full_text = extract_text(path)
-            .context("Failed to extract text from PDF")?;
+        // Scanned PDFs have no text layer; read their page images instead
+        let full_text = match extract_text(path).context("Failed to extract text from PDF") {
+            Ok(text) if !ocr::needs_ocr(&text) => text,
+            Ok(_) => self.ocr_pdf(path)?,
+            Err(e) => self.ocr_pdf(path).map_err(|_| e)?,
+        };
+        let full_text = cleaning::clean_text(&full_text, cleaning);
 
         // Try to detect chapters
-        let chapters = self.detect_chapters(&full_text);
+        let chapters = if chunking.respect_headings { self.detect_chapters(&full_text) } else { Vec::new() };
         
         // If no chapters found, split by pages/sections
         let final_chapters = if chapters.len() <= 1 {
-            self.split_text_by_sections(&full_text, &title)
+            vec![self.whole_text_chapter(&full_text, &title)]
         } else {
             chapters
         };
+        let final_chapters = chunking::arrange(final_chapters, &title, chunking);
 
         let total_chapters = final_chapters.len();
         Ok(ProcessedDocument {
@@ -74,10 +124,16 @@ impl DocumentProcessor {
             format: "pdf".to_string(),
             total_pages: None, // PDF page count would require more complex parsing
             total_chapters,
+            document_id: None,
         })
     }
 
-    fn process_epub<P: AsRef<Path>>(&self, file_path: P) -> Result<ProcessedDocument> {
+    fn process_epub<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        chunking: &ChunkingOptions,
+        cleaning: &TextCleaningOptions,
+    ) -> Result<ProcessedDocument> {
         use epub::doc::EpubDoc;
         
         let path = file_path.as_ref();
@@ -154,6 +210,11 @@ impl DocumentProcessor {
             }];
         }
 
+        for chapter in &mut chapters {
+            chapter.text = cleaning::clean_text(&chapter.text, cleaning);
+            chapter.word_count = chapter.text.split_whitespace().count();
+        }
+        let chapters = chunking::arrange(chapters, &title, chunking);
         let total_chapters = chapters.len();
         Ok(ProcessedDocument {
             title,
@@ -162,10 +223,16 @@ impl DocumentProcessor {
             format: "epub".to_string(),
             total_pages: None,
             total_chapters,
+            document_id: None,
         })
     }
 
-    fn process_text<P: AsRef<Path>>(&self, file_path: P) -> Result<ProcessedDocument> {
+    fn process_text<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        chunking: &ChunkingOptions,
+        cleaning: &TextCleaningOptions,
+    ) -> Result<ProcessedDocument> {
         let path = file_path.as_ref();
         let title = path.file_stem()
             .and_then(|stem| stem.to_str())
@@ -174,8 +241,14 @@ impl DocumentProcessor {
 
         let content = std::fs::read_to_string(path)
             .context("Failed to read text file")?;
+        let content = cleaning::clean_text(&content, cleaning);
 
-        let chapters = self.detect_text_chapters(&content, &title);
+        let chapters = if chunking.respect_headings {
+            self.detect_text_chapters(&content, &title)
+        } else {
+            vec![self.whole_text_chapter(&content, &title)]
+        };
+        let chapters = chunking::arrange(chapters, &title, chunking);
 
         let total_chapters = chapters.len();
         Ok(ProcessedDocument {
@@ -185,6 +258,16 @@ impl DocumentProcessor {
             format: "txt".to_string(),
             total_pages: None,
             total_chapters,
+            document_id: None,
+        })
+    }
+
+    fn ocr_pdf(&self, path: &Path) -> Result<String> {
+        log::info!("DOCUMENT: No text layer in {}, running OCR", path.display());
+        ocr::ocr_pdf(path, &self.ocr_language, |progress| {
+            if let Some(callback) = &self.on_ocr_progress {
+                callback(progress);
+            }
         })
     }
 
@@ -291,36 +374,14 @@ impl DocumentProcessor {
         chapters
     }
 
-    fn split_text_by_sections(&self, text: &str, title: &str) -> Vec<DocumentChapter> {
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut chapters = Vec::new();
-        let words_per_section = 2000; // ~8-10 minutes of audio
-        
-        for (i, chunk) in words.chunks(words_per_section).enumerate() {
-            let section_text = chunk.join(" ");
-            
-            if !section_text.trim().is_empty() {
-                chapters.push(DocumentChapter {
-                    title: format!("{} - Section {}", title, i + 1),
-                    text: section_text,
-                    word_count: chunk.len(),
-                    page_start: None,
-                    page_end: None,
-                });
-            }
+    fn whole_text_chapter(&self, text: &str, title: &str) -> DocumentChapter {
+        DocumentChapter {
+            title: title.to_string(),
+            text: text.trim().to_string(),
+            word_count: text.split_whitespace().count(),
+            page_start: None,
+            page_end: None,
         }
-        
-        if chapters.is_empty() {
-            chapters.push(DocumentChapter {
-                title: title.to_string(),
-                text: text.to_string(),
-                word_count: text.split_whitespace().count(),
-                page_start: None,
-                page_end: None,
-            });
-        }
-
-        chapters
     }
 
     fn is_chapter_header(&self, line: &str) -> bool {
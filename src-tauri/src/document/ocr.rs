@@ -0,0 +1,131 @@
+// OCR for scanned PDFs whose pages are images, so text extraction finds
+// nothing. Pages are rasterized with pdftoppm (poppler) and read with the
+// tesseract CLI; both are optional and only needed for these documents.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use ts_rs::TS;
+
+pub const DEFAULT_LANGUAGE: &str = "eng";
+/// Extracted text with fewer letters or digits than this is treated as a scan
+const MIN_EXTRACTED_CHARS: usize = 100;
+/// Rasterizing resolution; tesseract is most accurate around 300 DPI
+const RENDER_DPI: u32 = 300;
+
+/// Payload of the `ocr-progress` event, sent after each page is read
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OcrProgress {
+    pub file_path: String,
+    pub page: u32,
+    pub total_pages: u32,
+}
+
+/// Whether extraction produced so little text that the PDF is probably scanned images
+pub fn needs_ocr(extracted: &str) -> bool {
+    extracted.chars().filter(|c| c.is_alphanumeric()).count() < MIN_EXTRACTED_CHARS
+}
+
+/// Fail early with a readable message when the OCR tools are not installed
+pub fn ensure_ocr_available() -> Result<()> {
+    for (program, flag, package) in [("tesseract", "--version", "tesseract"), ("pdftoppm", "-v", "poppler")] {
+        Command::new(program).arg(flag).output().with_context(|| {
+            format!(
+                "This PDF is scanned and needs OCR, but {} was not found. Install {} and make sure it is on your PATH",
+                program, package
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// OCR every page of the PDF, returning the pages' text separated by blank lines
+pub fn ocr_pdf(path: &Path, language: &str, mut on_progress: impl FnMut(OcrProgress)) -> Result<String> {
+    ensure_ocr_available()?;
+    let total_pages = page_count(path)?;
+    let work_dir = tempfile::tempdir().context("Failed to create OCR working directory")?;
+
+    let mut pages = Vec::with_capacity(total_pages as usize);
+    for page in 1..=total_pages {
+        let image = render_page(path, page, work_dir.path())?;
+        let output = Command::new("tesseract")
+            .arg(&image)
+            .arg("stdout")
+            .args(["-l", language])
+            .output()
+            .context("Failed to run tesseract")?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "tesseract failed on page {}: {}",
+                page,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        pages.push(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        let _ = std::fs::remove_file(&image);
+
+        on_progress(OcrProgress {
+            file_path: path.to_string_lossy().to_string(),
+            page,
+            total_pages,
+        });
+    }
+
+    Ok(pages.join("\n\n"))
+}
+
+/// Rendering one page at a time keeps only a single page image on disk
+fn render_page(path: &Path, page: u32, work_dir: &Path) -> Result<std::path::PathBuf> {
+    let prefix = work_dir.join(format!("page-{}", page));
+    let page = page.to_string();
+    let output = Command::new("pdftoppm")
+        .args(["-r", &RENDER_DPI.to_string(), "-gray", "-png", "-singlefile", "-f", &page, "-l", &page])
+        .arg(path)
+        .arg(&prefix)
+        .output()
+        .context("Failed to run pdftoppm")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "pdftoppm failed on page {}: {}",
+            page,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(prefix.with_extension("png"))
+}
+
+fn page_count(path: &Path) -> Result<u32> {
+    let output = Command::new("pdfinfo")
+        .arg(path)
+        .output()
+        .context("Failed to run pdfinfo")?;
+    parse_page_count(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow::anyhow!("Could not read the page count of {}", path.display()))
+}
+
+fn parse_page_count(pdfinfo: &str) -> Option<u32> {
+    pdfinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("Pages:"))
+        .and_then(|count| count.trim().parse().ok())
+        .filter(|count| *count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_detection_and_page_count() {
+        assert!(needs_ocr(""));
+        assert!(needs_ocr("\n\x0c\n  3 \n\x0c 4"));
+        assert!(!needs_ocr(&"Call me Ishmael. ".repeat(20)));
+
+        let info = "Title:          Moby Dick\nProducer:       scanner\nPages:          312\nEncrypted:      no\n";
+        assert_eq!(parse_page_count(info), Some(312));
+        assert_eq!(parse_page_count("Pages: 0"), None);
+        assert_eq!(parse_page_count("Syntax Error"), None);
+    }
+}
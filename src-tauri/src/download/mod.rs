@@ -1,6 +1,11 @@
+use crate::events::{self, AppEvent};
+use crate::filesystem::atomic;
+use crate::filesystem::long_path::long_path;
+use crate::storage::disk_space;
 use anyhow::{Context, Result};
 use futures_util::StreamExt;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
 use tokio::fs::File;
@@ -8,11 +13,34 @@ use tokio::io::AsyncWriteExt;
 use zip::ZipArchive;
 use std::fs;
 use std::io::BufReader;
+use std::sync::Arc;
+
+pub mod preview;
+pub mod safe_path;
+pub mod throttle;
+
+use safe_path::{contained_path, flat_file_name, sanitize_file_name, sanitize_relative_path, UniqueNames};
+pub use throttle::{DownloadThrottle, RateWindow, ThrottleSettings};
+
+/// Minimum time between download-progress events for one file
+const PROGRESS_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
 
 #[derive(Debug, Clone)]
 pub struct DownloadManager {
     client: Client,
     cache_dir: PathBuf,
+    throttle: Arc<DownloadThrottle>,
+}
+
+/// Written into an Archive.org download folder before its files are fetched, so
+/// a download cut short (e.g. by the app closing) can be recognized and resumed
+pub const ARCHIVE_MANIFEST_FILE: &str = ".archive_manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    /// Size in bytes reported by Archive.org, when it gave one
+    pub size: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,17 +67,25 @@ impl DownloadManager {
             .build()
             .context("Failed to create HTTP client")?;
             
-        Ok(Self { client, cache_dir })
+        Ok(Self {
+            client,
+            cache_dir,
+            throttle: Arc::new(DownloadThrottle::new()),
+        })
+    }
+
+    pub fn throttle(&self) -> &Arc<DownloadThrottle> {
+        &self.throttle
+    }
+
+    /// Folder LibriVox/Archive.org downloads are stored in, one subfolder per identifier
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
     }
     
     fn get_cache_directory() -> Result<PathBuf> {
-        // Use platform-appropriate cache directory
-        if let Some(cache_dir) = dirs::cache_dir() {
-            Ok(cache_dir.join("audiovibe").join("librivox"))
-        } else {
-            // Fallback to current directory + cache
-            Ok(std::env::current_dir()?.join("cache").join("librivox"))
-        }
+        // The system cache, or next to the executable in portable mode
+        Ok(crate::storage::paths().download_cache_dir())
     }
     
     pub async fn download_and_extract_zip(&self, url: &str) -> Result<DownloadResult> {
@@ -89,7 +125,22 @@ impl DownloadManager {
     }
     
     async fn download_file(&self, url: &str, output_path: &Path) -> Result<()> {
+        let file_name = output_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let result = self.fetch_file(url, output_path, &file_name).await;
+        events::emit(match &result {
+            Ok(()) => AppEvent::DownloadCompleted {
+                url: url.to_string(),
+                file_name,
+                file_path: output_path.to_string_lossy().to_string(),
+            },
+            Err(e) => AppEvent::DownloadFailed { url: url.to_string(), file_name, error: format!("{:#}", e) },
+        });
+        result
+    }
+
+    async fn fetch_file(&self, url: &str, output_path: &Path, file_name: &str) -> Result<()> {
         println!("DOWNLOAD: Fetching {}", url);
+        self.throttle.wait_while_paused().await;
         
         // Archive.org URLs need proper encoding
         let fixed_url = if url.contains("archive.org") && url.contains("formats=64KBPS MP3") {
@@ -131,18 +182,36 @@ impl DownloadManager {
         let total_size = response.content_length();
         if let Some(size) = total_size {
             println!("📊 DOWNLOAD: File size: {} MB", size / 1024 / 1024);
+            disk_space::ensure_space(output_path, size)?;
         }
         
-        let mut file = File::create(output_path).await
+        // Written under a temporary name so an interrupted download never looks finished
+        let partial_path = atomic::sibling(output_path, atomic::PARTIAL_SUFFIX);
+        let mut file = File::create(long_path(&partial_path)).await
             .context("Failed to create output file")?;
             
         let mut stream = response.bytes_stream();
         let mut downloaded = 0u64;
+        let mut rate_window = RateWindow::new();
+        let mut last_event: Option<std::time::Instant> = None;
         
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.context("Failed to read chunk")?;
             file.write_all(&chunk).await.context("Failed to write chunk")?;
             downloaded += chunk.len() as u64;
+
+            self.throttle.consume(chunk.len() as u64, &mut rate_window).await;
+            self.throttle.wait_while_paused().await;
+
+            if last_event.is_none_or(|at| at.elapsed() >= PROGRESS_EVENT_INTERVAL) {
+                last_event = Some(std::time::Instant::now());
+                events::emit(AppEvent::DownloadProgress {
+                    url: url.to_string(),
+                    file_name: file_name.to_string(),
+                    downloaded_bytes: downloaded,
+                    total_bytes: total_size,
+                });
+            }
             
             if let Some(total) = total_size {
                 let progress = (downloaded as f64 / total as f64) * 100.0;
@@ -154,6 +223,10 @@ impl DownloadManager {
         }
         
         file.flush().await.context("Failed to flush file")?;
+        drop(file);
+        atomic::commit_async(&long_path(&partial_path), &long_path(output_path)).await
+            .context("Failed to move downloaded file into place")?;
+        disk_space::warn_if_low(&self.cache_dir);
         println!("✅ DOWNLOAD: File saved to: {}", output_path.display());
         
         Ok(())
@@ -163,38 +236,46 @@ impl DownloadManager {
         println!("📦 EXTRACT: Extracting zip file: {}", zip_path.display());
         
         // Create extraction directory
-        if !extract_dir.exists() {
-            fs::create_dir_all(extract_dir)
+        if !long_path(extract_dir).exists() {
+            fs::create_dir_all(long_path(extract_dir))
                 .context("Failed to create extraction directory")?;
         }
         
         // Open and extract zip file
-        let file = fs::File::open(zip_path)
+        let file = fs::File::open(long_path(zip_path))
             .context("Failed to open zip file")?;
         let reader = BufReader::new(file);
         let mut archive = ZipArchive::new(reader)
             .context("Failed to read zip archive")?;
         
         let mut extracted_files = Vec::new();
+        let mut names = UniqueNames::new();
         
         for i in 0..archive.len() {
             let mut file = archive.by_index(i)
                 .context("Failed to get file from zip")?;
+
+            // Links could point anywhere once extracted
+            if file.is_symlink() {
+                println!("⚠️ EXTRACT: Skipping link {}", file.name());
+                continue;
+            }
                 
-            let file_path = match file.enclosed_name() {
-                Some(path) => path,
+            let file_path = match file.enclosed_name().and(sanitize_relative_path(file.name())) {
+                Some(path) if file.is_dir() => path,
+                Some(path) => names.claim(path),
                 None => {
-                    println!("⚠️ EXTRACT: Skipping invalid file name at index {}", i);
+                    println!("⚠️ EXTRACT: Skipping unsafe file name at index {}: {:?}", i, file.name());
                     continue;
                 }
             };
             
-            let output_path = extract_dir.join(&file_path);
+            let output_path = contained_path(extract_dir, &file_path)?;
             
             // Create parent directories if needed
             if let Some(parent) = output_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)
+                if !long_path(parent).exists() {
+                    fs::create_dir_all(long_path(parent))
                         .context("Failed to create parent directory")?;
                 }
             }
@@ -203,11 +284,14 @@ impl DownloadManager {
             if file.is_file() {
                 println!("📁 EXTRACT: Extracting: {}", file_path.display());
                 
-                let mut output_file = fs::File::create(&output_path)
+                let partial_path = atomic::sibling(&output_path, atomic::PARTIAL_SUFFIX);
+                let mut output_file = fs::File::create(long_path(&partial_path))
                     .context("Failed to create extracted file")?;
                     
                 std::io::copy(&mut file, &mut output_file)
                     .context("Failed to copy file contents")?;
+                drop(output_file);
+                atomic::commit(&long_path(&partial_path), &long_path(&output_path))?;
                 
                 // Only track audio files
                 if self.is_audio_file(&output_path) {
@@ -222,15 +306,114 @@ impl DownloadManager {
         Ok(extracted_files)
     }
     
+    /// Whether a local file is an archive format that can be imported
+    pub fn is_supported_archive(path: &Path) -> bool {
+        path.extension()
+            .map(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "zip" | "rar"))
+            .unwrap_or(false)
+    }
+
+    /// Extract a local .zip or .rar archive and return the audio files it contained
+    pub async fn extract_local_archive(&self, archive_path: &Path, extract_dir: &Path) -> Result<Vec<PathBuf>> {
+        let extension = archive_path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let mut extracted_files = match extension.as_str() {
+            "zip" => self.extract_zip(archive_path, extract_dir).await?,
+            "rar" => self.extract_rar(archive_path, extract_dir)?,
+            _ => return Err(anyhow::anyhow!("Unsupported archive format: {}", archive_path.display())),
+        };
+
+        extracted_files.sort();
+        Ok(extracted_files)
+    }
+
+    /// RAR is a proprietary format, so extraction goes through an installed
+    /// `unrar` or `7z` binary rather than a bundled decoder
+    fn extract_rar(&self, rar_path: &Path, extract_dir: &Path) -> Result<Vec<PathBuf>> {
+        println!("📦 EXTRACT: Extracting rar file: {}", rar_path.display());
+
+        if !long_path(extract_dir).exists() {
+            fs::create_dir_all(long_path(extract_dir))
+                .context("Failed to create extraction directory")?;
+        }
+
+        // The extractors get the long form too; listing below keeps the normal one
+        let (rar_path, dest_dir) = (long_path(rar_path), long_path(extract_dir));
+        let rar_path = rar_path.as_path();
+        let mut unrar_dest = dest_dir.as_os_str().to_os_string();
+        unrar_dest.push(std::path::MAIN_SEPARATOR_STR);
+        let mut seven_zip_dest = std::ffi::OsString::from("-o");
+        seven_zip_dest.push(dest_dir.as_os_str());
+
+        let arg = std::ffi::OsStr::new;
+        let attempts = [
+            ("unrar", vec![arg("x"), arg("-o+"), arg("-y"), rar_path.as_os_str(), unrar_dest.as_os_str()]),
+            ("7z", vec![arg("x"), arg("-y"), seven_zip_dest.as_os_str(), rar_path.as_os_str()]),
+        ];
+
+        let mut last_error = String::from("no extractor available");
+        let mut extracted = false;
+        for (program, args) in attempts.iter() {
+            match std::process::Command::new(program).args(args).output() {
+                Ok(output) if output.status.success() => {
+                    extracted = true;
+                    break;
+                }
+                Ok(output) => {
+                    last_error = format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim());
+                }
+                Err(e) => {
+                    last_error = format!("{} not available: {}", program, e);
+                }
+            }
+        }
+
+        if !extracted {
+            return Err(anyhow::anyhow!("Failed to extract rar archive ({}). Install unrar or 7-Zip to import .rar files", last_error));
+        }
+
+        let extracted_files = self.list_audio_files_recursive(extract_dir)?;
+        println!("✅ EXTRACT: Extracted {} audio files to: {}",
+            extracted_files.len(), extract_dir.display());
+
+        Ok(extracted_files)
+    }
+
+    fn list_audio_files_recursive(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut audio_files = Vec::new();
+
+        if long_path(dir).is_dir() {
+            for entry in fs::read_dir(long_path(dir))? {
+                let entry = entry?;
+                // Links left by the extractor could lead out of the folder
+                let file_type = entry.file_type()?;
+                if file_type.is_symlink() {
+                    continue;
+                }
+                let path = dir.join(entry.file_name());
+                if file_type.is_dir() {
+                    audio_files.extend(self.list_audio_files_recursive(&path)?);
+                } else if file_type.is_file() && self.is_audio_file(&path) {
+                    audio_files.push(path);
+                }
+            }
+        }
+
+        audio_files.sort();
+        Ok(audio_files)
+    }
+
     fn list_audio_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
         let mut audio_files = Vec::new();
         
-        if dir.is_dir() {
-            for entry in fs::read_dir(dir)? {
+        if long_path(dir).is_dir() {
+            for entry in fs::read_dir(long_path(dir))? {
                 let entry = entry?;
-                let path = entry.path();
+                let path = dir.join(entry.file_name());
                 
-                if path.is_file() && self.is_audio_file(&path) {
+                if self.is_audio_file(&path) && long_path(&path).is_file() {
                     audio_files.push(path);
                 }
             }
@@ -253,9 +436,9 @@ impl DownloadManager {
     
     fn generate_cache_filename(&self, url: &str) -> String {
         // Extract filename from URL or generate one based on hash
-        if let Some(filename) = url.split('/').last() {
+        if let Some(filename) = url.split('/').last().and_then(sanitize_file_name) {
             if filename.contains('.') {
-                return filename.to_string();
+                return filename;
             }
         }
         
@@ -268,16 +451,23 @@ impl DownloadManager {
         println!("📥 ARCHIVE.ORG: Starting individual file downloads for identifier: {}", identifier);
         
         // Create extraction directory based on identifier
-        let extract_dir = self.cache_dir.join(identifier);
+        let folder_name = sanitize_file_name(identifier)
+            .ok_or_else(|| anyhow::anyhow!("Invalid Archive.org identifier: {}", identifier))?;
+        let extract_dir = self.cache_dir.join(folder_name);
         
-        // Check if already cached and extracted
+        // Check if already cached and extracted; a folder with files still to
+        // fetch is an interrupted download and carries on below
         if extract_dir.exists() {
-            println!("💾 CACHE: Using cached files at: {}", extract_dir.display());
-            let extracted_files = self.list_audio_files(&extract_dir)?;
-            return Ok(DownloadResult {
-                local_path: extract_dir,
-                extracted_files,
-            });
+            let missing = missing_archive_files(&extract_dir);
+            if missing.is_empty() {
+                println!("💾 CACHE: Using cached files at: {}", extract_dir.display());
+                let extracted_files = self.list_audio_files(&extract_dir)?;
+                return Ok(DownloadResult {
+                    local_path: extract_dir,
+                    extracted_files,
+                });
+            }
+            println!("🔁 ARCHIVE.ORG: Resuming download of {} missing files", missing.len());
         }
         
         // Get file metadata from Archive.org
@@ -288,10 +478,33 @@ impl DownloadManager {
         }
         
         // Create extraction directory
-        if !extract_dir.exists() {
-            fs::create_dir_all(&extract_dir)
+        if !long_path(&extract_dir).exists() {
+            fs::create_dir_all(long_path(&extract_dir))
                 .context("Failed to create extraction directory")?;
         }
+
+        let manifest: Vec<ManifestEntry> = files.iter()
+            .filter_map(|file_info| {
+                let name = file_info.get("name")?.as_str()?;
+                self.is_audio_file_name(name).then(|| ManifestEntry {
+                    name: name.to_string(),
+                    size: file_info.get("size")
+                        .and_then(|size| size.as_str())
+                        .and_then(|size| size.parse().ok()),
+                })
+            })
+            .collect();
+        atomic::write(&extract_dir.join(ARCHIVE_MANIFEST_FILE), serde_json::to_string(&manifest)?)
+            .context("Failed to write download manifest")?;
+        let local_names = local_file_names(&manifest);
+
+        // Archive.org lists every file's size, so a book that cannot fit is refused before the first file
+        let remaining_bytes: u64 = manifest.iter()
+            .zip(&local_names)
+            .filter(|(entry, local_name)| local_name.as_ref().is_some_and(|name| !is_downloaded(&extract_dir, name, entry)))
+            .filter_map(|(entry, _)| entry.size)
+            .sum();
+        disk_space::ensure_space(&extract_dir, remaining_bytes)?;
         
         let mut extracted_files = Vec::new();
         
@@ -307,7 +520,18 @@ impl DownloadManager {
             }
             
             let file_url = format!("https://archive.org/download/{}/{}", identifier, filename);
-            let output_path = extract_dir.join(filename);
+            let Some(index) = manifest.iter().position(|entry| entry.name == filename) else {
+                continue;
+            };
+            let Some(local_name) = &local_names[index] else {
+                println!("⚠️ ARCHIVE.ORG: Skipping unsafe file name: {:?}", filename);
+                continue;
+            };
+            let output_path = contained_path(&extract_dir, Path::new(local_name))?;
+            if is_downloaded(&extract_dir, local_name, &manifest[index]) {
+                extracted_files.push(output_path);
+                continue;
+            }
             
             println!("📥 ARCHIVE.ORG: Downloading: {}", filename);
             
@@ -338,7 +562,108 @@ impl DownloadManager {
         })
     }
     
-    async fn get_archive_files_metadata(&self, identifier: &str) -> Result<Vec<Value>> {
+    /// Download an ordered list of direct audio URLs (non-Archive.org sources)
+    /// into a single folder, preserving the list order in the file names
+    pub async fn download_url_list(&self, folder_key: &str, urls: &[String]) -> Result<DownloadResult> {
+        println!("📥 URL LIST: Downloading {} files into {}", urls.len(), folder_key);
+
+        let folder_name = sanitize_file_name(folder_key)
+            .ok_or_else(|| anyhow::anyhow!("Invalid download folder name: {}", folder_key))?;
+        let extract_dir = self.cache_dir.join(folder_name);
+        if !long_path(&extract_dir).exists() {
+            fs::create_dir_all(long_path(&extract_dir))
+                .context("Failed to create download directory")?;
+        }
+
+        let mut extracted_files = Vec::new();
+
+        for (index, url) in urls.iter().enumerate() {
+            let filename = format!("{:03}_{}", index + 1, Self::filename_from_url(url, index));
+            let output_path = contained_path(&extract_dir, Path::new(&filename))?;
+
+            if long_path(&output_path).exists() {
+                println!("💾 CACHE: Using cached file: {}", output_path.display());
+                extracted_files.push(output_path);
+                continue;
+            }
+
+            match self.download_file(url, &output_path).await {
+                Ok(_) => {
+                    println!("✅ URL LIST: Downloaded {}", filename);
+                    extracted_files.push(output_path);
+                }
+                Err(e) => {
+                    println!("⚠️ URL LIST: Failed to download {}: {}", url, e);
+                    // Don't leave a partial file behind for the next attempt to pick up
+                    let _ = fs::remove_file(long_path(&output_path));
+                }
+            }
+        }
+
+        if extracted_files.is_empty() {
+            return Err(anyhow::anyhow!("Failed to download any audio files"));
+        }
+
+        Ok(DownloadResult {
+            local_path: extract_dir,
+            extracted_files,
+        })
+    }
+
+    /// Fetch one file of an Archive.org download again and check it against
+    /// the MD5 Archive.org lists for it. The local copy is only replaced once
+    /// the new one has verified.
+    pub async fn redownload_archive_file(&self, identifier: &str, local_file: &Path) -> Result<()> {
+        let files = self.get_archive_files_metadata(identifier).await?;
+        let archive_name = archive_name_for(local_file, &files)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a file of {}", local_file.display(), identifier))?;
+        let expected_md5 = files.iter()
+            .find(|file| file.get("name").and_then(|name| name.as_str()) == Some(archive_name.as_str()))
+            .and_then(|file| file.get("md5"))
+            .and_then(|md5| md5.as_str())
+            .map(str::to_lowercase);
+
+        let repair_path = atomic::sibling(local_file, atomic::REPAIR_SUFFIX);
+        let file_url = format!("https://archive.org/download/{}/{}", identifier, archive_name);
+        println!("🩹 ARCHIVE.ORG: Downloading {} again", archive_name);
+        self.download_file(&file_url, &repair_path).await?;
+
+        if let Some(expected) = expected_md5 {
+            let actual = file_md5(&repair_path)?;
+            if actual != expected {
+                let _ = fs::remove_file(long_path(&repair_path));
+                return Err(anyhow::anyhow!("Checksum mismatch for {}: expected {}, got {}", archive_name, expected, actual));
+            }
+        }
+        atomic::commit(&long_path(&repair_path), &long_path(local_file))
+            .context("Failed to move repaired file into place")?;
+        Ok(())
+    }
+
+    fn filename_from_url(url: &str, index: usize) -> String {
+        let last_segment = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or("");
+
+        let cleaned: String = last_segment
+            .replace("%20", " ")
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_' | ' ') { c } else { '_' })
+            .collect();
+        let cleaned = sanitize_file_name(cleaned.trim().trim_start_matches('.')).unwrap_or_default();
+
+        if cleaned.is_empty() {
+            format!("part_{}.mp3", index + 1)
+        } else if Path::new(&cleaned).extension().is_none() {
+            format!("{}.mp3", cleaned)
+        } else {
+            cleaned
+        }
+    }
+
+    pub async fn get_archive_files_metadata(&self, identifier: &str) -> Result<Vec<Value>> {
         let url = format!("https://archive.org/metadata/{}/files?output=json", identifier);
         println!("🌐 ARCHIVE.ORG: Getting file metadata from: {}", url);
         
@@ -428,6 +753,81 @@ impl DownloadManager {
     }
 }
 
+/// Files listed in a download folder's manifest that are missing or incomplete.
+/// Folders downloaded before manifests were written report nothing missing.
+pub fn missing_archive_files(dir: &Path) -> Vec<String> {
+    let manifest = fs::read_to_string(dir.join(ARCHIVE_MANIFEST_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<ManifestEntry>>(&json).ok())
+        .unwrap_or_default();
+    let local_names = local_file_names(&manifest);
+    manifest
+        .into_iter()
+        .zip(local_names)
+        .filter_map(|(entry, local_name)| {
+            let local_name = local_name?;
+            (!is_downloaded(dir, &local_name, &entry)).then_some(entry.name)
+        })
+        .collect()
+}
+
+/// The file each manifest entry is saved as: flattened, sanitized and distinct
+/// from the others ignoring case. None for names that cannot be saved safely.
+/// Where each file Archive.org listed for a download folder was saved, by its Archive.org name
+pub fn archive_file_paths(dir: &Path) -> Vec<(String, PathBuf)> {
+    let manifest = fs::read_to_string(dir.join(ARCHIVE_MANIFEST_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str::<Vec<ManifestEntry>>(&json).ok())
+        .unwrap_or_default();
+    let local_names = local_file_names(&manifest);
+    manifest
+        .into_iter()
+        .zip(local_names)
+        .filter_map(|(entry, local_name)| Some((entry.name, dir.join(local_name?))))
+        .collect()
+}
+
+/// The Archive.org name of a downloaded file: from the folder's manifest, or
+/// for folders downloaded before manifests were written, the listed file that
+/// would be saved under the same name
+fn archive_name_for(local_file: &Path, files: &[Value]) -> Option<String> {
+    if let Some(dir) = local_file.parent() {
+        if let Some((name, _)) = archive_file_paths(dir).into_iter().find(|(_, path)| path == local_file) {
+            return Some(name);
+        }
+    }
+    let local_name = local_file.file_name()?.to_string_lossy().to_string();
+    files.iter()
+        .filter_map(|file| file.get("name").and_then(|name| name.as_str()))
+        .find(|name| flat_file_name(name).as_deref() == Some(local_name.as_str()))
+        .map(str::to_string)
+}
+
+fn file_md5(path: &Path) -> Result<String> {
+    let mut reader = BufReader::new(fs::File::open(long_path(path)).context("Failed to open downloaded file")?);
+    let mut context = md5::Context::new();
+    std::io::copy(&mut reader, &mut context).context("Failed to read downloaded file")?;
+    Ok(format!("{:x}", context.compute()))
+}
+
+fn local_file_names(manifest: &[ManifestEntry]) -> Vec<Option<String>> {
+    let mut names = UniqueNames::new();
+    manifest
+        .iter()
+        .map(|entry| {
+            let name = flat_file_name(&entry.name)?;
+            Some(names.claim(PathBuf::from(name)).to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+fn is_downloaded(dir: &Path, local_name: &str, entry: &ManifestEntry) -> bool {
+    match fs::metadata(long_path(&dir.join(local_name))) {
+        Ok(metadata) => entry.size.is_none_or(|size| metadata.len() == size),
+        Err(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -438,6 +838,149 @@ mod tests {
         assert!(manager.cache_dir.exists());
     }
     
+    #[test]
+    fn test_is_supported_archive() {
+        assert!(DownloadManager::is_supported_archive(Path::new("book.zip")));
+        assert!(DownloadManager::is_supported_archive(Path::new("Book.RAR")));
+        assert!(!DownloadManager::is_supported_archive(Path::new("book.7z")));
+        assert!(!DownloadManager::is_supported_archive(Path::new("book")));
+    }
+
+    #[tokio::test]
+    async fn test_extract_local_zip_archive() {
+        let manager = DownloadManager::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let zip_path = temp_dir.path().join("book.zip");
+
+        {
+            let file = fs::File::create(&zip_path).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("Book/02 - Two.mp3", options).unwrap();
+            std::io::Write::write_all(&mut writer, b"two").unwrap();
+            writer.start_file("Book/01 - One.mp3", options).unwrap();
+            std::io::Write::write_all(&mut writer, b"one").unwrap();
+            writer.start_file("Book/readme.txt", options).unwrap();
+            std::io::Write::write_all(&mut writer, b"notes").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let extract_dir = temp_dir.path().join("out");
+        let files = manager.extract_local_archive(&zip_path, &extract_dir).await.unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("Book/01 - One.mp3"));
+        assert!(extract_dir.join("Book").join("readme.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extract_zip_rejects_malicious_entries() {
+        let manager = DownloadManager::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let zip_path = temp_dir.path().join("evil.zip");
+
+        {
+            let file = fs::File::create(&zip_path).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, data) in [
+                ("../escaped.mp3", "slip"),
+                ("Book/../../escaped2.mp3", "slip"),
+                ("/absolute.mp3", "abs"),
+                ("Book/Track.mp3", "upper"),
+                ("book/track.mp3", "lower"),
+                ("Book/con.mp3", "device"),
+            ] {
+                writer.start_file(name, options).unwrap();
+                std::io::Write::write_all(&mut writer, data.as_bytes()).unwrap();
+            }
+            writer.add_symlink("Book/link", "../../", options).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let extract_dir = temp_dir.path().join("out");
+        let files = manager.extract_local_archive(&zip_path, &extract_dir).await.unwrap();
+        assert!(!temp_dir.path().join("escaped.mp3").exists());
+        assert!(!temp_dir.path().join("escaped2.mp3").exists());
+        assert!(!extract_dir.join("Book").join("link").exists());
+
+        let names: Vec<String> = files
+            .iter()
+            .map(|path| path.strip_prefix(&extract_dir).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(names, vec!["Book/Track.mp3", "Book/_con.mp3", "book/track (2).mp3"]);
+        // Both case variants survive even where the file system ignores case
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "upper");
+        assert_eq!(fs::read_to_string(&files[2]).unwrap(), "lower");
+    }
+
+    #[tokio::test]
+    async fn test_extract_zip_with_unicode_names() {
+        let manager = DownloadManager::new().unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let zip_path = temp_dir.path().join("книга 📖.zip");
+
+        {
+            let file = fs::File::create(&zip_path).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, data) in [("Мастер и Маргарита/01 - Глава 🎧.mp3", "one"), ("Мастер и Маргарита/02 - 第二章.mp3", "two")] {
+                writer.start_file(name, options).unwrap();
+                std::io::Write::write_all(&mut writer, data.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let extract_dir = temp_dir.path().join("out");
+        let files = manager.extract_local_archive(&zip_path, &extract_dir).await.unwrap();
+        let book_dir = extract_dir.join("Мастер и Маргарита");
+        assert_eq!(files, vec![book_dir.join("01 - Глава 🎧.mp3"), book_dir.join("02 - 第二章.mp3")]);
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "two");
+        assert_eq!(manager.list_audio_files(&book_dir).unwrap(), files);
+    }
+
+    #[test]
+    fn test_missing_archive_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(missing_archive_files(dir.path()).is_empty());
+
+        let manifest = vec![
+            ManifestEntry { name: "one.mp3".to_string(), size: Some(3) },
+            ManifestEntry { name: "two.mp3".to_string(), size: Some(3) },
+            ManifestEntry { name: "three.mp3".to_string(), size: None },
+        ];
+        fs::write(dir.path().join(ARCHIVE_MANIFEST_FILE), serde_json::to_string(&manifest).unwrap()).unwrap();
+        fs::write(dir.path().join("one.mp3"), b"one").unwrap();
+        fs::write(dir.path().join("two.mp3"), b"t").unwrap();
+        fs::write(dir.path().join("three.mp3.part"), b"thr").unwrap();
+
+        assert_eq!(missing_archive_files(dir.path()), vec!["two.mp3", "three.mp3"]);
+
+        // Names from the server are saved flattened and sanitized
+        let manifest = vec![
+            ManifestEntry { name: "disc1/01: Intro.mp3".to_string(), size: None },
+            ManifestEntry { name: "../escape.mp3".to_string(), size: None },
+        ];
+        assert_eq!(local_file_names(&manifest), vec![Some("disc1_01_ Intro.mp3".to_string()), None]);
+    }
+
+    #[test]
+    fn test_archive_name_for_and_file_md5() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = vec![
+            serde_json::json!({ "name": "disc1/01: Intro.mp3" }),
+            serde_json::json!({ "name": "02.mp3" }),
+        ];
+        assert_eq!(archive_name_for(&dir.path().join("disc1_01_ Intro.mp3"), &files).as_deref(), Some("disc1/01: Intro.mp3"));
+        assert_eq!(archive_name_for(&dir.path().join("03.mp3"), &files), None);
+
+        let manifest = vec![ManifestEntry { name: "renamed/02.mp3".to_string(), size: None }];
+        fs::write(dir.path().join(ARCHIVE_MANIFEST_FILE), serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(archive_name_for(&dir.path().join("renamed_02.mp3"), &files).as_deref(), Some("renamed/02.mp3"));
+
+        fs::write(dir.path().join("02.mp3"), b"hello").unwrap();
+        assert_eq!(file_md5(&dir.path().join("02.mp3")).unwrap(), "5d41402abc4b2a76b9719d911017c592");
+    }
+
     #[test]
     fn test_is_audio_file() {
         let manager = DownloadManager::new().unwrap();
@@ -447,4 +990,14 @@ mod tests {
         assert!(!manager.is_audio_file(Path::new("test.txt")));
         assert!(!manager.is_audio_file(Path::new("test")));
     }
+
+    #[test]
+    fn test_filename_from_url() {
+        assert_eq!(
+            DownloadManager::filename_from_url("https://example.com/course/lesson%201.mp3?token=abc", 0),
+            "lesson 1.mp3"
+        );
+        assert_eq!(DownloadManager::filename_from_url("https://example.com/stream", 1), "stream.mp3");
+        assert_eq!(DownloadManager::filename_from_url("https://example.com/", 2), "part_3.mp3");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,119 @@
+// Short samples of Archive.org audiobooks for listening before importing. Only
+// the start of the first chapter is fetched, with a Range request, into a temp
+// folder outside the download cache, so nothing reaches the library.
+
+use super::DownloadManager;
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+/// Length of a preview
+pub const PREVIEW_SECONDS: u64 = 30;
+/// Bitrate assumed when Archive.org does not say (LibriVox originals are 128 kbps)
+const DEFAULT_PREVIEW_KBPS: u64 = 128;
+/// Fetched on top of the estimate, for ID3 tags and cover art in front of the audio
+const PREVIEW_HEADROOM_BYTES: u64 = 256 * 1024;
+
+/// Folder previews are written to; emptied before each new preview
+pub fn preview_dir() -> PathBuf {
+    std::env::temp_dir().join("audiovibe-preview")
+}
+
+/// Bytes to fetch for `seconds` of audio, from the bitrate in an Archive.org
+/// format such as "64Kbps MP3". VBR files are estimated at twice the default.
+pub fn preview_byte_count(format: Option<&str>, seconds: u64) -> u64 {
+    let format = format.unwrap_or("").to_lowercase();
+    let kbps = format
+        .find("kbps")
+        .and_then(|end| {
+            let digits: String = format[..end].chars().rev().take_while(|c| c.is_ascii_digit()).collect();
+            digits.chars().rev().collect::<String>().parse::<u64>().ok()
+        })
+        .unwrap_or(if format.contains("vbr") { DEFAULT_PREVIEW_KBPS * 2 } else { DEFAULT_PREVIEW_KBPS });
+    kbps * 1000 / 8 * seconds + PREVIEW_HEADROOM_BYTES
+}
+
+/// The first chapter: lowest track number, then file name
+fn first_chapter(files: &[Value]) -> Option<&Value> {
+    let track = |file: &Value| -> u32 {
+        file.get("track")
+            .and_then(|track| track.as_str())
+            .and_then(|track| track.split('/').next())
+            .and_then(|track| track.trim().parse().ok())
+            .unwrap_or(u32::MAX)
+    };
+    let name = |file: &Value| file.get("name").and_then(|name| name.as_str()).unwrap_or("").to_string();
+    files.iter().filter(|file| file.get("name").is_some()).min_by_key(|file| (track(file), name(file)))
+}
+
+impl DownloadManager {
+    /// Fetch the first `PREVIEW_SECONDS` of an Archive.org audiobook into a temp file
+    pub async fn download_preview(&self, identifier: &str) -> Result<PathBuf> {
+        let files = self.get_archive_files_metadata(identifier).await?;
+        let file = first_chapter(&files).ok_or_else(|| anyhow!("No audio files found for identifier: {}", identifier))?;
+        let name = file.get("name").and_then(|name| name.as_str()).unwrap_or_default();
+        let bytes = preview_byte_count(file.get("format").and_then(|format| format.as_str()), PREVIEW_SECONDS);
+
+        let dir = preview_dir();
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        tokio::fs::create_dir_all(&dir).await.context("Failed to create preview folder")?;
+        let extension = std::path::Path::new(name).extension().and_then(|ext| ext.to_str()).unwrap_or("mp3");
+        let output_path = dir.join(format!("preview.{}", extension));
+
+        let url = format!("https://archive.org/download/{}/{}", identifier, name);
+        println!("PREVIEW: Fetching first {} bytes of {}", bytes, url);
+        let mut response = self.client
+            .get(&url)
+            .header("Range", format!("bytes=0-{}", bytes - 1))
+            .send()
+            .await
+            .context("Failed to request preview")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Preview request failed with status: {}", response.status()));
+        }
+
+        // Servers ignoring the Range header send the whole file; stop reading at the limit
+        let mut output = tokio::fs::File::create(&output_path).await.context("Failed to create preview file")?;
+        let mut written = 0u64;
+        while written < bytes {
+            let Some(chunk) = response.chunk().await.context("Failed to read preview")? else {
+                break;
+            };
+            let take = chunk.len().min((bytes - written) as usize);
+            output.write_all(&chunk[..take]).await.context("Failed to write preview file")?;
+            written += take as u64;
+        }
+        output.flush().await?;
+
+        if written == 0 {
+            return Err(anyhow!("Preview of {} is empty", identifier));
+        }
+        Ok(output_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_preview_byte_count_and_first_chapter() {
+        assert_eq!(preview_byte_count(Some("64Kbps MP3"), 30), 240_000 + PREVIEW_HEADROOM_BYTES);
+        assert_eq!(preview_byte_count(Some("128Kbps MP3"), 30), 480_000 + PREVIEW_HEADROOM_BYTES);
+        assert_eq!(preview_byte_count(Some("VBR MP3"), 30), 960_000 + PREVIEW_HEADROOM_BYTES);
+        assert_eq!(preview_byte_count(None, 30), 480_000 + PREVIEW_HEADROOM_BYTES);
+
+        let files = vec![
+            json!({"name": "emma_02_austen.mp3", "track": "02"}),
+            json!({"name": "emma_10_austen.mp3", "track": "10/55"}),
+            json!({"name": "emma_01_austen.mp3", "track": "1/55"}),
+        ];
+        assert_eq!(first_chapter(&files).unwrap()["name"], "emma_01_austen.mp3");
+
+        let untracked = vec![json!({"name": "b.mp3"}), json!({"name": "a.mp3"})];
+        assert_eq!(first_chapter(&untracked).unwrap()["name"], "a.mp3");
+        assert!(first_chapter(&[]).is_none());
+    }
+}
@@ -0,0 +1,188 @@
+// Names from archives and servers turned into paths that stay inside the folder
+// they are written to and are valid on every platform
+
+use crate::filesystem::long_path::long_path;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Longest file name written, in bytes; most file systems stop at 255
+const MAX_NAME_BYTES: usize = 200;
+/// Device names Windows reserves in every folder, whatever the extension
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// One path component with separators, control characters and characters
+/// Windows rejects replaced, trailing dots and spaces trimmed, reserved device
+/// names prefixed and the length capped. None when nothing usable is left.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let replaced: String = name
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') { '_' } else { c })
+        .collect();
+    let trimmed = replaced.trim_start().trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() || trimmed.chars().all(|c| c == '.' || c == '_') {
+        return None;
+    }
+
+    let stem = trimmed.split('.').next().unwrap_or("").trim_end();
+    let mut name = if WINDOWS_RESERVED.iter().any(|reserved| stem.eq_ignore_ascii_case(reserved)) {
+        format!("_{}", trimmed)
+    } else {
+        trimmed.to_string()
+    };
+
+    if name.len() > MAX_NAME_BYTES {
+        let extension = Path::new(&name)
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .filter(|ext| ext.len() <= 16)
+            .unwrap_or_default();
+        let mut cut = MAX_NAME_BYTES - extension.len();
+        while !name.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        name = format!("{}{}", name[..cut].trim_end_matches(['.', ' ']), extension);
+    }
+    Some(name)
+}
+
+/// The relative path of an archive entry with every component sanitized. None
+/// for entries that would leave the target folder (absolute paths, drive
+/// prefixes, `..`) or have no name at all.
+pub fn sanitize_relative_path(name: &str) -> Option<PathBuf> {
+    let mut chars = name.chars();
+    let drive_prefix = chars.next().is_some_and(|c| c.is_ascii_alphabetic()) && chars.next() == Some(':');
+    if name.starts_with(['/', '\\']) || drive_prefix {
+        return None;
+    }
+
+    let mut path = PathBuf::new();
+    for component in name.split(['/', '\\']) {
+        match component {
+            "" | "." => continue,
+            ".." => return None,
+            component => path.push(sanitize_file_name(component)?),
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// A server-provided name that may contain folders, flattened into one file name
+pub fn flat_file_name(name: &str) -> Option<String> {
+    let path = sanitize_relative_path(name)?;
+    let parts: Vec<String> = path.iter().map(|part| part.to_string_lossy().to_string()).collect();
+    sanitize_file_name(&parts.join("_"))
+}
+
+/// `root` joined with a sanitized relative path, refused if the result would
+/// end up outside `root`, including through a link already on disk
+pub fn contained_path(root: &Path, relative: &Path) -> Result<PathBuf> {
+    if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        return Err(anyhow!("Refusing to write outside {}: {}", root.display(), relative.display()));
+    }
+    let path = root.join(relative);
+
+    if let Ok(canonical_root) = long_path(root).canonicalize() {
+        let existing = path
+            .ancestors()
+            .map(long_path)
+            .find(|ancestor| ancestor.exists())
+            .and_then(|ancestor| ancestor.canonicalize().ok());
+        if existing.is_some_and(|existing| !existing.starts_with(&canonical_root)) {
+            return Err(anyhow!("Refusing to write outside {}: {}", root.display(), relative.display()));
+        }
+    }
+    Ok(path)
+}
+
+/// Hands out relative paths that differ by more than letter case, so entries
+/// like `Track.mp3` and `track.mp3` do not overwrite each other on Windows and macOS
+#[derive(Debug, Default)]
+pub struct UniqueNames {
+    taken: HashSet<String>,
+}
+
+impl UniqueNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn claim(&mut self, relative: PathBuf) -> PathBuf {
+        if self.taken.insert(case_key(&relative)) {
+            return relative;
+        }
+
+        let stem = relative.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = relative.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+        for n in 2.. {
+            let candidate = relative.with_file_name(format!("{} ({}){}", stem, n, extension));
+            if self.taken.insert(case_key(&candidate)) {
+                return candidate;
+            }
+        }
+        unreachable!("ran out of candidate names")
+    }
+}
+
+fn case_key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("01 - Chapter One.mp3").as_deref(), Some("01 - Chapter One.mp3"));
+        assert_eq!(sanitize_file_name("what?: \"a\" <b>|c*.mp3").as_deref(), Some("what__ _a_ _b__c_.mp3"));
+        assert_eq!(sanitize_file_name("bell\u{7}\n.mp3").as_deref(), Some("bell__.mp3"));
+        assert_eq!(sanitize_file_name("trailing. . ").as_deref(), Some("trailing"));
+        assert_eq!(sanitize_file_name("con.mp3").as_deref(), Some("_con.mp3"));
+        assert_eq!(sanitize_file_name("LPT1").as_deref(), Some("_LPT1"));
+        assert_eq!(sanitize_file_name("console.mp3").as_deref(), Some("console.mp3"));
+        assert_eq!(sanitize_file_name(".."), None);
+        assert_eq!(sanitize_file_name("  "), None);
+
+        let long = format!("{}.mp3", "é".repeat(300));
+        let name = sanitize_file_name(&long).unwrap();
+        assert!(name.len() <= MAX_NAME_BYTES && name.ends_with("é.mp3"));
+    }
+
+    #[test]
+    fn test_malicious_archive_entries_are_rejected() {
+        for entry in ["../evil.mp3", "Book/../../evil.mp3", "/etc/passwd", "\\\\server\\share\\x", "C:\\Windows\\x.dll", "C:evil", "..\\evil.mp3", ""] {
+            assert_eq!(sanitize_relative_path(entry), None, "{}", entry);
+        }
+        assert_eq!(sanitize_relative_path("Book\\Disc 1/./01.mp3"), Some(PathBuf::from("Book").join("Disc 1").join("01.mp3")));
+        assert_eq!(sanitize_relative_path("Book/aux/01?.mp3"), Some(PathBuf::from("Book").join("_aux").join("01_.mp3")));
+        assert_eq!(flat_file_name("disc1/01.mp3").as_deref(), Some("disc1_01.mp3"));
+        assert_eq!(flat_file_name("../01.mp3"), None);
+        assert_eq!(sanitize_relative_path("1:05 Epilogue.mp3"), Some(PathBuf::from("1_05 Epilogue.mp3")));
+    }
+
+    #[test]
+    fn test_contained_path_and_case_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("out");
+        std::fs::create_dir_all(&root).unwrap();
+        assert_eq!(contained_path(&root, Path::new("a/b.mp3")).unwrap(), root.join("a/b.mp3"));
+        assert!(contained_path(&root, Path::new("../b.mp3")).is_err());
+        assert!(contained_path(&root, Path::new("/b.mp3")).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path(), root.join("link")).unwrap();
+            assert!(contained_path(&root, Path::new("link/escaped.mp3")).is_err());
+        }
+
+        let mut names = UniqueNames::new();
+        assert_eq!(names.claim(PathBuf::from("Book/Track.mp3")), PathBuf::from("Book/Track.mp3"));
+        assert_eq!(names.claim(PathBuf::from("book/track.MP3")), PathBuf::from("book/track (2).MP3"));
+        assert_eq!(names.claim(PathBuf::from("BOOK/TRACK.mp3")), PathBuf::from("BOOK/TRACK (3).mp3"));
+        assert_eq!(names.claim(PathBuf::from("Book/Other.mp3")), PathBuf::from("Book/Other.mp3"));
+    }
+}
@@ -0,0 +1,230 @@
+// Bandwidth throttling shared by every download started through DownloadManager
+
+use crate::database::repository::PreferencesRepository;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+pub const PREF_GLOBAL_LIMIT_KBPS: &str = "download.global_limit_kbps";
+pub const PREF_PER_DOWNLOAD_LIMIT_KBPS: &str = "download.per_download_limit_kbps";
+pub const PREF_PAUSE_WHILE_PLAYING: &str = "download.pause_while_playing";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ThrottleSettings {
+    /// Limit for all downloads combined, in KB/s (0 = unlimited)
+    #[ts(type = "number")]
+    pub global_limit_kbps: u64,
+    /// Limit applied to each individual file transfer, in KB/s (0 = unlimited)
+    #[ts(type = "number")]
+    pub per_download_limit_kbps: u64,
+    /// Hold downloads while the audio engine is playing
+    pub pause_while_playing: bool,
+}
+
+/// One-second accounting window used to cap a byte stream to a rate
+#[derive(Debug)]
+pub struct RateWindow {
+    started: Instant,
+    bytes: u64,
+}
+
+impl RateWindow {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Record `bytes` and return how long the caller must wait to stay under `limit_kbps`
+    pub fn reserve(&mut self, bytes: u64, limit_kbps: u64, now: Instant) -> Option<Duration> {
+        if limit_kbps == 0 {
+            return None;
+        }
+
+        let window = Duration::from_secs(1);
+        if now.duration_since(self.started) >= window {
+            self.started = now;
+            self.bytes = 0;
+        }
+
+        self.bytes += bytes;
+        let allowed = limit_kbps * 1024;
+        if self.bytes <= allowed {
+            return None;
+        }
+
+        // Wait until everything counted in this window is paid for at the limit,
+        // however far past one window a large chunk reaches, then start afresh
+        let paid_at = self.started + Duration::from_secs_f64(self.bytes as f64 / allowed as f64);
+        self.started = paid_at;
+        self.bytes = 0;
+        Some(paid_at.saturating_duration_since(now))
+    }
+}
+
+impl Default for RateWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct DownloadThrottle {
+    global_limit_kbps: AtomicU64,
+    per_download_limit_kbps: AtomicU64,
+    pause_while_playing: AtomicBool,
+    playback_active: AtomicBool,
+    global_window: Mutex<RateWindow>,
+}
+
+impl DownloadThrottle {
+    pub fn new() -> Self {
+        Self {
+            global_limit_kbps: AtomicU64::new(0),
+            per_download_limit_kbps: AtomicU64::new(0),
+            pause_while_playing: AtomicBool::new(false),
+            playback_active: AtomicBool::new(false),
+            global_window: Mutex::new(RateWindow::new()),
+        }
+    }
+
+    pub fn apply(&self, settings: &ThrottleSettings) {
+        self.global_limit_kbps.store(settings.global_limit_kbps, Ordering::Relaxed);
+        self.per_download_limit_kbps.store(settings.per_download_limit_kbps, Ordering::Relaxed);
+        self.pause_while_playing.store(settings.pause_while_playing, Ordering::Relaxed);
+        log::info!("Download throttle updated: {:?}", settings);
+    }
+
+    pub fn settings(&self) -> ThrottleSettings {
+        ThrottleSettings {
+            global_limit_kbps: self.global_limit_kbps.load(Ordering::Relaxed),
+            per_download_limit_kbps: self.per_download_limit_kbps.load(Ordering::Relaxed),
+            pause_while_playing: self.pause_while_playing.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn set_playback_active(&self, active: bool) {
+        self.playback_active.store(active, Ordering::Relaxed);
+    }
+
+    fn should_hold(&self) -> bool {
+        self.pause_while_playing.load(Ordering::Relaxed) && self.playback_active.load(Ordering::Relaxed)
+    }
+
+    /// Block while downloads are paused for playback
+    pub async fn wait_while_paused(&self) {
+        if self.should_hold() {
+            println!("⏸️ DOWNLOAD: Holding download while audio is playing");
+            while self.should_hold() {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            println!("▶️ DOWNLOAD: Resuming download");
+        }
+    }
+
+    /// Account for a received chunk against both the per-download and global limits
+    pub async fn consume(&self, bytes: u64, download_window: &mut RateWindow) {
+        let now = Instant::now();
+        let per_download_wait = download_window.reserve(
+            bytes,
+            self.per_download_limit_kbps.load(Ordering::Relaxed),
+            now,
+        );
+        let global_wait = {
+            let mut window = self.global_window.lock().unwrap();
+            window.reserve(bytes, self.global_limit_kbps.load(Ordering::Relaxed), now)
+        };
+
+        if let Some(wait) = per_download_wait.max(global_wait) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Default for DownloadThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The running download manager's throttle, told by the audio thread whether
+// audio is playing after every command, so pauses from anywhere and a book that
+// ran out release held downloads
+static PLAYBACK_THROTTLE: Mutex<Option<Arc<DownloadThrottle>>> = Mutex::new(None);
+
+/// Hold `throttle`'s downloads while audio plays, when its settings ask for it
+pub fn follow_playback(throttle: Arc<DownloadThrottle>) {
+    *PLAYBACK_THROTTLE.lock().unwrap() = Some(throttle);
+}
+
+pub fn set_playback_active(active: bool) {
+    if let Some(throttle) = PLAYBACK_THROTTLE.lock().unwrap().as_ref() {
+        throttle.set_playback_active(active);
+    }
+}
+
+/// The throttle settings stored in preferences
+pub async fn load_settings(pool: &sqlx::SqlitePool) -> ThrottleSettings {
+    let repo = PreferencesRepository::new(pool);
+    ThrottleSettings {
+        global_limit_kbps: repo.get_i64(PREF_GLOBAL_LIMIT_KBPS, 0).await.unwrap_or(0).max(0) as u64,
+        per_download_limit_kbps: repo.get_i64(PREF_PER_DOWNLOAD_LIMIT_KBPS, 0).await.unwrap_or(0).max(0) as u64,
+        pause_while_playing: repo.get_bool(PREF_PAUSE_WHILE_PLAYING, false).await.unwrap_or(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_never_waits() {
+        let mut window = RateWindow::new();
+        let now = Instant::now();
+        assert!(window.reserve(10 * 1024 * 1024, 0, now).is_none());
+    }
+
+    #[test]
+    fn test_waits_once_limit_exceeded() {
+        let mut window = RateWindow::new();
+        let now = Instant::now();
+        assert!(window.reserve(512 * 1024, 1024, now).is_none());
+        assert!(window.reserve(512 * 1024, 1024, now).is_none());
+        assert!(window.reserve(64 * 1024, 1024, now).is_some());
+    }
+
+    #[test]
+    fn test_chunks_larger_than_the_limit_wait_for_all_their_bytes() {
+        let mut window = RateWindow::new();
+        let mut now = Instant::now();
+        let mut waited = Duration::ZERO;
+        for _ in 0..5 {
+            if let Some(wait) = window.reserve(1024 * 1024, 100, now) {
+                waited += wait;
+                now += wait;
+            }
+        }
+
+        // 5 MB at 100 KB/s
+        let expected = Duration::from_secs_f64(5.0 * 1024.0 / 100.0);
+        assert!(waited + Duration::from_millis(10) >= expected, "waited {:?}", waited);
+        assert!(waited <= expected, "waited {:?}", waited);
+    }
+
+    #[test]
+    fn test_pause_requires_both_flags() {
+        let throttle = DownloadThrottle::new();
+        throttle.set_playback_active(true);
+        assert!(!throttle.should_hold());
+
+        throttle.apply(&ThrottleSettings { pause_while_playing: true, ..Default::default() });
+        assert!(throttle.should_hold());
+
+        throttle.set_playback_active(false);
+        assert!(!throttle.should_hold());
+    }
+}
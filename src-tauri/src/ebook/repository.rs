@@ -2,6 +2,7 @@ use crate::database::models::*;
 use sqlx::SqlitePool;
 use anyhow::{Result, Context};
 use chrono::Utc;
+use crate::validation::Validate;
 
 pub struct EbookRepository<'a> {
     pool: &'a SqlitePool,
@@ -13,6 +14,7 @@ impl<'a> EbookRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateEbookDto) -> Result<Ebook> {
+        dto.validate()?;
         let mut ebook = Ebook::new(dto.title, dto.file_path, dto.file_format);
         ebook.author = dto.author;
         ebook.description = dto.description;
@@ -81,6 +83,7 @@ impl<'a> EbookRepository<'a> {
     }
 
     pub async fn update(&self, id: &str, dto: UpdateEbookDto) -> Result<Ebook> {
+        dto.validate()?;
         let now = Utc::now().to_rfc3339();
 
         sqlx::query(
@@ -169,6 +172,7 @@ impl<'a> ReadingProgressRepository<'a> {
     }
 
     pub async fn upsert(&self, ebook_id: &str, dto: UpdateReadingProgressDto) -> Result<ReadingProgress> {
+        dto.validate()?;
         // Check if progress exists
         let existing = self.find_by_ebook_id(ebook_id).await?;
 
@@ -279,6 +283,7 @@ impl<'a> BookmarkRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateBookmarkDto) -> Result<EbookBookmark> {
+        dto.validate()?;
         let mut bookmark = EbookBookmark::new(dto.ebook_id);
         bookmark.page_number = dto.page_number;
         bookmark.cfi = dto.cfi;
@@ -342,6 +347,7 @@ impl<'a> AnnotationRepository<'a> {
     }
 
     pub async fn create(&self, dto: CreateAnnotationDto) -> Result<EbookAnnotation> {
+        dto.validate()?;
         let mut annotation = EbookAnnotation::new(dto.ebook_id, dto.annotation_type);
         annotation.color = dto.color;
         annotation.cfi_range = dto.cfi_range;
@@ -408,6 +414,7 @@ impl<'a> ReaderSettingsRepository<'a> {
     }
 
     pub async fn upsert(&self, ebook_id: &str, dto: UpdateReaderSettingsDto) -> Result<EbookReaderSettings> {
+        dto.validate()?;
         // Check if settings exist
         let existing = self.find_by_ebook_id(ebook_id).await?;
 
@@ -0,0 +1,209 @@
+// Every event the backend sends to the frontend. Each variant goes out under
+// its own name with the variant's data as the payload; src/types/events.ts
+// declares the same names and payloads for listeners.
+
+use crate::audio::watchdog::PlaybackStall;
+use crate::database::models::{Audiobook, SortPreference, SortScope};
+use crate::document::ocr::OcrProgress;
+use crate::export::FolderExportProgress;
+use crate::inbox::InboxFileEvent;
+use crate::models::WarmUpReport;
+use crate::power::ResumeReport;
+use crate::services::{AudiobookValidation, BookFinished, ChapterErrorEvent, FolderSyncReport, LibrivoxRelease, MaintenanceTask, OfflineVolume, ReleaseAlert, TaskRun, WeeklyDigest};
+use crate::storage::disk_space::{InsufficientSpace, LowDiskSpace};
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Give the emitters an app handle; called once during setup
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Send an event to the frontend. Before setup (and in tests) this does nothing.
+pub fn emit(event: AppEvent) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    if let Err(e) = app.emit(event.name(), &event) {
+        log::warn!("Failed to emit {}: {}", event.name(), e);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export, rename = "PlaybackEventState")]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackState {
+    Loaded,
+    Playing,
+    Paused,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryChange {
+    Added,
+    Updated,
+    Removed,
+}
+
+/// Payloads serialize without a wrapper, so listeners receive the data as is
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AppEvent {
+    // Startup
+    InitComplete(WarmUpReport),
+    IncompleteImports(Vec<Audiobook>),
+
+    // Playback
+    PlaybackChanged {
+        state: PlaybackState,
+        file_path: Option<String>,
+        position: Option<u64>,
+    },
+    SystemResumed(ResumeReport),
+    /// The last chapter of a book played out; says what, if anything, was queued next
+    BookFinished(Box<BookFinished>),
+    /// A chapter file could not be loaded and was flagged in the library
+    ChapterError(ChapterErrorEvent),
+    /// The output stopped moving while playing; see audio::watchdog
+    PlaybackStalled(PlaybackStall),
+
+    // Downloads
+    DownloadProgress {
+        url: String,
+        file_name: String,
+        downloaded_bytes: u64,
+        total_bytes: Option<u64>,
+    },
+    DownloadCompleted {
+        url: String,
+        file_name: String,
+        file_path: String,
+    },
+    DownloadFailed {
+        url: String,
+        file_name: String,
+        error: String,
+    },
+
+    // Storage
+    /// A download, TTS job or export was refused for lack of space
+    InsufficientDiskSpace(InsufficientSpace),
+    /// The library or cache volume dropped below the low-space threshold
+    LowDiskSpace(LowDiskSpace),
+    /// A drive or share holding books was disconnected; its books are offline, not missing
+    VolumeOffline(OfflineVolume),
+    /// A disconnected drive is back and its books are being checked again
+    VolumeOnline(OfflineVolume),
+
+    // Scans
+    ScanStarted {
+        path: String,
+    },
+    ScanFinished {
+        path: String,
+        files_found: usize,
+        error: Option<String>,
+    },
+    /// Files of a newly imported book were decoded in full
+    AudiobookValidated(AudiobookValidation),
+
+    // Background jobs
+    OcrProgress(OcrProgress),
+    FolderExportProgress(FolderExportProgress),
+    InboxFileProcessed(InboxFileEvent),
+    InboxFileFailed(InboxFileEvent),
+    MaintenanceTaskFinished {
+        task: MaintenanceTask,
+        run: TaskRun,
+    },
+    RecommendationsRefreshed {
+        count: usize,
+    },
+    FolderSyncApplied(FolderSyncReport),
+    /// New LibriVox recordings by authors already in the library
+    NewLibrivoxReleases(Vec<LibrivoxRelease>),
+    /// Releases by followed authors or genres; pending ones are sent again at startup
+    FollowedReleaseAvailable(Vec<ReleaseAlert>),
+    /// A new weekly digest was compiled; get_latest_digest returns it again
+    WeeklyDigestReady(WeeklyDigest),
+
+    // Library
+    LibraryChanged {
+        change: LibraryChange,
+        audiobook_ids: Vec<String>,
+    },
+    /// The library or a collection is now sorted differently
+    SortPreferenceChanged {
+        scope: SortScope,
+        sort: SortPreference,
+    },
+}
+
+impl AppEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::InitComplete(_) => "init-complete",
+            AppEvent::IncompleteImports(_) => "incomplete-imports",
+            AppEvent::PlaybackChanged { .. } => "playback-changed",
+            AppEvent::SystemResumed(_) => "system-resumed",
+            AppEvent::BookFinished(_) => "book-finished",
+            AppEvent::ChapterError(_) => "chapter-error",
+            AppEvent::PlaybackStalled(_) => "playback-stalled",
+            AppEvent::DownloadProgress { .. } => "download-progress",
+            AppEvent::DownloadCompleted { .. } => "download-completed",
+            AppEvent::DownloadFailed { .. } => "download-failed",
+            AppEvent::InsufficientDiskSpace(_) => "insufficient-disk-space",
+            AppEvent::LowDiskSpace(_) => "low-disk-space",
+            AppEvent::VolumeOffline(_) => "volume-offline",
+            AppEvent::VolumeOnline(_) => "volume-online",
+            AppEvent::ScanStarted { .. } => "scan-started",
+            AppEvent::ScanFinished { .. } => "scan-finished",
+            AppEvent::AudiobookValidated(_) => "audiobook-validated",
+            AppEvent::OcrProgress(_) => "ocr-progress",
+            AppEvent::FolderExportProgress(_) => "folder-export-progress",
+            AppEvent::InboxFileProcessed(_) => "inbox-file-processed",
+            AppEvent::InboxFileFailed(_) => "inbox-file-failed",
+            AppEvent::MaintenanceTaskFinished { .. } => "maintenance-task-finished",
+            AppEvent::RecommendationsRefreshed { .. } => "recommendations-refreshed",
+            AppEvent::FolderSyncApplied(_) => "folder-sync-applied",
+            AppEvent::NewLibrivoxReleases(_) => "new-librivox-releases",
+            AppEvent::FollowedReleaseAvailable(_) => "followed-release-available",
+            AppEvent::WeeklyDigestReady(_) => "weekly-digest-ready",
+            AppEvent::LibraryChanged { .. } => "library-changed",
+            AppEvent::SortPreferenceChanged { .. } => "sort-preference-changed",
+        }
+    }
+
+    /// A library change touching one book
+    pub fn library_changed(change: LibraryChange, audiobook_id: &str) -> Self {
+        AppEvent::LibraryChanged { change, audiobook_ids: vec![audiobook_id.to_string()] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads_serialize_without_wrapper() {
+        let event = AppEvent::library_changed(LibraryChange::Removed, "book-1");
+        assert_eq!(event.name(), "library-changed");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "change": "removed", "audiobook_ids": ["book-1"] })
+        );
+
+        let event = AppEvent::OcrProgress(OcrProgress { file_path: "scan.pdf".to_string(), page: 2, total_pages: 9 });
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "file_path": "scan.pdf", "page": 2, "total_pages": 9 })
+        );
+    }
+}
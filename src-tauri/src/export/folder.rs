@@ -0,0 +1,281 @@
+// Folder export: copies chapter files to a device or folder with clean names,
+// tags and cover art, verifying every copy
+
+use super::{ExportChapter, ExportMetadata, ExportResult};
+use crate::audio::tags::{write_tags, TagValues};
+use crate::filesystem::atomic;
+use crate::storage::disk_space;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub const DEFAULT_STRUCTURE_TEMPLATE: &str = "{author}/{title}/{track} - {chapter_title}";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FolderExportProgress {
+    pub audiobook_id: String,
+    pub current_file: usize,
+    pub total_files: usize,
+    pub file_name: String,
+    #[ts(type = "number")]
+    pub bytes_copied: u64,
+    #[ts(type = "number")]
+    pub total_bytes: u64,
+}
+
+/// Copy every chapter into `dest` following `template`, calling `on_progress`
+/// as data is written
+pub fn export_to_folder<F>(
+    audiobook_id: &str,
+    metadata: &ExportMetadata,
+    chapters: &[ExportChapter],
+    dest: &Path,
+    template: &str,
+    mut on_progress: F,
+) -> Result<ExportResult>
+where
+    F: FnMut(FolderExportProgress),
+{
+    if chapters.is_empty() {
+        return Err(anyhow::anyhow!("Audiobook has no audio files to export"));
+    }
+
+    let mut total_bytes = 0u64;
+    for chapter in chapters {
+        let size = fs::metadata(&chapter.file_path)
+            .with_context(|| format!("Chapter file is missing: {}", chapter.file_path.display()))?
+            .len();
+        total_bytes += size;
+    }
+    disk_space::ensure_space(dest, total_bytes)?;
+
+    let cover_path = metadata.cover_path.as_ref().filter(|path| path.exists());
+    let total_files = chapters.len();
+    let mut bytes_copied = 0u64;
+    let mut total_duration_ms = 0u64;
+    let mut first_output: Option<PathBuf> = None;
+
+    println!("📤 FOLDER EXPORT: Copying {} files ({} bytes) to {}", total_files, total_bytes, dest.display());
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let extension = chapter.file_path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "mp3".to_string());
+        let relative = render_template(template, metadata, chapter, index + 1, total_files);
+        // Append rather than replace the extension; chapter titles may contain dots
+        let file_name = format!(
+            "{}.{}",
+            relative.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            extension
+        );
+        let output_path = dest.join(&relative).with_file_name(&file_name);
+
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create folder: {}", parent.display()))?;
+        }
+
+        let expected_size = copy_with_progress(&chapter.file_path, &output_path, |chunk| {
+            bytes_copied += chunk;
+            on_progress(FolderExportProgress {
+                audiobook_id: audiobook_id.to_string(),
+                current_file: index + 1,
+                total_files,
+                file_name: file_name.clone(),
+                bytes_copied,
+                total_bytes,
+            });
+        })?;
+
+        // Verify before tagging, since tagging legitimately changes the size
+        let copied_size = fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0);
+        if copied_size != expected_size {
+            let _ = fs::remove_file(&output_path);
+            return Err(anyhow::anyhow!(
+                "Size mismatch after copying {} ({} of {} bytes)",
+                chapter.file_path.display(), copied_size, expected_size
+            ));
+        }
+
+        let tags = TagValues {
+            title: Some(chapter.title.clone()),
+            artist: metadata.author.clone(),
+            album: Some(metadata.title.clone()),
+            album_artist: metadata.author.clone(),
+            narrator: metadata.narrator.clone(),
+            genre: metadata.genre.clone(),
+            comment: None,
+            track: Some((index + 1) as u32),
+            track_total: Some(total_files as u32),
+        };
+        if let Err(e) = write_tags(&output_path, &tags, cover_path.map(|p| p.as_path())) {
+            // Untaggable formats are still worth copying
+            log::warn!("Could not tag exported file {}: {}", output_path.display(), e);
+        }
+
+        total_duration_ms += chapter.duration_seconds.unwrap_or(0).max(0) as u64 * 1000;
+        first_output.get_or_insert(output_path);
+    }
+
+    // Many players pick up folder art from a cover.jpg next to the files
+    if let (Some(cover), Some(first)) = (cover_path, first_output.as_ref()) {
+        if let Some(book_dir) = first.parent() {
+            let cover_ext = cover.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_else(|| "jpg".to_string());
+            if let Err(e) = fs::copy(cover, book_dir.join(format!("cover.{}", cover_ext))) {
+                log::warn!("Could not copy cover image: {}", e);
+            }
+        }
+    }
+
+    let book_dir = first_output
+        .as_ref()
+        .and_then(|path| path.parent())
+        .unwrap_or(dest)
+        .to_path_buf();
+    println!("✅ FOLDER EXPORT: Copied {} files to {}", total_files, book_dir.display());
+
+    Ok(ExportResult {
+        output_path: book_dir.to_string_lossy().to_string(),
+        chapters_written: total_files,
+        total_duration_ms,
+        file_size: bytes_copied,
+    })
+}
+
+/// Copy through a `.part` file in 1 MB chunks, reporting each chunk; returns the source size
+fn copy_with_progress<F>(source: &Path, target: &Path, mut on_chunk: F) -> Result<u64>
+where
+    F: FnMut(u64),
+{
+    let partial_path = atomic::sibling(target, atomic::PARTIAL_SUFFIX);
+
+    let result = (|| -> Result<u64> {
+        let mut input = fs::File::open(source)
+            .with_context(|| format!("Failed to open {}", source.display()))?;
+        let mut output = fs::File::create(&partial_path)
+            .with_context(|| format!("Failed to create {}", partial_path.display()))?;
+
+        let mut buffer = vec![0u8; 1024 * 1024];
+        let mut total = 0u64;
+        loop {
+            let read = input.read(&mut buffer).context("Failed to read source file")?;
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read]).context("Failed to write exported file")?;
+            total += read as u64;
+            on_chunk(read as u64);
+        }
+        Ok(total)
+    })();
+
+    match result {
+        Ok(total) => {
+            atomic::commit(&partial_path, target).context("Failed to move exported file into place")?;
+            Ok(total)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial_path);
+            Err(e)
+        }
+    }
+}
+
+/// Expand `{author}`, `{title}`, `{narrator}`, `{track}` and `{chapter_title}`;
+/// `/` in the template creates sub-folders
+fn render_template(
+    template: &str,
+    metadata: &ExportMetadata,
+    chapter: &ExportChapter,
+    track: usize,
+    total: usize,
+) -> PathBuf {
+    let template = if template.trim().is_empty() { DEFAULT_STRUCTURE_TEMPLATE } else { template };
+    let width = total.to_string().len().max(2);
+    let track = format!("{:0width$}", track, width = width);
+
+    template
+        .split(['/', '\\'])
+        .map(|segment| {
+            let rendered = segment
+                .replace("{author}", metadata.author.as_deref().unwrap_or("Unknown Author"))
+                .replace("{title}", &metadata.title)
+                .replace("{narrator}", metadata.narrator.as_deref().unwrap_or("Unknown Narrator"))
+                .replace("{track}", &track)
+                .replace("{chapter_title}", &chapter.title);
+            clean_segment(&rendered)
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Strip characters that FAT32/exFAT devices and Windows reject
+fn clean_segment(segment: &str) -> String {
+    let cleaned: String = segment
+        .chars()
+        .map(|c| if matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') || c.is_control() { '_' } else { c })
+        .collect();
+    cleaned.trim().trim_end_matches('.').trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_metadata() -> ExportMetadata {
+        ExportMetadata {
+            title: "Pride: and Prejudice?".to_string(),
+            author: Some("Jane Austen".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn sample_chapter(path: PathBuf) -> ExportChapter {
+        ExportChapter {
+            title: "Chapter 1".to_string(),
+            file_path: path,
+            duration_seconds: Some(60),
+        }
+    }
+
+    #[test]
+    fn test_render_default_template() {
+        let path = render_template("", &sample_metadata(), &sample_chapter(PathBuf::from("a.mp3")), 3, 12);
+        assert_eq!(path, PathBuf::from("Jane Austen").join("Pride_ and Prejudice_").join("03 - Chapter 1"));
+    }
+
+    #[test]
+    fn test_render_custom_template_pads_track() {
+        let path = render_template("{title}/{track}", &sample_metadata(), &sample_chapter(PathBuf::from("a.mp3")), 7, 150);
+        assert_eq!(path, PathBuf::from("Pride_ and Prejudice_").join("007"));
+    }
+
+    #[test]
+    fn test_export_copies_and_reports_progress() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let dest_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().join("one.mp3");
+        fs::write(&source, vec![7u8; 4096]).unwrap();
+
+        let mut last_progress = None;
+        let result = export_to_folder(
+            "book-1",
+            &sample_metadata(),
+            &[sample_chapter(source)],
+            dest_dir.path(),
+            "{track}",
+            |progress| last_progress = Some(progress),
+        ).unwrap();
+
+        let exported = dest_dir.path().join("01.mp3");
+        assert_eq!(fs::metadata(&exported).unwrap().len(), 4096);
+        assert_eq!(result.chapters_written, 1);
+        let progress = last_progress.unwrap();
+        assert_eq!(progress.bytes_copied, 4096);
+        assert_eq!(progress.total_bytes, 4096);
+    }
+}
@@ -0,0 +1,266 @@
+// Single-file M4B export: concatenates chapter files with ffmpeg and writes
+// chapter markers, book tags and cover art into one audiobook file
+
+use super::{ensure_ffmpeg_available, probe_duration_ms, ExportChapter, ExportMetadata, ExportResult};
+use crate::filesystem::atomic;
+use crate::storage::disk_space;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct M4bExportOptions {
+    /// Re-encode to AAC even when the sources are already AAC and could be copied
+    #[serde(default)]
+    pub reencode: bool,
+    /// AAC bitrate used when re-encoding, in kbps
+    #[serde(default = "default_bitrate_kbps")]
+    pub bitrate_kbps: u32,
+}
+
+fn default_bitrate_kbps() -> u32 {
+    64
+}
+
+impl Default for M4bExportOptions {
+    fn default() -> Self {
+        Self {
+            reencode: false,
+            bitrate_kbps: default_bitrate_kbps(),
+        }
+    }
+}
+
+pub async fn export_m4b(
+    metadata: &ExportMetadata,
+    chapters: &[ExportChapter],
+    dest: &Path,
+    options: &M4bExportOptions,
+) -> Result<ExportResult> {
+    if chapters.is_empty() {
+        return Err(anyhow::anyhow!("Audiobook has no audio files to export"));
+    }
+    for chapter in chapters {
+        if !chapter.file_path.exists() {
+            return Err(anyhow::anyhow!("Chapter file is missing: {}", chapter.file_path.display()));
+        }
+    }
+
+    ensure_ffmpeg_available().await?;
+
+    let output_path = resolve_output_path(dest, &metadata.title);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).context("Failed to create export directory")?;
+    }
+    println!("📦 M4B EXPORT: Exporting '{}' ({} files) to {}", metadata.title, chapters.len(), output_path.display());
+
+    // Chapter markers need millisecond accuracy, so measure every file instead of
+    // trusting the whole-second durations stored in the library
+    let mut timed_chapters = Vec::with_capacity(chapters.len());
+    for chapter in chapters {
+        let duration_ms = match probe_duration_ms(&chapter.file_path).await {
+            Some(ms) => ms,
+            None => chapter.duration_seconds
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| seconds as u64 * 1000)
+                .ok_or_else(|| anyhow::anyhow!("Could not determine duration of {}", chapter.file_path.display()))?,
+        };
+        timed_chapters.push((chapter.title.clone(), duration_ms));
+    }
+
+    let work_dir = tempfile::tempdir().context("Failed to create temporary export directory")?;
+    let concat_list_path = work_dir.path().join("files.txt");
+    let metadata_path = work_dir.path().join("metadata.txt");
+    fs::write(&concat_list_path, build_concat_list(chapters))
+        .context("Failed to write concat list")?;
+    fs::write(&metadata_path, build_ffmetadata(metadata, &timed_chapters))
+        .context("Failed to write chapter metadata")?;
+
+    let cover_path = metadata.cover_path.as_ref().filter(|path| path.exists());
+    let copy_audio = !options.reencode && chapters.iter().all(|chapter| is_aac_container(&chapter.file_path));
+    disk_space::ensure_space(&output_path, estimated_size(chapters, &timed_chapters, copy_audio, options.bitrate_kbps))?;
+
+    // Write next to the destination and rename at the end so a failed export
+    // never leaves a truncated .m4b behind
+    let partial_path = atomic::sibling(&output_path, atomic::PARTIAL_SUFFIX);
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-y", "-hide_banner", "-loglevel", "error"])
+        .args(["-f", "concat", "-safe", "0", "-i"]).arg(&concat_list_path)
+        .arg("-i").arg(&metadata_path);
+    if let Some(cover) = cover_path {
+        command.arg("-i").arg(cover);
+    }
+    command.args(["-map", "0:a", "-map_metadata", "1", "-map_chapters", "1"]);
+    if cover_path.is_some() {
+        command.args(["-map", "2:v", "-c:v", "mjpeg", "-disposition:v:0", "attached_pic"]);
+    }
+    if copy_audio {
+        command.args(["-c:a", "copy"]);
+    } else {
+        command.args(["-c:a", "aac", "-b:a"]).arg(format!("{}k", options.bitrate_kbps));
+    }
+    command.args(["-movflags", "+faststart", "-f", "mp4"]).arg(&partial_path);
+
+    let output = command.output().await.context("Failed to run ffmpeg")?;
+    if !output.status.success() {
+        let _ = fs::remove_file(&partial_path);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_lines: Vec<&str> = stderr.lines().rev().take(5).collect();
+        return Err(anyhow::anyhow!(
+            "ffmpeg failed to create the M4B file: {}",
+            last_lines.into_iter().rev().collect::<Vec<_>>().join(" | ")
+        ));
+    }
+
+    atomic::commit(&partial_path, &output_path).context("Failed to move exported file into place")?;
+
+    let file_size = fs::metadata(&output_path).map(|m| m.len()).unwrap_or(0);
+    let total_duration_ms = timed_chapters.iter().map(|(_, ms)| ms).sum();
+    println!("✅ M4B EXPORT: Wrote {} ({} bytes, {} chapters)", output_path.display(), file_size, timed_chapters.len());
+
+    Ok(ExportResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        chapters_written: timed_chapters.len(),
+        total_duration_ms,
+        file_size,
+    })
+}
+
+/// A directory destination gets "<title>.m4b" inside it; a file destination is forced to .m4b
+fn resolve_output_path(dest: &Path, title: &str) -> PathBuf {
+    if dest.is_dir() {
+        let file_name: String = title
+            .chars()
+            .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+            .collect();
+        let file_name = file_name.trim();
+        let file_name = if file_name.is_empty() { "audiobook" } else { file_name };
+        dest.join(format!("{}.m4b", file_name))
+    } else {
+        dest.with_extension("m4b")
+    }
+}
+
+fn is_aac_container(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "m4a" | "m4b" | "mp4" | "aac"))
+        .unwrap_or(false)
+}
+
+/// Input list for ffmpeg's concat demuxer
+/// Copied audio is about as large as its sources; re-encoded audio is the
+/// bitrate times the running time
+fn estimated_size(chapters: &[ExportChapter], timed_chapters: &[(String, u64)], copy_audio: bool, bitrate_kbps: u32) -> u64 {
+    if copy_audio {
+        return chapters
+            .iter()
+            .filter_map(|chapter| fs::metadata(&chapter.file_path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+    }
+    let total_ms: u64 = timed_chapters.iter().map(|(_, ms)| ms).sum();
+    bitrate_kbps as u64 * 125 * total_ms / 1000
+}
+
+fn build_concat_list(chapters: &[ExportChapter]) -> String {
+    chapters
+        .iter()
+        .map(|chapter| {
+            let path = chapter.file_path.to_string_lossy().replace('\'', "'\\''");
+            format!("file '{}'\n", path)
+        })
+        .collect()
+}
+
+/// FFMETADATA document carrying the book tags and one [CHAPTER] block per file
+fn build_ffmetadata(metadata: &ExportMetadata, chapters: &[(String, u64)]) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    out.push_str(&format!("title={}\n", escape_ffmetadata(&metadata.title)));
+    out.push_str(&format!("album={}\n", escape_ffmetadata(&metadata.title)));
+    if let Some(author) = &metadata.author {
+        out.push_str(&format!("artist={}\n", escape_ffmetadata(author)));
+        out.push_str(&format!("album_artist={}\n", escape_ffmetadata(author)));
+    }
+    if let Some(narrator) = &metadata.narrator {
+        out.push_str(&format!("composer={}\n", escape_ffmetadata(narrator)));
+    }
+    if let Some(genre) = &metadata.genre {
+        out.push_str(&format!("genre={}\n", escape_ffmetadata(genre)));
+    }
+    if let Some(description) = &metadata.description {
+        out.push_str(&format!("comment={}\n", escape_ffmetadata(description)));
+    }
+    out.push_str("media_type=2\n");
+
+    let mut start_ms = 0u64;
+    for (title, duration_ms) in chapters {
+        let end_ms = start_ms + duration_ms;
+        out.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        out.push_str(&format!("START={}\nEND={}\n", start_ms, end_ms));
+        out.push_str(&format!("title={}\n", escape_ffmetadata(title)));
+        start_ms = end_ms;
+    }
+
+    out
+}
+
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_ffmetadata() {
+        assert_eq!(escape_ffmetadata("A=B; C#1\\"), "A\\=B\\; C\\#1\\\\");
+        assert_eq!(escape_ffmetadata("line\nbreak"), "line\\\nbreak");
+    }
+
+    #[test]
+    fn test_chapters_are_contiguous() {
+        let metadata = ExportMetadata {
+            title: "Book".to_string(),
+            author: Some("Author".to_string()),
+            ..Default::default()
+        };
+        let chapters = vec![("One".to_string(), 1500), ("Two".to_string(), 2500)];
+        let doc = build_ffmetadata(&metadata, &chapters);
+
+        assert!(doc.starts_with(";FFMETADATA1\n"));
+        assert!(doc.contains("artist=Author\n"));
+        assert!(doc.contains("START=0\nEND=1500\ntitle=One\n"));
+        assert!(doc.contains("START=1500\nEND=4000\ntitle=Two\n"));
+    }
+
+    #[test]
+    fn test_concat_list_quotes_paths() {
+        let chapters = vec![ExportChapter {
+            title: "One".to_string(),
+            file_path: PathBuf::from("/books/it's here.mp3"),
+            duration_seconds: None,
+        }];
+        assert_eq!(build_concat_list(&chapters), "file '/books/it'\\''s here.mp3'\n");
+    }
+
+    #[test]
+    fn test_resolve_output_path() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(resolve_output_path(dir.path(), "A: Story"), dir.path().join("A_ Story.m4b"));
+        assert_eq!(resolve_output_path(Path::new("/tmp/out/book.mp3"), "x"), PathBuf::from("/tmp/out/book.m4b"));
+    }
+}
@@ -0,0 +1,81 @@
+// Export module for AudioVibe
+// Turns library audiobooks back into files that other players and devices can use
+
+pub mod folder;
+pub mod m4b;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use ts_rs::TS;
+
+pub use folder::{export_to_folder, FolderExportProgress};
+pub use m4b::{export_m4b, M4bExportOptions};
+
+/// Book-level tags written into exported files
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportMetadata {
+    pub title: String,
+    pub author: Option<String>,
+    pub narrator: Option<String>,
+    pub genre: Option<String>,
+    pub description: Option<String>,
+    pub cover_path: Option<PathBuf>,
+}
+
+/// One source file of the book, in playback order
+#[derive(Debug, Clone)]
+pub struct ExportChapter {
+    pub title: String,
+    pub file_path: PathBuf,
+    /// Duration from the library, used when ffprobe cannot measure the file
+    pub duration_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportResult {
+    pub output_path: String,
+    pub chapters_written: usize,
+    #[ts(type = "number")]
+    pub total_duration_ms: u64,
+    #[ts(type = "number")]
+    pub file_size: u64,
+}
+
+/// Fail early with a readable message when the ffmpeg tools are not installed
+pub async fn ensure_ffmpeg_available() -> Result<()> {
+    let output = Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .await
+        .context("ffmpeg was not found. Install ffmpeg and make sure it is on your PATH to export audiobooks")?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("ffmpeg is installed but could not be started"));
+    }
+    Ok(())
+}
+
+/// Exact duration of a media file in milliseconds, measured with ffprobe
+pub async fn probe_duration_ms(path: &Path) -> Option<u64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+        .map(|seconds| (seconds * 1000.0).round() as u64)
+}
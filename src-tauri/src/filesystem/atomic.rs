@@ -0,0 +1,165 @@
+// Files are written under a temporary name next to their final path, flushed
+// to disk and only then renamed over it, so a crash or power cut leaves the
+// old file or the new one but never half of one. Temporary files a crash left
+// behind are removed at startup.
+
+use super::long_path::long_path;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Whole-file writes go through `<name>.tmp`
+pub const TEMP_SUFFIX: &str = ".tmp";
+/// Downloads and exports stream into `<name>.part`
+pub const PARTIAL_SUFFIX: &str = ".part";
+/// Single files downloaded again, before their checksum is verified
+pub const REPAIR_SUFFIX: &str = ".repair";
+/// Temporary files untouched for this long belong to a write that never finished
+pub const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// `path` with `suffix` added to its file name: "01.mp3" becomes "01.mp3.part"
+pub fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Replace `path` with `contents` in one step
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let temp = sibling(path, TEMP_SUFFIX);
+    let written = (|| -> Result<()> {
+        let mut file = fs::File::create(long_path(&temp)).with_context(|| format!("Failed to create {}", temp.display()))?;
+        file.write_all(contents.as_ref()).with_context(|| format!("Failed to write {}", temp.display()))?;
+        Ok(())
+    })();
+    match written {
+        Ok(()) => commit(&temp, path),
+        Err(e) => {
+            let _ = fs::remove_file(long_path(&temp));
+            Err(e)
+        }
+    }
+}
+
+/// `write` for async callers; the blocking file calls run off the runtime's workers
+pub async fn write_async(path: &Path, contents: impl Into<Vec<u8>>) -> Result<()> {
+    let path = path.to_path_buf();
+    let contents = contents.into();
+    tokio::task::spawn_blocking(move || write(&path, contents))
+        .await
+        .context("File write was cancelled")?
+}
+
+/// `commit` for async callers
+pub async fn commit_async(temp: &Path, path: &Path) -> Result<()> {
+    let (temp, path) = (temp.to_path_buf(), path.to_path_buf());
+    tokio::task::spawn_blocking(move || commit(&temp, &path))
+        .await
+        .context("File write was cancelled")?
+}
+
+/// Flush a finished temporary file to disk and move it over `path`. The
+/// temporary file is removed if it cannot be moved.
+pub fn commit(temp: &Path, path: &Path) -> Result<()> {
+    let synced = fs::OpenOptions::new()
+        .write(true)
+        .open(long_path(temp))
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to flush {}", temp.display()));
+    let moved = synced.and_then(|()| {
+        fs::rename(long_path(temp), long_path(path)).with_context(|| format!("Failed to move {} into place", path.display()))
+    });
+    if let Err(e) = moved {
+        let _ = fs::remove_file(long_path(temp));
+        return Err(e);
+    }
+    sync_dir(path);
+    Ok(())
+}
+
+/// The rename itself is only durable once the directory is flushed. Windows
+/// cannot open directories for this and needs nothing more.
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+pub fn is_temp_file(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    [TEMP_SUFFIX, PARTIAL_SUFFIX, REPAIR_SUFFIX].iter().any(|suffix| name.len() > suffix.len() && name.ends_with(suffix))
+}
+
+/// Remove temporary files under `dir` last modified before `now - STALE_AFTER`;
+/// returns how many were removed. Files still being written keep changing and
+/// are left alone.
+pub fn clean_stale(dir: &Path, now: SystemTime) -> usize {
+    let Ok(entries) = fs::read_dir(long_path(dir)) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            removed += clean_stale(&path, now);
+            continue;
+        }
+        if !file_type.is_file() || !is_temp_file(&path) {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| now.duration_since(modified).is_ok_and(|age| age >= STALE_AFTER));
+        if stale && fs::remove_file(long_path(&path)).is_ok() {
+            println!("🧹 CLEANUP: Removed unfinished file {}", path.display());
+            removed += 1;
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_replaces_without_leaving_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cover.jpg");
+        write(&path, b"old").unwrap();
+        write(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A failed write leaves nothing behind
+        assert!(write(&dir.path().join("missing").join("cover.jpg"), b"x").is_err());
+        assert_eq!(sibling(&path, PARTIAL_SUFFIX), dir.path().join("cover.jpg.part"));
+    }
+
+    #[test]
+    fn test_clean_stale_only_removes_old_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let book = dir.path().join("book_librivox");
+        fs::create_dir(&book).unwrap();
+        for name in ["01.mp3", "02.mp3.part", "cover.jpg.tmp", ".part"] {
+            fs::write(book.join(name), b"x").unwrap();
+        }
+
+        assert_eq!(clean_stale(dir.path(), SystemTime::now()), 0, "just written");
+        let later = SystemTime::now() + STALE_AFTER + Duration::from_secs(1);
+        assert_eq!(clean_stale(dir.path(), later), 2);
+        assert!(book.join("01.mp3").exists());
+        assert!(book.join(".part").exists());
+        assert!(!book.join("02.mp3.part").exists());
+    }
+}
@@ -0,0 +1,388 @@
+// Where chapter audio is read from. Most libraries sit on a local disk and are
+// opened directly. Libraries on a NAS reached over SMB or NFS stall, drop and
+// come back, so files there are opened with a few retries, and chapters that
+// are played are copied into the cache in the background; when the share has
+// dropped, a reload or seek carries on from the local copy. The backend is
+// chosen per file from what its mount point is.
+
+use super::long_path::long_path;
+use super::volume;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Opens on a share, counting the first
+const NETWORK_ATTEMPTS: u32 = 4;
+/// Pause after the first failed open; doubled after each further one
+const NETWORK_RETRY_DELAY: Duration = Duration::from_millis(250);
+/// Larger files, whole-book M4Bs mostly, are played from the share only
+const MAX_CACHED_FILE_BYTES: u64 = 512 * 1024 * 1024;
+/// The oldest copies are deleted past this
+const NETWORK_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+/// How long a read of the mount table is trusted
+const MOUNT_TABLE_TTL: Duration = Duration::from_secs(60);
+/// File systems reached over the network
+const NETWORK_FILE_SYSTEMS: &[&str] = &["cifs", "smb3", "smbfs", "nfs", "nfs4", "afpfs", "webdav", "davfs", "fuse.sshfs"];
+
+/// Mount points with their file system types
+type MountTable = Vec<(PathBuf, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Local,
+    NetworkShare,
+}
+
+/// What a file is opened for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Played now and likely reopened on seeks and reloads
+    Playback,
+    /// Read once, by metadata extraction or validation
+    Scan,
+}
+
+pub trait StorageBackend: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    fn open(&self, path: &Path, access: Access) -> io::Result<File>;
+}
+
+pub struct LocalStorage;
+
+impl StorageBackend for LocalStorage {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Local
+    }
+
+    fn open(&self, path: &Path, _access: Access) -> io::Result<File> {
+        File::open(long_path(path))
+    }
+}
+
+pub struct NetworkShareStorage {
+    cache_dir: PathBuf,
+    retry_delay: Duration,
+    /// Files being copied into the cache, so each is copied once
+    caching: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl NetworkShareStorage {
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self { cache_dir, retry_delay: NETWORK_RETRY_DELAY, caching: Arc::default() }
+    }
+
+    /// Run `operation` until it succeeds, gives an error retrying will not
+    /// fix, or runs out of attempts
+    fn retry<T>(&self, path: &Path, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < NETWORK_ATTEMPTS && is_transient(&e, path) => {
+                    log::warn!("STORAGE: {} failed ({}), retrying in {:?}", path.display(), e, delay);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The copy of this version of the file; a changed file gets a new name
+    fn cached_copy(&self, path: &Path, metadata: &fs::Metadata) -> PathBuf {
+        let modified = metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map_or(0, |age| age.as_secs());
+        let version = format!("{:x}", md5::compute(format!("{}:{}", metadata.len(), modified)));
+        self.cache_dir.join(format!("{}-{}{}", cache_prefix(path), &version[..8], extension(path)))
+    }
+
+    /// Any copy of the file, for when the share cannot be reached to say which is current
+    fn any_cached_copy(&self, path: &Path) -> Option<PathBuf> {
+        let prefix = format!("{}-", cache_prefix(path));
+        fs::read_dir(&self.cache_dir)
+            .ok()?
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .max_by_key(|entry| entry.metadata().and_then(|metadata| metadata.modified()).ok())
+            .map(|entry| entry.path())
+    }
+
+    /// Copy the file into the cache on a thread of its own, then trim the cache
+    fn cache_in_background(&self, path: &Path, copy: PathBuf) {
+        if !self.caching.lock().unwrap().insert(path.to_path_buf()) {
+            return;
+        }
+        let (path, cache_dir, caching) = (path.to_path_buf(), self.cache_dir.clone(), self.caching.clone());
+        std::thread::spawn(move || {
+            // Hidden from any_cached_copy until it is complete
+            let partial = cache_dir.join(format!(".{}.part", copy.file_name().unwrap_or_default().to_string_lossy()));
+            let copied = fs::create_dir_all(&cache_dir)
+                .and_then(|_| fs::copy(long_path(&path), &partial))
+                .and_then(|_| fs::rename(&partial, &copy));
+            match copied {
+                Ok(()) => trim_cache(&cache_dir, NETWORK_CACHE_BYTES),
+                Err(e) => {
+                    let _ = fs::remove_file(&partial);
+                    log::warn!("STORAGE: Failed to cache {}: {}", path.display(), e);
+                }
+            }
+            caching.lock().unwrap().remove(&path);
+        });
+    }
+}
+
+impl StorageBackend for NetworkShareStorage {
+    fn kind(&self) -> BackendKind {
+        BackendKind::NetworkShare
+    }
+
+    fn open(&self, path: &Path, access: Access) -> io::Result<File> {
+        let metadata = match self.retry(path, || fs::metadata(long_path(path))) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                if access == Access::Playback {
+                    if let Some(copy) = self.any_cached_copy(path) {
+                        log::warn!("STORAGE: {} is unreachable ({}), playing the cached copy", path.display(), e);
+                        return File::open(copy);
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        if access == Access::Playback && metadata.is_file() {
+            let copy = self.cached_copy(path, &metadata);
+            if let Ok(file) = File::open(&copy) {
+                return Ok(file);
+            }
+            if metadata.len() <= MAX_CACHED_FILE_BYTES {
+                self.cache_in_background(path, copy);
+            }
+        }
+        self.retry(path, || File::open(long_path(path)))
+    }
+}
+
+/// The backend for the file at `path`
+pub fn backend_for(path: &Path) -> &'static dyn StorageBackend {
+    static NETWORK: OnceLock<NetworkShareStorage> = OnceLock::new();
+    if is_network_path(path) {
+        NETWORK.get_or_init(|| NetworkShareStorage::new(crate::storage::paths().network_cache_dir()))
+    } else {
+        &LocalStorage
+    }
+}
+
+/// Open `path` through its backend
+pub fn open(path: &Path, access: Access) -> io::Result<File> {
+    backend_for(path).open(path, access)
+}
+
+/// UNC paths on Windows, and anything under a network file system mount elsewhere.
+/// Mapped drive letters cannot be told from local drives by their path.
+pub fn is_network_path(path: &Path) -> bool {
+    let text = path.to_string_lossy();
+    if text.starts_with(r"\\") && !text.starts_with(r"\\?\") && !text.starts_with(r"\\.\") {
+        return true;
+    }
+    if !path.is_absolute() {
+        return false;
+    }
+    mount_table()
+        .iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+        .is_some_and(|(_, fs_type)| NETWORK_FILE_SYSTEMS.contains(&fs_type.as_str()))
+}
+
+/// Mount points and their file system types, read again once MOUNT_TABLE_TTL has passed
+fn mount_table() -> MountTable {
+    static TABLE: Mutex<Option<(Instant, MountTable)>> = Mutex::new(None);
+    let mut table = TABLE.lock().unwrap();
+    if let Some((read_at, mounts)) = table.as_ref() {
+        if read_at.elapsed() < MOUNT_TABLE_TTL {
+            return mounts.clone();
+        }
+    }
+    let mounts = read_mount_table();
+    *table = Some((Instant::now(), mounts.clone()));
+    mounts
+}
+
+#[cfg(target_os = "linux")]
+fn read_mount_table() -> MountTable {
+    fs::read_to_string("/proc/self/mounts").map(|table| parse_proc_mounts(&table)).unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn read_mount_table() -> MountTable {
+    std::process::Command::new("mount")
+        .output()
+        .map(|output| parse_mount_output(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_mount_table() -> MountTable {
+    Vec::new()
+}
+
+/// Lines of /proc/mounts: "//nas/books /mnt/books cifs rw,... 0 0", with
+/// spaces in paths written as \040
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_mounts(table: &str) -> MountTable {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, mount_point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+            Some((PathBuf::from(mount_point.replace(r"\040", " ")), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// Lines of macOS `mount`: "//anna@nas/books on /Volumes/books (smbfs, nodev, nosuid)"
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_mount_output(output: &str) -> MountTable {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// Errors a dropped or slow connection gives. A missing file is only worth
+/// waiting for while the share itself is not mounted.
+fn is_transient(error: &io::Error, path: &Path) -> bool {
+    match error.kind() {
+        ErrorKind::NotFound => volume::is_offline(path),
+        ErrorKind::PermissionDenied | ErrorKind::InvalidInput | ErrorKind::InvalidData | ErrorKind::Unsupported => false,
+        _ => true,
+    }
+}
+
+fn cache_prefix(path: &Path) -> String {
+    format!("{:x}", md5::compute(path.to_string_lossy().as_bytes()))
+}
+
+/// The extension with its dot, kept so decoders can use it as a hint
+fn extension(path: &Path) -> String {
+    path.extension().map(|extension| format!(".{}", extension.to_string_lossy())).unwrap_or_default()
+}
+
+/// Delete the oldest copies until the cache fits in `limit` bytes
+fn trim_cache(cache_dir: &Path, limit: u64) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    let mut copies: Vec<(PathBuf, u64, std::time::SystemTime)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect();
+    copies.sort_by_key(|copy| std::cmp::Reverse(copy.2));
+
+    let mut total = 0;
+    for (path, size, _) in copies {
+        total += size;
+        if total > limit {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read(mut file: File) -> String {
+        let mut text = String::new();
+        file.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_network_share_plays_on_from_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let share = dir.path().join("share");
+        fs::create_dir(&share).unwrap();
+        let chapter = share.join("01.mp3");
+        fs::write(&chapter, b"chapter one").unwrap();
+
+        let mut storage = NetworkShareStorage::new(dir.path().join("cache"));
+        storage.retry_delay = Duration::from_millis(1);
+
+        // A scan reads the share and leaves the cache alone
+        assert_eq!(read(storage.open(&chapter, Access::Scan).unwrap()), "chapter one");
+        assert!(storage.any_cached_copy(&chapter).is_none());
+
+        assert_eq!(read(storage.open(&chapter, Access::Playback).unwrap()), "chapter one");
+        let started = Instant::now();
+        while storage.any_cached_copy(&chapter).is_none() || !storage.caching.lock().unwrap().is_empty() {
+            assert!(started.elapsed() < Duration::from_secs(5), "chapter was never cached");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // The share drops: playback carries on from the copy, scans fail
+        fs::remove_file(&chapter).unwrap();
+        assert_eq!(read(storage.open(&chapter, Access::Playback).unwrap()), "chapter one");
+        assert_eq!(storage.open(&chapter, Access::Scan).unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(storage.open(&share.join("02.mp3"), Access::Playback).unwrap_err().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_trim_cache_keeps_the_newest_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, age) in [("old.mp3", 300), ("mid.mp3", 200), ("new.mp3", 100)] {
+            let path = dir.path().join(name);
+            fs::write(&path, [0u8; 10]).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+        }
+        trim_cache(dir.path(), 25);
+        assert!(!dir.path().join("old.mp3").exists());
+        assert!(dir.path().join("mid.mp3").exists() && dir.path().join("new.mp3").exists());
+    }
+
+    #[test]
+    fn test_mount_tables() {
+        let linux = "sysfs /sys sysfs rw 0 0\n//nas/books /mnt/books cifs rw,vers=3.0 0 0\nnas:/export /mnt/My\\040Books nfs4 rw 0 0\n";
+        assert_eq!(
+            parse_proc_mounts(linux),
+            vec![
+                (PathBuf::from("/sys"), "sysfs".to_string()),
+                (PathBuf::from("/mnt/books"), "cifs".to_string()),
+                (PathBuf::from("/mnt/My Books"), "nfs4".to_string()),
+            ]
+        );
+
+        let macos = "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n//anna@nas/My Books on /Volumes/My Books (smbfs, nodev, nosuid, mounted by anna)\n";
+        assert_eq!(
+            parse_mount_output(macos),
+            vec![
+                (PathBuf::from("/"), "apfs".to_string()),
+                (PathBuf::from("/Volumes/My Books"), "smbfs".to_string()),
+            ]
+        );
+
+        assert!(is_network_path(Path::new(r"\\nas\books\01.mp3")));
+        assert!(!is_network_path(Path::new(r"\\?\C:\books\01.mp3")));
+        assert!(!is_network_path(Path::new("books/01.mp3")));
+    }
+}
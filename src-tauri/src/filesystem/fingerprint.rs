@@ -0,0 +1,110 @@
+// Content fingerprints that identify a file independently of its name or location
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use super::long_path::long_path;
+
+/// How much of the file is hashed; together with the size this is unique in practice
+/// for audio files while staying fast on multi-GB M4Bs
+pub const FINGERPRINT_SAMPLE_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub file_size: u64,
+    pub content_hash: String,
+}
+
+pub fn compute_fingerprint(path: &Path) -> io::Result<Fingerprint> {
+    let file = fs::File::open(long_path(path))?;
+    let file_size = file.metadata()?.len();
+
+    let mut sample = Vec::with_capacity(FINGERPRINT_SAMPLE_BYTES.min(file_size) as usize);
+    file.take(FINGERPRINT_SAMPLE_BYTES).read_to_end(&mut sample)?;
+
+    Ok(Fingerprint {
+        file_size,
+        content_hash: format!("{:x}", md5::compute(&sample)),
+    })
+}
+
+/// Recursively list files under `roots` whose size matches one of `wanted_sizes`.
+/// Sizes are cheap to read, so only these candidates ever get hashed.
+pub fn find_files_with_sizes(roots: &[PathBuf], wanted_sizes: &HashSet<u64>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    let mut visited = HashSet::new();
+
+    for root in roots {
+        collect_candidates(root, wanted_sizes, &mut visited, &mut candidates);
+    }
+
+    candidates.sort();
+    candidates
+}
+
+fn collect_candidates(
+    dir: &Path,
+    wanted_sizes: &HashSet<u64>,
+    visited: &mut HashSet<PathBuf>,
+    candidates: &mut Vec<PathBuf>,
+) {
+    // Roots often overlap (a book folder inside a library folder); scan each directory once
+    let canonical = fs::canonicalize(long_path(dir)).unwrap_or_else(|_| dir.to_path_buf());
+    if !visited.insert(canonical) {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(long_path(dir)) else { return };
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        let Ok(file_type) = entry.file_type() else { continue };
+
+        if file_type.is_dir() {
+            collect_candidates(&path, wanted_sizes, visited, candidates);
+        } else if file_type.is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                if wanted_sizes.contains(&metadata.len()) {
+                    candidates.push(path);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_name_and_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("chapter01.mp3");
+        fs::write(&original, b"same audio bytes").unwrap();
+
+        let moved_dir = dir.path().join("moved");
+        fs::create_dir_all(&moved_dir).unwrap();
+        let renamed = moved_dir.join("01 - Introduction.mp3");
+        fs::copy(&original, &renamed).unwrap();
+
+        let other = dir.path().join("other.mp3");
+        fs::write(&other, b"different bytes!").unwrap();
+
+        assert_eq!(compute_fingerprint(&original).unwrap(), compute_fingerprint(&renamed).unwrap());
+        assert_ne!(compute_fingerprint(&original).unwrap(), compute_fingerprint(&other).unwrap());
+    }
+
+    #[test]
+    fn test_find_files_with_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("match.mp3"), vec![0u8; 10]).unwrap();
+        fs::write(dir.path().join("skip.mp3"), vec![0u8; 11]).unwrap();
+
+        let wanted: HashSet<u64> = [10].into_iter().collect();
+        let roots = vec![dir.path().to_path_buf(), nested.clone()];
+        assert_eq!(find_files_with_sizes(&roots, &wanted), vec![nested.join("match.mp3")]);
+    }
+}
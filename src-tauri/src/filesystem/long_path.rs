@@ -0,0 +1,100 @@
+// Windows refuses paths longer than MAX_PATH (260 characters) unless they are
+// given in extended-length form (\\?\C:\... or \\?\UNC\server\share\...).
+// Deeply nested audiobook folders hit that limit, so file operations go
+// through long_path(). Paths shown to the user and stored in the database
+// stay in their normal form.
+
+use std::path::{Path, PathBuf};
+
+/// Paths at least this long are converted; directories are limited to 248
+/// characters, and a little room is left for file names joined on later
+pub const LONG_PATH_THRESHOLD: usize = 240;
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// The path to hand to file system calls: on Windows, long absolute paths in
+/// extended-length form; everywhere else the path as it is
+pub fn long_path(path: &Path) -> PathBuf {
+    if cfg!(windows) && path.as_os_str().len() >= LONG_PATH_THRESHOLD {
+        if let Some(extended) = path.to_str().and_then(extended_length) {
+            return PathBuf::from(extended);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// The extended-length form of an absolute Windows path. Windows does not
+/// normalize these, so separators are unified and `.`/`..` resolved here.
+/// None for relative paths and paths that already are extended.
+pub fn extended_length(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM_PREFIX) {
+        return None;
+    }
+
+    let normalized = path.replace('/', "\\");
+    let (prefix, rest) = if let Some(unc) = normalized.strip_prefix(r"\\") {
+        (VERBATIM_UNC_PREFIX.to_string(), unc.to_string())
+    } else {
+        let mut chars = normalized.chars();
+        let drive = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+        let rest = chars.as_str().strip_prefix(r":\")?;
+        (format!("{}{}:\\", VERBATIM_PREFIX, drive), rest.to_string())
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(format!("{}{}", prefix, parts.join("\\")))
+}
+
+/// A path without its extended-length prefix, e.g. after canonicalize() on
+/// Windows, so it can be displayed and stored
+pub fn strip_extended(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if let Some(unc) = text.strip_prefix(VERBATIM_UNC_PREFIX) {
+        return PathBuf::from(format!(r"\\{}", unc));
+    }
+    match text.strip_prefix(VERBATIM_PREFIX) {
+        Some(rest) => PathBuf::from(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_length_forms() {
+        assert_eq!(extended_length(r"C:\Books\Tolkien\01.mp3").as_deref(), Some(r"\\?\C:\Books\Tolkien\01.mp3"));
+        assert_eq!(extended_length("d:/Books/./Série/../Кафка/01.mp3").as_deref(), Some(r"\\?\d:\Books\Кафка\01.mp3"));
+        assert_eq!(extended_length(r"\\nas\audio\Books\📚 Box Set").as_deref(), Some(r"\\?\UNC\nas\audio\Books\📚 Box Set"));
+        assert_eq!(extended_length(r"\\?\C:\already"), None);
+        assert_eq!(extended_length(r"Books\relative.mp3"), None);
+        assert_eq!(extended_length("C:relative.mp3"), None);
+
+        assert_eq!(strip_extended(Path::new(r"\\?\C:\Books\01.mp3")), PathBuf::from(r"C:\Books\01.mp3"));
+        assert_eq!(strip_extended(Path::new(r"\\?\UNC\nas\audio")), PathBuf::from(r"\\nas\audio"));
+        assert_eq!(strip_extended(Path::new("/home/me/Books")), PathBuf::from("/home/me/Books"));
+    }
+
+    #[test]
+    fn test_long_path_leaves_short_paths_alone() {
+        let short = Path::new("books/01.mp3");
+        assert_eq!(long_path(short), short.to_path_buf());
+        if !cfg!(windows) {
+            let long = PathBuf::from(format!("/{}", "a".repeat(LONG_PATH_THRESHOLD)));
+            assert_eq!(long_path(&long), long);
+        }
+    }
+}
@@ -1,3 +1,9 @@
+pub mod atomic;
+pub mod backend;
+pub mod fingerprint;
+pub mod long_path;
+pub mod volume;
+
 use std::path::{Path, PathBuf};
 use std::fs;
 use serde::{Deserialize, Serialize};
@@ -6,11 +12,16 @@ use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::default::get_probe;
+use ts_rs::TS;
+
+use long_path::long_path;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct AudioFileInfo {
     pub path: String,
     pub filename: String,
+    #[ts(type = "number")]
     pub size: u64,
     pub extension: String,
     pub metadata: Option<AudioMetadata>,
@@ -18,7 +29,8 @@ pub struct AudioFileInfo {
     pub error_message: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct AudioMetadata {
     pub title: Option<String>,
     pub artist: Option<String>,
@@ -33,7 +45,8 @@ pub struct AudioMetadata {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ScanProgress {
     pub current_file: String,
     pub files_processed: usize,
@@ -42,7 +55,8 @@ pub struct ScanProgress {
     pub errors: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct AudiobookInfo {
     pub title: String,
     pub author: Option<String>,
@@ -52,12 +66,14 @@ pub struct AudiobookInfo {
     pub is_multi_file: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
 pub struct ChapterInfo {
     pub chapter_number: i32,
     pub title: String,
     pub file_path: String,
     pub duration: Option<f64>,
+    #[ts(type = "number")]
     pub file_size: u64,
 }
 
@@ -92,11 +108,12 @@ impl FileSystemScanner {
     }
 
     pub fn scan_directory(&self, directory: &Path) -> Result<Vec<AudioFileInfo>, String> {
-        if !directory.exists() {
+        let io_path = long_path(directory);
+        if !io_path.exists() {
             return Err("Directory does not exist".to_string());
         }
 
-        if !directory.is_dir() {
+        if !io_path.is_dir() {
             return Err("Path is not a directory".to_string());
         }
 
@@ -111,15 +128,16 @@ impl FileSystemScanner {
         directory: &Path,
         audio_files: &mut Vec<AudioFileInfo>,
     ) -> Result<(), String> {
-        let entries = fs::read_dir(directory)
+        let entries = fs::read_dir(long_path(directory))
             .map_err(|e| format!("Failed to read directory {}: {}", directory.display(), e))?;
 
         for entry in entries {
             let entry = entry
                 .map_err(|e| format!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
+            // Joined by hand so the stored path keeps its normal form
+            let path = directory.join(entry.file_name());
 
-            if path.is_dir() {
+            if long_path(&path).is_dir() {
                 // Recursively scan subdirectories
                 self.scan_directory_recursive(&path, audio_files)?;
             } else if self.is_supported_audio_file(&path) {
@@ -144,7 +162,7 @@ impl FileSystemScanner {
             .to_lowercase();
 
         // Get file size
-        let size = fs::metadata(path)
+        let size = fs::metadata(long_path(path))
             .map(|m| m.len())
             .unwrap_or(0);
 
@@ -167,7 +185,7 @@ impl FileSystemScanner {
 
     fn extract_metadata(&self, path: &Path) -> Result<AudioMetadata, String> {
         // Open the media source
-        let src = std::fs::File::open(path)
+        let src = std::fs::File::open(long_path(path))
             .map_err(|e| format!("Failed to open file: {}", e))?;
 
         let mss = MediaSourceStream::new(Box::new(src), Default::default());
@@ -265,18 +283,18 @@ impl FileSystemScanner {
 
         for name in &cover_names {
             let cover_path = directory.join(name);
-            if cover_path.exists() && cover_path.is_file() {
+            if long_path(&cover_path).is_file() {
                 return Some(cover_path);
             }
         }
 
         // If no common names found, look for any image file in the directory
-        if let Ok(entries) = fs::read_dir(directory) {
+        if let Ok(entries) = fs::read_dir(long_path(directory)) {
             let image_extensions = ["jpg", "jpeg", "png", "webp", "gif", "bmp"];
 
             for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
+                let path = directory.join(entry.file_name());
+                if long_path(&path).is_file() {
                     if let Some(extension) = path.extension() {
                         if let Some(ext_str) = extension.to_str() {
                             if image_extensions.contains(&ext_str.to_lowercase().as_str()) {
@@ -292,20 +310,20 @@ impl FileSystemScanner {
     }
 
     pub fn analyze_audiobook_directory(&self, directory: &Path) -> Result<AudiobookInfo, String> {
-        if !directory.exists() || !directory.is_dir() {
+        if !long_path(directory).is_dir() {
             return Err("Path is not a valid directory".to_string());
         }
 
         // Scan for audio files in the directory (non-recursive for audiobooks)
         let mut audio_files = Vec::new();
-        let entries = fs::read_dir(directory)
+        let entries = fs::read_dir(long_path(directory))
             .map_err(|e| format!("Failed to read directory: {}", e))?;
 
         for entry in entries {
             let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
+            let path = directory.join(entry.file_name());
 
-            if path.is_file() && self.is_supported_audio_file(&path) {
+            if self.is_supported_audio_file(&path) && long_path(&path).is_file() {
                 let file_info = self.get_audio_file_info(&path);
                 // Include audio files even if metadata extraction fails
                 // We'll still be able to play them, just won't have metadata initially
@@ -417,7 +435,8 @@ impl FileSystemScanner {
         if lower_name.contains("chapter") {
             // Extract the chapter number if present
             if let Some(ch_pos) = lower_name.find("chapter") {
-                let after_chapter = &name_without_ext[ch_pos + 7..].trim_start();
+                // Lowercasing can change byte lengths outside ASCII, so index the lowercased name
+                let after_chapter = &lower_name[ch_pos + 7..].trim_start();
                 
                 // Look for digits after "chapter"
                 let mut digits = String::new();
@@ -521,4 +540,43 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("does not exist"));
     }
+
+    #[test]
+    fn test_unicode_file_names() {
+        let temp_dir = tempdir().unwrap();
+        let book_dir = temp_dir.path().join("Достоевский – 罪と罰 🎧");
+        fs::create_dir(&book_dir).unwrap();
+        for name in ["01 - Глава первая.mp3", "02 - 第二章.MP3", "03 - İstanbul Chapter 3 🌙.mp3", "cover 封面.jpg"] {
+            fs::write(book_dir.join(name), b"not really audio").unwrap();
+        }
+
+        let scanner = FileSystemScanner::new();
+        let mut files = scanner.scan_directory(temp_dir.path()).unwrap();
+        files.sort_by(|a, b| a.filename.cmp(&b.filename));
+        let names: Vec<&str> = files.iter().map(|file| file.filename.as_str()).collect();
+        assert_eq!(names, vec!["01 - Глава первая.mp3", "02 - 第二章.MP3", "03 - İstanbul Chapter 3 🌙.mp3"]);
+        assert!(files.iter().all(|file| Path::new(&file.path).exists() && file.size == 16));
+
+        let info = scanner.analyze_audiobook_directory(&book_dir).unwrap();
+        assert_eq!(info.title, "Достоевский – 罪と罰 🎧");
+        let titles: Vec<&str> = info.chapters.iter().map(|chapter| chapter.title.as_str()).collect();
+        assert_eq!(titles, vec!["Chapter 01", "Chapter 02", "Chapter 3"]);
+        assert_eq!(scanner.find_cover_art(&book_dir), Some(book_dir.join("cover 封面.jpg")));
+    }
+
+    #[test]
+    fn test_scan_deeply_nested_directory() {
+        let temp_dir = tempdir().unwrap();
+        let mut deep = temp_dir.path().to_path_buf();
+        while deep.as_os_str().len() < 300 {
+            deep.push("Серия книг 📚 Volume");
+        }
+        fs::create_dir_all(long_path::long_path(&deep)).unwrap();
+        fs::write(long_path::long_path(&deep.join("01.mp3")), b"audio").unwrap();
+
+        let files = FileSystemScanner::new().scan_directory(temp_dir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, deep.join("01.mp3").to_string_lossy());
+        assert_eq!(files[0].size, 5);
+    }
 }
\ No newline at end of file
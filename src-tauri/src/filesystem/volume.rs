@@ -0,0 +1,119 @@
+// Libraries on external drives and network shares. A file whose drive is not
+// mounted is not missing: it comes back when the drive does. Whether a path is
+// on such a drive is read from where each platform mounts them: /Volumes on
+// macOS, /media, /run/media and /mnt on Linux, drive letters other than the
+// system drive and \\server\share paths on Windows.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The mount point of the external drive or share `path` is on, or None when
+/// it is on the system drive
+pub fn mount_root(path: &Path) -> Option<PathBuf> {
+    mount_root_of(&path.to_string_lossy(), &system_drive()).map(PathBuf::from)
+}
+
+/// Whether `path` is on an external drive or share that is not mounted now
+pub fn is_offline(path: &Path) -> bool {
+    mount_root(path).is_some_and(|root| !is_mounted(&root))
+}
+
+/// A drive that is not mounted leaves either nothing or an empty folder at its
+/// mount point. The empty folder is only taken for an unmounted drive when it
+/// is on the same device as its parent, so an empty folder that really is
+/// mounted still counts as online.
+pub fn is_mounted(root: &Path) -> bool {
+    let Ok(mut entries) = fs::read_dir(root) else {
+        return false;
+    };
+    if entries.next().is_some() {
+        return true;
+    }
+    !same_device_as_parent(root)
+}
+
+#[cfg(unix)]
+fn same_device_as_parent(root: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Some(parent) = root.parent() else {
+        return false;
+    };
+    match (fs::metadata(root), fs::metadata(parent)) {
+        (Ok(root), Ok(parent)) => root.dev() == parent.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_device_as_parent(_root: &Path) -> bool {
+    false
+}
+
+fn system_drive() -> String {
+    std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string())
+}
+
+fn mount_root_of(path: &str, system_drive: &str) -> Option<String> {
+    // \\server\share\... and drive letters, with either separator
+    let windows = path.replace('/', "\\");
+    if let Some(unc) = windows.strip_prefix(r"\\") {
+        if unc.starts_with(r"?\") || unc.starts_with(r".\") {
+            return None;
+        }
+        let mut parts = unc.split('\\').filter(|part| !part.is_empty());
+        let (server, share) = (parts.next()?, parts.next()?);
+        return Some(format!(r"\\{}\{}", server, share));
+    }
+    let mut chars = windows.chars();
+    if let (Some(drive), Some(':')) = (chars.next(), chars.next()) {
+        if !drive.is_ascii_alphabetic() || system_drive.to_ascii_uppercase().starts_with(drive.to_ascii_uppercase()) {
+            return None;
+        }
+        return Some(format!("{}:\\", drive.to_ascii_uppercase()));
+    }
+
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    let depth = match parts.as_slice() {
+        ["Volumes", ..] | ["mnt", ..] => 2,
+        ["media", ..] => 3,
+        ["run", "media", ..] => 4,
+        _ => return None,
+    };
+    (parts.len() >= depth).then(|| format!("/{}", parts[..depth].join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_root_per_platform() {
+        let root = |path: &str| mount_root_of(path, "C:");
+        assert_eq!(root("/Volumes/Books/Tolkien/hobbit.m4b").as_deref(), Some("/Volumes/Books"));
+        assert_eq!(root("/media/anna/USB STICK/book.mp3").as_deref(), Some("/media/anna/USB STICK"));
+        assert_eq!(root("/run/media/anna/disk/book.mp3").as_deref(), Some("/run/media/anna/disk"));
+        assert_eq!(root("/mnt/nas/audiobooks").as_deref(), Some("/mnt/nas"));
+        assert_eq!(root("/home/anna/Audiobooks/book.mp3"), None);
+        assert_eq!(root("/media/anna"), None);
+
+        assert_eq!(root(r"E:\Audiobooks\book.mp3").as_deref(), Some(r"E:\"));
+        assert_eq!(root("e:/Audiobooks").as_deref(), Some(r"E:\"));
+        assert_eq!(root(r"C:\Users\anna\book.mp3"), None);
+        assert_eq!(root(r"\\nas\media\Audiobooks\book.mp3").as_deref(), Some(r"\\nas\media"));
+        assert_eq!(root(r"\\?\C:\Users\anna\book.mp3"), None);
+    }
+
+    #[test]
+    fn test_is_mounted() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_mounted(&dir.path().join("USB STICK")), "mount point removed");
+
+        // An empty folder on the same device is a mount point with nothing mounted
+        let empty = dir.path().join("nas");
+        fs::create_dir(&empty).unwrap();
+        assert!(!is_mounted(&empty));
+
+        fs::write(empty.join("book.mp3"), b"x").unwrap();
+        assert!(is_mounted(&empty));
+    }
+}
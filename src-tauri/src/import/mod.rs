@@ -0,0 +1,148 @@
+// Steps every way of adding a book shares once its records exist: finding its
+// cover, fingerprinting its files, linking its author and narrator, and queueing
+// a full decode of its files. Folder imports go through here from both the
+// commands and the command line.
+
+use crate::covers;
+use crate::database::models::{Audiobook, CreateAudiobookDto, CreateChapterDto};
+use crate::database::repository::{AudiobookRepository, ChapterRepository};
+use crate::events::{self, AppEvent, LibraryChange};
+use crate::filesystem::FileSystemScanner;
+use crate::services::{audiobook_source_service, AuthorService, FileValidationService, ImportPreview, ImportPreviewSource, LibraryRootService, NarratorService, RelocationService};
+use std::path::Path;
+use std::sync::OnceLock;
+
+// Books waiting to have their files decoded in full after import
+static VALIDATION_QUEUE: OnceLock<tokio::sync::mpsc::UnboundedSender<String>> = OnceLock::new();
+
+pub fn queue_validation(audiobook_id: &str) {
+    if let Some(sender) = VALIDATION_QUEUE.get() {
+        let _ = sender.send(audiobook_id.to_string());
+    }
+}
+
+/// Validate imported books one at a time; a full decode of a long book keeps a
+/// core busy for a while, so imports are not held up by it
+pub fn start_validator(pool: sqlx::SqlitePool) {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if VALIDATION_QUEUE.set(sender).is_err() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(audiobook_id) = receiver.recv().await {
+            match FileValidationService::new(&pool).validate_audiobook(&audiobook_id).await {
+                Ok(validation) => {
+                    if validation.unplayable > 0 || validation.warnings > 0 {
+                        println!(
+                            "🩺 VALIDATION: {} has {} unplayable files and {} with warnings",
+                            audiobook_id, validation.unplayable, validation.warnings
+                        );
+                    }
+                    events::emit(AppEvent::AudiobookValidated(validation));
+                }
+                Err(e) => log::warn!("Failed to validate audiobook {}: {}", audiobook_id, e),
+            }
+        }
+    });
+}
+
+/// Point an audiobook at the author and narrator entities matching its text fields
+pub async fn link_people(pool: &sqlx::SqlitePool, audiobook_id: &str) {
+    let audiobook = match AudiobookRepository::new(pool).find_by_id(audiobook_id).await {
+        Ok(Some(audiobook)) => audiobook,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("Failed to load audiobook {} for author linking: {}", audiobook_id, e);
+            return;
+        }
+    };
+    if let Err(e) = AuthorService::new(pool).link_audiobook(audiobook_id, audiobook.author.as_deref()).await {
+        log::warn!("Failed to link author for audiobook {}: {}", audiobook_id, e);
+    }
+    if let Err(e) = NarratorService::new(pool).link_audiobook(audiobook_id, audiobook.narrator.as_deref()).await {
+        log::warn!("Failed to link narrator for audiobook {}: {}", audiobook_id, e);
+    }
+}
+
+/// Fingerprint a freshly imported audiobook; failures only cost the ability to auto-relocate
+pub async fn record_fingerprints(pool: &sqlx::SqlitePool, audiobook_id: &str) {
+    if let Err(e) = RelocationService::new(pool).record_audiobook_fingerprints(audiobook_id).await {
+        log::warn!("Failed to fingerprint audiobook {}: {}", audiobook_id, e);
+    }
+}
+
+/// Accept file paths under the folder of `path` from now on. Only for paths
+/// the user chose in a file dialog or on the command line.
+pub async fn add_library_root(pool: &sqlx::SqlitePool, path: &Path) {
+    if let Err(e) = LibraryRootService::new(pool).add(path).await {
+        log::warn!("Failed to add library root {}: {}", path.display(), e);
+    }
+}
+
+/// Analyze a directory as a single audiobook and create its audiobook and chapter records
+pub async fn import_directory(pool: &sqlx::SqlitePool, directory: &Path) -> Result<Audiobook, String> {
+    let scanner = FileSystemScanner::new();
+
+    // Analyze the directory for audiobook structure
+    let audiobook_info = scanner.analyze_audiobook_directory(directory)
+        .map_err(|e| format!("Failed to analyze directory: {}", e))?;
+
+    import_preview(pool, ImportPreview::from_directory(&audiobook_info)).await
+}
+
+/// Create the book a folder import preview describes, as previewed
+pub async fn import_preview(pool: &sqlx::SqlitePool, preview: ImportPreview) -> Result<Audiobook, String> {
+    let ImportPreviewSource::Directory { directory_path } = &preview.source else {
+        return Err("Not a folder import".to_string());
+    };
+
+    // Create audiobook record
+    let audiobook_dto = CreateAudiobookDto {
+        title: preview.title.clone(),
+        author: preview.author.clone(),
+        narrator: preview.narrator.clone(),
+        description: preview.description.clone(),
+        genre: preview.genre.clone(),
+        file_path: directory_path.clone(),
+        duration: preview.duration,
+        cover_image_path: None, // Filled in from embedded or folder art below
+        source_type: Some(audiobook_source_service::SOURCE_LOCAL.to_string()),
+        source_id: Some(directory_path.clone()),
+    };
+
+    let audiobook_repo = AudiobookRepository::new(pool);
+    let mut audiobook = audiobook_repo.create(audiobook_dto).await
+        .map_err(|e| format!("Failed to create audiobook: {}", e))?;
+
+    // Create chapter records if this is a multi-file audiobook
+    if preview.chapters.len() > 1 {
+        let chapter_dtos: Vec<CreateChapterDto> = preview.chapters.iter()
+            .map(|ch| CreateChapterDto {
+                audiobook_id: audiobook.id.clone(),
+                chapter_number: ch.chapter_number,
+                title: ch.title.clone(),
+                file_path: ch.file.clone(),
+                duration: ch.duration,
+                file_size: ch.file_size,
+            })
+            .collect();
+
+        let chapter_repo = ChapterRepository::new(pool);
+        chapter_repo.create_multiple(chapter_dtos).await
+            .map_err(|e| format!("Failed to create chapters: {}", e))?;
+
+        // The chapters count and duration were totalled as the chapters went in
+        audiobook = audiobook_repo.find_by_id(&audiobook.id).await
+            .map_err(|e| e.to_string())?
+            .ok_or("Audiobook not found")?;
+    }
+
+    covers::apply_local_cover(pool, &mut audiobook).await;
+    record_fingerprints(pool, &audiobook.id).await;
+    link_people(pool, &audiobook.id).await;
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    queue_validation(&audiobook.id);
+
+    Ok(audiobook)
+}
@@ -0,0 +1,213 @@
+// Watched inbox folder. Documents dropped into it are converted to TTS
+// audiobooks with the default voice, then moved to an archive subfolder so they
+// are not picked up again. Files that fail go to a separate subfolder instead.
+
+use crate::database::repository::PreferencesRepository;
+use crate::events::{self, AppEvent};
+use crate::services::TtsChapterService;
+use crate::tts;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use ts_rs::TS;
+
+pub const PREF_INBOX_FOLDER: &str = "inbox.folder";
+pub const POLL_INTERVAL: Duration = Duration::from_secs(10);
+pub const ARCHIVE_DIR: &str = "archive";
+pub const FAILED_DIR: &str = "failed";
+/// A file modified more recently than this may still be copying in
+const SETTLE_TIME: Duration = Duration::from_secs(5);
+const SUPPORTED_EXTENSIONS: [&str; 4] = ["txt", "text", "epub", "pdf"];
+
+/// Payload of the `inbox-file-processed` and `inbox-file-failed` events
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct InboxFileEvent {
+    pub file_path: String,
+    /// Where the file was moved after processing
+    pub archived_path: Option<String>,
+    pub audiobook_id: Option<String>,
+    pub title: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Supported documents in the inbox that have finished arriving, oldest first
+pub fn ready_files(inbox: &Path, now: SystemTime) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(inbox).context("Failed to read inbox folder")? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || !is_supported(&path) {
+            continue;
+        }
+
+        let modified = metadata.modified().unwrap_or(now);
+        if now.duration_since(modified).unwrap_or_default() < SETTLE_TIME {
+            continue;
+        }
+        files.push((modified, path));
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+fn is_supported(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.') || name.starts_with('~'));
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    !hidden && extension.is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Move a processed file into `subfolder` of the inbox without overwriting an
+/// earlier file of the same name
+pub fn move_to(path: &Path, inbox: &Path, subfolder: &str) -> Result<PathBuf> {
+    let dir = inbox.join(subfolder);
+    std::fs::create_dir_all(&dir).context("Failed to create inbox archive folder")?;
+
+    let file_name = path.file_name().context("Inbox file has no name")?;
+    let mut target = dir.join(file_name);
+    let mut copy = 2;
+    while target.exists() {
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("file");
+        let name = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => format!("{} ({}).{}", stem, copy, ext),
+            None => format!("{} ({})", stem, copy),
+        };
+        target = dir.join(name);
+        copy += 1;
+    }
+
+    std::fs::rename(path, &target).context("Failed to move inbox file")?;
+    Ok(target)
+}
+
+/// Poll the inbox folder and convert each document dropped there into a TTS
+/// audiobook, one at a time. The folder is read from preferences on every poll so
+/// changing or clearing it takes effect without a restart.
+pub fn start_watcher(pool: sqlx::SqlitePool) {
+    static STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+    if STARTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let folder = PreferencesRepository::new(&pool).get(PREF_INBOX_FOLDER).await.ok().flatten();
+            let Some(folder) = folder.map(PathBuf::from).filter(|folder| folder.is_dir()) else {
+                continue;
+            };
+            let files = match ready_files(&folder, SystemTime::now()) {
+                Ok(files) => files,
+                Err(e) => {
+                    log::warn!("INBOX: Failed to scan {}: {}", folder.display(), e);
+                    continue;
+                }
+            };
+
+            for file in files {
+                let event = process_inbox_file(&pool, &folder, &file).await;
+                events::emit(if event.error.is_some() {
+                    AppEvent::InboxFileFailed(event)
+                } else {
+                    AppEvent::InboxFileProcessed(event)
+                });
+            }
+        }
+    });
+}
+
+async fn process_inbox_file(
+    pool: &sqlx::SqlitePool,
+    folder: &Path,
+    file: &Path,
+) -> InboxFileEvent {
+    let file_path = file.to_string_lossy().to_string();
+    println!("📥 INBOX: Converting {}", file_path);
+
+    let mut event = InboxFileEvent {
+        file_path: file_path.clone(),
+        archived_path: None,
+        audiobook_id: None,
+        title: None,
+        error: None,
+    };
+    let result = async {
+        let (document, _) = tts::library::process_document(pool, &file_path, None, None, None).await?;
+        event.title = Some(document.title.clone());
+
+        let chapters = serde_json::to_value(&document.chapters).map_err(|e| e.to_string())?;
+        let chapters = chapters.as_array().cloned().unwrap_or_default();
+        let audiobook = tts::library::create_audiobook_record(
+            pool,
+            document.title.clone(),
+            document.author.clone(),
+            chapters,
+            Some(tts::DEFAULT_VOICE.to_string()),
+            document.document_id.clone(),
+        ).await?;
+        event.audiobook_id = Some(audiobook.id.clone());
+
+        TtsChapterService::new(pool).synthesize_book(&audiobook.id).await.map_err(|e| format!("{:#}", e))
+    }.await;
+
+    // Failed files are set aside too, or every poll would retry them. A book whose
+    // synthesis failed part-way is kept; its chapters can be regenerated one by one.
+    let subfolder = match &result {
+        Ok(chapters) => {
+            println!("📥 INBOX: Converted {} ({} chapters)", file_path, chapters);
+            ARCHIVE_DIR
+        }
+        Err(e) => {
+            println!("INBOX: Failed to convert {}: {}", file_path, e);
+            event.error = Some(e.clone());
+            FAILED_DIR
+        }
+    };
+    match move_to(file, folder, subfolder) {
+        Ok(moved) => event.archived_path = Some(moved.to_string_lossy().to_string()),
+        Err(e) => log::warn!("INBOX: Failed to move {} out of the inbox: {}", file_path, e),
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_files_and_archiving() {
+        let inbox = tempfile::tempdir().unwrap();
+        for name in ["book.txt", "Paper.PDF", ".hidden.txt", "notes.docx"] {
+            std::fs::write(inbox.path().join(name), "text").unwrap();
+        }
+        std::fs::create_dir(inbox.path().join("folder.txt")).unwrap();
+
+        // Everything was just written, so nothing has settled yet
+        assert!(ready_files(inbox.path(), SystemTime::now()).unwrap().is_empty());
+
+        let later = SystemTime::now() + SETTLE_TIME * 2;
+        let mut names: Vec<String> = ready_files(inbox.path(), later)
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["Paper.PDF", "book.txt"]);
+
+        let first = move_to(&inbox.path().join("book.txt"), inbox.path(), ARCHIVE_DIR).unwrap();
+        std::fs::write(inbox.path().join("book.txt"), "again").unwrap();
+        let second = move_to(&inbox.path().join("book.txt"), inbox.path(), ARCHIVE_DIR).unwrap();
+        assert_eq!(first, inbox.path().join(ARCHIVE_DIR).join("book.txt"));
+        assert_eq!(second, inbox.path().join(ARCHIVE_DIR).join("book (2).txt"));
+        assert!(!inbox.path().join("book.txt").exists());
+    }
+}
@@ -2,5 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Subcommands like `audiovibe import <dir>` run headless and exit
+    if let Some(code) = audiovibe_desktop_lib::run_cli() {
+        std::process::exit(code);
+    }
     audiovibe_desktop_lib::run()
 }
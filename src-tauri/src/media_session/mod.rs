@@ -0,0 +1,132 @@
+// The OS media session: MPRIS on Linux, the system media transport controls on
+// Windows and Now Playing on macOS. Bluetooth AVRCP is fed from these, so
+// headphones and car units show the book and chapter and their buttons work.
+// The controls live on a thread of their own and are updated over a channel.
+
+use anyhow::{anyhow, Result};
+use souvlaki::{MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig, SeekDirection};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+mod playback;
+mod state;
+
+pub use playback::start;
+pub use state::NowPlaying;
+use state::{SessionState, SessionUpdate};
+
+/// How often playback is polled for changes, chapter changes within a file included
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Seek step for devices that only send a direction
+const SEEK_STEP_SECONDS: i64 = 30;
+
+/// Buttons pressed on headphones, car units or the OS media overlay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaKey {
+    Play,
+    Pause,
+    Toggle,
+    Stop,
+    Next,
+    Previous,
+    /// Seconds, negative to go back
+    SeekBy(i64),
+    /// Seconds into the chapter
+    SetPosition(u64),
+}
+
+fn media_key(event: MediaControlEvent) -> Option<MediaKey> {
+    let seconds = |direction: SeekDirection, amount: i64| match direction {
+        SeekDirection::Forward => amount,
+        SeekDirection::Backward => -amount,
+    };
+    Some(match event {
+        MediaControlEvent::Play => MediaKey::Play,
+        MediaControlEvent::Pause => MediaKey::Pause,
+        MediaControlEvent::Toggle => MediaKey::Toggle,
+        MediaControlEvent::Stop => MediaKey::Stop,
+        MediaControlEvent::Next => MediaKey::Next,
+        MediaControlEvent::Previous => MediaKey::Previous,
+        MediaControlEvent::Seek(direction) => MediaKey::SeekBy(seconds(direction, SEEK_STEP_SECONDS)),
+        MediaControlEvent::SeekBy(direction, amount) => MediaKey::SeekBy(seconds(direction, amount.as_secs() as i64)),
+        MediaControlEvent::SetPosition(MediaPosition(position)) => MediaKey::SetPosition(position.as_secs()),
+        _ => return None,
+    })
+}
+
+pub struct MediaSession {
+    sender: mpsc::Sender<Option<NowPlaying>>,
+}
+
+impl MediaSession {
+    /// Register with the OS. Windows needs the handle of the main window.
+    pub fn start(hwnd: Option<*mut std::ffi::c_void>, on_key: impl Fn(MediaKey) + Send + 'static) -> Result<Self> {
+        // Raw pointers cannot cross threads; the handle is only passed on to the OS
+        let hwnd = hwnd.map(|hwnd| hwnd as usize);
+        let (sender, receiver) = mpsc::channel::<Option<NowPlaying>>();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let config = PlatformConfig {
+                display_name: "AudioVibe",
+                dbus_name: "audiovibe",
+                hwnd: hwnd.map(|hwnd| hwnd as *mut std::ffi::c_void),
+            };
+            let controls = MediaControls::new(config).map_err(|e| anyhow!("{:?}", e)).and_then(|mut controls| {
+                controls.attach(move |event| {
+                    if let Some(key) = media_key(event) {
+                        on_key(key);
+                    }
+                })
+                .map_err(|e| anyhow!("{:?}", e))?;
+                Ok(controls)
+            });
+            let mut controls = match controls {
+                Ok(controls) => {
+                    let _ = ready_sender.send(Ok(()));
+                    controls
+                }
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+            };
+
+            let mut state = SessionState::new();
+            while let Ok(now_playing) = receiver.recv() {
+                for update in state.next(now_playing, Instant::now()) {
+                    if let Err(e) = apply(&mut controls, update) {
+                        log::warn!("MEDIA SESSION: Failed to update: {}", e);
+                    }
+                }
+            }
+        });
+
+        ready_receiver.recv().map_err(|_| anyhow!("Media session thread exited"))??;
+        log::info!("MEDIA SESSION: Registered with the OS");
+        Ok(Self { sender })
+    }
+
+    /// What is playing now, or None when stopped; cheap to call on every poll
+    pub fn update(&self, now_playing: Option<NowPlaying>) {
+        let _ = self.sender.send(now_playing);
+    }
+}
+
+fn apply(controls: &mut MediaControls, update: SessionUpdate) -> Result<()> {
+    match update {
+        SessionUpdate::Metadata(now_playing) => controls.set_metadata(MediaMetadata {
+            title: Some(&now_playing.title),
+            album: now_playing.album.as_deref(),
+            artist: now_playing.artist.as_deref(),
+            cover_url: now_playing.cover_url.as_deref(),
+            duration: now_playing.duration.map(Duration::from_secs),
+        }),
+        SessionUpdate::Playback { playing, position } => {
+            let progress = Some(MediaPosition(Duration::from_secs(position)));
+            controls.set_playback(if playing { MediaPlayback::Playing { progress } } else { MediaPlayback::Paused { progress } })
+        }
+        SessionUpdate::Stopped => controls.set_playback(MediaPlayback::Stopped),
+    }
+    .map_err(|e| anyhow!("{:?}", e))
+}
@@ -0,0 +1,171 @@
+// Feeding the media session from the player: what is playing, polled from the
+// audio thread, and media keys turned into audio thread or cast commands.
+
+use super::{MediaKey, MediaSession, NowPlaying, POLL_INTERVAL};
+use crate::audio::thread::{self, AudioCommand};
+use crate::audio::{books, PlaybackState};
+use crate::casting::session::{self as cast_session, CastControl};
+use crate::database::models::Audiobook;
+use crate::database::repository::AudiobookRepository;
+use std::sync::mpsc;
+
+/// Register with the OS media session and keep it told what is playing, so
+/// Bluetooth devices show the chapter and book and follow chapter changes
+pub fn start(app: &tauri::AppHandle, pool: sqlx::SqlitePool) {
+    static STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+    if STARTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+
+    let key_pool = pool.clone();
+    let on_key = move |key| {
+        let pool = key_pool.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = handle_media_key(&pool, key).await {
+                log::warn!("MEDIA SESSION: Failed to handle {:?}: {}", key, e);
+            }
+        });
+    };
+    let session = match MediaSession::start(main_window_handle(app), on_key) {
+        Ok(session) => session,
+        Err(e) => {
+            log::warn!("MEDIA SESSION: Not available: {}", e);
+            return;
+        }
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut book: Option<Audiobook> = None;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            session.update(now_playing(&pool, &mut book).await);
+        }
+    });
+}
+
+#[cfg(windows)]
+fn main_window_handle(app: &tauri::AppHandle) -> Option<*mut std::ffi::c_void> {
+    use tauri::Manager;
+    let window = app.get_webview_window("main")?;
+    window.hwnd().ok().map(|hwnd| hwnd.0 as *mut std::ffi::c_void)
+}
+
+#[cfg(not(windows))]
+fn main_window_handle(_app: &tauri::AppHandle) -> Option<*mut std::ffi::c_void> {
+    None
+}
+
+// What the media session should show; `book` caches the last book looked up
+async fn now_playing(pool: &sqlx::SqlitePool, book: &mut Option<Audiobook>) -> Option<NowPlaying> {
+    let status = thread::status(&thread::running_sender()?).ok()?;
+    if matches!(status.state, PlaybackState::Stopped) {
+        return None;
+    }
+    let file_path = status.current_file?;
+    let playing = matches!(status.state, PlaybackState::Playing);
+
+    let chapter = match books::playing_book_layout(pool, &file_path).await {
+        Some(layout) => layout.position(&file_path, status.position),
+        None => None,
+    };
+    let Some(chapter) = chapter else {
+        let title = std::path::Path::new(&file_path).file_stem().map(|stem| stem.to_string_lossy().to_string());
+        return Some(NowPlaying {
+            title: title.unwrap_or(file_path),
+            album: None,
+            artist: None,
+            cover_url: None,
+            duration: status.duration,
+            position: status.position,
+            playing,
+        });
+    };
+
+    if book.as_ref().is_none_or(|book| book.id != chapter.audiobook_id) {
+        *book = AudiobookRepository::new(pool).find_by_id(&chapter.audiobook_id).await.ok().flatten();
+    }
+    let book = book.as_ref();
+    Some(NowPlaying {
+        title: chapter.title,
+        album: book.map(|book| book.title.clone()),
+        artist: book.and_then(|book| book.author.clone()),
+        cover_url: book.and_then(|book| media_cover_url(book.cover_image_path.as_deref()?)),
+        duration: chapter.chapter_duration,
+        position: chapter.chapter_position,
+        playing,
+    })
+}
+
+/// A cover the OS can load itself: remote URLs as they are, local files as file:// URLs
+fn media_cover_url(cover: &str) -> Option<String> {
+    if cover.starts_with("http://") || cover.starts_with("https://") {
+        return Some(cover.to_string());
+    }
+    let path = std::path::Path::new(cover);
+    if !path.is_file() {
+        return None;
+    }
+    let path = cover.replace('\\', "/");
+    Some(if path.starts_with('/') { format!("file://{}", path) } else { format!("file:///{}", path) })
+}
+
+// Buttons on headphones and car units. Positions are within the chapter, as
+// reported to the OS; next and previous move between chapters of the book.
+async fn handle_media_key(pool: &sqlx::SqlitePool, key: MediaKey) -> Result<(), String> {
+    if cast_session::is_active().await {
+        let cast_playing = matches!(key, MediaKey::Toggle) && cast_session::status().await.is_some_and(|cast| cast.playing);
+        let control = match key {
+            MediaKey::Play => CastControl::Play,
+            MediaKey::Pause => CastControl::Pause,
+            MediaKey::Toggle if cast_playing => CastControl::Pause,
+            MediaKey::Toggle => CastControl::Play,
+            MediaKey::Stop => CastControl::Stop,
+            _ => return Ok(()),
+        };
+        return cast_session::control(control).await.unwrap_or(Ok(())).map_err(|e| e.to_string());
+    }
+
+    let sender = thread::running_sender().ok_or("Audio is not running")?;
+    let status = thread::status(&sender)?;
+    let playing = matches!(status.state, PlaybackState::Playing);
+    let chapter = match status.current_file.as_deref() {
+        Some(file_path) => books::playing_book_layout(pool, file_path).await.map(|layout| (layout.position(file_path, status.position), layout)),
+        None => None,
+    };
+
+    let (response_sender, response_receiver) = mpsc::channel();
+    let command = match key {
+        MediaKey::Play => AudioCommand::Play { response: response_sender },
+        MediaKey::Pause => AudioCommand::Pause { response: response_sender },
+        MediaKey::Toggle if playing => AudioCommand::Pause { response: response_sender },
+        MediaKey::Toggle => AudioCommand::Play { response: response_sender },
+        MediaKey::Stop => AudioCommand::Stop { response: response_sender },
+        MediaKey::SeekBy(seconds) => {
+            let position = (status.position as i64 + seconds).max(0);
+            AudioCommand::Seek { position: position as f32, response: response_sender }
+        }
+        MediaKey::SetPosition(seconds) => {
+            let chapter_position = chapter.as_ref().and_then(|(position, _)| position.as_ref()).map_or(0, |position| position.chapter_position);
+            let file_offset = status.position.saturating_sub(chapter_position);
+            AudioCommand::Seek { position: (file_offset + seconds) as f32, response: response_sender }
+        }
+        MediaKey::Next | MediaKey::Previous => {
+            let Some((Some(position), layout)) = chapter else {
+                return Ok(());
+            };
+            // Previous goes back to the start of the chapter first, like a CD player
+            let index = match key {
+                MediaKey::Next => position.index + 1,
+                _ if position.chapter_position > 3 => position.index,
+                _ => position.index.saturating_sub(1),
+            };
+            if index >= position.count {
+                return Ok(());
+            }
+            let start = layout.chapter_start(index).ok_or("Chapter start is unknown")?;
+            return books::seek_book(pool, &sender, status, start).await.map(|_| ());
+        }
+    };
+    sender.send(command).map_err(|e| format!("Failed to send media key command: {}", e))?;
+    response_receiver.recv().map_err(|e| format!("Failed to receive response: {}", e))?
+}
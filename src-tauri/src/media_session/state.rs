@@ -0,0 +1,122 @@
+// Decides what to tell the OS media session as playback is polled. Metadata
+// goes out when the book or chapter changes; the position only on play/pause
+// and seeks, since the OS moves it along by itself in between.
+
+use std::time::Instant;
+
+/// A position this far from where the OS thinks playback is counts as a seek
+const DRIFT_SECONDS: u64 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NowPlaying {
+    /// The chapter, which is what headphones and car units show first
+    pub title: String,
+    /// The book
+    pub album: Option<String>,
+    pub artist: Option<String>,
+    pub cover_url: Option<String>,
+    /// Of the chapter, in seconds
+    pub duration: Option<u64>,
+    /// Into the chapter, in seconds
+    pub position: u64,
+    pub playing: bool,
+}
+
+impl NowPlaying {
+    fn same_metadata(&self, other: &NowPlaying) -> bool {
+        self.title == other.title
+            && self.album == other.album
+            && self.artist == other.artist
+            && self.cover_url == other.cover_url
+            && self.duration == other.duration
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionUpdate {
+    Metadata(NowPlaying),
+    Playback { playing: bool, position: u64 },
+    Stopped,
+}
+
+#[derive(Debug, Default)]
+pub struct SessionState {
+    /// What was last pushed, and when
+    pushed: Option<(NowPlaying, Instant)>,
+}
+
+impl SessionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The updates that bring the OS in line with `now_playing`
+    pub fn next(&mut self, now_playing: Option<NowPlaying>, now: Instant) -> Vec<SessionUpdate> {
+        let Some(current) = now_playing else {
+            return match self.pushed.take() {
+                Some(_) => vec![SessionUpdate::Stopped],
+                None => Vec::new(),
+            };
+        };
+
+        let mut updates = Vec::new();
+        let playback = SessionUpdate::Playback { playing: current.playing, position: current.position };
+        match &self.pushed {
+            Some((pushed, pushed_at)) if pushed.same_metadata(&current) => {
+                let elapsed = if pushed.playing { now.duration_since(*pushed_at).as_secs() } else { 0 };
+                let expected = pushed.position + elapsed;
+                if pushed.playing != current.playing || current.position.abs_diff(expected) > DRIFT_SECONDS {
+                    updates.push(playback);
+                }
+            }
+            _ => {
+                updates.push(SessionUpdate::Metadata(current.clone()));
+                updates.push(playback);
+            }
+        }
+        if !updates.is_empty() {
+            self.pushed = Some((current, now));
+        }
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn chapter(title: &str, position: u64, playing: bool) -> NowPlaying {
+        NowPlaying {
+            title: title.to_string(),
+            album: Some("Dune".to_string()),
+            artist: Some("Frank Herbert".to_string()),
+            cover_url: None,
+            duration: Some(1800),
+            position,
+            playing,
+        }
+    }
+
+    #[test]
+    fn test_pushes_metadata_on_chapter_changes_and_position_on_seeks() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut state = SessionState::new();
+
+        let updates = state.next(Some(chapter("Chapter 1", 0, true)), at(0));
+        assert!(matches!(updates.as_slice(), [SessionUpdate::Metadata(_), SessionUpdate::Playback { playing: true, position: 0 }]));
+
+        assert!(state.next(Some(chapter("Chapter 1", 10, true)), at(10)).is_empty(), "the OS keeps time itself");
+        assert_eq!(state.next(Some(chapter("Chapter 1", 300, true)), at(11)), vec![SessionUpdate::Playback { playing: true, position: 300 }]);
+        assert_eq!(state.next(Some(chapter("Chapter 1", 305, false)), at(16)), vec![SessionUpdate::Playback { playing: false, position: 305 }]);
+        assert!(state.next(Some(chapter("Chapter 1", 305, false)), at(60)).is_empty(), "paused time does not move");
+
+        // Mid-file chapter change in a single-file book
+        let updates = state.next(Some(chapter("Chapter 2", 0, true)), at(61));
+        assert!(matches!(&updates[0], SessionUpdate::Metadata(now_playing) if now_playing.title == "Chapter 2"));
+
+        assert_eq!(state.next(None, at(62)), vec![SessionUpdate::Stopped]);
+        assert!(state.next(None, at(63)).is_empty());
+    }
+}
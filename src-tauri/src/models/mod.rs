@@ -1,6 +1,10 @@
 // Data models for AudioVibe application
 
+use crate::audio::metrics::AudioMetrics;
+use crate::audio::output::OutputDiagnostics;
+use crate::database::SchemaVersion;
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 // use chrono::{DateTime, Utc}; // Will be used in future tasks
 
 #[allow(dead_code)]
@@ -25,18 +29,65 @@ pub struct PlaybackState {
     pub is_playing: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
 pub struct AppConfig {
     pub version: String,
     pub initialized: bool,
     pub app_name: String,
     pub build_date: String,
+    /// False until background warm-up finishes and `init-complete` is emitted
+    #[serde(default)]
+    pub warm_up_complete: bool,
+    /// What was playing last, so the player can be restored before the library loads
+    #[serde(default)]
+    pub last_playback: Option<LastPlaybackSnapshot>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct LastPlaybackSnapshot {
+    pub audiobook: crate::database::models::Audiobook,
+    pub progress: crate::database::models::PlaybackProgress,
+    /// The frontend's saved player state for this book, as stored by save_playback_state
+    pub state_data: Option<String>,
+}
+
+/// Payload of the `init-complete` event
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct WarmUpReport {
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    pub fingerprints_backfilled: usize,
+    pub authors_linked: usize,
+    pub narrators_linked: usize,
+    pub sources_backfilled: usize,
+    pub incomplete_imports: usize,
+    /// Release alerts raised while the app was closed or not yet dismissed
+    pub release_alerts: usize,
+    /// Unfinished temporary files left by writes a crash cut short
+    pub temp_files_removed: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
 pub struct SystemInfo {
     pub platform: String,
     pub arch: String,
     pub version: String,
     pub tauri_version: String,
+}
+
+/// Everything support asks for about a playback problem, in one file
+#[derive(Debug, Serialize, Deserialize, Clone, TS)]
+#[ts(export)]
+pub struct DiagnosticsBundle {
+    pub generated_at: String,
+    pub system: SystemInfo,
+    /// None before the database is initialized
+    pub schema: Option<SchemaVersion>,
+    pub output: OutputDiagnostics,
+    pub metrics: AudioMetrics,
 }
\ No newline at end of file
@@ -0,0 +1,124 @@
+// Keeps the system from going to sleep while audio is playing. Windows uses the
+// execution state API on the calling thread; macOS and Linux hold a helper
+// process (caffeinate / systemd-inhibit) for as long as the inhibitor is active.
+
+#[cfg(not(windows))]
+use std::process::{Child, Command, Stdio};
+
+#[derive(Debug, Default)]
+pub struct SleepInhibitor {
+    #[cfg(windows)]
+    active: bool,
+    #[cfg(not(windows))]
+    child: Option<Child>,
+    /// Set once the helper fails to start, so playback does not retry it on every poll
+    #[cfg(not(windows))]
+    unavailable: bool,
+}
+
+impl SleepInhibitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        #[cfg(windows)]
+        {
+            self.active
+        }
+        #[cfg(not(windows))]
+        {
+            self.child.is_some()
+        }
+    }
+
+    /// Hold or release the inhibitor; cheap to call repeatedly with the same value
+    pub fn set_active(&mut self, active: bool) {
+        if active == self.is_active() {
+            return;
+        }
+        if active {
+            self.acquire();
+        } else {
+            self.release();
+        }
+    }
+
+    #[cfg(windows)]
+    fn acquire(&mut self) {
+        // Applies to the calling thread until it is reset or the thread exits
+        if unsafe { windows::SetThreadExecutionState(windows::ES_CONTINUOUS | windows::ES_SYSTEM_REQUIRED) } == 0 {
+            log::warn!("POWER: Failed to prevent system sleep");
+            return;
+        }
+        self.active = true;
+        log::info!("POWER: Preventing system sleep during playback");
+    }
+
+    #[cfg(windows)]
+    fn release(&mut self) {
+        unsafe { windows::SetThreadExecutionState(windows::ES_CONTINUOUS) };
+        self.active = false;
+        log::info!("POWER: Allowing system sleep");
+    }
+
+    #[cfg(not(windows))]
+    fn acquire(&mut self) {
+        if self.unavailable {
+            return;
+        }
+        match inhibit_command().stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+            Ok(child) => {
+                self.child = Some(child);
+                log::info!("POWER: Preventing system sleep during playback");
+            }
+            Err(e) => {
+                log::warn!("POWER: Failed to prevent system sleep: {}", e);
+                self.unavailable = true;
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn release(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            log::info!("POWER: Allowing system sleep");
+        }
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        self.set_active(false);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn inhibit_command() -> Command {
+    // -i: no idle sleep; -w: give up if this process dies without releasing
+    let mut command = Command::new("caffeinate");
+    command.arg("-i").arg("-w").arg(std::process::id().to_string());
+    command
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn inhibit_command() -> Command {
+    let mut command = Command::new("systemd-inhibit");
+    command
+        .args(["--what=idle:sleep", "--who=AudioVibe", "--why=Playing audio", "--mode=block"])
+        .args(["sleep", "infinity"]);
+    command
+}
+
+#[cfg(windows)]
+mod windows {
+    pub const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
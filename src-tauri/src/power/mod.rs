@@ -0,0 +1,210 @@
+// System power awareness. Where the OS announces a suspend (see suspend.rs)
+// playback is paused and its position saved before the machine goes down. As a
+// fallback the monitor also polls the wall clock and treats a poll that arrives
+// far too late as a wake-up; the last position sampled before the gap is where
+// playback really stopped.
+
+use crate::audio::books;
+use crate::audio::thread::{self, AudioCommand};
+use crate::audio::PlaybackState;
+use crate::events::{self, AppEvent};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+use ts_rs::TS;
+
+mod keep_awake;
+mod suspend;
+
+pub use keep_awake::SleepInhibitor;
+use suspend::PowerEvent;
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// A poll this late means the machine was suspended in between; a busy system
+/// delays a timer by seconds, not this long
+const SLEEP_GAP: Duration = Duration::from_secs(15);
+
+/// Payload of the `system-resumed` event
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ResumeReport {
+    #[ts(type = "number")]
+    pub slept_seconds: u64,
+    /// Whether playback was running when the machine went to sleep
+    pub was_playing: bool,
+    /// Position playback was put back to, in seconds
+    #[ts(type = "number | null")]
+    pub position: Option<u64>,
+    pub file_path: Option<String>,
+    /// Set when the output device could not be reopened after waking
+    pub device_error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct SleepDetector {
+    last_tick: SystemTime,
+}
+
+impl SleepDetector {
+    pub fn new(now: SystemTime) -> Self {
+        Self { last_tick: now }
+    }
+
+    /// Record a poll; returns how long the machine slept when the gap since the
+    /// previous poll is too long to be a late timer
+    pub fn tick(&mut self, now: SystemTime) -> Option<Duration> {
+        // A clock set backwards is not a sleep
+        let elapsed = now.duration_since(self.last_tick).unwrap_or_default();
+        self.last_tick = now;
+        (elapsed >= POLL_INTERVAL + SLEEP_GAP).then(|| elapsed.saturating_sub(POLL_INTERVAL))
+    }
+}
+
+/// Watch for the machine sleeping. Playback is paused and its position saved when
+/// the OS announces the sleep; on waking it is put back to the position it had
+/// before the sleep, that position is saved, and the output is reopened.
+pub fn start_monitor(pool: sqlx::SqlitePool) {
+    static STARTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+    if STARTED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+
+    let (event_sender, mut power_events) = tokio::sync::mpsc::unbounded_channel();
+    suspend::watch(event_sender);
+
+    tauri::async_runtime::spawn(async move {
+        let mut detector = SleepDetector::new(SystemTime::now());
+        // File and position while playing, as of the previous poll or the suspend
+        let mut last_playing: Option<(String, u64)> = None;
+        // Set from a suspend notification until the wake-up is handled
+        let mut suspended_at: Option<SystemTime> = None;
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                Some(event) = power_events.recv() => {
+                    match event {
+                        PowerEvent::Suspending { ready } => {
+                            println!("💤 POWER: System going to sleep");
+                            if let Some(playing) = pause_for_sleep(&pool).await {
+                                last_playing = Some(playing);
+                            }
+                            suspended_at = Some(SystemTime::now());
+                            drop(ready);
+                        }
+                        // The wall clock may already have caught this wake-up
+                        PowerEvent::Resumed => {
+                            if let Some(since) = suspended_at.take() {
+                                let now = SystemTime::now();
+                                let slept = now.duration_since(since).unwrap_or_default();
+                                println!("💤 POWER: System resumed after {}s asleep", slept.as_secs());
+                                let report = handle_system_resume(&pool, slept, last_playing.take()).await;
+                                events::emit(AppEvent::SystemResumed(report));
+                                detector = SleepDetector::new(now);
+                            }
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if let Some(slept) = detector.tick(SystemTime::now()) {
+                println!("💤 POWER: System resumed after {}s asleep", slept.as_secs());
+                suspended_at = None;
+                let report = handle_system_resume(&pool, slept, last_playing.take()).await;
+                events::emit(AppEvent::SystemResumed(report));
+                continue;
+            }
+
+            // Paused for the suspend; keep what was playing for the wake-up
+            if suspended_at.is_none() {
+                last_playing = playing_position();
+            }
+        }
+    });
+}
+
+/// File and position of the book playing now, if any
+fn playing_position() -> Option<(String, u64)> {
+    let sender = thread::running_sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    sender.send(AudioCommand::GetStatus { response: response_sender }).ok()?;
+    let status = response_receiver.recv().ok()?;
+    match status.state {
+        PlaybackState::Playing => status.current_file.map(|file| (file, status.position)),
+        _ => None,
+    }
+}
+
+/// Pause what is playing and save its position before the machine sleeps;
+/// returns the file and position when something was playing
+async fn pause_for_sleep(pool: &sqlx::SqlitePool) -> Option<(String, u64)> {
+    let (file_path, position) = playing_position()?;
+    let sender = thread::running_sender()?;
+    let (response_sender, response_receiver) = mpsc::channel();
+    if sender.send(AudioCommand::Pause { response: response_sender }).is_ok() {
+        if let Ok(Err(e)) = response_receiver.recv() {
+            log::warn!("Failed to pause before sleep: {}", e);
+        }
+    }
+    if let Err(e) = books::save_position_for_file(pool, &file_path, position).await {
+        log::warn!("Failed to save position before sleep: {}", e);
+    }
+    Some((file_path, position))
+}
+
+async fn handle_system_resume(
+    pool: &sqlx::SqlitePool,
+    slept: Duration,
+    last_playing: Option<(String, u64)>,
+) -> ResumeReport {
+    let mut report = ResumeReport {
+        slept_seconds: slept.as_secs(),
+        was_playing: last_playing.is_some(),
+        position: last_playing.as_ref().map(|(_, position)| *position),
+        file_path: last_playing.as_ref().map(|(file, _)| file.clone()),
+        device_error: None,
+    };
+
+    let Some(sender) = thread::running_sender() else {
+        return report;
+    };
+    let (response_sender, response_receiver) = mpsc::channel();
+    let resumed = sender
+        .send(AudioCommand::HandleSystemResume { position: report.position, response: response_sender })
+        .map_err(|e| e.to_string())
+        .and_then(|_| response_receiver.recv().map_err(|e| e.to_string()))
+        .and_then(|result| result);
+    if let Err(e) = resumed {
+        log::warn!("Failed to reopen audio output after sleep: {}", e);
+        report.device_error = Some(e);
+    }
+
+    if let Some((file_path, position)) = &last_playing {
+        if let Err(e) = books::save_position_for_file(pool, file_path, *position).await {
+            log::warn!("Failed to save position after sleep: {}", e);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_long_gaps_count_as_sleep() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut detector = SleepDetector::new(start);
+
+        assert!(detector.tick(start + POLL_INTERVAL).is_none());
+        // A heavily loaded machine running a few seconds late
+        assert!(detector.tick(start + POLL_INTERVAL * 2 + Duration::from_secs(5)).is_none());
+        // Clock adjusted backwards
+        assert!(detector.tick(start).is_none());
+
+        let slept = detector.tick(start + Duration::from_secs(3_600)).unwrap();
+        assert_eq!(slept, Duration::from_secs(3_600) - POLL_INTERVAL);
+        assert!(detector.tick(start + Duration::from_secs(3_602)).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+// Notice of the machine going to sleep and waking, so playback can be paused and
+// its position saved before the sleep rather than pieced together after it.
+// Linux follows logind's PrepareForSleep signal through `gdbus monitor` and holds a
+// delay lock (systemd-inhibit --mode=delay) so the save finishes before the
+// machine goes down; Windows registers a suspend/resume callback. macOS, and
+// systems missing these tools, get no notice: the monitor's wall-clock
+// SleepDetector is the fallback there and only catches sleeps after waking.
+
+use std::sync::mpsc;
+use tokio::sync::mpsc::UnboundedSender;
+
+#[derive(Debug)]
+pub enum PowerEvent {
+    /// The machine is about to sleep; drop `ready` once playback is paused and saved
+    Suspending { ready: mpsc::Sender<()> },
+    Resumed,
+}
+
+/// Send the machine's suspend and resume notices to `events` where the platform has them
+pub fn watch(events: UnboundedSender<PowerEvent>) {
+    platform::watch(events);
+}
+
+/// Announce a suspend and wait, at most `timeout`, for the monitor to finish with it
+#[cfg(any(windows, all(unix, not(target_os = "macos"))))]
+fn announce_suspend(events: &UnboundedSender<PowerEvent>, timeout: std::time::Duration) -> bool {
+    let (ready, done) = mpsc::channel();
+    if events.send(PowerEvent::Suspending { ready }).is_err() {
+        return false;
+    }
+    // Disconnected once the monitor drops `ready`
+    let _ = done.recv_timeout(timeout);
+    true
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::{announce_suspend, PowerEvent};
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// logind waits at most InhibitDelayMaxSec, 5 seconds by default, for delay locks
+    const SAVE_TIMEOUT: Duration = Duration::from_secs(4);
+
+    pub fn watch(events: UnboundedSender<PowerEvent>) {
+        let monitor = Command::new("gdbus")
+            .args(["monitor", "--system", "--dest", "org.freedesktop.login1"])
+            .args(["--object-path", "/org/freedesktop/login1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut monitor = match monitor {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                log::warn!("POWER: No suspend notifications, relying on wake-up detection: {}", e);
+                return;
+            }
+        };
+        let Some(stdout) = monitor.stdout.take() else {
+            return;
+        };
+
+        std::thread::spawn(move || {
+            let mut delay = delay_lock();
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let Some(suspending) = prepare_for_sleep(&line) else {
+                    continue;
+                };
+                if suspending {
+                    if !announce_suspend(&events, SAVE_TIMEOUT) {
+                        break;
+                    }
+                    release(&mut delay);
+                } else {
+                    if events.send(PowerEvent::Resumed).is_err() {
+                        break;
+                    }
+                    if delay.is_none() {
+                        delay = delay_lock();
+                    }
+                }
+            }
+            release(&mut delay);
+            let _ = monitor.kill();
+            let _ = monitor.wait();
+            log::warn!("POWER: Stopped receiving suspend notifications");
+        });
+    }
+
+    /// Whether a `gdbus monitor` line is logind announcing a suspend (true) or the
+    /// wake-up after one (false); lines look like
+    /// "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)"
+    pub(super) fn prepare_for_sleep(line: &str) -> Option<bool> {
+        let (_, arguments) = line.split_once(".Manager.PrepareForSleep ")?;
+        match arguments.trim() {
+            "(true,)" => Some(true),
+            "(false,)" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Hold off suspends until released. The lock lives as long as the helper, which
+    /// waits on its stdin so it also goes away with this process.
+    fn delay_lock() -> Option<Child> {
+        Command::new("systemd-inhibit")
+            .args(["--what=sleep", "--who=AudioVibe", "--why=Saving the playback position", "--mode=delay"])
+            .arg("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| log::warn!("POWER: Failed to take a sleep delay lock: {}", e))
+            .ok()
+    }
+
+    fn release(delay: &mut Option<Child>) {
+        if let Some(mut child) = delay.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{announce_suspend, PowerEvent};
+    use std::ffi::c_void;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// Windows gives suspend handlers about two seconds
+    const SAVE_TIMEOUT: Duration = Duration::from_millis(1_500);
+    const DEVICE_NOTIFY_CALLBACK: u32 = 2;
+    const PBT_APMSUSPEND: u32 = 0x0004;
+    const PBT_APMRESUMEAUTOMATIC: u32 = 0x0012;
+
+    static EVENTS: OnceLock<UnboundedSender<PowerEvent>> = OnceLock::new();
+
+    #[repr(C)]
+    struct DeviceNotifySubscribeParameters {
+        callback: unsafe extern "system" fn(*mut c_void, u32, *mut c_void) -> u32,
+        context: *mut c_void,
+    }
+
+    #[link(name = "powrprof")]
+    extern "system" {
+        fn PowerRegisterSuspendResumeNotification(
+            flags: u32,
+            recipient: *mut c_void,
+            registration: *mut *mut c_void,
+        ) -> u32;
+    }
+
+    unsafe extern "system" fn on_power_change(_context: *mut c_void, kind: u32, _setting: *mut c_void) -> u32 {
+        if let Some(events) = EVENTS.get() {
+            match kind {
+                PBT_APMSUSPEND => {
+                    announce_suspend(events, SAVE_TIMEOUT);
+                }
+                PBT_APMRESUMEAUTOMATIC => {
+                    let _ = events.send(PowerEvent::Resumed);
+                }
+                _ => {}
+            }
+        }
+        0
+    }
+
+    pub fn watch(events: UnboundedSender<PowerEvent>) {
+        if EVENTS.set(events).is_err() {
+            return;
+        }
+        // Registered for the life of the app, so the parameters are never freed
+        let parameters = Box::leak(Box::new(DeviceNotifySubscribeParameters {
+            callback: on_power_change,
+            context: std::ptr::null_mut(),
+        }));
+        let mut registration = std::ptr::null_mut();
+        let result = unsafe {
+            PowerRegisterSuspendResumeNotification(
+                DEVICE_NOTIFY_CALLBACK,
+                parameters as *mut DeviceNotifySubscribeParameters as *mut c_void,
+                &mut registration,
+            )
+        };
+        if result != 0 {
+            log::warn!("POWER: No suspend notifications ({}), relying on wake-up detection", result);
+        }
+    }
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+mod platform {
+    use super::PowerEvent;
+    use tokio::sync::mpsc::UnboundedSender;
+
+    /// No notification here; the monitor notices sleeps after waking
+    pub fn watch(_events: UnboundedSender<PowerEvent>) {}
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::platform::prepare_for_sleep;
+
+    #[test]
+    fn test_prepare_for_sleep_lines() {
+        let signal = "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep";
+        assert_eq!(prepare_for_sleep(&format!("{} (true,)", signal)), Some(true));
+        assert_eq!(prepare_for_sleep(&format!("{} (false,)\n", signal)), Some(false));
+        assert_eq!(prepare_for_sleep("/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForShutdown (true,)"), None);
+        assert_eq!(prepare_for_sleep("Monitoring signals on object /org/freedesktop/login1 owned by :1.5"), None);
+    }
+}
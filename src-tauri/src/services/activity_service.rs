@@ -0,0 +1,175 @@
+// The activity calendar: minutes listened and read on each day of a year, for a
+// GitHub-style heatmap. Listening comes from the same history and daily
+// summaries as the other listening stats; reading comes from the per-day
+// reading totals kept alongside ebook reading progress.
+
+use crate::services::RetentionService;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ActivityDay {
+    pub day: String, // YYYY-MM-DD
+    #[ts(type = "number")]
+    pub listening_minutes: i64,
+    #[ts(type = "number")]
+    pub reading_minutes: i64,
+}
+
+pub struct ActivityService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ActivityService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Every day of the year in order, with nothing recorded left at zero
+    pub async fn heatmap(&self, year: i32) -> Result<Vec<ActivityDay>> {
+        let first = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| anyhow!("Invalid year {}", year))?;
+        let last = NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| anyhow!("Invalid year {}", year))?;
+        let (from, to) = (first.format("%Y-%m-%d").to_string(), last.format("%Y-%m-%d").to_string());
+
+        let listening: HashMap<String, i64> = RetentionService::new(self.pool)
+            .daily_listening(&from, &to)
+            .await?
+            .into_iter()
+            .map(|day| {
+                let seconds = day.listened_seconds();
+                (day.day, seconds)
+            })
+            .collect();
+
+        let reading: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT day, SUM(seconds)
+            FROM reading_daily_activity
+            WHERE day BETWEEN ? AND ?
+            GROUP BY day
+            "#,
+        )
+        .bind(&from)
+        .bind(&to)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load daily reading")?
+        .into_iter()
+        .collect();
+
+        Ok(first
+            .iter_days()
+            .take_while(|date| date.year() == year)
+            .map(|date| {
+                let day = date.format("%Y-%m-%d").to_string();
+                ActivityDay {
+                    listening_minutes: minutes(listening.get(&day).copied().unwrap_or(0)),
+                    reading_minutes: minutes(reading.get(&day).copied().unwrap_or(0)),
+                    day,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Seconds to the nearest whole minute
+fn minutes(seconds: i64) -> i64 {
+    (seconds + 30) / 60
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, UpdateReadingProgressDto};
+    use crate::database::repository::AudiobookRepository;
+    use crate::database::DatabaseManager;
+    use crate::ebook::ReadingProgressRepository;
+
+    async fn add_ebook(pool: &SqlitePool, id: &str) {
+        sqlx::query(
+            "INSERT INTO ebooks (id, title, file_path, file_format, added_date, modified_date)
+             VALUES (?, 'Middlemarch', '/books/middlemarch.epub', 'epub', '2024-01-01', '2024-01-01')",
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn reading_time(seconds: i64) -> UpdateReadingProgressDto {
+        UpdateReadingProgressDto {
+            current_page: None,
+            current_cfi: None,
+            current_chapter_href: None,
+            percentage_complete: None,
+            reading_time_seconds: Some(seconds),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heatmap_combines_listening_and_reading() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("activity.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let book = AudiobookRepository::new(pool)
+            .create(CreateAudiobookDto {
+                title: "Emma".to_string(),
+                file_path: "/books/emma".to_string(),
+                author: None,
+                narrator: None,
+                description: None,
+                genre: None,
+                duration: Some(36_000),
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            })
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO listening_daily_summaries (day, audiobook_id, session_count, session_seconds, play_count, played_seconds)
+             VALUES ('2024-02-29', ?1, 1, 900, 0, 0), ('2023-12-31', ?1, 1, 600, 0, 0)",
+        )
+        .bind(&book.id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // Only the rise in the running total counts, on the day it was saved
+        add_ebook(pool, "ebook").await;
+        let progress = ReadingProgressRepository::new(pool);
+        progress.upsert("ebook", reading_time(300)).await.unwrap();
+        progress.upsert("ebook", reading_time(1_500)).await.unwrap();
+        progress.upsert("ebook", reading_time(1_500)).await.unwrap();
+        sqlx::query("UPDATE reading_daily_activity SET day = '2024-03-01'")
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let days = ActivityService::new(pool).heatmap(2024).await.unwrap();
+        assert_eq!(days.len(), 366);
+        assert_eq!(days[0].day, "2024-01-01");
+        assert_eq!(days[365].day, "2024-12-31");
+
+        let leap_day = days.iter().find(|d| d.day == "2024-02-29").unwrap();
+        assert_eq!((leap_day.listening_minutes, leap_day.reading_minutes), (15, 0));
+        let next = days.iter().find(|d| d.day == "2024-03-01").unwrap();
+        assert_eq!((next.listening_minutes, next.reading_minutes), (0, 25));
+        assert_eq!(days.iter().map(|d| d.listening_minutes + d.reading_minutes).sum::<i64>(), 40);
+    }
+
+    #[test]
+    fn test_minutes_round_to_nearest() {
+        assert_eq!(minutes(0), 0);
+        assert_eq!(minutes(29), 0);
+        assert_eq!(minutes(30), 1);
+        assert_eq!(minutes(3_599), 60);
+    }
+}
@@ -0,0 +1,250 @@
+// Where each audiobook was imported from. Imports record it when they create
+// the book, and older books have it inferred from their paths, so a book's
+// details can be read again from LibriVox, its document or its own files.
+
+use crate::database::models::{Audiobook, SourceMetadata};
+use crate::database::repository::{AudiobookRepository, DocumentRepository};
+use crate::document::article::clean_inline;
+use crate::filesystem::FileSystemScanner;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::path::Path;
+
+pub const SOURCE_LIBRIVOX: &str = "librivox";
+pub const SOURCE_TTS: &str = "tts";
+pub const SOURCE_LOCAL: &str = "local";
+pub const SOURCE_URL_LIST: &str = "url_list";
+/// URL-list imports share the LibriVox download cache under folders with this prefix
+pub const URL_LIST_FOLDER_PREFIX: &str = "urls_";
+/// Genre given to every TTS book when it is created
+const TTS_GENRE: &str = "TTS Generated";
+
+pub struct AudiobookSourceService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> AudiobookSourceService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a source for books imported before sources were tracked.
+    /// `librivox_dir` is the download cache that LibriVox books are stored in.
+    pub async fn backfill(&self, librivox_dir: &Path) -> Result<usize> {
+        let repo = AudiobookRepository::new(self.pool);
+        let audiobooks = repo.find_without_source().await?;
+        for audiobook in &audiobooks {
+            let (source_type, source_id) = infer_source(audiobook, librivox_dir);
+            repo.set_source(&audiobook.id, source_type, source_id.as_deref()).await?;
+        }
+        Ok(audiobooks.len())
+    }
+
+    /// Read the book's details again from where it was imported and store them
+    pub async fn refresh(&self, audiobook_id: &str) -> Result<Audiobook> {
+        let repo = AudiobookRepository::new(self.pool);
+        let audiobook = repo.find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+
+        let metadata = match (audiobook.source_type.as_deref(), audiobook.source_id.as_deref()) {
+            (Some(SOURCE_LIBRIVOX), Some(identifier)) => fetch_archive_metadata(identifier).await?,
+            (Some(SOURCE_TTS), Some(document_id)) => {
+                let document = DocumentRepository::new(self.pool).find_by_id(document_id).await?
+                    .context("The document this audiobook was generated from is no longer stored")?;
+                SourceMetadata {
+                    title: Some(document.title),
+                    author: document.author,
+                    ..Default::default()
+                }
+            }
+            (Some(SOURCE_TTS), None) => {
+                return Err(anyhow::anyhow!("This audiobook was generated before documents were linked to their books"));
+            }
+            (Some(SOURCE_LOCAL), _) => file_metadata(Path::new(&audiobook.file_path))?,
+            (Some(SOURCE_URL_LIST), _) => {
+                return Err(anyhow::anyhow!("Audiobooks downloaded from a URL list have no source metadata"));
+            }
+            _ => return Err(anyhow::anyhow!("The source of this audiobook is unknown")),
+        };
+
+        repo.apply_source_metadata(audiobook_id, &metadata).await?;
+        println!("🔄 SOURCE REFRESH: Updated '{}' from {}", audiobook.title, audiobook.source_type.unwrap_or_default());
+        repo.find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))
+    }
+}
+
+/// Work out the source of a book from where its files live
+pub fn infer_source(audiobook: &Audiobook, librivox_dir: &Path) -> (&'static str, Option<String>) {
+    if audiobook.genre.as_deref() == Some(TTS_GENRE) {
+        return (SOURCE_TTS, None);
+    }
+
+    let path = Path::new(&audiobook.file_path);
+    let folder = if path.is_file() { path.parent() } else { Some(path) };
+    if let Some(folder) = folder.filter(|folder| folder.parent() == Some(librivox_dir)) {
+        let name = folder.file_name().map(|name| name.to_string_lossy().to_string());
+        if let Some(name) = name {
+            if name.starts_with(URL_LIST_FOLDER_PREFIX) {
+                return (SOURCE_URL_LIST, Some(name));
+            }
+            return (SOURCE_LIBRIVOX, Some(name));
+        }
+    }
+    (SOURCE_LOCAL, Some(audiobook.file_path.clone()))
+}
+
+/// The Archive.org identifier in a LibriVox ZIP URL, which LibriVox books use as their source id
+pub fn archive_identifier(zip_url: &str) -> Option<String> {
+    // ZIP URLs look like: https://archive.org/compress/picturedoriangr_1608_librivox/formats=64KBPS%20MP3&file=/picturedoriangr_1608_librivox.zip
+    // We want to extract "picturedoriangr_1608_librivox"
+
+    if let Some(compress_pos) = zip_url.find("/compress/") {
+        let after_compress = &zip_url[compress_pos + 10..]; // Skip "/compress/"
+        if let Some(slash_pos) = after_compress.find('/') {
+            let identifier = &after_compress[..slash_pos];
+            return Some(identifier.to_string());
+        }
+    }
+
+    // Fallback: try to extract from the file parameter
+    if let Some(file_param) = zip_url.split("file=/").nth(1) {
+        if let Some(dot_pos) = file_param.find('.') {
+            let identifier = &file_param[..dot_pos];
+            return Some(identifier.to_string());
+        }
+    }
+
+    None
+}
+
+async fn fetch_archive_metadata(identifier: &str) -> Result<SourceMetadata> {
+    let url = format!("https://archive.org/metadata/{}", identifier);
+    println!("🌐 ARCHIVE.ORG: Refreshing metadata from: {}", url);
+
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("User-Agent", "AudioVibe/1.0.0")
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .context("Failed to get Archive.org metadata")?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Archive.org metadata request failed with status: {}", response.status()));
+    }
+
+    let json: Value = response.json().await.context("Failed to parse Archive.org metadata JSON")?;
+    parse_archive_metadata(&json)
+}
+
+/// Archive.org fields may be a single string or a list of them
+fn parse_archive_metadata(json: &Value) -> Result<SourceMetadata> {
+    let metadata = json.get("metadata")
+        .filter(|metadata| metadata.is_object())
+        .context("Archive.org item not found")?;
+    let field = |name: &str| -> Option<String> {
+        let text = match metadata.get(name)? {
+            Value::String(value) => value.clone(),
+            Value::Array(values) => values
+                .iter()
+                .filter_map(|value| value.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => return None,
+        };
+        let text = clean_inline(&text);
+        (!text.is_empty()).then_some(text)
+    };
+
+    Ok(SourceMetadata {
+        title: field("title"),
+        author: field("creator"),
+        description: field("description"),
+        language: field("language"),
+        publish_date: field("date"),
+        ..Default::default()
+    })
+}
+
+/// Tags of a local import: the folder's first file for multi-file books
+fn file_metadata(path: &Path) -> Result<SourceMetadata> {
+    let scanner = FileSystemScanner::new();
+    if path.is_dir() {
+        let info = scanner.analyze_audiobook_directory(path).map_err(|e| anyhow::anyhow!(e))?;
+        return Ok(SourceMetadata {
+            title: Some(info.title),
+            author: info.author,
+            duration: info.total_duration.map(|duration| duration as i64),
+            cover_image_path: scanner.find_cover_art(path).map(|cover| cover.to_string_lossy().to_string()),
+            ..Default::default()
+        });
+    }
+
+    let info = scanner.get_audio_file_info(path);
+    if !info.is_valid {
+        return Err(anyhow::anyhow!(
+            "Could not read {}: {}",
+            path.display(),
+            info.error_message.unwrap_or_default()
+        ));
+    }
+    Ok(info.metadata
+        .map(|tags| SourceMetadata {
+            title: tags.title,
+            author: tags.artist,
+            genre: tags.genre,
+            duration: tags.duration.map(|duration| duration as i64),
+            ..Default::default()
+        })
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_archive_metadata() {
+        let json = serde_json::json!({
+            "metadata": {
+                "identifier": "picturedoriangr_1608_librivox",
+                "title": "The Picture of Dorian Gray",
+                "creator": ["Oscar Wilde"],
+                "description": "<p>Dorian&#39;s portrait ages <b>instead</b> of him.</p>",
+                "language": "English",
+                "date": "2016-08-01"
+            }
+        });
+        let metadata = parse_archive_metadata(&json).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("The Picture of Dorian Gray"));
+        assert_eq!(metadata.author.as_deref(), Some("Oscar Wilde"));
+        assert_eq!(metadata.description.as_deref(), Some("Dorian's portrait ages instead of him."));
+        assert_eq!(metadata.genre, None);
+
+        // Unknown identifiers return an empty object
+        assert!(parse_archive_metadata(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_infer_source_from_paths() {
+        let cache = tempfile::tempdir().unwrap();
+        let librivox = cache.path().join("picturedoriangr_1608_librivox");
+        let urls = cache.path().join("urls_1a2b");
+        std::fs::create_dir_all(&librivox).unwrap();
+        std::fs::create_dir_all(&urls).unwrap();
+        std::fs::write(librivox.join("chapter_01.mp3"), "audio").unwrap();
+
+        let book = |file_path: &Path| Audiobook::new("Book".into(), file_path.to_string_lossy().to_string());
+        assert_eq!(
+            infer_source(&book(&librivox.join("chapter_01.mp3")), cache.path()),
+            (SOURCE_LIBRIVOX, Some("picturedoriangr_1608_librivox".to_string()))
+        );
+        assert_eq!(infer_source(&book(&urls), cache.path()), (SOURCE_URL_LIST, Some("urls_1a2b".to_string())));
+        assert_eq!(infer_source(&book(Path::new("/music/Emma")), cache.path()), (SOURCE_LOCAL, Some("/music/Emma".to_string())));
+
+        let mut generated = book(Path::new("/data/audiobook_output/1"));
+        generated.genre = Some(TTS_GENRE.to_string());
+        assert_eq!(infer_source(&generated, cache.path()), (SOURCE_TTS, None));
+    }
+}
@@ -0,0 +1,301 @@
+// Author entities: resolving free-text author names to shared author records and
+// enriching them with OpenLibrary details
+
+use crate::database::models::{Author, AuthorBooks, AuthorDetails};
+use crate::database::repository::AuthorRepository;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+/// Spellings at least this similar (after normalization) are treated as the same author
+pub(crate) const FUZZY_MATCH_THRESHOLD: f64 = 0.9;
+/// Very short names are too ambiguous to fuzzy match
+pub(crate) const FUZZY_MIN_LENGTH: usize = 6;
+
+const OPENLIBRARY_BASE_URL: &str = "https://openlibrary.org";
+
+pub struct AuthorService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> AuthorService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Find the author a name refers to, creating one when nothing matches.
+    /// A fuzzy match records the new spelling as an alias.
+    pub async fn resolve(&self, name: &str) -> Result<Option<Author>> {
+        let display_name = display_name(name);
+        let normalized = normalize_author_name(&display_name);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        let repo = AuthorRepository::new(self.pool);
+        if let Some(author) = repo.find_by_alias(&normalized).await? {
+            return Ok(Some(author));
+        }
+
+        if normalized.len() >= FUZZY_MIN_LENGTH {
+            let best = repo.find_all_aliases().await?
+                .into_iter()
+                .map(|alias| (similarity(&token_key(&alias.normalized_alias), &token_key(&normalized)), alias))
+                .filter(|(score, _)| *score >= FUZZY_MATCH_THRESHOLD)
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+
+            if let Some((score, alias)) = best {
+                println!("👤 AUTHOR: '{}' matched '{}' ({:.2})", display_name, alias.alias, score);
+                repo.add_alias(&alias.author_id, &display_name, &normalized).await?;
+                return repo.find_by_id(&alias.author_id).await;
+            }
+        }
+
+        let author = repo.create(&display_name, &sort_name(&display_name), &normalized).await?;
+        println!("👤 AUTHOR: Created author '{}'", author.name);
+        Ok(Some(author))
+    }
+
+    pub async fn link_audiobook(&self, audiobook_id: &str, author: Option<&str>) -> Result<Option<Author>> {
+        let resolved = match author {
+            Some(name) => self.resolve(name).await?,
+            None => None,
+        };
+        AuthorRepository::new(self.pool)
+            .link_audiobook(audiobook_id, resolved.as_ref().map(|a| a.id.as_str()))
+            .await?;
+        Ok(resolved)
+    }
+
+    pub async fn link_ebook(&self, ebook_id: &str, author: Option<&str>) -> Result<Option<Author>> {
+        let resolved = match author {
+            Some(name) => self.resolve(name).await?,
+            None => None,
+        };
+        AuthorRepository::new(self.pool)
+            .link_ebook(ebook_id, resolved.as_ref().map(|a| a.id.as_str()))
+            .await?;
+        Ok(resolved)
+    }
+
+    /// Link every book imported before authors existed. Returns how many were linked.
+    pub async fn backfill(&self) -> Result<usize> {
+        let unlinked = AuthorRepository::new(self.pool).find_unlinked().await?;
+        let mut linked = 0;
+        for (kind, id, author) in unlinked {
+            let result = if kind == "ebook" {
+                self.link_ebook(&id, Some(&author)).await
+            } else {
+                self.link_audiobook(&id, Some(&author)).await
+            };
+            match result {
+                Ok(Some(_)) => linked += 1,
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to link author '{}' for {} {}: {}", author, kind, id, e),
+            }
+        }
+        Ok(linked)
+    }
+
+    pub async fn get_details(&self, author_id: &str) -> Result<Option<AuthorDetails>> {
+        let repo = AuthorRepository::new(self.pool);
+        let author = match repo.find_by_id(author_id).await? {
+            Some(author) => author,
+            None => return Ok(None),
+        };
+        let aliases = repo.find_aliases(author_id).await?
+            .into_iter()
+            .map(|alias| alias.alias)
+            .filter(|alias| *alias != author.name)
+            .collect();
+
+        Ok(Some(AuthorDetails { author, aliases }))
+    }
+
+    pub async fn get_books(&self, author_id: &str) -> Result<AuthorBooks> {
+        let repo = AuthorRepository::new(self.pool);
+        Ok(AuthorBooks {
+            audiobooks: repo.find_audiobooks(author_id).await?,
+            ebooks: repo.find_ebooks(author_id).await?,
+        })
+    }
+
+    /// Look the author up on OpenLibrary and store bio, photo and key. The attempt is
+    /// recorded even when nothing is found so it is not repeated on every visit.
+    pub async fn fetch_openlibrary_details(&self, author_id: &str) -> Result<()> {
+        let repo = AuthorRepository::new(self.pool);
+        let author = repo.find_by_id(author_id).await?
+            .with_context(|| format!("Author not found: {}", author_id))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        let search: OpenLibraryAuthorSearch = client
+            .get(format!("{}/search/authors.json", OPENLIBRARY_BASE_URL))
+            .query(&[("q", author.name.as_str()), ("limit", "5")])
+            .send().await.context("OpenLibrary author search failed")?
+            .error_for_status().context("OpenLibrary author search failed")?
+            .json().await.context("Invalid OpenLibrary author search response")?;
+
+        let wanted = normalize_author_name(&author.name);
+        let found = search.docs.into_iter()
+            .find(|doc| similarity(&token_key(&normalize_author_name(&doc.name)), &token_key(&wanted)) >= FUZZY_MATCH_THRESHOLD);
+
+        let Some(doc) = found else {
+            repo.update_details(author_id, None, None, None).await?;
+            return Ok(());
+        };
+
+        let detail: OpenLibraryAuthor = client
+            .get(format!("{}/authors/{}.json", OPENLIBRARY_BASE_URL, doc.key))
+            .send().await.context("OpenLibrary author lookup failed")?
+            .error_for_status().context("OpenLibrary author lookup failed")?
+            .json().await.context("Invalid OpenLibrary author response")?;
+
+        let bio = detail.bio.map(|bio| match bio {
+            OpenLibraryText::Plain(text) => text,
+            OpenLibraryText::Typed { value } => value,
+        });
+        let photo_url = (!detail.photos.unwrap_or_default().is_empty())
+            .then(|| format!("https://covers.openlibrary.org/a/olid/{}-M.jpg", doc.key));
+
+        repo.update_details(author_id, bio.as_deref(), photo_url.as_deref(), Some(&doc.key)).await?;
+        println!("👤 AUTHOR: Fetched OpenLibrary details for '{}' ({})", author.name, doc.key);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenLibraryAuthorSearch {
+    #[serde(default)]
+    docs: Vec<OpenLibraryAuthorDoc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenLibraryAuthorDoc {
+    key: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenLibraryAuthor {
+    bio: Option<OpenLibraryText>,
+    photos: Option<Vec<i64>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OpenLibraryText {
+    Plain(String),
+    Typed { value: String },
+}
+
+/// Comparable form of an author name: "Tolkien, J.R.R." and "J. R. R. Tolkien" both
+/// become "j r r tolkien"
+pub fn normalize_author_name(name: &str) -> String {
+    let name = name.trim();
+    let reordered = match name.split_once(',') {
+        Some((last, first)) if !first.trim().is_empty() && !last.trim().is_empty() => {
+            format!("{} {}", first.trim(), last.trim())
+        }
+        _ => name.to_string(),
+    };
+
+    reordered
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().next().unwrap_or(c) } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Name as shown on author pages: "Last, First" is turned around to "First Last"
+pub fn display_name(name: &str) -> String {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    match name.split_once(',') {
+        Some((last, first)) if !first.trim().is_empty() && !last.trim().is_empty() && !first.contains(',') => {
+            format!("{} {}", first.trim(), last.trim())
+        }
+        _ => name,
+    }
+}
+
+/// "Last, First" form used for sorting; names already containing a comma are kept
+pub fn sort_name(name: &str) -> String {
+    let name = name.trim();
+    if name.contains(',') {
+        return name.to_string();
+    }
+    match name.rsplit_once(' ') {
+        Some((first, last)) => format!("{}, {}", last, first),
+        None => name.to_string(),
+    }
+}
+
+/// Word order independent key so "wells h g" and "h g wells" compare equal
+pub(crate) fn token_key(normalized: &str) -> String {
+    let mut tokens: Vec<&str> = normalized.split(' ').collect();
+    tokens.sort_unstable();
+    tokens.join(" ")
+}
+
+/// Normalized Levenshtein similarity in 0.0..=1.0
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_author_name() {
+        assert_eq!(normalize_author_name("Tolkien, J.R.R."), "j r r tolkien");
+        assert_eq!(normalize_author_name("  J. R. R.   Tolkien "), "j r r tolkien");
+        assert_eq!(normalize_author_name("Arthur Conan Doyle"), "arthur conan doyle");
+        assert_eq!(normalize_author_name("..."), "");
+    }
+
+    #[test]
+    fn test_display_and_sort_name() {
+        assert_eq!(display_name("Tolkien,  J.R.R."), "J.R.R. Tolkien");
+        assert_eq!(display_name("Dumas, Alexandre, père"), "Dumas, Alexandre, père");
+        assert_eq!(sort_name("Arthur Conan Doyle"), "Doyle, Arthur Conan");
+        assert_eq!(sort_name("Homer"), "Homer");
+        assert_eq!(sort_name("Austen, Jane"), "Austen, Jane");
+    }
+
+    #[test]
+    fn test_fuzzy_similarity() {
+        let a = token_key(&normalize_author_name("Fyodor Dostoevsky"));
+        let b = token_key(&normalize_author_name("Fyodor Dostoyevsky"));
+        assert!(similarity(&a, &b) >= FUZZY_MATCH_THRESHOLD);
+
+        let c = token_key(&normalize_author_name("Mark Twain"));
+        let d = token_key(&normalize_author_name("Mary Shelley"));
+        assert!(similarity(&c, &d) < FUZZY_MATCH_THRESHOLD);
+
+        assert_eq!(token_key("wells h g"), token_key("h g wells"));
+    }
+}
@@ -0,0 +1,258 @@
+// Where playback is within the whole book rather than the current file: which
+// chapter, how far into it, and how much of the book is left. Multi-file books
+// use their chapter files; single-file books use their chapter markers.
+
+use crate::database::models::{Audiobook, Chapter, ChapterMarker};
+use crate::database::repository::{AudiobookRepository, ChapterMarkerRepository, ChapterRepository};
+use crate::services::PlayHistoryService;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+/// The current chapter and book position, alongside the raw file position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ChapterPosition {
+    pub audiobook_id: String,
+    /// Zero-based
+    pub index: usize,
+    pub count: usize,
+    pub title: String,
+    #[ts(type = "number")]
+    pub chapter_position: u64,
+    #[ts(type = "number | null")]
+    pub chapter_duration: Option<u64>,
+    /// None while a chapter before this one has no known duration
+    #[ts(type = "number | null")]
+    pub book_position: Option<u64>,
+    #[ts(type = "number | null")]
+    pub book_duration: Option<u64>,
+    #[ts(type = "number | null")]
+    pub book_remaining: Option<u64>,
+}
+
+/// Where to go for a position in the book
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookSeekTarget {
+    pub index: usize,
+    pub file_path: String,
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    title: String,
+    file_path: String,
+    /// Where the segment starts within its file
+    file_offset: u64,
+    duration: Option<u64>,
+}
+
+/// A book laid out as consecutive segments, each a span of one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookLayout {
+    pub audiobook_id: String,
+    segments: Vec<Segment>,
+}
+
+impl BookLayout {
+    fn from_chapters(audiobook: &Audiobook, mut chapters: Vec<Chapter>, markers: Vec<ChapterMarker>) -> Self {
+        chapters.sort_by_key(|chapter| chapter.chapter_number);
+        let segments = if !chapters.is_empty() {
+            chapters
+                .into_iter()
+                .map(|chapter| Segment {
+                    title: chapter.title,
+                    file_path: chapter.file_path,
+                    file_offset: 0,
+                    duration: chapter.duration.map(|seconds| seconds.max(0) as u64),
+                })
+                .collect()
+        } else {
+            let book_duration = audiobook.duration.map(|seconds| seconds.max(0) as u64);
+            let mut positions: Vec<(u64, String)> = markers
+                .into_iter()
+                .map(|marker| (marker.position.max(0) as u64, marker.title))
+                .collect();
+            positions.sort_by_key(|(position, _)| *position);
+            positions.dedup_by_key(|(position, _)| *position);
+            // Whatever comes before the first marker is a chapter of its own
+            if positions.first().is_none_or(|(position, _)| *position > 0) {
+                positions.insert(0, (0, audiobook.title.clone()));
+            }
+            let ends: Vec<Option<u64>> = positions.iter().skip(1).map(|(position, _)| Some(*position)).chain([book_duration]).collect();
+            positions
+                .into_iter()
+                .zip(ends)
+                .map(|((start, title), end)| Segment {
+                    title,
+                    file_path: audiobook.file_path.clone(),
+                    file_offset: start,
+                    duration: end.map(|end| end.saturating_sub(start)),
+                })
+                .collect()
+        };
+        Self { audiobook_id: audiobook.id.clone(), segments }
+    }
+
+    /// Book time at which each segment starts, None after an unknown duration
+    fn starts(&self) -> Vec<Option<u64>> {
+        let mut start = Some(0u64);
+        self.segments
+            .iter()
+            .map(|segment| {
+                let this = start;
+                start = start.zip(segment.duration).map(|(start, duration)| start + duration);
+                this
+            })
+            .collect()
+    }
+
+    fn book_duration(&self) -> Option<u64> {
+        self.segments.iter().map(|segment| segment.duration).sum()
+    }
+
+    /// Position in the book for `position` seconds into `file_path`
+    pub fn position(&self, file_path: &str, position: u64) -> Option<ChapterPosition> {
+        let index = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| segment.file_path == file_path && segment.file_offset <= position)
+            .map(|(index, _)| index)
+            .next_back()?;
+        let segment = &self.segments[index];
+        let chapter_position = position - segment.file_offset;
+        let book_position = self.starts()[index].map(|start| start + chapter_position);
+        let book_duration = self.book_duration();
+        Some(ChapterPosition {
+            audiobook_id: self.audiobook_id.clone(),
+            index,
+            count: self.segments.len(),
+            title: segment.title.clone(),
+            chapter_position,
+            chapter_duration: segment.duration,
+            book_position,
+            book_duration,
+            book_remaining: book_position.zip(book_duration).map(|(position, duration)| duration.saturating_sub(position)),
+        })
+    }
+
+    /// Book time at which chapter `index` starts; None past the end or after a
+    /// chapter of unknown length
+    pub fn chapter_start(&self, index: usize) -> Option<u64> {
+        self.starts().get(index).copied().flatten()
+    }
+
+    /// Whether `file_path` holds the book's final chapter, so reaching its end finishes the book
+    pub fn is_last_file(&self, file_path: &str) -> bool {
+        self.segments.last().is_some_and(|segment| segment.file_path == file_path)
+    }
+
+    /// The chapter file and offset for `seconds` into the book. Past the end
+    /// lands at the end of the last chapter; past a chapter of unknown length,
+    /// somewhere in that chapter.
+    pub fn seek_target(&self, seconds: u64) -> Result<BookSeekTarget> {
+        let starts = self.starts();
+        let mut target = None;
+        for (index, segment) in self.segments.iter().enumerate() {
+            let Some(start) = starts[index].filter(|start| seconds >= *start) else {
+                break;
+            };
+            let into = seconds - start;
+            let into = segment.duration.map_or(into, |duration| into.min(duration));
+            target = Some(BookSeekTarget { index, file_path: segment.file_path.clone(), offset: segment.file_offset + into });
+        }
+        target.ok_or_else(|| anyhow!("Audiobook has no chapters"))
+    }
+}
+
+pub struct BookPositionService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> BookPositionService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn layout(&self, audiobook_id: &str) -> Result<BookLayout> {
+        let audiobook = AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?
+            .ok_or_else(|| anyhow!("Audiobook not found: {}", audiobook_id))?;
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+        let markers = if chapters.is_empty() {
+            ChapterMarkerRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?
+        } else {
+            Vec::new()
+        };
+        Ok(BookLayout::from_chapters(&audiobook, chapters, markers))
+    }
+
+    /// Layout of the book `file_path` belongs to, if it is in the library
+    pub async fn layout_for_file(&self, file_path: &str) -> Result<Option<BookLayout>> {
+        match PlayHistoryService::new(self.pool).resolve_file(file_path).await? {
+            Some((audiobook_id, _)) => Ok(Some(self.layout(&audiobook_id).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(number: i32, duration: Option<i64>) -> Chapter {
+        let mut chapter = Chapter::new("book".to_string(), number, format!("Chapter {}", number), format!("/books/{}.mp3", number));
+        chapter.duration = duration;
+        chapter
+    }
+
+    fn audiobook(duration: Option<i64>) -> Audiobook {
+        let mut audiobook = Audiobook::new("Book".to_string(), "/books/book.m4b".to_string());
+        audiobook.id = "book".to_string();
+        audiobook.duration = duration;
+        audiobook
+    }
+
+    #[test]
+    fn test_multi_file_positions_and_seeking() {
+        let layout = BookLayout::from_chapters(&audiobook(None), vec![chapter(2, Some(200)), chapter(1, Some(100)), chapter(3, Some(300))], vec![]);
+
+        let position = layout.position("/books/2.mp3", 50).unwrap();
+        assert_eq!((position.index, position.count, position.title.as_str()), (1, 3, "Chapter 2"));
+        assert_eq!((position.chapter_position, position.chapter_duration), (50, Some(200)));
+        assert_eq!((position.book_position, position.book_duration, position.book_remaining), (Some(150), Some(600), Some(450)));
+        assert!(layout.position("/books/other.mp3", 0).is_none());
+        assert!(layout.is_last_file("/books/3.mp3") && !layout.is_last_file("/books/2.mp3"));
+
+        assert_eq!(layout.seek_target(0).unwrap(), BookSeekTarget { index: 0, file_path: "/books/1.mp3".to_string(), offset: 0 });
+        assert_eq!(layout.seek_target(350).unwrap(), BookSeekTarget { index: 2, file_path: "/books/3.mp3".to_string(), offset: 50 });
+        assert_eq!(layout.seek_target(100).unwrap().index, 1, "a chapter boundary belongs to the next chapter");
+        assert_eq!(layout.seek_target(10_000).unwrap().offset, 300, "past the end stops at the end");
+
+        let unknown = BookLayout::from_chapters(&audiobook(None), vec![chapter(1, None), chapter(2, Some(100))], vec![]);
+        assert_eq!(unknown.position("/books/2.mp3", 10).unwrap().book_position, None);
+        assert_eq!(unknown.seek_target(500).unwrap(), BookSeekTarget { index: 0, file_path: "/books/1.mp3".to_string(), offset: 500 });
+        let empty = BookLayout::from_chapters(&audiobook(None), vec![], vec![]);
+        assert_eq!(empty.seek_target(5).unwrap().offset, 5, "a single file without markers is one chapter");
+    }
+
+    #[test]
+    fn test_single_file_uses_markers() {
+        let markers = vec![
+            ChapterMarker::new("book".to_string(), 600, "Part 2".to_string(), true),
+            ChapterMarker::new("book".to_string(), 60, "Part 1".to_string(), true),
+        ];
+        let layout = BookLayout::from_chapters(&audiobook(Some(1000)), vec![], markers);
+
+        let intro = layout.position("/books/book.m4b", 30).unwrap();
+        assert_eq!((intro.index, intro.count, intro.title.as_str()), (0, 3, "Book"));
+        let part2 = layout.position("/books/book.m4b", 700).unwrap();
+        assert_eq!((part2.index, part2.chapter_position, part2.chapter_duration), (2, 100, Some(400)));
+        assert_eq!((part2.book_position, part2.book_remaining), (Some(700), Some(300)));
+
+        let target = layout.seek_target(650).unwrap();
+        assert_eq!((target.index, target.offset), (2, 650));
+    }
+}
@@ -0,0 +1,147 @@
+// Chapter files that failed to load during playback. The audio thread reports
+// them as it skips past; they stay flagged until the file loads again.
+
+use crate::services::PlayHistoryService;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, sqlx::FromRow)]
+#[ts(export)]
+pub struct ChapterError {
+    pub file_path: String,
+    pub audiobook_id: String,
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub error: String,
+    pub failed_at: String,
+}
+
+/// Payload of the `chapter-error` event
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ChapterErrorEvent {
+    pub file_path: String,
+    pub error: String,
+    /// Whether playback went on to the next chapter
+    pub skipped: bool,
+    /// None for files outside the library
+    pub chapter: Option<ChapterError>,
+}
+
+pub struct ChapterErrorService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChapterErrorService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Flag a library file as unplayable; None for files outside the library
+    pub async fn record(&self, file_path: &str, error: &str) -> Result<Option<ChapterError>> {
+        let Some((audiobook_id, chapter_id)) = PlayHistoryService::new(self.pool).resolve_file(file_path).await? else {
+            return Ok(None);
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO chapter_errors (file_path, audiobook_id, chapter_id, error, failed_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (file_path) DO UPDATE SET
+                audiobook_id = excluded.audiobook_id,
+                chapter_id = excluded.chapter_id,
+                error = excluded.error,
+                failed_at = excluded.failed_at
+            "#,
+        )
+        .bind(file_path)
+        .bind(&audiobook_id)
+        .bind(&chapter_id)
+        .bind(error)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to record chapter error")?;
+
+        let errors = self.for_audiobook(&audiobook_id).await?;
+        Ok(errors.into_iter().find(|chapter| chapter.file_path == file_path))
+    }
+
+    /// The file loaded after all; returns whether it had been flagged
+    pub async fn clear(&self, file_path: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM chapter_errors WHERE file_path = ?")
+            .bind(file_path)
+            .execute(self.pool)
+            .await
+            .context("Failed to clear chapter error")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn for_audiobook(&self, audiobook_id: &str) -> Result<Vec<ChapterError>> {
+        sqlx::query_as::<_, ChapterError>(
+            r#"
+            SELECT e.file_path, e.audiobook_id, e.chapter_id, c.title as chapter_title, e.error, e.failed_at
+            FROM chapter_errors e
+            LEFT JOIN chapters c ON c.id = e.chapter_id
+            WHERE e.audiobook_id = ?
+            ORDER BY c.chapter_number, e.file_path
+            "#,
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get chapter errors")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, CreateChapterDto};
+    use crate::database::repository::{AudiobookRepository, ChapterRepository};
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_chapter_errors_are_flagged_until_the_file_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("errors.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = ChapterErrorService::new(pool);
+
+        let book = AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: "Dune".to_string(),
+            file_path: "/books/dune".to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let chapter = ChapterRepository::new(pool).create(CreateChapterDto {
+            audiobook_id: book.id.clone(),
+            chapter_number: 3,
+            title: "The Desert".to_string(),
+            file_path: "/books/dune/03.mp3".to_string(),
+            duration: None,
+            file_size: None,
+        }).await.unwrap();
+
+        assert!(service.record("/elsewhere/song.mp3", "bad header").await.unwrap().is_none(), "not in the library");
+        service.record(&chapter.file_path, "first failure").await.unwrap();
+        let flagged = service.record(&chapter.file_path, "Failed to decode").await.unwrap().unwrap();
+        assert_eq!(flagged.chapter_id.as_deref(), Some(chapter.id.as_str()));
+        assert_eq!(flagged.chapter_title.as_deref(), Some("The Desert"));
+        assert_eq!(flagged.error, "Failed to decode");
+        assert_eq!(service.for_audiobook(&book.id).await.unwrap().len(), 1);
+
+        assert!(service.clear(&chapter.file_path).await.unwrap());
+        assert!(!service.clear(&chapter.file_path).await.unwrap());
+        assert!(service.for_audiobook(&book.id).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+// Pseudo-chapters for books that are a single long file: evenly spaced markers
+// generated on request, plus markers the listener drops while listening
+
+use crate::database::models::ChapterMarker;
+use crate::database::repository::{AudiobookRepository, ChapterMarkerRepository, ChapterRepository};
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+pub const MIN_INTERVAL_MINUTES: u32 = 1;
+/// Keeps a tiny interval on a very long book from producing an unusable list
+pub const MAX_AUTO_MARKERS: i64 = 500;
+
+pub struct ChapterMarkerService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChapterMarkerService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Replace the book's generated markers with one every `interval_minutes`.
+    /// Returns all of the book's markers, including hand-placed ones.
+    pub async fn auto_chapterize(&self, audiobook_id: &str, interval_minutes: u32) -> Result<Vec<ChapterMarker>> {
+        if interval_minutes < MIN_INTERVAL_MINUTES {
+            return Err(anyhow::anyhow!("Interval must be at least {} minute", MIN_INTERVAL_MINUTES));
+        }
+
+        let duration = self.single_file_duration(audiobook_id).await?;
+        let positions = evenly_spaced(duration, interval_minutes as i64 * 60);
+        if positions.len() as i64 > MAX_AUTO_MARKERS {
+            return Err(anyhow::anyhow!(
+                "An interval of {} minutes would create {} chapters; the limit is {}",
+                interval_minutes, positions.len(), MAX_AUTO_MARKERS
+            ));
+        }
+
+        let markers: Vec<ChapterMarker> = positions
+            .into_iter()
+            .enumerate()
+            .map(|(index, position)| {
+                ChapterMarker::new(audiobook_id.to_string(), position, format!("Part {}", index + 1), true)
+            })
+            .collect();
+
+        let repo = ChapterMarkerRepository::new(self.pool);
+        repo.replace_auto(audiobook_id, &markers).await?;
+        repo.find_by_audiobook_id(audiobook_id).await
+    }
+
+    pub async fn add_marker(&self, audiobook_id: &str, position: i64, title: Option<String>) -> Result<ChapterMarker> {
+        if position < 0 {
+            return Err(anyhow::anyhow!("Marker position cannot be negative"));
+        }
+        if AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?.is_none() {
+            return Err(anyhow::anyhow!("Audiobook not found: {}", audiobook_id));
+        }
+
+        let title = title
+            .map(|title| title.trim().to_string())
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| format!("Marker at {}", format_timestamp(position)));
+        let marker = ChapterMarker::new(audiobook_id.to_string(), position, title, false);
+        ChapterMarkerRepository::new(self.pool).create(&marker).await?;
+        Ok(marker)
+    }
+
+    /// Markers only make sense inside one file; books split into chapter files
+    /// already have real chapters to navigate
+    async fn single_file_duration(&self, audiobook_id: &str) -> Result<i64> {
+        let audiobook = AudiobookRepository::new(self.pool)
+            .find_by_id(audiobook_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Audiobook not found: {}", audiobook_id))?;
+
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+        if chapters.len() > 1 {
+            return Err(anyhow::anyhow!("Audiobook already has {} chapters", chapters.len()));
+        }
+
+        audiobook
+            .duration
+            .or_else(|| chapters.first().and_then(|chapter| chapter.duration))
+            .filter(|duration| *duration > 0)
+            .ok_or_else(|| anyhow::anyhow!("Audiobook duration is unknown"))
+    }
+}
+
+/// Marker offsets from the start; a final stretch shorter than a quarter of the
+/// interval is folded into the previous part instead of getting its own marker
+fn evenly_spaced(duration: i64, interval: i64) -> Vec<i64> {
+    if duration <= 0 || interval <= 0 {
+        return Vec::new();
+    }
+
+    let mut positions: Vec<i64> = (0..duration).step_by(interval as usize).collect();
+    if positions.len() > 1 && duration - positions[positions.len() - 1] < interval / 4 {
+        positions.pop();
+    }
+    positions
+}
+
+/// A book position as h:mm:ss
+pub fn format_timestamp(seconds: i64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evenly_spaced_markers() {
+        assert_eq!(evenly_spaced(3_600, 1_200), vec![0, 1_200, 2_400]);
+        // 100s left over is too short to be its own part
+        assert_eq!(evenly_spaced(3_700, 1_200), vec![0, 1_200, 2_400]);
+        assert_eq!(evenly_spaced(3_900, 1_200), vec![0, 1_200, 2_400, 3_600]);
+        assert_eq!(evenly_spaced(300, 1_200), vec![0]);
+        assert!(evenly_spaced(0, 1_200).is_empty());
+        assert_eq!(format_timestamp(3_725), "1:02:05");
+    }
+}
@@ -0,0 +1,308 @@
+// Chapter order fixed by hand, for books whose files sort wrongly by name.
+// Reordering rewrites chapter_number in one transaction, moves the saved
+// progress along with the chapter it was in, and locks the order, so a rescan
+// of the book's folder adds new files at the end instead of sorting
+// everything by file name again.
+
+use crate::database::models::{Chapter, CreateChapterDto};
+use crate::database::repository::{AudiobookRepository, ChapterRepository};
+use crate::filesystem::FileSystemScanner;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+pub struct ChapterOrderService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChapterOrderService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Number the book's chapters in the given order and lock it. Every
+    /// chapter of the book must be listed exactly once.
+    pub async fn reorder(&self, audiobook_id: &str, ordered_ids: &[String]) -> Result<Vec<Chapter>> {
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+        let count = renumber(&mut tx, audiobook_id, ordered_ids).await?;
+        set_locked(&mut tx, audiobook_id, true).await?;
+        tx.commit().await.context("Failed to commit chapter order")?;
+
+        println!("🔢 CHAPTERS: Reordered {} chapters of {}", count, audiobook_id);
+        ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await
+    }
+
+    pub async fn set_locked(&self, audiobook_id: &str, locked: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+        set_locked(&mut tx, audiobook_id, locked).await?;
+        tx.commit().await.context("Failed to commit chapter order lock")
+    }
+
+    /// Add chapters for audio files that appeared in a folder book since it
+    /// was imported. Unless the order is locked, all chapters are numbered in
+    /// file name order again. Chapters whose files are gone are kept, after
+    /// the others.
+    pub async fn rescan(&self, audiobook_id: &str) -> Result<Vec<Chapter>> {
+        let audiobook = AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+        let directory = PathBuf::from(&audiobook.file_path);
+        let info = tokio::task::spawn_blocking(move || FileSystemScanner::new().analyze_audiobook_directory(&directory))
+            .await
+            .context("Folder scan panicked")?
+            .map_err(|e| anyhow!("Failed to analyze directory: {}", e))?;
+
+        let chapter_repo = ChapterRepository::new(self.pool);
+        let mut chapters = chapter_repo.find_by_audiobook_id(audiobook_id).await?;
+        // Single-file books have no chapter rows until a second file turns up
+        if chapters.is_empty() && info.chapters.len() < 2 {
+            return Ok(chapters);
+        }
+
+        let known: HashSet<String> = chapters.iter().map(|chapter| chapter.file_path.clone()).collect();
+        let last_number = chapters.iter().map(|chapter| chapter.chapter_number).max().unwrap_or(0);
+        let new_files = info.chapters.iter().filter(|detected| !known.contains(&detected.file_path));
+        for (next_number, detected) in (last_number + 1..).zip(new_files) {
+            chapters.push(chapter_repo.create(CreateChapterDto {
+                audiobook_id: audiobook_id.to_string(),
+                chapter_number: next_number,
+                title: detected.title.clone(),
+                file_path: detected.file_path.clone(),
+                duration: detected.duration.map(|d| d as i64),
+                file_size: Some(detected.file_size as i64),
+            }).await?);
+        }
+        let added = chapters.len() - known.len();
+
+        let current: Vec<String> = chapters.iter().map(|chapter| chapter.id.clone()).collect();
+        if !audiobook.chapter_order_locked {
+            let detected: Vec<&str> = info.chapters.iter().map(|chapter| chapter.file_path.as_str()).collect();
+            let mut order: Vec<&Chapter> = chapters.iter().collect();
+            // Stable, so chapters without a file in the folder keep their order at the end
+            order.sort_by_key(|chapter| detected.iter().position(|file| *file == chapter.file_path).unwrap_or(usize::MAX));
+            let order: Vec<String> = order.into_iter().map(|chapter| chapter.id.clone()).collect();
+            if order != current {
+                let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+                renumber(&mut tx, audiobook_id, &order).await?;
+                tx.commit().await.context("Failed to commit chapter order")?;
+            }
+        }
+
+        println!("🔢 CHAPTERS: Rescanned '{}', {} new chapters", audiobook.title, added);
+        chapter_repo.find_by_audiobook_id(audiobook_id).await
+    }
+}
+
+/// Every chapter of the book, each once
+fn check_order(chapters: &[Chapter], ordered_ids: &[String]) -> Result<()> {
+    let known: HashSet<&str> = chapters.iter().map(|chapter| chapter.id.as_str()).collect();
+    let mut seen = HashSet::new();
+    for id in ordered_ids {
+        if !known.contains(id.as_str()) {
+            bail!("Chapter {} is not part of this audiobook", id);
+        }
+        if !seen.insert(id.as_str()) {
+            bail!("Chapter {} is listed more than once", id);
+        }
+    }
+    if seen.len() != known.len() {
+        bail!("Expected all {} chapters, got {}", known.len(), seen.len());
+    }
+    Ok(())
+}
+
+/// Number the chapters 1.. in `ordered_ids` order, which must list every
+/// chapter of the book once. The chapters are read inside `tx`, so one added
+/// by a rescan in the meantime fails the check instead of being left out.
+async fn renumber(tx: &mut Transaction<'_, Sqlite>, audiobook_id: &str, ordered_ids: &[String]) -> Result<usize> {
+    let chapters = sqlx::query_as::<_, Chapter>("SELECT * FROM chapters WHERE audiobook_id = ? ORDER BY chapter_number ASC")
+        .bind(audiobook_id)
+        .fetch_all(&mut **tx)
+        .await
+        .context("Failed to load chapters")?;
+    check_order(&chapters, ordered_ids)?;
+    let now = Utc::now().to_rfc3339();
+
+    // Progress stores chapter_number - 1; older rows may hold a list position
+    let progress_index: Option<i32> = sqlx::query_scalar("SELECT COALESCE(chapter_index, 0) FROM playback_progress WHERE audiobook_id = ?")
+        .bind(audiobook_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .context("Failed to load progress")?;
+    let moved_index = progress_index.and_then(|index| {
+        let chapter = chapters.iter().find(|chapter| chapter.chapter_number == index + 1)
+            .or_else(|| chapters.get(usize::try_from(index).ok()?))?;
+        ordered_ids.iter().position(|id| *id == chapter.id).map(|position| position as i32)
+    });
+
+    // Out of the way first, below both the old numbers and the new ones:
+    // numbers are unique per book, and a swap would collide
+    let lowest = chapters.first().map_or(1, |chapter| chapter.chapter_number.min(1));
+    let highest = chapters.last().map_or(0, |chapter| chapter.chapter_number);
+    sqlx::query("UPDATE chapters SET chapter_number = chapter_number - ? WHERE audiobook_id = ?")
+        .bind(highest - lowest + 1)
+        .bind(audiobook_id)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to renumber chapters")?;
+    for (index, id) in ordered_ids.iter().enumerate() {
+        sqlx::query("UPDATE chapters SET chapter_number = ?, updated_at = ? WHERE id = ?")
+            .bind((index + 1) as i32)
+            .bind(&now)
+            .bind(id)
+            .execute(&mut **tx)
+            .await
+            .context("Failed to renumber chapters")?;
+    }
+
+    if let Some(index) = moved_index.filter(|index| Some(*index) != progress_index) {
+        sqlx::query("UPDATE playback_progress SET chapter_index = ? WHERE audiobook_id = ?")
+            .bind(index)
+            .bind(audiobook_id)
+            .execute(&mut **tx)
+            .await
+            .context("Failed to move progress")?;
+    }
+    Ok(chapters.len())
+}
+
+async fn set_locked(tx: &mut Transaction<'_, Sqlite>, audiobook_id: &str, locked: bool) -> Result<()> {
+    let result = sqlx::query("UPDATE audiobooks SET chapter_order_locked = ?, updated_at = ? WHERE id = ?")
+        .bind(locked)
+        .bind(Utc::now().to_rfc3339())
+        .bind(audiobook_id)
+        .execute(&mut **tx)
+        .await
+        .context("Failed to update chapter order lock")?;
+    if result.rows_affected() == 0 {
+        bail!("Audiobook not found: {}", audiobook_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, UpdatePlaybackProgressDto};
+    use crate::database::repository::PlaybackProgressRepository;
+    use crate::database::DatabaseManager;
+
+    async fn add_book(pool: &SqlitePool, directory: &std::path::Path, files: &[&str]) -> (String, Vec<Chapter>) {
+        let book = AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: "Stories".to_string(),
+            file_path: directory.to_string_lossy().to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let dtos = files.iter().enumerate().map(|(index, file)| CreateChapterDto {
+            audiobook_id: book.id.clone(),
+            chapter_number: (index + 1) as i32,
+            title: file.to_string(),
+            file_path: directory.join(file).to_string_lossy().to_string(),
+            duration: Some(60),
+            file_size: None,
+        }).collect();
+        let chapters = ChapterRepository::new(pool).create_multiple(dtos).await.unwrap();
+        (book.id, chapters)
+    }
+
+    fn titles(chapters: &[Chapter]) -> Vec<&str> {
+        chapters.iter().map(|chapter| chapter.title.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_reorder_renumbers_and_moves_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("order.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let (book_id, chapters) = add_book(pool, dir.path(), &["a.mp3", "b.mp3", "c.mp3"]).await;
+        // Listening to b.mp3
+        PlaybackProgressRepository::new(pool).create_or_update(&book_id, UpdatePlaybackProgressDto {
+            position: 30,
+            chapter_index: Some(1),
+            playback_speed: None,
+            is_completed: None,
+        }).await.unwrap();
+
+        let service = ChapterOrderService::new(pool);
+        let order: Vec<String> = [2, 1, 0].iter().map(|&i| chapters[i].id.clone()).collect();
+        let reordered = service.reorder(&book_id, &order).await.unwrap();
+        assert_eq!(titles(&reordered), ["c.mp3", "b.mp3", "a.mp3"]);
+        assert_eq!(reordered.iter().map(|c| c.chapter_number).collect::<Vec<_>>(), [1, 2, 3]);
+        let book = AudiobookRepository::new(pool).find_by_id(&book_id).await.unwrap().unwrap();
+        assert!(book.chapter_order_locked);
+
+        let order: Vec<String> = [1, 0, 2].iter().map(|&i| chapters[i].id.clone()).collect();
+        service.reorder(&book_id, &order).await.unwrap();
+        let index: i32 = sqlx::query_scalar("SELECT chapter_index FROM playback_progress WHERE audiobook_id = ?")
+            .bind(&book_id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(index, 0, "progress follows b.mp3 to the front");
+
+        assert!(service.reorder(&book_id, &order[..2]).await.is_err());
+        let repeated = vec![order[0].clone(), order[0].clone(), order[1].clone()];
+        assert!(service.reorder(&book_id, &repeated).await.is_err());
+        let current = ChapterRepository::new(pool).find_by_audiobook_id(&book_id).await.unwrap();
+        assert_eq!(titles(&current), ["b.mp3", "a.mp3", "c.mp3"], "a rejected order changes nothing");
+    }
+
+    #[tokio::test]
+    async fn test_reorder_renumbers_zero_and_negative_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("order.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let (book_id, chapters) = add_book(pool, dir.path(), &["a.mp3", "b.mp3", "c.mp3"]).await;
+        // Numbered 0, 1 and -1 by older imports and interrupted renumbering
+        for (chapter, number) in chapters.iter().zip([0, 1, -1]) {
+            sqlx::query("UPDATE chapters SET chapter_number = ? WHERE id = ?")
+                .bind(number)
+                .bind(&chapter.id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+
+        let order: Vec<String> = [2, 0, 1].iter().map(|&i| chapters[i].id.clone()).collect();
+        let reordered = ChapterOrderService::new(pool).reorder(&book_id, &order).await.unwrap();
+        assert_eq!(titles(&reordered), ["c.mp3", "a.mp3", "b.mp3"]);
+        assert_eq!(reordered.iter().map(|c| c.chapter_number).collect::<Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_rescan_keeps_a_locked_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("order.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let book_dir = dir.path().join("Stories");
+        std::fs::create_dir(&book_dir).unwrap();
+        for file in ["01.mp3", "02.mp3", "03.mp3"] {
+            std::fs::write(book_dir.join(file), b"").unwrap();
+        }
+        let (book_id, chapters) = add_book(pool, &book_dir, &["02.mp3", "01.mp3"]).await;
+        let service = ChapterOrderService::new(pool);
+
+        // Unlocked: file name order, with the new file
+        let rescanned = service.rescan(&book_id).await.unwrap();
+        assert_eq!(titles(&rescanned), ["01.mp3", "02.mp3", "Chapter 03"]);
+
+        let order = vec![rescanned[2].id.clone(), chapters[0].id.clone(), chapters[1].id.clone()];
+        service.reorder(&book_id, &order).await.unwrap();
+        std::fs::write(book_dir.join("00.mp3"), b"").unwrap();
+        let rescanned = service.rescan(&book_id).await.unwrap();
+        assert_eq!(titles(&rescanned)[..3], ["Chapter 03", "02.mp3", "01.mp3"]);
+        assert_eq!(rescanned[3].file_path, book_dir.join("00.mp3").to_string_lossy());
+        assert_eq!(rescanned.iter().map(|c| c.chapter_number).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,69 @@
+// The text behind TTS chapters: reading and correcting it before a chapter is
+// (re)generated, and the cleaning options applied to extracted document text
+
+use crate::database::models::TtsChapterSource;
+use crate::database::repository::{ChapterRepository, PreferencesRepository, TtsChapterSourceRepository};
+use crate::document::cleaning::{clean_text, TextCleaningOptions};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+pub const PREF_TEXT_CLEANING: &str = "tts.text_cleaning";
+
+pub struct ChapterTextService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ChapterTextService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn load_options(&self) -> TextCleaningOptions {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_TEXT_CLEANING).await.ok().flatten();
+        match stored.map(|json| serde_json::from_str::<TextCleaningOptions>(&json)) {
+            Some(Ok(options)) => options,
+            Some(Err(e)) => {
+                log::warn!("Ignoring invalid text cleaning options: {}", e);
+                TextCleaningOptions::default()
+            }
+            None => TextCleaningOptions::default(),
+        }
+    }
+
+    pub async fn save_options(&self, options: &TextCleaningOptions) -> Result<()> {
+        let json = serde_json::to_string(options).context("Failed to serialize text cleaning options")?;
+        PreferencesRepository::new(self.pool).set(PREF_TEXT_CLEANING, &json).await
+    }
+
+    /// Clean text with the saved options
+    pub async fn clean(&self, text: &str) -> String {
+        clean_text(text, &self.load_options().await)
+    }
+
+    pub async fn get(&self, chapter_id: &str) -> Result<TtsChapterSource> {
+        TtsChapterSourceRepository::new(self.pool)
+            .find(chapter_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No source text stored for chapter {}", chapter_id))
+    }
+
+    /// Replace the chapter's text; the audio is unchanged until it is regenerated.
+    /// Chapters from books created before text was kept get a source row here.
+    pub async fn update(&self, chapter_id: &str, text: &str, clean: bool) -> Result<TtsChapterSource> {
+        let text = if clean { self.clean(text).await } else { text.trim().to_string() };
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("Chapter text cannot be empty"));
+        }
+
+        let repo = TtsChapterSourceRepository::new(self.pool);
+        if repo.find(chapter_id).await?.is_some() {
+            repo.update_text(chapter_id, &text).await?;
+        } else {
+            if ChapterRepository::new(self.pool).find_by_id(chapter_id).await?.is_none() {
+                return Err(anyhow::anyhow!("Chapter not found: {}", chapter_id));
+            }
+            repo.upsert(chapter_id, &text, None, 1.0).await?;
+        }
+        self.get(chapter_id).await
+    }
+}
@@ -0,0 +1,143 @@
+// Expands a collection into playback queue tracks, book by book and chapter by chapter
+
+use crate::audio::Track;
+use crate::database::models::{Audiobook, Chapter};
+use crate::database::repository::{AudiobookRepository, ChapterRepository, CollectionRepository};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::path::Path;
+
+pub struct CollectionQueueService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> CollectionQueueService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Every playable track of the collection in collection sort order. Shuffle
+    /// reorders whole books so stories and chapters stay in sequence.
+    pub async fn build_queue(&self, collection_id: &str, shuffle: bool) -> Result<Vec<Track>> {
+        let collections = CollectionRepository::new(self.pool);
+        collections.find_by_id(collection_id).await?
+            .with_context(|| format!("Collection not found: {}", collection_id))?;
+
+        let mut audiobooks = collections.get_collection_audiobooks(collection_id).await?;
+        if shuffle {
+            audiobooks.sort_by_cached_key(|_| uuid::Uuid::new_v4());
+        }
+
+        let chapter_repo = ChapterRepository::new(self.pool);
+        let mut tracks = Vec::new();
+        for audiobook in &audiobooks {
+            let chapters = chapter_repo.find_by_audiobook_id(&audiobook.id).await?;
+            let book_tracks = book_tracks(audiobook, chapters);
+            if book_tracks.is_empty() {
+                log::warn!("Skipping '{}' in collection queue: no playable files", audiobook.title);
+            }
+            tracks.extend(book_tracks);
+        }
+
+        Ok(tracks)
+    }
+
+    /// The playable tracks of a single book, chapter by chapter
+    pub async fn audiobook_queue(&self, audiobook_id: &str) -> Result<Vec<Track>> {
+        let audiobook = AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+        Ok(book_tracks(&audiobook, chapters))
+    }
+}
+
+fn book_tracks(audiobook: &Audiobook, mut chapters: Vec<Chapter>) -> Vec<Track> {
+    if chapters.is_empty() {
+        // Single-file books have no chapter rows; queue the book file itself
+        if !Path::new(&audiobook.file_path).is_file() {
+            return Vec::new();
+        }
+        return vec![Track {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_path: audiobook.file_path.clone(),
+            title: Some(audiobook.title.clone()),
+            duration: audiobook.duration.map(|seconds| seconds.max(0) as u64),
+            audiobook_id: Some(audiobook.id.clone()),
+        }];
+    }
+
+    chapters.sort_by_key(|chapter| chapter.chapter_number);
+    chapters
+        .into_iter()
+        .map(|chapter| Track {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: Some(format!("{} - {}", audiobook.title, chapter.title)),
+            file_path: chapter.file_path,
+            duration: chapter.duration.map(|seconds| seconds.max(0) as u64),
+            audiobook_id: Some(audiobook.id.clone()),
+        })
+        .collect()
+}
+
+/// The queue with the book's chapters after `current_file` put back in the
+/// book's order, where the first of them was queued. None when the current
+/// file is not one of the book's or nothing of the book is queued.
+pub fn requeue_book(queue: &[Track], current_file: &str, book_tracks: &[Track]) -> Option<Vec<Track>> {
+    let current = book_tracks.iter().position(|track| track.file_path == current_file)?;
+    let book_files: HashSet<&str> = book_tracks.iter().map(|track| track.file_path.as_str()).collect();
+    let insert_at = queue.iter().position(|track| book_files.contains(track.file_path.as_str()))?;
+
+    let mut requeued: Vec<Track> = queue.iter()
+        .filter(|track| !book_files.contains(track.file_path.as_str()))
+        .cloned()
+        .collect();
+    requeued.splice(insert_at..insert_at, book_tracks[current + 1..].iter().cloned());
+    Some(requeued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chapters_are_queued_in_order() {
+        let audiobook = Audiobook::new("Stories".to_string(), "/books/stories".to_string());
+        let chapters = vec![
+            Chapter::new(audiobook.id.clone(), 2, "Second".to_string(), "/books/stories/2.mp3".to_string()),
+            Chapter::new(audiobook.id.clone(), 1, "First".to_string(), "/books/stories/1.mp3".to_string()),
+        ];
+
+        let tracks = book_tracks(&audiobook, chapters);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].file_path, "/books/stories/1.mp3");
+        assert_eq!(tracks[1].title.as_deref(), Some("Stories - Second"));
+        assert!(tracks.iter().all(|track| track.audiobook_id.as_deref() == Some(audiobook.id.as_str())));
+    }
+
+    #[test]
+    fn test_requeue_puts_the_rest_of_the_book_in_its_new_order() {
+        let track = |file_path: &str| Track {
+            id: file_path.to_string(),
+            file_path: file_path.to_string(),
+            title: None,
+            duration: None,
+            audiobook_id: None,
+        };
+        let book: Vec<Track> = ["/b/3.mp3", "/b/1.mp3", "/b/2.mp3", "/b/4.mp3"].into_iter().map(track).collect();
+        let queue: Vec<Track> = ["/b/2.mp3", "/b/3.mp3", "/b/4.mp3", "/other.mp3"].into_iter().map(track).collect();
+
+        let requeued = requeue_book(&queue, "/b/1.mp3", &book).unwrap();
+        let files: Vec<&str> = requeued.iter().map(|track| track.file_path.as_str()).collect();
+        assert_eq!(files, ["/b/2.mp3", "/b/4.mp3", "/other.mp3"]);
+
+        assert!(requeue_book(&queue, "/other.mp3", &book).is_none());
+        assert!(requeue_book(&queue[3..], "/b/1.mp3", &book).is_none());
+    }
+
+    #[test]
+    fn test_book_without_chapters_or_file_is_skipped() {
+        let audiobook = Audiobook::new("Missing".to_string(), "/nonexistent/book.mp3".to_string());
+        assert!(book_tracks(&audiobook, Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+// Shareable collection files: a JSON list of books that can be matched against
+// another library, with LibriVox identifiers for anything that has to be fetched
+
+use crate::database::models::{Audiobook, Collection, CreateCollectionDto};
+use crate::database::repository::{AudiobookRepository, CollectionRepository};
+use crate::services::audiobook_source_service::{SOURCE_LIBRIVOX, URL_LIST_FOLDER_PREFIX};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::Path;
+use ts_rs::TS;
+
+pub const SHARED_COLLECTION_FORMAT: &str = "audiovibe-collection";
+pub const SHARED_COLLECTION_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SharedCollection {
+    pub format: String,
+    pub version: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub exported_at: String,
+    pub books: Vec<SharedCollectionBook>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SharedCollectionBook {
+    pub title: String,
+    pub author: Option<String>,
+    pub narrator: Option<String>,
+    /// Archive.org identifier of the LibriVox recording, when the book came from LibriVox
+    pub librivox_identifier: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CollectionImportReport {
+    pub collection: Collection,
+    pub matched: Vec<String>, // Audiobook ids added to the collection
+    /// Entries not in the library; those with a LibriVox identifier can be downloaded
+    pub missing: Vec<SharedCollectionBook>,
+}
+
+pub struct CollectionShareService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> CollectionShareService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// `librivox_dir` is the download cache that LibriVox books are stored in
+    pub async fn export(&self, collection_id: &str, librivox_dir: &Path) -> Result<SharedCollection> {
+        let repo = CollectionRepository::new(self.pool);
+        let collection = repo.find_by_id(collection_id).await?
+            .with_context(|| format!("Collection not found: {}", collection_id))?;
+        let audiobooks = repo.get_collection_audiobooks(collection_id).await?;
+
+        Ok(SharedCollection {
+            format: SHARED_COLLECTION_FORMAT.to_string(),
+            version: SHARED_COLLECTION_VERSION,
+            name: collection.name,
+            description: collection.description,
+            color: Some(collection.color),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            books: audiobooks
+                .iter()
+                .map(|audiobook| SharedCollectionBook {
+                    title: audiobook.title.clone(),
+                    author: audiobook.author.clone(),
+                    narrator: audiobook.narrator.clone(),
+                    librivox_identifier: librivox_identifier(audiobook, librivox_dir),
+                })
+                .collect(),
+        })
+    }
+
+    /// Create a new collection from a shared file, adding every book already in the library
+    pub async fn import(&self, shared: SharedCollection, librivox_dir: &Path) -> Result<CollectionImportReport> {
+        if shared.format != SHARED_COLLECTION_FORMAT {
+            return Err(anyhow::anyhow!("Not an AudioVibe collection file"));
+        }
+        if shared.version > SHARED_COLLECTION_VERSION {
+            return Err(anyhow::anyhow!("Collection file version {} is newer than this app supports", shared.version));
+        }
+
+        let library = AudiobookRepository::new(self.pool).find_all().await?;
+        let repo = CollectionRepository::new(self.pool);
+        let collection = repo.create(CreateCollectionDto {
+            name: shared.name,
+            description: shared.description,
+            color: shared.color,
+            parent_collection_id: None,
+        }).await?;
+
+        let mut matched = Vec::new();
+        let mut missing = Vec::new();
+        for book in shared.books {
+            match find_match(&book, &library, librivox_dir) {
+                Some(audiobook) if !matched.contains(&audiobook.id) => {
+                    repo.add_audiobook_to_collection(&collection.id, &audiobook.id).await?;
+                    matched.push(audiobook.id.clone());
+                }
+                Some(_) => {}
+                None => missing.push(book),
+            }
+        }
+
+        println!("📚 COLLECTION IMPORT: '{}' matched {} books, {} missing", collection.name, matched.len(), missing.len());
+        Ok(CollectionImportReport { collection, matched, missing })
+    }
+}
+
+/// The identifier recorded at import, or for older books the name of the cache
+/// folder LibriVox downloads live in, which is their Archive.org identifier
+pub(crate) fn librivox_identifier(audiobook: &Audiobook, librivox_dir: &Path) -> Option<String> {
+    if audiobook.source_type.as_deref() == Some(SOURCE_LIBRIVOX) {
+        return audiobook.source_id.clone();
+    }
+
+    let path = Path::new(&audiobook.file_path);
+    let folder = if path.is_file() { path.parent()? } else { path };
+    if folder.parent()? != librivox_dir {
+        return None;
+    }
+
+    let name = folder.file_name()?.to_string_lossy().to_string();
+    // URL-list imports share the cache but are not Archive.org items
+    if name.starts_with(URL_LIST_FOLDER_PREFIX) {
+        return None;
+    }
+    Some(name)
+}
+
+pub(crate) fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Match by LibriVox identifier first, then by title plus author when both sides have one
+pub(crate) fn find_match<'b>(book: &SharedCollectionBook, library: &'b [Audiobook], librivox_dir: &Path) -> Option<&'b Audiobook> {
+    if let Some(identifier) = &book.librivox_identifier {
+        let by_identifier = library
+            .iter()
+            .find(|audiobook| librivox_identifier(audiobook, librivox_dir).as_deref() == Some(identifier.as_str()));
+        if by_identifier.is_some() {
+            return by_identifier;
+        }
+    }
+
+    let title = normalize(&book.title);
+    let author = book.author.as_deref().map(normalize);
+    library.iter().find(|audiobook| {
+        normalize(&audiobook.title) == title
+            && match (&author, audiobook.author.as_deref()) {
+                (Some(author), Some(other)) => normalize(other) == *author,
+                _ => true,
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(title: &str, author: Option<&str>, file_path: &str) -> Audiobook {
+        let mut audiobook = Audiobook::new(title.to_string(), file_path.to_string());
+        audiobook.author = author.map(str::to_string);
+        audiobook
+    }
+
+    #[test]
+    fn test_librivox_identifier_from_cache_folder() {
+        let cache = Path::new("/cache/librivox");
+        assert_eq!(
+            librivox_identifier(&book("A", None, "/cache/librivox/picturedoriangr_1608_librivox"), cache).as_deref(),
+            Some("picturedoriangr_1608_librivox")
+        );
+        assert!(librivox_identifier(&book("A", None, "/cache/librivox/urls_abc"), cache).is_none());
+        assert!(librivox_identifier(&book("A", None, "/home/me/books/A"), cache).is_none());
+    }
+
+    #[test]
+    fn test_find_match_prefers_identifier_then_title_and_author() {
+        let cache = Path::new("/cache/librivox");
+        let library = vec![
+            book("Dracula", Some("Bram Stoker"), "/books/dracula"),
+            book("Dracula (Version 2)", Some("Bram Stoker"), "/cache/librivox/dracula_v2_librivox"),
+        ];
+
+        let shared = SharedCollectionBook {
+            title: "Dracula".to_string(),
+            author: Some("Bram Stoker".to_string()),
+            narrator: None,
+            librivox_identifier: Some("dracula_v2_librivox".to_string()),
+        };
+        assert_eq!(find_match(&shared, &library, cache).unwrap().title, "Dracula (Version 2)");
+
+        let shared = SharedCollectionBook {
+            title: "dracula!".to_string(),
+            author: Some("bram  stoker".to_string()),
+            narrator: None,
+            librivox_identifier: None,
+        };
+        assert_eq!(find_match(&shared, &library, cache).unwrap().title, "Dracula");
+
+        let shared = SharedCollectionBook { author: Some("Someone Else".to_string()), ..shared };
+        assert!(find_match(&shared, &library, cache).is_none());
+    }
+}
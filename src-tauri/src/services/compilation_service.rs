@@ -0,0 +1,245 @@
+// LibriVox compilations put several works in one recording: short story
+// collections, poetry anthologies, dramatic readings split into acts. The
+// section metadata Archive.org keeps for each file says which work a section
+// belongs to, so the import can offer one audiobook per work. The grouping is
+// only a proposal; the user confirms or rearranges it before importing.
+
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+/// One file of the recording, as Archive.org describes it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CompilationSection {
+    /// The file's name on Archive.org
+    pub file_name: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub track: Option<u32>,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CompilationWork {
+    pub title: String,
+    /// None falls back to the recording's author
+    pub author: Option<String>,
+    /// Archive.org file names, in playing order
+    pub file_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CompilationPlan {
+    pub identifier: String,
+    /// Whether the sections look like more than one work
+    pub is_compilation: bool,
+    pub sections: Vec<CompilationSection>,
+    /// The proposed grouping, one entry per work
+    pub works: Vec<CompilationWork>,
+}
+
+/// Propose a grouping for a recording from its Archive.org file list
+pub fn plan(identifier: &str, files: &[Value]) -> CompilationPlan {
+    let sections = parse_sections(files);
+    let works = group_works(&sections);
+    CompilationPlan {
+        identifier: identifier.to_string(),
+        is_compilation: looks_like_compilation(&works),
+        sections,
+        works,
+    }
+}
+
+/// Check a grouping the user confirmed: every work needs a title and files,
+/// and each file belongs to at most one work. Files left out are not imported.
+pub fn check_works(sections: &[CompilationSection], works: &[CompilationWork]) -> Result<()> {
+    anyhow::ensure!(!works.is_empty(), "No works to import");
+    let known: HashSet<&str> = sections.iter().map(|section| section.file_name.as_str()).collect();
+    let mut used = HashSet::new();
+    for work in works {
+        anyhow::ensure!(!work.title.trim().is_empty(), "Every work needs a title");
+        anyhow::ensure!(!work.file_names.is_empty(), "'{}' has no sections", work.title);
+        for file_name in &work.file_names {
+            anyhow::ensure!(known.contains(file_name.as_str()), "'{}' is not part of this recording", file_name);
+            anyhow::ensure!(used.insert(file_name.as_str()), "'{}' is in more than one work", file_name);
+        }
+    }
+    Ok(())
+}
+
+/// The MP3 sections in track order. LibriVox uploads an M4B of the whole
+/// recording alongside them, which would only repeat the sections.
+fn parse_sections(files: &[Value]) -> Vec<CompilationSection> {
+    let text = |file: &Value, key: &str| {
+        file.get(key)
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut sections: Vec<CompilationSection> = files
+        .iter()
+        .filter_map(|file| {
+            let file_name = text(file, "name")?;
+            if !file_name.to_lowercase().ends_with(".mp3") {
+                return None;
+            }
+            let title = text(file, "title").unwrap_or_else(|| {
+                std::path::Path::new(&file_name).file_stem().unwrap_or_default().to_string_lossy().replace('_', " ")
+            });
+            Some(CompilationSection {
+                title,
+                author: text(file, "creator").or_else(|| text(file, "artist")),
+                // Tracks may be given as "3" or "3/12"
+                track: text(file, "track").and_then(|track| track.split('/').next()?.trim().parse().ok()),
+                duration: text(file, "length").and_then(|length| parse_length(&length)),
+                file_name,
+            })
+        })
+        .collect();
+    sections.sort_by(|a, b| a.track.unwrap_or(u32::MAX).cmp(&b.track.unwrap_or(u32::MAX)).then_with(|| a.file_name.cmp(&b.file_name)));
+    sections
+}
+
+/// Archive.org lengths are seconds ("1234.56") or a clock ("20:34", "1:02:03")
+fn parse_length(length: &str) -> Option<i64> {
+    if let Ok(seconds) = length.parse::<f64>() {
+        return Some(seconds.round() as i64);
+    }
+    length
+        .split(':')
+        .try_fold(0i64, |total, part| part.trim().parse::<f64>().ok().map(|part| total * 60 + part as i64))
+}
+
+/// Consecutive sections of the same work by the same author form one work.
+/// Sections with only a part marker for a title ("Act 2") continue the work before them.
+fn group_works(sections: &[CompilationSection]) -> Vec<CompilationWork> {
+    let mut works: Vec<(String, CompilationWork)> = Vec::new();
+    for section in sections {
+        let title = work_title(&section.title);
+        let key = title.to_lowercase();
+        if let Some((last_key, last)) = works.last_mut() {
+            let same_author = match (&last.author, &section.author) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => true,
+            };
+            if same_author && (key.is_empty() || key == *last_key) {
+                last.file_names.push(section.file_name.clone());
+                if last.author.is_none() {
+                    last.author = section.author.clone();
+                }
+                continue;
+            }
+        }
+        works.push((
+            key,
+            CompilationWork {
+                title: if title.is_empty() { section.title.clone() } else { title },
+                author: section.author.clone(),
+                file_names: vec![section.file_name.clone()],
+            },
+        ));
+    }
+    works.into_iter().map(|(_, work)| work).collect()
+}
+
+/// The section title without its leading number and its part marker:
+/// "03 - The Canterville Ghost, Part 2" is "The Canterville Ghost"
+fn work_title(section_title: &str) -> String {
+    static NUMBER: OnceLock<Regex> = OnceLock::new();
+    static PART: OnceLock<Regex> = OnceLock::new();
+    let number = NUMBER.get_or_init(|| Regex::new(r"^\s*\d+\s*[-–—.:)]\s*").unwrap());
+    let part = PART.get_or_init(|| {
+        Regex::new(r"(?i)[\s,:;\-–—(\[]*\b(part|pt|act|scene|section|chapter|book|canto|volume|vol|episode)\b\.?\s*([0-9]+|[ivxlc]+|one|two|three|four|five|six|seven|eight|nine|ten)\b.*$").unwrap()
+    });
+    let title = number.replace(section_title, "");
+    part.replace(&title, "").trim().trim_end_matches([',', ':', ';', '-', '–', '—']).trim().to_string()
+}
+
+/// More than one work, and either several authors or works spanning several
+/// sections. A novel with named chapters gives one "work" per chapter, all by
+/// the same author and one section each, and is not a compilation.
+fn looks_like_compilation(works: &[CompilationWork]) -> bool {
+    if works.len() < 2 {
+        return false;
+    }
+    let authors: HashSet<String> = works.iter().filter_map(|work| work.author.as_ref()).map(|author| author.to_lowercase()).collect();
+    authors.len() > 1 || works.iter().any(|work| work.file_names.len() > 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, title: &str, creator: &str, track: &str) -> Value {
+        serde_json::json!({ "name": name, "title": title, "creator": creator, "track": track, "length": "600.4" })
+    }
+
+    #[test]
+    fn test_plan_groups_works_of_a_collection() {
+        let files = vec![
+            file("ghost_2.mp3", "The Canterville Ghost, Part 2", "Oscar Wilde", "2/5"),
+            file("ghost_1.mp3", "The Canterville Ghost, Part 1", "Oscar Wilde", "1/5"),
+            file("raven.mp3", "03 - The Raven", "Edgar Allan Poe", "3/5"),
+            file("earnest_1.mp3", "The Importance of Being Earnest - Act I", "Oscar Wilde", "4/5"),
+            file("earnest_2.mp3", "Act II", "", "5/5"),
+            serde_json::json!({ "name": "collection.m4b", "title": "Whole recording" }),
+        ];
+        let plan = plan("shortworks_librivox", &files);
+        assert!(plan.is_compilation);
+        assert_eq!(plan.sections.len(), 5);
+        assert_eq!(plan.sections[0].file_name, "ghost_1.mp3");
+        assert_eq!(plan.sections[0].duration, Some(600));
+
+        let titles: Vec<&str> = plan.works.iter().map(|work| work.title.as_str()).collect();
+        assert_eq!(titles, vec!["The Canterville Ghost", "The Raven", "The Importance of Being Earnest"]);
+        assert_eq!(plan.works[0].file_names, vec!["ghost_1.mp3", "ghost_2.mp3"]);
+        assert_eq!(plan.works[1].author.as_deref(), Some("Edgar Allan Poe"));
+        assert_eq!(plan.works[2].file_names, vec!["earnest_1.mp3", "earnest_2.mp3"]);
+        check_works(&plan.sections, &plan.works).unwrap();
+    }
+
+    #[test]
+    fn test_novel_is_not_a_compilation() {
+        let files = vec![
+            file("ch1.mp3", "Chapter 1", "Jane Austen", "1"),
+            file("ch2.mp3", "Chapter 2", "Jane Austen", "2"),
+        ];
+        let plan = plan("novel_librivox", &files);
+        assert_eq!(plan.works.len(), 1);
+        assert!(!plan.is_compilation);
+
+        let named = vec![
+            file("a.mp3", "The Storm", "Jane Austen", "1"),
+            file("b.mp3", "The Calm", "Jane Austen", "2"),
+        ];
+        assert!(!super::plan("novel_librivox", &named).is_compilation);
+    }
+
+    #[test]
+    fn test_check_works_rejects_bad_groupings() {
+        let plan = plan("x", &[file("a.mp3", "A", "", "1"), file("b.mp3", "B", "", "2")]);
+        let work = |title: &str, files: &[&str]| CompilationWork {
+            title: title.to_string(),
+            author: None,
+            file_names: files.iter().map(|name| name.to_string()).collect(),
+        };
+        assert!(check_works(&plan.sections, &[work("A", &["a.mp3"])]).is_ok());
+        assert!(check_works(&plan.sections, &[work("A", &["a.mp3"]), work("B", &["a.mp3"])]).is_err());
+        assert!(check_works(&plan.sections, &[work("A", &["c.mp3"])]).is_err());
+        assert!(check_works(&plan.sections, &[work(" ", &["a.mp3"])]).is_err());
+        assert!(check_works(&plan.sections, &[]).is_err());
+
+        assert_eq!(parse_length("1:02:03"), Some(3723));
+        assert_eq!(parse_length("soon"), None);
+    }
+}
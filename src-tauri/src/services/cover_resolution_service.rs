@@ -0,0 +1,226 @@
+// Cover art for audiobooks, tried from the most to the least trustworthy
+// source: art embedded in the audio, an image next to the files, the
+// Archive.org thumbnail of a LibriVox item, then an OpenLibrary lookup.
+
+use crate::audio::tags::read_embedded_cover;
+use crate::database::models::Audiobook;
+use crate::database::repository::{AudiobookRepository, ChapterRepository};
+use crate::filesystem::atomic;
+use crate::filesystem::FileSystemScanner;
+use crate::services::audiobook_source_service::SOURCE_LIBRIVOX;
+use anyhow::{Context, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub const COVER_SOURCE_EMBEDDED: &str = "embedded";
+pub const COVER_SOURCE_FOLDER: &str = "folder";
+pub const COVER_SOURCE_ARCHIVE_ORG: &str = "archive_org";
+pub const COVER_SOURCE_OPEN_LIBRARY: &str = "open_library";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CoverResult {
+    pub cover_image_path: String,
+    /// Which step of the chain found the cover
+    pub source: String,
+}
+
+pub struct CoverResolutionService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> CoverResolutionService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Find a cover for the book and store it with its source. Downloaded and
+    /// embedded images are saved in `covers_dir`. Without `online` only the
+    /// book's own files are looked at. Returns None when no source had one.
+    pub async fn fetch_cover(&self, audiobook_id: &str, covers_dir: &Path, online: bool) -> Result<Option<CoverResult>> {
+        let repo = AudiobookRepository::new(self.pool);
+        let audiobook = repo.find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+
+        let Some(result) = self.resolve(&audiobook, covers_dir, online).await else {
+            println!("📸 COVER: No cover found for '{}'", audiobook.title);
+            return Ok(None);
+        };
+        repo.set_cover(audiobook_id, &result.cover_image_path, &result.source).await?;
+        println!("📸 COVER: Using {} cover for '{}'", result.source, audiobook.title);
+        Ok(Some(result))
+    }
+
+    async fn resolve(&self, audiobook: &Audiobook, covers_dir: &Path, online: bool) -> Option<CoverResult> {
+        if let Some(audio_file) = self.first_audio_file(audiobook).await {
+            match read_embedded_cover(&audio_file) {
+                Ok(Some((data, extension))) => {
+                    match save_cover(covers_dir, &audiobook.id, extension, &data) {
+                        Ok(path) => return Some(found(path, COVER_SOURCE_EMBEDDED)),
+                        Err(e) => log::warn!("Failed to save embedded cover: {}", e),
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Could not read embedded cover of {}: {}", audio_file.display(), e),
+            }
+        }
+
+        // Only folder imports; a single file's parent may be an unrelated folder
+        let folder = Path::new(&audiobook.file_path);
+        if let Some(image) = folder.is_dir().then(|| FileSystemScanner::new().find_cover_art(folder)).flatten() {
+            return Some(found(image, COVER_SOURCE_FOLDER));
+        }
+        if !online {
+            return None;
+        }
+
+        if let (Some(SOURCE_LIBRIVOX), Some(identifier)) = (audiobook.source_type.as_deref(), audiobook.source_id.as_deref()) {
+            let url = format!("https://archive.org/services/img/{}", identifier);
+            match download_cover(&url, covers_dir, &audiobook.id).await {
+                Ok(path) => return Some(found(path, COVER_SOURCE_ARCHIVE_ORG)),
+                Err(e) => log::warn!("No Archive.org cover for {}: {}", identifier, e),
+            }
+        }
+
+        match open_library_lookup(&audiobook.title, audiobook.author.as_deref()).await {
+            Ok(Some(url)) => match download_cover(&url, covers_dir, &audiobook.id).await {
+                Ok(path) => Some(found(path, COVER_SOURCE_OPEN_LIBRARY)),
+                Err(e) => {
+                    log::warn!("Failed to download OpenLibrary cover: {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                log::warn!("OpenLibrary lookup failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The first chapter's file, or the first audio file of a folder import
+    async fn first_audio_file(&self, audiobook: &Audiobook) -> Option<PathBuf> {
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(&audiobook.id).await.ok()?;
+        if let Some(chapter) = chapters.first() {
+            return Some(PathBuf::from(&chapter.file_path));
+        }
+
+        let path = Path::new(&audiobook.file_path);
+        if path.is_file() {
+            return Some(path.to_path_buf());
+        }
+        let scanner = FileSystemScanner::new();
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| scanner.is_supported_audio_file(path))
+            .collect();
+        files.sort();
+        files.into_iter().next()
+    }
+}
+
+fn found(path: PathBuf, source: &str) -> CoverResult {
+    CoverResult {
+        cover_image_path: path.to_string_lossy().to_string(),
+        source: source.to_string(),
+    }
+}
+
+fn save_cover(covers_dir: &Path, audiobook_id: &str, extension: &str, data: &[u8]) -> Result<PathBuf> {
+    std::fs::create_dir_all(covers_dir).context("Failed to create covers directory")?;
+    let path = covers_dir.join(format!("{}.{}", audiobook_id, extension));
+    atomic::write(&path, data).context("Failed to save cover image")?;
+    Ok(path)
+}
+
+async fn download_cover(url: &str, covers_dir: &Path, audiobook_id: &str) -> Result<PathBuf> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .header("User-Agent", "AudioVibe/1.0.0")
+        .send()
+        .await
+        .context("Failed to download cover")?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("Cover download failed with status: {}", response.status()));
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    let extension = match content_type.as_str() {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/jpeg" | "image/jpg" => "jpg",
+        _ => return Err(anyhow::anyhow!("Cover URL did not return an image ({})", content_type)),
+    };
+    let bytes = response.bytes().await.context("Failed to read cover image")?;
+    save_cover(covers_dir, audiobook_id, extension, &bytes)
+}
+
+async fn open_library_lookup(title: &str, author: Option<&str>) -> Result<Option<String>> {
+    let mut url = Url::parse("https://openlibrary.org/search.json").expect("static URL");
+    url.query_pairs_mut()
+        .append_pair("title", title)
+        .append_pair("fields", "cover_i,isbn")
+        .append_pair("limit", "1");
+    if let Some(author) = author.filter(|author| !author.is_empty() && *author != "Unknown Author") {
+        url.query_pairs_mut().append_pair("author", author);
+    }
+
+    let json: Value = reqwest::Client::new()
+        .get(url)
+        .header("User-Agent", "AudioVibe/1.0.0")
+        .send()
+        .await
+        .context("Failed to search OpenLibrary")?
+        .json()
+        .await
+        .context("Failed to parse OpenLibrary response")?;
+    Ok(open_library_cover_url(&json))
+}
+
+/// Prefer the cover id of the best match, falling back to its first ISBN
+fn open_library_cover_url(search: &Value) -> Option<String> {
+    let doc = search.get("docs")?.as_array()?.first()?;
+    if let Some(cover_id) = doc.get("cover_i").and_then(|id| id.as_i64()) {
+        return Some(format!("https://covers.openlibrary.org/b/id/{}-L.jpg", cover_id));
+    }
+    let isbn = doc.get("isbn")?.as_array()?.first()?.as_str()?;
+    // default=false turns a missing cover into a 404 instead of a blank image
+    Some(format!("https://covers.openlibrary.org/b/isbn/{}-L.jpg?default=false", isbn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_library_cover_url() {
+        let with_cover = serde_json::json!({ "docs": [{ "cover_i": 8231856, "isbn": ["9780141439570"] }] });
+        assert_eq!(
+            open_library_cover_url(&with_cover).as_deref(),
+            Some("https://covers.openlibrary.org/b/id/8231856-L.jpg")
+        );
+
+        let isbn_only = serde_json::json!({ "docs": [{ "isbn": ["9780141439570", "0141439572"] }] });
+        assert_eq!(
+            open_library_cover_url(&isbn_only).as_deref(),
+            Some("https://covers.openlibrary.org/b/isbn/9780141439570-L.jpg?default=false")
+        );
+
+        assert_eq!(open_library_cover_url(&serde_json::json!({ "docs": [] })), None);
+        assert_eq!(open_library_cover_url(&serde_json::json!({ "docs": [{}] })), None);
+    }
+}
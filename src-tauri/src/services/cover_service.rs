@@ -0,0 +1,149 @@
+// Cover art generation for collections: an SVG collage of member book covers
+
+use crate::database::repository::CollectionRepository;
+use crate::filesystem::atomic;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+/// Collages use at most this many member covers
+pub const COLLAGE_TILES: usize = 4;
+const COLLAGE_SIZE: u32 = 400;
+
+pub struct CoverService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> CoverService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Regenerate the collage unless the user picked a cover for this collection
+    pub async fn refresh_collection_cover(&self, collection_id: &str, covers_dir: &Path) -> Result<Option<PathBuf>> {
+        let collection = CollectionRepository::new(self.pool).find_by_id(collection_id).await?
+            .with_context(|| format!("Collection not found: {}", collection_id))?;
+        if collection.cover_is_custom {
+            return Ok(collection.cover_image_path.map(PathBuf::from));
+        }
+
+        self.generate_collection_collage(collection_id, covers_dir).await
+    }
+
+    /// Build a collage from the first member covers in collection order and store
+    /// it as the collection cover. Collections without any usable cover get none.
+    pub async fn generate_collection_collage(&self, collection_id: &str, covers_dir: &Path) -> Result<Option<PathBuf>> {
+        let repo = CollectionRepository::new(self.pool);
+        let audiobooks = repo.get_collection_audiobooks(collection_id).await?;
+
+        let hrefs: Vec<String> = audiobooks
+            .iter()
+            .filter_map(|audiobook| audiobook.cover_image_path.as_deref())
+            .filter_map(cover_href)
+            .take(COLLAGE_TILES)
+            .collect();
+
+        let collage_path = covers_dir.join(format!("collection_{}.svg", collection_id));
+        if hrefs.is_empty() {
+            let _ = std::fs::remove_file(&collage_path);
+            repo.set_cover(collection_id, None, false).await?;
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(covers_dir).context("Failed to create covers directory")?;
+        atomic::write(&collage_path, build_collage_svg(&hrefs))
+            .context("Failed to write collection collage")?;
+
+        let path_str = collage_path.to_string_lossy().to_string();
+        repo.set_cover(collection_id, Some(&path_str), false).await?;
+        println!("🎨 COLLECTION COVER: Generated collage of {} covers at {}", hrefs.len(), path_str);
+
+        Ok(Some(collage_path))
+    }
+}
+
+/// Turn a stored cover reference into something an SVG <image> can load.
+/// Local files are inlined so the collage keeps working if the book moves.
+fn cover_href(cover: &str) -> Option<String> {
+    if cover.starts_with("data:") || cover.starts_with("http://") || cover.starts_with("https://") {
+        return Some(cover.to_string());
+    }
+
+    let path = Path::new(cover);
+    let data = std::fs::read(path).ok()?;
+    let mime_type = match path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase().as_str() {
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        _ => "image/jpeg",
+    };
+    Some(format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(data)))
+}
+
+/// Tile layout: one cover fills the square, two split it vertically, three put one
+/// tall cover beside two small ones, four make a grid
+fn collage_tiles(count: usize) -> Vec<(u32, u32, u32, u32)> {
+    let full = COLLAGE_SIZE;
+    let half = COLLAGE_SIZE / 2;
+    match count {
+        0 => Vec::new(),
+        1 => vec![(0, 0, full, full)],
+        2 => vec![(0, 0, half, full), (half, 0, half, full)],
+        3 => vec![(0, 0, half, full), (half, 0, half, half), (half, half, half, half)],
+        _ => vec![(0, 0, half, half), (half, 0, half, half), (0, half, half, half), (half, half, half, half)],
+    }
+}
+
+fn build_collage_svg(hrefs: &[String]) -> String {
+    let mut svg = format!(
+        r#"<svg width="{size}" height="{size}" viewBox="0 0 {size} {size}" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">"#,
+        size = COLLAGE_SIZE
+    );
+    svg.push_str(&format!(r##"<rect width="{0}" height="{0}" fill="#1f2937"/>"##, COLLAGE_SIZE));
+    for (href, (x, y, width, height)) in hrefs.iter().zip(collage_tiles(hrefs.len())) {
+        svg.push_str(&format!(
+            r#"<image x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="xMidYMid slice" href="{}" xlink:href="{}"/>"#,
+            x, y, width, height, escape_attribute(href), escape_attribute(href)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collage_layouts_cover_the_square() {
+        for count in 1..=COLLAGE_TILES {
+            let area: u32 = collage_tiles(count).iter().map(|(_, _, w, h)| w * h).sum();
+            assert_eq!(area, COLLAGE_SIZE * COLLAGE_SIZE, "layout for {} covers", count);
+        }
+    }
+
+    #[test]
+    fn test_collage_inlines_local_files_and_escapes_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let cover = dir.path().join("cover.png");
+        std::fs::write(&cover, b"png").unwrap();
+
+        let local = cover_href(cover.to_str().unwrap()).unwrap();
+        assert_eq!(local, "data:image/png;base64,cG5n");
+        assert!(cover_href("/nonexistent/cover.jpg").is_none());
+
+        let svg = build_collage_svg(&[local, "https://example.com/a.jpg?w=1&h=2".to_string()]);
+        assert_eq!(svg.matches("<image ").count(), 2);
+        assert!(svg.contains("a.jpg?w=1&amp;h=2"));
+    }
+}
@@ -0,0 +1,239 @@
+// The weekly digest: how much was listened to in the past week, which books
+// were finished, what followed authors released, and which books have stalled
+// and could be picked up again. The maintenance scheduler compiles one a week
+// and keeps it, so it can be shown whenever the listener next looks.
+
+use crate::database::models::DailyListening;
+use crate::services::follow_service::{FollowKind, FollowService, ReleaseAlert};
+use crate::services::RetentionService;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+pub const DIGEST_PERIOD_DAYS: i64 = 7;
+/// A book in progress that has not been played for this long has stalled
+pub const STALLED_AFTER_DAYS: i64 = 14;
+const STALLED_LIMIT: i64 = 5;
+const DIGESTS_KEPT: i64 = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, sqlx::FromRow)]
+#[ts(export)]
+pub struct DigestBook {
+    pub audiobook_id: String,
+    pub title: String,
+    pub author: Option<String>,
+    /// How far through the book, 0 to 1
+    pub progress: f64,
+    pub last_played_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct WeeklyDigest {
+    pub id: String,
+    /// The week covered, from the start of one UTC day to the start of the
+    /// day a week later
+    pub period_start: String,
+    pub period_end: String,
+    #[ts(type = "number")]
+    pub listened_seconds: i64,
+    pub books_finished: Vec<DigestBook>,
+    /// Releases by followed authors raised during the week
+    pub new_releases: Vec<ReleaseAlert>,
+    /// Books in progress not played for STALLED_AFTER_DAYS, furthest along first
+    pub stalled: Vec<DigestBook>,
+    pub created_at: String,
+}
+
+pub struct DigestService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> DigestService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Compile and store the digest of the week before the day `now` falls on
+    pub async fn compile(&self, now: DateTime<Utc>) -> Result<WeeklyDigest> {
+        let period_end = now.date_naive().and_hms_opt(0, 0, 0).context("Invalid digest date")?.and_utc();
+        let period_start = period_end - Duration::days(DIGEST_PERIOD_DAYS);
+        let (start, end) = (period_start.to_rfc3339(), period_end.to_rfc3339());
+
+        let days = RetentionService::new(self.pool)
+            .daily_listening(
+                &period_start.format("%Y-%m-%d").to_string(),
+                &(period_end - Duration::days(1)).format("%Y-%m-%d").to_string(),
+            )
+            .await?;
+
+        let books_finished = sqlx::query_as::<_, DigestBook>(
+            r#"
+            SELECT a.id as audiobook_id, a.title, a.author, 1.0 as progress, pp.last_played_at
+            FROM playback_progress pp
+            JOIN audiobooks a ON a.id = pp.audiobook_id
+            WHERE pp.is_completed
+              AND julianday(pp.last_played_at) >= julianday(?) AND julianday(pp.last_played_at) < julianday(?)
+            ORDER BY pp.last_played_at
+            "#,
+        )
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load finished books")?;
+
+        let new_releases = FollowService::new(self.pool)
+            .alerts_between(&start, &end)
+            .await?
+            .into_iter()
+            .filter(|alert| alert.follow.kind == FollowKind::Author)
+            .collect();
+
+        let stalled = sqlx::query_as::<_, DigestBook>(
+            r#"
+            SELECT a.id as audiobook_id, a.title, a.author,
+                   MIN(CAST(pp.position AS REAL) / COALESCE(NULLIF(pp.duration, 0), NULLIF(a.duration, 0)), 1.0) as progress,
+                   pp.last_played_at
+            FROM playback_progress pp
+            JOIN audiobooks a ON a.id = pp.audiobook_id
+            WHERE NOT pp.is_completed AND NOT pp.is_abandoned AND pp.position > 0
+              AND COALESCE(NULLIF(pp.duration, 0), NULLIF(a.duration, 0)) IS NOT NULL
+              AND julianday(pp.last_played_at) < julianday(?) - ?
+            ORDER BY progress DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&end)
+        .bind(STALLED_AFTER_DAYS)
+        .bind(STALLED_LIMIT)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load stalled books")?;
+
+        let digest = WeeklyDigest {
+            id: uuid::Uuid::new_v4().to_string(),
+            period_start: start,
+            period_end: end,
+            listened_seconds: listened_seconds(&days),
+            books_finished,
+            new_releases,
+            stalled,
+            created_at: now.to_rfc3339(),
+        };
+        self.store(&digest).await?;
+        Ok(digest)
+    }
+
+    /// The most recently compiled digest
+    pub async fn latest(&self) -> Result<Option<WeeklyDigest>> {
+        let json: Option<String> = sqlx::query_scalar("SELECT digest FROM weekly_digests ORDER BY created_at DESC LIMIT 1")
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to load the weekly digest")?;
+        json.map(|json| serde_json::from_str(&json).context("Invalid stored weekly digest")).transpose()
+    }
+
+    async fn store(&self, digest: &WeeklyDigest) -> Result<()> {
+        sqlx::query("INSERT INTO weekly_digests (id, period_start, period_end, digest, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&digest.id)
+            .bind(&digest.period_start)
+            .bind(&digest.period_end)
+            .bind(serde_json::to_string(digest).context("Failed to serialize weekly digest")?)
+            .bind(&digest.created_at)
+            .execute(self.pool)
+            .await
+            .context("Failed to save weekly digest")?;
+
+        sqlx::query("DELETE FROM weekly_digests WHERE id NOT IN (SELECT id FROM weekly_digests ORDER BY created_at DESC LIMIT ?)")
+            .bind(DIGESTS_KEPT)
+            .execute(self.pool)
+            .await
+            .context("Failed to prune weekly digests")?;
+        Ok(())
+    }
+}
+
+/// Time listened over the days
+fn listened_seconds(days: &[DailyListening]) -> i64 {
+    days.iter().map(DailyListening::listened_seconds).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, UpdatePlaybackProgressDto};
+    use crate::database::repository::{AudiobookRepository, PlaybackProgressRepository};
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_compile_summarizes_the_week_and_keeps_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("digest.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let mut ids = Vec::new();
+        for title in ["Emma", "Dracula", "Kim"] {
+            let book = AudiobookRepository::new(pool)
+                .create(CreateAudiobookDto {
+                    title: title.to_string(),
+                    file_path: format!("/books/{}", title),
+                    author: None,
+                    narrator: None,
+                    description: None,
+                    genre: None,
+                    duration: Some(1000),
+                    cover_image_path: None,
+                    source_type: None,
+                    source_id: None,
+                })
+                .await
+                .unwrap();
+            ids.push(book.id);
+        }
+        let progress = PlaybackProgressRepository::new(pool);
+        progress
+            .create_or_update(&ids[0], UpdatePlaybackProgressDto { position: 1000, chapter_index: None, playback_speed: None, is_completed: Some(true) })
+            .await
+            .unwrap();
+        for id in &ids[1..] {
+            progress
+                .create_or_update(id, UpdatePlaybackProgressDto { position: 250, chapter_index: None, playback_speed: None, is_completed: None })
+                .await
+                .unwrap();
+        }
+        sqlx::query("UPDATE playback_progress SET last_played_at = ? WHERE audiobook_id = ?")
+            .bind((Utc::now() - Duration::days(30)).to_rfc3339())
+            .bind(&ids[2])
+            .execute(pool)
+            .await
+            .unwrap();
+
+        let service = DigestService::new(pool);
+        assert!(service.latest().await.unwrap().is_none());
+
+        // Compiled the day after, so today's listening falls in the week
+        let digest = service.compile(Utc::now() + Duration::days(1)).await.unwrap();
+        assert_eq!(digest.books_finished.iter().map(|book| book.title.as_str()).collect::<Vec<_>>(), vec!["Emma"]);
+        assert_eq!(digest.stalled.len(), 1);
+        assert_eq!(digest.stalled[0].title, "Kim");
+        assert_eq!(digest.stalled[0].progress, 0.25);
+        assert!(digest.new_releases.is_empty());
+        assert_eq!(service.latest().await.unwrap(), Some(digest));
+    }
+
+    #[test]
+    fn test_listened_seconds_prefers_plays() {
+        let day = |play_count, played_seconds, session_seconds| DailyListening {
+            day: "2025-03-01".to_string(),
+            session_count: 1,
+            session_seconds,
+            play_count,
+            played_seconds,
+        };
+        assert_eq!(listened_seconds(&[day(2, 600, 900), day(0, 0, 300)]), 900);
+    }
+}
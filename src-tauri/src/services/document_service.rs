@@ -0,0 +1,100 @@
+// Documents processed for TTS. Remembers how each file was divided into
+// chapters so processing it again gives the same layout, and holds the default
+// chunking used for files seen for the first time.
+
+use crate::database::models::StoredDocument;
+use crate::database::repository::{DocumentRepository, PreferencesRepository};
+use crate::document::article::Article;
+use crate::document::chunking::{self, ChunkingOptions};
+use crate::document::ProcessedDocument;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+pub const PREF_CHUNKING: &str = "tts.chunking";
+
+pub struct DocumentService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> DocumentService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn load_default_chunking(&self) -> ChunkingOptions {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_CHUNKING).await.ok().flatten();
+        match stored.map(|json| serde_json::from_str::<ChunkingOptions>(&json)) {
+            Some(Ok(options)) if options.validate().is_ok() => options,
+            Some(Ok(_)) | Some(Err(_)) => {
+                log::warn!("Ignoring invalid chunking options");
+                ChunkingOptions::default()
+            }
+            None => ChunkingOptions::default(),
+        }
+    }
+
+    pub async fn save_default_chunking(&self, options: &ChunkingOptions) -> Result<()> {
+        options.validate()?;
+        let json = serde_json::to_string(options).context("Failed to serialize chunking options")?;
+        PreferencesRepository::new(self.pool).set(PREF_CHUNKING, &json).await
+    }
+
+    /// The chunking a file was last processed with, or the default for new files
+    pub async fn chunking_for(&self, file_path: &str) -> Result<ChunkingOptions> {
+        match DocumentRepository::new(self.pool).find_by_path(file_path).await? {
+            Some(document) => Ok(ChunkingOptions {
+                target_minutes: document.target_minutes as f32,
+                split_on_sentences: document.split_on_sentences,
+                respect_headings: document.respect_headings,
+            }),
+            None => Ok(self.load_default_chunking().await),
+        }
+    }
+
+    /// Record the structure a document was divided into and link it to the stored row
+    pub async fn store(
+        &self,
+        file_path: &str,
+        document: &mut ProcessedDocument,
+        options: &ChunkingOptions,
+    ) -> Result<StoredDocument> {
+        let sections = serde_json::to_string(&chunking::sections(&document.chapters))
+            .context("Failed to serialize document sections")?;
+        let now = Utc::now().to_rfc3339();
+
+        let stored = DocumentRepository::new(self.pool)
+            .upsert(&StoredDocument {
+                id: Uuid::new_v4().to_string(),
+                file_path: file_path.to_string(),
+                title: document.title.clone(),
+                author: document.author.clone(),
+                format: document.format.clone(),
+                target_minutes: options.target_minutes as f64,
+                split_on_sentences: options.split_on_sentences,
+                respect_headings: options.respect_headings,
+                sections,
+                created_at: now.clone(),
+                updated_at: now,
+                source_url: None,
+                site_name: None,
+            })
+            .await?;
+        document.document_id = Some(stored.id.clone());
+        Ok(stored)
+    }
+
+    /// Record the web page an article document was imported from
+    pub async fn set_article_source(&self, document_id: &str, article: &Article) -> Result<StoredDocument> {
+        let repo = DocumentRepository::new(self.pool);
+        repo.set_source(document_id, &article.url, article.site_name.as_deref()).await?;
+        repo.find_by_id(document_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document not found: {}", document_id))
+    }
+
+    pub async fn find(&self, file_path: &str) -> Result<Option<StoredDocument>> {
+        DocumentRepository::new(self.pool).find_by_path(file_path).await
+    }
+}
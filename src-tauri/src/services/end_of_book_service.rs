@@ -0,0 +1,138 @@
+// What happens when the last chapter of a book plays out: stop there, go on
+// to the next book in the series, or start the top recommendation
+
+use crate::database::models::Audiobook;
+use crate::database::repository::{PlaybackProgressRepository, PreferencesRepository};
+use crate::services::{RecommendationService, SeriesService};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+pub const PREF_END_OF_BOOK: &str = "playback.end_of_book";
+/// How many current recommendations are considered before generating fresh ones
+const RECOMMENDATION_CANDIDATES: i32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EndOfBookAction {
+    /// Stop, leaving the listener to pick what is next
+    #[default]
+    Stop,
+    /// Queue the next unfinished book of the series
+    NextInSeries,
+    /// Queue the best unfinished recommendation
+    Recommendation,
+}
+
+impl EndOfBookAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EndOfBookAction::Stop => "stop",
+            EndOfBookAction::NextInSeries => "next_in_series",
+            EndOfBookAction::Recommendation => "recommendation",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stop" => Some(EndOfBookAction::Stop),
+            "next_in_series" => Some(EndOfBookAction::NextInSeries),
+            "recommendation" => Some(EndOfBookAction::Recommendation),
+            _ => None,
+        }
+    }
+}
+
+/// Sent when a book finishes; `next_audiobook` is what was queued, if anything
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BookFinished {
+    pub audiobook_id: String,
+    pub action: EndOfBookAction,
+    pub next_audiobook: Option<Audiobook>,
+}
+
+pub struct EndOfBookService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> EndOfBookService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn action(&self) -> Result<EndOfBookAction> {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_END_OF_BOOK).await?;
+        Ok(match stored {
+            Some(value) => EndOfBookAction::parse(&value).unwrap_or_else(|| {
+                log::warn!("Ignoring unknown end-of-book action: {}", value);
+                EndOfBookAction::default()
+            }),
+            None => EndOfBookAction::default(),
+        })
+    }
+
+    pub async fn set_action(&self, action: EndOfBookAction) -> Result<EndOfBookAction> {
+        PreferencesRepository::new(self.pool).set(PREF_END_OF_BOOK, action.as_str()).await?;
+        Ok(action)
+    }
+
+    /// Apply the policy to a finished book and say what should play next
+    pub async fn book_finished(&self, audiobook_id: &str) -> Result<BookFinished> {
+        let action = self.action().await?;
+        let next_audiobook = match action {
+            EndOfBookAction::Stop => None,
+            EndOfBookAction::NextInSeries => SeriesService::new(self.pool).next_in_series(audiobook_id).await?,
+            EndOfBookAction::Recommendation => self.top_recommendation(audiobook_id).await?,
+        };
+        Ok(BookFinished { audiobook_id: audiobook_id.to_string(), action, next_audiobook })
+    }
+
+    async fn top_recommendation(&self, finished_id: &str) -> Result<Option<Audiobook>> {
+        let recommendations = RecommendationService::new(self.pool);
+        let mut candidates = recommendations.get_current_recommendations(Some(RECOMMENDATION_CANDIDATES)).await?;
+        if candidates.is_empty() {
+            candidates = recommendations.generate_recommendations(Some(RECOMMENDATION_CANDIDATES)).await?;
+        }
+
+        let progress = PlaybackProgressRepository::new(self.pool);
+        for candidate in candidates {
+            if candidate.audiobook.id == finished_id {
+                continue;
+            }
+            let finished = progress.find_by_audiobook_id(&candidate.audiobook.id).await?
+                .is_some_and(|progress| progress.is_completed);
+            if !finished {
+                return Ok(Some(candidate.audiobook));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_end_of_book_action_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("end.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = EndOfBookService::new(pool);
+
+        assert_eq!(service.action().await.unwrap(), EndOfBookAction::Stop);
+        service.set_action(EndOfBookAction::NextInSeries).await.unwrap();
+        assert_eq!(service.action().await.unwrap(), EndOfBookAction::NextInSeries);
+
+        PreferencesRepository::new(pool).set(PREF_END_OF_BOOK, "shuffle").await.unwrap();
+        assert_eq!(service.action().await.unwrap(), EndOfBookAction::Stop, "unknown values fall back to stopping");
+
+        let finished = service.book_finished("book").await.unwrap();
+        assert_eq!((finished.action, finished.next_audiobook.is_none()), (EndOfBookAction::Stop, true));
+    }
+}
@@ -0,0 +1,276 @@
+// Import-time validation: every file of a book is decoded in full and the
+// findings stored, so files likely to fail in the player are flagged before
+// the listener reaches them mid-book.
+
+use crate::audio::bitrate_kbps;
+use crate::audio::probe::{probe_file, FileProbe, ProbeStatus};
+use crate::database::repository::{AudiobookRepository, ChapterRepository};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FileValidation {
+    pub file_path: String,
+    pub audiobook_id: String,
+    pub chapter_id: Option<String>,
+    pub chapter_title: Option<String>,
+    pub status: ProbeStatus,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub declared_duration: Option<f64>,
+    pub decoded_duration: f64,
+    #[ts(type = "number")]
+    pub packets: i64,
+    #[ts(type = "number")]
+    pub damaged_packets: i64,
+    pub issues: Vec<String>,
+    pub validated_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct ValidationRow {
+    file_path: String,
+    audiobook_id: String,
+    chapter_id: Option<String>,
+    chapter_title: Option<String>,
+    status: String,
+    codec: Option<String>,
+    sample_rate: Option<i64>,
+    channels: Option<i64>,
+    declared_duration: Option<f64>,
+    decoded_duration: f64,
+    packets: i64,
+    damaged_packets: i64,
+    issues: String,
+    validated_at: String,
+}
+
+impl From<ValidationRow> for FileValidation {
+    fn from(row: ValidationRow) -> Self {
+        Self {
+            file_path: row.file_path,
+            audiobook_id: row.audiobook_id,
+            chapter_id: row.chapter_id,
+            chapter_title: row.chapter_title,
+            status: ProbeStatus::parse(&row.status),
+            codec: row.codec,
+            sample_rate: row.sample_rate.map(|rate| rate as u32),
+            channels: row.channels.map(|channels| channels as u16),
+            declared_duration: row.declared_duration,
+            decoded_duration: row.decoded_duration,
+            packets: row.packets,
+            damaged_packets: row.damaged_packets,
+            issues: serde_json::from_str(&row.issues).unwrap_or_default(),
+            validated_at: row.validated_at,
+        }
+    }
+}
+
+/// Stored findings for one book's files
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct AudiobookValidation {
+    pub audiobook_id: String,
+    pub files: Vec<FileValidation>,
+    pub unplayable: usize,
+    pub warnings: usize,
+}
+
+pub struct FileValidationService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> FileValidationService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Decode every file of the book and store what was found
+    pub async fn validate_audiobook(&self, audiobook_id: &str) -> Result<AudiobookValidation> {
+        let audiobook = AudiobookRepository::new(self.pool)
+            .find_by_id(audiobook_id)
+            .await?
+            .ok_or_else(|| anyhow!("Audiobook {} not found", audiobook_id))?;
+
+        let mut files: Vec<(String, Option<String>)> = Vec::new();
+        for chapter in ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await? {
+            // Chapters marked inside one file share its path
+            if !files.iter().any(|(path, _)| *path == chapter.file_path) {
+                files.push((chapter.file_path, Some(chapter.id)));
+            }
+        }
+        if files.is_empty() && !std::path::Path::new(&audiobook.file_path).is_dir() {
+            files.push((audiobook.file_path.clone(), None));
+        }
+
+        for (file_path, chapter_id) in files {
+            let path = file_path.clone();
+            let probe = tokio::task::spawn_blocking(move || probe_file(path)).await.context("File probe panicked")?;
+            let (status, _) = probe.assess();
+            if status != ProbeStatus::Ok {
+                log::warn!("VALIDATION: {} is {}", file_path, status.as_str());
+            }
+            self.save(audiobook_id, chapter_id.as_deref(), &file_path, &probe).await?;
+        }
+        self.for_audiobook(audiobook_id).await
+    }
+
+    pub async fn save(&self, audiobook_id: &str, chapter_id: Option<&str>, file_path: &str, probe: &FileProbe) -> Result<()> {
+        let (status, issues) = probe.assess();
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO file_validations (
+                file_path, audiobook_id, chapter_id, status, codec, sample_rate, channels,
+                declared_duration, decoded_duration, packets, damaged_packets, issues, validated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(file_path)
+        .bind(audiobook_id)
+        .bind(chapter_id)
+        .bind(status.as_str())
+        .bind(&probe.codec)
+        .bind(probe.sample_rate.map(|rate| rate as i64))
+        .bind(probe.channels.map(|channels| channels as i64))
+        .bind(probe.declared_duration)
+        .bind(probe.decoded_duration)
+        .bind(probe.packets as i64)
+        .bind(probe.damaged_packets as i64)
+        .bind(serde_json::to_string(&issues)?)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to save file validation")?;
+
+        // The quality shown next to the chapters; files that did not decode keep what they had
+        let file_size = std::fs::metadata(file_path).ok().map(|metadata| metadata.len());
+        let seconds = probe.declared_duration.unwrap_or(probe.decoded_duration);
+        let bitrate = file_size.and_then(|size| bitrate_kbps(size, seconds)).map(|kbps| kbps as i64);
+        if chapter_id.is_some() {
+            sqlx::query(
+                r#"
+                UPDATE chapters
+                SET bitrate = COALESCE(?, bitrate), codec = COALESCE(?, codec), file_size = COALESCE(file_size, ?)
+                WHERE audiobook_id = ? AND file_path = ?
+                "#,
+            )
+            .bind(bitrate)
+            .bind(&probe.codec)
+            .bind(file_size.map(|size| size as i64))
+            .bind(audiobook_id)
+            .bind(file_path)
+            .execute(self.pool)
+            .await
+            .context("Failed to save chapter quality")?;
+        } else {
+            sqlx::query("UPDATE audiobooks SET bitrate = COALESCE(?, bitrate) WHERE id = ?")
+                .bind(bitrate)
+                .bind(audiobook_id)
+                .execute(self.pool)
+                .await
+                .context("Failed to save audiobook bitrate")?;
+        }
+        Ok(())
+    }
+
+    pub async fn for_audiobook(&self, audiobook_id: &str) -> Result<AudiobookValidation> {
+        let files: Vec<FileValidation> = sqlx::query_as::<_, ValidationRow>(
+            r#"
+            SELECT v.file_path, v.audiobook_id, v.chapter_id, c.title as chapter_title, v.status, v.codec,
+                   v.sample_rate, v.channels, v.declared_duration, v.decoded_duration, v.packets,
+                   v.damaged_packets, v.issues, v.validated_at
+            FROM file_validations v
+            LEFT JOIN chapters c ON c.id = v.chapter_id
+            WHERE v.audiobook_id = ?
+            ORDER BY c.chapter_number, v.file_path
+            "#,
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get file validations")?
+        .into_iter()
+        .map(FileValidation::from)
+        .collect();
+
+        Ok(AudiobookValidation {
+            audiobook_id: audiobook_id.to_string(),
+            unplayable: files.iter().filter(|file| file.status == ProbeStatus::Unplayable).count(),
+            warnings: files.iter().filter(|file| file.status == ProbeStatus::Warning).count(),
+            files,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, CreateChapterDto};
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_validation_flags_files_that_will_not_play() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("validation.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let good = dir.path().join("01.wav");
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36u32 + 16_000).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        for value in [16u32, 0x0001_0001, 8_000, 16_000, 0x0010_0002] {
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&16_000u32.to_le_bytes());
+        wav.resize(wav.len() + 16_000, 0);
+        std::fs::write(&good, wav).unwrap();
+        let bad = dir.path().join("02.mp3");
+        std::fs::write(&bad, b"ID3 but nothing after it").unwrap();
+
+        let book = AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: "Dune".to_string(),
+            file_path: dir.path().to_string_lossy().to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        for (number, path) in [(1, &good), (2, &bad)] {
+            ChapterRepository::new(pool).create(CreateChapterDto {
+                audiobook_id: book.id.clone(),
+                chapter_number: number,
+                title: format!("Chapter {}", number),
+                file_path: path.to_string_lossy().to_string(),
+                duration: None,
+                file_size: None,
+            }).await.unwrap();
+        }
+
+        let service = FileValidationService::new(pool);
+        let report = service.validate_audiobook(&book.id).await.unwrap();
+        assert_eq!((report.unplayable, report.warnings), (1, 0));
+        assert_eq!(report.files[0].status, ProbeStatus::Ok);
+        assert_eq!(report.files[0].sample_rate, Some(8_000));
+        let chapters = ChapterRepository::new(pool).find_by_audiobook_id(&book.id).await.unwrap();
+        assert_eq!((chapters[0].bitrate, chapters[0].codec.as_deref()), (Some(128), Some("pcm_s16le")));
+        assert_eq!((chapters[1].bitrate, chapters[1].codec.as_deref()), (None, None));
+        assert_eq!(report.files[1].chapter_title.as_deref(), Some("Chapter 2"));
+        assert!(!report.files[1].issues.is_empty());
+
+        // Validating again replaces the stored findings
+        assert_eq!(service.validate_audiobook(&book.id).await.unwrap().files.len(), 2);
+    }
+}
@@ -0,0 +1,468 @@
+// Progress sync between computers through a folder the user already syncs
+// (Dropbox, Syncthing, a network share). Every device writes its own file with
+// the progress, bookmarks and collections of its library and reads the files
+// of the others; no device ever writes another device's file, so the sync tool
+// never sees a conflict. Books are matched like shared collections, because
+// ids differ between libraries. Progress is last-writer-wins per book;
+// bookmarks and collection members are merged, as the files carry no deletions.
+
+use crate::database::models::{Audiobook, ChapterMarker, CreateCollectionDto, PlaybackProgress};
+use crate::database::repository::{
+    AudiobookRepository, ChapterMarkerRepository, CollectionRepository, PlaybackProgressRepository, PreferencesRepository,
+};
+use crate::services::collection_share_service::{find_match, librivox_identifier, normalize};
+use crate::services::SharedCollectionBook;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ts_rs::TS;
+
+pub const PREF_SYNC_FOLDER: &str = "sync.folder";
+pub const PREF_SYNC_DEVICE_ID: &str = "sync.device_id";
+pub const SYNC_FORMAT: &str = "audiovibe-sync";
+pub const SYNC_VERSION: u32 = 1;
+/// Created inside the chosen folder so the device files stay together
+pub const SYNC_SUBFOLDER: &str = "AudioVibe Sync";
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// One device's file
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncSnapshot {
+    pub format: String,
+    pub version: u32,
+    pub device_id: String,
+    pub device_name: String,
+    pub written_at: String,
+    pub books: Vec<SyncedBook>,
+    pub collections: Vec<SyncedCollection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncedBook {
+    pub book: SharedCollectionBook,
+    #[ts(type = "number")]
+    pub position: i64,
+    pub chapter_index: i32,
+    pub playback_speed: f64,
+    pub is_completed: bool,
+    pub is_abandoned: bool,
+    pub last_played_at: String,
+    /// When the progress last changed; the newest device wins
+    pub updated_at: String,
+    #[serde(default)]
+    pub bookmarks: Vec<SyncedBookmark>,
+}
+
+/// A marker the listener set by hand; generated markers are not synced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncedBookmark {
+    #[ts(type = "number")]
+    pub position: i64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SyncedCollection {
+    pub name: String,
+    pub description: Option<String>,
+    pub color: String,
+    pub books: Vec<SharedCollectionBook>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FolderSyncReport {
+    /// Other devices whose files were merged
+    pub devices: Vec<String>,
+    /// Local books whose progress was replaced by a newer one
+    pub books_updated: Vec<String>,
+    pub bookmarks_added: usize,
+    pub collections_created: usize,
+    pub collection_books_added: usize,
+    /// Books other devices have that this library does not
+    pub unmatched_books: usize,
+    /// Files that could not be read, with the reason
+    pub errors: Vec<String>,
+}
+
+pub struct FolderSyncService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> FolderSyncService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The synced folder, when sync is switched on
+    pub async fn folder(&self) -> Result<Option<PathBuf>> {
+        Ok(PreferencesRepository::new(self.pool).get(PREF_SYNC_FOLDER).await?.map(PathBuf::from))
+    }
+
+    /// This device's id, created on first use
+    pub async fn device_id(&self) -> Result<String> {
+        let prefs = PreferencesRepository::new(self.pool);
+        if let Some(id) = prefs.get(PREF_SYNC_DEVICE_ID).await? {
+            return Ok(id);
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        prefs.set(PREF_SYNC_DEVICE_ID, &id).await?;
+        Ok(id)
+    }
+
+    /// Merge the other devices' files into the library, then write ours
+    pub async fn sync(&self, folder: &Path, librivox_dir: &Path) -> Result<FolderSyncReport> {
+        let dir = folder.join(SYNC_SUBFOLDER);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let device_id = self.device_id().await?;
+
+        let mut report = FolderSyncReport::default();
+        for (path, snapshot) in read_snapshots(&dir, &device_id, &mut report.errors) {
+            if let Err(e) = self.merge(&snapshot, librivox_dir, &mut report).await {
+                report.errors.push(format!("{}: {:#}", path.display(), e));
+            }
+            report.devices.push(snapshot.device_name);
+        }
+
+        let snapshot = self.snapshot(&device_id, librivox_dir).await?;
+        write_snapshot(&dir, &snapshot)?;
+
+        if !report.books_updated.is_empty() || report.bookmarks_added > 0 {
+            log::info!(
+                "SYNC: {} books updated and {} bookmarks added from {} devices",
+                report.books_updated.len(), report.bookmarks_added, report.devices.len()
+            );
+        }
+        Ok(report)
+    }
+
+    /// This library's progress, bookmarks and collections
+    pub async fn snapshot(&self, device_id: &str, librivox_dir: &Path) -> Result<SyncSnapshot> {
+        let library = AudiobookRepository::new(self.pool).find_all().await?;
+        let mut progress: HashMap<String, PlaybackProgress> = PlaybackProgressRepository::new(self.pool)
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|progress| (progress.audiobook_id.clone(), progress))
+            .collect();
+
+        let markers = ChapterMarkerRepository::new(self.pool);
+        let mut books = Vec::new();
+        for audiobook in &library {
+            let Some(progress) = progress.remove(&audiobook.id) else {
+                continue;
+            };
+            let bookmarks = markers
+                .find_by_audiobook_id(&audiobook.id)
+                .await?
+                .into_iter()
+                .filter(|marker| !marker.is_auto)
+                .map(|marker| SyncedBookmark { position: marker.position, title: marker.title })
+                .collect();
+            books.push(SyncedBook {
+                book: shared_book(audiobook, librivox_dir),
+                position: progress.position,
+                chapter_index: progress.chapter_index,
+                playback_speed: progress.playback_speed,
+                is_completed: progress.is_completed,
+                is_abandoned: progress.is_abandoned,
+                last_played_at: progress.last_played_at,
+                updated_at: progress.updated_at,
+                bookmarks,
+            });
+        }
+
+        let repo = CollectionRepository::new(self.pool);
+        let mut collections = Vec::new();
+        for collection in repo.find_all().await? {
+            if collection.is_smart {
+                continue;
+            }
+            let members = repo.get_collection_audiobooks(&collection.id).await?;
+            collections.push(SyncedCollection {
+                name: collection.name,
+                description: collection.description,
+                color: collection.color,
+                books: members.iter().map(|audiobook| shared_book(audiobook, librivox_dir)).collect(),
+            });
+        }
+
+        Ok(SyncSnapshot {
+            format: SYNC_FORMAT.to_string(),
+            version: SYNC_VERSION,
+            device_id: device_id.to_string(),
+            device_name: device_name(),
+            written_at: Utc::now().to_rfc3339(),
+            books,
+            collections,
+        })
+    }
+
+    async fn merge(&self, snapshot: &SyncSnapshot, librivox_dir: &Path, report: &mut FolderSyncReport) -> Result<()> {
+        let library = AudiobookRepository::new(self.pool).find_all().await?;
+        let progress_repo = PlaybackProgressRepository::new(self.pool);
+        let markers = ChapterMarkerRepository::new(self.pool);
+
+        for remote in &snapshot.books {
+            let Some(audiobook) = find_match(&remote.book, &library, librivox_dir) else {
+                report.unmatched_books += 1;
+                continue;
+            };
+
+            let local = progress_repo.find_by_audiobook_id(&audiobook.id).await?;
+            let remote_is_newer = match &local {
+                Some(local) => is_newer(&remote.updated_at, &local.updated_at),
+                None => true,
+            };
+            if remote_is_newer {
+                self.apply_progress(&audiobook.id, remote, local.is_some()).await?;
+                report.books_updated.push(audiobook.id.clone());
+            }
+
+            let existing = markers.find_by_audiobook_id(&audiobook.id).await?;
+            for bookmark in &remote.bookmarks {
+                let known = existing
+                    .iter()
+                    .any(|marker| !marker.is_auto && marker.position == bookmark.position && marker.title == bookmark.title);
+                if !known {
+                    markers.create(&ChapterMarker::new(audiobook.id.clone(), bookmark.position, bookmark.title.clone(), false)).await?;
+                    report.bookmarks_added += 1;
+                }
+            }
+        }
+
+        let repo = CollectionRepository::new(self.pool);
+        let mut collections = repo.find_all().await?;
+        for remote in &snapshot.collections {
+            let name = normalize(&remote.name);
+            let collection = match collections.iter().find(|collection| !collection.is_smart && normalize(&collection.name) == name) {
+                Some(collection) => collection.clone(),
+                None => {
+                    let created = repo.create(CreateCollectionDto {
+                        name: remote.name.clone(),
+                        description: remote.description.clone(),
+                        color: Some(remote.color.clone()),
+                        parent_collection_id: None,
+                    }).await?;
+                    report.collections_created += 1;
+                    collections.push(created.clone());
+                    created
+                }
+            };
+
+            let members = repo.get_collection_audiobooks(&collection.id).await?;
+            for book in &remote.books {
+                let Some(audiobook) = find_match(book, &library, librivox_dir) else {
+                    continue;
+                };
+                if !members.iter().any(|member| member.id == audiobook.id) {
+                    repo.add_audiobook_to_collection(&collection.id, &audiobook.id).await?;
+                    report.collection_books_added += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Take over another device's progress, keeping its timestamps so this
+    /// device's next file does not claim the change as its own
+    async fn apply_progress(&self, audiobook_id: &str, remote: &SyncedBook, exists: bool) -> Result<()> {
+        let query = if exists {
+            r#"
+            UPDATE playback_progress SET
+                position = ?2, chapter_index = ?3, playback_speed = ?4, is_completed = ?5,
+                is_abandoned = ?6, last_played_at = ?7, updated_at = ?8
+            WHERE audiobook_id = ?1
+            "#
+        } else {
+            r#"
+            INSERT INTO playback_progress (
+                id, audiobook_id, position, chapter_index, playback_speed, is_completed,
+                is_abandoned, last_played_at, created_at, updated_at
+            ) VALUES (?9, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+            "#
+        };
+        sqlx::query(query)
+            .bind(audiobook_id)
+            .bind(remote.position)
+            .bind(remote.chapter_index)
+            .bind(remote.playback_speed)
+            .bind(remote.is_completed)
+            .bind(remote.is_abandoned)
+            .bind(&remote.last_played_at)
+            .bind(&remote.updated_at)
+            .bind(uuid::Uuid::new_v4().to_string())
+            .execute(self.pool)
+            .await
+            .context("Failed to apply synced progress")?;
+        Ok(())
+    }
+}
+
+fn shared_book(audiobook: &Audiobook, librivox_dir: &Path) -> SharedCollectionBook {
+    SharedCollectionBook {
+        title: audiobook.title.clone(),
+        author: audiobook.author.clone(),
+        narrator: audiobook.narrator.clone(),
+        librivox_identifier: librivox_identifier(audiobook, librivox_dir),
+    }
+}
+
+fn device_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "AudioVibe".to_string())
+}
+
+/// Progress timestamps are RFC 3339, except rows written with SQLite's datetime('now')
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok().map(|time| time.and_utc()))
+}
+
+/// Whether `remote` is strictly later than `local`; unreadable timestamps never win
+fn is_newer(remote: &str, local: &str) -> bool {
+    match (parse_timestamp(remote), parse_timestamp(local)) {
+        (Some(remote), Some(local)) => remote > local,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// The other devices' files; unreadable ones are reported and skipped
+fn read_snapshots(dir: &Path, device_id: &str, errors: &mut Vec<String>) -> Vec<(PathBuf, SyncSnapshot)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") || path.file_stem().and_then(|stem| stem.to_str()) == Some(device_id) {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| serde_json::from_str::<SyncSnapshot>(&json).map_err(anyhow::Error::from));
+        match parsed {
+            Ok(snapshot) if snapshot.format != SYNC_FORMAT => errors.push(format!("{}: not an AudioVibe sync file", path.display())),
+            Ok(snapshot) if snapshot.version > SYNC_VERSION => {
+                errors.push(format!("{}: written by a newer version of AudioVibe", path.display()))
+            }
+            Ok(snapshot) => snapshots.push((path, snapshot)),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    snapshots.sort_by(|a, b| a.0.cmp(&b.0));
+    snapshots
+}
+
+/// Write through a temporary file so other devices never read half a file
+fn write_snapshot(dir: &Path, snapshot: &SyncSnapshot) -> Result<()> {
+    let json = serde_json::to_string_pretty(snapshot).context("Failed to serialize sync file")?;
+    let path = dir.join(format!("{}.json", snapshot.device_id));
+    let temp = dir.join(format!(".{}.json.tmp", snapshot.device_id));
+    std::fs::write(&temp, json).with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, &path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, UpdatePlaybackProgressDto};
+    use crate::database::DatabaseManager;
+
+    async fn library(dir: &Path, name: &str) -> DatabaseManager {
+        let mut db = DatabaseManager::new(dir.join(format!("{}.db", name)).to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        for title in ["Dune", "Emma"] {
+            AudiobookRepository::new(db.get_pool().unwrap()).create(CreateAudiobookDto {
+                title: title.to_string(),
+                file_path: format!("/{}/{}", name, title),
+                author: None,
+                narrator: None,
+                description: None,
+                genre: None,
+                duration: None,
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            }).await.unwrap();
+        }
+        db
+    }
+
+    async fn book_id(pool: &SqlitePool, title: &str) -> String {
+        sqlx::query_scalar("SELECT id FROM audiobooks WHERE title = ?").bind(title).fetch_one(pool).await.unwrap()
+    }
+
+    async fn set_progress(pool: &SqlitePool, title: &str, position: i64) {
+        let id = book_id(pool, title).await;
+        PlaybackProgressRepository::new(pool)
+            .create_or_update(&id, UpdatePlaybackProgressDto { position, chapter_index: None, playback_speed: None, is_completed: None })
+            .await
+            .unwrap();
+    }
+
+    async fn position(pool: &SqlitePool, title: &str) -> Option<i64> {
+        let id = book_id(pool, title).await;
+        PlaybackProgressRepository::new(pool).find_by_audiobook_id(&id).await.unwrap().map(|progress| progress.position)
+    }
+
+    #[tokio::test]
+    async fn test_sync_merges_newest_progress_and_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let (desktop, laptop) = (library(dir.path(), "desktop").await, library(dir.path(), "laptop").await);
+        let (desktop, laptop) = (desktop.get_pool().unwrap(), laptop.get_pool().unwrap());
+        let folder = dir.path().join("Dropbox");
+        let librivox = dir.path().join("cache");
+
+        set_progress(laptop, "Emma", 100).await;
+        set_progress(desktop, "Dune", 30).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        set_progress(desktop, "Emma", 500).await;
+        let dune = book_id(laptop, "Dune").await;
+        ChapterMarkerRepository::new(laptop).create(&ChapterMarker::new(dune, 42, "Litany".to_string(), false)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        set_progress(laptop, "Dune", 900).await;
+
+        FolderSyncService::new(desktop).sync(&folder, &librivox).await.unwrap();
+        let report = FolderSyncService::new(laptop).sync(&folder, &librivox).await.unwrap();
+        assert_eq!(report.devices.len(), 1);
+        // The laptop's Emma is older than the desktop's, its Dune newer
+        assert_eq!(report.books_updated, vec![book_id(laptop, "Emma").await]);
+        assert_eq!(position(laptop, "Emma").await, Some(500));
+
+        let report = FolderSyncService::new(desktop).sync(&folder, &librivox).await.unwrap();
+        assert_eq!(report.books_updated, vec![book_id(desktop, "Dune").await]);
+        assert_eq!(report.bookmarks_added, 1);
+        assert_eq!(position(desktop, "Dune").await, Some(900));
+
+        // Nothing changed since, so another round is a no-op on both sides
+        let report = FolderSyncService::new(laptop).sync(&folder, &librivox).await.unwrap();
+        assert!(report.books_updated.is_empty() && report.bookmarks_added == 0, "{:?}", report);
+        let report = FolderSyncService::new(desktop).sync(&folder, &librivox).await.unwrap();
+        assert!(report.books_updated.is_empty() && report.bookmarks_added == 0, "{:?}", report);
+    }
+
+    #[test]
+    fn test_is_newer_across_timestamp_formats() {
+        assert!(is_newer("2025-01-02T10:00:00+00:00", "2025-01-02 09:59:59"));
+        assert!(!is_newer("2025-01-02T10:00:00+02:00", "2025-01-02T09:00:00Z"));
+        assert!(!is_newer("2025-01-02T10:00:00Z", "2025-01-02T10:00:00Z"));
+        assert!(!is_newer("yesterday", "2025-01-02T10:00:00Z"));
+        assert!(is_newer("2025-01-02T10:00:00Z", "garbage"));
+    }
+}
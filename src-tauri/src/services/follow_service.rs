@@ -0,0 +1,346 @@
+// Followed authors and genres. Each LibriVox release fetch is checked against
+// them, and matches are kept as alerts until dismissed, so an alert raised
+// while the window was closed still shows on the next launch.
+
+use crate::services::author_service::{display_name, normalize_author_name};
+use crate::services::LibrivoxRelease;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowKind {
+    Author,
+    Genre,
+}
+
+impl FollowKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FollowKind::Author => "author",
+            FollowKind::Genre => "genre",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "author" => Some(FollowKind::Author),
+            "genre" => Some(FollowKind::Genre),
+            _ => None,
+        }
+    }
+
+    fn display(self, name: &str) -> String {
+        match self {
+            FollowKind::Author => display_name(name),
+            FollowKind::Genre => name.trim().to_string(),
+        }
+    }
+
+    /// Authors match however their name is written; genres ignore case and spacing
+    fn normalize(self, name: &str) -> String {
+        match self {
+            FollowKind::Author => normalize_author_name(name),
+            FollowKind::Genre => name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Follow {
+    pub id: String,
+    pub kind: FollowKind,
+    pub name: String,
+    pub created_at: String,
+}
+
+/// A release by something followed; `release.url_zip_file` imports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ReleaseAlert {
+    pub id: String,
+    pub follow: Follow,
+    pub release: LibrivoxRelease,
+    pub created_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct FollowRow {
+    id: String,
+    kind: String,
+    name: String,
+    created_at: String,
+}
+
+impl FollowRow {
+    fn into_follow(self) -> Option<Follow> {
+        Some(Follow { id: self.id, kind: FollowKind::parse(&self.kind)?, name: self.name, created_at: self.created_at })
+    }
+}
+
+pub struct FollowService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> FollowService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Follow an author or genre; following it again returns the existing follow
+    pub async fn follow(&self, kind: FollowKind, name: &str) -> Result<Follow> {
+        let name = kind.display(name);
+        let normalized = kind.normalize(&name);
+        if normalized.is_empty() {
+            return Err(anyhow!("Nothing to follow"));
+        }
+
+        sqlx::query("INSERT OR IGNORE INTO follows (id, kind, name, normalized_name, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(kind.as_str())
+            .bind(&name)
+            .bind(&normalized)
+            .bind(Utc::now().to_rfc3339())
+            .execute(self.pool)
+            .await
+            .context("Failed to save follow")?;
+
+        sqlx::query_as::<_, FollowRow>("SELECT id, kind, name, created_at FROM follows WHERE kind = ? AND normalized_name = ?")
+            .bind(kind.as_str())
+            .bind(&normalized)
+            .fetch_one(self.pool)
+            .await
+            .context("Failed to load follow")?
+            .into_follow()
+            .context("Invalid follow")
+    }
+
+    pub async fn rename(&self, id: &str, name: &str) -> Result<Follow> {
+        let follow = self.find(id).await?.with_context(|| format!("Follow not found: {}", id))?;
+        let name = follow.kind.display(name);
+        let normalized = follow.kind.normalize(&name);
+        if normalized.is_empty() {
+            return Err(anyhow!("Nothing to follow"));
+        }
+        sqlx::query("UPDATE follows SET name = ?, normalized_name = ? WHERE id = ?")
+            .bind(&name)
+            .bind(&normalized)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .with_context(|| format!("Already following {} '{}'", follow.kind.as_str(), name))?;
+        Ok(Follow { name, ..follow })
+    }
+
+    /// Stop following; its alerts go too
+    pub async fn unfollow(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM release_alerts WHERE follow_id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete release alerts")?;
+        let deleted = sqlx::query("DELETE FROM follows WHERE id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete follow")?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(anyhow!("Follow not found: {}", id));
+        }
+        Ok(())
+    }
+
+    pub async fn find(&self, id: &str) -> Result<Option<Follow>> {
+        let row = sqlx::query_as::<_, FollowRow>("SELECT id, kind, name, created_at FROM follows WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to load follow")?;
+        Ok(row.and_then(FollowRow::into_follow))
+    }
+
+    pub async fn list(&self) -> Result<Vec<Follow>> {
+        let rows = sqlx::query_as::<_, FollowRow>("SELECT id, kind, name, created_at FROM follows ORDER BY kind, name COLLATE NOCASE")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load follows")?;
+        Ok(rows.into_iter().filter_map(FollowRow::into_follow).collect())
+    }
+
+    /// Raise an alert for every release matching a follow, once per follow and
+    /// release. Returns the alerts that are new.
+    pub async fn record_alerts(&self, releases: &[LibrivoxRelease]) -> Result<Vec<ReleaseAlert>> {
+        let follows = self.list().await?;
+        if follows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut alerts = Vec::new();
+        for release in releases {
+            for follow in follows.iter().filter(|follow| matches(follow, release)) {
+                let alert = ReleaseAlert {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    follow: follow.clone(),
+                    release: release.clone(),
+                    created_at: Utc::now().to_rfc3339(),
+                };
+                let inserted = sqlx::query(
+                    "INSERT OR IGNORE INTO release_alerts (id, follow_id, release_id, release, created_at) VALUES (?, ?, ?, ?, ?)"
+                )
+                .bind(&alert.id)
+                .bind(&follow.id)
+                .bind(&release.id)
+                .bind(serde_json::to_string(release)?)
+                .bind(&alert.created_at)
+                .execute(self.pool)
+                .await
+                .context("Failed to save release alert")?
+                .rows_affected();
+                if inserted > 0 {
+                    alerts.push(alert);
+                }
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// Alerts not dismissed yet, newest first
+    pub async fn pending_alerts(&self) -> Result<Vec<ReleaseAlert>> {
+        let rows = sqlx::query_as::<_, AlertRow>(
+            r#"
+            SELECT a.id, a.release, a.created_at, f.id, f.kind, f.name, f.created_at
+            FROM release_alerts a
+            JOIN follows f ON f.id = a.follow_id
+            WHERE a.dismissed_at IS NULL
+            ORDER BY a.created_at DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load release alerts")?;
+        Ok(into_alerts(rows))
+    }
+
+    /// Alerts raised from `from` up to `to` (RFC 3339), dismissed or not, newest first
+    pub async fn alerts_between(&self, from: &str, to: &str) -> Result<Vec<ReleaseAlert>> {
+        let rows = sqlx::query_as::<_, AlertRow>(
+            r#"
+            SELECT a.id, a.release, a.created_at, f.id, f.kind, f.name, f.created_at
+            FROM release_alerts a
+            JOIN follows f ON f.id = a.follow_id
+            WHERE a.created_at >= ? AND a.created_at < ?
+            ORDER BY a.created_at DESC
+            "#
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load release alerts")?;
+        Ok(into_alerts(rows))
+    }
+
+    /// Dismiss one alert, or all of them when `id` is None
+    pub async fn dismiss_alerts(&self, id: Option<&str>) -> Result<u64> {
+        let dismissed = sqlx::query("UPDATE release_alerts SET dismissed_at = ?1 WHERE dismissed_at IS NULL AND (?2 IS NULL OR id = ?2)")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to dismiss release alerts")?
+            .rows_affected();
+        Ok(dismissed)
+    }
+}
+
+/// An alert joined with its follow: alert id, release JSON, alert created_at,
+/// then the follow's id, kind, name and created_at
+type AlertRow = (String, String, String, String, String, String, String);
+
+fn into_alerts(rows: Vec<AlertRow>) -> Vec<ReleaseAlert> {
+    rows.into_iter()
+        .filter_map(|(id, release, created_at, follow_id, kind, name, follow_created_at)| {
+            Some(ReleaseAlert {
+                id,
+                follow: FollowRow { id: follow_id, kind, name, created_at: follow_created_at }.into_follow()?,
+                release: serde_json::from_str(&release).ok()?,
+                created_at,
+            })
+        })
+        .collect()
+}
+
+fn matches(follow: &Follow, release: &LibrivoxRelease) -> bool {
+    let wanted = follow.kind.normalize(&follow.name);
+    let names = match follow.kind {
+        FollowKind::Author => &release.authors,
+        FollowKind::Genre => &release.genres,
+    };
+    names.iter().any(|name| follow.kind.normalize(name) == wanted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    fn release(id: &str, authors: &[&str], genres: &[&str]) -> LibrivoxRelease {
+        LibrivoxRelease {
+            id: id.to_string(),
+            title: format!("Release {}", id),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            genres: genres.iter().map(|g| g.to_string()).collect(),
+            language: Some("English".to_string()),
+            description: None,
+            total_seconds: None,
+            url_zip_file: Some(format!("https://archive.org/compress/{}/formats=64KBPS%20MP3&file=/{}.zip", id, id)),
+            url_librivox: None,
+            first_seen_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follows_raise_persistent_alerts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("follows.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let service = FollowService::new(db.get_pool().unwrap());
+
+        let austen = service.follow(FollowKind::Author, "Austen, Jane").await.unwrap();
+        assert_eq!(austen.name, "Jane Austen");
+        assert_eq!(service.follow(FollowKind::Author, "jane  austen").await.unwrap().id, austen.id);
+        let poetry = service.follow(FollowKind::Genre, " Poetry ").await.unwrap();
+        assert!(service.follow(FollowKind::Genre, "  ").await.is_err());
+        assert_eq!(service.list().await.unwrap().len(), 2);
+
+        let releases = [
+            release("emma_v5", &["Jane Austen"], &["Romance"]),
+            release("odes", &["John Keats"], &["poetry"]),
+            release("sonnets", &["Jane Austen"], &["Poetry"]),
+            release("other", &["Someone"], &["Horror"]),
+        ];
+        let alerts = service.record_alerts(&releases).await.unwrap();
+        assert_eq!(alerts.len(), 4, "sonnets matches both follows");
+        assert!(service.record_alerts(&releases).await.unwrap().is_empty(), "alerts are raised once");
+
+        let pending = service.pending_alerts().await.unwrap();
+        assert_eq!(pending.len(), 4);
+        assert!(pending.iter().all(|alert| alert.release.url_zip_file.is_some()));
+
+        assert_eq!(service.dismiss_alerts(Some(&pending[0].id)).await.unwrap(), 1);
+        assert_eq!(service.pending_alerts().await.unwrap().len(), 3);
+
+        service.unfollow(&poetry.id).await.unwrap();
+        assert_eq!(service.pending_alerts().await.unwrap().iter().filter(|a| a.follow.kind == FollowKind::Genre).count(), 0);
+        assert!(service.unfollow(&poetry.id).await.is_err());
+        let remaining = service.pending_alerts().await.unwrap().len() as u64;
+        assert_eq!(service.dismiss_alerts(None).await.unwrap(), remaining);
+        assert!(service.pending_alerts().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+// Frame indexes of MP3 files, built the first time a file is played and kept
+// in the database so later sessions seek exactly without reading the whole
+// file again. An index is built anew once the file's size or modification
+// time changes.
+
+use crate::audio::frame_index::{self, FrameIndex};
+use crate::filesystem::long_path::long_path;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+pub struct FrameIndexService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> FrameIndexService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The stored index of an MP3, or one built now. None for other files
+    /// and for files with no MPEG audio frames in them.
+    pub async fn get_or_build(&self, file_path: &str) -> Result<Option<FrameIndex>> {
+        let path = PathBuf::from(file_path);
+        if !frame_index::is_indexable(&path) {
+            return Ok(None);
+        }
+        let metadata = tokio::fs::metadata(long_path(&path))
+            .await
+            .with_context(|| format!("Failed to read metadata of {}", file_path))?;
+        let file_size = metadata.len() as i64;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |age| age.as_secs() as i64);
+
+        let stored = sqlx::query_as::<_, (i64, i64, i64, Vec<u8>)>(
+            r#"
+            SELECT sample_rate, samples_per_frame, total_samples, points
+            FROM audio_frame_indexes
+            WHERE file_path = ? AND file_size = ? AND modified = ?
+            "#,
+        )
+        .bind(file_path)
+        .bind(file_size)
+        .bind(modified)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to load frame index")?;
+        if let Some(index) = stored.and_then(|(sample_rate, samples_per_frame, total_samples, points)| {
+            FrameIndex::from_parts(sample_rate as u32, samples_per_frame as u32, total_samples as u64, &points)
+        }) {
+            return Ok(Some(index));
+        }
+
+        let scanned = tokio::task::spawn_blocking(move || frame_index::scan(&path)).await.context("Frame scan panicked")??;
+        let Some(index) = scanned else {
+            return Ok(None);
+        };
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO audio_frame_indexes
+                (file_path, file_size, modified, sample_rate, samples_per_frame, total_samples, points, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(file_path)
+        .bind(file_size)
+        .bind(modified)
+        .bind(index.sample_rate as i64)
+        .bind(index.samples_per_frame as i64)
+        .bind(index.total_samples as i64)
+        .bind(index.points_blob())
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to save frame index")?;
+        log::info!("Built frame index of {} ({} frames)", file_path, index.total_samples / index.samples_per_frame as u64);
+        Ok(Some(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    /// MPEG-1 Layer III frames at 44.1 kHz, alternating 64 and 128 kbps
+    fn write_mp3(path: &std::path::Path, frames: usize) {
+        let mut file = Vec::new();
+        for i in 0..frames {
+            let (index, length) = if i % 2 == 0 { (5, 208) } else { (9, 417) };
+            let start = file.len();
+            file.extend_from_slice(&[0xFF, 0xFB, index << 4, 0x00]);
+            file.resize(start + length, 0);
+        }
+        std::fs::write(path, file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_is_stored_and_rebuilt_when_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("frames.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = FrameIndexService::new(pool);
+
+        let path = dir.path().join("chapter.mp3");
+        write_mp3(&path, 40);
+        let file_path = path.to_string_lossy().to_string();
+        let built = service.get_or_build(&file_path).await.unwrap().unwrap();
+        assert_eq!(built.total_samples, 40 * 1_152);
+
+        // Served from the table while the file is unchanged
+        sqlx::query("UPDATE audio_frame_indexes SET total_samples = 1").execute(pool).await.unwrap();
+        assert_eq!(service.get_or_build(&file_path).await.unwrap().unwrap().total_samples, 1);
+
+        write_mp3(&path, 41);
+        assert_eq!(service.get_or_build(&file_path).await.unwrap().unwrap().total_samples, 41 * 1_152);
+        let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audio_frame_indexes").fetch_one(pool).await.unwrap();
+        assert_eq!(rows, 1);
+
+        let other = dir.path().join("chapter.m4b");
+        std::fs::write(&other, b"ftyp").unwrap();
+        assert!(service.get_or_build(&other.to_string_lossy()).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,260 @@
+// Home screen feed: every shelf assembled in one call, following the shelf layout
+// stored in preferences
+
+use super::RecommendationService;
+use crate::database::content_filter;
+use crate::database::models::{Audiobook, PlaybackProgress};
+use crate::database::repository::{PlaybackProgressRepository, PreferencesRepository};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use ts_rs::TS;
+
+pub const PREF_HOME_SHELVES: &str = "home.shelves";
+
+/// Books untouched for this long show up in the rediscovery shelf
+const REDISCOVERY_AFTER_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ShelfKind {
+    ContinueListening,
+    RecentlyAdded,
+    Recommendations,
+    FavoriteNarrators,
+    Rediscover,
+}
+
+impl ShelfKind {
+    fn title(self) -> &'static str {
+        match self {
+            ShelfKind::ContinueListening => "Continue listening",
+            ShelfKind::RecentlyAdded => "Recently added",
+            ShelfKind::Recommendations => "Recommended for you",
+            ShelfKind::FavoriteNarrators => "From your favorite narrators",
+            ShelfKind::Rediscover => "Rediscover",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ShelfConfig {
+    pub kind: ShelfKind,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_shelf_limit")]
+    #[ts(type = "number")]
+    pub limit: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_shelf_limit() -> i64 {
+    10
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HomeFeedConfig {
+    /// Shelves in display order
+    pub shelves: Vec<ShelfConfig>,
+}
+
+impl Default for HomeFeedConfig {
+    fn default() -> Self {
+        let shelf = |kind| ShelfConfig { kind, enabled: true, limit: default_shelf_limit() };
+        Self {
+            shelves: vec![
+                shelf(ShelfKind::ContinueListening),
+                shelf(ShelfKind::RecentlyAdded),
+                shelf(ShelfKind::Recommendations),
+                shelf(ShelfKind::FavoriteNarrators),
+                shelf(ShelfKind::Rediscover),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HomeFeedItem {
+    pub audiobook: Audiobook,
+    pub progress: Option<PlaybackProgress>,
+    /// Why the book is on the shelf, for recommendation shelves
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HomeShelf {
+    pub kind: ShelfKind,
+    pub title: String,
+    pub items: Vec<HomeFeedItem>,
+}
+
+pub struct HomeFeedService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> HomeFeedService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn load_config(&self) -> HomeFeedConfig {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_HOME_SHELVES).await.ok().flatten();
+        match stored.map(|json| serde_json::from_str::<HomeFeedConfig>(&json)) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                log::warn!("Ignoring invalid home shelf configuration: {}", e);
+                HomeFeedConfig::default()
+            }
+            None => HomeFeedConfig::default(),
+        }
+    }
+
+    pub async fn save_config(&self, config: &HomeFeedConfig) -> Result<()> {
+        let json = serde_json::to_string(config).context("Failed to serialize home shelf configuration")?;
+        PreferencesRepository::new(self.pool).set(PREF_HOME_SHELVES, &json).await
+    }
+
+    /// Every enabled shelf in configured order; empty shelves are left out
+    pub async fn get_feed(&self) -> Result<Vec<HomeShelf>> {
+        let config = self.load_config().await;
+        let mut shelves = Vec::new();
+
+        for shelf in config.shelves.iter().filter(|shelf| shelf.enabled && shelf.limit > 0) {
+            let books = self.shelf_books(shelf.kind, shelf.limit).await?;
+            if books.is_empty() {
+                continue;
+            }
+
+            let ids: Vec<&str> = books.iter().map(|(book, _)| book.id.as_str()).collect();
+            let mut progress = self.progress_for(&ids).await?;
+            let items = books
+                .into_iter()
+                .map(|(audiobook, reason)| HomeFeedItem {
+                    progress: progress.remove(&audiobook.id),
+                    audiobook,
+                    reason,
+                })
+                .collect();
+
+            shelves.push(HomeShelf {
+                kind: shelf.kind,
+                title: shelf.kind.title().to_string(),
+                items,
+            });
+        }
+
+        Ok(shelves)
+    }
+
+    async fn shelf_books(&self, kind: ShelfKind, limit: i64) -> Result<Vec<(Audiobook, Option<String>)>> {
+        let books = match kind {
+            ShelfKind::ContinueListening => {
+                PlaybackProgressRepository::new(self.pool).find_in_progress(limit).await?
+            }
+            ShelfKind::RecentlyAdded => {
+                self.query_books("SELECT * FROM audiobooks ORDER BY added_date DESC LIMIT ?", limit).await?
+            }
+            ShelfKind::Recommendations => {
+                let service = RecommendationService::new(self.pool);
+                let mut recommendations = service.get_current_recommendations(Some(limit as i32)).await?;
+                if recommendations.is_empty() {
+                    recommendations = service.generate_recommendations(Some(limit as i32)).await?;
+                }
+                let filter = content_filter::active();
+                return Ok(recommendations
+                    .into_iter()
+                    .filter(|rec| !filter.as_ref().is_some_and(|filter| filter.blocks_audiobook(&rec.audiobook)))
+                    .map(|rec| (rec.audiobook, rec.recommendation.recommendation_reason))
+                    .collect());
+            }
+            ShelfKind::FavoriteNarrators => {
+                self.query_books(
+                    r#"
+                    SELECT a.* FROM audiobooks a
+                    JOIN narrators n ON n.id = a.narrator_id
+                    LEFT JOIN playback_progress pp ON pp.audiobook_id = a.id
+                    WHERE n.is_favorite
+                      AND NOT COALESCE(pp.is_completed, FALSE)
+                      AND NOT COALESCE(pp.is_abandoned, FALSE)
+                    ORDER BY RANDOM()
+                    LIMIT ?
+                    "#,
+                    limit,
+                ).await?
+            }
+            ShelfKind::Rediscover => {
+                // Books never started, or put down long ago without finishing
+                let sql = format!(
+                    r#"
+                    SELECT a.* FROM audiobooks a
+                    LEFT JOIN playback_progress pp ON pp.audiobook_id = a.id
+                    WHERE a.added_date < datetime('now', '-{days} days')
+                      AND (pp.id IS NULL
+                           OR (NOT pp.is_completed AND NOT pp.is_abandoned
+                               AND pp.last_played_at < datetime('now', '-{days} days')))
+                    ORDER BY RANDOM()
+                    LIMIT ?
+                    "#,
+                    days = REDISCOVERY_AFTER_DAYS
+                );
+                self.query_books(&sql, limit).await?
+            }
+        };
+
+        Ok(books.into_iter().map(|book| (book, None)).collect())
+    }
+
+    async fn query_books(&self, sql: &str, limit: i64) -> Result<Vec<Audiobook>> {
+        let books = sqlx::query_as::<_, Audiobook>(sql)
+            .bind(limit)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load home shelf")?;
+
+        Ok(content_filter::apply(books))
+    }
+
+    async fn progress_for(&self, audiobook_ids: &[&str]) -> Result<HashMap<String, PlaybackProgress>> {
+        if audiobook_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let placeholders = vec!["?"; audiobook_ids.len()].join(", ");
+        let sql = format!("SELECT * FROM playback_progress WHERE audiobook_id IN ({})", placeholders);
+        let mut query = sqlx::query_as::<_, PlaybackProgress>(&sql);
+        for id in audiobook_ids {
+            query = query.bind(*id);
+        }
+
+        let rows = query.fetch_all(self.pool).await.context("Failed to load shelf progress")?;
+        Ok(rows.into_iter().map(|row| (row.audiobook_id.clone(), row)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_shelf_config_uses_defaults() {
+        let config: HomeFeedConfig = serde_json::from_str(
+            r#"{"shelves":[{"kind":"recently_added","limit":4},{"kind":"rediscover","enabled":false}]}"#
+        ).unwrap();
+
+        assert_eq!(config.shelves.len(), 2);
+        assert_eq!(config.shelves[0].kind, ShelfKind::RecentlyAdded);
+        assert!(config.shelves[0].enabled);
+        assert_eq!(config.shelves[0].limit, 4);
+        assert!(!config.shelves[1].enabled);
+        assert_eq!(config.shelves[1].limit, 10);
+    }
+}
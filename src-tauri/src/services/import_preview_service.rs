@@ -0,0 +1,277 @@
+// Dry runs of folder and LibriVox imports: the book and chapters an import
+// would create, worked out without writing to the database, so the user can
+// fix the title, author or chapter names before anything is saved. Previews
+// are kept in memory for PREVIEW_TTL; confirming one applies the user's
+// overrides and imports exactly what the preview showed.
+
+use crate::audio::tags::read_embedded_cover;
+use crate::filesystem::{AudiobookInfo, FileSystemScanner};
+use crate::services::compilation_service::CompilationSection;
+use crate::services::cover_resolution_service::{COVER_SOURCE_EMBEDDED, COVER_SOURCE_FOLDER};
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// How long a preview can be confirmed
+const PREVIEW_TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_PREVIEWS: usize = 16;
+
+/// Where the previewed book comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum ImportPreviewSource {
+    Directory { directory_path: String },
+    /// Nothing is downloaded until the import is confirmed
+    Librivox { identifier: String, cover_url: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportPreviewChapter {
+    pub chapter_number: i32,
+    pub title: String,
+    /// The file's path for a folder, its Archive.org name for LibriVox
+    pub file: String,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+    #[ts(type = "number | null")]
+    pub file_size: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportPreviewCover {
+    /// Where it was found: embedded, folder or librivox
+    pub source: String,
+    /// An image file, a data URL of art embedded in the audio, or a web address
+    pub image: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportPreview {
+    pub preview_id: String,
+    pub source: ImportPreviewSource,
+    pub title: String,
+    pub author: Option<String>,
+    pub narrator: Option<String>,
+    pub description: Option<String>,
+    pub genre: Option<String>,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+    pub chapters: Vec<ImportPreviewChapter>,
+    pub cover: Option<ImportPreviewCover>,
+}
+
+/// Changes to a preview before it is imported. Fields left out keep what was
+/// detected; an empty author, narrator, description or genre clears it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct ImportOverrides {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub narrator: Option<String>,
+    pub description: Option<String>,
+    pub genre: Option<String>,
+    /// Every chapter's title, in order
+    pub chapter_titles: Option<Vec<String>>,
+}
+
+impl ImportPreview {
+    pub fn new(source: ImportPreviewSource, title: String, chapters: Vec<ImportPreviewChapter>) -> Self {
+        Self {
+            preview_id: uuid::Uuid::new_v4().to_string(),
+            source,
+            title,
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            chapters,
+            cover: None,
+        }
+    }
+
+    /// The book a folder import creates from the folder's analysis
+    pub fn from_directory(info: &AudiobookInfo) -> Self {
+        let chapters = info.chapters.iter()
+            .map(|chapter| ImportPreviewChapter {
+                chapter_number: chapter.chapter_number,
+                title: chapter.title.clone(),
+                file: chapter.file_path.clone(),
+                duration: chapter.duration.map(|d| d as i64),
+                file_size: Some(chapter.file_size as i64),
+            })
+            .collect();
+        let mut preview = Self::new(
+            ImportPreviewSource::Directory { directory_path: info.directory_path.clone() },
+            info.title.clone(),
+            chapters,
+        );
+        preview.author = info.author.clone();
+        preview.duration = info.total_duration.map(|d| d as i64);
+        preview
+    }
+
+    /// The preview with the user's changes applied
+    pub fn with_overrides(mut self, overrides: ImportOverrides) -> Result<Self> {
+        let cleared = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        if let Some(title) = overrides.title {
+            self.title = cleared(title).context("The title cannot be empty")?;
+        }
+        if let Some(author) = overrides.author {
+            self.author = cleared(author);
+        }
+        if let Some(narrator) = overrides.narrator {
+            self.narrator = cleared(narrator);
+        }
+        if let Some(description) = overrides.description {
+            self.description = cleared(description);
+        }
+        if let Some(genre) = overrides.genre {
+            self.genre = cleared(genre);
+        }
+        if let Some(titles) = overrides.chapter_titles {
+            anyhow::ensure!(
+                titles.len() == self.chapters.len(),
+                "Expected {} chapter titles, got {}", self.chapters.len(), titles.len()
+            );
+            for (chapter, title) in self.chapters.iter_mut().zip(titles) {
+                chapter.title = cleared(title).with_context(|| format!("Chapter {} needs a title", chapter.chapter_number))?;
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// One chapter per section of a LibriVox recording, in track order
+pub fn librivox_chapters(sections: &[CompilationSection]) -> Vec<ImportPreviewChapter> {
+    sections.iter().enumerate()
+        .map(|(index, section)| ImportPreviewChapter {
+            chapter_number: (index + 1) as i32,
+            title: section.title.clone(),
+            file: section.file_name.clone(),
+            duration: section.duration,
+            file_size: None,
+        })
+        .collect()
+}
+
+/// The cover a folder import would pick up, looked for in the same order:
+/// art embedded in the first file, then an image in the folder
+pub fn detect_folder_cover(info: &AudiobookInfo) -> Option<ImportPreviewCover> {
+    if let Some(chapter) = info.chapters.first() {
+        match read_embedded_cover(&chapter.file_path) {
+            Ok(Some((data, extension))) => {
+                let mime = if extension == "jpg" { "jpeg" } else { extension };
+                return Some(ImportPreviewCover {
+                    source: COVER_SOURCE_EMBEDDED.to_string(),
+                    image: format!("data:image/{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(data)),
+                });
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Could not read embedded cover of {}: {}", chapter.file_path, e),
+        }
+    }
+    FileSystemScanner::new()
+        .find_cover_art(Path::new(&info.directory_path))
+        .map(|image| ImportPreviewCover {
+            source: COVER_SOURCE_FOLDER.to_string(),
+            image: image.to_string_lossy().to_string(),
+        })
+}
+
+static PREVIEWS: Mutex<Vec<(Instant, ImportPreview)>> = Mutex::new(Vec::new());
+
+/// Keep a preview until it is confirmed or expires. The oldest goes first
+/// once MAX_PREVIEWS are waiting.
+pub fn store(preview: &ImportPreview) {
+    let mut previews = PREVIEWS.lock().unwrap();
+    let now = Instant::now();
+    previews.retain(|(created, _)| now.duration_since(*created) < PREVIEW_TTL);
+    if previews.len() >= MAX_PREVIEWS {
+        previews.remove(0);
+    }
+    previews.push((now, preview.clone()));
+}
+
+pub fn get(preview_id: &str) -> Result<ImportPreview> {
+    PREVIEWS.lock().unwrap()
+        .iter()
+        .find(|(created, preview)| preview.preview_id == preview_id && created.elapsed() < PREVIEW_TTL)
+        .map(|(_, preview)| preview.clone())
+        .context("The import preview has expired; preview the import again")
+}
+
+/// Forget a preview once it has been imported
+pub fn discard(preview_id: &str) {
+    PREVIEWS.lock().unwrap().retain(|(_, preview)| preview.preview_id != preview_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filesystem::ChapterInfo;
+
+    fn folder_info() -> AudiobookInfo {
+        let chapter = |number: i32, title: &str| ChapterInfo {
+            chapter_number: number,
+            title: title.to_string(),
+            file_path: format!("/books/dune/{:02}.mp3", number),
+            duration: Some(600.5),
+            file_size: 1_000,
+        };
+        AudiobookInfo {
+            title: "Dune".to_string(),
+            author: Some("Frank Herbert".to_string()),
+            directory_path: "/books/dune".to_string(),
+            chapters: vec![chapter(1, "Chapter 1"), chapter(2, "Chapter 2")],
+            total_duration: Some(1_201.0),
+            is_multi_file: true,
+        }
+    }
+
+    #[test]
+    fn test_overrides_change_only_what_they_name() {
+        let preview = ImportPreview::from_directory(&folder_info());
+        assert_eq!(preview.duration, Some(1_201));
+        assert_eq!(preview.chapters[1].duration, Some(600));
+
+        let overrides = ImportOverrides {
+            title: Some(" Dune Messiah ".to_string()),
+            author: Some(String::new()),
+            narrator: Some("Scott Brick".to_string()),
+            chapter_titles: Some(vec!["Prologue".to_string(), "Book One".to_string()]),
+            ..Default::default()
+        };
+        let confirmed = preview.clone().with_overrides(overrides).unwrap();
+        assert_eq!(confirmed.title, "Dune Messiah");
+        assert_eq!(confirmed.author, None);
+        assert_eq!(confirmed.narrator.as_deref(), Some("Scott Brick"));
+        assert_eq!(confirmed.chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), ["Prologue", "Book One"]);
+        assert_eq!(confirmed.chapters[0].file, preview.chapters[0].file);
+        assert_eq!(preview.clone().with_overrides(ImportOverrides::default()).unwrap(), preview);
+
+        let blank_title = ImportOverrides { title: Some("  ".to_string()), ..Default::default() };
+        assert!(preview.clone().with_overrides(blank_title).is_err());
+        let too_few = ImportOverrides { chapter_titles: Some(vec!["Prologue".to_string()]), ..Default::default() };
+        assert!(preview.with_overrides(too_few).is_err());
+    }
+
+    #[test]
+    fn test_stored_previews_are_kept_until_discarded() {
+        let preview = ImportPreview::from_directory(&folder_info());
+        assert!(get(&preview.preview_id).is_err());
+        store(&preview);
+        assert_eq!(get(&preview.preview_id).unwrap(), preview);
+        discard(&preview.preview_id);
+        assert!(get(&preview.preview_id).is_err());
+    }
+}
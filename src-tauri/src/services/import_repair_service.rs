@@ -0,0 +1,102 @@
+// Startup check for LibriVox imports cut short, e.g. by the app closing while
+// files were still downloading. Such books are marked incomplete until their
+// missing files have been fetched again.
+
+use crate::database::models::{Audiobook, IMPORT_STATUS_COMPLETE, IMPORT_STATUS_INCOMPLETE};
+use crate::database::repository::AudiobookRepository;
+use crate::download::missing_archive_files;
+use crate::filesystem::FileSystemScanner;
+use crate::services::audiobook_source_service::SOURCE_LIBRIVOX;
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::path::Path;
+
+pub struct ImportRepairService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ImportRepairService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Mark LibriVox books whose folder lacks files as incomplete, and clear the
+    /// mark from any finished since. Returns the books that are incomplete.
+    pub async fn reconcile(&self) -> Result<Vec<Audiobook>> {
+        let repo = AudiobookRepository::new(self.pool);
+        let mut incomplete = Vec::new();
+        for mut audiobook in repo.find_by_source_type(SOURCE_LIBRIVOX).await? {
+            let status = self.check(&audiobook).await?;
+            if status == IMPORT_STATUS_INCOMPLETE {
+                audiobook.import_status = status.to_string();
+                incomplete.push(audiobook);
+            }
+        }
+
+        if !incomplete.is_empty() {
+            println!("🧩 IMPORT REPAIR: {} LibriVox imports are incomplete", incomplete.len());
+        }
+        Ok(incomplete)
+    }
+
+    /// Store whether the book's download folder has all its files
+    pub async fn check(&self, audiobook: &Audiobook) -> Result<&'static str> {
+        let status = import_status(Path::new(&audiobook.file_path));
+        if audiobook.import_status != status {
+            AudiobookRepository::new(self.pool).set_import_status(&audiobook.id, status).await?;
+        }
+        Ok(status)
+    }
+
+    /// Point the book at its download folder after its files were fetched again
+    /// and record whether anything is still missing
+    pub async fn finish_resume(&self, audiobook_id: &str, local_path: &Path) -> Result<Audiobook> {
+        let repo = AudiobookRepository::new(self.pool);
+        repo.set_file_path(audiobook_id, &local_path.to_string_lossy()).await?;
+        repo.set_import_status(audiobook_id, import_status(local_path)).await?;
+        repo.find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))
+    }
+}
+
+/// A download folder is complete when it holds audio and nothing listed in its
+/// manifest is missing
+pub fn import_status(path: &Path) -> &'static str {
+    let dir = if path.is_file() { path.parent().unwrap_or(path) } else { path };
+    let scanner = FileSystemScanner::new();
+    let has_audio = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().any(|entry| scanner.is_supported_audio_file(&entry.path())))
+        .unwrap_or(false);
+
+    if has_audio && missing_archive_files(dir).is_empty() {
+        IMPORT_STATUS_COMPLETE
+    } else {
+        IMPORT_STATUS_INCOMPLETE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::{ManifestEntry, ARCHIVE_MANIFEST_FILE};
+
+    #[test]
+    fn test_import_status() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(import_status(dir.path()), IMPORT_STATUS_INCOMPLETE);
+        assert_eq!(import_status(&dir.path().join("gone")), IMPORT_STATUS_INCOMPLETE);
+
+        std::fs::write(dir.path().join("chapter_01.mp3"), b"audio").unwrap();
+        assert_eq!(import_status(dir.path()), IMPORT_STATUS_COMPLETE);
+
+        let manifest = vec![
+            ManifestEntry { name: "chapter_01.mp3".to_string(), size: Some(5) },
+            ManifestEntry { name: "chapter_02.mp3".to_string(), size: Some(5) },
+        ];
+        std::fs::write(dir.path().join(ARCHIVE_MANIFEST_FILE), serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert_eq!(import_status(dir.path()), IMPORT_STATUS_INCOMPLETE);
+
+        std::fs::write(dir.path().join("chapter_02.mp3"), b"audio").unwrap();
+        assert_eq!(import_status(&dir.path().join("chapter_02.mp3")), IMPORT_STATUS_COMPLETE);
+    }
+}
@@ -0,0 +1,79 @@
+// Whole-library JSON export for scripts and tools outside the app: every book
+// with its chapters and listening progress
+
+use crate::database::models::{Audiobook, Chapter, PlaybackProgress};
+use crate::database::repository::{AudiobookRepository, ChapterRepository, PlaybackProgressRepository};
+use crate::filesystem::atomic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
+use ts_rs::TS;
+
+pub const LIBRARY_EXPORT_FORMAT: &str = "audiovibe-library";
+pub const LIBRARY_EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibraryExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub books: Vec<ExportedBook>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExportedBook {
+    pub audiobook: Audiobook,
+    pub chapters: Vec<Chapter>,
+    pub progress: Option<PlaybackProgress>,
+}
+
+pub struct LibraryExportService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> LibraryExportService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn export(&self) -> Result<LibraryExport> {
+        let audiobooks = AudiobookRepository::new(self.pool).find_all().await?;
+        let mut progress: HashMap<String, PlaybackProgress> = PlaybackProgressRepository::new(self.pool)
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|progress| (progress.audiobook_id.clone(), progress))
+            .collect();
+
+        let chapter_repo = ChapterRepository::new(self.pool);
+        let mut books = Vec::with_capacity(audiobooks.len());
+        for audiobook in audiobooks {
+            let mut chapters = chapter_repo.find_by_audiobook_id(&audiobook.id).await?;
+            chapters.sort_by_key(|chapter| chapter.chapter_number);
+            books.push(ExportedBook {
+                progress: progress.remove(&audiobook.id),
+                chapters,
+                audiobook,
+            });
+        }
+
+        Ok(LibraryExport {
+            format: LIBRARY_EXPORT_FORMAT.to_string(),
+            version: LIBRARY_EXPORT_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            books,
+        })
+    }
+
+    /// Export to a pretty-printed JSON file; returns the number of books written
+    pub async fn export_to_file(&self, path: &Path) -> Result<usize> {
+        let export = self.export().await?;
+        let json = serde_json::to_string_pretty(&export).context("Failed to serialize library")?;
+        atomic::write(path, json)?;
+        Ok(export.books.len())
+    }
+}
@@ -0,0 +1,121 @@
+// The folders the library lives in. Command file paths must resolve inside one
+// of them (or the app's own data folders), so the webview cannot read arbitrary
+// files. A folder becomes a root when the user picks it, or a file in it, in a
+// file dialog, or imports it from the command line; never from a path passed to
+// a command.
+
+use crate::database::repository::PreferencesRepository;
+use crate::filesystem::long_path::strip_extended;
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+pub const PREF_LIBRARY_ROOTS: &str = "library.roots";
+
+pub struct LibraryRootService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> LibraryRootService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The saved roots. Libraries from before roots were tracked get theirs from
+    /// the folders of the books already imported.
+    pub async fn roots(&self) -> Result<Vec<PathBuf>> {
+        let prefs = PreferencesRepository::new(self.pool);
+        if let Some(json) = prefs.get(PREF_LIBRARY_ROOTS).await? {
+            let roots: Vec<String> = serde_json::from_str(&json).context("Invalid library roots")?;
+            return Ok(roots.into_iter().map(PathBuf::from).collect());
+        }
+
+        let paths: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT file_path FROM audiobooks
+            UNION SELECT file_path FROM chapters
+            UNION SELECT file_path FROM ebooks
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load library file paths")?;
+
+        let mut roots = Vec::new();
+        for path in paths.iter().filter(|path| !path.contains("://")) {
+            merge_root(&mut roots, folder_of(Path::new(path)));
+        }
+        self.save(&roots).await?;
+        log::info!("Library roots initialized from existing books: {}", roots.len());
+        Ok(roots)
+    }
+
+    /// Make the folder of `path` (itself, when it is a folder) a root
+    pub async fn add(&self, path: &Path) -> Result<()> {
+        let mut roots = self.roots().await?;
+        if merge_root(&mut roots, folder_of(path)) {
+            self.save(&roots).await?;
+        }
+        Ok(())
+    }
+
+    /// Move the roots under `old` to the same place under `new`, after the
+    /// library's storage folder moved
+    pub async fn relocate(&self, old: &Path, new: &Path) -> Result<()> {
+        let roots = self.roots().await?;
+        let mut moved = Vec::new();
+        for root in &roots {
+            let root = match root.strip_prefix(old) {
+                Ok(rest) => new.join(rest),
+                Err(_) => root.clone(),
+            };
+            merge_root(&mut moved, root);
+        }
+        if moved != roots {
+            self.save(&moved).await?;
+        }
+        Ok(())
+    }
+
+    async fn save(&self, roots: &[PathBuf]) -> Result<()> {
+        let roots: Vec<String> = roots.iter().map(|root| root.to_string_lossy().to_string()).collect();
+        let json = serde_json::to_string(&roots).context("Failed to serialize library roots")?;
+        PreferencesRepository::new(self.pool).set(PREF_LIBRARY_ROOTS, &json).await
+    }
+}
+
+fn folder_of(path: &Path) -> PathBuf {
+    let path = std::fs::canonicalize(path).map(|path| strip_extended(&path)).unwrap_or_else(|_| path.to_path_buf());
+    if path.is_dir() {
+        return path;
+    }
+    path.parent().map(Path::to_path_buf).unwrap_or(path)
+}
+
+/// Add `root` unless an existing root covers it, dropping roots it covers.
+/// Returns whether the list changed.
+fn merge_root(roots: &mut Vec<PathBuf>, root: PathBuf) -> bool {
+    if root.as_os_str().is_empty() || roots.iter().any(|existing| root.starts_with(existing)) {
+        return false;
+    }
+    roots.retain(|existing| !existing.starts_with(&root));
+    roots.push(root);
+    roots.sort();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_root_keeps_outermost_folders() {
+        let mut roots = Vec::new();
+        assert!(merge_root(&mut roots, PathBuf::from("/books/fantasy/tolkien")));
+        assert!(merge_root(&mut roots, PathBuf::from("/podcasts")));
+        assert!(!merge_root(&mut roots, PathBuf::from("/books/fantasy/tolkien/hobbit")));
+        assert!(merge_root(&mut roots, PathBuf::from("/books")));
+        assert_eq!(roots, vec![PathBuf::from("/books"), PathBuf::from("/podcasts")]);
+        assert!(!merge_root(&mut roots, PathBuf::new()));
+    }
+}
@@ -0,0 +1,305 @@
+// Everything LibriVox has recorded by one author. LibriVox sometimes catalogues
+// the same person under several author ids, so the page gathers the recordings
+// of every id with the same name, and marks those already in the library.
+
+use crate::database::content_filter;
+use crate::services::audiobook_source_service::{archive_identifier, SOURCE_LIBRIVOX};
+use crate::services::author_service::normalize_author_name;
+use crate::services::librivox_release_service::{parse_feed, LibrivoxRelease};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use ts_rs::TS;
+
+const LIBRIVOX_AUTHORS_URL: &str = "https://librivox.org/api/feed/authors";
+const LIBRIVOX_FEED_URL: &str = "https://librivox.org/api/feed/audiobooks";
+const PAGE_SIZE: usize = 100;
+/// Stop paging after this many recordings; no LibriVox author comes close
+const MAX_RECORDINGS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibrivoxAuthorPage {
+    pub name: String,
+    /// Every LibriVox author id the recordings were gathered from
+    pub author_ids: Vec<String>,
+    pub born: Option<String>,
+    pub died: Option<String>,
+    /// Sorted by title
+    pub works: Vec<LibrivoxWork>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibrivoxWork {
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub genres: Vec<String>,
+    pub language: Option<String>,
+    pub description: Option<String>,
+    #[ts(type = "number | null")]
+    pub total_seconds: Option<i64>,
+    pub url_zip_file: Option<String>,
+    pub url_librivox: Option<String>,
+    /// The library's copy of this recording, if it was imported already
+    pub audiobook_id: Option<String>,
+}
+
+impl From<LibrivoxRelease> for LibrivoxWork {
+    fn from(release: LibrivoxRelease) -> Self {
+        Self {
+            id: release.id,
+            title: release.title,
+            authors: release.authors,
+            genres: release.genres,
+            language: release.language,
+            description: release.description,
+            total_seconds: release.total_seconds,
+            url_zip_file: release.url_zip_file,
+            url_librivox: release.url_librivox,
+            audiobook_id: None,
+        }
+    }
+}
+
+/// An entry of the LibriVox authors feed
+#[derive(Debug, Clone, PartialEq)]
+struct LibrivoxAuthor {
+    id: String,
+    first_name: String,
+    last_name: String,
+    dob: Option<String>,
+    dod: Option<String>,
+}
+
+impl LibrivoxAuthor {
+    fn name(&self) -> String {
+        format!("{} {}", self.first_name, self.last_name).trim().to_string()
+    }
+}
+
+pub struct LibrivoxAuthorService<'a> {
+    pool: &'a SqlitePool,
+    client: reqwest::Client,
+}
+
+impl<'a> LibrivoxAuthorService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self { pool, client })
+    }
+
+    /// The author page for a LibriVox author id, or for a name such as
+    /// "Mark Twain" or "Twain, Mark"
+    pub async fn author_page(&self, author_id_or_name: &str) -> Result<LibrivoxAuthorPage> {
+        let query = author_id_or_name.trim();
+        anyhow::ensure!(!query.is_empty(), "No author given");
+
+        let wanted = if query.chars().all(|c| c.is_ascii_digit()) {
+            self.fetch_authors(&[("id", query)])
+                .await?
+                .into_iter()
+                .next()
+                .with_context(|| format!("No LibriVox author with id {}", query))?
+                .name()
+        } else {
+            query.to_string()
+        };
+
+        let last_name = last_name(&wanted);
+        let candidates = self.fetch_authors(&[("last_name", last_name.as_str())]).await?;
+        let authors = same_author(&candidates, &wanted);
+        anyhow::ensure!(!authors.is_empty(), "No LibriVox author named {}", wanted);
+
+        let ids: HashSet<&str> = authors.iter().map(|author| author.id.as_str()).collect();
+        let mut books = Vec::new();
+        for last_name in authors.iter().map(|author| author.last_name.as_str()).collect::<HashSet<_>>() {
+            books.extend(self.fetch_books(last_name).await?.into_iter().filter(|book| written_by(book, &ids)));
+        }
+        if let Some(filter) = content_filter::active() {
+            books.retain(|book| !filter.blocks_librivox_book(book));
+        }
+
+        let mut works: Vec<LibrivoxWork> = parse_feed(&serde_json::json!({ "books": books }), Utc::now())
+            .into_iter()
+            .map(LibrivoxWork::from)
+            .collect();
+        let mut seen = HashSet::new();
+        works.retain(|work| seen.insert(work.id.clone()));
+        works.sort_by_cached_key(|work| (work.title.to_lowercase(), work.id.clone()));
+        self.mark_in_library(&mut works).await?;
+
+        let mut author_ids: Vec<String> = authors.iter().map(|author| author.id.clone()).collect();
+        author_ids.sort_by_key(|id| id.parse::<u64>().unwrap_or(u64::MAX));
+        Ok(LibrivoxAuthorPage {
+            name: authors[0].name(),
+            author_ids,
+            born: authors.iter().find_map(|author| author.dob.clone()),
+            died: authors.iter().find_map(|author| author.dod.clone()),
+            works,
+        })
+    }
+
+    /// Set audiobook_id on the works whose recording is already imported
+    async fn mark_in_library(&self, works: &mut [LibrivoxWork]) -> Result<()> {
+        let imported: HashMap<String, String> =
+            sqlx::query_as::<_, (String, String)>("SELECT source_id, id FROM audiobooks WHERE source_type = ? AND source_id IS NOT NULL")
+                .bind(SOURCE_LIBRIVOX)
+                .fetch_all(self.pool)
+                .await
+                .context("Failed to load imported LibriVox books")?
+                .into_iter()
+                .collect();
+
+        for work in works.iter_mut() {
+            work.audiobook_id = work
+                .url_zip_file
+                .as_deref()
+                .and_then(archive_identifier)
+                .and_then(|identifier| imported.get(&identifier).cloned());
+        }
+        Ok(())
+    }
+
+    async fn fetch_authors(&self, params: &[(&str, &str)]) -> Result<Vec<LibrivoxAuthor>> {
+        let feed = self.get(LIBRIVOX_AUTHORS_URL, params).await?;
+        Ok(parse_authors(&feed))
+    }
+
+    /// Every recording LibriVox lists under an author's last name, page by page
+    async fn fetch_books(&self, last_name: &str) -> Result<Vec<Value>> {
+        let mut books = Vec::new();
+        while books.len() < MAX_RECORDINGS {
+            let offset = books.len().to_string();
+            let limit = PAGE_SIZE.to_string();
+            let feed = self
+                .get(LIBRIVOX_FEED_URL, &[("author", last_name), ("extended", "1"), ("offset", offset.as_str()), ("limit", limit.as_str())])
+                .await?;
+            let page = feed.get("books").and_then(|books| books.as_array()).cloned().unwrap_or_default();
+            let done = page.len() < PAGE_SIZE;
+            books.extend(page);
+            if done {
+                break;
+            }
+        }
+        Ok(books)
+    }
+
+    async fn get(&self, url: &str, params: &[(&str, &str)]) -> Result<Value> {
+        let response = self
+            .client
+            .get(url)
+            .query(&[("format", "json")])
+            .query(params)
+            .header("User-Agent", "AudioVibe/1.0.0")
+            .send()
+            .await
+            .context("LibriVox request failed")?;
+
+        // LibriVox answers 404 when nothing matches
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Value::Null);
+        }
+        response
+            .error_for_status()
+            .context("LibriVox request failed")?
+            .json()
+            .await
+            .context("Invalid LibriVox response")
+    }
+}
+
+/// The last name LibriVox files an author under: the part before a comma, or the last word
+pub fn last_name(name: &str) -> String {
+    match name.split_once(',') {
+        Some((last, _)) => last.trim().to_string(),
+        None => name.split_whitespace().last().unwrap_or_default().to_string(),
+    }
+}
+
+/// The entries that are the wanted author, whatever id each was catalogued under
+fn same_author(candidates: &[LibrivoxAuthor], wanted: &str) -> Vec<LibrivoxAuthor> {
+    let wanted = normalize_author_name(wanted);
+    candidates
+        .iter()
+        .filter(|author| normalize_author_name(&author.name()) == wanted)
+        .cloned()
+        .collect()
+}
+
+fn written_by(book: &Value, ids: &HashSet<&str>) -> bool {
+    book.get("authors")
+        .and_then(|authors| authors.as_array())
+        .is_some_and(|authors| authors.iter().any(|author| text(author, "id").is_some_and(|id| ids.contains(id.as_str()))))
+}
+
+fn parse_authors(feed: &Value) -> Vec<LibrivoxAuthor> {
+    let Some(authors) = feed.get("authors").and_then(|authors| authors.as_array()) else {
+        return Vec::new();
+    };
+    authors
+        .iter()
+        .filter_map(|author| {
+            Some(LibrivoxAuthor {
+                id: text(author, "id")?,
+                first_name: text(author, "first_name").unwrap_or_default(),
+                last_name: text(author, "last_name")?,
+                dob: text(author, "dob"),
+                dod: text(author, "dod"),
+            })
+        })
+        .collect()
+}
+
+fn text(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|field| match field {
+            Value::String(text) => Some(text.trim().to_string()),
+            Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        })
+        .filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_author_gathers_every_id() {
+        let feed = serde_json::json!({ "authors": [
+            { "id": "119", "first_name": "Mark", "last_name": "Twain", "dob": "1835", "dod": "1910" },
+            { "id": 4021, "first_name": "Mark ", "last_name": "TWAIN", "dob": "", "dod": "" },
+            { "id": "77", "first_name": "Shania", "last_name": "Twain" },
+            { "id": "", "first_name": "Broken", "last_name": "Entry" }
+        ]});
+        let candidates = parse_authors(&feed);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[1].dob, None);
+
+        let twain = same_author(&candidates, "Twain, Mark");
+        assert_eq!(twain.iter().map(|author| author.id.as_str()).collect::<Vec<_>>(), vec!["119", "4021"]);
+        assert!(same_author(&candidates, "Samuel Clemens").is_empty());
+
+        assert_eq!(last_name("Twain, Mark"), "Twain");
+        assert_eq!(last_name("Arthur Conan Doyle"), "Doyle");
+    }
+
+    #[test]
+    fn test_written_by_matches_any_author_id() {
+        let ids: HashSet<&str> = ["119", "4021"].into_iter().collect();
+        let book = |author_id: Value| serde_json::json!({ "title": "Book", "authors": [{ "id": "3" }, { "id": author_id }] });
+        assert!(written_by(&book(serde_json::json!("4021")), &ids));
+        assert!(written_by(&book(serde_json::json!(119)), &ids));
+        assert!(!written_by(&book(serde_json::json!("5")), &ids));
+        assert!(!written_by(&serde_json::json!({ "title": "No authors" }), &ids));
+    }
+}
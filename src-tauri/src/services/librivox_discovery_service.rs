@@ -0,0 +1,206 @@
+// Discovery mode: LibriVox recordings that match the listener's taste and are
+// not in the library yet. It needs no listening history: a new listener's
+// taste is the genres and authors they picked during onboarding, and the
+// scores listening builds up take over from there.
+
+use crate::database::content_filter;
+use crate::services::audiobook_source_service::{archive_identifier, SOURCE_LIBRIVOX};
+use crate::services::author_service::normalize_author_name;
+use crate::services::librivox_author_service::last_name;
+use crate::services::librivox_release_service::{parse_feed, LibrivoxRelease};
+use crate::services::recommendation_service::{FactorKind, RecommendationFactor, RecommendationService, RecommendationWeights};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use ts_rs::TS;
+
+const LIBRIVOX_FEED_URL: &str = "https://librivox.org/api/feed/audiobooks";
+/// How many of the strongest genres and authors are searched
+const SEARCHED_PREFERENCES: i32 = 3;
+const RESULTS_PER_SEARCH: u32 = 50;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibrivoxSuggestion {
+    pub release: LibrivoxRelease,
+    pub score: f64,
+    pub reason: String,
+    /// The picked or learned genres and authors the recording matches
+    pub factors: Vec<RecommendationFactor>,
+}
+
+pub struct LibrivoxDiscoveryService<'a> {
+    pool: &'a SqlitePool,
+    client: reqwest::Client,
+}
+
+impl<'a> LibrivoxDiscoveryService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self { pool, client })
+    }
+
+    /// Up to `limit` recordings for the listener's strongest genres and
+    /// authors, best match first. Empty until a taste profile is submitted
+    /// or something has been listened to.
+    pub async fn discover(&self, limit: usize) -> Result<Vec<LibrivoxSuggestion>> {
+        let recommendations = RecommendationService::new(self.pool);
+        let genres = recommendations.taste_preferences("genre", SEARCHED_PREFERENCES).await?;
+        let authors = recommendations.taste_preferences("author", SEARCHED_PREFERENCES).await?;
+        if genres.is_empty() && authors.is_empty() {
+            return Ok(Vec::new());
+        }
+        let weights = recommendations.weights().await?;
+
+        let mut books = Vec::new();
+        for (genre, _) in &genres {
+            books.extend(self.fetch_books(&[("genre", genre.as_str())]).await?);
+        }
+        for (author, _) in &authors {
+            books.extend(self.fetch_books(&[("author", last_name(author).as_str())]).await?);
+        }
+        if let Some(filter) = content_filter::active() {
+            books.retain(|book| !filter.blocks_librivox_book(book));
+        }
+
+        let imported = self.imported().await?;
+        let mut seen = HashSet::new();
+        let mut suggestions: Vec<LibrivoxSuggestion> = parse_feed(&serde_json::json!({ "books": books }), Utc::now())
+            .into_iter()
+            .filter(|release| seen.insert(release.id.clone()))
+            .filter(|release| {
+                !release.url_zip_file.as_deref().and_then(archive_identifier).is_some_and(|identifier| imported.contains(&identifier))
+            })
+            .filter_map(|release| suggest(release, &genres, &authors, weights))
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.release.title.to_lowercase().cmp(&b.release.title.to_lowercase()))
+        });
+        suggestions.truncate(limit);
+        Ok(suggestions)
+    }
+
+    /// Archive.org identifiers of the LibriVox recordings already imported
+    async fn imported(&self) -> Result<HashSet<String>> {
+        let identifiers = sqlx::query_scalar::<_, String>("SELECT source_id FROM audiobooks WHERE source_type = ? AND source_id IS NOT NULL")
+            .bind(SOURCE_LIBRIVOX)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load imported LibriVox books")?;
+        Ok(identifiers.into_iter().collect())
+    }
+
+    async fn fetch_books(&self, params: &[(&str, &str)]) -> Result<Vec<Value>> {
+        let response = self
+            .client
+            .get(LIBRIVOX_FEED_URL)
+            .query(&[("format", "json"), ("extended", "1")])
+            .query(&[("limit", RESULTS_PER_SEARCH)])
+            .query(params)
+            .header("User-Agent", "AudioVibe/1.0.0")
+            .send()
+            .await
+            .context("LibriVox request failed")?;
+
+        // LibriVox answers 404 when nothing matches
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let feed: Value = response
+            .error_for_status()
+            .context("LibriVox request failed")?
+            .json()
+            .await
+            .context("Invalid LibriVox response")?;
+        Ok(feed.get("books").and_then(|books| books.as_array()).cloned().unwrap_or_default())
+    }
+}
+
+/// Score a recording by the genres and authors it matches, or None when it
+/// matches none. The search by last name also finds other authors of that
+/// name, so authors are compared in full.
+fn suggest(
+    release: LibrivoxRelease,
+    genres: &[(String, f64)],
+    authors: &[(String, f64)],
+    weights: RecommendationWeights,
+) -> Option<LibrivoxSuggestion> {
+    let release_genres: Vec<String> = release.genres.iter().map(|genre| genre.to_lowercase()).collect();
+    let release_authors: HashSet<String> = release.authors.iter().map(|author| normalize_author_name(author)).collect();
+
+    let mut factors = Vec::new();
+    for (genre, score) in genres {
+        let wanted = genre.to_lowercase();
+        if release_genres.iter().any(|genre| genre.contains(&wanted)) {
+            factors.push(RecommendationFactor { kind: FactorKind::Genre, value: genre.clone(), score: *score, weight: weights.genre });
+        }
+    }
+    for (author, score) in authors {
+        if release_authors.contains(&normalize_author_name(author)) {
+            factors.push(RecommendationFactor { kind: FactorKind::Author, value: author.clone(), score: *score, weight: weights.author });
+        }
+    }
+
+    let score: f64 = factors.iter().map(|factor| factor.score * factor.weight).sum();
+    if score <= 0.0 {
+        return None;
+    }
+    let best = factors
+        .iter()
+        .max_by(|a, b| (a.score * a.weight).partial_cmp(&(b.score * b.weight)).unwrap_or(std::cmp::Ordering::Equal))?;
+    let reason = match best.kind {
+        FactorKind::Author => format!("Because you like {}", best.value),
+        _ => format!("Because you enjoy {} audiobooks", best.value),
+    };
+    Some(LibrivoxSuggestion { release, score, reason, factors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(title: &str, authors: &[&str], genres: &[&str]) -> LibrivoxRelease {
+        LibrivoxRelease {
+            id: title.to_string(),
+            title: title.to_string(),
+            authors: authors.iter().map(|author| author.to_string()).collect(),
+            genres: genres.iter().map(|genre| genre.to_string()).collect(),
+            language: Some("English".to_string()),
+            description: None,
+            total_seconds: None,
+            url_zip_file: None,
+            url_librivox: None,
+            first_seen_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_suggest_scores_by_picked_genres_and_authors() {
+        let genres = vec![("Horror".to_string(), 0.6)];
+        let authors = vec![("Jane Austen".to_string(), 0.6)];
+        let weights = RecommendationWeights::default();
+
+        let dracula = suggest(release("Dracula", &["Bram Stoker"], &["Horror & Supernatural Fiction"]), &genres, &authors, weights).unwrap();
+        assert_eq!(dracula.factors.len(), 1);
+        assert_eq!(dracula.reason, "Because you enjoy Horror audiobooks");
+
+        let sanditon = suggest(release("Sanditon", &["Jane Austen"], &["Gothic Fiction", "Horror"]), &genres, &authors, weights).unwrap();
+        assert!(sanditon.score > dracula.score);
+        assert_eq!(sanditon.reason, "Because you like Jane Austen");
+
+        // Found by last name only
+        assert!(suggest(release("Poems", &["Wilfred Austen"], &["Poetry"]), &genres, &authors, weights).is_none());
+
+        let off = RecommendationWeights { genre: 0.0, ..weights };
+        assert!(suggest(release("Dracula", &["Bram Stoker"], &["Horror"]), &genres, &authors, off).is_none());
+    }
+}
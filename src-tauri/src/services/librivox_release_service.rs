@@ -0,0 +1,341 @@
+// LibriVox's newest recordings: fetched in the background from the LibriVox
+// API, cached in the database so the feed opens instantly and offline, and
+// checked against the authors in the library for release alerts.
+
+use crate::database::content_filter;
+use crate::database::repository::{AuthorRepository, PreferencesRepository};
+use crate::services::author_service::normalize_author_name;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+const LIBRIVOX_FEED_URL: &str = "https://librivox.org/api/feed/audiobooks";
+pub const PREF_RELEASES_FETCHED_AT: &str = "librivox.releases.fetched_at";
+/// Emit an alert when a new recording is by an author already in the library
+pub const PREF_RELEASE_ALERTS: &str = "librivox.releases.alerts";
+/// How far back the first fetch looks, and how long releases stay in the feed
+pub const RELEASE_WINDOW_DAYS: i64 = 30;
+/// The feed is fetched again when the cache is older than this
+pub const RELEASES_MAX_AGE_HOURS: i64 = 12;
+const FETCH_LIMIT: u32 = 200;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibrivoxRelease {
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub genres: Vec<String>,
+    pub language: Option<String>,
+    pub description: Option<String>,
+    #[ts(type = "number | null")]
+    pub total_seconds: Option<i64>,
+    /// Pass to load_and_play_librivox to import the recording
+    pub url_zip_file: Option<String>,
+    pub url_librivox: Option<String>,
+    pub first_seen_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct ReleaseRow {
+    id: String,
+    title: String,
+    authors: String,
+    genres: String,
+    language: Option<String>,
+    description: Option<String>,
+    total_seconds: Option<i64>,
+    url_zip_file: Option<String>,
+    url_librivox: Option<String>,
+    first_seen_at: String,
+}
+
+impl From<ReleaseRow> for LibrivoxRelease {
+    fn from(row: ReleaseRow) -> Self {
+        Self {
+            id: row.id,
+            title: row.title,
+            authors: serde_json::from_str(&row.authors).unwrap_or_default(),
+            genres: serde_json::from_str(&row.genres).unwrap_or_default(),
+            language: row.language,
+            description: row.description,
+            total_seconds: row.total_seconds,
+            url_zip_file: row.url_zip_file,
+            url_librivox: row.url_librivox,
+            first_seen_at: row.first_seen_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseFetch {
+    /// Releases not in the cache before this fetch
+    pub new_releases: Vec<LibrivoxRelease>,
+    /// The new releases by authors the library already has books by
+    pub by_known_authors: Vec<LibrivoxRelease>,
+    pub pruned: u64,
+}
+
+pub struct LibrivoxReleaseService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> LibrivoxReleaseService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Cached releases, newest first, without those the content filter blocks.
+    /// Genre and language match case-insensitively.
+    pub async fn releases(&self, genre: Option<&str>, language: Option<&str>) -> Result<Vec<LibrivoxRelease>> {
+        let rows = sqlx::query_as::<_, ReleaseRow>("SELECT * FROM librivox_releases ORDER BY first_seen_at DESC, id DESC")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load LibriVox releases")?;
+
+        let genre = genre.map(str::trim).filter(|genre| !genre.is_empty()).map(str::to_lowercase);
+        let language = language.map(str::trim).filter(|language| !language.is_empty()).map(str::to_lowercase);
+        Ok(rows
+            .into_iter()
+            .map(LibrivoxRelease::from)
+            .filter(|release| genre.as_ref().is_none_or(|genre| release.genres.iter().any(|g| g.to_lowercase() == *genre)))
+            .filter(|release| language.as_ref().is_none_or(|language| release.language.as_deref().map(str::to_lowercase).as_ref() == Some(language)))
+            .filter(|release| !is_blocked(release))
+            .collect())
+    }
+
+    /// Whether the cache is missing or older than RELEASES_MAX_AGE_HOURS
+    pub async fn is_stale(&self, now: DateTime<Utc>) -> Result<bool> {
+        let fetched_at = PreferencesRepository::new(self.pool).get(PREF_RELEASES_FETCHED_AT).await?;
+        Ok(fetched_at
+            .and_then(|fetched_at| DateTime::parse_from_rfc3339(&fetched_at).ok())
+            .is_none_or(|fetched_at| now - fetched_at.with_timezone(&Utc) > Duration::hours(RELEASES_MAX_AGE_HOURS)))
+    }
+
+    /// Fetch what LibriVox catalogued since the last fetch (or within the
+    /// release window on the first one) and drop releases past the window
+    pub async fn fetch(&self, now: DateTime<Utc>) -> Result<ReleaseFetch> {
+        let prefs = PreferencesRepository::new(self.pool);
+        let window_start = now - Duration::days(RELEASE_WINDOW_DAYS);
+        let since = prefs
+            .get(PREF_RELEASES_FETCHED_AT)
+            .await?
+            .and_then(|fetched_at| DateTime::parse_from_rfc3339(&fetched_at).ok())
+            .map(|fetched_at| fetched_at.with_timezone(&Utc).max(window_start))
+            .unwrap_or(window_start);
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+        let response = client
+            .get(LIBRIVOX_FEED_URL)
+            .query(&[
+                ("format", "json".to_string()),
+                ("extended", "1".to_string()),
+                ("since", since.timestamp().to_string()),
+                ("limit", FETCH_LIMIT.to_string()),
+            ])
+            .header("User-Agent", "AudioVibe/1.0.0")
+            .send()
+            .await
+            .context("LibriVox request failed")?;
+
+        // LibriVox answers 404 when nothing was catalogued in the period
+        let feed = if response.status() == reqwest::StatusCode::NOT_FOUND {
+            serde_json::Value::Null
+        } else {
+            response
+                .error_for_status()
+                .context("LibriVox request failed")?
+                .json()
+                .await
+                .context("Invalid LibriVox response")?
+        };
+
+        let fetch = self.store(parse_feed(&feed, now), now).await?;
+        prefs.set(PREF_RELEASES_FETCHED_AT, &now.to_rfc3339()).await?;
+        Ok(fetch)
+    }
+
+    /// Add fetched releases to the cache, keeping when each was first seen
+    async fn store(&self, releases: Vec<LibrivoxRelease>, now: DateTime<Utc>) -> Result<ReleaseFetch> {
+        let mut fetch = ReleaseFetch::default();
+        for release in releases {
+            let inserted = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO librivox_releases (
+                    id, title, authors, genres, language, description, total_seconds,
+                    url_zip_file, url_librivox, first_seen_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&release.id)
+            .bind(&release.title)
+            .bind(serde_json::to_string(&release.authors)?)
+            .bind(serde_json::to_string(&release.genres)?)
+            .bind(&release.language)
+            .bind(&release.description)
+            .bind(release.total_seconds)
+            .bind(&release.url_zip_file)
+            .bind(&release.url_librivox)
+            .bind(&release.first_seen_at)
+            .execute(self.pool)
+            .await
+            .context("Failed to cache LibriVox release")?
+            .rows_affected();
+            if inserted > 0 {
+                fetch.new_releases.push(release);
+            }
+        }
+
+        let authors = AuthorRepository::new(self.pool);
+        for release in fetch.new_releases.iter().filter(|release| !is_blocked(release)) {
+            for author in &release.authors {
+                if authors.find_by_alias(&normalize_author_name(author)).await?.is_some() {
+                    fetch.by_known_authors.push(release.clone());
+                    break;
+                }
+            }
+        }
+
+        let cutoff = (now - Duration::days(RELEASE_WINDOW_DAYS)).to_rfc3339();
+        fetch.pruned = sqlx::query("DELETE FROM librivox_releases WHERE first_seen_at < ?")
+            .bind(&cutoff)
+            .execute(self.pool)
+            .await
+            .context("Failed to prune LibriVox releases")?
+            .rows_affected();
+        Ok(fetch)
+    }
+}
+
+fn is_blocked(release: &LibrivoxRelease) -> bool {
+    content_filter::active().is_some_and(|filter| {
+        filter.blocks(&release.title, Some(&release.authors.join(", ")), Some(&release.genres.join(", ")), release.description.as_deref())
+    })
+}
+
+/// The releases in a LibriVox API response; entries without an id or title are skipped
+pub fn parse_feed(feed: &serde_json::Value, seen_at: DateTime<Utc>) -> Vec<LibrivoxRelease> {
+    let Some(books) = feed.get("books").and_then(|books| books.as_array()) else {
+        return Vec::new();
+    };
+
+    let text = |value: &serde_json::Value, key: &str| {
+        value
+            .get(key)
+            .and_then(|field| match field {
+                serde_json::Value::String(text) => Some(text.trim().to_string()),
+                serde_json::Value::Number(number) => Some(number.to_string()),
+                _ => None,
+            })
+            .filter(|text| !text.is_empty())
+    };
+
+    books
+        .iter()
+        .filter_map(|book| {
+            let id = text(book, "id")?;
+            let title = text(book, "title")?;
+            let authors = book
+                .get("authors")
+                .and_then(|authors| authors.as_array())
+                .map(|authors| {
+                    authors
+                        .iter()
+                        .map(|author| {
+                            let first = text(author, "first_name").unwrap_or_default();
+                            let last = text(author, "last_name").unwrap_or_default();
+                            format!("{} {}", first, last).trim().to_string()
+                        })
+                        .filter(|name| !name.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let genres = book
+                .get("genres")
+                .and_then(|genres| genres.as_array())
+                .map(|genres| genres.iter().filter_map(|genre| text(genre, "name")).collect())
+                .unwrap_or_default();
+
+            Some(LibrivoxRelease {
+                id,
+                title,
+                authors,
+                genres,
+                language: text(book, "language"),
+                description: text(book, "description"),
+                total_seconds: text(book, "totaltimesecs").and_then(|secs| secs.parse().ok()),
+                url_zip_file: text(book, "url_zip_file"),
+                url_librivox: text(book, "url_librivox"),
+                first_seen_at: seen_at.to_rfc3339(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+    use crate::services::AuthorService;
+
+    fn feed() -> serde_json::Value {
+        serde_json::json!({ "books": [
+            {
+                "id": "20114", "title": "Persuasion (version 4)", "language": "English",
+                "totaltimesecs": 31010, "url_zip_file": "https://archive.org/compress/persuasion_v4_2401_librivox/formats=64KBPS%20MP3&file=/persuasion_v4_2401_librivox.zip",
+                "authors": [{ "id": "3", "first_name": "Jane", "last_name": "Austen" }],
+                "genres": [{ "id": "28", "name": "Romance" }]
+            },
+            {
+                "id": "20115", "title": "Aus meinem Leben", "language": "German", "totaltimesecs": "7200",
+                "authors": [{ "first_name": "", "last_name": "Anonymous" }], "genres": []
+            },
+            { "id": "", "title": "Broken entry" }
+        ]})
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let releases = parse_feed(&feed(), Utc::now());
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].authors, vec!["Jane Austen"]);
+        assert_eq!(releases[0].genres, vec!["Romance"]);
+        assert_eq!(releases[0].total_seconds, Some(31010));
+        assert_eq!(releases[1].authors, vec!["Anonymous"]);
+        assert_eq!(releases[1].total_seconds, Some(7200));
+        assert!(parse_feed(&serde_json::Value::Null, Utc::now()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_filters_and_flags_known_authors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("releases.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        AuthorService::new(pool).resolve("Austen, Jane").await.unwrap();
+
+        let service = LibrivoxReleaseService::new(pool);
+        let now = Utc::now();
+        let fetch = service.store(parse_feed(&feed(), now), now).await.unwrap();
+        assert_eq!(fetch.new_releases.len(), 2);
+        assert_eq!(fetch.by_known_authors.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec!["20114"]);
+
+        // Seen again later: nothing new, and the first sighting is kept
+        let later = now + Duration::hours(13);
+        assert!(service.store(parse_feed(&feed(), later), later).await.unwrap().new_releases.is_empty());
+        assert_eq!(service.releases(None, None).await.unwrap()[0].first_seen_at, now.to_rfc3339());
+
+        assert_eq!(service.releases(Some("romance"), None).await.unwrap().len(), 1);
+        assert_eq!(service.releases(None, Some("GERMAN")).await.unwrap()[0].id, "20115");
+        assert!(service.releases(Some("Poetry"), None).await.unwrap().is_empty());
+
+        let much_later = now + Duration::days(RELEASE_WINDOW_DAYS + 1);
+        assert_eq!(service.store(Vec::new(), much_later).await.unwrap().pruned, 2);
+        assert!(service.is_stale(now).await.unwrap());
+    }
+}
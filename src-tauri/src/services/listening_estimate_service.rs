@@ -0,0 +1,157 @@
+// Remaining listening time and finish-date estimates for library responses
+
+use crate::database::models::{Audiobook, AudiobookWithEstimate, PlaybackProgress};
+use crate::database::repository::PlaybackProgressRepository;
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// The daily listening average is taken over this many days
+pub const PACE_WINDOW_DAYS: i64 = 14;
+
+pub struct ListeningEstimateService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> ListeningEstimateService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Seconds listened per day over the recent window, idle days included
+    pub async fn daily_average_seconds(&self) -> Result<f64> {
+        let total: i64 = sqlx::query_scalar(
+            &format!(
+                "SELECT COALESCE(SUM(session_duration), 0) FROM listening_history WHERE listened_at >= datetime('now', '-{} days')",
+                PACE_WINDOW_DAYS
+            ),
+        )
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to compute daily listening average")?;
+
+        Ok(total as f64 / PACE_WINDOW_DAYS as f64)
+    }
+
+    pub async fn estimate(&self, audiobook: Audiobook) -> Result<AudiobookWithEstimate> {
+        let progress = PlaybackProgressRepository::new(self.pool).find_by_audiobook_id(&audiobook.id).await?;
+        let daily_average = self.daily_average_seconds().await?;
+        Ok(estimate(audiobook, progress.as_ref(), daily_average, Utc::now().date_naive()))
+    }
+
+    /// Progress is loaded once for the whole list rather than per book
+    pub async fn estimate_all(&self, audiobooks: Vec<Audiobook>) -> Result<Vec<AudiobookWithEstimate>> {
+        if audiobooks.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let progress: HashMap<String, PlaybackProgress> = PlaybackProgressRepository::new(self.pool)
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|progress| (progress.audiobook_id.clone(), progress))
+            .collect();
+        let daily_average = self.daily_average_seconds().await?;
+        let today = Utc::now().date_naive();
+
+        Ok(audiobooks
+            .into_iter()
+            .map(|audiobook| {
+                let book_progress = progress.get(&audiobook.id);
+                estimate(audiobook, book_progress, daily_average, today)
+            })
+            .collect())
+    }
+}
+
+fn estimate(
+    audiobook: Audiobook,
+    progress: Option<&PlaybackProgress>,
+    daily_average_seconds: f64,
+    today: NaiveDate,
+) -> AudiobookWithEstimate {
+    let playback_speed = progress
+        .map(|p| p.playback_speed)
+        .filter(|speed| *speed > 0.0)
+        .unwrap_or(1.0);
+    let completed = progress.is_some_and(|p| p.is_completed);
+
+    let duration = audiobook.duration.or_else(|| progress.and_then(|p| p.duration));
+    let remaining_seconds = duration.map(|duration| {
+        if completed {
+            0
+        } else {
+            (duration - progress.map_or(0, |p| p.position)).max(0)
+        }
+    });
+    let remaining_listening_seconds = remaining_seconds.map(|seconds| (seconds as f64 / playback_speed).round() as i64);
+
+    // No recent listening means no meaningful pace to project from
+    let estimated_finish_date = remaining_listening_seconds
+        .filter(|seconds| *seconds > 0 && daily_average_seconds > 0.0)
+        .map(|seconds| {
+            let days = (seconds as f64 / daily_average_seconds).ceil() as i64;
+            (today + Duration::days(days)).format("%Y-%m-%d").to_string()
+        });
+
+    AudiobookWithEstimate {
+        audiobook,
+        playback_speed,
+        remaining_seconds,
+        remaining_listening_seconds,
+        estimated_finish_date,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(duration: Option<i64>) -> Audiobook {
+        let mut audiobook = Audiobook::new("Book".to_string(), "/books/book".to_string());
+        audiobook.duration = duration;
+        audiobook
+    }
+
+    fn progress(audiobook: &Audiobook, position: i64, speed: f64) -> PlaybackProgress {
+        let mut progress = PlaybackProgress::new(audiobook.id.clone());
+        progress.position = position;
+        progress.playback_speed = speed;
+        progress
+    }
+
+    #[test]
+    fn test_estimate_uses_book_speed_and_daily_pace() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let audiobook = book(Some(36_000));
+        let progress = progress(&audiobook, 7_200, 1.6);
+
+        // 28_800s left, 18_000s at 1.6x, one hour a day: five days
+        let result = estimate(audiobook, Some(&progress), 3_600.0, today);
+        assert_eq!(result.remaining_seconds, Some(28_800));
+        assert_eq!(result.remaining_listening_seconds, Some(18_000));
+        assert_eq!(result.estimated_finish_date.as_deref(), Some("2024-03-06"));
+    }
+
+    #[test]
+    fn test_estimate_without_pace_progress_or_duration() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        let result = estimate(book(Some(600)), None, 0.0, today);
+        assert_eq!(result.playback_speed, 1.0);
+        assert_eq!(result.remaining_listening_seconds, Some(600));
+        assert!(result.estimated_finish_date.is_none());
+
+        let result = estimate(book(None), None, 3_600.0, today);
+        assert!(result.remaining_seconds.is_none());
+        assert!(result.estimated_finish_date.is_none());
+
+        let audiobook = book(Some(600));
+        let mut finished = progress(&audiobook, 100, 1.0);
+        finished.is_completed = true;
+        let result = estimate(audiobook, Some(&finished), 3_600.0, today);
+        assert_eq!(result.remaining_seconds, Some(0));
+        assert!(result.estimated_finish_date.is_none());
+    }
+}
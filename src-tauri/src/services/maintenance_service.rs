@@ -0,0 +1,451 @@
+// Periodic housekeeping run by the background scheduler: which jobs are
+// enabled and how often, when each last ran and how it went, and the
+// database-side jobs themselves.
+
+use crate::audio::{bitrate_kbps, extract_audio_metadata, AudioInfo};
+use crate::database::repository::PreferencesRepository;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub const PREF_MAINTENANCE: &str = "maintenance.tasks";
+const PREF_LAST_RUN_PREFIX: &str = "maintenance.last_run.";
+/// How often the scheduler checks whether a task is due
+pub const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// Files probed per duration backfill run, so one run stays short
+const DURATION_BACKFILL_BATCH: i64 = 200;
+pub const BACKUPS_KEPT: usize = 7;
+const BACKUP_PREFIX: &str = "audiovibe-";
+/// Tables whose rows belong to an audiobook. Older databases were written
+/// without foreign keys enforced, so deleting a book could leave these behind.
+const AUDIOBOOK_TABLES: [&str; 10] = [
+    "chapters",
+    "playback_progress",
+    "playback_states",
+    "collection_audiobooks",
+    "listening_history",
+    "listening_daily_summaries",
+    "recommendations",
+    "file_fingerprints",
+    "plays",
+    "chapter_markers",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    CacheEviction,
+    DurationBackfill,
+    OrphanCleanup,
+    FeedRefresh,
+    Backup,
+    Retention,
+    LibrivoxReleases,
+    WeeklyDigest,
+}
+
+impl MaintenanceTask {
+    pub const ALL: [MaintenanceTask; 8] = [
+        MaintenanceTask::CacheEviction,
+        MaintenanceTask::DurationBackfill,
+        MaintenanceTask::OrphanCleanup,
+        MaintenanceTask::FeedRefresh,
+        MaintenanceTask::Backup,
+        MaintenanceTask::Retention,
+        MaintenanceTask::LibrivoxReleases,
+        MaintenanceTask::WeeklyDigest,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            MaintenanceTask::CacheEviction => "cache_eviction",
+            MaintenanceTask::DurationBackfill => "duration_backfill",
+            MaintenanceTask::OrphanCleanup => "orphan_cleanup",
+            MaintenanceTask::FeedRefresh => "feed_refresh",
+            MaintenanceTask::Backup => "backup",
+            MaintenanceTask::Retention => "retention",
+            MaintenanceTask::LibrivoxReleases => "librivox_releases",
+            MaintenanceTask::WeeklyDigest => "weekly_digest",
+        }
+    }
+
+    fn default_interval_hours(self) -> u32 {
+        match self {
+            MaintenanceTask::DurationBackfill => 6,
+            MaintenanceTask::FeedRefresh | MaintenanceTask::LibrivoxReleases => 12,
+            MaintenanceTask::OrphanCleanup | MaintenanceTask::Backup | MaintenanceTask::Retention => 24,
+            MaintenanceTask::CacheEviction | MaintenanceTask::WeeklyDigest => 24 * 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskConfig {
+    pub task: MaintenanceTask,
+    pub enabled: bool,
+    pub interval_hours: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MaintenanceConfig {
+    pub tasks: Vec<TaskConfig>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            tasks: MaintenanceTask::ALL
+                .iter()
+                .map(|&task| TaskConfig { task, enabled: true, interval_hours: task.default_interval_hours() })
+                .collect(),
+        }
+    }
+}
+
+impl MaintenanceConfig {
+    /// The task's settings, or its defaults when the stored config predates it
+    pub fn task(&self, task: MaintenanceTask) -> TaskConfig {
+        self.tasks
+            .iter()
+            .find(|config| config.task == task)
+            .cloned()
+            .unwrap_or(TaskConfig { task, enabled: true, interval_hours: task.default_interval_hours() })
+    }
+}
+
+/// Outcome of a task's most recent run
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskRun {
+    pub started_at: String,
+    #[ts(type = "number")]
+    pub duration_ms: u64,
+    pub success: bool,
+    /// What the run did, or why it failed
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TaskStatus {
+    pub task: MaintenanceTask,
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub last_run: Option<TaskRun>,
+    pub next_run_at: Option<String>,
+}
+
+pub struct MaintenanceService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> MaintenanceService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn load_config(&self) -> MaintenanceConfig {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_MAINTENANCE).await.ok().flatten();
+        match stored.map(|json| serde_json::from_str::<MaintenanceConfig>(&json)) {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                log::warn!("Ignoring invalid maintenance configuration: {}", e);
+                MaintenanceConfig::default()
+            }
+            None => MaintenanceConfig::default(),
+        }
+    }
+
+    pub async fn save_config(&self, config: &MaintenanceConfig) -> Result<()> {
+        if config.tasks.iter().any(|task| task.interval_hours == 0) {
+            return Err(anyhow::anyhow!("Maintenance intervals must be at least one hour"));
+        }
+        let json = serde_json::to_string(config).context("Failed to serialize maintenance configuration")?;
+        PreferencesRepository::new(self.pool).set(PREF_MAINTENANCE, &json).await
+    }
+
+    pub async fn last_run(&self, task: MaintenanceTask) -> Option<TaskRun> {
+        let key = format!("{}{}", PREF_LAST_RUN_PREFIX, task.key());
+        let json = PreferencesRepository::new(self.pool).get(&key).await.ok().flatten()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    pub async fn record_run(&self, task: MaintenanceTask, run: &TaskRun) -> Result<()> {
+        let key = format!("{}{}", PREF_LAST_RUN_PREFIX, task.key());
+        let json = serde_json::to_string(run).context("Failed to serialize maintenance run")?;
+        PreferencesRepository::new(self.pool).set(&key, &json).await
+    }
+
+    /// Enabled tasks whose interval has passed since they last started
+    pub async fn due_tasks(&self, now: DateTime<Utc>) -> Vec<MaintenanceTask> {
+        let config = self.load_config().await;
+        let mut due = Vec::new();
+        for task in MaintenanceTask::ALL {
+            let settings = config.task(task);
+            let last_run = self.last_run(task).await;
+            if settings.enabled && next_run(last_run.as_ref(), settings.interval_hours).is_none_or(|next| next <= now) {
+                due.push(task);
+            }
+        }
+        due
+    }
+
+    pub async fn status(&self) -> Vec<TaskStatus> {
+        let config = self.load_config().await;
+        let mut statuses = Vec::new();
+        for task in MaintenanceTask::ALL {
+            let settings = config.task(task);
+            let last_run = self.last_run(task).await;
+            let next_run_at = settings
+                .enabled
+                .then(|| next_run(last_run.as_ref(), settings.interval_hours))
+                .flatten()
+                .map(|next| next.to_rfc3339());
+            statuses.push(TaskStatus {
+                task,
+                enabled: settings.enabled,
+                interval_hours: settings.interval_hours,
+                last_run,
+                next_run_at,
+            });
+        }
+        statuses
+    }
+
+    /// Fill in missing chapter and book durations from the audio files, along
+    /// with each chapter's bitrate and codec
+    pub async fn backfill_durations(&self) -> Result<String> {
+        let chapters = sqlx::query_as::<_, (String, String, Option<i64>)>(
+            "SELECT id, file_path, duration FROM chapters WHERE duration IS NULL OR codec IS NULL LIMIT ?"
+        )
+        .bind(DURATION_BACKFILL_BATCH)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to find chapters without a duration or codec")?;
+
+        let mut probed = 0;
+        for (chapter_id, file_path, known_duration) in chapters {
+            let Some(info) = read_audio_info(file_path).await else {
+                continue;
+            };
+            let duration = info.duration.map(|duration| duration as i64).or(known_duration);
+            let bitrate = info.bitrate.or_else(|| bitrate_kbps(info.file_size, duration? as f64));
+            // A codec symphonia cannot name is still recorded, so the chapter is not probed again
+            let codec = info.codec.unwrap_or_else(|| "unknown".to_string());
+            sqlx::query(
+                r#"
+                UPDATE chapters
+                SET duration = ?, bitrate = ?, codec = ?, file_size = COALESCE(file_size, ?), updated_at = ?
+                WHERE id = ?
+                "#
+            )
+            .bind(duration)
+            .bind(bitrate.map(|kbps| kbps as i64))
+            .bind(&codec)
+            .bind(info.file_size as i64)
+            .bind(Utc::now().to_rfc3339())
+            .bind(&chapter_id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update chapter duration")?;
+            probed += 1;
+        }
+
+        // Books without chapters are a single file
+        let books = sqlx::query_as::<_, (String, String)>(
+            r#"
+            SELECT id, file_path FROM audiobooks
+            WHERE duration IS NULL
+              AND NOT EXISTS (SELECT 1 FROM chapters c WHERE c.audiobook_id = audiobooks.id)
+            LIMIT ?
+            "#
+        )
+        .bind(DURATION_BACKFILL_BATCH)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to find audiobooks without a duration")?;
+
+        for (audiobook_id, file_path) in books {
+            let Some(duration) = read_audio_info(file_path).await.and_then(|info| info.duration).map(|duration| duration as i64) else {
+                continue;
+            };
+            sqlx::query("UPDATE audiobooks SET duration = ?, updated_at = ? WHERE id = ?")
+                .bind(duration)
+                .bind(Utc::now().to_rfc3339())
+                .bind(&audiobook_id)
+                .execute(self.pool)
+                .await
+                .context("Failed to update audiobook duration")?;
+            probed += 1;
+        }
+
+        // Book totals follow the chapter durations through the chapter triggers
+        Ok(format!("Read {} audio files", probed))
+    }
+
+    /// Delete rows and cover files left behind by deleted audiobooks
+    pub async fn remove_orphans(&self, covers_dir: &Path) -> Result<String> {
+        let mut rows = 0;
+        for table in AUDIOBOOK_TABLES {
+            rows += sqlx::query(&format!(
+                "DELETE FROM {} WHERE audiobook_id NOT IN (SELECT id FROM audiobooks)",
+                table
+            ))
+            .execute(self.pool)
+            .await
+            .with_context(|| format!("Failed to remove orphaned {}", table))?
+            .rows_affected();
+        }
+
+        let ids: std::collections::HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM audiobooks UNION SELECT id FROM collections"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to list audiobook and collection ids")?
+        .into_iter()
+        .collect();
+
+        let mut files = 0;
+        for path in orphaned_covers(covers_dir, &ids) {
+            match std::fs::remove_file(&path) {
+                Ok(()) => files += 1,
+                Err(e) => log::warn!("Failed to remove orphaned cover {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(format!("Removed {} orphaned rows and {} cover files", rows, files))
+    }
+
+    /// Copy the database into `backup_dir` and delete all but the newest backups
+    pub async fn backup_database(&self, backup_dir: &Path, now: DateTime<Utc>) -> Result<String> {
+        std::fs::create_dir_all(backup_dir).context("Failed to create backup directory")?;
+        let path = backup_dir.join(format!("{}{}.db", BACKUP_PREFIX, now.format("%Y%m%d-%H%M%S")));
+        // VACUUM INTO writes a consistent, compacted copy without pausing other connections
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(self.pool)
+            .await
+            .context("Failed to back up database")?;
+
+        let mut removed = 0;
+        for old in stale_backups(backup_dir, BACKUPS_KEPT)? {
+            if std::fs::remove_file(&old).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(format!("Backed up to {} ({} old backups removed)", path.display(), removed))
+    }
+}
+
+async fn read_audio_info(file_path: String) -> Option<AudioInfo> {
+    if !Path::new(&file_path).is_file() {
+        return None;
+    }
+    tokio::task::spawn_blocking(move || extract_audio_metadata(&file_path)).await.ok()?.ok()
+}
+
+/// When a task is next due; None when it has never run
+fn next_run(last_run: Option<&TaskRun>, interval_hours: u32) -> Option<DateTime<Utc>> {
+    let started = DateTime::parse_from_rfc3339(&last_run?.started_at).ok()?;
+    Some(started.with_timezone(&Utc) + Duration::hours(interval_hours as i64))
+}
+
+/// Cover files named after a book or collection that no longer exists. Only
+/// names built from an id are considered, so other files are left alone.
+fn orphaned_covers(covers_dir: &Path, ids: &std::collections::HashSet<String>) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(covers_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                return false;
+            };
+            let id = stem.strip_prefix("collection_").unwrap_or(stem);
+            uuid::Uuid::parse_str(id).is_ok() && !ids.contains(id)
+        })
+        .collect()
+}
+
+/// Backups beyond the newest `keep`; names sort by their timestamp
+fn stale_backups(backup_dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(backup_dir)
+        .context("Failed to read backup directory")?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(".db"))
+        })
+        .collect();
+    backups.sort();
+    backups.reverse();
+    Ok(backups.into_iter().skip(keep).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_run_and_config_defaults() {
+        let run = TaskRun {
+            started_at: "2025-03-01T10:00:00+00:00".to_string(),
+            duration_ms: 5,
+            success: true,
+            message: String::new(),
+        };
+        assert_eq!(next_run(Some(&run), 6).unwrap().to_rfc3339(), "2025-03-01T16:00:00+00:00");
+        assert_eq!(next_run(None, 6), None);
+
+        // A stored config missing a task falls back to that task's defaults
+        let config = MaintenanceConfig {
+            tasks: vec![TaskConfig { task: MaintenanceTask::Backup, enabled: false, interval_hours: 48 }],
+        };
+        assert!(!config.task(MaintenanceTask::Backup).enabled);
+        let retention = config.task(MaintenanceTask::Retention);
+        assert!(retention.enabled);
+        assert_eq!(retention.interval_hours, 24);
+    }
+
+    #[test]
+    fn test_orphaned_covers_and_stale_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = uuid::Uuid::new_v4().to_string();
+        let gone = uuid::Uuid::new_v4().to_string();
+        for name in [format!("{}.jpg", kept), format!("{}.png", gone), format!("collection_{}.svg", gone), "notes.txt".to_string()] {
+            std::fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        let ids = std::iter::once(kept).collect();
+        let mut orphans: Vec<String> = orphaned_covers(dir.path(), &ids)
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        orphans.sort();
+        let mut expected = vec![format!("{}.png", gone), format!("collection_{}.svg", gone)];
+        expected.sort();
+        assert_eq!(orphans, expected);
+
+        let backups = tempfile::tempdir().unwrap();
+        for day in 1..=4 {
+            std::fs::write(backups.path().join(format!("audiovibe-2025030{}-120000.db", day)), b"").unwrap();
+        }
+        std::fs::write(backups.path().join("other.db"), b"").unwrap();
+        let stale = stale_backups(backups.path(), 2).unwrap();
+        assert_eq!(
+            stale.iter().map(|path| path.file_name().unwrap().to_string_lossy().to_string()).collect::<Vec<_>>(),
+            vec!["audiovibe-20250302-120000.db", "audiovibe-20250301-120000.db"]
+        );
+    }
+}
@@ -1,10 +1,107 @@
 // Services module for AudioVibe
 // This module will handle external services like AI conversion, cloud sync, etc.
 
+pub mod activity_service;
+pub mod audiobook_source_service;
+pub mod author_service;
+pub mod book_position_service;
+pub mod chapter_error_service;
+pub mod chapter_marker_service;
+pub mod chapter_order_service;
+pub mod chapter_text_service;
+pub mod collection_queue_service;
+pub mod collection_share_service;
+pub mod compilation_service;
+pub mod cover_resolution_service;
+pub mod cover_service;
+pub mod digest_service;
+pub mod document_service;
+pub mod end_of_book_service;
+pub mod file_validation_service;
+pub mod folder_sync_service;
+pub mod follow_service;
+pub mod frame_index_service;
+pub mod home_feed_service;
+pub mod import_preview_service;
+pub mod import_repair_service;
+pub mod library_export_service;
+pub mod library_root_service;
+pub mod librivox_author_service;
+pub mod librivox_discovery_service;
+pub mod librivox_release_service;
+pub mod listening_estimate_service;
+pub mod maintenance_service;
+pub mod narrator_service;
+pub mod note_service;
+pub mod play_history_service;
+pub mod player_state_service;
+pub mod privacy;
+pub mod random_pick_service;
 pub mod recommendation_service;
+pub mod relocation_service;
+pub mod remote_access_service;
+pub mod retention_service;
+pub mod saved_search_service;
+pub mod series_service;
+pub mod sort_preference_service;
+pub mod speed_preset_service;
+pub mod suggestion_service;
+pub mod tts_chapter_service;
+pub mod tts_timing_service;
+pub mod version_service;
+pub mod voice_boost_service;
+pub mod volume_service;
 
 use serde::{Deserialize, Serialize};
-pub use recommendation_service::RecommendationService;
+pub use activity_service::{ActivityDay, ActivityService};
+pub use audiobook_source_service::AudiobookSourceService;
+pub use author_service::AuthorService;
+pub use book_position_service::{BookLayout, BookPositionService, ChapterPosition};
+pub use chapter_error_service::{ChapterError, ChapterErrorEvent, ChapterErrorService};
+pub use chapter_marker_service::ChapterMarkerService;
+pub use chapter_order_service::ChapterOrderService;
+pub use chapter_text_service::ChapterTextService;
+pub use collection_queue_service::CollectionQueueService;
+pub use collection_share_service::{CollectionImportReport, CollectionShareService, SharedCollection, SharedCollectionBook};
+pub use compilation_service::{CompilationPlan, CompilationWork};
+pub use cover_resolution_service::{CoverResolutionService, CoverResult};
+pub use cover_service::CoverService;
+pub use digest_service::{DigestService, WeeklyDigest};
+pub use document_service::DocumentService;
+pub use end_of_book_service::{BookFinished, EndOfBookAction, EndOfBookService};
+pub use file_validation_service::{AudiobookValidation, FileValidationService};
+pub use folder_sync_service::{FolderSyncReport, FolderSyncService};
+pub use follow_service::{Follow, FollowKind, FollowService, ReleaseAlert};
+pub use frame_index_service::FrameIndexService;
+pub use home_feed_service::{HomeFeedConfig, HomeFeedService, HomeShelf};
+pub use import_preview_service::{ImportOverrides, ImportPreview, ImportPreviewCover, ImportPreviewSource};
+pub use import_repair_service::ImportRepairService;
+pub use library_export_service::LibraryExportService;
+pub use library_root_service::LibraryRootService;
+pub use librivox_author_service::{LibrivoxAuthorPage, LibrivoxAuthorService};
+pub use librivox_discovery_service::{LibrivoxDiscoveryService, LibrivoxSuggestion};
+pub use librivox_release_service::{LibrivoxRelease, LibrivoxReleaseService};
+pub use listening_estimate_service::ListeningEstimateService;
+pub use maintenance_service::{MaintenanceConfig, MaintenanceService, MaintenanceTask, TaskRun, TaskStatus};
+pub use narrator_service::NarratorService;
+pub use note_service::{AudiobookNote, NoteService};
+pub use play_history_service::{DeletedHistory, HistoryRange, PlayHistoryService, PlaySessionTracker, PlaybackEvent};
+pub use player_state_service::{PlayerState, PlayerStateService};
+pub use random_pick_service::RandomPickService;
+pub use recommendation_service::{RecommendationExplanation, RecommendationService, RecommendationWeights, TasteProfile};
+pub use relocation_service::{RelocationReport, RelocationService};
+pub use remote_access_service::{IssuedRemoteToken, RemoteAccessService, RemoteAuditEntry, RemoteScope, RemoteToken};
+pub use retention_service::{RetentionReport, RetentionService, RetentionSettings};
+pub use saved_search_service::{SavedSearch, SavedSearchService, SearchHistoryEntry};
+pub use series_service::{SeriesEntry, SeriesService};
+pub use sort_preference_service::SortPreferenceService;
+pub use speed_preset_service::{SpeedDirection, SpeedPresetService, SpeedPresets};
+pub use suggestion_service::{Suggestion, SuggestionService};
+pub use tts_chapter_service::TtsChapterService;
+pub use tts_timing_service::TtsTimingService;
+pub use version_service::{MergedVersions, VersionComparison, VersionService};
+pub use voice_boost_service::{VoiceBoost, VoiceBoostService};
+pub use volume_service::{OfflineVolume, VolumeService};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServiceManager {
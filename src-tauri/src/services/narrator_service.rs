@@ -0,0 +1,77 @@
+// Narrator entities: linking free-text narrator names to shared narrator records
+
+use super::author_service::{display_name, normalize_author_name, similarity, sort_name, token_key, FUZZY_MATCH_THRESHOLD, FUZZY_MIN_LENGTH};
+use crate::database::models::{Audiobook, Narrator};
+use crate::database::repository::NarratorRepository;
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+pub struct NarratorService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> NarratorService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Find the narrator a name refers to, creating one when nothing matches.
+    /// Names are matched the same way as author names.
+    pub async fn resolve(&self, name: &str) -> Result<Option<Narrator>> {
+        let name = display_name(name);
+        let normalized = normalize_author_name(&name);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        let repo = NarratorRepository::new(self.pool);
+        if let Some(narrator) = repo.find_by_normalized_name(&normalized).await? {
+            return Ok(Some(narrator));
+        }
+
+        if normalized.len() >= FUZZY_MIN_LENGTH {
+            let key = token_key(&normalized);
+            let best = repo.find_all().await?
+                .into_iter()
+                .map(|narrator| (similarity(&token_key(&narrator.normalized_name), &key), narrator))
+                .filter(|(score, _)| *score >= FUZZY_MATCH_THRESHOLD)
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((_, narrator)) = best {
+                return Ok(Some(narrator));
+            }
+        }
+
+        let narrator = repo.create(&name, &normalized, &sort_name(&name)).await?;
+        println!("🎙️ NARRATOR: Created narrator '{}'", narrator.name);
+        Ok(Some(narrator))
+    }
+
+    pub async fn link_audiobook(&self, audiobook_id: &str, narrator: Option<&str>) -> Result<Option<Narrator>> {
+        let resolved = match narrator {
+            Some(name) => self.resolve(name).await?,
+            None => None,
+        };
+        NarratorRepository::new(self.pool)
+            .link_audiobook(audiobook_id, resolved.as_ref().map(|n| n.id.as_str()))
+            .await?;
+        Ok(resolved)
+    }
+
+    /// Link every audiobook imported before narrators existed. Returns how many were linked.
+    pub async fn backfill(&self) -> Result<usize> {
+        let unlinked = NarratorRepository::new(self.pool).find_unlinked().await?;
+        let mut linked = 0;
+        for (audiobook_id, narrator) in unlinked {
+            match self.link_audiobook(&audiobook_id, Some(&narrator)).await {
+                Ok(Some(_)) => linked += 1,
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to link narrator '{}' for audiobook {}: {}", narrator, audiobook_id, e),
+            }
+        }
+        Ok(linked)
+    }
+
+    pub async fn get_books(&self, narrator_id: &str) -> Result<Vec<Audiobook>> {
+        NarratorRepository::new(self.pool).find_audiobooks(narrator_id).await
+    }
+}
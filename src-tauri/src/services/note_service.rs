@@ -0,0 +1,294 @@
+// Notes the listener keeps on a book. Unlike markers they hold long Markdown
+// text, and a note may be pinned to a position in the book to capture a quote
+// or left unpinned as a note on the whole book. Where the book has a
+// transcript, a quote can also be captured from it. A book's notes, or just
+// its quotes, export as one Markdown document.
+
+use crate::database::repository::{AudiobookRepository, TtsTimingRepository};
+use crate::services::book_position_service::BookPositionService;
+use crate::services::chapter_marker_service::format_timestamp;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+/// Transcript taken from either side of the position a quote is captured at
+pub const QUOTE_WINDOW_SECONDS: f64 = 15.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, sqlx::FromRow)]
+#[ts(export)]
+pub struct AudiobookNote {
+    pub id: String,
+    pub audiobook_id: String,
+    /// Seconds from the start of the book, or None for a note on the whole book
+    #[ts(type = "number | null")]
+    pub position: Option<i64>,
+    /// Markdown
+    pub body: String,
+    /// Captured from the transcript by capture_quote
+    pub is_quote: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+pub struct NoteService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> NoteService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, audiobook_id: &str, position: Option<i64>, body: &str) -> Result<AudiobookNote> {
+        AudiobookRepository::new(self.pool)
+            .find_by_id(audiobook_id)
+            .await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+        let body = clean_body(body)?;
+        anyhow::ensure!(position.is_none_or(|position| position >= 0), "Note position cannot be negative");
+        self.insert(audiobook_id, position, body, false).await
+    }
+
+    /// Save the transcript within QUOTE_WINDOW_SECONDS of `position` as a
+    /// quote. The transcript is the sentence timings recorded for TTS books,
+    /// and only the chapter file playing at `position` is searched.
+    pub async fn capture_quote(&self, audiobook_id: &str, position: i64) -> Result<AudiobookNote> {
+        anyhow::ensure!(position >= 0, "Note position cannot be negative");
+        let target = BookPositionService::new(self.pool).layout(audiobook_id).await?.seek_target(position as u64)?;
+        let offset = target.offset as f64;
+        let sentences = TtsTimingRepository::new(self.pool)
+            .find_in_file(&target.file_path, offset - QUOTE_WINDOW_SECONDS, offset + QUOTE_WINDOW_SECONDS)
+            .await?;
+        let text = sentences.iter().map(|sentence| sentence.text.trim()).filter(|text| !text.is_empty()).collect::<Vec<_>>().join(" ");
+        anyhow::ensure!(!text.is_empty(), "There is no transcript at {} in this book", format_timestamp(position));
+        self.insert(audiobook_id, Some(position), format!("> {}", text), true).await
+    }
+
+    async fn insert(&self, audiobook_id: &str, position: Option<i64>, body: String, is_quote: bool) -> Result<AudiobookNote> {
+        let now = Utc::now().to_rfc3339();
+        let note = AudiobookNote {
+            id: uuid::Uuid::new_v4().to_string(),
+            audiobook_id: audiobook_id.to_string(),
+            position,
+            body,
+            is_quote,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        sqlx::query(
+            "INSERT INTO audiobook_notes (id, audiobook_id, position, body, is_quote, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&note.id)
+        .bind(&note.audiobook_id)
+        .bind(note.position)
+        .bind(&note.body)
+        .bind(note.is_quote)
+        .bind(&note.created_at)
+        .bind(&note.updated_at)
+        .execute(self.pool)
+        .await
+        .context("Failed to save note")?;
+        Ok(note)
+    }
+
+    /// Replace a note's text and position; a None position unpins it
+    pub async fn update(&self, id: &str, position: Option<i64>, body: &str) -> Result<AudiobookNote> {
+        let body = clean_body(body)?;
+        anyhow::ensure!(position.is_none_or(|position| position >= 0), "Note position cannot be negative");
+        let note = self.find(id).await?;
+
+        let updated_at = Utc::now().to_rfc3339();
+        sqlx::query("UPDATE audiobook_notes SET position = ?, body = ?, updated_at = ? WHERE id = ?")
+            .bind(position)
+            .bind(&body)
+            .bind(&updated_at)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update note")?;
+        Ok(AudiobookNote { position, body, updated_at, ..note })
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM audiobook_notes WHERE id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete note")?;
+        anyhow::ensure!(result.rows_affected() > 0, "Note not found: {}", id);
+        Ok(())
+    }
+
+    /// A book's notes: those on the whole book first, oldest first, then the
+    /// pinned ones in book order
+    pub async fn list(&self, audiobook_id: &str) -> Result<Vec<AudiobookNote>> {
+        sqlx::query_as::<_, AudiobookNote>(
+            "SELECT * FROM audiobook_notes WHERE audiobook_id = ? ORDER BY position IS NOT NULL, position, created_at"
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load notes")
+    }
+
+    /// A book's notes, or only its quotes, as one Markdown document headed by
+    /// the book
+    pub async fn export_markdown(&self, audiobook_id: &str, quotes_only: bool) -> Result<String> {
+        let audiobook = AudiobookRepository::new(self.pool)
+            .find_by_id(audiobook_id)
+            .await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+        let mut notes = self.list(audiobook_id).await?;
+        if quotes_only {
+            notes.retain(|note| note.is_quote);
+        }
+        let heading = if quotes_only { "Quotes from" } else { "Notes on" };
+        Ok(to_markdown(&format!("{} {}", heading, audiobook.title), audiobook.author.as_deref(), &notes))
+    }
+
+    async fn find(&self, id: &str) -> Result<AudiobookNote> {
+        sqlx::query_as::<_, AudiobookNote>("SELECT * FROM audiobook_notes WHERE id = ?")
+            .bind(id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to load note")?
+            .with_context(|| format!("Note not found: {}", id))
+    }
+}
+
+fn clean_body(body: &str) -> Result<String> {
+    // Leading spaces can be Markdown (an indented code block), so only blank
+    // lines are trimmed
+    let body = body.trim_matches('\n').trim_end();
+    anyhow::ensure!(!body.trim().is_empty(), "A note cannot be empty");
+    Ok(body.to_string())
+}
+
+/// Each note becomes a section headed by its position in the book, or by the
+/// day it was written for notes on the whole book
+fn to_markdown(title: &str, author: Option<&str>, notes: &[AudiobookNote]) -> String {
+    let mut markdown = format!("# {}\n", title);
+    if let Some(author) = author.filter(|author| !author.trim().is_empty()) {
+        markdown.push_str(&format!("\n*{}*\n", author.trim()));
+    }
+    for note in notes {
+        let heading = match note.position {
+            Some(position) => format_timestamp(position),
+            None => note.created_at.get(..10).unwrap_or(&note.created_at).to_string(),
+        };
+        markdown.push_str(&format!("\n## {}\n\n{}\n", heading, note.body));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{AlignedSegment, Audiobook, CreateAudiobookDto, CreateChapterDto};
+    use crate::database::repository::ChapterRepository;
+    use crate::database::DatabaseManager;
+
+    async fn add_book(pool: &SqlitePool, title: &str, author: Option<&str>) -> Audiobook {
+        AudiobookRepository::new(pool)
+            .create(CreateAudiobookDto {
+                title: title.to_string(),
+                file_path: format!("/books/{}", title),
+                author: author.map(str::to_string),
+                narrator: None,
+                description: None,
+                genre: None,
+                duration: Some(36_000),
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_notes_are_kept_in_book_order_and_exported() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("notes.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let book = add_book(pool, "Emma", Some("Jane Austen")).await;
+
+        let service = NoteService::new(pool);
+        assert!(service.create(&book.id, None, " \n\n").await.is_err());
+        assert!(service.create(&book.id, Some(-1), "Too early").await.is_err());
+        assert!(service.create("missing", None, "Lost").await.is_err());
+
+        let quote = service.create(&book.id, Some(3_725), "> Silly things do cease to be silly\n").await.unwrap();
+        service.create(&book.id, Some(60), "Opening is *brisk*").await.unwrap();
+        let general = service.create(&book.id, None, "\nA comedy of errors.\n\n- Harriet\n- Mr. Knightley").await.unwrap();
+        assert_eq!(general.body, "A comedy of errors.\n\n- Harriet\n- Mr. Knightley");
+
+        let notes = service.list(&book.id).await.unwrap();
+        let positions: Vec<Option<i64>> = notes.iter().map(|note| note.position).collect();
+        assert_eq!(positions, vec![None, Some(60), Some(3_725)]);
+
+        let edited = service.update(&quote.id, Some(3_730), "> Silly things do cease to be silly if they are done by sensible people").await.unwrap();
+        assert_eq!(edited.created_at, quote.created_at);
+        assert_eq!(service.list(&book.id).await.unwrap()[2], edited);
+
+        let markdown = service.export_markdown(&book.id, false).await.unwrap();
+        let day = &general.created_at[..10];
+        assert_eq!(
+            markdown,
+            format!(
+                "# Notes on Emma\n\n*Jane Austen*\n\n## {}\n\nA comedy of errors.\n\n- Harriet\n- Mr. Knightley\n\n## 0:01:00\n\nOpening is *brisk*\n\n## 1:02:10\n\n> Silly things do cease to be silly if they are done by sensible people\n",
+                day
+            )
+        );
+
+        service.delete(&general.id).await.unwrap();
+        assert!(service.delete(&general.id).await.is_err());
+        AudiobookRepository::new(pool).delete(&book.id).await.unwrap();
+        assert!(service.list(&book.id).await.unwrap().is_empty(), "notes go with the book");
+    }
+
+    #[tokio::test]
+    async fn test_quotes_are_captured_from_the_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("quotes.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let book = add_book(pool, "Kim", None).await;
+        let mut chapters = Vec::new();
+        for number in 1..=2 {
+            let chapter = ChapterRepository::new(pool)
+                .create(CreateChapterDto {
+                    audiobook_id: book.id.clone(),
+                    chapter_number: number,
+                    title: format!("Chapter {}", number),
+                    file_path: format!("/books/kim/{}.wav", number),
+                    duration: Some(60),
+                    file_size: None,
+                })
+                .await
+                .unwrap();
+            chapters.push(chapter);
+        }
+        let sentences: Vec<AlignedSegment> = [("He sat.", 0.0), ("In defiance of orders.", 10.0), ("Astride the gun.", 20.0), ("Zam-Zammah.", 40.0)]
+            .into_iter()
+            .map(|(text, start)| AlignedSegment { text: text.to_string(), start, end: start + 10.0 })
+            .collect();
+        TtsTimingRepository::new(pool).replace_for_file(&chapters[1].id, &chapters[1].file_path, &sentences).await.unwrap();
+
+        let service = NoteService::new(pool);
+        // 25 seconds into the second chapter
+        let quote = service.capture_quote(&book.id, 85).await.unwrap();
+        assert_eq!(quote.body, "> In defiance of orders. Astride the gun.");
+        assert_eq!((quote.position, quote.is_quote), (Some(85), true));
+        assert!(service.capture_quote(&book.id, 30).await.is_err(), "no transcript in the first chapter");
+
+        let note = service.create(&book.id, Some(90), "Lahore").await.unwrap();
+        assert!(!note.is_quote);
+        assert_eq!(service.export_markdown(&book.id, true).await.unwrap(), "# Quotes from Kim\n\n## 0:01:25\n\n> In defiance of orders. Astride the gun.\n");
+    }
+}
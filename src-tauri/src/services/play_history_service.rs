@@ -0,0 +1,299 @@
+use crate::database::{models::*, repository::PlayRepository};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+/// Plays shorter than this are skips or accidental taps, not listening
+pub const MIN_PLAY_SECONDS: i64 = 30;
+/// A new play of the same book and chapter this soon after the last one continues it
+pub const MERGE_GAP_SECONDS: i64 = 5 * 60;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Playback transitions reported by the audio thread
+#[derive(Debug, Clone)]
+pub enum PlaybackEvent {
+    Loaded { file_path: String },
+    Started { position: u64 },
+    Paused { position: u64 },
+    Stopped { position: u64 },
+    /// The file played to its end by itself
+    Finished { file_path: String, position: u64 },
+}
+
+/// A continuous stretch of playback of one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletedPlay {
+    pub file_path: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub start_position: u64,
+    pub end_position: u64,
+}
+
+/// Turns the raw event stream into completed plays
+#[derive(Debug, Default)]
+pub struct PlaySessionTracker {
+    current_file: Option<String>,
+    active: Option<(DateTime<Utc>, u64)>,
+}
+
+impl PlaySessionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle(&mut self, event: PlaybackEvent, now: DateTime<Utc>) -> Option<CompletedPlay> {
+        match event {
+            PlaybackEvent::Loaded { file_path } => {
+                // Loading a new file ends whatever was playing; the end position is unknown
+                // at this point, so estimate it from wall-clock time
+                let play = self.active.and_then(|(started_at, start_position)| {
+                    let elapsed = (now - started_at).num_seconds().max(0) as u64;
+                    self.finish(start_position + elapsed, now)
+                });
+                self.current_file = Some(file_path);
+                play
+            }
+            PlaybackEvent::Started { position } => {
+                if self.active.is_none() && self.current_file.is_some() {
+                    self.active = Some((now, position));
+                }
+                None
+            }
+            PlaybackEvent::Paused { position }
+            | PlaybackEvent::Stopped { position }
+            | PlaybackEvent::Finished { position, .. } => {
+                self.finish(position, now)
+            }
+        }
+    }
+
+    fn finish(&mut self, end_position: u64, now: DateTime<Utc>) -> Option<CompletedPlay> {
+        let (started_at, start_position) = self.active.take()?;
+        Some(CompletedPlay {
+            file_path: self.current_file.clone()?,
+            started_at,
+            ended_at: now,
+            start_position,
+            end_position,
+        })
+    }
+}
+
+pub struct PlayHistoryService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PlayHistoryService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a completed play against the book/chapter that owns the file, applying
+    /// the dedupe rules. Returns false when the play was dropped.
+    pub async fn record_play(&self, play: &CompletedPlay) -> Result<bool> {
+        if super::privacy::is_incognito() {
+            return Ok(false);
+        }
+
+        let listened_seconds = (play.ended_at - play.started_at).num_seconds();
+        if listened_seconds < MIN_PLAY_SECONDS {
+            return Ok(false);
+        }
+
+        let Some((audiobook_id, chapter_id)) = self.resolve_file(&play.file_path).await? else {
+            return Ok(false);
+        };
+
+        let repo = PlayRepository::new(self.pool);
+        if let Some(previous) = repo.find_latest(&audiobook_id, chapter_id.as_deref()).await? {
+            let continues_previous = DateTime::parse_from_rfc3339(&previous.ended_at)
+                .map(|ended| play.started_at - ended.with_timezone(&Utc) <= Duration::seconds(MERGE_GAP_SECONDS))
+                .unwrap_or(false);
+
+            if continues_previous {
+                repo.extend(
+                    &previous.id,
+                    &play.ended_at.to_rfc3339(),
+                    play.end_position as i64,
+                    previous.listened_seconds + listened_seconds,
+                ).await?;
+                return Ok(true);
+            }
+        }
+
+        let mut new_play = Play::new(
+            audiobook_id,
+            chapter_id,
+            play.started_at.to_rfc3339(),
+            play.ended_at.to_rfc3339(),
+        );
+        new_play.start_position = play.start_position as i64;
+        new_play.end_position = play.end_position as i64;
+        new_play.listened_seconds = listened_seconds;
+        repo.create(&new_play).await?;
+
+        Ok(true)
+    }
+
+    pub async fn get_play_history(&self, page: i64, page_size: i64) -> Result<PlayHistoryPage> {
+        let page = page.max(0);
+        let page_size = if page_size > 0 { page_size.min(500) } else { DEFAULT_PAGE_SIZE };
+
+        let repo = PlayRepository::new(self.pool);
+        let entries = repo.find_page(page_size, page * page_size).await?;
+        let total = repo.count().await?;
+
+        Ok(PlayHistoryPage {
+            entries,
+            page,
+            page_size,
+            total,
+        })
+    }
+
+    /// Delete listening sessions and plays inside `range`.
+    /// Bounds are RFC 3339 timestamps or YYYY-MM-DD dates; a date `to` includes that whole day.
+    pub async fn delete_history(&self, range: &HistoryRange) -> Result<DeletedHistory> {
+        let from = range.from.as_deref().map(|bound| parse_range_bound(bound, false)).transpose()?;
+        let to = range.to.as_deref().map(|bound| parse_range_bound(bound, true)).transpose()?;
+
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+
+        let listening_sessions = sqlx::query(
+            "DELETE FROM listening_history WHERE (? IS NULL OR listened_at >= ?) AND (? IS NULL OR listened_at < ?)"
+        )
+        .bind(&from).bind(&from)
+        .bind(&to).bind(&to)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete listening history")?
+        .rows_affected();
+
+        let plays = sqlx::query(
+            "DELETE FROM plays WHERE (? IS NULL OR started_at >= ?) AND (? IS NULL OR started_at < ?)"
+        )
+        .bind(&from).bind(&from)
+        .bind(&to).bind(&to)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete plays")?
+        .rows_affected();
+
+        tx.commit().await.context("Failed to commit history deletion")?;
+
+        Ok(DeletedHistory { listening_sessions, plays })
+    }
+
+    /// Chapter files map to their chapter; single-file books map to the audiobook
+    pub async fn resolve_file(&self, file_path: &str) -> Result<Option<(String, Option<String>)>> {
+        let chapter: Option<(String, String)> = sqlx::query_as(
+            "SELECT audiobook_id, id FROM chapters WHERE file_path = ? LIMIT 1"
+        )
+        .bind(file_path)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to look up chapter for file")?;
+
+        if let Some((audiobook_id, chapter_id)) = chapter {
+            return Ok(Some((audiobook_id, Some(chapter_id))));
+        }
+
+        let audiobook_id: Option<String> = sqlx::query_scalar(
+            "SELECT id FROM audiobooks WHERE file_path = ? LIMIT 1"
+        )
+        .bind(file_path)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to look up audiobook for file")?;
+
+        Ok(audiobook_id.map(|id| (id, None)))
+    }
+}
+
+/// Time range for clearing history; `None` leaves that side open
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct HistoryRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DeletedHistory {
+    #[ts(type = "number")]
+    pub listening_sessions: u64,
+    #[ts(type = "number")]
+    pub plays: u64,
+}
+
+/// Normalize a range bound to the RFC 3339 UTC form the history tables store
+fn parse_range_bound(bound: &str, is_end: bool) -> Result<String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(bound) {
+        return Ok(timestamp.with_timezone(&Utc).to_rfc3339());
+    }
+
+    let date = NaiveDate::parse_from_str(bound, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date: {}", bound))?;
+    let date = if is_end { date.succ_opt().unwrap_or(date) } else { date };
+    Ok(date.and_time(NaiveTime::MIN).and_utc().to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range_bound() {
+        assert_eq!(parse_range_bound("2024-03-01", false).unwrap(), "2024-03-01T00:00:00+00:00");
+        assert_eq!(parse_range_bound("2024-03-01", true).unwrap(), "2024-03-02T00:00:00+00:00");
+        assert_eq!(parse_range_bound("2024-03-01T12:00:00+02:00", false).unwrap(), "2024-03-01T10:00:00+00:00");
+        assert!(parse_range_bound("yesterday", false).is_err());
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_play_spans_start_to_pause() {
+        let mut tracker = PlaySessionTracker::new();
+        assert!(tracker.handle(PlaybackEvent::Loaded { file_path: "a.mp3".into() }, at(0)).is_none());
+        assert!(tracker.handle(PlaybackEvent::Started { position: 10 }, at(5)).is_none());
+
+        let play = tracker.handle(PlaybackEvent::Paused { position: 130 }, at(125)).unwrap();
+        assert_eq!(play.file_path, "a.mp3");
+        assert_eq!(play.started_at, at(5));
+        assert_eq!(play.ended_at, at(125));
+        assert_eq!((play.start_position, play.end_position), (10, 130));
+
+        // A second pause without playing in between is not another play
+        assert!(tracker.handle(PlaybackEvent::Paused { position: 130 }, at(130)).is_none());
+    }
+
+    #[test]
+    fn test_loading_next_file_closes_current_play() {
+        let mut tracker = PlaySessionTracker::new();
+        tracker.handle(PlaybackEvent::Loaded { file_path: "a.mp3".into() }, at(0));
+        tracker.handle(PlaybackEvent::Started { position: 0 }, at(0));
+
+        let play = tracker.handle(PlaybackEvent::Loaded { file_path: "b.mp3".into() }, at(60)).unwrap();
+        assert_eq!(play.file_path, "a.mp3");
+        assert_eq!(play.end_position, 60);
+
+        tracker.handle(PlaybackEvent::Started { position: 0 }, at(61));
+        let play = tracker.handle(PlaybackEvent::Stopped { position: 20 }, at(81)).unwrap();
+        assert_eq!(play.file_path, "b.mp3");
+    }
+
+    #[test]
+    fn test_start_without_loaded_file_is_ignored() {
+        let mut tracker = PlaySessionTracker::new();
+        tracker.handle(PlaybackEvent::Started { position: 0 }, at(0));
+        assert!(tracker.handle(PlaybackEvent::Paused { position: 50 }, at(50)).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+// The whole player as it was: loaded track and position, what is queued behind
+// it, speed, volume and ambience. Snapshotted while the app runs and on exit,
+// and loaded back into the audio thread on the next start.
+
+use crate::audio::Track;
+use crate::database::repository::PreferencesRepository;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::time::Duration;
+use ts_rs::TS;
+
+pub const PREF_PLAYER_STATE: &str = "player.state";
+pub const PREF_RESTORE_PLAYER: &str = "player.restore_on_startup";
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlayerState {
+    pub current: Track,
+    /// Into the current track, in seconds
+    #[ts(type = "number")]
+    pub position: u64,
+    pub queue: Vec<Track>,
+    pub speed: f32,
+    pub volume: f32,
+    pub ambience: Option<String>,
+    pub ambience_volume: f32,
+    pub audiobook_id: Option<String>,
+    pub chapter_id: Option<String>,
+    pub saved_at: String,
+}
+
+impl PlayerState {
+    /// Same place and settings, whenever it was taken
+    pub fn same_as(&self, other: &PlayerState) -> bool {
+        PlayerState { saved_at: String::new(), ..self.clone() } == PlayerState { saved_at: String::new(), ..other.clone() }
+    }
+
+    /// The tracks to load, current first, leaving out files that have gone
+    pub fn tracks(&self) -> Vec<Track> {
+        std::iter::once(&self.current)
+            .chain(&self.queue)
+            .filter(|track| Path::new(&track.file_path).is_file())
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct PlayerStateService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> PlayerStateService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn save(&self, state: &PlayerState) -> Result<()> {
+        PreferencesRepository::new(self.pool).set(PREF_PLAYER_STATE, &serde_json::to_string(state)?).await
+    }
+
+    /// The last snapshot; one that no longer parses counts as none
+    pub async fn load(&self) -> Result<Option<PlayerState>> {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_PLAYER_STATE).await?;
+        Ok(stored.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+
+    #[allow(dead_code)]
+    pub async fn clear(&self) -> Result<()> {
+        PreferencesRepository::new(self.pool).delete(PREF_PLAYER_STATE).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_player_state_round_trips_and_drops_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("player.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let service = PlayerStateService::new(db.get_pool().unwrap());
+        assert!(service.load().await.unwrap().is_none());
+
+        let track = |name: &str| {
+            let path = dir.path().join(name);
+            Track { id: name.to_string(), file_path: path.to_string_lossy().to_string(), title: None, duration: None, audiobook_id: Some("book-1".to_string()) }
+        };
+        for name in ["02.mp3", "04.mp3"] {
+            std::fs::write(dir.path().join(name), b"audio").unwrap();
+        }
+        let state = PlayerState {
+            current: track("02.mp3"),
+            position: 754,
+            queue: vec![track("03.mp3"), track("04.mp3")],
+            speed: 1.25,
+            volume: 0.8,
+            ambience: Some("rain".to_string()),
+            ambience_volume: 0.3,
+            audiobook_id: Some("book-1".to_string()),
+            chapter_id: None,
+            saved_at: "2024-01-01T00:00:00Z".to_string(),
+        };
+        service.save(&state).await.unwrap();
+        let loaded = service.load().await.unwrap().unwrap();
+        assert_eq!(loaded, state);
+        assert!(loaded.same_as(&PlayerState { saved_at: "later".to_string(), ..state.clone() }));
+        assert!(!loaded.same_as(&PlayerState { position: 760, ..state.clone() }));
+
+        let ids: Vec<String> = loaded.tracks().into_iter().map(|track| track.id).collect();
+        assert_eq!(ids, vec!["02.mp3", "04.mp3"], "03.mp3 was deleted");
+
+        service.clear().await.unwrap();
+        assert!(service.load().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,17 @@
+// Private listening: while incognito is on, nothing about what is played is
+// recorded except the resume position
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const PREF_INCOGNITO: &str = "privacy.incognito";
+
+static INCOGNITO: AtomicBool = AtomicBool::new(false);
+
+pub fn is_incognito() -> bool {
+    INCOGNITO.load(Ordering::Relaxed)
+}
+
+pub fn set_incognito(enabled: bool) {
+    INCOGNITO.store(enabled, Ordering::Relaxed);
+    log::info!("Incognito listening {}", if enabled { "enabled" } else { "disabled" });
+}
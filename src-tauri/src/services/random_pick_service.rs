@@ -0,0 +1,86 @@
+// "Surprise me": a random book from the library, weighted towards titles that
+// have been left alone the longest
+
+use crate::database::models::{Audiobook, RandomPickCandidate, RandomPickFilters};
+use crate::database::repository::AudiobookRepository;
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// Neglect stops adding weight after this many days
+const MAX_NEGLECT_DAYS: f64 = 365.0;
+/// Every this many idle days count as one more draw
+const NEGLECT_DAYS_PER_DRAW: f64 = 30.0;
+
+pub struct RandomPickService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> RandomPickService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// None when nothing in the library satisfies the filters
+    pub async fn pick(&self, filters: &RandomPickFilters) -> Result<Option<Audiobook>> {
+        let candidates = AudiobookRepository::new(self.pool).find_random_candidates(filters).await?;
+        Ok(pick_weighted(candidates, random_fraction()))
+    }
+}
+
+/// A book idle for a year is thirteen times as likely as one played today
+fn neglect_weight(idle_days: f64) -> f64 {
+    1.0 + idle_days.clamp(0.0, MAX_NEGLECT_DAYS) / NEGLECT_DAYS_PER_DRAW
+}
+
+/// Roulette-wheel selection; `fraction` is a uniform draw in 0.0..1.0
+fn pick_weighted(candidates: Vec<RandomPickCandidate>, fraction: f64) -> Option<Audiobook> {
+    let total: f64 = candidates.iter().map(|c| neglect_weight(c.idle_days)).sum();
+    let mut remaining = fraction * total;
+    let last = candidates.len().checked_sub(1)?;
+
+    for (index, candidate) in candidates.into_iter().enumerate() {
+        remaining -= neglect_weight(candidate.idle_days);
+        if remaining < 0.0 || index == last {
+            return Some(candidate.audiobook);
+        }
+    }
+    None
+}
+
+/// Uniform value in 0.0..1.0 from the 48 leading random bits of a v4 UUID
+fn random_fraction() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() >> 80) as f64 / (1u64 << 48) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(title: &str, idle_days: f64) -> RandomPickCandidate {
+        RandomPickCandidate {
+            audiobook: Audiobook::new(title.to_string(), format!("/books/{}", title)),
+            idle_days,
+        }
+    }
+
+    #[test]
+    fn test_neglected_books_get_more_weight() {
+        assert_eq!(neglect_weight(0.0), 1.0);
+        assert_eq!(neglect_weight(-3.0), 1.0);
+        assert_eq!(neglect_weight(MAX_NEGLECT_DAYS), neglect_weight(MAX_NEGLECT_DAYS * 4.0));
+        assert!(neglect_weight(200.0) > neglect_weight(10.0));
+    }
+
+    #[test]
+    fn test_pick_weighted_follows_weights() {
+        // Weights 1.0 and 4.0: the first fifth of the wheel belongs to the fresh book
+        let books = || vec![candidate("fresh", 0.0), candidate("neglected", 90.0)];
+        assert_eq!(pick_weighted(books(), 0.1).unwrap().title, "fresh");
+        assert_eq!(pick_weighted(books(), 0.3).unwrap().title, "neglected");
+        assert_eq!(pick_weighted(books(), 0.999_999).unwrap().title, "neglected");
+        assert!(pick_weighted(Vec::new(), 0.5).is_none());
+
+        let fraction = random_fraction();
+        assert!((0.0..1.0).contains(&fraction));
+    }
+}
@@ -1,8 +1,169 @@
-use crate::database::{models::*, repository::AudiobookRepository};
+use crate::database::{models::*, repository::{AudiobookRepository, PreferencesRepository}};
 use anyhow::{Result, Context};
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use std::collections::HashMap;
+use ts_rs::TS;
+use crate::validation::Validate;
+
+/// Score multiplier for recommendations narrated by a favorite narrator
+const FAVORITE_NARRATOR_BOOST: f64 = 1.2;
+pub const PREF_RECOMMENDATION_WEIGHTS: &str = "recommendations.weights";
+/// Weights above this would let one source crowd out every other
+const MAX_WEIGHT: f64 = 2.0;
+/// Taste score of a genre or author picked during onboarding: about what a
+/// well-liked book earns, so real listening soon outweighs it
+pub const SEED_SCORE: f64 = 0.6;
+
+/// How much each source of recommendations counts; a source's scores are
+/// multiplied by its weight, so 0 turns it off
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecommendationWeights {
+    pub genre: f64,
+    pub author: f64,
+    pub narrator: f64,
+    /// Books similar to ones recently finished or rated highly
+    pub similar: f64,
+}
+
+impl Default for RecommendationWeights {
+    fn default() -> Self {
+        Self { genre: 0.8, author: 0.9, narrator: 0.7, similar: 1.0 }
+    }
+}
+
+impl RecommendationWeights {
+    fn clamped(self) -> Self {
+        let clamp = |weight: f64, default: f64| if weight.is_finite() { weight.clamp(0.0, MAX_WEIGHT) } else { default };
+        let default = Self::default();
+        Self {
+            genre: clamp(self.genre, default.genre),
+            author: clamp(self.author, default.author),
+            narrator: clamp(self.narrator, default.narrator),
+            similar: clamp(self.similar, default.similar),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum FactorKind {
+    Genre,
+    Author,
+    Narrator,
+    Similarity,
+    FavoriteNarrator,
+}
+
+/// One thing a recommendation's score was built from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecommendationFactor {
+    pub kind: FactorKind,
+    /// The genre, author or narrator matched, or how a similar book is similar
+    pub value: String,
+    /// The listener's taste score for the value, or the similarity
+    pub score: f64,
+    /// What the score was multiplied by
+    pub weight: f64,
+}
+
+/// The finished book a recommendation is similar to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SimilaritySource {
+    pub audiobook_id: String,
+    pub title: String,
+}
+
+/// Stored with each recommendation when it is generated
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct RecommendationFactors {
+    factors: Vec<RecommendationFactor>,
+    similar_to: Option<SimilaritySource>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecommendationExplanation {
+    pub recommendation: Recommendation,
+    /// Empty for recommendations made before factors were recorded
+    pub factors: Vec<RecommendationFactor>,
+    pub similar_to: Option<SimilaritySource>,
+    /// The weights in use now, which later recommendations will be scored with
+    pub weights: RecommendationWeights,
+}
+
+/// The genres and authors a new listener picks before anything is played
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TasteProfile {
+    pub genres: Vec<String>,
+    pub authors: Vec<String>,
+}
+
+/// A proposed recommendation and what its score was built from
+struct Candidate {
+    recommendation: RecommendationWithAudiobook,
+    factors: RecommendationFactors,
+}
+
+impl Candidate {
+    fn new(book: Audiobook, recommendation_type: &str, reason: String, factor: RecommendationFactor) -> Self {
+        let recommendation = Recommendation::new(
+            book.id.clone(),
+            recommendation_type.to_string(),
+            factor.score * factor.weight,
+            Some(reason),
+        );
+        Self {
+            recommendation: RecommendationWithAudiobook { recommendation, audiobook: book },
+            factors: RecommendationFactors { factors: vec![factor], similar_to: None },
+        }
+    }
+
+    fn score(&self) -> f64 {
+        self.recommendation.recommendation.recommendation_score
+    }
+}
+
+/// A recommendation joined with its audiobook. Only the id column clashes
+/// between the two tables, so the recommendation's is renamed.
+#[derive(sqlx::FromRow)]
+struct RecommendationRow {
+    #[sqlx(flatten)]
+    audiobook: Audiobook,
+    recommendation_id: String,
+    recommendation_type: String,
+    recommendation_score: f64,
+    recommendation_reason: Option<String>,
+    generated_at: String,
+    expires_at: Option<String>,
+    is_dismissed: bool,
+    user_feedback: Option<i32>,
+}
+
+impl RecommendationRow {
+    fn into_recommendation(self) -> RecommendationWithAudiobook {
+        RecommendationWithAudiobook {
+            recommendation: Recommendation {
+                id: self.recommendation_id,
+                audiobook_id: self.audiobook.id.clone(),
+                recommendation_type: self.recommendation_type,
+                recommendation_score: self.recommendation_score,
+                recommendation_reason: self.recommendation_reason,
+                generated_at: self.generated_at,
+                expires_at: self.expires_at,
+                is_dismissed: self.is_dismissed,
+                user_feedback: self.user_feedback,
+            },
+            audiobook: self.audiobook,
+        }
+    }
+}
 
 pub struct RecommendationService<'a> {
     pool: &'a SqlitePool,
@@ -15,6 +176,7 @@ impl<'a> RecommendationService<'a> {
 
     // Track listening session
     pub async fn track_listening_session(&self, dto: CreateListeningHistoryDto) -> Result<ListeningHistory> {
+        dto.validate()?;
         let mut history = ListeningHistory::new(
             dto.audiobook_id.clone(),
             dto.position_seconds,
@@ -33,6 +195,11 @@ impl<'a> RecommendationService<'a> {
             history.playback_speed = speed;
         }
 
+        // Private listening leaves no trace in history or taste preferences
+        if super::privacy::is_incognito() {
+            return Ok(history);
+        }
+
         sqlx::query(
             r#"
             INSERT INTO listening_history (
@@ -68,35 +235,179 @@ impl<'a> RecommendationService<'a> {
         self.cleanup_old_recommendations().await?;
 
         // Generate different types of recommendations
+        let weights = self.weights().await?;
         let mut all_recommendations = Vec::new();
 
         // 1. Genre-based recommendations
-        let genre_recs = self.generate_genre_based_recommendations(limit / 3).await?;
+        let genre_recs = self.generate_genre_based_recommendations(limit / 3, weights.genre).await?;
         all_recommendations.extend(genre_recs);
 
         // 2. Author-based recommendations
-        let author_recs = self.generate_author_based_recommendations(limit / 3).await?;
+        let author_recs = self.generate_author_based_recommendations(limit / 3, weights.author).await?;
         all_recommendations.extend(author_recs);
 
         // 3. Similar audiobooks based on listening patterns
-        let similar_recs = self.generate_similar_recommendations(limit / 3).await?;
+        let similar_recs = self.generate_similar_recommendations(limit / 3, weights.similar).await?;
         all_recommendations.extend(similar_recs);
 
+        // 4. Narrators the listener keeps coming back to or marked as favorite
+        let narrator_recs = self.generate_narrator_based_recommendations(limit / 3, weights.narrator).await?;
+        all_recommendations.extend(narrator_recs);
+
+        // A source weighted to zero is turned off
+        all_recommendations.retain(|candidate| candidate.score() > 0.0);
+
+        // The same book can come from several sources; keep its best-scoring entry
+        all_recommendations.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap_or(std::cmp::Ordering::Equal));
+        let mut seen = std::collections::HashSet::new();
+        all_recommendations.retain(|candidate| seen.insert(candidate.recommendation.audiobook.id.clone()));
+
+        // Favorite narrators lift every recommendation they narrate
+        let favorite_narrators: std::collections::HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM narrators WHERE is_favorite"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get favorite narrators")?
+        .into_iter()
+        .collect();
+        for candidate in &mut all_recommendations {
+            let rec = &mut candidate.recommendation;
+            if rec.audiobook.narrator_id.as_ref().is_some_and(|id| favorite_narrators.contains(id)) {
+                rec.recommendation.recommendation_score *= FAVORITE_NARRATOR_BOOST;
+                candidate.factors.factors.push(RecommendationFactor {
+                    kind: FactorKind::FavoriteNarrator,
+                    value: rec.audiobook.narrator.clone().unwrap_or_default(),
+                    score: FAVORITE_NARRATOR_BOOST,
+                    weight: 1.0,
+                });
+            }
+        }
+
         // Sort by score and take top recommendations
-        all_recommendations.sort_by(|a, b| {
-            b.recommendation.recommendation_score
-                .partial_cmp(&a.recommendation.recommendation_score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        all_recommendations.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap_or(std::cmp::Ordering::Equal));
 
         all_recommendations.truncate(limit as usize);
 
         // Save recommendations to database
-        for rec_with_book in &all_recommendations {
-            self.save_recommendation(&rec_with_book.recommendation).await?;
+        for candidate in &all_recommendations {
+            self.save_recommendation(&candidate.recommendation.recommendation, Some(&candidate.factors)).await?;
+        }
+
+        Ok(all_recommendations.into_iter().map(|candidate| candidate.recommendation).collect())
+    }
+
+    /// The stored tuning weights, or the defaults
+    pub async fn weights(&self) -> Result<RecommendationWeights> {
+        let Some(json) = PreferencesRepository::new(self.pool).get(PREF_RECOMMENDATION_WEIGHTS).await? else {
+            return Ok(RecommendationWeights::default());
+        };
+        match serde_json::from_str::<RecommendationWeights>(&json) {
+            Ok(weights) => Ok(weights.clamped()),
+            Err(e) => {
+                log::warn!("Ignoring invalid recommendation weights: {}", e);
+                Ok(RecommendationWeights::default())
+            }
         }
+    }
+
+    /// Store new weights, limited to 0..=MAX_WEIGHT; they apply from the next generation
+    pub async fn set_weights(&self, weights: RecommendationWeights) -> Result<RecommendationWeights> {
+        let weights = weights.clamped();
+        let json = serde_json::to_string(&weights).context("Failed to serialize recommendation weights")?;
+        PreferencesRepository::new(self.pool).set(PREF_RECOMMENDATION_WEIGHTS, &json).await?;
+        Ok(weights)
+    }
 
-        Ok(all_recommendations)
+    /// Seed the taste preferences with the genres and authors picked during
+    /// onboarding. Blank and repeated entries are dropped, and values already
+    /// scored higher keep their score. Returns what was seeded.
+    pub async fn submit_taste_profile(&self, profile: TasteProfile) -> Result<TasteProfile> {
+        let clean = |values: Vec<String>| {
+            let mut seen = std::collections::HashSet::new();
+            values
+                .into_iter()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty() && seen.insert(value.to_lowercase()))
+                .collect::<Vec<_>>()
+        };
+        let profile = TasteProfile { genres: clean(profile.genres), authors: clean(profile.authors) };
+        anyhow::ensure!(!profile.genres.is_empty() || !profile.authors.is_empty(), "Pick at least one genre or author");
+
+        for (pref_type, values) in [("genre", &profile.genres), ("author", &profile.authors)] {
+            for value in values {
+                let current: Option<f64> = sqlx::query_scalar(
+                    "SELECT preference_score FROM user_preferences WHERE preference_type = ? AND preference_value = ?"
+                )
+                .bind(pref_type)
+                .bind(value)
+                .fetch_optional(self.pool)
+                .await
+                .context("Failed to check existing preference")?;
+                let current = current.unwrap_or(0.0);
+                if current < SEED_SCORE {
+                    self.update_preference(pref_type, value, SEED_SCORE - current).await?;
+                }
+            }
+        }
+        Ok(profile)
+    }
+
+    /// The values of one preference type ("genre", "author") the listener
+    /// leans towards, strongest first
+    pub async fn taste_preferences(&self, pref_type: &str, limit: i32) -> Result<Vec<(String, f64)>> {
+        sqlx::query_as::<_, (String, f64)>(
+            r#"
+            SELECT preference_value, preference_score FROM user_preferences
+            WHERE preference_type = ? AND preference_score > 0
+            ORDER BY preference_score DESC, preference_value
+            LIMIT ?
+            "#,
+        )
+        .bind(pref_type)
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get taste preferences")
+    }
+
+    /// Nothing has been listened to or rated yet, so only the taste profile
+    /// picked during onboarding says what the listener likes
+    pub async fn is_cold_start(&self) -> Result<bool> {
+        sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT NOT EXISTS (SELECT 1 FROM listening_history)
+               AND NOT EXISTS (SELECT 1 FROM audiobooks WHERE rating IS NOT NULL)
+            "#,
+        )
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to check listening history")
+    }
+
+    /// Why a book was recommended: what its score was built from
+    pub async fn explain(&self, recommendation_id: &str) -> Result<RecommendationExplanation> {
+        let recommendation = sqlx::query_as::<_, Recommendation>("SELECT * FROM recommendations WHERE id = ?")
+            .bind(recommendation_id)
+            .fetch_optional(self.pool)
+            .await
+            .context("Failed to get recommendation")?
+            .with_context(|| format!("Recommendation not found: {}", recommendation_id))?;
+        let factors: Option<String> = sqlx::query_scalar("SELECT factors FROM recommendations WHERE id = ?")
+            .bind(recommendation_id)
+            .fetch_one(self.pool)
+            .await
+            .context("Failed to get recommendation factors")?;
+
+        let factors: RecommendationFactors = factors
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Ok(RecommendationExplanation {
+            recommendation,
+            factors: factors.factors,
+            similar_to: factors.similar_to,
+            weights: self.weights().await?,
+        })
     }
 
     // Get user's listening statistics for recommendations
@@ -152,6 +463,7 @@ impl<'a> RecommendationService<'a> {
 
     // Provide recommendation feedback
     pub async fn submit_recommendation_feedback(&self, dto: CreateRecommendationFeedbackDto) -> Result<RecommendationFeedback> {
+        dto.validate()?;
         let feedback = RecommendationFeedback::new(
             dto.recommendation_id.clone(),
             dto.feedback_type,
@@ -196,12 +508,16 @@ impl<'a> RecommendationService<'a> {
     // Get current recommendations
     pub async fn get_current_recommendations(&self, limit: Option<i32>) -> Result<Vec<RecommendationWithAudiobook>> {
         let limit = limit.unwrap_or(10);
-        
-        // Get recommendations first
-        let recommendations = sqlx::query_as::<_, Recommendation>(
+
+        // One query for the recommendations and their books; recommendations
+        // whose book was deleted drop out of the join
+        let rows = sqlx::query_as::<_, RecommendationRow>(
             r#"
-            SELECT *
+            SELECT a.*,
+                   r.id as recommendation_id, r.recommendation_type, r.recommendation_score,
+                   r.recommendation_reason, r.generated_at, r.expires_at, r.is_dismissed, r.user_feedback
             FROM recommendations r
+            JOIN audiobooks a ON a.id = r.audiobook_id
             WHERE r.is_dismissed = FALSE 
               AND (r.expires_at IS NULL OR r.expires_at > datetime('now'))
             ORDER BY r.recommendation_score DESC
@@ -213,33 +529,30 @@ impl<'a> RecommendationService<'a> {
         .await
         .context("Failed to get current recommendations")?;
 
-        let mut result = Vec::new();
-        
-        // Get audiobook for each recommendation
-        let audiobook_repo = AudiobookRepository::new(self.pool);
-        for recommendation in recommendations {
-            if let Some(audiobook) = audiobook_repo.find_by_id(&recommendation.audiobook_id).await? {
-                result.push(RecommendationWithAudiobook {
-                    recommendation,
-                    audiobook,
-                });
-            }
-        }
-
-        Ok(result)
+        Ok(rows.into_iter().map(RecommendationRow::into_recommendation).collect())
     }
 
     // Private helper methods
 
-    async fn generate_genre_based_recommendations(&self, limit: i32) -> Result<Vec<RecommendationWithAudiobook>> {
+    async fn generate_genre_based_recommendations(&self, limit: i32, weight: f64) -> Result<Vec<Candidate>> {
+        // An explicit rating says more than how far someone got, so it replaces
+        // completion as the per-book signal when present; abandoning a book
+        // counts as the weakest signal short of a rating
         let preferred_genres = sqlx::query_as::<_, (String, f64)>(
             r#"
-            SELECT a.genre, AVG(lh.completion_percentage) as score
-            FROM listening_history lh
-            JOIN audiobooks a ON lh.audiobook_id = a.id
-            WHERE a.genre IS NOT NULL AND a.genre != ''
-            GROUP BY a.genre
-            HAVING COUNT(*) >= 2
+            SELECT genre, AVG(book_score) as score
+            FROM (
+                SELECT a.genre,
+                       COALESCE((a.rating - 1) / 4.0, CASE WHEN abandoned THEN 0.0 END, MAX(lh.completion_percentage)) as book_score,
+                       COUNT(lh.id) + (a.rating IS NOT NULL) + abandoned as signals
+                FROM (SELECT *, a.id IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned) as abandoned FROM audiobooks a) a
+                LEFT JOIN listening_history lh ON lh.audiobook_id = a.id
+                WHERE a.genre IS NOT NULL AND a.genre != ''
+                  AND (a.rating IS NOT NULL OR a.abandoned OR lh.id IS NOT NULL)
+                GROUP BY a.id
+            )
+            GROUP BY genre
+            HAVING SUM(signals) >= 2
             ORDER BY score DESC
             LIMIT 5
             "#,
@@ -248,6 +561,12 @@ impl<'a> RecommendationService<'a> {
         .await
         .context("Failed to get preferred genres")?;
 
+        // A new library has no history to go on yet, only the picked genres
+        let preferred_genres = if preferred_genres.is_empty() && self.is_cold_start().await? {
+            self.taste_preferences("genre", 5).await?
+        } else {
+            preferred_genres
+        };
         if preferred_genres.is_empty() {
             return Ok(Vec::new());
         }
@@ -260,7 +579,8 @@ impl<'a> RecommendationService<'a> {
                 r#"
                 SELECT a.* FROM audiobooks a
                 LEFT JOIN listening_history lh ON a.id = lh.audiobook_id
-                WHERE a.genre = ? AND lh.audiobook_id IS NULL
+                WHERE a.genre = ? AND lh.audiobook_id IS NULL AND a.rating IS NULL
+                  AND a.id NOT IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned)
                 ORDER BY a.added_date DESC
                 LIMIT ?
                 "#,
@@ -272,32 +592,33 @@ impl<'a> RecommendationService<'a> {
             .context("Failed to get genre-based recommendations")?;
 
             for book in books {
-                let recommendation = Recommendation::new(
-                    book.id.clone(),
-                    "genre_preference".to_string(),
-                    preference_score * 0.8, // Scale down genre recommendations
-                    Some(format!("Because you enjoy {} audiobooks", genre)),
-                );
-
-                recommendations.push(RecommendationWithAudiobook {
-                    recommendation,
-                    audiobook: book,
-                });
+                recommendations.push(Candidate::new(
+                    book,
+                    "genre_preference",
+                    format!("Because you enjoy {} audiobooks", genre),
+                    RecommendationFactor { kind: FactorKind::Genre, value: genre.clone(), score: preference_score, weight },
+                ));
             }
         }
 
         Ok(recommendations)
     }
 
-    async fn generate_author_based_recommendations(&self, limit: i32) -> Result<Vec<RecommendationWithAudiobook>> {
+    async fn generate_author_based_recommendations(&self, limit: i32, weight: f64) -> Result<Vec<Candidate>> {
         let preferred_authors = sqlx::query_as::<_, (String, f64)>(
             r#"
-            SELECT a.author, AVG(lh.completion_percentage) as score
-            FROM listening_history lh
-            JOIN audiobooks a ON lh.audiobook_id = a.id
-            WHERE a.author IS NOT NULL AND a.author != ''
-            GROUP BY a.author
-            HAVING COUNT(*) >= 1 AND AVG(lh.completion_percentage) > 0.5
+            SELECT author, AVG(book_score) as score
+            FROM (
+                SELECT a.author,
+                       COALESCE((a.rating - 1) / 4.0, CASE WHEN abandoned THEN 0.0 END, MAX(lh.completion_percentage)) as book_score
+                FROM (SELECT *, a.id IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned) as abandoned FROM audiobooks a) a
+                LEFT JOIN listening_history lh ON lh.audiobook_id = a.id
+                WHERE a.author IS NOT NULL AND a.author != ''
+                  AND (a.rating IS NOT NULL OR a.abandoned OR lh.id IS NOT NULL)
+                GROUP BY a.id
+            )
+            GROUP BY author
+            HAVING AVG(book_score) > 0.5
             ORDER BY score DESC
             LIMIT 5
             "#,
@@ -306,6 +627,11 @@ impl<'a> RecommendationService<'a> {
         .await
         .context("Failed to get preferred authors")?;
 
+        let preferred_authors = if preferred_authors.is_empty() && self.is_cold_start().await? {
+            self.taste_preferences("author", 5).await?
+        } else {
+            preferred_authors
+        };
         if preferred_authors.is_empty() {
             return Ok(Vec::new());
         }
@@ -318,7 +644,8 @@ impl<'a> RecommendationService<'a> {
                 r#"
                 SELECT a.* FROM audiobooks a
                 LEFT JOIN listening_history lh ON a.id = lh.audiobook_id
-                WHERE a.author = ? AND lh.audiobook_id IS NULL
+                WHERE a.author = ? AND lh.audiobook_id IS NULL AND a.rating IS NULL
+                  AND a.id NOT IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned)
                 ORDER BY a.added_date DESC
                 LIMIT ?
                 "#,
@@ -330,31 +657,30 @@ impl<'a> RecommendationService<'a> {
             .context("Failed to get author-based recommendations")?;
 
             for book in books {
-                let recommendation = Recommendation::new(
-                    book.id.clone(),
-                    "author_preference".to_string(),
-                    preference_score * 0.9, // Author preferences get higher weight
-                    Some(format!("More audiobooks by {}", author)),
-                );
-
-                recommendations.push(RecommendationWithAudiobook {
-                    recommendation,
-                    audiobook: book,
-                });
+                recommendations.push(Candidate::new(
+                    book,
+                    "author_preference",
+                    format!("More audiobooks by {}", author),
+                    RecommendationFactor { kind: FactorKind::Author, value: author.clone(), score: preference_score, weight },
+                ));
             }
         }
 
         Ok(recommendations)
     }
 
-    async fn generate_similar_recommendations(&self, limit: i32) -> Result<Vec<RecommendationWithAudiobook>> {
-        // Find audiobooks similar to recently completed ones
+    async fn generate_similar_recommendations(&self, limit: i32, weight: f64) -> Result<Vec<Candidate>> {
+        // Find audiobooks similar to recently completed or highly rated ones,
+        // skipping books that were finished but rated poorly
         let recently_completed = sqlx::query_as::<_, Audiobook>(
             r#"
             SELECT a.* FROM audiobooks a
-            JOIN listening_history lh ON a.id = lh.audiobook_id
-            WHERE lh.completion_percentage > 0.8
-            ORDER BY lh.listened_at DESC
+            LEFT JOIN listening_history lh ON a.id = lh.audiobook_id
+            WHERE (lh.completion_percentage > 0.8 OR a.rating >= 4)
+              AND (a.rating IS NULL OR a.rating >= 3)
+              AND a.id NOT IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned)
+            GROUP BY a.id
+            ORDER BY MAX(COALESCE(lh.listened_at, a.updated_at)) DESC
             LIMIT 3
             "#,
         )
@@ -377,6 +703,8 @@ impl<'a> RecommendationService<'a> {
                 LEFT JOIN listening_history lh ON a.id = lh.audiobook_id
                 WHERE a.id != ?
                   AND lh.audiobook_id IS NULL
+                  AND a.rating IS NULL
+                  AND a.id NOT IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned)
                   AND (a.genre = ? OR a.author = ?)
                 ORDER BY 
                   CASE 
@@ -399,8 +727,9 @@ impl<'a> RecommendationService<'a> {
             .context("Failed to get similar audiobooks")?;
 
             for book in similar_books {
-                let score = if book.author == completed_book.author { 0.9 } else { 0.7 };
-                let reason = if book.author == completed_book.author {
+                let same_author = book.author == completed_book.author;
+                let score = if same_author { 0.9 } else { 0.7 };
+                let reason = if same_author {
                     format!("More books by {} (similar to '{}')", 
                            book.author.as_ref().unwrap_or(&"Unknown".to_string()), 
                            completed_book.title)
@@ -408,30 +737,104 @@ impl<'a> RecommendationService<'a> {
                     format!("Similar to '{}' (same genre)", completed_book.title)
                 };
 
-                let recommendation = Recommendation::new(
-                    book.id.clone(),
-                    "similar_to_completed".to_string(),
-                    score,
-                    Some(reason),
-                );
+                let value = if same_author { "same author" } else { "same genre" };
 
-                recommendations.push(RecommendationWithAudiobook {
-                    recommendation,
-                    audiobook: book,
+                let mut candidate = Candidate::new(
+                    book,
+                    "similar_to_completed",
+                    reason,
+                    RecommendationFactor { kind: FactorKind::Similarity, value: value.to_string(), score, weight },
+                );
+                candidate.factors.similar_to = Some(SimilaritySource {
+                    audiobook_id: completed_book.id.clone(),
+                    title: completed_book.title.clone(),
                 });
+                recommendations.push(candidate);
             }
         }
 
         Ok(recommendations)
     }
 
-    async fn save_recommendation(&self, recommendation: &Recommendation) -> Result<()> {
+    async fn generate_narrator_based_recommendations(&self, limit: i32, weight: f64) -> Result<Vec<Candidate>> {
+        // Narrator preferences are keyed by the narrator text of listened books
+        let narrators = sqlx::query_as::<_, (String, String, bool, f64)>(
+            r#"
+            SELECT n.id, n.name, n.is_favorite,
+                   COALESCE((
+                       SELECT MAX(up.preference_score) FROM user_preferences up
+                       WHERE up.preference_type = 'narrator'
+                         AND up.preference_value IN (SELECT a.narrator FROM audiobooks a WHERE a.narrator_id = n.id)
+                   ), 0.0) as score
+            FROM narrators n
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get preferred narrators")?;
+
+        let mut preferred: Vec<(String, String, bool, f64)> = narrators
+            .into_iter()
+            .filter(|(_, _, is_favorite, score)| *is_favorite || *score >= 0.2)
+            .collect();
+        preferred.sort_by(|a, b| b.3.total_cmp(&a.3));
+        preferred.truncate(5);
+
+        if preferred.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut recommendations = Vec::new();
+        let narrator_count = preferred.len() as i32;
+
+        for (narrator_id, name, is_favorite, preference_score) in preferred {
+            let books = sqlx::query_as::<_, Audiobook>(
+                r#"
+                SELECT a.* FROM audiobooks a
+                LEFT JOIN listening_history lh ON a.id = lh.audiobook_id
+                WHERE a.narrator_id = ? AND lh.audiobook_id IS NULL AND a.rating IS NULL
+                  AND a.id NOT IN (SELECT audiobook_id FROM playback_progress WHERE is_abandoned)
+                ORDER BY a.added_date DESC
+                LIMIT ?
+                "#,
+            )
+            .bind(&narrator_id)
+            .bind((limit / narrator_count).max(1))
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to get narrator-based recommendations")?;
+
+            for book in books {
+                let reason = if is_favorite {
+                    format!("Narrated by {}, one of your favorite narrators", name)
+                } else {
+                    format!("Narrated by {}", name)
+                };
+                recommendations.push(Candidate::new(
+                    book,
+                    "narrator_preference",
+                    reason,
+                    RecommendationFactor {
+                        kind: FactorKind::Narrator,
+                        value: name.clone(),
+                        score: preference_score.clamp(0.2, 1.0),
+                        weight,
+                    },
+                ));
+            }
+        }
+
+        Ok(recommendations)
+    }
+
+    async fn save_recommendation(&self, recommendation: &Recommendation, factors: Option<&RecommendationFactors>) -> Result<()> {
+        let factors = factors.map(serde_json::to_string).transpose().context("Failed to serialize recommendation factors")?;
         sqlx::query(
             r#"
             INSERT OR IGNORE INTO recommendations (
                 id, audiobook_id, recommendation_type, recommendation_score,
-                recommendation_reason, generated_at, expires_at, is_dismissed, user_feedback
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                recommendation_reason, generated_at, expires_at, is_dismissed, user_feedback, factors
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&recommendation.id)
@@ -443,6 +846,7 @@ impl<'a> RecommendationService<'a> {
         .bind(&recommendation.expires_at)
         .bind(&recommendation.is_dismissed)
         .bind(&recommendation.user_feedback)
+        .bind(&factors)
         .execute(self.pool)
         .await
         .context("Failed to save recommendation")?;
@@ -477,6 +881,50 @@ impl<'a> RecommendationService<'a> {
         Ok(())
     }
 
+    /// Shift taste preferences by the change in a book's rating: 3 stars is neutral,
+    /// each star above or below moves the genre/author score by 0.1
+    pub async fn apply_rating_change(&self, audiobook: &Audiobook, previous: Option<i32>, rating: Option<i32>) -> Result<()> {
+        if super::privacy::is_incognito() {
+            return Ok(());
+        }
+
+        let weight = |stars: Option<i32>| stars.map(|s| (s - 3) as f64 * 0.1).unwrap_or(0.0);
+        let delta = weight(rating) - weight(previous);
+        if delta == 0.0 {
+            return Ok(());
+        }
+
+        if let Some(genre) = &audiobook.genre {
+            self.update_preference("genre", genre, delta).await?;
+        }
+        if let Some(author) = &audiobook.author {
+            self.update_preference("author", author, delta).await?;
+        }
+        if let Some(narrator) = &audiobook.narrator {
+            self.update_preference("narrator", narrator, delta / 2.0).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Abandoning a book weighs against its genre/author like a one-star rating;
+    /// un-abandoning it takes that back
+    pub async fn apply_abandon_change(&self, audiobook: &Audiobook, abandoned: bool) -> Result<()> {
+        if super::privacy::is_incognito() {
+            return Ok(());
+        }
+
+        let delta = if abandoned { -0.2 } else { 0.2 };
+        if let Some(genre) = &audiobook.genre {
+            self.update_preference("genre", genre, delta).await?;
+        }
+        if let Some(author) = &audiobook.author {
+            self.update_preference("author", author, delta).await?;
+        }
+
+        Ok(())
+    }
+
     async fn update_preference(&self, pref_type: &str, pref_value: &str, increment: f64) -> Result<()> {
         // Check if preference exists
         let existing = sqlx::query_as::<_, UserPreference>(
@@ -574,4 +1022,160 @@ impl<'a> RecommendationService<'a> {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_weights_are_stored_and_recommendations_explained() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("weights.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = RecommendationService::new(pool);
+
+        assert_eq!(service.weights().await.unwrap(), RecommendationWeights::default());
+        let weights = service
+            .set_weights(RecommendationWeights { genre: 0.3, author: -1.0, narrator: 5.0, similar: f64::NAN })
+            .await
+            .unwrap();
+        assert_eq!(weights, RecommendationWeights { genre: 0.3, author: 0.0, narrator: MAX_WEIGHT, similar: 1.0 });
+        assert_eq!(service.weights().await.unwrap(), weights);
+
+        let book = AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: "Emma".to_string(),
+            file_path: "/books/emma".to_string(),
+            author: Some("Jane Austen".to_string()),
+            narrator: Some("Juliet Stevenson".to_string()),
+            description: None,
+            genre: Some("Classics".to_string()),
+            duration: Some(3600),
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let factor = RecommendationFactor { kind: FactorKind::Narrator, value: "Juliet Stevenson".to_string(), score: 0.5, weight: weights.narrator };
+        let candidate = Candidate::new(book.clone(), "narrator_preference", "Narrated by Juliet Stevenson".to_string(), factor.clone());
+        assert_eq!(candidate.score(), 1.0);
+        service.save_recommendation(&candidate.recommendation.recommendation, Some(&candidate.factors)).await.unwrap();
+
+        let explanation = service.explain(&candidate.recommendation.recommendation.id).await.unwrap();
+        assert_eq!(explanation.factors, vec![factor]);
+        assert_eq!(explanation.similar_to, None);
+        assert_eq!(explanation.weights, weights);
+
+        // Recommendations from before factors were recorded explain with none
+        let older = Recommendation::new(book.id, "genre_preference".to_string(), 0.4, None);
+        service.save_recommendation(&older, None).await.unwrap();
+        assert!(service.explain(&older.id).await.unwrap().factors.is_empty());
+        assert!(service.explain("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_taste_profile_recommends_for_a_new_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("onboarding.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = RecommendationService::new(pool);
+
+        for (title, author, genre) in [("Emma", "Jane Austen", "Romance"), ("Dracula", "Bram Stoker", "Horror"), ("Kim", "Rudyard Kipling", "Adventure")] {
+            AudiobookRepository::new(pool).create(CreateAudiobookDto {
+                title: title.to_string(),
+                file_path: format!("/books/{}", title),
+                author: Some(author.to_string()),
+                narrator: None,
+                description: None,
+                genre: Some(genre.to_string()),
+                duration: Some(3600),
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            }).await.unwrap();
+        }
+        assert!(service.is_cold_start().await.unwrap());
+        assert!(service.generate_recommendations(Some(10)).await.unwrap().is_empty());
+        assert!(service.submit_taste_profile(TasteProfile::default()).await.is_err());
+
+        let profile = service
+            .submit_taste_profile(TasteProfile {
+                genres: vec!["Horror".to_string(), " horror ".to_string(), "".to_string()],
+                authors: vec!["Jane Austen".to_string()],
+            })
+            .await
+            .unwrap();
+        assert_eq!(profile.genres, vec!["Horror"]);
+        assert_eq!(service.taste_preferences("genre", 5).await.unwrap(), vec![("Horror".to_string(), SEED_SCORE)]);
+
+        let mut titles: Vec<String> = service
+            .generate_recommendations(Some(10))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|rec| rec.audiobook.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Dracula", "Emma"]);
+
+        // Submitting again does not pile up the scores
+        service.submit_taste_profile(profile).await.unwrap();
+        assert_eq!(service.taste_preferences("author", 5).await.unwrap(), vec![("Jane Austen".to_string(), SEED_SCORE)]);
+    }
+
+    /// Run with `cargo test --release bench_ -- --ignored --nocapture`
+    #[tokio::test]
+    #[ignore]
+    async fn bench_current_recommendations_join() {
+        const BOOKS: i32 = 1500;
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("bench.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let repo = AudiobookRepository::new(pool);
+        let service = RecommendationService::new(pool);
+        for i in 0..BOOKS {
+            let book = repo.create(CreateAudiobookDto {
+                title: format!("Book {}", i),
+                file_path: format!("/books/{}", i),
+                author: Some(format!("Author {}", i % 50)),
+                narrator: None,
+                description: Some("A long description. ".repeat(20)),
+                genre: Some("Fiction".to_string()),
+                duration: Some(3600),
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            }).await.unwrap();
+            let recommendation = Recommendation::new(book.id, "genre_based".to_string(), i as f64, None);
+            service.save_recommendation(&recommendation, None).await.unwrap();
+        }
+
+        // The previous approach: one audiobook lookup per recommendation
+        let started = Instant::now();
+        let recommendations = sqlx::query_as::<_, Recommendation>(
+            "SELECT * FROM recommendations WHERE is_dismissed = FALSE ORDER BY recommendation_score DESC LIMIT ?"
+        )
+        .bind(BOOKS)
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        let mut per_row = Vec::new();
+        for recommendation in recommendations {
+            if let Some(audiobook) = repo.find_by_id(&recommendation.audiobook_id).await.unwrap() {
+                per_row.push(audiobook.id);
+            }
+        }
+        let per_row_time = started.elapsed();
+
+        let started = Instant::now();
+        let joined = service.get_current_recommendations(Some(BOOKS)).await.unwrap();
+        let join_time = started.elapsed();
+
+        println!("{} recommendations: per-row lookups {:?}, join {:?}", BOOKS, per_row_time, join_time);
+        assert_eq!(joined.into_iter().map(|rec| rec.audiobook.id).collect::<Vec<_>>(), per_row);
+        assert!(join_time < per_row_time);
+    }
+}
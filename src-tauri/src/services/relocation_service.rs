@@ -0,0 +1,294 @@
+use crate::database::{models::*, repository::{AudiobookRepository, ChapterRepository, FingerprintRepository}};
+use crate::filesystem::fingerprint::{compute_fingerprint, find_files_with_sizes, Fingerprint};
+use crate::filesystem::volume;
+use crate::services::LibraryRootService;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RelocatedFile {
+    pub audiobook_id: String,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RelocationReport {
+    pub missing_files: usize,
+    pub relocated: Vec<RelocatedFile>,
+    /// Missing files with more than one identical candidate; left for the user to pick
+    pub ambiguous: Vec<String>,
+    pub still_missing: Vec<String>,
+    /// Files on a drive or share that is not mounted; not missing, so left where they are
+    pub volume_offline: Vec<String>,
+}
+
+pub struct RelocationService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> RelocationService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Fingerprint every file of an audiobook that currently exists on disk
+    pub async fn record_audiobook_fingerprints(&self, audiobook_id: &str) -> Result<usize> {
+        let audiobook = AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Audiobook not found: {}", audiobook_id))?;
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+
+        let repo = FingerprintRepository::new(self.pool);
+        let known: HashMap<String, i64> = repo.find_by_audiobook_id(audiobook_id).await?
+            .into_iter()
+            .map(|fp| (fp.file_path, fp.file_size))
+            .collect();
+
+        let mut files: Vec<(Option<String>, String)> = Vec::new();
+        if Path::new(&audiobook.file_path).is_file() {
+            files.push((None, audiobook.file_path.clone()));
+        }
+        for chapter in chapters {
+            if chapter.file_path != audiobook.file_path && Path::new(&chapter.file_path).is_file() {
+                files.push((Some(chapter.id), chapter.file_path));
+            }
+        }
+
+        // Skip files already fingerprinted at the same path and size
+        files.retain(|(_, path)| {
+            let size = std::fs::metadata(path).map(|m| m.len() as i64).ok();
+            known.get(path).copied() != size
+        });
+
+        let fingerprints = tokio::task::spawn_blocking(move || {
+            files.into_iter()
+                .filter_map(|(chapter_id, path)| {
+                    compute_fingerprint(Path::new(&path)).ok().map(|fp| (chapter_id, path, fp))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .context("Fingerprint task failed")?;
+
+        for (chapter_id, path, fingerprint) in &fingerprints {
+            repo.upsert(&FileFingerprint::new(
+                audiobook_id.to_string(),
+                chapter_id.clone(),
+                path.clone(),
+                fingerprint.file_size as i64,
+                fingerprint.content_hash.clone(),
+            )).await?;
+        }
+
+        Ok(fingerprints.len())
+    }
+
+    /// Fingerprint audiobooks imported before fingerprinting existed, and chapters
+    /// that were created after their audiobook was imported
+    pub async fn backfill_fingerprints(&self) -> Result<usize> {
+        let audiobook_ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT a.id FROM audiobooks a
+            WHERE NOT EXISTS (SELECT 1 FROM file_fingerprints f WHERE f.audiobook_id = a.id)
+               OR EXISTS (
+                   SELECT 1 FROM chapters c
+                   WHERE c.audiobook_id = a.id
+                     AND NOT EXISTS (SELECT 1 FROM file_fingerprints f WHERE f.chapter_id = c.id)
+               )
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to find audiobooks without fingerprints")?;
+
+        let mut total = 0;
+        for audiobook_id in audiobook_ids {
+            match self.record_audiobook_fingerprints(&audiobook_id).await {
+                Ok(count) => total += count,
+                Err(e) => log::warn!("Failed to fingerprint audiobook {}: {}", audiobook_id, e),
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Find files that no longer exist at their recorded path and re-point them
+    /// to an identical file found under the library roots or `extra_dirs`
+    pub async fn auto_relocate_missing(&self, extra_dirs: &[PathBuf]) -> Result<RelocationReport> {
+        let repo = FingerprintRepository::new(self.pool);
+        let fingerprints = repo.find_all().await?;
+
+        let (missing, present): (Vec<FileFingerprint>, Vec<FileFingerprint>) = fingerprints
+            .into_iter()
+            .partition(|fp| !Path::new(&fp.file_path).exists());
+        // A copy found elsewhere must not take over from a file whose drive is only unplugged
+        let (offline, missing): (Vec<FileFingerprint>, Vec<FileFingerprint>) = missing
+            .into_iter()
+            .partition(|fp| volume::is_offline(Path::new(&fp.file_path)));
+
+        let mut report = RelocationReport {
+            missing_files: missing.len(),
+            volume_offline: offline.into_iter().map(|fp| fp.file_path).collect(),
+            ..Default::default()
+        };
+        if missing.is_empty() {
+            return Ok(report);
+        }
+
+        let mut roots: Vec<PathBuf> = extra_dirs.to_vec();
+        roots.extend(LibraryRootService::new(self.pool).roots().await?);
+
+        let wanted_sizes: HashSet<u64> = missing.iter().map(|fp| fp.file_size as u64).collect();
+        let tracked_paths: HashSet<PathBuf> = present.iter().map(|fp| PathBuf::from(&fp.file_path)).collect();
+
+        // Hash only same-size candidates that are not already some other book's file
+        let by_fingerprint = tokio::task::spawn_blocking(move || {
+            let mut by_fingerprint: HashMap<Fingerprint, Vec<PathBuf>> = HashMap::new();
+            for path in find_files_with_sizes(&roots, &wanted_sizes) {
+                if tracked_paths.contains(&path) {
+                    continue;
+                }
+                if let Ok(fingerprint) = compute_fingerprint(&path) {
+                    by_fingerprint.entry(fingerprint).or_default().push(path);
+                }
+            }
+            by_fingerprint
+        })
+        .await
+        .context("Relocation scan failed")?;
+
+        let mut relocated_books: HashSet<String> = HashSet::new();
+        for fp in missing {
+            let key = Fingerprint {
+                file_size: fp.file_size as u64,
+                content_hash: fp.content_hash.clone(),
+            };
+
+            match by_fingerprint.get(&key).map(|paths| paths.as_slice()) {
+                Some([new_path]) => {
+                    let new_path = new_path.to_string_lossy().to_string();
+                    self.apply_relocation(&fp, &new_path).await?;
+                    println!("🔗 RELOCATE: {} -> {}", fp.file_path, new_path);
+                    relocated_books.insert(fp.audiobook_id.clone());
+                    report.relocated.push(RelocatedFile {
+                        audiobook_id: fp.audiobook_id,
+                        old_path: fp.file_path,
+                        new_path,
+                    });
+                }
+                Some(_) => report.ambiguous.push(fp.file_path),
+                None => report.still_missing.push(fp.file_path),
+            }
+        }
+
+        for audiobook_id in relocated_books {
+            self.repair_book_folder(&audiobook_id).await?;
+        }
+
+        Ok(report)
+    }
+
+    async fn apply_relocation(&self, fp: &FileFingerprint, new_path: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        match &fp.chapter_id {
+            Some(chapter_id) => {
+                sqlx::query("UPDATE chapters SET file_path = ?, updated_at = ? WHERE id = ?")
+                    .bind(new_path)
+                    .bind(&now)
+                    .bind(chapter_id)
+                    .execute(self.pool)
+                    .await
+                    .context("Failed to update chapter file path")?;
+            }
+            None => {
+                sqlx::query("UPDATE audiobooks SET file_path = ?, updated_at = ? WHERE id = ?")
+                    .bind(new_path)
+                    .bind(&now)
+                    .bind(&fp.audiobook_id)
+                    .execute(self.pool)
+                    .await
+                    .context("Failed to update audiobook file path")?;
+            }
+        }
+
+        FingerprintRepository::new(self.pool).update_path(&fp.id, new_path).await
+    }
+
+    /// Multi-file books point at their folder; follow the chapters if the whole folder moved
+    async fn repair_book_folder(&self, audiobook_id: &str) -> Result<()> {
+        let Some(audiobook) = AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await? else {
+            return Ok(());
+        };
+        if Path::new(&audiobook.file_path).exists() {
+            return Ok(());
+        }
+
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+        let parents: HashSet<PathBuf> = chapters.iter()
+            .filter_map(|chapter| Path::new(&chapter.file_path).parent().map(|p| p.to_path_buf()))
+            .collect();
+
+        if parents.len() == 1 {
+            let folder = parents.into_iter().next().unwrap_or_default();
+            if folder.is_dir() {
+                sqlx::query("UPDATE audiobooks SET file_path = ?, updated_at = ? WHERE id = ?")
+                    .bind(folder.to_string_lossy().to_string())
+                    .bind(Utc::now().to_rfc3339())
+                    .bind(audiobook_id)
+                    .execute(self.pool)
+                    .await
+                    .context("Failed to update audiobook folder")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_relocation_scans_only_library_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("library.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let books = dir.path().join("books");
+        LibraryRootService::new(pool).add(&books.join("Dracula")).await.unwrap();
+        let inside = books.join("Dracula").join("01.mp3");
+        let outside = dir.path().join("elsewhere").join("Carmilla").join("01.mp3");
+        for (id, path, data) in [("a", &inside, "dracula"), ("b", &outside, "carmilla")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+            sqlx::query("INSERT INTO audiobooks (id, title, file_path, added_date) VALUES (?, ?, ?, '')")
+                .bind(id)
+                .bind(id)
+                .bind(path.to_string_lossy().to_string())
+                .execute(pool)
+                .await
+                .unwrap();
+            RelocationService::new(pool).record_audiobook_fingerprints(id).await.unwrap();
+        }
+
+        // Both renamed within their folder; only the one under a root is looked for
+        std::fs::rename(&inside, inside.with_file_name("chapter-01.mp3")).unwrap();
+        std::fs::rename(&outside, outside.with_file_name("chapter-01.mp3")).unwrap();
+
+        let report = RelocationService::new(pool).auto_relocate_missing(&[]).await.unwrap();
+        assert_eq!(report.missing_files, 2);
+        assert_eq!(report.relocated.len(), 1);
+        assert_eq!(report.relocated[0].new_path, inside.with_file_name("chapter-01.mp3").to_string_lossy());
+        assert_eq!(report.still_missing, vec![outside.to_string_lossy().to_string()]);
+    }
+}
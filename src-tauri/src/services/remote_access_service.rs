@@ -0,0 +1,322 @@
+// Who may use the remote-control API and what for. Each client gets its own
+// token with the scopes it needs: reading the player and library status,
+// controlling playback, or changing the library. LAN sharing checks every
+// request through `authorize`, which also writes the audit log, so nothing
+// reaches the network without a scoped, revocable token.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use subtle::ConstantTimeEq;
+use ts_rs::TS;
+
+/// Audit entries kept; older ones are dropped as new ones are written
+const AUDIT_KEPT: i64 = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteScope {
+    /// Read what is playing, the queue and the library
+    Status,
+    /// Play, pause, seek, skip, volume and speed
+    Transport,
+    /// Add, edit and delete books, collections and progress
+    Library,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RemoteToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<RemoteScope>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    /// Revoked tokens are kept so the audit log still names them
+    pub revoked_at: Option<String>,
+}
+
+/// A newly issued token; `secret` is shown this once and never stored
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct IssuedRemoteToken {
+    pub token: RemoteToken,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS, sqlx::FromRow)]
+#[ts(export)]
+pub struct RemoteAuditEntry {
+    #[ts(type = "number")]
+    pub id: i64,
+    pub token_id: Option<String>,
+    pub token_name: Option<String>,
+    pub scope: String,
+    /// What the client asked for, e.g. "transport.pause"
+    pub action: String,
+    pub allowed: bool,
+    /// Why a refused request was refused
+    pub reason: Option<String>,
+    pub at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct TokenRow {
+    id: String,
+    name: String,
+    scopes: String,
+    created_at: String,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+}
+
+impl From<TokenRow> for RemoteToken {
+    fn from(row: TokenRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            scopes: serde_json::from_str(&row.scopes).unwrap_or_default(),
+            created_at: row.created_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+pub struct RemoteAccessService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> RemoteAccessService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn issue(&self, name: &str, scopes: Vec<RemoteScope>) -> Result<IssuedRemoteToken> {
+        let name = name.trim();
+        anyhow::ensure!(!name.is_empty(), "Give the token a name");
+        let scopes = clean_scopes(scopes)?;
+
+        let secret = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let token = RemoteToken {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            scopes,
+            created_at: Utc::now().to_rfc3339(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        sqlx::query("INSERT INTO remote_tokens (id, name, token_hash, scopes, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&token.id)
+            .bind(&token.name)
+            .bind(hash_secret(&secret))
+            .bind(serde_json::to_string(&token.scopes)?)
+            .bind(&token.created_at)
+            .execute(self.pool)
+            .await
+            .context("Failed to save remote token")?;
+        Ok(IssuedRemoteToken { token, secret })
+    }
+
+    /// Every token, revoked ones included, newest first
+    pub async fn list(&self) -> Result<Vec<RemoteToken>> {
+        let rows = sqlx::query_as::<_, TokenRow>(
+            "SELECT id, name, scopes, created_at, last_used_at, revoked_at FROM remote_tokens ORDER BY created_at DESC"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load remote tokens")?;
+        Ok(rows.into_iter().map(RemoteToken::from).collect())
+    }
+
+    async fn find(&self, id: &str) -> Result<RemoteToken> {
+        let row = sqlx::query_as::<_, TokenRow>(
+            "SELECT id, name, scopes, created_at, last_used_at, revoked_at FROM remote_tokens WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to load remote token")?
+        .with_context(|| format!("Remote token not found: {}", id))?;
+        Ok(row.into())
+    }
+
+    /// Change what a token may do; takes effect on its next request
+    pub async fn set_scopes(&self, id: &str, scopes: Vec<RemoteScope>) -> Result<RemoteToken> {
+        let scopes = clean_scopes(scopes)?;
+        let token = self.find(id).await?;
+        anyhow::ensure!(token.revoked_at.is_none(), "The token '{}' has been revoked", token.name);
+        sqlx::query("UPDATE remote_tokens SET scopes = ? WHERE id = ?")
+            .bind(serde_json::to_string(&scopes)?)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to update remote token")?;
+        Ok(RemoteToken { scopes, ..token })
+    }
+
+    pub async fn revoke(&self, id: &str) -> Result<RemoteToken> {
+        let token = self.find(id).await?;
+        if token.revoked_at.is_some() {
+            return Ok(token);
+        }
+        let revoked_at = Utc::now().to_rfc3339();
+        sqlx::query("UPDATE remote_tokens SET revoked_at = ? WHERE id = ?")
+            .bind(&revoked_at)
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to revoke remote token")?;
+        Ok(RemoteToken { revoked_at: Some(revoked_at), ..token })
+    }
+
+    /// Check that `secret` belongs to a live token granted `scope`, and record
+    /// the request in the audit log either way. `action` names what was asked
+    /// for, for the log.
+    pub async fn authorize(&self, secret: &str, scope: RemoteScope, action: &str) -> Result<RemoteToken> {
+        let hashes = sqlx::query_as::<_, (String, String)>("SELECT id, token_hash FROM remote_tokens")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to look up remote token")?;
+        // Every hash is compared in full, so timing says nothing about which
+        // one came close
+        let hash = hash_secret(secret);
+        let mut matched = None;
+        for (id, stored) in hashes {
+            if bool::from(stored.as_bytes().ct_eq(hash.as_bytes())) {
+                matched = Some(id);
+            }
+        }
+        let token = match matched {
+            Some(id) => Some(self.find(&id).await?),
+            None => None,
+        };
+
+        let refusal = match &token {
+            None => Some("Unknown token"),
+            Some(token) if token.revoked_at.is_some() => Some("Token revoked"),
+            Some(token) if !token.scopes.contains(&scope) => Some("Scope not granted"),
+            Some(_) => None,
+        };
+        self.record(token.as_ref(), scope, action, refusal).await?;
+
+        match (token, refusal) {
+            (Some(token), None) => {
+                let now = Utc::now().to_rfc3339();
+                sqlx::query("UPDATE remote_tokens SET last_used_at = ? WHERE id = ?")
+                    .bind(&now)
+                    .bind(&token.id)
+                    .execute(self.pool)
+                    .await
+                    .context("Failed to update remote token")?;
+                Ok(RemoteToken { last_used_at: Some(now), ..token })
+            }
+            (_, reason) => Err(anyhow::anyhow!("Remote request refused: {}", reason.unwrap_or_default())),
+        }
+    }
+
+    /// The newest audit entries first
+    pub async fn audit_log(&self, limit: i64) -> Result<Vec<RemoteAuditEntry>> {
+        sqlx::query_as::<_, RemoteAuditEntry>("SELECT * FROM remote_audit_log ORDER BY id DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load the remote audit log")
+    }
+
+    async fn record(&self, token: Option<&RemoteToken>, scope: RemoteScope, action: &str, refusal: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO remote_audit_log (token_id, token_name, scope, action, allowed, reason, at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(token.map(|token| token.id.as_str()))
+        .bind(token.map(|token| token.name.as_str()))
+        .bind(scope.as_str())
+        .bind(action)
+        .bind(refusal.is_none())
+        .bind(refusal)
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to write the remote audit log")?;
+
+        sqlx::query("DELETE FROM remote_audit_log WHERE id <= (SELECT MAX(id) FROM remote_audit_log) - ?")
+            .bind(AUDIT_KEPT)
+            .execute(self.pool)
+            .await
+            .context("Failed to prune the remote audit log")?;
+        Ok(())
+    }
+}
+
+impl RemoteScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RemoteScope::Status => "status",
+            RemoteScope::Transport => "transport",
+            RemoteScope::Library => "library",
+        }
+    }
+}
+
+fn clean_scopes(mut scopes: Vec<RemoteScope>) -> Result<Vec<RemoteScope>> {
+    scopes.sort();
+    scopes.dedup();
+    anyhow::ensure!(!scopes.is_empty(), "A token needs at least one scope");
+    Ok(scopes)
+}
+
+/// Tokens are long and random, so an unsalted hash is enough to keep them
+/// out of the database
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.trim().as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_tokens_are_scoped_revocable_and_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("remote.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = RemoteAccessService::new(pool);
+
+        assert!(service.issue("Phone", Vec::new()).await.is_err());
+        let issued = service.issue(" Phone ", vec![RemoteScope::Transport, RemoteScope::Status, RemoteScope::Status]).await.unwrap();
+        assert_eq!(issued.token.name, "Phone");
+        assert_eq!(issued.token.scopes, vec![RemoteScope::Status, RemoteScope::Transport]);
+
+        let token = service.authorize(&issued.secret, RemoteScope::Transport, "transport.pause").await.unwrap();
+        assert!(token.last_used_at.is_some());
+        assert!(service.authorize(&issued.secret, RemoteScope::Library, "library.delete").await.is_err());
+        assert!(service.authorize("guess", RemoteScope::Status, "status.now_playing").await.is_err());
+
+        service.set_scopes(&issued.token.id, vec![RemoteScope::Library]).await.unwrap();
+        assert!(service.authorize(&issued.secret, RemoteScope::Library, "library.delete").await.is_ok());
+        service.revoke(&issued.token.id).await.unwrap();
+        assert!(service.authorize(&issued.secret, RemoteScope::Library, "library.delete").await.is_err());
+        assert!(service.list().await.unwrap()[0].revoked_at.is_some());
+
+        let log = service.audit_log(10).await.unwrap();
+        let outcomes: Vec<(bool, Option<&str>)> = log.iter().map(|entry| (entry.allowed, entry.reason.as_deref())).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (false, Some("Token revoked")),
+                (true, None),
+                (false, Some("Unknown token")),
+                (false, Some("Scope not granted")),
+                (true, None),
+            ]
+        );
+        assert_eq!(log[2].token_id, None);
+        assert_eq!(log[4].token_name.as_deref(), Some("Phone"));
+        assert_eq!(log[4].action, "transport.pause");
+    }
+}
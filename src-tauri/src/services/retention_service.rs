@@ -0,0 +1,254 @@
+// How long listening data is kept. Raw sessions and plays past the retention
+// window are folded into per-day summaries (which long-range stats read), and
+// stale recommendations and saved playback states are deleted.
+
+use crate::database::models::DailyListening;
+use crate::database::repository::{ChangeLogRepository, PreferencesRepository};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+pub const PREF_RETENTION: &str = "retention.settings";
+
+/// Retention windows; 0 keeps that data forever
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Listening sessions and plays older than this become daily summaries
+    pub raw_history_months: u32,
+    /// Dismissed or expired recommendations older than this are deleted
+    pub recommendation_days: u32,
+    /// Saved playback states untouched for this long are deleted
+    pub playback_state_days: u32,
+    /// Change journal entries older than this are deleted; readers that fell
+    /// further behind start over from a full read
+    pub change_log_days: u32,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            raw_history_months: 12,
+            recommendation_days: 30,
+            playback_state_days: 90,
+            change_log_days: 180,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RetentionReport {
+    #[ts(type = "number")]
+    pub sessions_summarized: u64,
+    #[ts(type = "number")]
+    pub plays_summarized: u64,
+    #[ts(type = "number")]
+    pub recommendations_deleted: u64,
+    #[ts(type = "number")]
+    pub playback_states_deleted: u64,
+    #[ts(type = "number")]
+    pub changes_pruned: u64,
+}
+
+pub struct RetentionService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> RetentionService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn load_settings(&self) -> RetentionSettings {
+        let stored = PreferencesRepository::new(self.pool).get(PREF_RETENTION).await.ok().flatten();
+        match stored.map(|json| serde_json::from_str::<RetentionSettings>(&json)) {
+            Some(Ok(settings)) => settings,
+            Some(Err(e)) => {
+                log::warn!("Ignoring invalid retention settings: {}", e);
+                RetentionSettings::default()
+            }
+            None => RetentionSettings::default(),
+        }
+    }
+
+    pub async fn save_settings(&self, settings: &RetentionSettings) -> Result<()> {
+        let json = serde_json::to_string(settings).context("Failed to serialize retention settings")?;
+        PreferencesRepository::new(self.pool).set(PREF_RETENTION, &json).await
+    }
+
+    /// Apply the saved retention settings as of `now`
+    pub async fn enforce(&self, now: DateTime<Utc>) -> Result<RetentionReport> {
+        let settings = self.load_settings().await;
+        let mut report = RetentionReport::default();
+
+        if let Some(cutoff_day) = history_cutoff(now, settings.raw_history_months) {
+            let (sessions, plays) = self.summarize_before(&cutoff_day).await?;
+            report.sessions_summarized = sessions;
+            report.plays_summarized = plays;
+        }
+
+        if settings.recommendation_days > 0 {
+            let cutoff = (now - Duration::days(settings.recommendation_days as i64)).to_rfc3339();
+            report.recommendations_deleted = sqlx::query(
+                "DELETE FROM recommendations WHERE (is_dismissed OR expires_at < ?) AND generated_at < ?"
+            )
+            .bind(now.to_rfc3339())
+            .bind(&cutoff)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete old recommendations")?
+            .rows_affected();
+        }
+
+        if settings.playback_state_days > 0 {
+            // Playback states are stamped with SQLite's datetime('now') format
+            let cutoff = (now - Duration::days(settings.playback_state_days as i64)).format("%Y-%m-%d %H:%M:%S").to_string();
+            report.playback_states_deleted = sqlx::query("DELETE FROM playback_states WHERE updated_at < ?")
+                .bind(&cutoff)
+                .execute(self.pool)
+                .await
+                .context("Failed to delete old playback states")?
+                .rows_affected();
+        }
+
+        if settings.change_log_days > 0 {
+            // Same format as the journal's strftime('%Y-%m-%dT%H:%M:%fZ') stamps
+            let cutoff = (now - Duration::days(settings.change_log_days as i64)).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+            report.changes_pruned = ChangeLogRepository::new(self.pool).prune_before(&cutoff).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Fold sessions and plays from before `cutoff_day` into the daily summaries
+    /// and delete them. Whole days move at once, so a day is never split
+    /// between raw rows and its summary.
+    async fn summarize_before(&self, cutoff_day: &str) -> Result<(u64, u64)> {
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO listening_daily_summaries (day, audiobook_id, session_count, session_seconds, max_completion)
+            SELECT substr(listened_at, 1, 10), audiobook_id, COUNT(*), SUM(session_duration), MAX(completion_percentage)
+            FROM listening_history
+            WHERE listened_at < ?
+            GROUP BY substr(listened_at, 1, 10), audiobook_id
+            ON CONFLICT(day, audiobook_id) DO UPDATE SET
+                session_count = session_count + excluded.session_count,
+                session_seconds = session_seconds + excluded.session_seconds,
+                max_completion = MAX(max_completion, excluded.max_completion)
+            "#
+        )
+        .bind(cutoff_day)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to summarize listening history")?;
+
+        let sessions = sqlx::query("DELETE FROM listening_history WHERE listened_at < ?")
+            .bind(cutoff_day)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete summarized listening history")?
+            .rows_affected();
+
+        sqlx::query(
+            r#"
+            INSERT INTO listening_daily_summaries (day, audiobook_id, play_count, played_seconds)
+            SELECT substr(started_at, 1, 10), audiobook_id, COUNT(*), SUM(listened_seconds)
+            FROM plays
+            WHERE started_at < ?
+            GROUP BY substr(started_at, 1, 10), audiobook_id
+            ON CONFLICT(day, audiobook_id) DO UPDATE SET
+                play_count = play_count + excluded.play_count,
+                played_seconds = played_seconds + excluded.played_seconds
+            "#
+        )
+        .bind(cutoff_day)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to summarize plays")?;
+
+        let plays = sqlx::query("DELETE FROM plays WHERE started_at < ?")
+            .bind(cutoff_day)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete summarized plays")?
+            .rows_affected();
+
+        tx.commit().await.context("Failed to commit history summaries")?;
+
+        if sessions > 0 || plays > 0 {
+            log::info!("Summarized {} listening sessions and {} plays before {}", sessions, plays, cutoff_day);
+        }
+        Ok((sessions, plays))
+    }
+
+    /// Listening per day between two YYYY-MM-DD dates (inclusive), from the
+    /// summaries for old days and the raw history for recent ones
+    pub async fn daily_listening(&self, from_day: &str, to_day: &str) -> Result<Vec<DailyListening>> {
+        let days = sqlx::query_as::<_, DailyListening>(
+            r#"
+            SELECT day,
+                   SUM(session_count) as session_count,
+                   SUM(session_seconds) as session_seconds,
+                   SUM(play_count) as play_count,
+                   SUM(played_seconds) as played_seconds
+            FROM (
+                SELECT day, session_count, session_seconds, play_count, played_seconds
+                FROM listening_daily_summaries
+                WHERE day BETWEEN ?1 AND ?2
+                UNION ALL
+                SELECT substr(listened_at, 1, 10), 1, session_duration, 0, 0
+                FROM listening_history
+                WHERE substr(listened_at, 1, 10) BETWEEN ?1 AND ?2
+                UNION ALL
+                SELECT substr(started_at, 1, 10), 0, 0, 1, listened_seconds
+                FROM plays
+                WHERE substr(started_at, 1, 10) BETWEEN ?1 AND ?2
+            )
+            GROUP BY day
+            ORDER BY day
+            "#
+        )
+        .bind(from_day)
+        .bind(to_day)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load daily listening")?;
+
+        Ok(days)
+    }
+}
+
+/// First day whose history is kept raw, or None when history is kept forever
+fn history_cutoff(now: DateTime<Utc>, months: u32) -> Option<String> {
+    if months == 0 {
+        return None;
+    }
+    let day = now.date_naive().checked_sub_months(Months::new(months))?;
+    Some(day.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_history_cutoff() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 31, 18, 30, 0).unwrap();
+        assert_eq!(history_cutoff(now, 12).as_deref(), Some("2024-03-31"));
+        // Clamped to the end of a shorter month
+        assert_eq!(history_cutoff(now, 1).as_deref(), Some("2025-02-28"));
+        assert_eq!(history_cutoff(now, 0), None);
+
+        // A full timestamp on the cutoff day sorts after it, so that day stays raw
+        let cutoff = history_cutoff(now, 12).unwrap();
+        assert!("2024-03-30T23:59:59+00:00" < cutoff.as_str());
+        assert!("2024-03-31T00:00:00+00:00" > cutoff.as_str());
+    }
+}
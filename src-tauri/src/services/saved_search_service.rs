@@ -0,0 +1,343 @@
+// Recent searches and named saved searches. History keeps the latest time each
+// query was run; typing a query out keystroke by keystroke leaves one entry,
+// the finished query. Saved searches store the filters and run them again on
+// demand; pinned ones are shown next to the collections with a live count.
+
+use crate::database::models::{Audiobook, SearchFilters};
+use crate::database::repository::AudiobookRepository;
+use crate::services::privacy;
+use crate::validation::Validate;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+/// Oldest entries beyond this many are dropped
+pub const MAX_HISTORY_ENTRIES: i64 = 50;
+pub const DEFAULT_HISTORY_LIMIT: u32 = 10;
+/// A query run this soon after one it extends or shortens replaces it
+const TYPING_WINDOW_SECONDS: i64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow, TS)]
+#[ts(export)]
+pub struct SearchHistoryEntry {
+    pub id: String,
+    pub query: String,
+    pub searched_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SavedSearch {
+    pub id: String,
+    pub name: String,
+    pub filters: SearchFilters,
+    pub pinned: bool,
+    /// Books the search finds now; filled in for pinned searches
+    pub book_count: Option<u32>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct SavedSearchRow {
+    id: String,
+    name: String,
+    filters: String,
+    pinned: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+impl SavedSearchRow {
+    fn into_saved_search(self) -> Option<SavedSearch> {
+        Some(SavedSearch {
+            id: self.id,
+            name: self.name,
+            filters: serde_json::from_str(&self.filters).ok()?,
+            pinned: self.pinned,
+            book_count: None,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+pub struct SavedSearchService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SavedSearchService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Remember a query that was run; nothing is kept while incognito
+    pub async fn record_search(&self, query: &str) -> Result<()> {
+        if privacy::is_incognito() {
+            return Ok(());
+        }
+        self.record_search_at(query, Utc::now()).await
+    }
+
+    async fn record_search_at(&self, query: &str, now: DateTime<Utc>) -> Result<()> {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        let normalized = query.to_lowercase();
+        if normalized.is_empty() {
+            return Ok(());
+        }
+
+        // Still typing: the last query is a step on the way to this one, or this one a step back
+        if let Some(last) = self.recent(1).await?.into_iter().next() {
+            let last_normalized = last.query.to_lowercase();
+            let recent = DateTime::parse_from_rfc3339(&last.searched_at)
+                .is_ok_and(|at| (now - at.with_timezone(&Utc)).num_seconds() < TYPING_WINDOW_SECONDS);
+            if recent && last_normalized != normalized
+                && (normalized.starts_with(&last_normalized) || last_normalized.starts_with(&normalized))
+            {
+                sqlx::query("DELETE FROM search_history WHERE id = ?")
+                    .bind(&last.id)
+                    .execute(self.pool)
+                    .await
+                    .context("Failed to replace search history entry")?;
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO search_history (id, query, normalized_query, searched_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT (normalized_query) DO UPDATE SET query = excluded.query, searched_at = excluded.searched_at"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&query)
+        .bind(&normalized)
+        .bind(now.to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to record search")?;
+
+        sqlx::query("DELETE FROM search_history WHERE id NOT IN (SELECT id FROM search_history ORDER BY searched_at DESC LIMIT ?)")
+            .bind(MAX_HISTORY_ENTRIES)
+            .execute(self.pool)
+            .await
+            .context("Failed to trim search history")?;
+        Ok(())
+    }
+
+    /// Newest first
+    pub async fn recent(&self, limit: u32) -> Result<Vec<SearchHistoryEntry>> {
+        sqlx::query_as::<_, SearchHistoryEntry>(
+            "SELECT id, query, searched_at FROM search_history ORDER BY searched_at DESC LIMIT ?"
+        )
+        .bind(limit as i64)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load search history")
+    }
+
+    /// Forget one entry, or all of them when `id` is None
+    pub async fn clear_history(&self, id: Option<&str>) -> Result<u64> {
+        let deleted = sqlx::query("DELETE FROM search_history WHERE ?1 IS NULL OR id = ?1")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to clear search history")?
+            .rows_affected();
+        Ok(deleted)
+    }
+
+    /// Save filters under a name; saving under a name already in use replaces
+    /// that search's filters
+    pub async fn save(&self, name: &str, filters: &SearchFilters, pinned: Option<bool>) -> Result<SavedSearch> {
+        let name = name.trim();
+        let normalized = name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if normalized.is_empty() {
+            return Err(anyhow!("A saved search needs a name"));
+        }
+        filters.validate()?;
+
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO saved_searches (id, name, normalized_name, filters, pinned, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, COALESCE(?5, FALSE), ?6, ?6)
+             ON CONFLICT (normalized_name) DO UPDATE SET
+                name = excluded.name,
+                filters = excluded.filters,
+                pinned = COALESCE(?5, saved_searches.pinned),
+                updated_at = excluded.updated_at"
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(&normalized)
+        .bind(serde_json::to_string(filters)?)
+        .bind(pinned)
+        .bind(&now)
+        .execute(self.pool)
+        .await
+        .context("Failed to save search")?;
+
+        let row = sqlx::query_as::<_, SavedSearchRow>(
+            "SELECT id, name, filters, pinned, created_at, updated_at FROM saved_searches WHERE normalized_name = ?"
+        )
+        .bind(&normalized)
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to load saved search")?;
+        let id = row.id.clone();
+        self.with_count(row.into_saved_search().with_context(|| format!("Invalid saved search: {}", id))?).await
+    }
+
+    pub async fn find(&self, id: &str) -> Result<Option<SavedSearch>> {
+        let row = sqlx::query_as::<_, SavedSearchRow>(
+            "SELECT id, name, filters, pinned, created_at, updated_at FROM saved_searches WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to load saved search")?;
+        Ok(row.and_then(SavedSearchRow::into_saved_search))
+    }
+
+    /// Pinned searches first, each with its current count, then the rest by name
+    pub async fn list(&self) -> Result<Vec<SavedSearch>> {
+        let rows = sqlx::query_as::<_, SavedSearchRow>(
+            "SELECT id, name, filters, pinned, created_at, updated_at FROM saved_searches ORDER BY pinned DESC, name COLLATE NOCASE"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load saved searches")?;
+
+        let mut searches = Vec::new();
+        for search in rows.into_iter().filter_map(SavedSearchRow::into_saved_search) {
+            searches.push(self.with_count(search).await?);
+        }
+        Ok(searches)
+    }
+
+    pub async fn run(&self, id: &str) -> Result<Vec<Audiobook>> {
+        let search = self.find(id).await?.with_context(|| format!("Saved search not found: {}", id))?;
+        AudiobookRepository::new(self.pool).search_with_filters(search.filters).await
+    }
+
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> Result<SavedSearch> {
+        let updated = sqlx::query("UPDATE saved_searches SET pinned = ?, updated_at = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to pin saved search")?
+            .rows_affected();
+        if updated == 0 {
+            return Err(anyhow!("Saved search not found: {}", id));
+        }
+        let search = self.find(id).await?.with_context(|| format!("Saved search not found: {}", id))?;
+        self.with_count(search).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let deleted = sqlx::query("DELETE FROM saved_searches WHERE id = ?")
+            .bind(id)
+            .execute(self.pool)
+            .await
+            .context("Failed to delete saved search")?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(anyhow!("Saved search not found: {}", id));
+        }
+        Ok(())
+    }
+
+    async fn with_count(&self, mut search: SavedSearch) -> Result<SavedSearch> {
+        if search.pinned {
+            let books = AudiobookRepository::new(self.pool).search_with_filters(search.filters.clone()).await?;
+            search.book_count = Some(books.len() as u32);
+        }
+        Ok(search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::CreateAudiobookDto;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_history_collapses_typing_and_keeps_latest_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("searches.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let service = SavedSearchService::new(db.get_pool().unwrap());
+
+        let at = |seconds: i64| DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap();
+        for (seconds, query) in [(0, "pri"), (1, "pride"), (2, "pride and"), (3, "Pride  and Prejudice")] {
+            service.record_search_at(query, at(seconds)).await.unwrap();
+        }
+        service.record_search_at("emma", at(100)).await.unwrap();
+        service.record_search_at("pride and prejudice", at(200)).await.unwrap();
+        service.record_search_at("   ", at(300)).await.unwrap();
+
+        let queries: Vec<String> = service.recent(10).await.unwrap().into_iter().map(|entry| entry.query).collect();
+        assert_eq!(queries, vec!["pride and prejudice", "emma"]);
+
+        // Long after the last search, a longer query is a search of its own
+        service.record_search_at("emma woodhouse", at(1000)).await.unwrap();
+        assert_eq!(service.recent(10).await.unwrap().len(), 3);
+
+        assert_eq!(service.clear_history(None).await.unwrap(), 3);
+        assert!(service.recent(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_saved_searches_run_and_pin() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("saved.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let repository = AudiobookRepository::new(pool);
+        for (title, genre) in [("Emma", "Romance"), ("Persuasion", "Romance"), ("Dracula", "Horror")] {
+            repository.create(CreateAudiobookDto {
+                title: title.to_string(),
+                file_path: format!("/books/{}", title),
+                author: None,
+                narrator: None,
+                description: None,
+                genre: Some(genre.to_string()),
+                duration: None,
+                cover_image_path: None,
+                source_type: None,
+                source_id: None,
+            }).await.unwrap();
+        }
+        let service = SavedSearchService::new(pool);
+
+        let romance = SearchFilters { genre: Some("Romance".to_string()), ..Default::default() };
+        let saved = service.save("Romance", &romance, None).await.unwrap();
+        assert!(!saved.pinned);
+        assert_eq!(saved.book_count, None);
+        assert!(service.save("  ", &romance, None).await.is_err());
+
+        let mut titles: Vec<String> = service.run(&saved.id).await.unwrap().into_iter().map(|book| book.title).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Emma", "Persuasion"]);
+
+        let pinned = service.set_pinned(&saved.id, true).await.unwrap();
+        assert_eq!(pinned.book_count, Some(2));
+
+        // Saving under the same name replaces the filters and keeps the pin
+        let horror = SearchFilters { genre: Some("Horror".to_string()), ..Default::default() };
+        let replaced = service.save("romance ", &horror, None).await.unwrap();
+        assert_eq!(replaced.id, saved.id);
+        assert!(replaced.pinned);
+        assert_eq!(replaced.book_count, Some(1));
+
+        service.save("Everything", &SearchFilters::default(), None).await.unwrap();
+        let names: Vec<String> = service.list().await.unwrap().into_iter().map(|search| search.name).collect();
+        assert_eq!(names, vec!["romance", "Everything"], "pinned first");
+
+        service.delete(&saved.id).await.unwrap();
+        assert!(service.run(&saved.id).await.is_err());
+        assert!(service.delete(&saved.id).await.is_err());
+    }
+}
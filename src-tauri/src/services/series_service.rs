@@ -0,0 +1,175 @@
+// Series membership: which series a book is part of and its number in it, so
+// finishing one book can lead on to the next
+
+use crate::database::models::Audiobook;
+use crate::database::repository::AudiobookRepository;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SeriesEntry {
+    pub audiobook_id: String,
+    pub name: String,
+    /// Number within the series; None when unknown
+    pub position: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SeriesBookRow {
+    audiobook_id: String,
+    position: Option<f64>,
+    is_completed: bool,
+}
+
+pub struct SeriesService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SeriesService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, audiobook_id: &str) -> Result<Option<SeriesEntry>> {
+        let row = sqlx::query_as::<_, (String, String, Option<f64>)>(
+            "SELECT audiobook_id, name, position FROM audiobook_series WHERE audiobook_id = ?",
+        )
+        .bind(audiobook_id)
+        .fetch_optional(self.pool)
+        .await
+        .context("Failed to get series")?;
+        Ok(row.map(|(audiobook_id, name, position)| SeriesEntry { audiobook_id, name, position }))
+    }
+
+    /// Put a book in a series, or take it out of its series with an empty name
+    pub async fn set(&self, audiobook_id: &str, name: Option<&str>, position: Option<f64>) -> Result<Option<SeriesEntry>> {
+        let name = name.map(|name| name.split_whitespace().collect::<Vec<_>>().join(" ")).filter(|name| !name.is_empty());
+        let Some(name) = name else {
+            sqlx::query("DELETE FROM audiobook_series WHERE audiobook_id = ?")
+                .bind(audiobook_id)
+                .execute(self.pool)
+                .await
+                .context("Failed to remove book from series")?;
+            return Ok(None);
+        };
+        AudiobookRepository::new(self.pool).find_by_id(audiobook_id).await?
+            .with_context(|| format!("Audiobook not found: {}", audiobook_id))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO audiobook_series (audiobook_id, name, normalized_name, position, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (audiobook_id) DO UPDATE SET
+                name = excluded.name,
+                normalized_name = excluded.normalized_name,
+                position = excluded.position,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(audiobook_id)
+        .bind(&name)
+        .bind(normalize_series_name(&name))
+        .bind(position.filter(|position| position.is_finite()))
+        .bind(Utc::now().to_rfc3339())
+        .execute(self.pool)
+        .await
+        .context("Failed to save series")?;
+        self.get(audiobook_id).await
+    }
+
+    /// The first unfinished book numbered after this one in its series
+    pub async fn next_in_series(&self, audiobook_id: &str) -> Result<Option<Audiobook>> {
+        let books = sqlx::query_as::<_, SeriesBookRow>(
+            r#"
+            SELECT s.audiobook_id, s.position, COALESCE(p.is_completed, FALSE) as is_completed
+            FROM audiobook_series s
+            JOIN audiobook_series current ON current.normalized_name = s.normalized_name
+            LEFT JOIN playback_progress p ON p.audiobook_id = s.audiobook_id
+            WHERE current.audiobook_id = ?
+            "#,
+        )
+        .bind(audiobook_id)
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to get series books")?;
+
+        let Some(next_id) = next_book(audiobook_id, books) else {
+            return Ok(None);
+        };
+        AudiobookRepository::new(self.pool).find_by_id(&next_id).await
+    }
+}
+
+/// Books without a number cannot be placed, so only numbered books follow on
+fn next_book(audiobook_id: &str, books: Vec<SeriesBookRow>) -> Option<String> {
+    let current = books.iter().find(|book| book.audiobook_id == audiobook_id)?.position?;
+    books
+        .into_iter()
+        .filter(|book| !book.is_completed)
+        .filter_map(|book| book.position.filter(|position| *position > current).map(|position| (position, book.audiobook_id)))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, audiobook_id)| audiobook_id)
+}
+
+pub fn normalize_series_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, UpdatePlaybackProgressDto};
+    use crate::database::repository::PlaybackProgressRepository;
+    use crate::database::DatabaseManager;
+
+    async fn add_book(pool: &SqlitePool, title: &str) -> String {
+        AudiobookRepository::new(pool).create(CreateAudiobookDto {
+            title: title.to_string(),
+            file_path: format!("/books/{}", title),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap().id
+    }
+
+    #[tokio::test]
+    async fn test_next_in_series_skips_finished_books() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("series.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+        let service = SeriesService::new(pool);
+
+        let first = add_book(pool, "Book One").await;
+        let second = add_book(pool, "Book Two").await;
+        let novella = add_book(pool, "Book Two and a Half").await;
+        let third = add_book(pool, "Book Three").await;
+        service.set(&first, Some("The  Saga"), Some(1.0)).await.unwrap();
+        service.set(&third, Some("the saga"), Some(3.0)).await.unwrap();
+        service.set(&novella, Some("The Saga"), Some(2.5)).await.unwrap();
+        let entry = service.set(&second, Some("The Saga "), Some(2.0)).await.unwrap().unwrap();
+        assert_eq!(entry.name, "The Saga");
+
+        assert_eq!(service.next_in_series(&first).await.unwrap().unwrap().id, second);
+
+        PlaybackProgressRepository::new(pool)
+            .create_or_update(&second, UpdatePlaybackProgressDto { position: 0, chapter_index: None, playback_speed: None, is_completed: Some(true) })
+            .await
+            .unwrap();
+        assert_eq!(service.next_in_series(&first).await.unwrap().unwrap().id, novella, "finished books are skipped");
+        assert!(service.next_in_series(&third).await.unwrap().is_none(), "last in the series");
+
+        assert!(service.set(&novella, Some("  "), None).await.unwrap().is_none());
+        assert!(service.get(&novella).await.unwrap().is_none());
+        assert_eq!(service.next_in_series(&first).await.unwrap().unwrap().id, third);
+    }
+}
@@ -0,0 +1,153 @@
+// How the library and each collection are sorted. The choice is stored here
+// rather than in the view, so every window and the LAN clients list books in
+// the same order.
+
+use crate::database::models::{Audiobook, LibrarySortField, SortPreference, SortScope};
+use crate::database::repository::{AudiobookRepository, CollectionRepository, PreferencesRepository};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+pub const PREF_LIBRARY_SORT: &str = "library.sort";
+/// Collection sorts are stored under this prefix followed by the collection id
+pub const PREF_COLLECTION_SORT_PREFIX: &str = "library.sort.collection.";
+
+pub struct SortPreferenceService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SortPreferenceService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The stored sort for `scope`, or the list's default
+    pub async fn get(&self, scope: &SortScope) -> Result<SortPreference> {
+        let default = match scope {
+            SortScope::Library => SortPreference::LIBRARY_DEFAULT,
+            SortScope::Collection { .. } => SortPreference::COLLECTION_DEFAULT,
+        };
+        let Some(json) = PreferencesRepository::new(self.pool).get(&key(scope)).await? else {
+            return Ok(default);
+        };
+        match serde_json::from_str(&json) {
+            Ok(sort) => Ok(sort),
+            Err(e) => {
+                log::warn!("Ignoring invalid sort preference in {}: {}", key(scope), e);
+                Ok(default)
+            }
+        }
+    }
+
+    pub async fn set(&self, scope: &SortScope, sort: SortPreference) -> Result<SortPreference> {
+        match scope {
+            SortScope::Library => {
+                anyhow::ensure!(sort.field != LibrarySortField::Manual, "Manual order is only available in collections");
+            }
+            SortScope::Collection { collection_id } => {
+                CollectionRepository::new(self.pool)
+                    .find_by_id(collection_id)
+                    .await?
+                    .with_context(|| format!("Collection not found: {}", collection_id))?;
+            }
+        }
+        let json = serde_json::to_string(&sort).context("Failed to serialize sort preference")?;
+        PreferencesRepository::new(self.pool).set(&key(scope), &json).await?;
+        Ok(sort)
+    }
+
+    /// Drop the stored sort of a deleted collection
+    pub async fn forget_collection(&self, collection_id: &str) -> Result<()> {
+        let scope = SortScope::Collection { collection_id: collection_id.to_string() };
+        PreferencesRepository::new(self.pool).delete(&key(&scope)).await
+    }
+
+    /// The whole library in its stored order
+    pub async fn library(&self) -> Result<Vec<Audiobook>> {
+        let sort = self.get(&SortScope::Library).await?;
+        AudiobookRepository::new(self.pool).find_all_sorted(sort).await
+    }
+
+    /// A collection's books in its stored order
+    pub async fn collection(&self, collection_id: &str) -> Result<Vec<Audiobook>> {
+        let sort = self.get(&SortScope::Collection { collection_id: collection_id.to_string() }).await?;
+        CollectionRepository::new(self.pool).get_collection_audiobooks_sorted(collection_id, sort).await
+    }
+}
+
+fn key(scope: &SortScope) -> String {
+    match scope {
+        SortScope::Library => PREF_LIBRARY_SORT.to_string(),
+        SortScope::Collection { collection_id } => format!("{}{}", PREF_COLLECTION_SORT_PREFIX, collection_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, CreateCollectionDto, UpdatePlaybackProgressDto};
+    use crate::database::repository::PlaybackProgressRepository;
+    use crate::database::DatabaseManager;
+    use crate::services::SeriesService;
+
+    #[tokio::test]
+    async fn test_sorts_are_stored_per_list_and_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("sort.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let collections = CollectionRepository::new(pool);
+        let collection = collections
+            .create(CreateCollectionDto { name: "Discworld".to_string(), description: None, color: None, parent_collection_id: None })
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for (title, position) in [("Mort", Some(4.0)), ("Guards! Guards!", Some(8.0)), ("Equal Rites", Some(3.0)), ("Dune", None)] {
+            let book = AudiobookRepository::new(pool)
+                .create(CreateAudiobookDto {
+                    title: title.to_string(),
+                    file_path: format!("/books/{}", title),
+                    author: None,
+                    narrator: None,
+                    description: None,
+                    genre: None,
+                    duration: Some(60),
+                    cover_image_path: None,
+                    source_type: None,
+                    source_id: None,
+                })
+                .await
+                .unwrap();
+            if position.is_some() {
+                SeriesService::new(pool).set(&book.id, Some("Discworld"), position).await.unwrap();
+                collections.add_audiobook_to_collection(&collection.id, &book.id).await.unwrap();
+            }
+            ids.push(book.id);
+        }
+        let progress = UpdatePlaybackProgressDto { position: 10, chapter_index: None, playback_speed: None, is_completed: None };
+        PlaybackProgressRepository::new(pool).create_or_update(&ids[3], progress).await.unwrap();
+
+        let service = SortPreferenceService::new(pool);
+        let titles = |books: Vec<Audiobook>| books.into_iter().map(|book| book.title).collect::<Vec<_>>();
+        let library = SortScope::Library;
+        let discworld = SortScope::Collection { collection_id: collection.id.clone() };
+
+        assert_eq!(service.get(&library).await.unwrap(), SortPreference::LIBRARY_DEFAULT);
+        assert_eq!(titles(service.collection(&collection.id).await.unwrap()), vec!["Mort", "Guards! Guards!", "Equal Rites"]);
+
+        service.set(&library, SortPreference { field: LibrarySortField::Title, descending: false }).await.unwrap();
+        assert_eq!(titles(service.library().await.unwrap()), vec!["Dune", "Equal Rites", "Guards! Guards!", "Mort"]);
+        service.set(&library, SortPreference { field: LibrarySortField::RecentlyPlayed, descending: true }).await.unwrap();
+        assert_eq!(service.library().await.unwrap()[0].title, "Dune");
+        assert!(service.set(&library, SortPreference::COLLECTION_DEFAULT).await.is_err());
+
+        service.set(&discworld, SortPreference { field: LibrarySortField::Series, descending: false }).await.unwrap();
+        assert_eq!(titles(service.collection(&collection.id).await.unwrap()), vec!["Equal Rites", "Mort", "Guards! Guards!"]);
+        assert_eq!(service.get(&library).await.unwrap().field, LibrarySortField::RecentlyPlayed, "kept apart");
+
+        service.forget_collection(&collection.id).await.unwrap();
+        assert_eq!(service.get(&discworld).await.unwrap(), SortPreference::COLLECTION_DEFAULT);
+        let missing = SortScope::Collection { collection_id: "missing".to_string() };
+        assert!(service.set(&missing, SortPreference::COLLECTION_DEFAULT).await.is_err());
+    }
+}
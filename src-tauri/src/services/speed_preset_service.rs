@@ -0,0 +1,193 @@
+// Playback speed presets: a global list, optional per-book lists, and the step
+// used when nudging speed up or down from shortcuts and media keys
+
+use crate::database::repository::PreferencesRepository;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+pub const PREF_SPEED_PRESETS: &str = "playback.speed_presets";
+/// Per-book preset lists are stored under this prefix followed by the audiobook id
+pub const PREF_BOOK_SPEED_PRESETS_PREFIX: &str = "playback.speed_presets.";
+pub const PREF_SPEED_STEP: &str = "playback.speed_step";
+
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
+const DEFAULT_PRESETS: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+const DEFAULT_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedDirection {
+    /// Next preset, wrapping around to the slowest
+    Next,
+    /// Previous preset, wrapping around to the fastest
+    Previous,
+    /// One step faster
+    Up,
+    /// One step slower
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SpeedPresets {
+    /// Sorted, without duplicates
+    pub presets: Vec<f32>,
+    pub step: f32,
+    /// Whether `presets` is the book's own list rather than the global one
+    pub is_book_specific: bool,
+}
+
+impl SpeedPresets {
+    /// Preset closest to `speed`; the engine always lands on one when cycling
+    pub fn snap(&self, speed: f32) -> f32 {
+        self.presets
+            .iter()
+            .copied()
+            .min_by(|a, b| (a - speed).abs().total_cmp(&(b - speed).abs()))
+            .unwrap_or(speed)
+    }
+
+    pub fn apply(&self, current: f32, direction: SpeedDirection) -> f32 {
+        match direction {
+            SpeedDirection::Next | SpeedDirection::Previous if !self.presets.is_empty() => {
+                let snapped = self.snap(current);
+                let index = self.presets.iter().position(|p| *p == snapped).unwrap_or(0);
+                let len = self.presets.len();
+                let next = if direction == SpeedDirection::Next { (index + 1) % len } else { (index + len - 1) % len };
+                self.presets[next]
+            }
+            SpeedDirection::Next | SpeedDirection::Previous => current,
+            SpeedDirection::Up => round_speed(current + self.step),
+            SpeedDirection::Down => round_speed(current - self.step),
+        }
+    }
+}
+
+/// Clamp to the engine's range and drop float noise like 1.2000001
+fn round_speed(speed: f32) -> f32 {
+    (speed.clamp(MIN_SPEED, MAX_SPEED) * 100.0).round() / 100.0
+}
+
+fn normalize_presets(presets: impl IntoIterator<Item = f32>) -> Vec<f32> {
+    let mut presets: Vec<f32> = presets.into_iter().filter(|p| p.is_finite()).map(round_speed).collect();
+    presets.sort_by(f32::total_cmp);
+    presets.dedup();
+    presets
+}
+
+pub struct SpeedPresetService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SpeedPresetService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The book's own presets when it has any, otherwise the global list
+    pub async fn get(&self, audiobook_id: Option<&str>) -> Result<SpeedPresets> {
+        let repo = PreferencesRepository::new(self.pool);
+        let step = repo.get(PREF_SPEED_STEP).await?
+            .and_then(|value| value.parse::<f32>().ok())
+            .filter(|step| *step > 0.0)
+            .unwrap_or(DEFAULT_STEP);
+
+        if let Some(audiobook_id) = audiobook_id {
+            if let Some(presets) = self.read_list(&book_key(audiobook_id)).await? {
+                return Ok(SpeedPresets { presets, step, is_book_specific: true });
+            }
+        }
+
+        let presets = self.read_list(PREF_SPEED_PRESETS).await?
+            .unwrap_or_else(|| DEFAULT_PRESETS.to_vec());
+        Ok(SpeedPresets { presets, step, is_book_specific: false })
+    }
+
+    /// Adding to a book that has no list yet starts from a copy of the global presets
+    pub async fn add(&self, speed: f32, audiobook_id: Option<&str>) -> Result<SpeedPresets> {
+        let mut presets = self.get(audiobook_id).await?.presets;
+        presets.push(speed);
+        self.write_list(audiobook_id, &presets).await?;
+        self.get(audiobook_id).await
+    }
+
+    pub async fn remove(&self, speed: f32, audiobook_id: Option<&str>) -> Result<SpeedPresets> {
+        let mut presets = self.get(audiobook_id).await?.presets;
+        let speed = round_speed(speed);
+        presets.retain(|preset| *preset != speed);
+        if presets.is_empty() {
+            return Err(anyhow::anyhow!("At least one speed preset is required"));
+        }
+        self.write_list(audiobook_id, &presets).await?;
+        self.get(audiobook_id).await
+    }
+
+    /// Drop a book's own presets so it follows the global list again
+    pub async fn reset_book(&self, audiobook_id: &str) -> Result<()> {
+        PreferencesRepository::new(self.pool).delete(&book_key(audiobook_id)).await
+    }
+
+    pub async fn set_step(&self, step: f32) -> Result<()> {
+        if !(step > 0.0 && step <= 1.0) {
+            return Err(anyhow::anyhow!("Speed step must be between 0 and 1"));
+        }
+        PreferencesRepository::new(self.pool).set(PREF_SPEED_STEP, &step.to_string()).await
+    }
+
+    async fn read_list(&self, key: &str) -> Result<Option<Vec<f32>>> {
+        let Some(json) = PreferencesRepository::new(self.pool).get(key).await? else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<Vec<f32>>(&json) {
+            Ok(presets) => Ok(Some(normalize_presets(presets)).filter(|presets| !presets.is_empty())),
+            Err(e) => {
+                log::warn!("Ignoring invalid speed presets in {}: {}", key, e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn write_list(&self, audiobook_id: Option<&str>, presets: &[f32]) -> Result<()> {
+        let key = audiobook_id.map(book_key).unwrap_or_else(|| PREF_SPEED_PRESETS.to_string());
+        let json = serde_json::to_string(&normalize_presets(presets.iter().copied()))
+            .context("Failed to serialize speed presets")?;
+        PreferencesRepository::new(self.pool).set(&key, &json).await
+    }
+}
+
+fn book_key(audiobook_id: &str) -> String {
+    format!("{}{}", PREF_BOOK_SPEED_PRESETS_PREFIX, audiobook_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presets() -> SpeedPresets {
+        SpeedPresets { presets: vec![1.0, 1.5, 2.0], step: 0.1, is_book_specific: false }
+    }
+
+    #[test]
+    fn test_cycle_snaps_to_presets_and_wraps() {
+        let presets = presets();
+        assert_eq!(presets.apply(1.0, SpeedDirection::Next), 1.5);
+        // 1.4 snaps to 1.5 first
+        assert_eq!(presets.apply(1.4, SpeedDirection::Next), 2.0);
+        assert_eq!(presets.apply(2.0, SpeedDirection::Next), 1.0);
+        assert_eq!(presets.apply(1.0, SpeedDirection::Previous), 2.0);
+        assert_eq!(presets.apply(0.5, SpeedDirection::Previous), 2.0);
+    }
+
+    #[test]
+    fn test_step_is_clamped_and_rounded() {
+        let presets = presets();
+        assert_eq!(presets.apply(1.1, SpeedDirection::Up), 1.2);
+        assert_eq!(presets.apply(MAX_SPEED, SpeedDirection::Up), MAX_SPEED);
+        assert_eq!(presets.apply(0.3, SpeedDirection::Down), MIN_SPEED);
+        assert_eq!(normalize_presets([2.0, 1.0, 1.0, 9.0, f32::NAN]), vec![1.0, 2.0, MAX_SPEED]);
+    }
+}
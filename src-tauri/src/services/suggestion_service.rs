@@ -0,0 +1,403 @@
+// Type-ahead for the library search box: titles, authors, series, narrators and
+// genre tags matched by word prefix, with trigrams to catch typos and matches
+// inside words. The index lives in memory and is rebuilt once the change journal
+// or the series table has moved on; that check runs at most once a second, so a
+// burst of keystrokes costs a single cheap query.
+
+use crate::database::content_filter::{self, ContentFilter};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+pub const DEFAULT_SUGGESTION_LIMIT: u32 = 10;
+pub const MAX_SUGGESTION_LIMIT: u32 = 50;
+/// How long an index is trusted before the library is checked for changes again
+const REFRESH_DEBOUNCE: Duration = Duration::from_secs(1);
+/// Longer words are indexed by their first this many characters and checked in full
+const MAX_PREFIX_CHARS: usize = 8;
+/// Percentage of the query's trigrams a fuzzy match has to contain
+const MIN_TRIGRAM_SHARE: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Title,
+    Author,
+    Series,
+    Narrator,
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub text: String,
+    /// The book, for title suggestions
+    pub audiobook_id: Option<String>,
+    /// Books in the library under this suggestion
+    pub book_count: u32,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IndexedBook {
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub narrator: Option<String>,
+    pub genre: Option<String>,
+    pub description: Option<String>,
+    pub series: Option<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    kind: SuggestionKind,
+    text: String,
+    normalized: String,
+    words: Vec<String>,
+    audiobook_id: Option<String>,
+    books: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+pub struct SuggestionIndex {
+    books: Vec<IndexedBook>,
+    entries: Vec<Entry>,
+    prefixes: HashMap<String, Vec<usize>>,
+    trigrams: HashMap<[char; 3], Vec<usize>>,
+}
+
+impl SuggestionIndex {
+    pub fn build(books: Vec<IndexedBook>) -> Self {
+        let mut index = SuggestionIndex::default();
+        let mut by_key: HashMap<(SuggestionKind, String), usize> = HashMap::new();
+
+        for (book, row) in books.iter().enumerate() {
+            let mut fields = vec![(SuggestionKind::Title, row.title.clone())];
+            fields.extend(row.author.iter().map(|author| (SuggestionKind::Author, author.clone())));
+            fields.extend(row.series.iter().map(|series| (SuggestionKind::Series, series.clone())));
+            fields.extend(row.narrator.iter().map(|narrator| (SuggestionKind::Narrator, narrator.clone())));
+            fields.extend(row.genre.iter().flat_map(|genre| split_tags(genre)).map(|tag| (SuggestionKind::Tag, tag)));
+
+            for (kind, text) in fields {
+                let text = text.trim().to_string();
+                let words = words(&text);
+                if words.is_empty() {
+                    continue;
+                }
+                let normalized = words.join(" ");
+                // Every book is its own title suggestion; the rest group the books they cover
+                let key = match kind {
+                    SuggestionKind::Title => (kind, row.id.clone()),
+                    _ => (kind, normalized.clone()),
+                };
+                if let Some(&entry) = by_key.get(&key) {
+                    index.entries[entry].books.push(book);
+                    continue;
+                }
+                by_key.insert(key, index.entries.len());
+                index.entries.push(Entry {
+                    kind,
+                    text,
+                    normalized,
+                    words,
+                    audiobook_id: (kind == SuggestionKind::Title).then(|| row.id.clone()),
+                    books: vec![book],
+                });
+            }
+        }
+
+        for (id, entry) in index.entries.iter().enumerate() {
+            let mut prefixes = HashSet::new();
+            let mut trigrams = HashSet::new();
+            for word in &entry.words {
+                let chars: Vec<char> = word.chars().collect();
+                for len in 1..=chars.len().min(MAX_PREFIX_CHARS) {
+                    prefixes.insert(chars[..len].iter().collect::<String>());
+                }
+                trigrams.extend(word_trigrams(word));
+            }
+            for prefix in prefixes {
+                index.prefixes.entry(prefix).or_default().push(id);
+            }
+            for trigram in trigrams {
+                index.trigrams.entry(trigram).or_default().push(id);
+            }
+        }
+
+        index.books = books;
+        index
+    }
+
+    /// Best matches first: whole-text prefixes, then word prefixes, then fuzzy
+    /// matches. Books the filter blocks are not counted or suggested.
+    pub fn suggest(&self, query: &str, limit: usize, filter: Option<&ContentFilter>) -> Vec<Suggestion> {
+        let tokens = words(query);
+        if tokens.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let normalized_query = tokens.join(" ");
+
+        // (rank, trigram share, entry): rank 0 for a whole-text prefix, 1 for word prefixes, 2 for fuzzy
+        let mut matches: Vec<(u8, usize, usize)> = self
+            .prefix_matches(&tokens)
+            .into_iter()
+            .map(|id| {
+                let rank = if self.entries[id].normalized.starts_with(&normalized_query) { 0 } else { 1 };
+                (rank, 100, id)
+            })
+            .collect();
+        if matches.len() < limit {
+            let found: HashSet<usize> = matches.iter().map(|&(_, _, id)| id).collect();
+            matches.extend(
+                self.fuzzy_matches(&tokens)
+                    .into_iter()
+                    .filter(|(id, _)| !found.contains(id))
+                    .map(|(id, share)| (2, share, id)),
+            );
+        }
+
+        let mut suggestions: Vec<(u8, usize, Suggestion)> = matches
+            .into_iter()
+            .filter_map(|(rank, share, id)| {
+                let entry = &self.entries[id];
+                let book_count = entry.books.iter().filter(|&&book| !self.is_blocked(book, filter)).count();
+                (book_count > 0).then(|| {
+                    (rank, share, Suggestion {
+                        kind: entry.kind,
+                        text: entry.text.clone(),
+                        audiobook_id: entry.audiobook_id.clone(),
+                        book_count: book_count as u32,
+                    })
+                })
+            })
+            .collect();
+        suggestions.sort_by(|(rank_a, share_a, a), (rank_b, share_b, b)| {
+            rank_a.cmp(rank_b)
+                .then(share_b.cmp(share_a))
+                .then(a.kind.cmp(&b.kind))
+                .then(b.book_count.cmp(&a.book_count))
+                .then(a.text.len().cmp(&b.text.len()))
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        suggestions.into_iter().take(limit).map(|(_, _, suggestion)| suggestion).collect()
+    }
+
+    /// Entries where every token starts one of the words
+    fn prefix_matches(&self, tokens: &[String]) -> Vec<usize> {
+        let mut candidates: Option<HashSet<usize>> = None;
+        for token in tokens {
+            let key: String = token.chars().take(MAX_PREFIX_CHARS).collect();
+            let ids: HashSet<usize> = self.prefixes.get(&key).into_iter().flatten().copied().collect();
+            candidates = Some(match candidates {
+                Some(found) => found.intersection(&ids).copied().collect(),
+                None => ids,
+            });
+        }
+        candidates
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&id| {
+                let words = &self.entries[id].words;
+                tokens.iter().all(|token| words.iter().any(|word| word.starts_with(token.as_str())))
+            })
+            .collect()
+    }
+
+    /// Entries sharing enough of the query's trigrams, with the share in percent
+    fn fuzzy_matches(&self, tokens: &[String]) -> Vec<(usize, usize)> {
+        let query_trigrams: HashSet<[char; 3]> = tokens.iter().flat_map(|token| word_trigrams(token)).collect();
+        if query_trigrams.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: HashMap<usize, usize> = HashMap::new();
+        for trigram in &query_trigrams {
+            for &id in self.trigrams.get(trigram).into_iter().flatten() {
+                *hits.entry(id).or_default() += 1;
+            }
+        }
+        hits.into_iter()
+            .map(|(id, count)| (id, count * 100 / query_trigrams.len()))
+            .filter(|&(_, share)| share >= MIN_TRIGRAM_SHARE)
+            .collect()
+    }
+
+    fn is_blocked(&self, book: usize, filter: Option<&ContentFilter>) -> bool {
+        let Some(filter) = filter else {
+            return false;
+        };
+        let book = &self.books[book];
+        filter.blocks(&book.title, book.author.as_deref(), book.genre.as_deref(), book.description.as_deref())
+    }
+}
+
+/// Lowercased runs of letters and digits
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn word_trigrams(word: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = word.chars().collect();
+    chars.windows(3).map(|window| [window[0], window[1], window[2]]).collect()
+}
+
+/// Genres are stored as one string, often several separated by commas
+fn split_tags(genre: &str) -> Vec<String> {
+    genre.split([',', ';', '/'])
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+struct CachedIndex {
+    index: Arc<SuggestionIndex>,
+    version: String,
+    checked_at: Instant,
+}
+
+static CACHE: Mutex<Option<CachedIndex>> = Mutex::new(None);
+
+pub struct SuggestionService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> SuggestionService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn suggest(&self, query: &str, limit: u32) -> Result<Vec<Suggestion>> {
+        if words(query).is_empty() {
+            return Ok(Vec::new());
+        }
+        let index = self.index().await?;
+        let limit = limit.clamp(1, MAX_SUGGESTION_LIMIT) as usize;
+        Ok(index.suggest(query, limit, content_filter::active().as_ref()))
+    }
+
+    /// The cached index, rebuilt first if the library changed since it was built
+    async fn index(&self) -> Result<Arc<SuggestionIndex>> {
+        let now = Instant::now();
+        let cached = CACHE.lock().unwrap().as_ref().map(|cached| (cached.index.clone(), cached.version.clone(), cached.checked_at));
+        if let Some((index, _, checked_at)) = &cached {
+            if now.duration_since(*checked_at) < REFRESH_DEBOUNCE {
+                return Ok(index.clone());
+            }
+        }
+
+        let version = self.library_version().await?;
+        let index = match cached {
+            Some((index, cached_version, _)) if cached_version == version => index,
+            _ => {
+                let index = Arc::new(self.build_index().await?);
+                log::debug!("Rebuilt suggestion index over {} books", index.books.len());
+                index
+            }
+        };
+        *CACHE.lock().unwrap() = Some(CachedIndex { index: index.clone(), version, checked_at: now });
+        Ok(index)
+    }
+
+    pub async fn build_index(&self) -> Result<SuggestionIndex> {
+        let books = sqlx::query_as::<_, IndexedBook>(
+            "SELECT a.id, a.title, a.author, a.narrator, a.genre, a.description, s.name AS series
+             FROM audiobooks a LEFT JOIN audiobook_series s ON s.audiobook_id = a.id"
+        )
+        .fetch_all(self.pool)
+        .await
+        .context("Failed to load books for suggestions")?;
+        Ok(SuggestionIndex::build(books))
+    }
+
+    /// Moves on whenever a book is added, changed or removed, or a series is set.
+    /// Playback progress has its own journal entries and does not count.
+    async fn library_version(&self) -> Result<String> {
+        let (books, series_count, series_updated) = sqlx::query_as::<_, (Option<i64>, i64, Option<String>)>(
+            "SELECT (SELECT MAX(seq) FROM change_log WHERE entity_type = 'audiobook'),
+                    (SELECT COUNT(*) FROM audiobook_series),
+                    (SELECT MAX(updated_at) FROM audiobook_series)"
+        )
+        .fetch_one(self.pool)
+        .await
+        .context("Failed to check library for changes")?;
+        Ok(format!("{}:{}:{}", books.unwrap_or(0), series_count, series_updated.unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(id: &str, title: &str, author: &str, narrator: Option<&str>, genre: Option<&str>, series: Option<&str>) -> IndexedBook {
+        IndexedBook {
+            id: id.to_string(),
+            title: title.to_string(),
+            author: Some(author.to_string()),
+            narrator: narrator.map(str::to_string),
+            genre: genre.map(str::to_string),
+            description: None,
+            series: series.map(str::to_string),
+        }
+    }
+
+    fn library() -> SuggestionIndex {
+        SuggestionIndex::build(vec![
+            book("1", "Pride and Prejudice", "Jane Austen", Some("Karen Savage"), Some("Romance, Classics"), None),
+            book("2", "Emma", "Jane Austen", Some("Moira Fogarty"), Some("Romance"), None),
+            book("3", "The War of the Worlds", "H. G. Wells", Some("Mark Nelson"), Some("Science Fiction"), None),
+            book("4", "Leviathan Wakes", "James S. A. Corey", None, Some("Science Fiction"), Some("The Expanse")),
+        ])
+    }
+
+    #[test]
+    fn test_suggestions_mix_kinds_by_prefix() {
+        let suggestions = library().suggest("jan", 10, None);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].kind, SuggestionKind::Author);
+        assert_eq!(suggestions[0].text, "Jane Austen");
+        assert_eq!(suggestions[0].book_count, 2);
+
+        let suggestions = library().suggest("rom", 10, None);
+        assert_eq!(suggestions[0].kind, SuggestionKind::Tag);
+        assert_eq!(suggestions[0].book_count, 2);
+
+        let kinds: Vec<SuggestionKind> = library().suggest("the exp", 10, None).iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![SuggestionKind::Series]);
+
+        // Every word has to match, in any order
+        let titles: Vec<String> = library().suggest("worlds war", 10, None).into_iter().map(|s| s.text).collect();
+        assert_eq!(titles, vec!["The War of the Worlds"]);
+    }
+
+    #[test]
+    fn test_whole_text_prefix_ranks_first_and_fuzzy_last() {
+        let suggestions = library().suggest("emma", 10, None);
+        assert_eq!(suggestions[0].audiobook_id.as_deref(), Some("2"));
+
+        // Typo: no word starts with "leviathn", but most of its trigrams are there
+        let suggestions = library().suggest("leviathn", 10, None);
+        assert_eq!(suggestions[0].text, "Leviathan Wakes");
+        assert!(library().suggest("zzz", 10, None).is_empty());
+    }
+
+    #[test]
+    fn test_blocked_books_are_left_out() {
+        let filter = ContentFilter {
+            enabled: true,
+            blocked_genres: vec!["Science Fiction".to_string()],
+            ..Default::default()
+        };
+        assert!(library().suggest("science", 10, Some(&filter)).is_empty());
+        assert!(library().suggest("leviathan", 10, Some(&filter)).is_empty());
+        assert_eq!(library().suggest("jane", 10, Some(&filter))[0].book_count, 2);
+    }
+}
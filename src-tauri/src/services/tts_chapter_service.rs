@@ -0,0 +1,189 @@
+// Regenerates a single TTS chapter with a different voice or speed, from the
+// text stored when the book was created. The new audio replaces the chapter's
+// file in place so playlists, bookmarks and history keep pointing at it.
+
+use crate::database::models::{Chapter, CreateChapterDto};
+use crate::database::repository::{
+    AudiobookRepository, ChapterRepository, PlaybackProgressRepository, PreferencesRepository,
+    TtsChapterSourceRepository, TtsTimingRepository,
+};
+use crate::filesystem::atomic;
+use crate::services::chapter_text_service::ChapterTextService;
+use crate::services::speed_preset_service::{MAX_SPEED, MIN_SPEED};
+use crate::services::tts_timing_service::group_into_sentences;
+use crate::storage::disk_space;
+use crate::tts::{self, SynthesizedAudio, TtsClient};
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+pub struct TtsChapterService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TtsChapterService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Synthesize the chapter again; `None` keeps the voice or speed it was last generated with
+    pub async fn regenerate(&self, chapter_id: &str, voice: Option<String>, speed: Option<f32>) -> Result<Chapter> {
+        let chapter_repo = ChapterRepository::new(self.pool);
+        let chapter = chapter_repo
+            .find_by_id(chapter_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Chapter not found: {}", chapter_id))?;
+        let source_repo = TtsChapterSourceRepository::new(self.pool);
+        let source = source_repo
+            .find(chapter_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No source text stored for chapter '{}'", chapter.title))?;
+
+        let voice = voice
+            .filter(|voice| !voice.trim().is_empty())
+            .or(source.voice)
+            .unwrap_or_else(|| tts::DEFAULT_VOICE.to_string());
+        let speed = speed.unwrap_or(source.speed as f32);
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(anyhow::anyhow!("Speed must be between {} and {}", MIN_SPEED, MAX_SPEED));
+        }
+
+        let api_url = PreferencesRepository::new(self.pool)
+            .get(tts::PREF_TTS_API_URL)
+            .await?
+            .unwrap_or_else(|| tts::DEFAULT_TTS_API_URL.to_string());
+        let client = TtsClient::new(&api_url)?;
+
+        // Edited text may have picked up artifacts again; cleaning is a no-op on clean text
+        let text = ChapterTextService::new(self.pool).clean(&source.text).await;
+        if let Some(dir) = Path::new(&chapter.file_path).parent() {
+            disk_space::ensure_space(dir, text.chars().count() as u64 * disk_space::TTS_BYTES_PER_CHAR)?;
+        }
+        let mut audio = SynthesizedAudio::default();
+        for chunk in tts::split_text(&text, tts::MAX_CHUNK_CHARS) {
+            audio.append(client.synthesize(&chunk, &voice).await?)?;
+        }
+        if audio.samples.is_empty() {
+            return Err(anyhow::anyhow!("Chapter '{}' has no text to synthesize", chapter.title));
+        }
+        let audio = tokio::task::spawn_blocking(move || audio.stretch(speed))
+            .await
+            .context("Time stretching failed")?;
+
+        let wav = audio.to_wav();
+        let file_path = PathBuf::from(&chapter.file_path);
+        replace_file(&file_path, &wav)?;
+        remove_stale_chunks(&file_path, chapter.chapter_number);
+
+        let new_duration = audio.duration_seconds().round() as i64;
+        chapter_repo
+            .update_chapter(&chapter.id, CreateChapterDto {
+                audiobook_id: chapter.audiobook_id.clone(),
+                chapter_number: chapter.chapter_number,
+                title: chapter.title.clone(),
+                file_path: chapter.file_path.clone(),
+                duration: Some(new_duration),
+                file_size: Some(wav.len() as i64),
+            })
+            .await?;
+        source_repo.upsert(&chapter.id, &source.text, Some(&voice), speed as f64).await?;
+
+        let timing_repo = TtsTimingRepository::new(self.pool);
+        timing_repo.delete_for_chapter(&chapter.id).await?;
+        timing_repo
+            .replace_for_file(&chapter.id, &chapter.file_path, &group_into_sentences(audio.alignment))
+            .await?;
+
+        self.rescale_progress(&chapter, new_duration).await?;
+
+        log::info!("TTS: Regenerated chapter {} of {} with voice {} at {}x", chapter.chapter_number, chapter.audiobook_id, voice, speed);
+        chapter_repo
+            .find_by_id(&chapter.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Chapter disappeared while regenerating"))
+    }
+
+    /// Synthesize every chapter of a book created with stored text, then point the
+    /// book at its first chapter's audio. Returns the number of chapters generated.
+    pub async fn synthesize_book(&self, audiobook_id: &str) -> Result<usize> {
+        let chapters = ChapterRepository::new(self.pool).find_by_audiobook_id(audiobook_id).await?;
+        if chapters.is_empty() {
+            return Err(anyhow::anyhow!("Audiobook {} has no chapters", audiobook_id));
+        }
+
+        for chapter in &chapters {
+            self.regenerate(&chapter.id, None, None)
+                .await
+                .with_context(|| format!("Failed to synthesize chapter {}", chapter.chapter_number))?;
+        }
+        AudiobookRepository::new(self.pool).set_file_path(audiobook_id, &chapters[0].file_path).await?;
+        Ok(chapters.len())
+    }
+
+    /// A listener part-way through the chapter stays at the same point in the text
+    async fn rescale_progress(&self, chapter: &Chapter, new_duration: i64) -> Result<()> {
+        let progress_repo = PlaybackProgressRepository::new(self.pool);
+        let Some(progress) = progress_repo.find_by_audiobook_id(&chapter.audiobook_id).await? else {
+            return Ok(());
+        };
+        if progress.chapter_index != chapter.chapter_number - 1 {
+            return Ok(());
+        }
+
+        let position = rescale_position(progress.position, chapter.duration, new_duration);
+        if position != progress.position {
+            progress_repo.set_position(&chapter.audiobook_id, position).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Write next to the target and rename over it, so a failed write never leaves
+/// the chapter without playable audio
+fn replace_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path.parent().context("Chapter file has no parent directory")?;
+    std::fs::create_dir_all(dir).context("Failed to create chapter directory")?;
+
+    atomic::write(path, contents).context("Failed to replace chapter audio")
+}
+
+/// The frontend saves long chapters as `chapter_N_chunk_M.wav` with the chapter
+/// pointing at chunk 1; the regenerated file now holds the whole chapter
+fn remove_stale_chunks(file_path: &Path, chapter_number: i32) {
+    let prefix = format!("chapter_{}_chunk_", chapter_number);
+    let is_first_chunk = file_path.file_name().and_then(|name| name.to_str()) == Some(&format!("{}1.wav", prefix));
+    let Some(dir) = file_path.parent().filter(|_| is_first_chunk) else {
+        return;
+    };
+
+    for chunk in 2.. {
+        let stale = dir.join(format!("{}{}.wav", prefix, chunk));
+        if std::fs::remove_file(&stale).is_err() {
+            break;
+        }
+    }
+}
+
+/// Keep the same fraction of the chapter behind the listener
+fn rescale_position(position: i64, old_duration: Option<i64>, new_duration: i64) -> i64 {
+    let position = match old_duration.filter(|duration| *duration > 0) {
+        Some(old) => (position as f64 * new_duration as f64 / old as f64).round() as i64,
+        None => position,
+    };
+    position.clamp(0, new_duration.max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rescale_position_keeps_place_in_text() {
+        // Halfway through at 1x is halfway through at 2x
+        assert_eq!(rescale_position(300, Some(600), 300), 150);
+        assert_eq!(rescale_position(300, Some(600), 900), 450);
+        // Unknown old length: only make sure it still fits
+        assert_eq!(rescale_position(300, None, 200), 200);
+        assert_eq!(rescale_position(100, Some(0), 200), 100);
+    }
+}
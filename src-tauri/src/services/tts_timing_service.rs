@@ -0,0 +1,119 @@
+// Sentence timings for TTS chapters, so the player can highlight the sentence
+// being spoken. Engines report alignment per word or per sentence; either way
+// it is stored one row per sentence.
+
+use crate::database::models::{AlignedSegment, TtsTiming};
+use crate::database::repository::{ChapterRepository, TtsTimingRepository};
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+pub struct TtsTimingService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> TtsTimingService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Record the alignment for one synthesized chunk file of a chapter
+    pub async fn record(
+        &self,
+        audiobook_id: &str,
+        chapter_number: i32,
+        file_path: &str,
+        alignment: Vec<AlignedSegment>,
+    ) -> Result<Vec<TtsTiming>> {
+        let chapter = ChapterRepository::new(self.pool)
+            .get_chapter_by_number(audiobook_id, chapter_number)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Chapter {} not found for audiobook {}", chapter_number, audiobook_id))?;
+
+        let sentences = group_into_sentences(alignment);
+        TtsTimingRepository::new(self.pool).replace_for_file(&chapter.id, file_path, &sentences).await
+    }
+
+    pub async fn get(&self, chapter_id: &str) -> Result<Vec<TtsTiming>> {
+        TtsTimingRepository::new(self.pool).find_by_chapter_id(chapter_id).await
+    }
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(['.', '!', '?', '…'])
+}
+
+/// Merge word-level spans into sentences; sentence-level input passes through.
+/// Spans with no text or an invalid time range are dropped.
+pub(crate) fn group_into_sentences(alignment: Vec<AlignedSegment>) -> Vec<AlignedSegment> {
+    let mut sentences = Vec::new();
+    let mut current: Option<AlignedSegment> = None;
+
+    for segment in alignment {
+        let text = segment.text.trim();
+        if text.is_empty() || !segment.start.is_finite() || !segment.end.is_finite() || segment.end < segment.start {
+            continue;
+        }
+
+        let sentence = current.get_or_insert_with(|| AlignedSegment {
+            text: String::new(),
+            start: segment.start,
+            end: segment.end,
+        });
+        if !sentence.text.is_empty() {
+            sentence.text.push(' ');
+        }
+        sentence.text.push_str(text);
+        sentence.end = sentence.end.max(segment.end);
+
+        if ends_sentence(text) {
+            sentences.extend(current.take());
+        }
+    }
+
+    // Trailing words without closing punctuation still form a sentence
+    sentences.extend(current);
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, start: f64, end: f64) -> AlignedSegment {
+        AlignedSegment { text: text.to_string(), start, end }
+    }
+
+    #[test]
+    fn test_words_are_grouped_into_sentences() {
+        let words = vec![
+            span("Call", 0.0, 0.3),
+            span("me", 0.3, 0.5),
+            span("Ishmael.", 0.5, 1.1),
+            span(" ", 1.1, 1.2),
+            span("Some", 1.4, 1.6),
+            span("years", 1.6, 1.9),
+            span("ago—\"never", 1.9, 2.5),
+            span("mind!\"", 2.5, 3.0),
+            span("Trailing", 3.2, 3.6),
+            span("bad", 4.0, 3.0),
+        ];
+
+        let sentences = group_into_sentences(words);
+        assert_eq!(sentences.len(), 3);
+        assert_eq!(sentences[0].text, "Call me Ishmael.");
+        assert_eq!((sentences[0].start, sentences[0].end), (0.0, 1.1));
+        assert_eq!(sentences[1].text, "Some years ago—\"never mind!\"");
+        assert_eq!((sentences[1].start, sentences[1].end), (1.4, 3.0));
+        assert_eq!(sentences[2].text, "Trailing");
+    }
+
+    #[test]
+    fn test_sentence_level_alignment_passes_through() {
+        let input = vec![span("First one.", 0.0, 2.0), span("Second one?", 2.0, 3.5)];
+        let sentences = group_into_sentences(input.clone());
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(sentences[1].text, input[1].text);
+        assert_eq!(sentences[1].start, 2.0);
+    }
+}
@@ -0,0 +1,415 @@
+// Choosing between copies of the same book, such as two LibriVox recordings of
+// one title. Versions are compared side by side; merging keeps one copy and
+// moves the listener's own data from the other onto it before removing it
+// from the library. The removed copy's files are left on disk.
+//
+// Positions inside the removed copy (progress, bookmarks, notes) are scaled
+// to the kept copy's runtime, since two readings of a book rarely line up.
+
+use crate::database::models::Audiobook;
+use crate::database::repository::AudiobookRepository;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BookVersion {
+    pub audiobook_id: String,
+    pub title: String,
+    pub author: Option<String>,
+    /// The narrator field split into names; LibriVox group recordings list several
+    pub readers: Vec<String>,
+    #[ts(type = "number | null")]
+    pub duration: Option<i64>,
+    /// kbps, of the lowest-quality file
+    pub bitrate: Option<i32>,
+    pub sample_rate: Option<i32>,
+    #[ts(type = "number | null")]
+    pub file_size: Option<i64>,
+    pub chapters_count: i32,
+    pub rating: Option<i32>,
+    pub source_type: Option<String>,
+    pub added_date: String,
+    /// Seconds listened into this copy, when it has been started
+    #[ts(type = "number | null")]
+    pub position: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct VersionComparison {
+    pub versions: Vec<BookVersion>,
+    /// The copy worth keeping: highest bitrate, then highest rating, then the
+    /// one with the most chapters
+    pub suggested_keep_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct MergedVersions {
+    /// Whether the removed copy's progress replaced the kept copy's
+    pub progress_moved: bool,
+    pub bookmarks_moved: u64,
+    pub notes_moved: u64,
+    pub collections_added: u64,
+}
+
+pub struct VersionService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> VersionService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The books side by side, in the order given
+    pub async fn compare(&self, ids: &[String]) -> Result<VersionComparison> {
+        if ids.len() < 2 {
+            bail!("Pick at least two versions to compare");
+        }
+
+        let repo = AudiobookRepository::new(self.pool);
+        let mut versions = Vec::with_capacity(ids.len());
+        for id in ids {
+            let book = repo.find_by_id(id).await?.ok_or_else(|| anyhow!("Audiobook not found: {}", id))?;
+            let position: Option<i64> = sqlx::query_scalar("SELECT position FROM playback_progress WHERE audiobook_id = ?")
+                .bind(id)
+                .fetch_optional(self.pool)
+                .await
+                .context("Failed to load progress")?;
+            let lowest_bitrate: Option<i32> = sqlx::query_scalar("SELECT MIN(bitrate) FROM chapters WHERE audiobook_id = ?")
+                .bind(id)
+                .fetch_one(self.pool)
+                .await
+                .context("Failed to load chapter bitrates")?;
+            let mut version = version(book, position);
+            version.bitrate = lowest_bitrate.or(version.bitrate);
+            versions.push(version);
+        }
+
+        let suggested_keep_id = versions
+            .iter()
+            .max_by_key(|v| (v.bitrate.unwrap_or(0), v.rating.unwrap_or(0), v.chapters_count))
+            .map(|v| v.audiobook_id.clone());
+
+        Ok(VersionComparison { versions, suggested_keep_id })
+    }
+
+    /// Move progress, bookmarks, notes, collection membership and listening
+    /// history from `remove_id` onto `keep_id`, then delete `remove_id`. The
+    /// most recently played copy's progress wins; the kept copy's rating,
+    /// review and series are only filled in when it has none.
+    pub async fn merge(&self, keep_id: &str, remove_id: &str) -> Result<MergedVersions> {
+        if keep_id == remove_id {
+            bail!("A book cannot be merged into itself");
+        }
+        let repo = AudiobookRepository::new(self.pool);
+        let keep = repo.find_by_id(keep_id).await?.ok_or_else(|| anyhow!("Audiobook not found: {}", keep_id))?;
+        let remove = repo.find_by_id(remove_id).await?.ok_or_else(|| anyhow!("Audiobook not found: {}", remove_id))?;
+        let scale = position_scale(remove.duration, keep.duration);
+
+        let mut merged = MergedVersions::default();
+        let mut tx = self.pool.begin().await.context("Failed to start transaction")?;
+
+        let newer_progress: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT r.id
+            FROM playback_progress r
+            LEFT JOIN playback_progress k ON k.audiobook_id = ?1
+            WHERE r.audiobook_id = ?2 AND (k.id IS NULL OR r.last_played_at > k.last_played_at)
+            "#,
+        )
+        .bind(keep_id)
+        .bind(remove_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .context("Failed to compare progress")?;
+
+        if let Some(progress_id) = newer_progress {
+            sqlx::query("DELETE FROM playback_progress WHERE audiobook_id = ?")
+                .bind(keep_id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to replace progress")?;
+            sqlx::query(
+                r#"
+                UPDATE playback_progress
+                SET audiobook_id = ?1,
+                    position = CASE WHEN ?2 IS NULL THEN position ELSE MIN(CAST(ROUND(position * ?2) AS INTEGER), ?3) END,
+                    duration = COALESCE(?3, duration),
+                    chapter_index = 0
+                WHERE id = ?4
+                "#,
+            )
+            .bind(keep_id)
+            .bind(scale)
+            .bind(keep.duration)
+            .bind(&progress_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to move progress")?;
+            merged.progress_moved = true;
+        }
+
+        // Generated markers belong to the removed file and go with it
+        merged.bookmarks_moved = sqlx::query(
+            r#"
+            UPDATE chapter_markers
+            SET audiobook_id = ?1,
+                position = CASE WHEN ?2 IS NULL THEN position ELSE MIN(CAST(ROUND(position * ?2) AS INTEGER), ?3) END
+            WHERE audiobook_id = ?4 AND NOT is_auto
+            "#,
+        )
+        .bind(keep_id)
+        .bind(scale)
+        .bind(keep.duration)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move bookmarks")?
+        .rows_affected();
+
+        merged.notes_moved = sqlx::query(
+            r#"
+            UPDATE audiobook_notes
+            SET audiobook_id = ?1,
+                position = CASE WHEN ?2 IS NULL THEN position ELSE MIN(CAST(ROUND(position * ?2) AS INTEGER), ?3) END
+            WHERE audiobook_id = ?4
+            "#,
+        )
+        .bind(keep_id)
+        .bind(scale)
+        .bind(keep.duration)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move notes")?
+        .rows_affected();
+
+        merged.collections_added = sqlx::query(
+            r#"
+            UPDATE OR IGNORE collection_audiobooks
+            SET audiobook_id = ?1
+            WHERE audiobook_id = ?2
+            "#,
+        )
+        .bind(keep_id)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move collection membership")?
+        .rows_affected();
+
+        for table in ["listening_history", "plays"] {
+            sqlx::query(&format!("UPDATE {} SET audiobook_id = ? WHERE audiobook_id = ?", table))
+                .bind(keep_id)
+                .bind(remove_id)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to move {}", table))?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO listening_daily_summaries (day, audiobook_id, session_count, session_seconds, max_completion, play_count, played_seconds)
+            SELECT day, ?1, session_count, session_seconds, max_completion, play_count, played_seconds
+            FROM listening_daily_summaries
+            WHERE audiobook_id = ?2
+            ON CONFLICT(day, audiobook_id) DO UPDATE SET
+                session_count = session_count + excluded.session_count,
+                session_seconds = session_seconds + excluded.session_seconds,
+                max_completion = MAX(max_completion, excluded.max_completion),
+                play_count = play_count + excluded.play_count,
+                played_seconds = played_seconds + excluded.played_seconds
+            "#,
+        )
+        .bind(keep_id)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move daily listening")?;
+
+        sqlx::query(
+            r#"
+            UPDATE audiobooks
+            SET rating = COALESCE(rating, ?2),
+                review = COALESCE(review, ?3)
+            WHERE id = ?1
+            "#,
+        )
+        .bind(keep_id)
+        .bind(remove.rating)
+        .bind(&remove.review)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to carry over the rating")?;
+
+        sqlx::query("UPDATE OR IGNORE audiobook_series SET audiobook_id = ? WHERE audiobook_id = ?")
+            .bind(keep_id)
+            .bind(remove_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to carry over the series")?;
+
+        sqlx::query("DELETE FROM audiobooks WHERE id = ?")
+            .bind(remove_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete the merged copy")?;
+
+        tx.commit().await.context("Failed to commit merge")?;
+
+        Ok(merged)
+    }
+}
+
+fn version(book: Audiobook, position: Option<i64>) -> BookVersion {
+    BookVersion {
+        readers: reader_names(book.narrator.as_deref()),
+        audiobook_id: book.id,
+        title: book.title,
+        author: book.author,
+        duration: book.duration,
+        bitrate: book.bitrate,
+        sample_rate: book.sample_rate,
+        file_size: book.file_size,
+        chapters_count: book.chapters_count,
+        rating: book.rating,
+        source_type: book.source_type,
+        added_date: book.added_date,
+        position,
+    }
+}
+
+/// Names in a narrator field such as "Kara Shallenberg, Mark Nelson & others"
+fn reader_names(narrator: Option<&str>) -> Vec<String> {
+    narrator
+        .unwrap_or_default()
+        .split([',', ';', '&'])
+        .flat_map(|part| part.split(" and "))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// How far a second into one copy is into the other, when both runtimes are known
+fn position_scale(from_duration: Option<i64>, to_duration: Option<i64>) -> Option<f64> {
+    match (from_duration, to_duration) {
+        (Some(from), Some(to)) if from > 0 && to > 0 => Some(to as f64 / from as f64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{ChapterMarker, CreateAudiobookDto, CreateCollectionDto, SearchFilters, UpdatePlaybackProgressDto};
+    use crate::database::repository::{ChapterMarkerRepository, CollectionRepository, PlaybackProgressRepository};
+    use crate::database::DatabaseManager;
+    use crate::services::NoteService;
+
+    async fn add_book(pool: &SqlitePool, path: &str, narrator: &str, duration: i64) -> Audiobook {
+        AudiobookRepository::new(pool)
+            .create(CreateAudiobookDto {
+                title: "Pride and Prejudice".to_string(),
+                file_path: path.to_string(),
+                author: Some("Jane Austen".to_string()),
+                narrator: Some(narrator.to_string()),
+                description: None,
+                genre: None,
+                duration: Some(duration),
+                cover_image_path: None,
+                source_type: Some("librivox".to_string()),
+                source_id: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_merge_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("versions.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let solo = add_book(pool, "/books/solo", "Karen Savage", 40_000).await;
+        let group = add_book(pool, "/books/group", "Kara Shallenberg, Mark Nelson and Ruth Golding", 20_000).await;
+        sqlx::query("UPDATE audiobooks SET bitrate = 128, rating = 4 WHERE id = ?")
+            .bind(&solo.id)
+            .execute(pool)
+            .await
+            .unwrap();
+
+        PlaybackProgressRepository::new(pool)
+            .create_or_update(&group.id, UpdatePlaybackProgressDto {
+                position: 5_000,
+                chapter_index: Some(3),
+                playback_speed: None,
+                is_completed: None,
+            })
+            .await
+            .unwrap();
+        ChapterMarkerRepository::new(pool)
+            .create(&ChapterMarker::new(group.id.clone(), 1_000, "Proposal".to_string(), false))
+            .await
+            .unwrap();
+        ChapterMarkerRepository::new(pool)
+            .create(&ChapterMarker::new(group.id.clone(), 600, "Chapter 2".to_string(), true))
+            .await
+            .unwrap();
+        NoteService::new(pool).create(&group.id, Some(2_000), "Ball at Netherfield").await.unwrap();
+        let collections = CollectionRepository::new(pool);
+        let classics = collections
+            .create(CreateCollectionDto {
+                name: "Classics".to_string(),
+                description: None,
+                color: None,
+                parent_collection_id: None,
+            })
+            .await
+            .unwrap();
+        collections.add_audiobook_to_collection(&classics.id, &group.id).await.unwrap();
+
+        let service = VersionService::new(pool);
+        assert!(service.compare(std::slice::from_ref(&solo.id)).await.is_err());
+        let comparison = service.compare(&[group.id.clone(), solo.id.clone()]).await.unwrap();
+        assert_eq!(comparison.suggested_keep_id.as_deref(), Some(solo.id.as_str()));
+        assert_eq!(comparison.versions[0].readers, vec!["Kara Shallenberg", "Mark Nelson", "Ruth Golding"]);
+        assert_eq!(comparison.versions[0].position, Some(5_000));
+        assert_eq!(comparison.versions[1].position, None);
+
+        // Versions of unknown quality are left out of a bitrate filter
+        let filters = SearchFilters { min_bitrate: Some(64), ..Default::default() };
+        let found = AudiobookRepository::new(pool).search_with_filters(filters).await.unwrap();
+        assert_eq!(found.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec![solo.id.as_str()]);
+
+        assert!(service.merge(&solo.id, &solo.id).await.is_err());
+        let merged = service.merge(&solo.id, &group.id).await.unwrap();
+        assert_eq!(merged, MergedVersions { progress_moved: true, bookmarks_moved: 1, notes_moved: 1, collections_added: 1 });
+
+        assert!(AudiobookRepository::new(pool).find_by_id(&group.id).await.unwrap().is_none());
+        let progress = PlaybackProgressRepository::new(pool).find_by_audiobook_id(&solo.id).await.unwrap().unwrap();
+        assert_eq!((progress.position, progress.duration), (10_000, Some(40_000)));
+        let markers = ChapterMarkerRepository::new(pool).find_by_audiobook_id(&solo.id).await.unwrap();
+        assert_eq!(markers.iter().map(|m| (m.position, m.title.as_str())).collect::<Vec<_>>(), vec![(2_000, "Proposal")]);
+        let notes = NoteService::new(pool).list(&solo.id).await.unwrap();
+        assert_eq!(notes[0].position, Some(4_000));
+        let members = collections.get_collection_audiobooks(&classics.id).await.unwrap();
+        assert_eq!(members.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec![solo.id.as_str()]);
+    }
+
+    #[test]
+    fn test_reader_names_and_position_scale() {
+        assert_eq!(reader_names(Some("Karen Savage")), vec!["Karen Savage"]);
+        assert_eq!(reader_names(Some(" A ; B & C ")), vec!["A", "B", "C"]);
+        assert!(reader_names(None).is_empty());
+        assert_eq!(position_scale(Some(100), Some(150)), Some(1.5));
+        assert_eq!(position_scale(None, Some(150)), None);
+        assert_eq!(position_scale(Some(0), Some(150)), None);
+    }
+}
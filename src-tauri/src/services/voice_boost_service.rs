@@ -0,0 +1,100 @@
+// Voice boost settings: a global setting, and optional per-book overrides for
+// books that need it when others do not (a quiet recording, a shouty narrator)
+
+use crate::audio::voice_boost::VoiceBoostSettings;
+use crate::database::repository::PreferencesRepository;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+
+pub const PREF_VOICE_BOOST: &str = "playback.voice_boost";
+/// Per-book settings are stored under this prefix followed by the audiobook id
+pub const PREF_BOOK_VOICE_BOOST_PREFIX: &str = "playback.voice_boost.";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct VoiceBoost {
+    pub settings: VoiceBoostSettings,
+    /// Whether `settings` is the book's own rather than the global one
+    pub is_book_specific: bool,
+}
+
+pub struct VoiceBoostService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> VoiceBoostService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The book's own setting when it has one, otherwise the global one
+    pub async fn get(&self, audiobook_id: Option<&str>) -> Result<VoiceBoost> {
+        if let Some(audiobook_id) = audiobook_id {
+            if let Some(settings) = self.read(&book_key(audiobook_id)).await? {
+                return Ok(VoiceBoost { settings, is_book_specific: true });
+            }
+        }
+        let settings = self.read(PREF_VOICE_BOOST).await?.unwrap_or_default();
+        Ok(VoiceBoost { settings, is_book_specific: false })
+    }
+
+    pub async fn set(&self, settings: VoiceBoostSettings, audiobook_id: Option<&str>) -> Result<VoiceBoost> {
+        let key = audiobook_id.map(book_key).unwrap_or_else(|| PREF_VOICE_BOOST.to_string());
+        let json = serde_json::to_string(&settings.clamped()).context("Failed to serialize voice boost")?;
+        PreferencesRepository::new(self.pool).set(&key, &json).await?;
+        self.get(audiobook_id).await
+    }
+
+    /// Drop a book's own setting so it follows the global one again
+    pub async fn reset_book(&self, audiobook_id: &str) -> Result<VoiceBoost> {
+        PreferencesRepository::new(self.pool).delete(&book_key(audiobook_id)).await?;
+        self.get(Some(audiobook_id)).await
+    }
+
+    async fn read(&self, key: &str) -> Result<Option<VoiceBoostSettings>> {
+        let Some(json) = PreferencesRepository::new(self.pool).get(key).await? else {
+            return Ok(None);
+        };
+        match serde_json::from_str::<VoiceBoostSettings>(&json) {
+            Ok(settings) => Ok(Some(settings.clamped())),
+            Err(e) => {
+                log::warn!("Ignoring invalid voice boost setting in {}: {}", key, e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn book_key(audiobook_id: &str) -> String {
+    format!("{}{}", PREF_BOOK_VOICE_BOOST_PREFIX, audiobook_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_book_voice_boost_overrides_global() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("boost.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let service = VoiceBoostService::new(db.get_pool().unwrap());
+
+        assert!(!service.get(Some("book")).await.unwrap().settings.enabled);
+        service.set(VoiceBoostSettings { enabled: true, amount: 0.3 }, None).await.unwrap();
+
+        let book = service.set(VoiceBoostSettings { enabled: true, amount: 7.0 }, Some("book")).await.unwrap();
+        assert!(book.is_book_specific);
+        assert_eq!(book.settings.amount, 1.0);
+        let other = service.get(Some("other")).await.unwrap();
+        assert!(!other.is_book_specific);
+        assert_eq!(other.settings.amount, 0.3);
+
+        let reset = service.reset_book("book").await.unwrap();
+        assert!(!reset.is_book_specific);
+        assert_eq!(reset.settings, VoiceBoostSettings { enabled: true, amount: 0.3 });
+    }
+}
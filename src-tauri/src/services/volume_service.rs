@@ -0,0 +1,98 @@
+// Books on external drives and network shares that are not mounted. They are
+// reported as offline rather than missing, and the library is checked again
+// when their drive comes back.
+
+use crate::filesystem::volume;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use ts_rs::TS;
+
+/// How often the monitor looks for drives coming and going
+pub const VOLUME_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OfflineVolume {
+    /// Where the drive or share is mounted when it is connected
+    pub path: String,
+    pub audiobook_ids: Vec<String>,
+}
+
+pub struct VolumeService<'a> {
+    pool: &'a SqlitePool,
+}
+
+impl<'a> VolumeService<'a> {
+    pub fn new(pool: &'a SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// The drives and shares holding books that are not mounted now, sorted by path
+    pub async fn offline_volumes(&self) -> Result<Vec<OfflineVolume>> {
+        let books: Vec<(String, String)> = sqlx::query_as("SELECT id, file_path FROM audiobooks")
+            .fetch_all(self.pool)
+            .await
+            .context("Failed to load library file paths")?;
+
+        // An unreachable share can keep a directory listing waiting
+        tokio::task::spawn_blocking(move || group_offline(books, volume::is_mounted))
+            .await
+            .context("Volume check was cancelled")
+    }
+}
+
+/// Group books by the unmounted drive they are on; each drive is checked once
+fn group_offline(books: Vec<(String, String)>, is_mounted: impl Fn(&Path) -> bool) -> Vec<OfflineVolume> {
+    let mut by_root: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for (id, file_path) in books {
+        if let Some(root) = volume::mount_root(Path::new(&file_path)) {
+            by_root.entry(root).or_default().push(id);
+        }
+    }
+    by_root
+        .into_iter()
+        .filter(|(root, _)| !is_mounted(root))
+        .map(|(root, audiobook_ids)| OfflineVolume {
+            path: root.to_string_lossy().to_string(),
+            audiobook_ids,
+        })
+        .collect()
+}
+
+/// The volumes in `current` that were not offline before, and the volumes that
+/// were offline before and are back
+pub fn changes(previous: &[OfflineVolume], current: &[OfflineVolume]) -> (Vec<OfflineVolume>, Vec<OfflineVolume>) {
+    let went_offline = current.iter().filter(|volume| !previous.iter().any(|old| old.path == volume.path)).cloned().collect();
+    let came_back = previous.iter().filter(|volume| !current.iter().any(|new| new.path == volume.path)).cloned().collect();
+    (went_offline, came_back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_offline_by_drive() {
+        let books = vec![
+            ("a".to_string(), "/Volumes/Books/Tolkien/hobbit.m4b".to_string()),
+            ("b".to_string(), "/home/anna/Audiobooks/emma".to_string()),
+            ("c".to_string(), "/Volumes/Books/Austen".to_string()),
+            ("d".to_string(), "/media/anna/USB/dracula.mp3".to_string()),
+        ];
+        let offline = group_offline(books, |root| root == Path::new("/media/anna/USB"));
+        assert_eq!(offline, vec![OfflineVolume { path: "/Volumes/Books".to_string(), audiobook_ids: vec!["a".to_string(), "c".to_string()] }]);
+    }
+
+    #[test]
+    fn test_changes() {
+        let volume = |path: &str| OfflineVolume { path: path.to_string(), audiobook_ids: vec!["a".to_string()] };
+        let (went_offline, came_back) = changes(&[volume("/Volumes/Books"), volume("/mnt/nas")], &[volume("/mnt/nas"), volume("/media/anna/USB")]);
+        assert_eq!(went_offline, vec![volume("/media/anna/USB")]);
+        assert_eq!(came_back, vec![volume("/Volumes/Books")]);
+        assert_eq!(changes(&[], &[]), (vec![], vec![]));
+    }
+}
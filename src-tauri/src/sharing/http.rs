@@ -0,0 +1,217 @@
+// Just enough HTTP/1.1 for LAN sharing: GET and HEAD, one request per
+// connection, and byte ranges so players can seek within chapter files
+
+use anyhow::{anyhow, Context, Result};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+/// Request line and headers together may not be longer than this
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    /// Percent-decoded, without the query
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub range: Option<String>,
+}
+
+impl Request {
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+pub enum Body {
+    Bytes(Vec<u8>),
+    /// `len` bytes of the file from `start`
+    File { path: PathBuf, start: u64, len: u64 },
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn bytes(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self { status, headers: vec![("Content-Type", content_type.to_string())], body: Body::Bytes(body) }
+    }
+
+    pub fn text(status: u16, message: &str) -> Self {
+        Self::bytes(status, "text/plain; charset=utf-8", message.as_bytes().to_vec())
+    }
+
+    pub fn json<T: serde::Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self::bytes(200, "application/json", body),
+            Err(e) => Self::text(500, &e.to_string()),
+        }
+    }
+
+    /// A whole file, or the part a Range header asks for
+    pub fn file(path: PathBuf, content_type: &str, range: Option<&str>) -> Self {
+        let len = match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => return Self::text(404, "File not found"),
+        };
+        let mut headers = vec![("Content-Type", content_type.to_string()), ("Accept-Ranges", "bytes".to_string())];
+        let Some(range) = range else {
+            return Self { status: 200, headers, body: Body::File { path, start: 0, len } };
+        };
+        match parse_range(range, len) {
+            Some((start, end)) => {
+                headers.push(("Content-Range", format!("bytes {}-{}/{}", start, end, len)));
+                Self { status: 206, headers, body: Body::File { path, start, len: end - start + 1 } }
+            }
+            None => {
+                headers.push(("Content-Range", format!("bytes */{}", len)));
+                Self { status: 416, headers, body: Body::Bytes(Vec::new()) }
+            }
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Request line and headers, up to the blank line
+pub async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String> {
+    let mut head = String::new();
+    loop {
+        let read = reader.read_line(&mut head).await.context("Failed to read request")?;
+        if read == 0 || head.ends_with("\r\n\r\n") || head.ends_with("\n\n") {
+            return Ok(head);
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            return Err(anyhow!("Request headers too long"));
+        }
+    }
+}
+
+pub fn parse_request(head: &str) -> Result<Request> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(anyhow!("Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+    let range = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("range"))
+        .map(|(_, value)| value.trim().to_string());
+    Ok(Request { method: method.to_string(), path: percent_decode(path), query, range })
+}
+
+/// Inclusive byte range of a `bytes=` header within a file of `len` bytes.
+/// Only single ranges are supported; players do not ask for more.
+pub fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse().ok()?, len - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(len - 1)),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+pub async fn write_response<W: AsyncWrite + Unpin>(writer: &mut W, response: Response, head_only: bool) -> Result<()> {
+    let len = match &response.body {
+        Body::Bytes(bytes) => bytes.len() as u64,
+        Body::File { len, .. } => *len,
+    };
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", len));
+    writer.write_all(head.as_bytes()).await?;
+    if head_only {
+        return writer.flush().await.map_err(Into::into);
+    }
+
+    match response.body {
+        Body::Bytes(bytes) => writer.write_all(&bytes).await?,
+        Body::File { path, start, len } => {
+            let mut file = tokio::fs::File::open(&path).await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            tokio::io::copy(&mut file.take(len), writer).await?;
+        }
+    }
+    writer.flush().await.map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_and_ranges() {
+        let request = parse_request("GET /books/a%20b?key=s3cret&x=1+2 HTTP/1.1\r\nHost: pc\r\nrange: bytes=100-\r\n\r\n").unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("GET", "/books/a b"));
+        assert_eq!((request.query("key"), request.query("x")), (Some("s3cret"), Some("1 2")));
+        assert_eq!(request.range.as_deref(), Some("bytes=100-"));
+        assert!(parse_request("\r\n").is_err());
+
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=500-5000", 1000), Some((500, 999)), "end is clamped");
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+    }
+}
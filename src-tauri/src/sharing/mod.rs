@@ -0,0 +1,581 @@
+// Read-only library sharing over the local network. A small HTTP server lists
+// the library as JSON and as plain pages a phone browser can use, and streams
+// covers and chapter files with range support so players can seek. Every
+// request must carry a remote access token with the status scope as `?key=`,
+// either the sharing key (itself such a token) or one issued to a client, and
+// is checked and audited through `RemoteAccessService::authorize`. Only files
+// the library database points at are ever served.
+
+use crate::covers;
+use crate::storage;
+use crate::database::content_filter;
+use crate::database::models::Audiobook;
+use crate::database::repository::{AudiobookRepository, ChapterRepository, PreferencesRepository};
+use crate::services::{RemoteAccessService, RemoteScope, SortPreferenceService};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+use ts_rs::TS;
+
+mod http;
+
+use http::{Request, Response};
+
+pub const PREF_LAN_SHARING: &str = "sharing.lan";
+pub const DEFAULT_PORT: u16 = 8765;
+/// Name of the remote access token behind the sharing key
+const SHARING_TOKEN_NAME: &str = "LAN sharing";
+/// Connections served at once; more are closed until one finishes
+const MAX_CONNECTIONS: usize = 32;
+/// How long a client has to send its request line and headers
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LanSharingSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Secret of the status-scoped remote token the share links carry as
+    /// `?key=`; issued once and kept
+    pub key: String,
+    /// That token, so revoking it in the remote access list ends the links
+    pub token_id: String,
+}
+
+impl Default for LanSharingSettings {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT, key: String::new(), token_id: String::new() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LanSharingStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    /// Addresses to open on another device, key included
+    pub urls: Vec<String>,
+    /// Why the server is not running although sharing is enabled
+    pub error: Option<String>,
+}
+
+pub async fn load_settings(pool: &SqlitePool) -> Result<LanSharingSettings> {
+    let stored = PreferencesRepository::new(pool).get(PREF_LAN_SHARING).await?;
+    let mut settings = match stored.as_deref().map(serde_json::from_str::<LanSharingSettings>) {
+        Some(Ok(settings)) => return Ok(settings),
+        Some(Err(e)) => {
+            log::warn!("Ignoring unreadable LAN sharing settings: {}", e);
+            LanSharingSettings::default()
+        }
+        None => LanSharingSettings::default(),
+    };
+    issue_key(pool, &mut settings).await?;
+    Ok(settings)
+}
+
+/// Replace the sharing key, revoking the old one so links handed out with
+/// it stop working
+pub async fn regenerate_key(pool: &SqlitePool) -> Result<LanSharingSettings> {
+    let mut settings = load_settings(pool).await?;
+    RemoteAccessService::new(pool).revoke(&settings.token_id).await?;
+    issue_key(pool, &mut settings).await?;
+    Ok(settings)
+}
+
+async fn issue_key(pool: &SqlitePool, settings: &mut LanSharingSettings) -> Result<()> {
+    let issued = RemoteAccessService::new(pool).issue(SHARING_TOKEN_NAME, vec![RemoteScope::Status]).await?;
+    settings.key = issued.secret;
+    settings.token_id = issued.token.id;
+    // Store the new key straight away so links stay valid across restarts
+    save_settings(pool, settings).await
+}
+
+pub async fn save_settings(pool: &SqlitePool, settings: &LanSharingSettings) -> Result<()> {
+    let json = serde_json::to_string(settings).context("Failed to serialize LAN sharing settings")?;
+    PreferencesRepository::new(pool).set(PREF_LAN_SHARING, &json).await
+}
+
+/// The address other devices reach this machine on, found by asking the OS
+/// which interface it would route outside traffic through. Nothing is sent.
+pub fn local_address() -> Option<std::net::IpAddr> {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|address| address.ip()).filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+pub fn share_urls(port: u16, key: &str) -> Vec<String> {
+    local_address().map(|ip| format!("http://{}:{}/?key={}", ip, port, key)).into_iter().collect()
+}
+
+/// Where a device on the network can fetch a library file from, for handing
+/// playback to a renderer; None for files that are not in the library
+pub async fn stream_url(pool: &SqlitePool, port: u16, key: &str, file_path: &str) -> Result<Option<String>> {
+    let ip = local_address().context("This machine has no network address to share from")?;
+    let chapter_id: Option<String> = sqlx::query_scalar("SELECT id FROM chapters WHERE file_path = ? LIMIT 1")
+        .bind(file_path)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to find chapter by file")?;
+    let path = match chapter_id {
+        Some(id) => format!("/stream/chapters/{}", id),
+        None => {
+            let book_id: Option<String> = sqlx::query_scalar("SELECT id FROM audiobooks WHERE file_path = ? LIMIT 1")
+                .bind(file_path)
+                .fetch_optional(pool)
+                .await
+                .context("Failed to find audiobook by file")?;
+            match book_id {
+                Some(id) => format!("/stream/books/{}", id),
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(format!("http://{}:{}{}?key={}", ip, port, path, key)))
+}
+
+/// A running server; dropping it does not stop it, `stop` does
+pub struct LanServer {
+    pub port: u16,
+    shutdown: tokio::sync::oneshot::Sender<()>,
+}
+
+impl LanServer {
+    pub fn stop(self) {
+        let _ = self.shutdown.send(());
+    }
+}
+
+struct Share {
+    pool: SqlitePool,
+    cover_cache_dir: PathBuf,
+}
+
+/// Bind the port and serve until stopped. Connections already being served
+/// finish on their own. At most MAX_CONNECTIONS are served at once.
+pub async fn start(pool: SqlitePool, settings: &LanSharingSettings, cover_cache_dir: PathBuf) -> Result<LanServer> {
+    let listener = TcpListener::bind(("0.0.0.0", settings.port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", settings.port))?;
+    let port = listener.local_addr()?.port();
+    let share = Arc::new(Share { pool, cover_cache_dir });
+    let (shutdown, mut stopped) = tokio::sync::oneshot::channel();
+    let slots = Arc::new(tokio::sync::Semaphore::new(MAX_CONNECTIONS));
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, address)) => {
+                        let Ok(slot) = slots.clone().try_acquire_owned() else {
+                            log::warn!("LAN sharing is serving {} connections, closing one from {}", MAX_CONNECTIONS, address);
+                            continue;
+                        };
+                        let share = share.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = serve_connection(&share, stream).await {
+                                log::debug!("LAN sharing connection ended: {}", e);
+                            }
+                            drop(slot);
+                        });
+                    }
+                    Err(e) => log::warn!("LAN sharing failed to accept a connection: {}", e),
+                },
+            }
+        }
+        log::info!("LAN sharing stopped");
+    });
+    log::info!("LAN sharing on port {}", port);
+    Ok(LanServer { port, shutdown })
+}
+
+// The LAN sharing server: None when sharing is off, the error when it failed to start
+static SERVER: tokio::sync::Mutex<Option<Result<LanServer, String>>> = tokio::sync::Mutex::const_new(None);
+
+/// Stop any running sharing server and start a new one if sharing is enabled
+pub async fn apply(pool: SqlitePool, settings: &LanSharingSettings) -> LanSharingStatus {
+    let mut server = SERVER.lock().await;
+    if let Some(Ok(running)) = server.take() {
+        running.stop();
+    }
+    if settings.enabled {
+        let cache_dir = storage::paths().covers_dir().join("sized");
+        *server = Some(start(pool, settings, cache_dir).await.map_err(|e| format!("{:#}", e)));
+    }
+    describe(settings, server.as_ref())
+}
+
+/// Whether sharing is on and where it can be reached
+pub async fn status(settings: &LanSharingSettings) -> LanSharingStatus {
+    describe(settings, SERVER.lock().await.as_ref())
+}
+
+/// The port the server is listening on, if it is running
+pub async fn running_port() -> Option<u16> {
+    match SERVER.lock().await.as_ref() {
+        Some(Ok(server)) => Some(server.port),
+        _ => None,
+    }
+}
+
+fn describe(settings: &LanSharingSettings, server: Option<&Result<LanServer, String>>) -> LanSharingStatus {
+    let running = server.and_then(|server| server.as_ref().ok());
+    LanSharingStatus {
+        enabled: settings.enabled,
+        running: running.is_some(),
+        port: running.map(|server| server.port).unwrap_or(settings.port),
+        urls: running.map(|server| share_urls(server.port, &settings.key)).unwrap_or_default(),
+        error: server.and_then(|server| server.as_ref().err().cloned()),
+    }
+}
+
+/// Start the server in the background when sharing was left on
+pub fn start_if_enabled(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        match load_settings(&pool).await {
+            Ok(settings) if settings.enabled => {
+                let status = apply(pool, &settings).await;
+                if let Some(error) = status.error {
+                    log::warn!("LAN sharing could not start: {}", error);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to load LAN sharing settings: {}", e),
+        }
+    });
+}
+
+async fn serve_connection(share: &Share, stream: TcpStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let head = tokio::time::timeout(REQUEST_TIMEOUT, http::read_head(&mut BufReader::new(reader)))
+        .await
+        .context("Timed out waiting for the request")??;
+    let (response, head_only) = match http::parse_request(&head) {
+        Ok(request) => (respond(share, &request).await, request.method == "HEAD"),
+        Err(e) => (Response::text(400, &e.to_string()), false),
+    };
+    http::write_response(&mut writer, response, head_only).await
+}
+
+async fn respond(share: &Share, request: &Request) -> Response {
+    if request.method != "GET" && request.method != "HEAD" {
+        return Response::text(405, "Only GET and HEAD are supported");
+    }
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let action = match segments.as_slice() {
+        ["api", ..] => "sharing.api",
+        ["covers", ..] => "sharing.cover",
+        ["stream", ..] => "sharing.stream",
+        _ => "sharing.page",
+    };
+    let Some(key) = request.query("key") else {
+        return Response::text(403, "Missing or wrong sharing key");
+    };
+    if let Err(e) = RemoteAccessService::new(&share.pool).authorize(key, RemoteScope::Status, action).await {
+        log::debug!("LAN sharing refused {}: {:#}", request.path, e);
+        return Response::text(403, "Missing or wrong sharing key");
+    }
+
+    let result = match segments.as_slice() {
+        [""] => library_page(share, key).await,
+        ["books", id] => book_page(share, key, id).await,
+        ["api", "library"] => library_json(share, key).await,
+        ["api", "books", id] => book_json(share, key, id).await,
+        ["covers", id] => cover(share, id).await,
+        ["stream", "chapters", id] => stream_chapter(share, id, request.range.as_deref()).await,
+        ["stream", "books", id] => stream_book(share, id, request.range.as_deref()).await,
+        _ => Ok(None),
+    };
+    match result {
+        Ok(Some(response)) => response,
+        Ok(None) => Response::text(404, "Not found"),
+        Err(e) => {
+            log::warn!("LAN sharing failed to answer {}: {:#}", request.path, e);
+            Response::text(500, "Something went wrong")
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SharedBook {
+    id: String,
+    title: String,
+    author: Option<String>,
+    narrator: Option<String>,
+    duration: Option<i64>,
+    cover_url: Option<String>,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SharedBookDetail {
+    #[serde(flatten)]
+    book: SharedBook,
+    description: Option<String>,
+    chapters: Vec<SharedChapter>,
+}
+
+#[derive(Debug, Serialize)]
+struct SharedChapter {
+    number: i32,
+    title: String,
+    duration: Option<i64>,
+    stream_url: String,
+}
+
+impl Share {
+    /// Links carry the key the client came with, so a client's token never
+    /// hands it another one
+    fn link(&self, key: &str, path: &str) -> String {
+        format!("{}?key={}", path, key)
+    }
+
+    fn shared_book(&self, key: &str, book: &Audiobook) -> SharedBook {
+        let has_cover = book.cover_image_path.as_deref().is_some_and(|cover| !cover.is_empty());
+        SharedBook {
+            id: book.id.clone(),
+            title: book.title.clone(),
+            author: book.author.clone(),
+            narrator: book.narrator.clone(),
+            duration: book.duration,
+            cover_url: has_cover.then(|| self.link(key, &format!("/covers/{}", book.id))),
+            url: self.link(key, &format!("/api/books/{}", book.id)),
+        }
+    }
+
+    /// A book the content filter lets through
+    async fn visible_book(&self, id: &str) -> Result<Option<Audiobook>> {
+        let book = AudiobookRepository::new(&self.pool).find_by_id(id).await?;
+        Ok(content_filter::apply(book.into_iter().collect()).pop())
+    }
+
+    async fn detail(&self, key: &str, book: &Audiobook) -> Result<SharedBookDetail> {
+        let chapters = ChapterRepository::new(&self.pool).find_by_audiobook_id(&book.id).await?;
+        let mut shared: Vec<SharedChapter> = chapters
+            .iter()
+            .map(|chapter| SharedChapter {
+                number: chapter.chapter_number,
+                title: chapter.title.clone(),
+                duration: chapter.duration,
+                stream_url: self.link(key, &format!("/stream/chapters/{}", chapter.id)),
+            })
+            .collect();
+        if shared.is_empty() && Path::new(&book.file_path).is_file() {
+            shared.push(SharedChapter {
+                number: 1,
+                title: book.title.clone(),
+                duration: book.duration,
+                stream_url: self.link(key, &format!("/stream/books/{}", book.id)),
+            });
+        }
+        Ok(SharedBookDetail { book: self.shared_book(key, book), description: book.description.clone(), chapters: shared })
+    }
+}
+
+async fn library_json(share: &Share, key: &str) -> Result<Option<Response>> {
+    let books = SortPreferenceService::new(&share.pool).library().await?;
+    let shared: Vec<SharedBook> = books.iter().map(|book| share.shared_book(key, book)).collect();
+    Ok(Some(Response::json(&shared)))
+}
+
+async fn book_json(share: &Share, key: &str, id: &str) -> Result<Option<Response>> {
+    let Some(book) = share.visible_book(id).await? else {
+        return Ok(None);
+    };
+    Ok(Some(Response::json(&share.detail(key, &book).await?)))
+}
+
+async fn cover(share: &Share, id: &str) -> Result<Option<Response>> {
+    let Some(book) = share.visible_book(id).await? else {
+        return Ok(None);
+    };
+    let Some(cover) = book.cover_image_path.filter(|cover| !cover.is_empty()) else {
+        return Ok(None);
+    };
+    if cover.starts_with("http://") || cover.starts_with("https://") {
+        // Remote covers are left to the device to fetch
+        return Ok(None);
+    }
+    let (bytes, content_type) = covers::load_cover(&book.id, &cover, Some(512), &share.cover_cache_dir)?;
+    let mut response = Response::bytes(200, &content_type, bytes);
+    response.headers.push(("Cache-Control", "max-age=3600".to_string()));
+    Ok(Some(response))
+}
+
+async fn stream_chapter(share: &Share, id: &str, range: Option<&str>) -> Result<Option<Response>> {
+    let Some(chapter) = ChapterRepository::new(&share.pool).find_by_id(id).await? else {
+        return Ok(None);
+    };
+    if share.visible_book(&chapter.audiobook_id).await?.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(stream_file(&chapter.file_path, range)))
+}
+
+async fn stream_book(share: &Share, id: &str, range: Option<&str>) -> Result<Option<Response>> {
+    let Some(book) = share.visible_book(id).await? else {
+        return Ok(None);
+    };
+    Ok(Path::new(&book.file_path).is_file().then(|| stream_file(&book.file_path, range)))
+}
+
+fn stream_file(file_path: &str, range: Option<&str>) -> Response {
+    let path = PathBuf::from(file_path);
+    let content_type = audio_content_type(&path);
+    Response::file(path, content_type, range)
+}
+
+pub fn audio_content_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("mp3") => "audio/mpeg",
+        Some("m4a" | "m4b" | "mp4" | "aac") => "audio/mp4",
+        Some("flac") => "audio/flac",
+        Some("ogg" | "opus") => "audio/ogg",
+        Some("wav") => "audio/wav",
+        Some("wma") => "audio/x-ms-wma",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn library_page(share: &Share, key: &str) -> Result<Option<Response>> {
+    let books = SortPreferenceService::new(&share.pool).library().await?;
+    let mut body = String::from("<h1>AudioVibe library</h1>\n<ul>\n");
+    for book in &books {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>{}</li>\n",
+            share.link(key, &format!("/books/{}", book.id)),
+            escape_html(&book.title),
+            book.author.as_deref().map(|author| format!(" &middot; {}", escape_html(author))).unwrap_or_default(),
+        ));
+    }
+    body.push_str("</ul>\n");
+    Ok(Some(html_page("AudioVibe library", &body)))
+}
+
+async fn book_page(share: &Share, key: &str, id: &str) -> Result<Option<Response>> {
+    let Some(book) = share.visible_book(id).await? else {
+        return Ok(None);
+    };
+    let detail = share.detail(key, &book).await?;
+    let mut body = format!("<p><a href=\"{}\">&larr; Library</a></p>\n", share.link(key, "/"));
+    if let Some(cover_url) = &detail.book.cover_url {
+        body.push_str(&format!("<img src=\"{}\" alt=\"\" width=\"240\">\n", cover_url));
+    }
+    body.push_str(&format!("<h1>{}</h1>\n", escape_html(&book.title)));
+    if let Some(author) = &book.author {
+        body.push_str(&format!("<p>{}</p>\n", escape_html(author)));
+    }
+    body.push_str("<ol>\n");
+    for chapter in &detail.chapters {
+        body.push_str(&format!(
+            "<li>{}<br><audio controls preload=\"none\" src=\"{}\"></audio></li>\n",
+            escape_html(&chapter.title),
+            chapter.stream_url,
+        ));
+    }
+    body.push_str("</ol>\n");
+    Ok(Some(html_page(&book.title, &body)))
+}
+
+fn html_page(title: &str, body: &str) -> Response {
+    let page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>{}</title><style>body{{font-family:sans-serif;max-width:40em;margin:auto;padding:1em}}audio{{width:100%}}li{{margin:.5em 0}}</style>\
+         </head><body>\n{}</body></html>\n",
+        escape_html(title),
+        body
+    );
+    Response::bytes(200, "text/html; charset=utf-8", page.into_bytes())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{CreateAudiobookDto, CreateChapterDto};
+    use crate::database::DatabaseManager;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn get(port: u16, request: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_shares_library_and_streams_ranges_with_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("share.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap().clone();
+
+        let chapter_file = dir.path().join("01.mp3");
+        std::fs::write(&chapter_file, b"0123456789").unwrap();
+        let book = AudiobookRepository::new(&pool).create(CreateAudiobookDto {
+            title: "Tom & Jerry".to_string(),
+            file_path: dir.path().to_string_lossy().to_string(),
+            author: None,
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: None,
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        }).await.unwrap();
+        let chapter = ChapterRepository::new(&pool).create(CreateChapterDto {
+            audiobook_id: book.id.clone(),
+            chapter_number: 1,
+            title: "Opening".to_string(),
+            file_path: chapter_file.to_string_lossy().to_string(),
+            duration: Some(10),
+            file_size: Some(10),
+        }).await.unwrap();
+
+        let settings = load_settings(&pool).await.unwrap();
+        assert_eq!(load_settings(&pool).await.unwrap(), settings);
+        let k = settings.key.clone();
+        let remote = RemoteAccessService::new(&pool);
+        let server = start(pool.clone(), &settings, dir.path().join("sized")).await.unwrap();
+
+        assert!(get(server.port, "GET /api/library HTTP/1.1\r\n\r\n").await.starts_with("HTTP/1.1 403"));
+
+        let library = get(server.port, &format!("GET /api/library?key={} HTTP/1.1\r\n\r\n", k)).await;
+        assert!(library.starts_with("HTTP/1.1 200") && library.contains("\"Tom & Jerry\""), "{}", library);
+        let page = get(server.port, &format!("GET /books/{}?key={} HTTP/1.1\r\n\r\n", book.id, k)).await;
+        assert!(page.contains("Tom &amp; Jerry") && page.contains(&format!("/stream/chapters/{}?key={}", chapter.id, k)));
+
+        let partial = get(server.port, &format!("GET /stream/chapters/{}?key={} HTTP/1.1\r\nRange: bytes=4-\r\n\r\n", chapter.id, k)).await;
+        assert!(partial.starts_with("HTTP/1.1 206") && partial.contains("Content-Range: bytes 4-9/10"), "{}", partial);
+        assert!(partial.ends_with("\r\n\r\n456789"));
+        assert!(get(server.port, &format!("GET /stream/chapters/nope?key={} HTTP/1.1\r\n\r\n", k)).await.starts_with("HTTP/1.1 404"));
+
+        // A client's own token works and its links carry that token, not the sharing key
+        let phone = remote.issue("Phone", vec![RemoteScope::Status]).await.unwrap();
+        let listed = get(server.port, &format!("GET /api/library?key={} HTTP/1.1\r\n\r\n", phone.secret)).await;
+        assert!(listed.contains(&phone.secret) && !listed.contains(&k), "{}", listed);
+        let controller = remote.issue("Remote", vec![RemoteScope::Transport]).await.unwrap();
+        assert!(get(server.port, &format!("GET /api/library?key={} HTTP/1.1\r\n\r\n", controller.secret)).await.starts_with("HTTP/1.1 403"));
+
+        let regenerated = regenerate_key(&pool).await.unwrap();
+        assert_ne!(regenerated.key, k);
+        assert!(get(server.port, &format!("GET /api/library?key={} HTTP/1.1\r\n\r\n", k)).await.starts_with("HTTP/1.1 403"));
+        let audit = remote.audit_log(20).await.unwrap();
+        assert_eq!(audit[0].reason.as_deref(), Some("Token revoked"));
+        let library = get(server.port, &format!("GET /api/library?key={} HTTP/1.1\r\n\r\n", regenerated.key)).await;
+        assert!(library.starts_with("HTTP/1.1 200"));
+        assert!(audit.iter().any(|entry| entry.action == "sharing.stream" && entry.allowed));
+        server.stop();
+    }
+}
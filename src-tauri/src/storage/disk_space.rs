@@ -0,0 +1,151 @@
+// Free-space checks before operations that write a lot. Downloads, TTS
+// generation and exports estimate what they need and fail up front instead of
+// filling the disk halfway, and the frontend is warned once the library or
+// cache volume runs low.
+
+use crate::events::{self, AppEvent};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use ts_rs::TS;
+
+/// Kept free on top of what an operation needs, for the database and logs
+pub const HEADROOM_BYTES: u64 = 100 * 1024 * 1024;
+/// Below this the library or cache volume is reported as low on space
+pub const LOW_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Generated speech per character of text: WAV at 24 kHz, 16-bit mono is
+/// 48 KB a second, and speech runs at about 15 characters a second
+pub const TTS_BYTES_PER_CHAR: u64 = 3_200;
+
+/// Folders already warned about, until their volume has room again
+static WARNED: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+/// Why an operation was refused; also sent as the insufficient-disk-space event
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct InsufficientSpace {
+    pub path: String,
+    #[ts(type = "number")]
+    pub required_bytes: u64,
+    #[ts(type = "number")]
+    pub available_bytes: u64,
+}
+
+impl std::fmt::Display for InsufficientSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not enough disk space on {}: {} needed, {} free",
+            self.path,
+            format_bytes(self.required_bytes),
+            format_bytes(self.available_bytes)
+        )
+    }
+}
+
+impl std::error::Error for InsufficientSpace {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct LowDiskSpace {
+    pub path: String,
+    #[ts(type = "number")]
+    pub available_bytes: u64,
+    #[ts(type = "number")]
+    pub threshold_bytes: u64,
+}
+
+/// Free space on the volume holding `path`. The path need not exist yet; its
+/// nearest existing parent is asked instead.
+pub fn available_bytes(path: &Path) -> std::io::Result<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists()).unwrap_or(path);
+    fs2::available_space(existing)
+}
+
+/// Refuse an operation that would write `required_bytes` under `path` when the
+/// volume lacks that plus HEADROOM_BYTES. When the free space cannot be read the
+/// operation goes ahead.
+pub fn ensure_space(path: &Path, required_bytes: u64) -> Result<(), InsufficientSpace> {
+    let Ok(available) = available_bytes(path) else {
+        return Ok(());
+    };
+    if available >= required_bytes.saturating_add(HEADROOM_BYTES) {
+        return Ok(());
+    }
+
+    let shortfall = InsufficientSpace {
+        path: path.to_string_lossy().to_string(),
+        required_bytes,
+        available_bytes: available,
+    };
+    log::warn!("{}", shortfall);
+    events::emit(AppEvent::InsufficientDiskSpace(shortfall.clone()));
+    Err(shortfall)
+}
+
+/// Send a low-disk-space event when the volume of `dir` has dropped below
+/// LOW_SPACE_BYTES. Each folder is warned about once until it has room again.
+pub fn warn_if_low(dir: &Path) {
+    let Ok(available) = available_bytes(dir) else {
+        return;
+    };
+    let mut warned = WARNED.lock().unwrap();
+    let warned = warned.get_or_insert_with(HashSet::new);
+    if available >= LOW_SPACE_BYTES {
+        warned.remove(dir);
+        return;
+    }
+    if warned.insert(dir.to_path_buf()) {
+        log::warn!("Only {} free for {}", format_bytes(available), dir.display());
+        events::emit(AppEvent::LowDiskSpace(LowDiskSpace {
+            path: dir.to_string_lossy().to_string(),
+            available_bytes: available,
+            threshold_bytes: LOW_SPACE_BYTES,
+        }));
+    }
+}
+
+/// Sizes for messages: "512 KB", "1.5 GB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{:.1} {}", value, UNITS[unit])
+    } else {
+        format!("{:.0} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_yet_created = dir.path().join("export").join("book");
+        assert!(available_bytes(&not_yet_created).unwrap() > 0);
+        assert!(ensure_space(&not_yet_created, 0).is_ok());
+
+        let error = ensure_space(dir.path(), u64::MAX / 2).unwrap_err();
+        assert_eq!(error.required_bytes, u64::MAX / 2);
+        assert!(error.to_string().starts_with("Not enough disk space"));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 bytes");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(300 * 1024 * 1024), "300 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GB");
+    }
+}
@@ -0,0 +1,274 @@
+// Moving a library between installed and portable storage, and following a
+// portable copy whose folder moved (a USB stick mounted under another drive
+// letter). The database stores absolute paths, so the paths under the old
+// folders are rewritten to the new ones.
+
+use super::{StorageMode, StoragePaths, DATABASE_FILE};
+use crate::database::repository::PreferencesRepository;
+use crate::services::LibraryRootService;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use std::path::Path;
+use ts_rs::TS;
+
+/// Where data and cache lived on the last run, to notice the folders moving
+pub const PREF_LAST_DATA_DIR: &str = "storage.last_data_dir";
+pub const PREF_LAST_CACHE_DIR: &str = "storage.last_cache_dir";
+
+/// Every column holding a path to a file the app stores or imported
+const PATH_COLUMNS: [(&str, &str); 12] = [
+    ("audiobooks", "file_path"),
+    ("audiobooks", "cover_image_path"),
+    ("chapters", "file_path"),
+    ("collections", "cover_image_path"),
+    ("ebooks", "file_path"),
+    ("ebooks", "cover_path"),
+    ("documents", "file_path"),
+    ("file_fingerprints", "file_path"),
+    ("tts_timings", "file_path"),
+    ("file_validations", "file_path"),
+    ("audio_frame_indexes", "file_path"),
+    ("chapter_errors", "file_path"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageMigrationReport {
+    pub from: StorageMode,
+    pub to: StorageMode,
+    pub files_copied: usize,
+    #[ts(type = "number")]
+    pub bytes_copied: u64,
+    #[ts(type = "number")]
+    pub paths_rewritten: u64,
+    /// The copy is only used after the app is started again
+    pub restart_required: bool,
+}
+
+/// Copy the library from `from` to `to`: data and download cache files, then a
+/// consistent copy of the database with its paths pointed at the new folders.
+/// The old files stay where they are until the user removes them.
+pub async fn migrate(pool: &SqlitePool, from: &StoragePaths, to: &StoragePaths) -> Result<StorageMigrationReport> {
+    let same_data_dir = from.data_dir == to.data_dir;
+    if !same_data_dir && to.database_file().exists() {
+        return Err(anyhow!(
+            "{} already contains a library; move or delete it before switching storage",
+            to.data_dir.display()
+        ));
+    }
+
+    let mut report = StorageMigrationReport {
+        from: from.mode,
+        to: to.mode,
+        files_copied: 0,
+        bytes_copied: 0,
+        paths_rewritten: 0,
+        restart_required: true,
+    };
+
+    if !same_data_dir {
+        copy_dir(&from.data_dir, &to.data_dir, &is_database_file, &mut report)?;
+    }
+    let (from_cache, to_cache) = (from.download_cache_dir(), to.download_cache_dir());
+    if from_cache != to_cache {
+        copy_dir(&from_cache, &to_cache, &|_| false, &mut report)?;
+    }
+
+    if same_data_dir {
+        report.paths_rewritten = relocate_paths(pool, &from.cache_dir, &to.cache_dir).await?;
+        return Ok(report);
+    }
+
+    std::fs::create_dir_all(&to.data_dir).context("Failed to create data folder")?;
+    let database_file = to.database_file();
+    sqlx::query("VACUUM INTO ?")
+        .bind(database_file.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+        .context("Failed to copy database")?;
+
+    let copy = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&format!("sqlite:{}", database_file.display()))
+        .await
+        .context("Failed to open copied database")?;
+    let rewritten = async {
+        Ok::<_, anyhow::Error>(
+            relocate_paths(&copy, &from.data_dir, &to.data_dir).await?
+                + relocate_paths(&copy, &from.cache_dir, &to.cache_dir).await?,
+        )
+    }
+    .await;
+    copy.close().await;
+    report.paths_rewritten = rewritten?;
+
+    log::info!(
+        "Storage migrated from {:?} to {:?}: {} files, {} bytes, {} paths rewritten",
+        from.mode, to.mode, report.files_copied, report.bytes_copied, report.paths_rewritten
+    );
+    Ok(report)
+}
+
+/// Rewrite stored paths when the folders of this run differ from the last
+/// run's, then remember the current ones. Returns the number of paths changed.
+pub async fn follow_moved_folders(pool: &SqlitePool, paths: &StoragePaths) -> Result<u64> {
+    let prefs = PreferencesRepository::new(pool);
+    let mut rewritten = 0;
+    for (key, current) in [(PREF_LAST_DATA_DIR, &paths.data_dir), (PREF_LAST_CACHE_DIR, &paths.cache_dir)] {
+        let current_str = current.to_string_lossy();
+        match prefs.get(key).await? {
+            Some(last) if last == current_str => continue,
+            Some(last) => {
+                log::info!("Storage folder moved from {} to {}", last, current_str);
+                rewritten += relocate_paths(pool, Path::new(&last), current).await?;
+            }
+            None => {}
+        }
+        prefs.set(key, &current_str).await?;
+    }
+    Ok(rewritten)
+}
+
+/// Point every stored path and library root under `old` at the same place
+/// under `new`. Returns the number of database rows changed.
+pub async fn relocate_paths(pool: &SqlitePool, old: &Path, new: &Path) -> Result<u64> {
+    let (old, new) = (old.to_string_lossy().to_string(), new.to_string_lossy().to_string());
+    if old == new || old.is_empty() {
+        return Ok(0);
+    }
+
+    // substr() counts characters, and only whole path components match
+    let rest_from = old.chars().count() as i64 + 1;
+    let mut changed = 0;
+    for (table, column) in PATH_COLUMNS {
+        let result = sqlx::query(&format!(
+            "UPDATE {table} SET {column} = ?2 || substr({column}, ?3)
+             WHERE {column} = ?1 OR substr({column}, 1, ?3) IN (?1 || '/', ?1 || '\\')"
+        ))
+        .bind(&old)
+        .bind(&new)
+        .bind(rest_from)
+        .execute(pool)
+        .await
+        .with_context(|| format!("Failed to relocate {}.{}", table, column))?;
+        changed += result.rows_affected();
+    }
+
+    LibraryRootService::new(pool).relocate(Path::new(&old), Path::new(&new)).await?;
+    Ok(changed)
+}
+
+/// The live database and its journal files, which are copied through VACUUM INTO instead
+fn is_database_file(relative: &Path) -> bool {
+    relative.to_str().is_some_and(|name| name.starts_with(DATABASE_FILE) && !name.contains(['/', '\\']))
+}
+
+fn copy_dir(from: &Path, to: &Path, skip: &dyn Fn(&Path) -> bool, report: &mut StorageMigrationReport) -> Result<()> {
+    if !from.is_dir() {
+        return Ok(());
+    }
+    copy_tree(from, from, to, skip, report)
+}
+
+fn copy_tree(root: &Path, dir: &Path, to: &Path, skip: &dyn Fn(&Path) -> bool, report: &mut StorageMigrationReport) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let path = dir.join(entry.file_name());
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let file_type = entry.file_type()?;
+        if skip(relative) || file_type.is_symlink() {
+            continue;
+        }
+
+        let target = to.join(relative);
+        if file_type.is_dir() {
+            copy_tree(root, &path, to, skip, report)?;
+        } else if !target.exists() {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            report.bytes_copied += std::fs::copy(&path, &target).with_context(|| format!("Failed to copy {}", path.display()))?;
+            report.files_copied += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseManager;
+
+    #[tokio::test]
+    async fn test_migrate_copies_files_and_rewrites_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = StoragePaths::installed(&dir.path().join("work"), Some(dir.path().join("system-cache")));
+        let to = StoragePaths::portable(&dir.path().join("usb"));
+
+        let mut db = DatabaseManager::new(from.database_file().to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap().clone();
+
+        let cover = from.data_dir.join("covers").join("book.jpg");
+        let chapter = from.download_cache_dir().join("book").join("01.mp3");
+        for (path, data) in [(&cover, "jpg"), (&chapter, "mp3")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+        let lookalike = format!("{}-other/01.mp3", from.cache_dir.display());
+        sqlx::query("INSERT INTO audiobooks (id, title, file_path, cover_image_path, added_date) VALUES ('a', 'A', ?, ?, ''), ('b', 'B', ?, NULL, '')")
+            .bind(chapter.parent().unwrap().to_string_lossy().to_string())
+            .bind(cover.to_string_lossy().to_string())
+            .bind(&lookalike)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let report = migrate(&pool, &from, &to).await.unwrap();
+        assert_eq!(report.files_copied, 2);
+        assert_eq!(report.paths_rewritten, 2);
+        assert!(to.data_dir.join("covers").join("book.jpg").is_file());
+        assert!(to.download_cache_dir().join("book").join("01.mp3").is_file());
+
+        let copy = SqlitePoolOptions::new().connect(&format!("sqlite:{}", to.database_file().display())).await.unwrap();
+        let rows: Vec<(String, Option<String>)> = sqlx::query_as("SELECT file_path, cover_image_path FROM audiobooks ORDER BY id")
+            .fetch_all(&copy)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].0, to.download_cache_dir().join("book").to_string_lossy());
+        assert_eq!(rows[0].1.as_deref(), Some(to.data_dir.join("covers").join("book.jpg").to_string_lossy().as_ref()));
+        assert_eq!(rows[1].0, lookalike);
+
+        assert!(migrate(&pool, &from, &to).await.is_err(), "an existing library is never overwritten");
+        assert_eq!(follow_moved_folders(&copy, &to).await.unwrap(), 0);
+        let moved = StoragePaths::portable(&dir.path().join("usb-remounted"));
+        assert_eq!(follow_moved_folders(&copy, &moved).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_every_path_column_is_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = DatabaseManager::new(dir.path().join("library.db").to_string_lossy().to_string());
+        db.initialize().await.unwrap();
+        let pool = db.get_pool().unwrap();
+
+        let columns: Vec<(String, String)> = sqlx::query_as(
+            "SELECT m.name, c.name FROM sqlite_master m, pragma_table_info(m.name) c
+             WHERE m.type = 'table' AND (c.name = 'path' OR c.name LIKE '%\\_path' ESCAPE '\\')",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        assert!(!columns.is_empty());
+        for (table, column) in columns {
+            assert!(
+                PATH_COLUMNS.contains(&(table.as_str(), column.as_str())),
+                "{}.{} holds a path but is not in PATH_COLUMNS",
+                table,
+                column
+            );
+        }
+    }
+}
@@ -0,0 +1,245 @@
+// Where the app keeps its files. An installed copy uses the data folder in its
+// working directory and the system cache; a portable copy keeps the database,
+// covers, downloads and logs next to the executable so it can run from a USB
+// stick. Portable mode is switched on by a marker file beside the executable
+// or by starting the app with --portable.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use ts_rs::TS;
+
+pub mod disk_space;
+pub mod migrate;
+
+/// A file with this name next to the executable turns on portable mode
+pub const PORTABLE_MARKER: &str = "portable";
+pub const PORTABLE_ARG: &str = "--portable";
+pub const DATABASE_FILE: &str = "audiovibe.db";
+pub const LOG_FILE: &str = "audiovibe.log";
+/// A log larger than this is moved aside at startup, keeping one old log
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum StorageMode {
+    Installed,
+    Portable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoragePaths {
+    pub mode: StorageMode,
+    /// Database, covers, backups and everything else the library owns
+    pub data_dir: PathBuf,
+    /// Downloads and other files that can be fetched again
+    pub cache_dir: PathBuf,
+    pub logs_dir: PathBuf,
+}
+
+impl StoragePaths {
+    pub fn installed(working_dir: &Path, system_cache_dir: Option<PathBuf>) -> Self {
+        let data_dir = working_dir.join("data");
+        Self {
+            mode: StorageMode::Installed,
+            cache_dir: system_cache_dir.map(|dir| dir.join("audiovibe")).unwrap_or_else(|| working_dir.join("cache")),
+            logs_dir: data_dir.join("logs"),
+            data_dir,
+        }
+    }
+
+    pub fn portable(exe_dir: &Path) -> Self {
+        Self {
+            mode: StorageMode::Portable,
+            data_dir: exe_dir.join("data"),
+            cache_dir: exe_dir.join("cache"),
+            logs_dir: exe_dir.join("logs"),
+        }
+    }
+
+    /// The paths of `mode` on this machine
+    pub fn for_mode(mode: StorageMode) -> std::io::Result<Self> {
+        Ok(match mode {
+            StorageMode::Installed => Self::installed(&std::env::current_dir()?, dirs::cache_dir()),
+            StorageMode::Portable => Self::portable(&exe_dir()?),
+        })
+    }
+
+    pub fn database_file(&self) -> PathBuf {
+        self.data_dir.join(DATABASE_FILE)
+    }
+
+    /// LibriVox and Archive.org downloads, one subfolder per book
+    pub fn download_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("librivox")
+    }
+
+    /// Local copies of chapters played from network shares
+    pub fn network_cache_dir(&self) -> PathBuf {
+        self.cache_dir.join("network")
+    }
+
+    /// Ambience loops the user added, played under the book
+    pub fn ambience_dir(&self) -> PathBuf {
+        self.data_dir.join("ambience")
+    }
+
+    /// Saved and generated covers, with resized copies under `sized`
+    pub fn covers_dir(&self) -> PathBuf {
+        self.data_dir.join("covers")
+    }
+
+    /// Database backups taken before migrations and by maintenance
+    pub fn backups_dir(&self) -> PathBuf {
+        self.data_dir.join("backups")
+    }
+
+    pub fn log_file(&self) -> PathBuf {
+        self.logs_dir.join(LOG_FILE)
+    }
+
+    /// The log file of this run, opened for appending
+    pub fn open_log_file(&self) -> std::io::Result<File> {
+        std::fs::create_dir_all(&self.logs_dir)?;
+        let path = self.log_file();
+        if std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() > MAX_LOG_BYTES) {
+            std::fs::rename(&path, path.with_extension("log.1"))?;
+        }
+        std::fs::OpenOptions::new().create(true).append(true).open(path)
+    }
+}
+
+/// Storage folders as shown in settings
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StorageInfo {
+    pub mode: StorageMode,
+    pub data_dir: String,
+    pub cache_dir: String,
+    pub logs_dir: String,
+    /// Set when portable mode comes from the command line; the marker alone
+    /// cannot turn it off then
+    pub forced_by_argument: bool,
+}
+
+impl From<&StoragePaths> for StorageInfo {
+    fn from(paths: &StoragePaths) -> Self {
+        Self {
+            mode: paths.mode,
+            data_dir: paths.data_dir.to_string_lossy().to_string(),
+            cache_dir: paths.cache_dir.to_string_lossy().to_string(),
+            logs_dir: paths.logs_dir.to_string_lossy().to_string(),
+            forced_by_argument: std::env::args().any(|arg| arg == PORTABLE_ARG),
+        }
+    }
+}
+
+/// Log output written to both stderr and the log file
+pub struct LogTee {
+    file: File,
+}
+
+impl LogTee {
+    pub fn new(file: File) -> Self {
+        Self { file }
+    }
+}
+
+impl Write for LogTee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let _ = std::io::stderr().write_all(buf);
+        self.file.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let _ = std::io::stderr().flush();
+        self.file.flush()
+    }
+}
+
+/// Portable when the marker sits next to the executable or `--portable` was passed
+pub fn detect_mode(exe_dir: &Path, args: impl IntoIterator<Item = String>) -> StorageMode {
+    if args.into_iter().any(|arg| arg == PORTABLE_ARG) || exe_dir.join(PORTABLE_MARKER).is_file() {
+        StorageMode::Portable
+    } else {
+        StorageMode::Installed
+    }
+}
+
+pub fn exe_dir() -> std::io::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    exe.parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "Executable has no parent folder"))
+}
+
+static PATHS: OnceLock<StoragePaths> = OnceLock::new();
+
+/// The storage paths of this run, decided once at startup
+pub fn paths() -> &'static StoragePaths {
+    PATHS.get_or_init(|| {
+        let exe_dir = exe_dir().unwrap_or_default();
+        let mode = detect_mode(&exe_dir, std::env::args().skip(1));
+        StoragePaths::for_mode(mode).unwrap_or_else(|e| {
+            log::warn!("Failed to resolve {:?} storage folders, using the working directory: {}", mode, e);
+            StoragePaths::installed(Path::new("."), None)
+        })
+    })
+}
+
+/// Send logs to stderr and to the log file of this run; false when a logger
+/// was already set up
+pub fn init_logging() -> bool {
+    let mut builder = env_logger::Builder::from_default_env();
+    match paths().open_log_file() {
+        Ok(file) => {
+            builder.target(env_logger::Target::Pipe(Box::new(LogTee::new(file))));
+        }
+        Err(e) => eprintln!("Failed to open log file, logging to stderr only: {}", e),
+    }
+    builder.try_init().is_ok()
+}
+
+/// Turn portable mode on or off for the next start by writing or removing the marker
+pub fn set_portable_marker(exe_dir: &Path, portable: bool) -> std::io::Result<()> {
+    let marker = exe_dir.join(PORTABLE_MARKER);
+    if portable {
+        std::fs::write(marker, b"AudioVibe keeps its data next to the executable while this file exists\n")
+    } else if marker.exists() {
+        std::fs::remove_file(marker)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_mode_and_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect_mode(dir.path(), Vec::new()), StorageMode::Installed);
+        assert_eq!(detect_mode(dir.path(), vec!["--portable".to_string()]), StorageMode::Portable);
+
+        set_portable_marker(dir.path(), true).unwrap();
+        assert_eq!(detect_mode(dir.path(), Vec::new()), StorageMode::Portable);
+        set_portable_marker(dir.path(), false).unwrap();
+        set_portable_marker(dir.path(), false).unwrap();
+        assert_eq!(detect_mode(dir.path(), Vec::new()), StorageMode::Installed);
+
+        let portable = StoragePaths::portable(Path::new("/media/usb/AudioVibe"));
+        assert_eq!(portable.database_file(), PathBuf::from("/media/usb/AudioVibe/data/audiovibe.db"));
+        assert_eq!(portable.download_cache_dir(), PathBuf::from("/media/usb/AudioVibe/cache/librivox"));
+        assert_eq!(portable.log_file(), PathBuf::from("/media/usb/AudioVibe/logs/audiovibe.log"));
+
+        let installed = StoragePaths::installed(Path::new("/opt/audiovibe"), Some(PathBuf::from("/home/me/.cache")));
+        assert_eq!(installed.database_file(), PathBuf::from("/opt/audiovibe/data/audiovibe.db"));
+        assert_eq!(installed.download_cache_dir(), PathBuf::from("/home/me/.cache/audiovibe/librivox"));
+        assert_eq!(StoragePaths::installed(Path::new("/opt/audiovibe"), None).cache_dir, PathBuf::from("/opt/audiovibe/cache"));
+    }
+}
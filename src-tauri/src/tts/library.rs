@@ -0,0 +1,217 @@
+// Turning documents into TTS books in the library: processing a document with
+// the saved cleaning and chunking settings, and creating the audiobook and
+// placeholder chapters its audio is generated into.
+
+use crate::database::models::{Audiobook, CreateAudiobookDto, CreateChapterDto};
+use crate::database::repository::{AudiobookRepository, ChapterRepository, TtsChapterSourceRepository};
+use crate::document::chunking::ChunkingOptions;
+use crate::document::cleaning::TextCleaningOptions;
+use crate::document::{ocr as document_ocr, DocumentProcessor, ProcessedDocument};
+use crate::events::{self, AppEvent, LibraryChange};
+use crate::services::{audiobook_source_service, ChapterTextService, DocumentService};
+use crate::{filesystem, storage};
+
+/// Resolve cleaning and chunking settings, then extract and divide the document
+/// off the async runtime. Scanned PDFs go through OCR, which takes seconds per page.
+/// The path is trusted as given: commands resolve paths from the frontend
+/// against the library folders first, the inbox and articles pass their own files.
+pub async fn process_document(
+    pool: &sqlx::SqlitePool,
+    file_path: &str,
+    cleaning: Option<TextCleaningOptions>,
+    chunking: Option<ChunkingOptions>,
+    ocr_language: Option<String>
+) -> Result<(ProcessedDocument, ChunkingOptions), String> {
+    // Text is cleaned here so what the user previews is what gets synthesized
+    let cleaning = match cleaning {
+        Some(options) => options,
+        None => ChapterTextService::new(pool).load_options().await,
+    };
+    let chunking = match chunking {
+        Some(options) => options,
+        None => DocumentService::new(pool).chunking_for(file_path).await.map_err(|e| e.to_string())?,
+    };
+    chunking.validate().map_err(|e| e.to_string())?;
+
+    let processor = DocumentProcessor::new()
+        .with_ocr_language(ocr_language.as_deref().unwrap_or(document_ocr::DEFAULT_LANGUAGE))
+        .on_ocr_progress(|progress| events::emit(AppEvent::OcrProgress(progress)));
+    let path = file_path.to_string();
+    let document = tauri::async_runtime::spawn_blocking(move || {
+        processor.process_document(&path, &chunking, &cleaning)
+    })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| {
+            println!("DOCUMENT: Failed to process {}: {}", file_path, e);
+            e.to_string()
+        })?;
+    Ok((document, chunking))
+}
+
+/// Create the audiobook and placeholder chapter records a TTS book is generated into.
+/// `document_id` links the book to the stored document it was generated from.
+pub async fn create_audiobook_record(
+    pool: &sqlx::SqlitePool,
+    title: String,
+    author: Option<String>,
+    chapters: Vec<serde_json::Value>,
+    voice: Option<String>,
+    document_id: Option<String>
+) -> Result<Audiobook, String> {
+    println!("🎤 TTS: Creating TTS audiobook: {} by {:?}", title, author);
+    
+    // Generate unique audiobook ID
+    let audiobook_id = uuid::Uuid::new_v4().to_string();
+    
+    // Refuse a book whose generated audio cannot fit before any of it is synthesized
+    let output_dir = storage::paths().data_dir.join("audiobook_output").join(&audiobook_id);
+    let text_chars: usize = chapters.iter()
+        .filter_map(|chapter| chapter.get("text").and_then(|v| v.as_str()))
+        .map(|text| text.chars().count())
+        .sum();
+    storage::disk_space::ensure_space(&output_dir, text_chars as u64 * storage::disk_space::TTS_BYTES_PER_CHAR)
+        .map_err(|e| e.to_string())?;
+    
+    // Create output directory
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    
+    // Generate a simple cover image for TTS audiobooks
+    let cover_image_path = generate_cover(&title, &author, &audiobook_id).await
+        .unwrap_or_else(|e| {
+            println!("TTS: Failed to generate cover: {}", e);
+            None
+        });
+    
+    // Create audiobook record with a placeholder file path that will be updated later
+    // For TTS audiobooks, we'll store the directory path for now and update with first audio file later
+    let audiobook_dto = CreateAudiobookDto {
+        title: title.clone(),
+        author: author.clone(),
+        narrator: Some("Generated via TTS".to_string()),
+        description: Some("Generated via Text-to-Speech".to_string()),
+        genre: Some("TTS Generated".to_string()),
+        file_path: output_dir.to_string_lossy().to_string(), // Directory path - will be updated when first audio file is saved
+        duration: None, // Will be calculated after all chapters are saved
+        cover_image_path,
+        source_type: Some(audiobook_source_service::SOURCE_TTS.to_string()),
+        source_id: document_id,
+    };
+    
+    let audiobook_repo = AudiobookRepository::new(pool);
+    let audiobook = audiobook_repo.create(audiobook_dto).await
+        .map_err(|e| format!("Failed to create audiobook: {}", e))?;
+    
+    // Create chapter records based on the input chapters
+    let chapter_repo = ChapterRepository::new(pool);
+    let source_repo = TtsChapterSourceRepository::new(pool);
+    for (index, chapter_data) in chapters.iter().enumerate() {
+        let default_title = format!("Chapter {}", index + 1);
+        let chapter_title = chapter_data.get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&default_title);
+            
+        // Create placeholder file path following TTS naming convention
+        let placeholder_path = output_dir.join(format!("chapter_{}_chunk_1.wav", index + 1));
+        
+        let chapter_dto = CreateChapterDto {
+            audiobook_id: audiobook.id.clone(),
+            chapter_number: (index + 1) as i32,
+            title: chapter_title.to_string(),
+            file_path: placeholder_path.to_string_lossy().to_string(),
+            duration: None,
+            file_size: None,
+        };
+        
+        match chapter_repo.create(chapter_dto).await {
+            Ok(chapter) => {
+                println!("TTS: Created chapter {} record", index + 1);
+                // Keep the text so the chapter can be regenerated on its own later
+                if let Some(text) = chapter_data.get("text").and_then(|v| v.as_str()) {
+                    if let Err(e) = source_repo.upsert(&chapter.id, text, voice.as_deref(), 1.0).await {
+                        log::warn!("TTS: Failed to store text for chapter {}: {}", index + 1, e);
+                    }
+                }
+            }
+            Err(e) => println!("TTS: Failed to create chapter {} record: {}", index + 1, e),
+        }
+    }
+    
+    println!("TTS: Created audiobook record with ID: {}", audiobook.id);
+    events::emit(AppEvent::library_changed(LibraryChange::Added, &audiobook.id));
+    Ok(audiobook)
+}
+
+async fn generate_cover(
+    title: &str,
+    author: &Option<String>,
+    audiobook_id: &str,
+) -> Result<Option<String>, String> {
+    use base64::{Engine as _, engine::general_purpose};
+    
+    println!("🎨 TTS COVER: Generating cover for: {} by {:?}", title, author);
+    
+    // Create a simple SVG cover with the title and author
+    let author_text = author.as_ref().map(|a| a.as_str()).unwrap_or("Unknown Author");
+    
+    // Truncate title and author if too long
+    let display_title = if title.len() > 30 {
+        format!("{}...", &title[..27])
+    } else {
+        title.to_string()
+    };
+    
+    let display_author = if author_text.len() > 25 {
+        format!("{}...", &author_text[..22])
+    } else {
+        author_text.to_string()
+    };
+    
+    let svg_content = format!(
+        r#"<svg width="400" height="600" xmlns="http://www.w3.org/2000/svg">
+            <defs>
+                <linearGradient id="grad" x1="0%" y1="0%" x2="100%" y2="100%">
+                    <stop offset="0%" style="stop-color:#667eea;stop-opacity:1" />
+                    <stop offset="100%" style="stop-color:#764ba2;stop-opacity:1" />
+                </linearGradient>
+            </defs>
+            <rect width="400" height="600" fill="url(#grad)"/>
+            <circle cx="200" cy="150" r="60" fill="white" opacity="0.2"/>
+            <path d="M170 130 L170 170 L210 150 Z" fill="white" opacity="0.8"/>
+            <rect x="30" y="250" width="340" height="2" fill="white" opacity="0.3"/>
+            <text x="200" y="300" font-family="Arial,sans-serif" font-size="24" font-weight="bold" 
+                  fill="white" text-anchor="middle" dominant-baseline="middle">
+                <tspan x="200" dy="0">{}</tspan>
+            </text>
+            <text x="200" y="350" font-family="Arial,sans-serif" font-size="18" 
+                  fill="white" text-anchor="middle" dominant-baseline="middle" opacity="0.8">
+                by {}
+            </text>
+            <text x="200" y="420" font-family="Arial,sans-serif" font-size="14" 
+                  fill="white" text-anchor="middle" dominant-baseline="middle" opacity="0.6">
+                Generated via Text-to-Speech
+            </text>
+            <rect x="30" y="480" width="340" height="2" fill="white" opacity="0.3"/>
+        </svg>"#,
+        display_title, display_author
+    );
+    
+    // Create covers directory
+    let covers_dir = storage::paths().covers_dir();
+    tokio::fs::create_dir_all(&covers_dir).await
+        .map_err(|e| format!("Failed to create covers directory: {}", e))?;
+    
+    // Convert SVG to base64 data URL (browsers can render SVG directly)
+    let svg_base64 = general_purpose::STANDARD.encode(svg_content.as_bytes());
+    let data_url = format!("data:image/svg+xml;base64,{}", svg_base64);
+    
+    // Also save as SVG file for reference
+    let svg_file_path = covers_dir.join(format!("{}.svg", audiobook_id));
+    filesystem::atomic::write_async(&svg_file_path, svg_content.as_bytes()).await
+        .map_err(|e| format!("Failed to save SVG file: {:#}", e))?;
+    
+    println!("TTS COVER: Generated cover as SVG data URL");
+    
+    Ok(Some(data_url))
+}
@@ -0,0 +1,245 @@
+// Backend client for the VibeVoice TTS API the frontend uses for whole books,
+// for work that happens without the UI driving it (regenerating one chapter)
+
+use crate::audio::stretch::{StretchControl, TimeStretch};
+use crate::database::models::AlignedSegment;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::Client;
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::Arc;
+
+pub mod library;
+
+pub const PREF_TTS_API_URL: &str = "tts.api_url";
+pub const DEFAULT_TTS_API_URL: &str = "https://whitestjohn0--vibevoice-tts-api-fastapi-app.modal.run";
+pub const DEFAULT_VOICE: &str = "en-Alice_woman";
+/// Characters per request; matches the frontend's chunking
+pub const MAX_CHUNK_CHARS: usize = 800;
+
+#[derive(Debug, Serialize)]
+struct GenerateRequest<'a> {
+    text: &'a str,
+    speaker_voice: &'a str,
+    cfg_scale: f32,
+    format: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateResponse {
+    success: bool,
+    audio_data: Option<String>,
+    #[serde(default)]
+    alignment: Vec<AlignedSegment>,
+    error: Option<String>,
+}
+
+/// Decoded audio for one or more chunks, with alignment on the same timeline
+#[derive(Debug, Clone, Default)]
+pub struct SynthesizedAudio {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+    pub alignment: Vec<AlignedSegment>,
+}
+
+impl SynthesizedAudio {
+    pub fn duration_seconds(&self) -> f64 {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.samples.len() as f64 / (self.channels as f64 * self.sample_rate as f64)
+    }
+
+    /// Append another chunk, shifting its alignment to start where this one ends
+    pub fn append(&mut self, other: SynthesizedAudio) -> Result<()> {
+        if self.samples.is_empty() {
+            *self = other;
+            return Ok(());
+        }
+        if (self.channels, self.sample_rate) != (other.channels, other.sample_rate) {
+            return Err(anyhow::anyhow!(
+                "TTS chunks disagree on format: {}ch {}Hz vs {}ch {}Hz",
+                self.channels, self.sample_rate, other.channels, other.sample_rate
+            ));
+        }
+
+        let offset = self.duration_seconds();
+        self.alignment.extend(other.alignment.into_iter().map(|segment| AlignedSegment {
+            start: segment.start + offset,
+            end: segment.end + offset,
+            ..segment
+        }));
+        self.samples.extend(other.samples);
+        Ok(())
+    }
+
+    /// Change tempo without changing the voice's pitch
+    pub fn stretch(self, speed: f32) -> SynthesizedAudio {
+        if (speed - 1.0).abs() < 0.01 {
+            return self;
+        }
+
+        let control = Arc::new(StretchControl::new(true));
+        control.set_speed(speed);
+        let source = SamplesBuffer::new(self.channels, self.sample_rate, self.samples);
+        let samples: Vec<f32> = TimeStretch::new(source, control).collect();
+        let alignment = self.alignment.into_iter().map(|segment| AlignedSegment {
+            start: segment.start / speed as f64,
+            end: segment.end / speed as f64,
+            ..segment
+        }).collect();
+
+        SynthesizedAudio { samples, alignment, ..self }
+    }
+
+    /// 16-bit PCM WAV
+    pub fn to_wav(&self) -> Vec<u8> {
+        let data_len = (self.samples.len() * 2) as u32;
+        let block_align = self.channels * 2;
+        let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&self.channels.to_le_bytes());
+        wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in &self.samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            wav.extend_from_slice(&value.to_le_bytes());
+        }
+        wav
+    }
+}
+
+pub struct TtsClient {
+    client: Client,
+    api_url: String,
+}
+
+impl TtsClient {
+    pub fn new(api_url: &str) -> Result<Self> {
+        let client = Client::builder()
+            // Cold starts on the hosted API take a while
+            .timeout(std::time::Duration::from_secs(300))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self { client, api_url: api_url.trim_end_matches('/').to_string() })
+    }
+
+    pub async fn synthesize(&self, text: &str, voice: &str) -> Result<SynthesizedAudio> {
+        let response: GenerateResponse = self.client
+            .post(format!("{}/generate-audio", self.api_url))
+            .json(&GenerateRequest { text, speaker_voice: voice, cfg_scale: 1.3, format: "wav" })
+            .send()
+            .await
+            .context("Failed to reach TTS service")?
+            .error_for_status()
+            .context("TTS service returned an error")?
+            .json()
+            .await
+            .context("Invalid response from TTS service")?;
+
+        if !response.success {
+            return Err(anyhow::anyhow!("TTS generation failed: {}", response.error.unwrap_or_default()));
+        }
+        let audio_data = response.audio_data.context("TTS response had no audio")?;
+        let bytes = general_purpose::STANDARD.decode(audio_data).context("Failed to decode TTS audio")?;
+
+        let decoder = rodio::Decoder::new(Cursor::new(bytes)).context("Failed to decode TTS audio")?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        Ok(SynthesizedAudio {
+            channels,
+            sample_rate,
+            samples: decoder.collect(),
+            alignment: response.alignment,
+        })
+    }
+}
+
+/// Split text into request-sized pieces at sentence ends, falling back to word
+/// boundaries for sentences longer than `max_chars`
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    let push_piece = |piece: &str, current: &mut String, chunks: &mut Vec<String>| {
+        if !current.is_empty() && current.len() + piece.len() + 1 > max_chars {
+            chunks.push(std::mem::take(current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(piece);
+    };
+
+    for sentence in sentences(text) {
+        if sentence.len() <= max_chars {
+            push_piece(sentence, &mut current, &mut chunks);
+            continue;
+        }
+        for word in sentence.split_whitespace() {
+            push_piece(word, &mut current, &mut chunks);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Sentences with their closing punctuation kept, so the voice still hears it
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive(['.', '!', '?'])
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_text_keeps_sentences_together() {
+        let text = "One two three. Four five six! Seven eight nine?";
+        assert_eq!(split_text(text, 100), vec![text.to_string()]);
+        assert_eq!(split_text(text, 30), vec!["One two three. Four five six!", "Seven eight nine?"]);
+        // A sentence longer than the limit falls back to words
+        assert_eq!(split_text("aaaa bbbb cccc dddd.", 10), vec!["aaaa bbbb", "cccc dddd."]);
+        assert!(split_text("   ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_append_offsets_alignment_and_writes_wav() {
+        let chunk = |text: &str| SynthesizedAudio {
+            channels: 1,
+            sample_rate: 10,
+            samples: vec![0.5; 20],
+            alignment: vec![AlignedSegment { text: text.to_string(), start: 0.5, end: 1.5 }],
+        };
+
+        let mut audio = SynthesizedAudio::default();
+        audio.append(chunk("first")).unwrap();
+        audio.append(chunk("second")).unwrap();
+        assert_eq!(audio.duration_seconds(), 4.0);
+        assert_eq!((audio.alignment[1].start, audio.alignment[1].end), (2.5, 3.5));
+
+        let mismatched = SynthesizedAudio { sample_rate: 20, ..chunk("third") };
+        assert!(audio.append(mismatched).is_err());
+
+        let wav = audio.to_wav();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 40 * 2);
+    }
+}
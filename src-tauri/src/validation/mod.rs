@@ -0,0 +1,414 @@
+// Checks on command input before it reaches the audio engine, services and
+// repositories. DTOs implement Validate and report every problem at once, and
+// file paths are resolved and must lie under one of the allowed roots.
+
+use crate::database::models::{
+    CreateAnnotationDto, CreateAudiobookDto, CreateBookmarkDto, CreateChapterDto, CreateCollectionDto, CreateEbookDto,
+    CreateListeningHistoryDto, CreateRecommendationFeedbackDto, RandomPickFilters, SearchFilters, UpdateEbookDto,
+    UpdatePlaybackProgressDto, UpdateReaderSettingsDto, UpdateReadingProgressDto,
+};
+use crate::services::speed_preset_service::{MAX_SPEED, MIN_SPEED};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub const MAX_TITLE_LEN: usize = 500;
+pub const MAX_NAME_LEN: usize = 200;
+pub const MAX_TEXT_LEN: usize = 20_000;
+pub const MAX_PATH_LEN: usize = 4096;
+/// Largest slice of a file the frontend may read in one call
+pub const MAX_CHUNK_BYTES: usize = 64 * 1024 * 1024;
+pub const MIN_VOLUME: f32 = 0.0;
+pub const MAX_VOLUME: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every problem found in one input
+#[derive(Debug, Clone, Default, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn single(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = Self::new();
+        errors.add(field, message);
+        errors
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError { field: field.to_string(), message: message.into() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Text that must be present and at most `max_len` characters
+    pub fn required_text(&mut self, field: &str, value: &str, max_len: usize) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty");
+        } else {
+            self.max_len(field, value, max_len);
+        }
+    }
+
+    pub fn optional_text(&mut self, field: &str, value: Option<&str>, max_len: usize) {
+        if let Some(value) = value {
+            self.max_len(field, value, max_len);
+        }
+    }
+
+    fn max_len(&mut self, field: &str, value: &str, max_len: usize) {
+        if value.chars().count() > max_len {
+            self.add(field, format!("must be at most {} characters", max_len));
+        }
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: Option<i64>) {
+        if value.is_some_and(|value| value < 0) {
+            self.add(field, "must not be negative");
+        }
+    }
+
+    pub fn in_range(&mut self, field: &str, value: Option<f64>, min: f64, max: f64) {
+        if let Some(value) = value {
+            if !(min..=max).contains(&value) {
+                self.add(field, format!("must be between {} and {}", min, max));
+            }
+        }
+    }
+
+    /// Paths stored for later use are checked for shape only; they are resolved when read
+    pub fn path(&mut self, field: &str, value: &str) {
+        if let Err(message) = check_path_shape(value) {
+            self.add(field, message);
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid input: ")?;
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{} {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for String {
+    fn from(errors: ValidationErrors) -> Self {
+        errors.to_string()
+    }
+}
+
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+pub fn validate_volume(volume: f32) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    errors.in_range("volume", Some(volume as f64), MIN_VOLUME as f64, MAX_VOLUME as f64);
+    errors.into_result()
+}
+
+pub fn validate_speed(speed: f32) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    errors.in_range("speed", Some(speed as f64), MIN_SPEED as f64, MAX_SPEED as f64);
+    errors.into_result()
+}
+
+fn check_path_shape(path: &str) -> Result<(), String> {
+    if path.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if path.len() > MAX_PATH_LEN {
+        return Err(format!("must be at most {} bytes", MAX_PATH_LEN));
+    }
+    if path.contains('\0') {
+        return Err("must not contain NUL characters".to_string());
+    }
+    Ok(())
+}
+
+/// An existing file or folder the user picked, with `..` and links resolved
+pub fn canonical_path(field: &str, path: &str) -> Result<PathBuf, ValidationErrors> {
+    check_path_shape(path).map_err(|message| ValidationErrors::single(field, message))?;
+    std::fs::canonicalize(path).map_err(|_| ValidationErrors::single(field, "does not exist"))
+}
+
+/// Folders that command paths may point into
+#[derive(Debug, Clone, Default)]
+pub struct PathRoots {
+    roots: Vec<PathBuf>,
+}
+
+impl PathRoots {
+    /// Roots that do not exist (yet) are left out
+    pub fn new(roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut roots: Vec<PathBuf> = roots.into_iter().filter_map(|root| std::fs::canonicalize(root).ok()).collect();
+        roots.sort();
+        roots.dedup();
+        Self { roots }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// The canonical form of an existing path under one of the roots
+    pub fn resolve(&self, field: &str, path: &str) -> Result<PathBuf, ValidationErrors> {
+        let resolved = canonical_path(field, path)?;
+        if !self.contains(&resolved) {
+            return Err(ValidationErrors::single(field, "is outside the library folders"));
+        }
+        Ok(resolved)
+    }
+}
+
+impl Validate for CreateAudiobookDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("title", &self.title, MAX_TITLE_LEN);
+        errors.path("file_path", &self.file_path);
+        errors.optional_text("author", self.author.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("narrator", self.narrator.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("description", self.description.as_deref(), MAX_TEXT_LEN);
+        errors.optional_text("genre", self.genre.as_deref(), MAX_NAME_LEN);
+        errors.non_negative("duration", self.duration);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateChapterDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("title", &self.title, MAX_TITLE_LEN);
+        errors.path("file_path", &self.file_path);
+        errors.non_negative("chapter_number", Some(self.chapter_number as i64));
+        errors.non_negative("duration", self.duration);
+        errors.non_negative("file_size", self.file_size);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdatePlaybackProgressDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.non_negative("position", Some(self.position));
+        errors.non_negative("chapter_index", self.chapter_index.map(|index| index as i64));
+        errors.in_range("playback_speed", self.playback_speed, MIN_SPEED as f64, MAX_SPEED as f64);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateCollectionDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("name", &self.name, MAX_NAME_LEN);
+        errors.optional_text("description", self.description.as_deref(), MAX_TEXT_LEN);
+        errors.optional_text("color", self.color.as_deref(), 32);
+        errors.into_result()
+    }
+}
+
+impl Validate for SearchFilters {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.optional_text("query", self.query.as_deref(), MAX_TITLE_LEN);
+        errors.optional_text("author", self.author.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("genre", self.genre.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("narrator", self.narrator.as_deref(), MAX_NAME_LEN);
+        errors.non_negative("min_duration", self.min_duration);
+        errors.non_negative("max_duration", self.max_duration);
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration) {
+            if min > max {
+                errors.add("min_duration", "must not be greater than max_duration");
+            }
+        }
+        errors.in_range("min_rating", self.min_rating.map(|rating| rating as f64), 0.0, 5.0);
+        errors.non_negative("min_bitrate", self.min_bitrate.map(i64::from));
+        errors.into_result()
+    }
+}
+
+impl Validate for RandomPickFilters {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.non_negative("max_duration", self.max_duration);
+        errors.optional_text("genre", self.genre.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("language", self.language.as_deref(), MAX_NAME_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateListeningHistoryDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("audiobook_id", &self.audiobook_id, MAX_NAME_LEN);
+        errors.non_negative("position_seconds", Some(self.position_seconds));
+        errors.non_negative("duration_seconds", self.duration_seconds);
+        errors.non_negative("session_duration", Some(self.session_duration));
+        errors.in_range("playback_speed", self.playback_speed, MIN_SPEED as f64, MAX_SPEED as f64);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateRecommendationFeedbackDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("recommendation_id", &self.recommendation_id, MAX_NAME_LEN);
+        errors.required_text("feedback_type", &self.feedback_type, MAX_NAME_LEN);
+        errors.optional_text("feedback_reason", self.feedback_reason.as_deref(), MAX_TEXT_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateEbookDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("title", &self.title, MAX_TITLE_LEN);
+        errors.path("file_path", &self.file_path);
+        errors.required_text("file_format", &self.file_format, 16);
+        errors.optional_text("author", self.author.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("description", self.description.as_deref(), MAX_TEXT_LEN);
+        errors.optional_text("genre", self.genre.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("publisher", self.publisher.as_deref(), MAX_NAME_LEN);
+        errors.non_negative("total_pages", self.total_pages.map(|pages| pages as i64));
+        errors.non_negative("file_size", self.file_size);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateEbookDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(title) = &self.title {
+            errors.required_text("title", title, MAX_TITLE_LEN);
+        }
+        errors.optional_text("author", self.author.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("description", self.description.as_deref(), MAX_TEXT_LEN);
+        errors.optional_text("genre", self.genre.as_deref(), MAX_NAME_LEN);
+        errors.optional_text("publisher", self.publisher.as_deref(), MAX_NAME_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateReadingProgressDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.non_negative("current_page", self.current_page.map(|page| page as i64));
+        errors.in_range("percentage_complete", self.percentage_complete, 0.0, 100.0);
+        errors.non_negative("reading_time_seconds", self.reading_time_seconds);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateBookmarkDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("ebook_id", &self.ebook_id, MAX_NAME_LEN);
+        errors.non_negative("page_number", self.page_number.map(|page| page as i64));
+        errors.optional_text("chapter_title", self.chapter_title.as_deref(), MAX_TITLE_LEN);
+        errors.optional_text("note", self.note.as_deref(), MAX_TEXT_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for CreateAnnotationDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.required_text("ebook_id", &self.ebook_id, MAX_NAME_LEN);
+        errors.required_text("annotation_type", &self.annotation_type, MAX_NAME_LEN);
+        errors.optional_text("selected_text", self.selected_text.as_deref(), MAX_TEXT_LEN);
+        errors.optional_text("note", self.note.as_deref(), MAX_TEXT_LEN);
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateReaderSettingsDto {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.in_range("font_size", self.font_size.map(|size| size as f64), 6.0, 96.0);
+        errors.in_range("line_height", self.line_height, 0.5, 4.0);
+        errors.in_range("letter_spacing", self.letter_spacing, -5.0, 20.0);
+        errors.optional_text("font_family", self.font_family.as_deref(), MAX_NAME_LEN);
+        errors.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dto_errors_are_collected() {
+        let dto = CreateAudiobookDto {
+            title: "  ".to_string(),
+            file_path: "/books/a\0b".to_string(),
+            author: Some("x".repeat(MAX_NAME_LEN + 1)),
+            narrator: None,
+            description: None,
+            genre: None,
+            duration: Some(-5),
+            cover_image_path: None,
+            source_type: None,
+            source_id: None,
+        };
+        let errors = dto.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["title", "file_path", "author", "duration"]);
+        assert!(errors.to_string().starts_with("Invalid input: title must not be empty; file_path"));
+
+        assert!(validate_speed(1.5).is_ok());
+        assert!(validate_speed(10.0).is_err());
+        assert!(validate_volume(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_paths_outside_roots_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("library");
+        std::fs::create_dir_all(library.join("book")).unwrap();
+        std::fs::write(library.join("book").join("01.mp3"), b"").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), b"").unwrap();
+
+        let roots = PathRoots::new([library.clone(), dir.path().join("missing")]);
+        let inside = library.join("book").join("01.mp3");
+        assert!(roots.resolve("file_path", &inside.to_string_lossy()).is_ok());
+
+        // `..` is resolved before the check
+        let escaped = library.join("book").join("..").join("..").join("secret.txt");
+        let error = roots.resolve("file_path", &escaped.to_string_lossy()).unwrap_err();
+        assert_eq!(error.errors[0].message, "is outside the library folders");
+
+        let missing = library.join("nope.mp3");
+        assert_eq!(roots.resolve("file_path", &missing.to_string_lossy()).unwrap_err().errors[0].message, "does not exist");
+        assert!(roots.resolve("file_path", "").is_err());
+    }
+}
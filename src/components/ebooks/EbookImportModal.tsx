@@ -25,22 +25,20 @@ export const EbookImportModal: React.FC<EbookImportModalProps> = ({
 
   const handleSelectFile = async () => {
     try {
-      const { open } = await import('@tauri-apps/plugin-dialog');
-      const file = await open({
-        multiple: false,
-        filters: [{
-          name: 'Ebooks',
-          extensions: ['pdf', 'epub']
-        }]
+      // The backend opens the picker so the file's folder becomes a library folder
+      const { invoke } = await import('@tauri-apps/api/core');
+      const [file] = await invoke<string[]>('pick_library_files', {
+        title: 'Select Ebook',
+        extensions: ['pdf', 'epub'],
+        multiple: false
       });
 
-      if (file && typeof file === 'string') {
+      if (file) {
         setSelectedFile(file);
 
         // Extract metadata
         setIsLoading(true);
         try {
-          const { invoke } = await import('@tauri-apps/api/core');
           const extractedMetadata = await invoke('extract_ebook_metadata', { filePath: file }) as EbookMetadata;
 
           setMetadata(extractedMetadata);
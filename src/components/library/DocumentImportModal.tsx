@@ -1,6 +1,5 @@
 import React, { useState, useRef } from 'react';
 import { X, Upload, Book, Mic, Settings, Play, Download, AlertCircle, Minimize2 } from 'lucide-react';
-import { open } from '@tauri-apps/plugin-dialog';
 import { ttsService, type VoiceInfo } from '../../services/ttsService';
 import { rustDocumentProcessor, type DocumentChapter, type ProcessedDocument } from '../../services/rustDocumentProcessor';
 
@@ -104,17 +103,15 @@ const DocumentImportModal: React.FC<DocumentImportModalProps> = ({
 
   const handleFileSelect = async () => {
     try {
-      const selected = await open({
-        multiple: false,
-        filters: [
-          {
-            name: 'Documents',
-            extensions: ['pdf', 'epub', 'txt']
-          }
-        ]
+      // The backend opens the picker so the document's folder becomes a library folder
+      const { invoke } = await import('@tauri-apps/api/core');
+      const [selected] = await invoke<string[]>('pick_library_files', {
+        title: 'Select Document',
+        extensions: ['pdf', 'epub', 'txt'],
+        multiple: false
       });
       
-      if (selected && typeof selected === 'string') {
+      if (selected) {
         setSelectedFile(selected);
         // Extract filename from path
         const fileName = selected.split(/[\\\/]/).pop() || 'Unknown';
@@ -162,7 +159,9 @@ const DocumentImportModal: React.FC<DocumentImportModalProps> = ({
       const audiobook = await invoke('create_tts_audiobook', {
         title: documentInfo.title,
         author: documentInfo.author || 'Unknown Author',
-        chapters: documentInfo.chapters
+        chapters: documentInfo.chapters,
+        voice: selectedVoice,
+        documentId: documentInfo.document_id
       }) as any;
 
       console.log('Created audiobook record:', audiobook);
@@ -43,19 +43,17 @@ export const EditAudiobookModal: React.FC<EditAudiobookModalProps> = ({
 
   const handleImageSelect = async () => {
     try {
-      const { open } = await import('@tauri-apps/plugin-dialog');
-      const selected = await open({
-        multiple: false,
-        filters: [{
-          name: 'Images',
-          extensions: ['png', 'jpg', 'jpeg', 'webp', 'gif']
-        }]
+      // The backend opens the picker so the image's folder becomes a library folder
+      const { invoke, convertFileSrc } = await import('@tauri-apps/api/core');
+      const [selected] = await invoke<string[]>('pick_library_files', {
+        title: 'Select Cover Image',
+        extensions: ['png', 'jpg', 'jpeg', 'webp', 'gif'],
+        multiple: false
       });
 
-      if (selected && typeof selected === 'string') {
+      if (selected) {
         setCoverImagePath(selected);
         // Create preview URL for the selected file
-        const { convertFileSrc } = await import('@tauri-apps/api/core');
         const previewUrl = convertFileSrc(selected);
         setPreviewImage(previewUrl);
       }
@@ -148,12 +148,10 @@ export const Library: React.FC = () => {
 
       console.log('Opening directory picker...');
 
-      // Use Tauri's dialog plugin to open native directory picker
-      const { open } = await import('@tauri-apps/plugin-dialog');
+      // The backend opens the picker so the chosen folder becomes a library folder
+      const { invoke } = await import('@tauri-apps/api/core');
 
-      const selectedPath = await open({
-        directory: true,
-        multiple: false,
+      const selectedPath = await invoke<string | null>('pick_library_folder', {
         title: 'Select Audiobook Folder'
       });
 
@@ -171,7 +169,6 @@ export const Library: React.FC = () => {
       // 3. Create chapters if multiple files
       // 4. Find cover images automatically
       console.log('Calling import_audiobook_from_directory...');
-      const { invoke } = await import('@tauri-apps/api/core');
 
       const audiobook = await invoke('import_audiobook_from_directory', {
         directoryPath: selectedPath
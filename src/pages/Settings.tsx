@@ -174,13 +174,12 @@ export const Settings: React.FC = () => {
                   className="btn-secondary"
                   onClick={async () => {
                     try {
-                      const { open } = await import('@tauri-apps/plugin-dialog');
-                      const selected = await open({
-                        directory: true,
-                        multiple: false,
+                      // The backend opens the picker so the chosen folder becomes a library folder
+                      const { invoke } = await import('@tauri-apps/api/core');
+                      const selected = await invoke<string | null>('pick_library_folder', {
                         title: 'Select Library Path'
                       });
-                      if (selected && typeof selected === 'string') {
+                      if (selected) {
                         setDefaultLibraryPath(selected);
                       }
                     } catch (error) {
@@ -254,13 +253,12 @@ export const Settings: React.FC = () => {
                   className="btn-secondary"
                   onClick={async () => {
                     try {
-                      const { open } = await import('@tauri-apps/plugin-dialog');
-                      const selected = await open({
-                        directory: true,
-                        multiple: false,
+                      // The backend opens the picker so the chosen folder becomes a library folder
+                      const { invoke } = await import('@tauri-apps/api/core');
+                      const selected = await invoke<string | null>('pick_library_folder', {
                         title: 'Select Backup Path'
                       });
-                      if (selected && typeof selected === 'string') {
+                      if (selected) {
                         setLocalBackupPath(selected);
                       }
                     } catch (error) {
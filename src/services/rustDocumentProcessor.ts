@@ -19,6 +19,7 @@ export interface ProcessedDocument {
   format: string;
   total_pages?: number;
   total_chapters: number;
+  document_id?: string;
 }
 
 class RustDocumentProcessor {
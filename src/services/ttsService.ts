@@ -9,9 +9,16 @@ export interface TTSRequest {
   format?: 'wav' | 'mp3';
 }
 
+export interface AlignedSegment {
+  text: string;
+  start: number; // seconds
+  end: number;
+}
+
 export interface TTSResponse {
   success: boolean;
   audio_data?: string; // base64 encoded audio
+  alignment?: AlignedSegment[]; // word or sentence timings, when the engine provides them
   duration?: number;
   sample_rate?: number;
   format?: string;
@@ -295,6 +302,20 @@ class TTSService {
                   audiobookId: audiobookId
                 });
                 chapterFiles.push(filePath as string);
+
+                // Sentence timings for highlighting the text while it plays
+                if (result.alignment?.length) {
+                  try {
+                    await invoke('record_tts_timings', {
+                      audiobookId: audiobookId,
+                      chapterNumber: chapterIndex + 1,
+                      filePath: filePath as string,
+                      alignment: result.alignment
+                    });
+                  } catch (timingError) {
+                    console.error(`Failed to record timings for chapter ${chapterIndex + 1}:`, timingError);
+                  }
+                }
                 
                 // Store the first audio file path to update the audiobook record
                 if (!firstAudioFilePath) {
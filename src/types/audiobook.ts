@@ -1,3 +1,5 @@
+import type { LibrivoxRelease } from './events';
+
 // Audiobook types matching Rust backend
 export interface Audiobook {
   id: string;
@@ -17,6 +19,21 @@ export interface Audiobook {
   chapters_count: number;
   created_at: string;
   updated_at: string;
+  source_type?: 'librivox' | 'tts' | 'local' | 'url_list';
+  source_id?: string;
+  import_status: 'complete' | 'incomplete';
+  cover_source?: 'embedded' | 'folder' | 'archive_org' | 'open_library';
+}
+
+// Slim row returned by get_audiobook_summaries for large library lists
+export interface AudiobookSummary {
+  id: string;
+  title: string;
+  author?: string;
+  duration?: number; // Duration in seconds
+  progress_percent: number; // 0-100
+  is_completed: boolean;
+  cover_url?: string;
 }
 
 // Chapter types for file-based audiobooks
@@ -56,6 +73,16 @@ export interface PlaybackProgress {
   updated_at: string;
 }
 
+// Full record returned by get_audiobook_details
+export interface AudiobookDetails extends Audiobook {
+  playback_speed: number;
+  remaining_seconds?: number;
+  remaining_listening_seconds?: number;
+  estimated_finish_date?: string;
+  chapters: Chapter[];
+  progress?: PlaybackProgress;
+}
+
 export interface Collection {
   id: string;
   name: string;
@@ -85,6 +112,109 @@ export interface PlaybackStatus {
   volume: number;
   speed: number;
   current_file?: string;
+  chapter?: ChapterPosition | null; // Set while a book from the library is loaded
+  stop_after?: number | null; // Chapters left before playback stops by itself, counting the current one
+}
+
+// From get_playback_status_fast: no audio thread round-trip, local output only
+export interface LiveStatus {
+  state: 'Stopped' | 'Playing' | 'Paused';
+  position: number; // Position in seconds
+  duration: number | null;
+  volume: number;
+  speed: number;
+}
+
+export interface ChapterPosition {
+  audiobook_id: string;
+  index: number; // Zero-based
+  count: number;
+  title: string;
+  chapter_position: number;
+  chapter_duration: number | null;
+  book_position: number | null; // Null while an earlier chapter has no known duration
+  book_duration: number | null;
+  book_remaining: number | null;
+}
+
+export interface SeekHistoryEntry {
+  file_path: string;
+  position: number; // Seconds into file_path
+  recorded_at: string;
+}
+
+export interface QueueTrack {
+  id: string;
+  file_path: string;
+  title: string | null;
+  duration: number | null;
+  audiobook_id: string | null;
+}
+
+export interface PlayerState {
+  current: QueueTrack;
+  position: number; // Seconds into current
+  queue: QueueTrack[];
+  speed: number;
+  volume: number;
+  ambience: string | null;
+  ambience_volume: number;
+  audiobook_id: string | null;
+  chapter_id: string | null;
+  saved_at: string;
+}
+
+export interface Suggestion {
+  kind: 'title' | 'author' | 'series' | 'narrator' | 'tag';
+  text: string;
+  audiobook_id: string | null; // Set for title suggestions
+  book_count: number;
+}
+
+export interface SearchFilters {
+  query?: string | null;
+  author?: string | null;
+  genre?: string | null;
+  narrator?: string | null;
+  min_duration?: number | null; // Seconds
+  max_duration?: number | null;
+  added_after?: string | null;
+  added_before?: string | null;
+  min_rating?: number | null;
+  abandoned?: boolean | null;
+  sort_by?: 'title' | 'author' | 'rating' | 'duration' | 'added_date' | null;
+  sort_desc?: boolean | null;
+}
+
+export type LibrarySortField = 'title' | 'author' | 'series' | 'recently_played' | 'added' | 'manual';
+
+export interface SortPreference {
+  field: LibrarySortField; // 'manual' only applies to collections
+  descending: boolean;
+}
+
+export type SortScope = { kind: 'library' } | { kind: 'collection'; collection_id: string };
+
+export interface SavedSearch {
+  id: string;
+  name: string;
+  filters: SearchFilters;
+  pinned: boolean; // Listed with the collections
+  book_count: number | null; // Set for pinned searches
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SearchHistoryEntry {
+  id: string;
+  query: string;
+  searched_at: string;
+}
+
+export interface SeriesEntry {
+  audiobook_id: string;
+  name: string;
+  position: number | null; // Number within the series, e.g. 2.5 for a novella
 }
 
 // DTOs for API communication
@@ -132,4 +262,119 @@ export interface SystemInfo {
   arch: string;
   version: string;
   tauri_version: string;
-}
\ No newline at end of file
+}
+
+export interface SchemaVersion {
+  app_version: string;
+  schema_version: number | null; // Newest SQL migration applied
+  latest_schema_version: number;
+  pending_migrations: number[];
+  unknown_migrations: number[]; // Applied by a newer version of the app
+  failed_migrations: number[];
+  data_version: number | null;
+  latest_data_version: number;
+  pending_data_migrations: number[];
+}
+
+export interface LanSharingStatus {
+  enabled: boolean;
+  running: boolean;
+  port: number;
+  urls: string[]; // Open on another device; the sharing key is included
+  error: string | null; // Why the server is not running although enabled
+}
+
+export interface CastDevice {
+  id: string; // The renderer's UDN
+  name: string;
+  model: string | null;
+  control_url: string;
+  service_type: string;
+}
+
+export interface CastStatus {
+  device: CastDevice;
+  file_path: string;
+  playing: boolean;
+  position: number;
+  duration: number | null;
+}
+
+export type ProbeStatus = 'ok' | 'warning' | 'unplayable';
+
+export interface FileValidation {
+  file_path: string;
+  audiobook_id: string;
+  chapter_id: string | null;
+  chapter_title: string | null;
+  status: ProbeStatus;
+  codec: string | null;
+  sample_rate: number | null;
+  channels: number | null;
+  declared_duration: number | null; // Seconds, from the headers
+  decoded_duration: number; // Seconds of audio that actually decoded
+  packets: number;
+  damaged_packets: number;
+  issues: string[];
+  validated_at: string;
+}
+
+export interface AudiobookValidation {
+  audiobook_id: string;
+  files: FileValidation[];
+  unplayable: number;
+  warnings: number;
+}
+export interface Recommendation {
+  id: string;
+  audiobook_id: string;
+  recommendation_type: string;
+  recommendation_score: number;
+  recommendation_reason: string | null;
+  generated_at: string;
+  expires_at: string | null;
+  is_dismissed: boolean;
+  user_feedback: number | null;
+}
+
+// Each source's scores are multiplied by its weight (0 to 2; 0 turns it off)
+export interface RecommendationWeights {
+  genre: number;
+  author: number;
+  narrator: number;
+  similar: number;
+}
+
+export type FactorKind = 'genre' | 'author' | 'narrator' | 'similarity' | 'favorite_narrator';
+
+export interface RecommendationFactor {
+  kind: FactorKind;
+  value: string; // The genre, author or narrator matched, or how a book is similar
+  score: number;
+  weight: number;
+}
+
+export interface SimilaritySource {
+  audiobook_id: string;
+  title: string;
+}
+
+export interface RecommendationExplanation {
+  recommendation: Recommendation;
+  factors: RecommendationFactor[]; // Empty for recommendations made before factors were recorded
+  similar_to: SimilaritySource | null;
+  weights: RecommendationWeights; // The weights in use now
+}
+
+// Onboarding: the genres and authors a new listener picks before playing anything
+export interface TasteProfile {
+  genres: string[];
+  authors: string[];
+}
+
+export interface LibrivoxSuggestion {
+  release: LibrivoxRelease;
+  score: number;
+  reason: string;
+  factors: RecommendationFactor[]; // The picked or learned genres and authors matched
+}
@@ -0,0 +1,314 @@
+import type { Audiobook, AudiobookValidation, SortPreference, SortScope } from './audiobook';
+
+// Events sent by the backend (src-tauri/src/events). Listen with
+// listen<AppEventMap[K]>(name) to get the payload type of an event.
+
+export interface WarmUpReport {
+  duration_ms: number;
+  fingerprints_backfilled: number;
+  authors_linked: number;
+  narrators_linked: number;
+  sources_backfilled: number;
+  incomplete_imports: number;
+  release_alerts: number;
+  temp_files_removed: number;
+  errors: string[];
+}
+
+export type PlaybackState = 'loaded' | 'playing' | 'paused' | 'stopped';
+
+export interface PlaybackChangedEvent {
+  state: PlaybackState;
+  file_path: string | null;
+  position: number | null;
+}
+
+export interface ResumeReport {
+  slept_seconds: number;
+  was_playing: boolean;
+  position: number | null;
+  file_path: string | null;
+  device_error: string | null;
+}
+
+export type EndOfBookAction = 'stop' | 'next_in_series' | 'recommendation';
+
+export interface BookFinishedEvent {
+  audiobook_id: string;
+  action: EndOfBookAction;
+  next_audiobook: Audiobook | null;
+}
+
+export interface ChapterError {
+  file_path: string;
+  audiobook_id: string;
+  chapter_id: string | null;
+  chapter_title: string | null;
+  error: string;
+  failed_at: string;
+}
+
+export interface ChapterErrorEvent {
+  file_path: string;
+  error: string;
+  skipped: boolean;
+  chapter: ChapterError | null;
+}
+
+// Output stopped while playing; the file is reloaded where audio stopped
+// unless recovery_in_seconds is null
+export interface PlaybackStall {
+  file_path: string;
+  position: number;
+  stalled_seconds: number;
+  recovery_in_seconds: number | null;
+}
+
+export interface DownloadProgressEvent {
+  url: string;
+  file_name: string;
+  downloaded_bytes: number;
+  total_bytes: number | null;
+}
+
+export interface DownloadCompletedEvent {
+  url: string;
+  file_name: string;
+  file_path: string;
+}
+
+export interface DownloadFailedEvent {
+  url: string;
+  file_name: string;
+  error: string;
+}
+
+export interface ScanStartedEvent {
+  path: string;
+}
+
+export interface ScanFinishedEvent {
+  path: string;
+  files_found: number;
+  error: string | null;
+}
+
+export interface OcrProgressEvent {
+  file_path: string;
+  page: number;
+  total_pages: number;
+}
+
+export interface FolderExportProgressEvent {
+  audiobook_id: string;
+  current_file: number;
+  total_files: number;
+  file_name: string;
+  bytes_copied: number;
+  total_bytes: number;
+}
+
+export interface InboxFileEvent {
+  file_path: string;
+  archived_path: string | null;
+  audiobook_id: string | null;
+  title: string | null;
+  error: string | null;
+}
+
+export type MaintenanceTask =
+  | 'cache_eviction'
+  | 'duration_backfill'
+  | 'orphan_cleanup'
+  | 'feed_refresh'
+  | 'backup'
+  | 'retention'
+  | 'librivox_releases'
+  | 'weekly_digest';
+
+export interface TaskRun {
+  started_at: string;
+  duration_ms: number;
+  success: boolean;
+  message: string;
+}
+
+export interface MaintenanceTaskFinishedEvent {
+  task: MaintenanceTask;
+  run: TaskRun;
+}
+
+export interface RecommendationsRefreshedEvent {
+  count: number;
+}
+
+export interface FolderSyncReport {
+  devices: string[];
+  books_updated: string[];
+  bookmarks_added: number;
+  collections_created: number;
+  collection_books_added: number;
+  unmatched_books: number;
+  errors: string[];
+}
+
+export interface LibrivoxRelease {
+  id: string;
+  title: string;
+  authors: string[];
+  genres: string[];
+  language: string | null;
+  description: string | null;
+  total_seconds: number | null;
+  url_zip_file: string | null;
+  url_librivox: string | null;
+  first_seen_at: string;
+}
+
+export interface LibrivoxWork {
+  id: string;
+  title: string;
+  authors: string[];
+  genres: string[];
+  language: string | null;
+  description: string | null;
+  total_seconds: number | null;
+  url_zip_file: string | null;
+  url_librivox: string | null;
+  audiobook_id: string | null; // Set when the recording is already in the library
+}
+
+export interface LibrivoxAuthorPage {
+  name: string;
+  author_ids: string[]; // Every LibriVox id the works were gathered from
+  born: string | null;
+  died: string | null;
+  works: LibrivoxWork[];
+}
+
+export interface LibrivoxWorkImport {
+  work_id: string;
+  title: string;
+  error: string | null; // null when the import succeeded
+}
+
+export interface CompilationSection {
+  file_name: string; // The file's name on Archive.org
+  title: string;
+  author: string | null;
+  track: number | null;
+  duration: number | null; // Seconds
+}
+
+export interface CompilationWork {
+  title: string;
+  author: string | null; // null falls back to the recording's author
+  file_names: string[];
+}
+
+export interface CompilationPlan {
+  identifier: string;
+  is_compilation: boolean;
+  sections: CompilationSection[];
+  works: CompilationWork[]; // Proposed grouping, one entry per work
+}
+
+export type FollowKind = 'author' | 'genre';
+
+export interface Follow {
+  id: string;
+  kind: FollowKind;
+  name: string;
+  created_at: string;
+}
+
+export interface ReleaseAlert {
+  id: string;
+  follow: Follow;
+  release: LibrivoxRelease;
+  created_at: string;
+}
+
+export interface DigestBook {
+  audiobook_id: string;
+  title: string;
+  author: string | null;
+  progress: number; // 0 to 1
+  last_played_at: string;
+}
+
+// Also returned by get_latest_digest
+export interface WeeklyDigest {
+  id: string;
+  period_start: string;
+  period_end: string;
+  listened_seconds: number;
+  books_finished: DigestBook[];
+  new_releases: ReleaseAlert[]; // Releases by followed authors
+  stalled: DigestBook[]; // In progress but not played for two weeks
+  created_at: string;
+}
+
+export type LibraryChange = 'added' | 'updated' | 'removed';
+
+export interface LibraryChangedEvent {
+  change: LibraryChange;
+  audiobook_ids: string[];
+}
+
+// Sent when an operation is refused for lack of space, and returned as its error
+export interface InsufficientSpace {
+  path: string;
+  required_bytes: number;
+  available_bytes: number;
+}
+
+export interface LowDiskSpace {
+  path: string; // The library or cache folder whose volume is running low
+  available_bytes: number;
+  threshold_bytes: number;
+}
+
+export interface OfflineVolume {
+  path: string; // Where the drive or share is mounted when connected
+  audiobook_ids: string[];
+}
+
+export interface SortPreferenceChangedEvent {
+  scope: SortScope;
+  sort: SortPreference;
+}
+
+export interface AppEventMap {
+  'init-complete': WarmUpReport;
+  'incomplete-imports': Audiobook[];
+  'playback-changed': PlaybackChangedEvent;
+  'system-resumed': ResumeReport;
+  'book-finished': BookFinishedEvent;
+  'chapter-error': ChapterErrorEvent;
+  'playback-stalled': PlaybackStall;
+  'download-progress': DownloadProgressEvent;
+  'download-completed': DownloadCompletedEvent;
+  'download-failed': DownloadFailedEvent;
+  'scan-started': ScanStartedEvent;
+  'scan-finished': ScanFinishedEvent;
+  'audiobook-validated': AudiobookValidation;
+  'ocr-progress': OcrProgressEvent;
+  'folder-export-progress': FolderExportProgressEvent;
+  'inbox-file-processed': InboxFileEvent;
+  'inbox-file-failed': InboxFileEvent;
+  'maintenance-task-finished': MaintenanceTaskFinishedEvent;
+  'recommendations-refreshed': RecommendationsRefreshedEvent;
+  'folder-sync-applied': FolderSyncReport;
+  'new-librivox-releases': LibrivoxRelease[];
+  'followed-release-available': ReleaseAlert[];
+  'weekly-digest-ready': WeeklyDigest;
+  'library-changed': LibraryChangedEvent;
+  'insufficient-disk-space': InsufficientSpace;
+  'low-disk-space': LowDiskSpace;
+  'volume-offline': OfflineVolume;
+  'volume-online': OfflineVolume;
+  'sort-preference-changed': SortPreferenceChangedEvent;
+}
+
+export type AppEventName = keyof AppEventMap;
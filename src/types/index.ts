@@ -2,6 +2,7 @@ import React from 'react';
 
 export * from './audiobook';
 export * from './collection';
+export * from './events';
 
 // Navigation and UI types
 export interface NavigationItem {